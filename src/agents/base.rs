@@ -1,7 +1,9 @@
-use super::AgentCapability;
+use super::{AgentCapability, OutputValidator};
+use crate::db::Database;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentResult {
@@ -9,6 +11,10 @@ pub struct AgentResult {
     pub output: String,
     pub tokens_used: usize,
     pub execution_time_ms: u64,
+    /// Dollars the calls of the execution cost, at list prices when the
+    /// backend doesn't report it
+    #[serde(default)]
+    pub cost_usd: f64,
 }
 
 /// Base trait for all agents
@@ -23,6 +29,33 @@ pub trait Agent: Send + Sync {
     /// Get agent capability
     fn capability(&self) -> &AgentCapability;
 
+    /// Get the system prompt (if applicable)
+    fn system_prompt(&self) -> Option<&str> {
+        None
+    }
+
+    /// Replace the system prompt, e.g. after the user edited it. Backends
+    /// without one ignore this.
+    fn set_system_prompt(&mut self, _prompt: String) {}
+
+    /// Limit how many tokens a single execution may use, across all its
+    /// calls, continuations and retries. Backends that can't cap usage
+    /// ignore this.
+    fn set_max_tokens(&mut self, _max_tokens: usize) {}
+
+    /// Require the output of each execution to be JSON matching `schema`.
+    /// Backends that can't enforce it ignore this.
+    fn set_output_schema(&mut self, _schema: serde_json::Value) {}
+
+    /// Check the output of each execution with `validators` before
+    /// accepting it, asking the agent to fix what they find. Backends that
+    /// can't ask again ignore this.
+    fn set_validators(&mut self, _validators: Vec<Arc<dyn OutputValidator>>) {}
+
+    /// Record every model API call the agent makes in `db`'s `api_calls`
+    /// table
+    fn set_audit_log(&mut self, _db: Arc<Database>) {}
+
     /// Execute a task
     async fn execute(&mut self, task: &str) -> Result<AgentResult>;
 
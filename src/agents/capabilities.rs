@@ -1,3 +1,4 @@
+use crate::style;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -14,6 +15,18 @@ pub enum AgentCapability {
 }
 
 impl AgentCapability {
+    pub const ALL: [AgentCapability; 9] = [
+        Self::Architecture,
+        Self::CodeWriting,
+        Self::Testing,
+        Self::Security,
+        Self::Documentation,
+        Self::Debugging,
+        Self::Performance,
+        Self::Migration,
+        Self::Review,
+    ];
+
     pub fn description(&self) -> &str {
         match self {
             Self::Architecture => "system design and architecture",
@@ -29,7 +42,7 @@ impl AgentCapability {
     }
 
     pub fn emoji(&self) -> &str {
-        match self {
+        style::icon(match self {
             Self::Architecture => "🏗️",
             Self::CodeWriting => "💻",
             Self::Testing => "🧪",
@@ -39,11 +52,30 @@ impl AgentCapability {
             Self::Performance => "⚡",
             Self::Migration => "🔄",
             Self::Review => "👁️",
+        })
+    }
+
+    /// Relative share of a run's token budget an agent of this capability
+    /// usually needs
+    pub fn token_weight(&self) -> f64 {
+        match self {
+            Self::CodeWriting => 2.0,
+            Self::Architecture | Self::Debugging | Self::Migration => 1.5,
+            Self::Testing | Self::Performance => 1.2,
+            Self::Security => 1.0,
+            Self::Documentation | Self::Review => 0.8,
         }
     }
 
+    /// Whether the agent mostly writes code, so its output grows with the
+    /// complexity of the task
+    pub fn produces_code(&self) -> bool {
+        matches!(self, Self::CodeWriting | Self::Testing | Self::Migration)
+    }
+
     /// Parse capability from Debug string representation
     /// Returns None for invalid/unknown capability strings
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "Architecture" => Some(Self::Architecture),
@@ -58,4 +90,18 @@ impl AgentCapability {
             _ => None,
         }
     }
+
+    /// Parse a name typed on the command line, e.g. `testing` or
+    /// `code-writing`, ignoring case and separators
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name: String = name
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+
+        Self::ALL
+            .into_iter()
+            .find(|capability| format!("{:?}", capability).to_lowercase() == name)
+    }
 }
@@ -1,10 +1,56 @@
-use super::{Agent, AgentCapability, AgentResult};
+use super::rate_limit::{estimate_tokens, RateLimiter};
+use super::validate::{self, OutputValidator, MAX_FIX_RETRIES};
+use super::{schema, Agent, AgentCapability, AgentResult};
+use crate::cache::{namespace, Cache};
 use crate::config::Config;
+use crate::db::{ApiCall, Database};
+use crate::error::ClaudeHelperError;
+use crate::pricing;
+use crate::redact::Redactor;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use std::time::Instant;
+use tracing::{debug, warn};
+
+/// Model used for all agent API calls
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-5-20250929";
+
+/// Short model names accepted wherever a model is configured
+const MODEL_ALIASES: &[(&str, &str)] = &[
+    ("sonnet", DEFAULT_MODEL),
+    ("haiku", "claude-haiku-4-5-20251001"),
+    ("opus", "claude-opus-4-1-20250805"),
+];
+
+/// Full model ID for `name`, which may be an alias such as `haiku`
+pub fn resolve_model(name: &str) -> String {
+    MODEL_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map_or(name, |(_, model)| model)
+        .to_string()
+}
+
+/// Most output tokens an agent asks for per API call
+const MAX_OUTPUT_TOKENS: usize = 8192;
+
+/// Fewest output tokens an agent is limited to, however small its share
+/// of the budget. With less than this left of its allotment an agent
+/// stops continuing and retrying.
+const MIN_OUTPUT_TOKENS: usize = 1024;
+
+/// Turn sent to pick a truncated answer back up
+const CONTINUE_PROMPT: &str =
+    "Continue exactly where you left off. Do not repeat anything you already wrote.";
+
+/// Blended cost per million tokens for [`DEFAULT_MODEL`]
+/// ($3 input / $15 output, assuming an even split)
+pub const DEFAULT_COST_PER_MILLION: f64 = 9.0;
 
 #[derive(Debug, Serialize)]
 struct ClaudeRequest {
@@ -20,23 +66,58 @@ struct Message {
     content: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ClaudeResponse {
     content: Vec<ContentBlock>,
     usage: Usage,
+    #[serde(default)]
+    stop_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl ClaudeResponse {
+    fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter(|block| block.block_type == "text")
+            .filter_map(|block| block.text.as_ref())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Whether the answer was cut off by the output token limit
+    fn is_truncated(&self) -> bool {
+        self.stop_reason.as_deref() == Some("max_tokens")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ContentBlock {
     #[serde(rename = "type")]
     block_type: String,
     text: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Usage {
     input_tokens: usize,
     output_tokens: usize,
+    #[serde(default)]
+    cache_creation_input_tokens: usize,
+    #[serde(default)]
+    cache_read_input_tokens: usize,
+}
+
+impl Usage {
+    /// Dollar cost of these tokens at `model`'s prices
+    fn cost(&self, model: &str) -> f64 {
+        pricing::price_or_default(model).cost(
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_read_input_tokens,
+            self.cache_creation_input_tokens,
+        )
+    }
 }
 
 pub struct ClaudeAgent {
@@ -47,6 +128,20 @@ pub struct ClaudeAgent {
     config: Config,
     client: Client,
     conversation: Vec<Message>,
+    model: String,
+    /// Tokens a single execution may use across all its calls, if limited
+    allotment: Option<usize>,
+    /// Tokens the current execution has used so far
+    tokens_spent: usize,
+    output_schema: Option<Value>,
+    validators: Vec<Arc<dyn OutputValidator>>,
+    /// Masks secrets in everything sent to the API
+    redactor: Redactor,
+    audit: Option<Arc<Database>>,
+    /// Earlier answers to identical requests, when `master_coder.reuse_responses` is on
+    response_cache: Option<Cache>,
+    /// `[limits.api]`, shared with the other agents
+    limiter: Arc<RateLimiter>,
 }
 
 impl ClaudeAgent {
@@ -57,11 +152,42 @@ impl ClaudeAgent {
         system_prompt: String,
         config: Config,
     ) -> Result<Self> {
-        let client = Client::builder()
+        let client = config
+            .network
+            .client_builder()?
             .timeout(std::time::Duration::from_secs(300))
             .build()
             .context("Failed to create HTTP client")?;
 
+        let redactor = if config.redaction.applies_to(&capability) {
+            Redactor::from_config(&config.redaction)?
+        } else {
+            Redactor::default()
+        };
+        let system_prompt = redactor.redact(&system_prompt).into_owned();
+
+        let model = config.models.resolve(
+            config
+                .master_coder
+                .model
+                .as_deref()
+                .unwrap_or(DEFAULT_MODEL),
+        );
+
+        let response_cache = if config.master_coder.reuse_responses {
+            match Cache::new() {
+                Ok(cache) => Some(cache),
+                Err(e) => {
+                    warn!("Response cache unavailable, not reusing responses: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let limiter = RateLimiter::shared(&config, "api");
+
         Ok(Self {
             id,
             agent_type,
@@ -70,26 +196,193 @@ impl ClaudeAgent {
             config,
             client,
             conversation: Vec::new(),
+            model,
+            allotment: None,
+            tokens_spent: 0,
+            output_schema: None,
+            validators: Vec::new(),
+            redactor,
+            audit: None,
+            response_cache,
+            limiter,
         })
     }
 
-    async fn call_claude_api(&self, messages: &[Message]) -> Result<ClaudeResponse> {
-        let token = self.config.auth.get_token().await?;
+    /// Send `message` as the next turn of the conversation and return the
+    /// answer with the tokens and dollars it used. Answers cut off by the
+    /// output limit are continued up to `master_coder.max_continuations`
+    /// times and stitched together, while the allotment lasts.
+    async fn ask(&mut self, message: String) -> Result<(String, usize, f64)> {
+        self.conversation.push(Message {
+            role: "user".to_string(),
+            content: self.redactor.redact(&message).into_owned(),
+        });
+
+        let mut output = String::new();
+        let mut tokens_used = 0;
+        let mut cost_usd = 0.0;
+        let mut continuations = 0;
+        loop {
+            let response = self.call_claude_api(&self.conversation).await?;
+            let tokens = response.usage.input_tokens + response.usage.output_tokens;
+            tokens_used += tokens;
+            self.tokens_spent += tokens;
+            cost_usd += response.usage.cost(&self.model);
+
+            let text = response.text();
+            output.push_str(&text);
 
+            // Add assistant response to conversation
+            self.conversation.push(Message {
+                role: "assistant".to_string(),
+                content: text,
+            });
+
+            if !response.is_truncated() {
+                break;
+            }
+            if continuations == self.config.master_coder.max_continuations {
+                warn!(
+                    "{}: answer still truncated after {} continuations",
+                    self.agent_type, continuations
+                );
+                break;
+            }
+            if self.allotment_spent() {
+                warn!(
+                    "{}: answer truncated, its allotment of {} tokens is spent",
+                    self.agent_type,
+                    self.allotment.unwrap_or_default()
+                );
+                break;
+            }
+            continuations += 1;
+
+            self.conversation.push(Message {
+                role: "user".to_string(),
+                content: CONTINUE_PROMPT.to_string(),
+            });
+        }
+
+        Ok((output, tokens_used, cost_usd))
+    }
+
+    /// Output tokens to ask for in the next call: what is left of the
+    /// allotment, within [`MIN_OUTPUT_TOKENS`]..[`MAX_OUTPUT_TOKENS`]
+    fn output_limit(&self) -> usize {
+        self.allotment.map_or(MAX_OUTPUT_TOKENS, |allotment| {
+            allotment
+                .saturating_sub(self.tokens_spent)
+                .clamp(MIN_OUTPUT_TOKENS, MAX_OUTPUT_TOKENS)
+        })
+    }
+
+    /// Whether too little of the allotment is left for another useful call
+    fn allotment_spent(&self) -> bool {
+        self.allotment.is_some_and(|allotment| {
+            allotment.saturating_sub(self.tokens_spent) < MIN_OUTPUT_TOKENS
+        })
+    }
+
+    /// Call the API and record the call in the audit log, if there is one
+    async fn call_claude_api(&self, messages: &[Message]) -> Result<ClaudeResponse> {
         let request = ClaudeRequest {
-            model: "claude-sonnet-4-5-20250929".to_string(),
-            max_tokens: 8192,
+            model: self.model.clone(),
+            max_tokens: self.output_limit(),
             messages: messages.to_vec(),
             system: Some(self.system_prompt.clone()),
         };
 
+        // An identical request answered before is reused at no token cost
+        let cache_key = self
+            .response_cache
+            .as_ref()
+            .map(|_| response_cache_key(&request))
+            .transpose()?;
+        if let (Some(cache), Some(key)) = (&self.response_cache, &cache_key) {
+            if let Ok(Some(mut response)) = cache.get::<ClaudeResponse>(key) {
+                debug!("{}: reusing cached response {}", self.agent_type, key);
+                response.usage = Usage::default();
+                return Ok(response);
+            }
+        }
+
+        // Output tokens are unknown until the answer arrives
+        let estimate = estimate_tokens(&self.system_prompt)
+            + messages
+                .iter()
+                .map(|m| estimate_tokens(&m.content))
+                .sum::<usize>();
+        let permit = self.limiter.acquire(estimate).await;
+
+        let started = Instant::now();
+        let result = self.send_request(&request).await;
+        match &result {
+            Ok(response) => {
+                permit.finish(response.usage.input_tokens + response.usage.output_tokens)
+            }
+            Err(e) => permit.failed(matches!(
+                e.downcast_ref::<ClaudeHelperError>(),
+                Some(ClaudeHelperError::ApiError {
+                    status: 429 | 529,
+                    ..
+                })
+            )),
+        }
+
+        if let Some(db) = &self.audit {
+            let mut call = ApiCall {
+                agent_id: self.id.clone(),
+                agent_type: self.agent_type.clone(),
+                model: self.model.clone(),
+                latency_ms: started.elapsed().as_millis() as u64,
+                ..Default::default()
+            };
+            match &result {
+                Ok(response) => {
+                    call.input_tokens = response.usage.input_tokens;
+                    call.output_tokens = response.usage.output_tokens;
+                    call.cache_read_tokens = response.usage.cache_read_input_tokens;
+                    call.cache_write_tokens = response.usage.cache_creation_input_tokens;
+                    call.cost_usd = call.token_cost();
+                    call.status = "ok".to_string();
+                }
+                Err(e) => {
+                    call.status = match e.downcast_ref::<ClaudeHelperError>() {
+                        Some(ClaudeHelperError::ApiError { status, .. }) => status.to_string(),
+                        _ => "failed".to_string(),
+                    };
+                }
+            }
+            if let Err(e) = db.record_api_call(&call).await {
+                warn!("Failed to record API call in the audit log: {:#}", e);
+            }
+        }
+
+        if let (Some(cache), Some(key), Ok(response)) = (&self.response_cache, &cache_key, &result)
+        {
+            let ttl = self.config.master_coder.response_cache_ttl_hours * 60 * 60;
+            if let Err(e) = cache.set(key, response.clone(), ttl) {
+                debug!("Failed to cache response {}: {}", key, e);
+            }
+        }
+
+        result
+    }
+
+    async fn send_request(&self, request: &ClaudeRequest) -> Result<ClaudeResponse> {
+        self.config
+            .network
+            .ensure_online("Calling the Claude API")?;
+        let token = self.config.auth.get_token(&self.config.network).await?;
+
         let response = self
             .client
             .post("https://api.anthropic.com/v1/messages")
             .header("x-api-key", token)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .await
             .context("Failed to send request to Claude API")?;
@@ -97,7 +390,11 @@ impl ClaudeAgent {
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Claude API error ({}): {}", status, error_text);
+            return Err(ClaudeHelperError::ApiError {
+                status: status.as_u16(),
+                message: error_text,
+            }
+            .into());
         }
 
         let claude_response: ClaudeResponse = response
@@ -123,41 +420,119 @@ impl Agent for ClaudeAgent {
         &self.capability
     }
 
+    fn system_prompt(&self) -> Option<&str> {
+        Some(&self.system_prompt)
+    }
+
+    fn set_system_prompt(&mut self, prompt: String) {
+        self.system_prompt = self.redactor.redact(&prompt).into_owned();
+    }
+
+    fn set_max_tokens(&mut self, max_tokens: usize) {
+        self.allotment = Some(max_tokens);
+    }
+
+    fn set_output_schema(&mut self, schema: Value) {
+        self.output_schema = Some(schema);
+    }
+
+    fn set_validators(&mut self, validators: Vec<Arc<dyn OutputValidator>>) {
+        self.validators = validators;
+    }
+
+    fn set_audit_log(&mut self, db: Arc<Database>) {
+        self.audit = Some(db);
+    }
+
     async fn execute(&mut self, task: &str) -> Result<AgentResult> {
         let start_time = Instant::now();
+        let output_schema = self.output_schema.clone();
+        self.tokens_spent = 0;
 
-        // Add user message to conversation
-        self.conversation.push(Message {
-            role: "user".to_string(),
-            content: task.to_string(),
-        });
+        let mut message = task.to_string();
+        if let Some(schema) = &output_schema {
+            message.push_str(&format!(
+                "\n\nRespond with only a JSON value matching this JSON Schema, \
+                without any other text:\n{}",
+                serde_json::to_string_pretty(schema)?
+            ));
+        }
 
-        // Call Claude API
-        let response = self.call_claude_api(&self.conversation).await?;
+        let mut tokens_used = 0;
+        let mut cost_usd = 0.0;
+        let mut retries = 0;
+        let output = loop {
+            let (mut output, tokens, cost) = self.ask(message).await?;
+            tokens_used += tokens;
+            cost_usd += cost;
 
-        // Extract text from response
-        let output = response
-            .content
-            .iter()
-            .filter_map(|block| block.text.as_ref())
-            .cloned()
-            .collect::<Vec<_>>()
-            .join("\n");
+            let mut schema_errors = Vec::new();
+            if let Some(schema) = &output_schema {
+                match schema::extract_json(&output) {
+                    Ok(value) => {
+                        schema_errors = schema::validate(schema, &value);
+                        output = serde_json::to_string_pretty(&value)?;
+                    }
+                    Err(e) => schema_errors.push(format!("not valid JSON: {}", e)),
+                }
+            }
+            // Validators only look at output that matches the schema
+            let errors = if schema_errors.is_empty() {
+                validate::check_all(&self.validators, &output).await
+            } else {
+                Vec::new()
+            };
+            if schema_errors.is_empty() && errors.is_empty() {
+                break output;
+            }
 
-        // Add assistant response to conversation
-        self.conversation.push(Message {
-            role: "assistant".to_string(),
-            content: output.clone(),
-        });
+            if retries == MAX_FIX_RETRIES {
+                if errors.is_empty() {
+                    anyhow::bail!(
+                        "Output did not match the expected schema after {} retries: {}",
+                        retries,
+                        schema_errors.join("; ")
+                    );
+                }
+                anyhow::bail!(
+                    "Output failed validation after {} retries: {}",
+                    retries,
+                    errors.join("; ")
+                );
+            }
+            if self.allotment_spent() {
+                anyhow::bail!(
+                    "Token allotment of {} spent after {} retries, output still invalid: {}",
+                    self.allotment.unwrap_or_default(),
+                    retries,
+                    schema_errors
+                        .iter()
+                        .chain(&errors)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                );
+            }
+            retries += 1;
 
-        let execution_time = start_time.elapsed().as_millis() as u64;
-        let tokens_used = response.usage.input_tokens + response.usage.output_tokens;
+            // Feed the violations back so the next answer can fix them
+            message = if errors.is_empty() {
+                format!(
+                    "Your answer did not match the schema:\n- {}\n\n\
+                    Reply again with only the corrected JSON.",
+                    schema_errors.join("\n- ")
+                )
+            } else {
+                validate::fix_request(&errors)
+            };
+        };
 
         Ok(AgentResult {
             success: true,
             output,
             tokens_used,
-            execution_time_ms: execution_time,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+            cost_usd,
         })
     }
 
@@ -172,3 +547,84 @@ impl Agent for ClaudeAgent {
         self.conversation.clear();
     }
 }
+
+/// Cache key of a request: a SHA-256 of the model, output limit, system
+/// prompt and every message
+fn response_cache_key(request: &ClaudeRequest) -> Result<String> {
+    let digest = Sha256::digest(serde_json::to_vec(request)?);
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}/{}", namespace::RESPONSES, hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_truncation() {
+        let response: ClaudeResponse = serde_json::from_str(
+            r#"{"content": [{"type": "text", "text": "part"}],
+                "usage": {"input_tokens": 10, "output_tokens": 20},
+                "stop_reason": "max_tokens"}"#,
+        )
+        .unwrap();
+        assert!(response.is_truncated());
+        assert_eq!(response.text(), "part");
+
+        let response: ClaudeResponse = serde_json::from_str(
+            r#"{"content": [], "usage": {"input_tokens": 1, "output_tokens": 1}}"#,
+        )
+        .unwrap();
+        assert!(!response.is_truncated());
+    }
+
+    #[test]
+    fn test_response_cache_key() {
+        let request = |system: &str| ClaudeRequest {
+            model: DEFAULT_MODEL.to_string(),
+            max_tokens: 1024,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Write a parser".to_string(),
+            }],
+            system: Some(system.to_string()),
+        };
+
+        let key = response_cache_key(&request("You are Tester")).unwrap();
+        assert!(key.starts_with("responses/"));
+        assert_eq!(key, response_cache_key(&request("You are Tester")).unwrap());
+        assert_ne!(key, response_cache_key(&request("You are Coder")).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_allotment_limits_calls() {
+        let mut agent = ClaudeAgent::new(
+            "agent-1".to_string(),
+            "Code Writer".to_string(),
+            AgentCapability::CodeWriting,
+            "You write code".to_string(),
+            Config::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(agent.output_limit(), MAX_OUTPUT_TOKENS);
+        assert!(!agent.allotment_spent());
+
+        agent.set_max_tokens(5000);
+        agent.tokens_spent = 2000;
+        assert_eq!(agent.output_limit(), 3000);
+        assert!(!agent.allotment_spent());
+
+        // Continuations and retries stop once too little is left
+        agent.tokens_spent = 4500;
+        assert_eq!(agent.output_limit(), MIN_OUTPUT_TOKENS);
+        assert!(agent.allotment_spent());
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        assert_eq!(resolve_model("sonnet"), DEFAULT_MODEL);
+        assert_eq!(resolve_model("Haiku"), "claude-haiku-4-5-20251001");
+        assert_eq!(resolve_model("claude-custom-1"), "claude-custom-1");
+    }
+}
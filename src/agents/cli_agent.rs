@@ -0,0 +1,362 @@
+use super::rate_limit::{estimate_tokens, is_rate_limit_message, RateLimiter};
+use super::validate::{self, OutputValidator, MAX_FIX_RETRIES};
+use super::{Agent, AgentCapability, AgentResult, DEFAULT_MODEL};
+use crate::config::Config;
+use crate::db::{ApiCall, Database};
+use crate::pricing;
+use crate::project::Project;
+use crate::redact::Redactor;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// Final message of `claude -p --output-format json`
+#[derive(Debug, Deserialize)]
+struct CliOutput {
+    #[serde(default)]
+    is_error: bool,
+    #[serde(default)]
+    subtype: Option<String>,
+    #[serde(default)]
+    result: Option<String>,
+    session_id: Option<String>,
+    #[serde(default)]
+    usage: Option<CliUsage>,
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CliUsage {
+    #[serde(default)]
+    input_tokens: usize,
+    #[serde(default)]
+    output_tokens: usize,
+    #[serde(default)]
+    cache_creation_input_tokens: usize,
+    #[serde(default)]
+    cache_read_input_tokens: usize,
+}
+
+impl CliOutput {
+    fn parse(stdout: &str) -> Result<Self> {
+        serde_json::from_str(stdout.trim()).context("Failed to parse `claude` JSON output")
+    }
+
+    fn tokens_used(&self) -> usize {
+        self.usage
+            .as_ref()
+            .map_or(0, |usage| usage.input_tokens + usage.output_tokens)
+    }
+
+    /// What Claude Code says the call cost, or its tokens at `model`'s prices
+    fn cost_usd(&self, model: &str) -> f64 {
+        self.total_cost_usd.unwrap_or_else(|| {
+            self.usage.as_ref().map_or(0.0, |usage| {
+                pricing::price_or_default(model).cost(
+                    usage.input_tokens,
+                    usage.output_tokens,
+                    usage.cache_read_input_tokens,
+                    usage.cache_creation_input_tokens,
+                )
+            })
+        })
+    }
+}
+
+/// Agent that runs the installed Claude Code CLI in print mode, so it uses
+/// the user's own login and MCP servers instead of an API key. Follow-up
+/// tasks resume the same Claude Code session.
+pub struct ClaudeCliAgent {
+    id: String,
+    agent_type: String,
+    capability: AgentCapability,
+    system_prompt: String,
+    command: String,
+    /// `--model` passed to `claude`; its own default when None
+    model: Option<String>,
+    session_id: Option<String>,
+    conversation: Vec<String>,
+    /// Masks secrets in everything passed to `claude`
+    redactor: Redactor,
+    audit: Option<Arc<Database>>,
+    validators: Vec<Arc<dyn OutputValidator>>,
+    /// `[limits.claude_cli]`, shared with the other agents
+    limiter: Arc<RateLimiter>,
+    /// `CLAUDE_CODE_SHELL_PREFIX` running Bash tool commands in the
+    /// `[sandbox]` container
+    shell_prefix: Option<String>,
+}
+
+impl ClaudeCliAgent {
+    pub fn new(
+        id: String,
+        agent_type: String,
+        capability: AgentCapability,
+        system_prompt: String,
+        config: &Config,
+    ) -> Result<Self> {
+        let redactor = if config.redaction.applies_to(&capability) {
+            Redactor::from_config(&config.redaction)?
+        } else {
+            Redactor::default()
+        };
+        let system_prompt = redactor.redact(&system_prompt).into_owned();
+        let shell_prefix = if config.sandbox.enabled {
+            let project = Project::current()?;
+            Some(config.sandbox.shell_prefix(Path::new(&project.root)))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            id,
+            agent_type,
+            capability,
+            system_prompt,
+            command: config.master_coder.claude_command.clone(),
+            // Claude Code knows the built-in aliases itself
+            model: config
+                .master_coder
+                .model
+                .as_deref()
+                .map(|model| config.models.expand(model).to_string()),
+            session_id: None,
+            conversation: Vec::new(),
+            redactor,
+            audit: None,
+            validators: Vec::new(),
+            limiter: RateLimiter::shared(config, "claude-cli"),
+            shell_prefix,
+        })
+    }
+
+    /// Run `task` as the next turn of the session and return the answer
+    /// with the tokens and dollars it used
+    async fn ask(&mut self, task: &str) -> Result<(String, usize, f64)> {
+        let permit = self
+            .limiter
+            .acquire(estimate_tokens(&self.system_prompt) + estimate_tokens(task))
+            .await;
+        let start_time = Instant::now();
+
+        let result = self.run_claude(task).await;
+        match &result {
+            Ok(output) => permit.finish(output.tokens_used()),
+            Err(e) => permit.failed(is_rate_limit_message(&format!("{:#}", e))),
+        }
+        self.record_call(&result, start_time.elapsed().as_millis() as u64)
+            .await;
+        let response = result?;
+        let output = response.result.clone().unwrap_or_default();
+        if response.session_id.is_some() {
+            self.session_id = response.session_id.clone();
+        }
+
+        self.conversation.push(format!("user: {}", task));
+        self.conversation.push(format!("assistant: {}", output));
+
+        let cost_usd = response.cost_usd(self.model.as_deref().unwrap_or(DEFAULT_MODEL));
+        Ok((output, response.tokens_used(), cost_usd))
+    }
+
+    async fn run_claude(&self, task: &str) -> Result<CliOutput> {
+        let mut cmd = tokio::process::Command::new(&self.command);
+        cmd.arg("-p")
+            .arg("--output-format")
+            .arg("json")
+            .arg("--append-system-prompt")
+            .arg(&self.system_prompt);
+        if let Some(model) = &self.model {
+            cmd.arg("--model").arg(model);
+        }
+        if let Some(session_id) = &self.session_id {
+            cmd.arg("--resume").arg(session_id);
+        }
+        if let Some(prefix) = &self.shell_prefix {
+            cmd.env("CLAUDE_CODE_SHELL_PREFIX", prefix);
+        }
+
+        // The task goes through stdin so long specs don't hit argument limits
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to run `{}`; is Claude Code installed? \
+                    Set master_coder.claude_command to its path",
+                    self.command
+                )
+            })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(self.redactor.redact(task).as_bytes())
+                .await
+                .context("Failed to send the task to `claude`")?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .await
+            .context("Failed to wait for `claude`")?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        if !output.status.success() && stdout.trim().is_empty() {
+            anyhow::bail!(
+                "`{}` exited with {}: {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let parsed = CliOutput::parse(&stdout)?;
+        if parsed.is_error {
+            anyhow::bail!(
+                "Claude Code returned an error ({}): {}",
+                parsed.subtype.as_deref().unwrap_or("unknown"),
+                parsed.result.as_deref().unwrap_or_default()
+            );
+        }
+
+        Ok(parsed)
+    }
+
+    /// Record a `claude` run in the audit log, if there is one
+    async fn record_call(&self, result: &Result<CliOutput>, latency_ms: u64) {
+        let Some(db) = &self.audit else {
+            return;
+        };
+
+        let mut call = ApiCall {
+            agent_id: self.id.clone(),
+            agent_type: self.agent_type.clone(),
+            model: self
+                .model
+                .clone()
+                .unwrap_or_else(|| "claude-cli".to_string()),
+            latency_ms,
+            status: "failed".to_string(),
+            ..Default::default()
+        };
+        if let Ok(output) = result {
+            let usage = output.usage.as_ref();
+            call.input_tokens = usage.map_or(0, |u| u.input_tokens);
+            call.output_tokens = usage.map_or(0, |u| u.output_tokens);
+            call.cache_read_tokens = usage.map_or(0, |u| u.cache_read_input_tokens);
+            call.cache_write_tokens = usage.map_or(0, |u| u.cache_creation_input_tokens);
+            call.cost_usd = output.total_cost_usd.unwrap_or_else(|| call.token_cost());
+            call.status = "ok".to_string();
+        }
+
+        if let Err(e) = db.record_api_call(&call).await {
+            warn!("Failed to record API call in the audit log: {:#}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl Agent for ClaudeCliAgent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn agent_type(&self) -> &str {
+        &self.agent_type
+    }
+
+    fn capability(&self) -> &AgentCapability {
+        &self.capability
+    }
+
+    fn system_prompt(&self) -> Option<&str> {
+        Some(&self.system_prompt)
+    }
+
+    fn set_system_prompt(&mut self, prompt: String) {
+        self.system_prompt = self.redactor.redact(&prompt).into_owned();
+    }
+
+    fn set_validators(&mut self, validators: Vec<Arc<dyn OutputValidator>>) {
+        self.validators = validators;
+    }
+
+    fn set_audit_log(&mut self, db: Arc<Database>) {
+        self.audit = Some(db);
+    }
+
+    async fn execute(&mut self, task: &str) -> Result<AgentResult> {
+        let start_time = Instant::now();
+        let mut message = task.to_string();
+        let mut tokens_used = 0;
+        let mut cost_usd = 0.0;
+        let mut retries = 0;
+        let output = loop {
+            let (output, tokens, cost) = self.ask(&message).await?;
+            tokens_used += tokens;
+            cost_usd += cost;
+
+            let errors = validate::check_all(&self.validators, &output).await;
+            if errors.is_empty() {
+                break output;
+            }
+            if retries == MAX_FIX_RETRIES {
+                anyhow::bail!(
+                    "Output failed validation after {} retries: {}",
+                    retries,
+                    errors.join("; ")
+                );
+            }
+            retries += 1;
+            // The next turn resumes the session, so the agent sees its answer
+            message = validate::fix_request(&errors);
+        };
+
+        Ok(AgentResult {
+            success: true,
+            output,
+            tokens_used,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+            cost_usd,
+        })
+    }
+
+    fn conversation_history(&self) -> Vec<String> {
+        self.conversation.clone()
+    }
+
+    fn reset(&mut self) {
+        self.session_id = None;
+        self.conversation.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cli_output() {
+        let output = CliOutput::parse(
+            r#"{"type":"result","subtype":"success","is_error":false,"result":"Done.",
+            "session_id":"abc-123","total_cost_usd":0.01,
+            "usage":{"input_tokens":120,"cache_read_input_tokens":9000,"output_tokens":30}}"#,
+        )
+        .unwrap();
+
+        assert!(!output.is_error);
+        assert_eq!(output.result.as_deref(), Some("Done."));
+        assert_eq!(output.session_id.as_deref(), Some("abc-123"));
+        assert_eq!(output.tokens_used(), 150);
+    }
+}
@@ -1,29 +1,35 @@
 use super::AgentCapability;
-use crate::config::Config;
-use crate::db::Database;
+use crate::db::{
+    self, AgentHistoryFilter, AgentRanking, CapabilityRegression, CapabilityTrend, Database,
+    ROLLING_WINDOW,
+};
+use crate::style::{self, ColoredString, Colorize};
 use anyhow::Result;
-use colored::Colorize;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+/// Change in success rate (percentage points) shown as a trend rather
+/// than noise
+const TREND_THRESHOLD: f64 = 5.0;
+
+/// How far back `agents stats` looks for runs to compute rolling success
+/// rates and spot regressions from
+const REGRESSION_LOOKBACK_DAYS: i64 = 90;
 
 pub struct AgentManager {
-    config: Config,
-    db: Database,
+    db: Arc<Database>,
 }
 
 impl AgentManager {
-    pub async fn new(config: &Config) -> Result<Self> {
-        let db = Database::new(config).await?;
-
-        Ok(Self {
-            config: config.clone(),
-            db,
-        })
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
     }
 
     pub async fn show_stats(&self) -> Result<()> {
         let stats = self.db.get_agent_stats().await?;
 
         println!("\n{}", "Agent Statistics".bright_cyan().bold());
-        println!("{}", "═".repeat(50).bright_cyan());
+        println!("{}", style::rule(50).bright_cyan());
 
         println!("\n{}", "Total Agents Executed:".white().bold());
         println!("  {}", stats.total_executions);
@@ -53,14 +59,7 @@ impl AgentManager {
         if stats.total_executions > 0 {
             let success_rate =
                 (stats.successful_executions as f64 / stats.total_executions as f64) * 100.0;
-            let rate_str = format!("{:.1}%", success_rate);
-            let colored_rate = if success_rate >= 90.0 {
-                rate_str.green()
-            } else if success_rate >= 70.0 {
-                rate_str.yellow()
-            } else {
-                rate_str.red()
-            };
+            let colored_rate = color_rate(format!("{:.1}%", success_rate), success_rate);
             println!(
                 "  {} ({}/{})",
                 colored_rate, stats.successful_executions, stats.total_executions
@@ -69,30 +68,52 @@ impl AgentManager {
             println!("  {} (no executions yet)", "N/A".white());
         }
 
+        let runs = self
+            .db
+            .get_capability_runs(Utc::now() - Duration::days(REGRESSION_LOOKBACK_DAYS))
+            .await?;
+        let rates = db::rolling_rates(&runs);
+        if !rates.is_empty() {
+            println!(
+                "\n{}",
+                format!("Recent Success (last {} runs):", ROLLING_WINDOW)
+                    .white()
+                    .bold()
+            );
+            for rate in &rates {
+                println!(
+                    "  {} {} on {}: {} ({}/{})",
+                    rate.capability.emoji(),
+                    rate.capability.description(),
+                    rate.model,
+                    color_rate(format!("{:.1}%", rate.success_rate()), rate.success_rate()),
+                    rate.successful,
+                    rate.executions
+                );
+            }
+        }
+
+        let regressions = db::find_regressions(&runs);
+        if !regressions.is_empty() {
+            println!("\n{}", "⚠ Possible Regressions:".yellow().bold());
+            for regression in &regressions {
+                print_regression(regression);
+            }
+        }
+
         Ok(())
     }
 
     pub fn list_types(&self) -> Result<()> {
         println!("\n{}", "Available Agent Capabilities".bright_cyan().bold());
-        println!("{}", "═".repeat(50).bright_cyan());
-
-        let capabilities = vec![
-            AgentCapability::Architecture,
-            AgentCapability::CodeWriting,
-            AgentCapability::Testing,
-            AgentCapability::Security,
-            AgentCapability::Documentation,
-            AgentCapability::Debugging,
-            AgentCapability::Performance,
-            AgentCapability::Migration,
-            AgentCapability::Review,
-        ];
-
-        for cap in capabilities {
+        println!("{}", style::rule(50).bright_cyan());
+
+        for cap in AgentCapability::ALL {
             println!(
-                "\n{} {}",
+                "\n{} {} {}",
                 cap.emoji(),
-                cap.description().bright_white().bold()
+                cap.description().bright_white().bold(),
+                format!("({:?})", cap).dimmed()
             );
         }
 
@@ -105,11 +126,25 @@ impl AgentManager {
         Ok(())
     }
 
-    pub async fn show_history(&self, limit: usize) -> Result<()> {
-        let history = self.db.get_agent_history(limit).await?;
+    pub async fn show_history(
+        &self,
+        filter: &AgentHistoryFilter,
+        limit: usize,
+        json: bool,
+    ) -> Result<()> {
+        let history = self.db.get_agent_history_filtered(filter, limit).await?;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&history)?);
+            return Ok(());
+        }
 
         println!("\n{}", "Recent Agent Executions".bright_cyan().bold());
-        println!("{}", "═".repeat(80).bright_cyan());
+        println!("{}", style::rule(80).bright_cyan());
+
+        if history.is_empty() {
+            println!("  No matching agent executions");
+        }
 
         for entry in history {
             println!(
@@ -127,13 +162,168 @@ impl AgentManager {
                 entry.tokens_used,
                 entry.execution_time_secs,
                 if entry.success {
-                    "✓".green()
+                    style::ok().green()
                 } else {
-                    "✗".red()
+                    style::fail().red()
                 }
             );
         }
 
         Ok(())
     }
+
+    pub async fn show_leaderboard(&self, by: AgentRanking, limit: usize) -> Result<()> {
+        let leaderboard = self.db.get_agent_leaderboard(by, limit).await?;
+
+        println!("\n{}", "Agent Leaderboard".bright_cyan().bold());
+        println!("{}", style::rule(80).bright_cyan());
+
+        if leaderboard.is_empty() {
+            println!("  No agent executions yet");
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            format!(
+                "  {:<3} {:<28} {:>5} {:>8} {:>11} {:>9}",
+                "#", "Agent", "Runs", "Success", "Avg tokens", "Avg time"
+            )
+            .white()
+            .bold()
+        );
+        for (rank, agent) in leaderboard.iter().enumerate() {
+            let rate = agent.success_rate();
+            println!(
+                "  {:<3} {} {:<25} {:>5} {} {:>11} {:>8.1}s",
+                rank + 1,
+                agent.capability.emoji(),
+                agent.agent_type.chars().take(25).collect::<String>(),
+                agent.executions,
+                color_rate(format!("{:>7.1}%", rate), rate),
+                agent.avg_tokens,
+                agent.avg_time_secs
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn show_trends(&self, weeks: usize) -> Result<()> {
+        let since = Utc::now() - Duration::weeks(weeks as i64);
+        let trends = self.db.get_capability_trends(since).await?;
+
+        println!(
+            "\n{}",
+            "Success Rate by Capability (weekly)".bright_cyan().bold()
+        );
+        println!("{}", style::rule(80).bright_cyan());
+
+        if trends.is_empty() {
+            println!("  No agent executions in the last {} weeks", weeks);
+            return Ok(());
+        }
+
+        let mut week_starts: Vec<&str> = trends.iter().map(|t| t.week.as_str()).collect();
+        week_starts.dedup();
+        let mut capabilities: Vec<_> = trends.iter().map(|t| &t.capability).collect();
+        capabilities.sort_by_key(|c| format!("{:?}", c));
+        capabilities.dedup();
+
+        let header: String = week_starts
+            .iter()
+            .map(|week| format!(" {:>6}", week.get(5..).unwrap_or(week)))
+            .collect();
+        println!(
+            "{}",
+            format!("  {:<24}{}  Trend", "Week of", header)
+                .white()
+                .bold()
+        );
+
+        for capability in capabilities {
+            let rates: Vec<Option<&CapabilityTrend>> = week_starts
+                .iter()
+                .map(|week| {
+                    trends
+                        .iter()
+                        .find(|t| t.week == *week && &t.capability == capability)
+                })
+                .collect();
+
+            let cells: Vec<String> = rates
+                .iter()
+                .map(|trend| match trend {
+                    Some(trend) => color_rate(
+                        format!(" {:>5.0}%", trend.success_rate()),
+                        trend.success_rate(),
+                    )
+                    .to_string(),
+                    None => format!(" {:>6}", "-"),
+                })
+                .collect();
+
+            let observed: Vec<f64> = rates.iter().flatten().map(|t| t.success_rate()).collect();
+            println!(
+                "  {} {:<21}{}  {}",
+                capability.emoji(),
+                format!("{:?}", capability),
+                cells.concat(),
+                trend_arrow(&observed)
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// One regression warning, with the window the change was made in
+fn print_regression(regression: &CapabilityRegression) {
+    println!(
+        "  {} {}: {} → {} success ({}/{} → {}/{} runs) after {}",
+        regression.capability.emoji(),
+        regression.capability.description(),
+        format!("{:.1}%", regression.rate_before()).green(),
+        format!("{:.1}%", regression.rate_after()).red(),
+        regression.successful_before,
+        regression.runs_before,
+        regression.successful_after,
+        regression.runs_after,
+        regression.change()
+    );
+    println!(
+        "    {}",
+        format!(
+            "changed between {} and {}",
+            regression.last_unchanged.format("%Y-%m-%d %H:%M"),
+            regression.first_changed.format("%Y-%m-%d %H:%M")
+        )
+        .dimmed()
+    );
+}
+
+fn color_rate(text: String, rate: f64) -> ColoredString {
+    if rate >= 90.0 {
+        text.green()
+    } else if rate >= 70.0 {
+        text.yellow()
+    } else {
+        text.red()
+    }
+}
+
+/// Compare the latest weekly success rate with the first one
+fn trend_arrow(rates: &[f64]) -> ColoredString {
+    let (Some(first), Some(last)) = (rates.first(), rates.last()) else {
+        return "".normal();
+    };
+
+    let change = last - first;
+    if rates.len() < 2 || change.abs() < TREND_THRESHOLD {
+        style::arrow().normal()
+    } else if change > 0.0 {
+        format!("{} {:+.0}", style::pick("↑", "^"), change).green()
+    } else {
+        format!("{} {:+.0}", style::pick("↓", "v"), change).red()
+    }
 }
@@ -1,11 +1,19 @@
 mod base;
 mod capabilities;
 mod claude_agent;
+mod cli_agent;
 mod manager;
+mod rate_limit;
+pub mod schema;
+mod validate;
 
 pub use base::{Agent, AgentResult};
 pub use capabilities::AgentCapability;
-pub use claude_agent::ClaudeAgent;
+pub use claude_agent::{resolve_model, ClaudeAgent, DEFAULT_COST_PER_MILLION, DEFAULT_MODEL};
+pub use cli_agent::ClaudeCliAgent;
 pub use manager::AgentManager;
+pub use rate_limit::{is_rate_limited, Health, RateLimiter};
+pub use validate::OutputValidator;
 
-use async_trait::async_trait;
+/// Values accepted by `master_coder.backend`
+pub const AGENT_BACKENDS: &[&str] = &["api", "claude-cli"];
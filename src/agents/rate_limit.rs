@@ -0,0 +1,356 @@
+use crate::config::{BackendLimits, Config};
+use crate::error::ClaudeHelperError;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// A call counts as slow when it takes this many times the usual latency
+const SLOW_FACTOR: f64 = 3.0;
+
+/// Calls needed before the usual latency is known
+const BASELINE_CALLS: u32 = 5;
+
+/// One limiter per backend, shared by every agent of the process
+static LIMITERS: LazyLock<Mutex<HashMap<String, Arc<RateLimiter>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Keeps calls to a backend within `[limits]`: a call waits until the
+/// requests and tokens of the last minute leave room for it. It also keeps
+/// track of 429s and slow calls, which the orchestrator reads to adapt how
+/// many agents it runs at once.
+#[derive(Debug)]
+pub struct RateLimiter {
+    backend: String,
+    limits: BackendLimits,
+    window: Mutex<Window>,
+    health: Mutex<Latency>,
+}
+
+/// How calls to a backend went since [`RateLimiter::take_health`] was last
+/// called
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Health {
+    /// Calls answered normally
+    pub ok: usize,
+    /// Calls that succeeded, but took much longer than usual
+    pub slow: usize,
+    /// Calls refused for rate limiting or overload (429, 529)
+    pub rate_limited: usize,
+}
+
+/// Usual latency of a backend and the health counts since they were taken
+#[derive(Debug, Default)]
+struct Latency {
+    /// Moving average of successful calls, in seconds
+    average: f64,
+    calls: u32,
+    health: Health,
+}
+
+/// A call admitted by [`RateLimiter::acquire`]. Its token count is an
+/// estimate until [`Permit::finish`] replaces it with the real usage.
+#[derive(Debug)]
+pub struct Permit {
+    limiter: Arc<RateLimiter>,
+    id: u64,
+    started: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    calls: VecDeque<Call>,
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct Call {
+    id: u64,
+    started: Instant,
+    tokens: usize,
+}
+
+impl RateLimiter {
+    /// The limiter of `backend` under `config`'s limits
+    pub fn shared(config: &Config, backend: &str) -> Arc<Self> {
+        let limits = config.limits.for_backend(backend);
+        let mut limiters = LIMITERS.lock().unwrap_or_else(|e| e.into_inner());
+        let limiter = limiters
+            .entry(backend.to_string())
+            .or_insert_with(|| Arc::new(Self::new(backend, limits)));
+        // A config with other limits, e.g. reloaded, starts a fresh window
+        if limiter.limits != limits {
+            *limiter = Arc::new(Self::new(backend, limits));
+        }
+        limiter.clone()
+    }
+
+    fn new(backend: &str, limits: BackendLimits) -> Self {
+        Self {
+            backend: backend.to_string(),
+            limits,
+            window: Mutex::new(Window::default()),
+            health: Mutex::new(Latency::default()),
+        }
+    }
+
+    /// Health counts since the last call, which are reset
+    pub fn take_health(&self) -> Health {
+        let mut latency = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut latency.health)
+    }
+
+    /// Wait until a call expected to use `estimated_tokens` fits the limits
+    pub async fn acquire(self: &Arc<Self>, estimated_tokens: usize) -> Permit {
+        loop {
+            let wait = {
+                let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                match window.wait_time(now, estimated_tokens, &self.limits) {
+                    None => {
+                        let id = window.admit(now, estimated_tokens);
+                        return Permit {
+                            limiter: self.clone(),
+                            id,
+                            started: now,
+                        };
+                    }
+                    Some(wait) => wait,
+                }
+            };
+            info!(
+                "Rate limit of {} reached ([limits]), waiting {:.0}s",
+                self.backend,
+                wait.as_secs_f64().ceil()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Permit {
+    /// The call succeeded: count the tokens it really used and how long it took
+    pub fn finish(self, tokens_used: usize) {
+        self.set_tokens(tokens_used);
+        let mut latency = self
+            .limiter
+            .health
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        latency.record(self.started.elapsed());
+    }
+
+    /// The call failed; `rate_limited` if the backend refused it for
+    /// rate limiting or overload
+    pub fn failed(self, rate_limited: bool) {
+        self.set_tokens(0);
+        if rate_limited {
+            let mut latency = self
+                .limiter
+                .health
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            latency.health.rate_limited += 1;
+        }
+    }
+
+    fn set_tokens(&self, tokens: usize) {
+        let mut window = self
+            .limiter
+            .window
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(call) = window.calls.iter_mut().find(|call| call.id == self.id) {
+            call.tokens = tokens;
+        }
+    }
+}
+
+impl Latency {
+    /// Count a successful call as ok or slow, compared to the usual latency
+    fn record(&mut self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if self.calls >= BASELINE_CALLS && secs > self.average * SLOW_FACTOR {
+            self.health.slow += 1;
+        } else {
+            self.health.ok += 1;
+        }
+
+        // Slow calls count too, so lasting slowness becomes the new usual
+        self.calls = self.calls.saturating_add(1);
+        let weight = (1.0 / self.calls as f64).max(0.2);
+        self.average += (secs - self.average) * weight;
+    }
+}
+
+impl Window {
+    /// How long until a call of `tokens` fits, or None if it fits now
+    fn wait_time(
+        &mut self,
+        now: Instant,
+        tokens: usize,
+        limits: &BackendLimits,
+    ) -> Option<Duration> {
+        while self
+            .calls
+            .front()
+            .is_some_and(|call| now.duration_since(call.started) >= WINDOW)
+        {
+            self.calls.pop_front();
+        }
+        // Nothing to wait for, even if a single call is over the token limit
+        if limits.is_unlimited() || self.calls.is_empty() {
+            return None;
+        }
+
+        let rpm = limits.requests_per_minute as usize;
+        let tpm = limits.tokens_per_minute as usize;
+
+        // Drop the oldest calls until the new one fits; it can start once the
+        // last of them has left the window
+        let mut requests = self.calls.len();
+        let mut used: usize = self.calls.iter().map(|call| call.tokens).sum();
+        let mut until = None;
+        for call in &self.calls {
+            let over_requests = rpm != 0 && requests >= rpm;
+            let over_tokens = tpm != 0 && used + tokens > tpm;
+            if !over_requests && !over_tokens {
+                break;
+            }
+            requests -= 1;
+            used -= call.tokens;
+            until = Some(call.started + WINDOW);
+        }
+
+        until.map(|until| until.saturating_duration_since(now))
+    }
+
+    fn admit(&mut self, now: Instant, tokens: usize) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.calls.push_back(Call {
+            id,
+            started: now,
+            tokens,
+        });
+        id
+    }
+}
+
+/// Whether an agent failed because the backend refused its calls for rate
+/// limiting or overload, after its own retries
+pub fn is_rate_limited(error: &anyhow::Error) -> bool {
+    let refused = error.chain().any(|e| {
+        matches!(
+            e.downcast_ref::<ClaudeHelperError>(),
+            Some(ClaudeHelperError::ApiError {
+                status: 429 | 529,
+                ..
+            })
+        )
+    });
+    refused || is_rate_limit_message(&format!("{:#}", error))
+}
+
+/// Whether a `claude` failure is the API refusing calls for rate limiting or
+/// overload; Claude Code only reports these as text
+pub(super) fn is_rate_limit_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["rate limit", "rate_limit", "overloaded"]
+        .iter()
+        .any(|hint| message.contains(hint))
+}
+
+/// Rough token count of `text` for [`RateLimiter::acquire`], about four
+/// characters per token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len() / 4 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_wait_time() {
+        let limits = BackendLimits {
+            requests_per_minute: 2,
+            tokens_per_minute: 10_000,
+        };
+        let start = Instant::now();
+        let mut window = Window::default();
+
+        assert_eq!(window.wait_time(start, 4_000, &limits), None);
+        window.admit(start, 4_000);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(window.wait_time(later, 4_000, &limits), None);
+        window.admit(later, 4_000);
+
+        // Two requests in the window: the third waits for the first to expire
+        let now = start + Duration::from_secs(20);
+        assert_eq!(
+            window.wait_time(now, 100, &limits),
+            Some(Duration::from_secs(40))
+        );
+
+        // Tokens too: 8,000 used, so 7,000 more need both earlier calls gone
+        let tokens_only = BackendLimits {
+            requests_per_minute: 0,
+            ..limits
+        };
+        assert_eq!(window.wait_time(now, 1_000, &tokens_only), None);
+        assert_eq!(
+            window.wait_time(now, 7_000, &tokens_only),
+            Some(Duration::from_secs(50))
+        );
+
+        // Once a minute has passed the window is empty again
+        let next_minute = start + Duration::from_secs(75);
+        assert_eq!(window.wait_time(next_minute, 4_000, &limits), None);
+        assert_eq!(window.calls.len(), 0);
+        assert_eq!(window.wait_time(next_minute, 50_000, &limits), None);
+    }
+
+    #[test]
+    fn test_slow_calls() {
+        let mut latency = Latency::default();
+        // Slow from the start: nothing to compare with yet
+        latency.record(Duration::from_secs(30));
+        for _ in 0..5 {
+            latency.record(Duration::from_secs(2));
+        }
+        assert_eq!(latency.health.slow, 0);
+
+        latency.record(Duration::from_secs(60));
+        latency.record(Duration::from_secs(3));
+        assert_eq!(
+            std::mem::take(&mut latency.health),
+            Health {
+                ok: 7,
+                slow: 1,
+                rate_limited: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_errors() {
+        assert!(is_rate_limit_message(
+            r#"Claude Code returned an error (error_during_execution): API Error: 429 {"type":"error","error":{"type":"rate_limit_error"}}"#
+        ));
+        assert!(is_rate_limit_message("API Error: 529 Overloaded"));
+        assert!(!is_rate_limit_message(
+            "`claude` exited with exit status: 1: not logged in"
+        ));
+
+        let refused = anyhow::Error::from(ClaudeHelperError::ApiError {
+            status: 529,
+            message: "busy".to_string(),
+        })
+        .context("Code Writer failed");
+        assert!(is_rate_limited(&refused));
+        assert!(!is_rate_limited(&anyhow::anyhow!("backend unavailable")));
+    }
+}
@@ -0,0 +1,147 @@
+use serde_json::Value;
+
+/// Check `value` against a JSON Schema and list every violation with its
+/// path, e.g. `$.files[2]: expected string`. Supports the subset agents use:
+/// `type`, `properties`, `required`, `items` and `enum`.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, value, "$", &mut errors);
+    errors
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{}: must be one of {}",
+                path,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    errors.push(format!("{}: missing required property '{}'", path, key));
+                }
+            }
+        }
+
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (key, property_schema) in properties {
+                if let Some(property) = object.get(key) {
+                    check(
+                        property_schema,
+                        property,
+                        &format!("{}.{}", path, key),
+                        errors,
+                    );
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(item_schema, item, &format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Pull the JSON out of a model answer: the answer itself, or the first
+/// fenced block in it
+pub fn extract_json(output: &str) -> Result<Value, serde_json::Error> {
+    let trimmed = output.trim();
+    match serde_json::from_str(trimmed) {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            let fenced = trimmed.split_once("```").and_then(|(_, rest)| {
+                let rest = rest.strip_prefix("json").unwrap_or(rest);
+                rest.split_once("```").map(|(body, _)| body)
+            });
+            match fenced {
+                Some(body) => serde_json::from_str(body.trim()),
+                None => Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "required": ["files", "risk"],
+            "properties": {
+                "files": {"type": "array", "items": {"type": "string"}},
+                "risk": {"enum": ["low", "high"]}
+            }
+        });
+
+        assert!(validate(&schema, &json!({"files": ["a.rs"], "risk": "low"})).is_empty());
+        assert_eq!(
+            validate(&schema, &json!({"files": ["a.rs", 3]})),
+            vec![
+                "$: missing required property 'risk'",
+                "$.files[1]: expected string, got number"
+            ]
+        );
+        assert_eq!(
+            validate(&schema, &json!([])),
+            vec!["$: expected object, got array"]
+        );
+    }
+
+    #[test]
+    fn test_extract_json() {
+        assert_eq!(extract_json(" {\"a\": 1} ").unwrap(), json!({"a": 1}));
+        assert_eq!(
+            extract_json("Here you go:\n```json\n[1, 2]\n```\nDone.").unwrap(),
+            json!([1, 2])
+        );
+        assert!(extract_json("no json here").is_err());
+    }
+}
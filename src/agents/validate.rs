@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// How often an agent is asked to fix output that doesn't match its schema
+/// or fails a validator
+pub(super) const MAX_FIX_RETRIES: usize = 2;
+
+/// A check agent output must pass before it is accepted, e.g. that its
+/// diffs apply. What it finds is sent back to the agent to fix.
+#[async_trait]
+pub trait OutputValidator: Send + Sync {
+    /// Short name shown with its errors, e.g. `json`
+    fn name(&self) -> &str;
+
+    /// What is wrong with `output`; empty when it passes
+    async fn check(&self, output: &str) -> Vec<String>;
+}
+
+/// The errors of every validator on `output`, each with the validator's name
+pub(super) async fn check_all(
+    validators: &[Arc<dyn OutputValidator>],
+    output: &str,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for validator in validators {
+        errors.extend(
+            validator
+                .check(output)
+                .await
+                .into_iter()
+                .map(|error| format!("{}: {}", validator.name(), error)),
+        );
+    }
+    errors
+}
+
+/// Turn asking the agent to fix the `errors` validators found
+pub(super) fn fix_request(errors: &[String]) -> String {
+    format!(
+        "Your answer failed these checks:\n- {}\n\n\
+        Reply again with the whole corrected answer.",
+        errors.join("\n- ")
+    )
+}
@@ -0,0 +1,240 @@
+use super::optimizer::{Optimization, OptimizationType};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lines of the existing file shown above an addition
+const CONTEXT_LINES: usize = 3;
+
+/// One line of a change preview
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+/// A file change that applies an optimization (a project slash command for
+/// repeated command sequences, or a CLAUDE.md note for everything else) or
+/// writes a generated file
+#[derive(Debug)]
+pub struct ChangePreview {
+    pub path: PathBuf,
+    current: Option<String>,
+    proposed: String,
+    /// Append to the file instead of replacing it
+    append: bool,
+}
+
+impl ChangePreview {
+    /// Work out the change for `opt` in the project at `project_dir`
+    /// without writing anything
+    pub fn for_optimization(opt: &Optimization, project_dir: &Path) -> Result<Self> {
+        match opt.opt_type {
+            OptimizationType::QuickCommand | OptimizationType::ParameterizedScript => {
+                let path = project_dir
+                    .join(".claude")
+                    .join("commands")
+                    .join(format!("{}.md", command_name(&opt.title)));
+                Ok(Self {
+                    current: read_existing(&path)?,
+                    proposed: slash_command(opt),
+                    append: false,
+                    path,
+                })
+            }
+            _ => {
+                let path = project_dir.join("CLAUDE.md");
+                let current = read_existing(&path)?;
+                let heading = format!("## {}", opt.title);
+                if current
+                    .as_deref()
+                    .is_some_and(|c| c.lines().any(|l| l.trim() == heading))
+                {
+                    anyhow::bail!("CLAUDE.md already has a section for '{}'", opt.title);
+                }
+
+                Ok(Self {
+                    current,
+                    proposed: claude_md_section(opt),
+                    append: true,
+                    path,
+                })
+            }
+        }
+    }
+
+    /// Replace `path`, if it exists, with `proposed`
+    pub fn replace(path: PathBuf, proposed: String) -> Result<Self> {
+        Ok(Self {
+            current: read_existing(&path)?,
+            proposed,
+            append: false,
+            path,
+        })
+    }
+
+    /// The change as a line diff
+    pub fn diff(&self) -> Vec<DiffLine> {
+        let current: Vec<&str> = self
+            .current
+            .as_deref()
+            .map_or(Vec::new(), |c| c.lines().collect());
+        let mut diff = Vec::new();
+
+        if self.append {
+            let start = current.len().saturating_sub(CONTEXT_LINES);
+            diff.extend(
+                current[start..]
+                    .iter()
+                    .map(|l| DiffLine::Context(l.to_string())),
+            );
+        } else {
+            diff.extend(current.iter().map(|l| DiffLine::Removed(l.to_string())));
+        }
+        diff.extend(
+            self.proposed
+                .lines()
+                .map(|l| DiffLine::Added(l.to_string())),
+        );
+
+        diff
+    }
+
+    /// Write the change to disk
+    pub fn apply(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+
+        let contents = match (&self.current, self.append) {
+            (Some(current), true) => format!("{}{}", current, self.proposed),
+            _ => self.proposed.clone(),
+        };
+        fs::write(&self.path, contents).with_context(|| format!("Failed to write {:?}", self.path))
+    }
+}
+
+fn read_existing(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+/// Slash command file name from an optimization title, e.g.
+/// "Create test-and-build script" -> "create-test-and-build-script"
+fn command_name(title: &str) -> String {
+    let name: String = title
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    let name: String = name.chars().take(40).collect();
+    let name = name.trim_end_matches('-');
+    if name.is_empty() {
+        "optimization".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn slash_command(opt: &Optimization) -> String {
+    let mut text = format!(
+        "---\ndescription: {}\n---\n\n{}\n",
+        opt.title, opt.description
+    );
+
+    if let Some(suggestion) = &opt.suggestion {
+        text.push_str(&format!("\n{}\n", suggestion));
+    }
+    if !opt.examples.is_empty() {
+        text.push_str("\nCommands this replaces:\n");
+        for example in &opt.examples {
+            text.push_str(&format!("- `{}`\n", example));
+        }
+    }
+
+    text
+}
+
+fn claude_md_section(opt: &Optimization) -> String {
+    let mut text = format!("\n## {}\n\n{}\n", opt.title, opt.description);
+
+    if let Some(suggestion) = &opt.suggestion {
+        text.push_str(&format!("\n{}\n", suggestion));
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn optimization(opt_type: OptimizationType, title: &str) -> Optimization {
+        Optimization {
+            opt_type,
+            title: title.to_string(),
+            description: "Why it helps".to_string(),
+            estimated_savings: 500,
+            examples: vec!["git add -A".to_string()],
+            suggestion: Some("Do it this way".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_command_name() {
+        assert_eq!(
+            command_name("Create test-and-build script"),
+            "create-test-and-build-script"
+        );
+        assert_eq!(command_name("  !!  "), "optimization");
+    }
+
+    #[test]
+    fn test_slash_command_preview_and_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let opt = optimization(OptimizationType::QuickCommand, "Combine git operations");
+
+        let preview = ChangePreview::for_optimization(&opt, dir.path()).unwrap();
+        assert!(preview
+            .path
+            .ends_with(".claude/commands/combine-git-operations.md"));
+        assert!(preview
+            .diff()
+            .iter()
+            .all(|line| matches!(line, DiffLine::Added(_))));
+
+        preview.apply().unwrap();
+        let written = fs::read_to_string(&preview.path).unwrap();
+        assert!(written.starts_with("---\ndescription: Combine git operations\n"));
+        assert!(written.contains("- `git add -A`"));
+    }
+
+    #[test]
+    fn test_claude_md_appends_once() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("CLAUDE.md"),
+            "# Project\n\nExisting notes\n",
+        )
+        .unwrap();
+        let opt = optimization(OptimizationType::FileMerge, "Merge helpers");
+
+        let preview = ChangePreview::for_optimization(&opt, dir.path()).unwrap();
+        let diff = preview.diff();
+        assert_eq!(diff[0], DiffLine::Context("# Project".to_string()));
+        assert!(diff.contains(&DiffLine::Added("## Merge helpers".to_string())));
+
+        preview.apply().unwrap();
+        let written = fs::read_to_string(dir.path().join("CLAUDE.md")).unwrap();
+        assert!(written.starts_with("# Project\n\nExisting notes\n\n## Merge helpers\n"));
+
+        // A second apply would duplicate the section
+        assert!(ChangePreview::for_optimization(&opt, dir.path()).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use super::session_parser::transcript_name;
+use super::SessionAnalyzer;
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Gzip `path` to `<name>.gz` next to it, keeping its modification time so
+/// it sorts as before, and remove the original
+pub fn compress(path: &Path) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Session file has no name")?;
+    let archived = path.with_file_name(format!("{}.gz", name));
+    // Not a transcript name, so a half-written archive is never listed
+    let partial = path.with_file_name(format!("{}.gz.partial", name));
+
+    let modified = fs::metadata(path)?.modified()?;
+    let mut input = fs::File::open(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let output =
+        fs::File::create(&partial).with_context(|| format!("Failed to create {:?}", partial))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    let written = io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .and_then(|file| file.set_modified(modified));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&partial);
+        return Err(e).with_context(|| format!("Failed to compress {:?}", path));
+    }
+
+    fs::rename(&partial, &archived)
+        .with_context(|| format!("Failed to move {:?} into place", archived))?;
+    fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))?;
+    Ok(archived)
+}
+
+/// Uncompressed transcripts in `dir` last modified before `cutoff`
+fn stale_transcripts(dir: &Path, cutoff: SystemTime) -> Result<Vec<PathBuf>> {
+    let mut stale = Vec::new();
+    for entry in fs::read_dir(dir).context("Failed to read sessions directory")? {
+        let path = entry?.path();
+        let uncompressed = transcript_name(&path).is_some_and(|(_, compressed)| !compressed);
+        if uncompressed
+            && fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified < cutoff)
+        {
+            stale.push(path);
+        }
+    }
+    stale.sort();
+    Ok(stale)
+}
+
+impl SessionAnalyzer {
+    /// Compress the session transcripts not written to for `days` days.
+    /// They stay listed and analyzable as `.jsonl.gz`.
+    pub async fn archive_sessions(&self, days: u64) -> Result<()> {
+        let dir = self.parser.get_sessions_dir()?;
+        if !dir.exists() {
+            println!("\n{}", "No Claude Code sessions found.".yellow());
+            return Ok(());
+        }
+
+        let cutoff = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        let stale = stale_transcripts(&dir, cutoff)?;
+        if stale.is_empty() {
+            println!("No sessions older than {} days to archive.", days);
+            return Ok(());
+        }
+
+        let (mut before, mut after) = (0, 0);
+        for path in &stale {
+            before += fs::metadata(path).map_or(0, |m| m.len());
+            let archived = compress(path)?;
+            after += fs::metadata(&archived).map_or(0, |m| m.len());
+        }
+
+        println!(
+            "{} Archived {} sessions ({} KB -> {} KB)",
+            style::ok().green(),
+            stale.len(),
+            before / 1024,
+            after / 1024
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::session_parser::read_transcript;
+
+    #[test]
+    fn test_compress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.jsonl");
+        fs::write(&path, "{\"role\":\"user\",\"content\":\"Hi\"}\n").unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+        let archived = compress(&path).unwrap();
+        assert_eq!(archived, dir.path().join("abc.jsonl.gz"));
+        assert!(!path.exists());
+        assert_eq!(
+            fs::metadata(&archived).unwrap().modified().unwrap(),
+            modified
+        );
+        assert_eq!(
+            read_transcript(&archived).unwrap(),
+            "{\"role\":\"user\",\"content\":\"Hi\"}\n"
+        );
+
+        // Archives are not archived again
+        let cutoff = SystemTime::now() + Duration::from_secs(60);
+        assert!(stale_transcripts(dir.path(), cutoff).unwrap().is_empty());
+    }
+}
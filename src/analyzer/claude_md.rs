@@ -0,0 +1,400 @@
+use super::apply::{ChangePreview, DiffLine};
+use super::session_parser::SessionData;
+use super::SessionAnalyzer;
+use crate::style::{self, Colorize};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Directories never worth describing
+const SKIPPED_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "vendor",
+    "venv",
+    "__pycache__",
+];
+
+/// Entries listed per section
+const LIST_LIMIT: usize = 8;
+
+/// A toolchain found in the repository and how to work with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toolchain {
+    pub language: &'static str,
+    /// The file it was recognized by
+    pub manifest: &'static str,
+    pub build: Option<String>,
+    pub test: Option<String>,
+    pub lint: Option<String>,
+}
+
+/// What a starter CLAUDE.md says about a repository
+#[derive(Debug, Clone, Default)]
+pub struct RepoProfile {
+    pub name: String,
+    pub toolchains: Vec<Toolchain>,
+    /// Top-level directories with the number of files in each
+    pub layout: Vec<(String, usize)>,
+    /// Project files Claude read most often in past sessions, relative to
+    /// the root, with their read counts
+    pub hot_files: Vec<(String, usize)>,
+    /// Shell commands run most often in past sessions, with run counts
+    pub commands: Vec<(String, usize)>,
+}
+
+impl RepoProfile {
+    /// Inspect the repository at `root` and what past `sessions` did in it
+    pub fn inspect(root: &Path, sessions: &[SessionData]) -> Self {
+        let mut profile = Self {
+            name: root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "Project".to_string()),
+            toolchains: detect_toolchains(root),
+            layout: layout(root),
+            ..Default::default()
+        };
+        profile.add_history(root, sessions);
+        profile
+    }
+
+    /// Hot files and frequent commands of the sessions that touched `root`
+    fn add_history(&mut self, root: &Path, sessions: &[SessionData]) {
+        let prefix = format!("{}/", root.to_string_lossy().trim_end_matches('/'));
+        let mut reads: HashMap<String, usize> = HashMap::new();
+        let mut commands: HashMap<String, usize> = HashMap::new();
+
+        for session in sessions {
+            let mut in_project = false;
+            for usage in session.file_usage() {
+                if let Some(relative) = usage.file_path.strip_prefix(&prefix) {
+                    in_project = true;
+                    if usage.reads > 0 {
+                        *reads.entry(relative.to_string()).or_default() += usage.reads;
+                    }
+                }
+            }
+            if !in_project {
+                continue;
+            }
+
+            let bash = session
+                .tool_calls
+                .iter()
+                .filter(|call| call.tool_name == "Bash")
+                .filter_map(|call| call.parameters.get("command")?.as_str());
+            for command in bash {
+                let command = command.trim();
+                if !command.is_empty() && !command.contains('\n') {
+                    *commands.entry(command.to_string()).or_default() += 1;
+                }
+            }
+        }
+
+        self.hot_files = top(reads, 2);
+        self.commands = top(commands, 3);
+    }
+
+    /// The starter CLAUDE.md
+    pub fn render(&self) -> String {
+        let mut text = format!(
+            "# {}\n\nNotes for Claude Code about this repository, so it can start \
+            working without exploring first. Generated by `claude-helper generate \
+            claude-md`; edit freely.\n",
+            self.name
+        );
+
+        if !self.toolchains.is_empty() {
+            text.push_str("\n## Commands\n\n");
+            for toolchain in &self.toolchains {
+                text.push_str(&format!(
+                    "{} (`{}`):\n",
+                    toolchain.language, toolchain.manifest
+                ));
+                for (label, command) in [
+                    ("Build", &toolchain.build),
+                    ("Test", &toolchain.test),
+                    ("Lint", &toolchain.lint),
+                ] {
+                    if let Some(command) = command {
+                        text.push_str(&format!("- {}: `{}`\n", label, command));
+                    }
+                }
+            }
+        }
+
+        if !self.layout.is_empty() {
+            text.push_str("\n## Layout\n\n");
+            for (dir, files) in &self.layout {
+                text.push_str(&format!("- `{}/` ({} files)\n", dir, files));
+            }
+        }
+
+        if !self.hot_files.is_empty() {
+            text.push_str(
+                "\n## Key files\n\nRead most often in past sessions; start here \
+                instead of searching:\n\n",
+            );
+            for (file, _) in &self.hot_files {
+                text.push_str(&format!("- `{}`\n", file));
+            }
+        }
+
+        if !self.commands.is_empty() {
+            text.push_str("\n## Frequently run\n\n");
+            for (command, _) in &self.commands {
+                text.push_str(&format!("- `{}`\n", command));
+            }
+        }
+
+        text
+    }
+}
+
+/// The most frequent entries seen at least `min` times, up to `LIST_LIMIT`
+fn top(counts: HashMap<String, usize>, min: usize) -> Vec<(String, usize)> {
+    let mut entries: Vec<(String, usize)> = counts.into_iter().filter(|(_, n)| *n >= min).collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(LIST_LIMIT);
+    entries
+}
+
+fn detect_toolchains(root: &Path) -> Vec<Toolchain> {
+    let has = |file: &str| root.join(file).is_file();
+    let mut toolchains = Vec::new();
+
+    if has("Cargo.toml") {
+        toolchains.push(Toolchain {
+            language: "Rust",
+            manifest: "Cargo.toml",
+            build: Some("cargo build".to_string()),
+            test: Some("cargo test".to_string()),
+            lint: Some("cargo clippy --all-targets -- -D warnings".to_string()),
+        });
+    }
+    if has("package.json") {
+        toolchains.push(node_toolchain(root));
+    }
+    if has("pyproject.toml") || has("requirements.txt") || has("setup.py") {
+        toolchains.push(Toolchain {
+            language: "Python",
+            manifest: if has("pyproject.toml") {
+                "pyproject.toml"
+            } else if has("requirements.txt") {
+                "requirements.txt"
+            } else {
+                "setup.py"
+            },
+            build: None,
+            test: Some("pytest".to_string()),
+            lint: Some("ruff check .".to_string()),
+        });
+    }
+    if has("go.mod") {
+        toolchains.push(Toolchain {
+            language: "Go",
+            manifest: "go.mod",
+            build: Some("go build ./...".to_string()),
+            test: Some("go test ./...".to_string()),
+            lint: Some("go vet ./...".to_string()),
+        });
+    }
+    if has("pom.xml") {
+        toolchains.push(Toolchain {
+            language: "Java",
+            manifest: "pom.xml",
+            build: Some("mvn -q compile".to_string()),
+            test: Some("mvn -q test".to_string()),
+            lint: None,
+        });
+    }
+    if has("Makefile") && toolchains.is_empty() {
+        toolchains.push(Toolchain {
+            language: "Make",
+            manifest: "Makefile",
+            build: Some("make".to_string()),
+            test: Some("make test".to_string()),
+            lint: None,
+        });
+    }
+
+    toolchains
+}
+
+/// Node commands from the `scripts` of package.json
+fn node_toolchain(root: &Path) -> Toolchain {
+    let scripts: HashMap<String, serde_json::Value> = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|json| serde_json::from_value(json.get("scripts")?.clone()).ok())
+        .unwrap_or_default();
+    let runner = if root.join("pnpm-lock.yaml").is_file() {
+        "pnpm"
+    } else if root.join("yarn.lock").is_file() {
+        "yarn"
+    } else {
+        "npm"
+    };
+    let script = |name: &str| {
+        scripts.contains_key(name).then(|| match (runner, name) {
+            ("npm", "test") => "npm test".to_string(),
+            ("npm", _) => format!("npm run {}", name),
+            _ => format!("{} {}", runner, name),
+        })
+    };
+
+    Toolchain {
+        language: "JavaScript/TypeScript",
+        manifest: "package.json",
+        build: script("build"),
+        test: script("test"),
+        lint: script("lint"),
+    }
+}
+
+/// Top-level directories and how many files each holds
+fn layout(root: &Path) -> Vec<(String, usize)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(String, usize)> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            (!name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()))
+                .then(|| (name, count_files(&entry.path())))
+        })
+        .collect();
+    dirs.sort();
+    dirs.truncate(LIST_LIMIT * 2);
+    dirs
+}
+
+fn count_files(dir: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                count_files(&path)
+            } else {
+                1
+            }
+        })
+        .sum()
+}
+
+impl SessionAnalyzer {
+    /// Generate a starter CLAUDE.md for the repository at `root`, show how
+    /// it changes the current one and write it once confirmed (or right
+    /// away with `yes`)
+    pub async fn generate_claude_md(&self, root: &Path, yes: bool) -> Result<()> {
+        use dialoguer::Confirm;
+
+        let sessions: Vec<SessionData> = self
+            .list_sessions(self.config.analyzer.history_depth)?
+            .into_iter()
+            .map(|session| session.data)
+            .collect();
+        let profile = RepoProfile::inspect(root, &sessions);
+        let preview = ChangePreview::replace(root.join("CLAUDE.md"), profile.render())?;
+
+        println!(
+            "\n{} {}",
+            "Proposed".bright_cyan().bold(),
+            preview.path.display()
+        );
+        println!("{}", style::rule(60).bright_cyan());
+        for line in preview.diff() {
+            match line {
+                DiffLine::Context(l) => println!("  {}", l),
+                DiffLine::Added(l) => println!("{}", format!("+ {}", l).green()),
+                DiffLine::Removed(l) => println!("{}", format!("- {}", l).red()),
+            }
+        }
+        println!("{}", style::rule(60).bright_cyan());
+
+        let write = yes
+            || Confirm::new()
+                .with_prompt(format!("Write {}?", preview.path.display()))
+                .default(false)
+                .interact()?;
+        if !write {
+            println!("Nothing written.");
+            return Ok(());
+        }
+
+        preview.apply()?;
+        println!("{} Wrote {}", style::ok().green(), preview.path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::session_parser::{FileAccess, ToolCall};
+
+    #[test]
+    fn test_inspect_and_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"scripts": {"build": "vite build", "test": "vitest"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/bin/cli.rs"), "").unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+
+        let lib = root.join("src/lib.rs").to_string_lossy().into_owned();
+        let mut session = SessionData::new("s");
+        session.file_accesses = (0..3)
+            .map(|_| FileAccess {
+                file_path: lib.clone(),
+                operation: "read".to_string(),
+                line_count: None,
+            })
+            .collect();
+        session.tool_calls = (0..3)
+            .map(|_| ToolCall {
+                tool_name: "Bash".to_string(),
+                parameters: serde_json::json!({ "command": "cargo test -q" }),
+                timestamp: None,
+            })
+            .collect();
+        // Sessions elsewhere do not count
+        let mut other = session.clone();
+        other.file_accesses[0].file_path = "/elsewhere/a.rs".to_string();
+        other.file_accesses.truncate(1);
+
+        let profile = RepoProfile::inspect(root, &[session, other]);
+        assert_eq!(profile.toolchains.len(), 2);
+        assert_eq!(
+            profile.toolchains[1].build.as_deref(),
+            Some("npm run build")
+        );
+        assert_eq!(profile.toolchains[1].lint, None);
+        assert_eq!(profile.layout, vec![("src".to_string(), 2)]);
+        assert_eq!(profile.hot_files, vec![("src/lib.rs".to_string(), 3)]);
+        assert_eq!(profile.commands, vec![("cargo test -q".to_string(), 3)]);
+
+        let text = profile.render();
+        assert!(text.contains("- Test: `cargo test`\n"));
+        assert!(text.contains("- `src/` (2 files)\n"));
+        assert!(text.contains("## Key files"));
+        assert!(text.contains("- `cargo test -q`\n"));
+    }
+}
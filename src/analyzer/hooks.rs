@@ -0,0 +1,320 @@
+use super::session_parser::SessionData;
+use super::SessionAnalyzer;
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+
+/// Runs of the same lint command after which linting on every edit pays off
+const LINT_REPEATS: usize = 3;
+
+/// Runs of the same read-only command after which approving it is a chore
+const PROMPT_REPEATS: usize = 5;
+
+/// Path of the file an Edit or Write hook is about, from the hook's input
+const HOOK_FILE: &str = "\"$(jq -r .tool_input.file_path)\"";
+
+/// Lint and format commands that can run on each edited file instead:
+/// what to look for in Bash commands and the hook command
+const LINTERS: &[(&str, &str)] = &[
+    ("cargo fmt", "cargo fmt"),
+    (
+        "cargo clippy",
+        "out=$(cargo clippy --quiet 2>&1) || { echo \"$out\" | tail -30 >&2; exit 2; }",
+    ),
+    ("eslint", "npx eslint --fix FILE"),
+    ("prettier", "npx prettier --write FILE"),
+    ("ruff", "ruff check --fix FILE"),
+    ("black", "black --quiet FILE"),
+];
+
+/// Destructive commands worth blocking: what to look for and the
+/// extended regex the guard hook matches
+const DANGEROUS: &[(&str, &str)] = &[
+    ("rm -rf", "rm -rf"),
+    ("git push --force", "git push (-f|--force)"),
+    ("git push -f", "git push (-f|--force)"),
+    ("git reset --hard", "git reset --hard"),
+    ("git clean -f", "git clean -[a-z]*f"),
+];
+
+/// Read-only commands that need no approval
+const READ_ONLY: &[&str] = &["git status", "git diff", "git log", "ls", "cargo check"];
+
+/// A Claude Code hook that addresses a pattern seen in the sessions
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookSuggestion {
+    /// `PreToolUse` or `PostToolUse`
+    pub event: &'static str,
+    /// Tools the hook applies to
+    pub matcher: &'static str,
+    pub command: String,
+    /// What was seen and what the hook does about it
+    pub reason: String,
+}
+
+impl HookSuggestion {
+    /// The entry as it goes under `hooks.<event>` in settings.json
+    pub fn entry(&self) -> Value {
+        json!({
+            "matcher": self.matcher,
+            "hooks": [{ "type": "command", "command": self.command }]
+        })
+    }
+}
+
+/// Hooks for the recurring tool patterns in `sessions` and their sidechains
+pub fn suggest(sessions: &[SessionData]) -> Vec<HookSuggestion> {
+    let commands: Vec<&str> = sessions
+        .iter()
+        .flat_map(|s| std::iter::once(s).chain(&s.sidechains))
+        .flat_map(|s| &s.tool_calls)
+        .filter(|call| call.tool_name == "Bash")
+        .filter_map(|call| call.parameters.get("command").and_then(Value::as_str))
+        .collect();
+    let runs = |needle: &str| {
+        commands
+            .iter()
+            .filter(|command| command.contains(needle))
+            .count()
+    };
+    let mut suggestions = Vec::new();
+
+    for (needle, hook) in LINTERS {
+        let count = runs(needle);
+        if count >= LINT_REPEATS {
+            suggestions.push(HookSuggestion {
+                event: "PostToolUse",
+                matcher: "Edit|Write",
+                command: hook.replace("FILE", HOOK_FILE),
+                reason: format!(
+                    "`{}` was run {} times; run it after every edit instead",
+                    needle, count
+                ),
+            });
+        }
+    }
+
+    let mut seen = Vec::new();
+    let mut patterns = Vec::new();
+    for (needle, pattern) in DANGEROUS {
+        if runs(needle) > 0 {
+            seen.push(*needle);
+            if !patterns.contains(pattern) {
+                patterns.push(*pattern);
+            }
+        }
+    }
+    if !patterns.is_empty() {
+        suggestions.push(HookSuggestion {
+            event: "PreToolUse",
+            matcher: "Bash",
+            command: format!(
+                "jq -r .tool_input.command | grep -qE '{}' && {{ echo 'Blocked by a claude-helper hook: destructive command' >&2; exit 2; }}; exit 0",
+                patterns.join("|")
+            ),
+            reason: format!("Destructive commands were run: {}", seen.join(", ")),
+        });
+    }
+
+    let frequent: Vec<&str> = READ_ONLY
+        .iter()
+        .copied()
+        .filter(|needle| {
+            commands
+                .iter()
+                .filter(|command| {
+                    command
+                        .strip_prefix(needle)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+                })
+                .count()
+                >= PROMPT_REPEATS
+        })
+        .collect();
+    if !frequent.is_empty() {
+        suggestions.push(HookSuggestion {
+            event: "PreToolUse",
+            matcher: "Bash",
+            command: format!(
+                "jq -r .tool_input.command | grep -qE '^({})( |$)' && echo '{{\"hookSpecificOutput\":{{\"hookEventName\":\"PreToolUse\",\"permissionDecision\":\"allow\"}}}}'; exit 0",
+                frequent.join("|")
+            ),
+            reason: format!(
+                "Read-only commands were approved over and over: {}",
+                frequent.join(", ")
+            ),
+        });
+    }
+
+    suggestions
+}
+
+/// Add the suggested hooks to Claude Code `settings`, keeping what is
+/// already there. Returns how many were added.
+pub fn merge(settings: &mut Value, suggestions: &[HookSuggestion]) -> Result<usize> {
+    let settings = settings
+        .as_object_mut()
+        .context("settings.json is not a JSON object")?;
+    let hooks = settings
+        .entry("hooks")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .context("`hooks` in settings.json is not an object")?;
+
+    let mut added = 0;
+    for suggestion in suggestions {
+        let entries = hooks
+            .entry(suggestion.event)
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+            .with_context(|| {
+                format!(
+                    "`hooks.{}` in settings.json is not a list",
+                    suggestion.event
+                )
+            })?;
+        let entry = suggestion.entry();
+        if !entries.contains(&entry) {
+            entries.push(entry);
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+impl SessionAnalyzer {
+    /// Suggest hooks for the recurring tool patterns in the last `count`
+    /// sessions and offer to add them to Claude Code's settings.json
+    pub async fn suggest_hooks(&self, count: usize) -> Result<()> {
+        use dialoguer::Confirm;
+
+        println!("\n{}", "Hook Suggestions".bright_cyan().bold());
+        println!("{}", style::rule(60).bright_cyan());
+
+        let sessions: Vec<SessionData> = self
+            .list_sessions(count)?
+            .into_iter()
+            .map(|session| session.data)
+            .collect();
+        let suggestions = suggest(&sessions);
+        if suggestions.is_empty() {
+            println!(
+                "\n{}",
+                format!(
+                    "No recurring patterns that a hook would help with {}",
+                    style::ok()
+                )
+                .green()
+            );
+            return Ok(());
+        }
+
+        for (i, suggestion) in suggestions.iter().enumerate() {
+            println!(
+                "\n{} {}",
+                format!("{}.", i + 1).bright_yellow().bold(),
+                suggestion.reason.white().bold()
+            );
+            println!(
+                "  {}",
+                serde_json::to_string_pretty(&json!({ suggestion.event: [suggestion.entry()] }))?
+                    .replace('\n', "\n  ")
+            );
+        }
+        println!(
+            "\n{} The hooks use `jq` to read the tool input.",
+            style::arrow().bright_cyan()
+        );
+
+        let settings_path = crate::config::auth::AuthConfig::claude_settings_path()
+            .context("Could not find Claude settings path")?;
+        let install = Confirm::new()
+            .with_prompt(format!("Add these hooks to {:?}?", settings_path))
+            .default(false)
+            .interact()?;
+        if !install {
+            return Ok(());
+        }
+
+        let mut settings: Value = if settings_path.exists() {
+            let content =
+                fs::read_to_string(&settings_path).context("Failed to read Claude settings")?;
+            fs::write(settings_path.with_extension("json.backup"), &content)
+                .context("Failed to create backup of settings.json")?;
+            serde_json::from_str(&content).context("Failed to parse Claude settings")?
+        } else {
+            json!({})
+        };
+        let added = merge(&mut settings, &suggestions)?;
+        if let Some(dir) = settings_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&settings_path, serde_json::to_string_pretty(&settings)?)
+            .context("Failed to write Claude settings")?;
+
+        println!(
+            "{} Added {} hooks (existing settings backed up to settings.json.backup)",
+            style::ok().green(),
+            added
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::session_parser::ToolCall;
+
+    fn session(commands: &[&str]) -> SessionData {
+        let mut session = SessionData::new("s");
+        session.tool_calls = commands
+            .iter()
+            .map(|command| ToolCall {
+                tool_name: "Bash".to_string(),
+                parameters: json!({ "command": command }),
+                timestamp: None,
+            })
+            .collect();
+        session
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut commands = vec!["cargo clippy -- -D warnings"; 3];
+        commands.extend(["git status"; 5]);
+        commands.extend(["lsof -i"; 5]);
+        commands.extend(["rm -rf target", "git push --force", "cargo fmt"]);
+        let suggestions = suggest(&[session(&commands)]);
+
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].event, "PostToolUse");
+        assert!(suggestions[0].command.contains("cargo clippy"));
+        assert!(suggestions[1]
+            .command
+            .contains("grep -qE 'rm -rf|git push (-f|--force)'"));
+        assert!(suggestions[2].command.contains("'^(git status)( |$)'"));
+        assert!(suggest(&[session(&["cargo test"])]).is_empty());
+    }
+
+    #[test]
+    fn test_merge() {
+        let suggestions = suggest(&[session(&["ruff check ."; 3])]);
+        assert!(suggestions[0].command.contains(HOOK_FILE));
+
+        let mut settings = json!({
+            "hooks": { "PostToolUse": [{ "matcher": "*", "hooks": [] }] },
+            "model": "opus"
+        });
+        assert_eq!(merge(&mut settings, &suggestions).unwrap(), 1);
+        assert_eq!(merge(&mut settings, &suggestions).unwrap(), 0);
+        assert_eq!(
+            settings["hooks"]["PostToolUse"].as_array().unwrap().len(),
+            2
+        );
+        assert_eq!(settings["model"], "opus");
+
+        assert!(merge(&mut json!([]), &suggestions).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use super::session_parser::SessionData;
+use super::{Optimization, SessionAnalyzer};
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{debug, warn};
+
+/// Claude Code writes a turn as several lines in quick succession; wait
+/// this long after a change before parsing the session again
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Optimizations listed while watching, highest savings first
+const SHOWN_OPTIMIZATIONS: usize = 5;
+
+/// What `claude-helper analyze --watch` does besides redrawing the session
+#[derive(Debug, Clone, Default)]
+pub struct LiveOptions {
+    /// Context window percentages to warn about when crossed
+    pub context_thresholds: Vec<u8>,
+    /// Show a desktop notification on each crossing
+    pub notify: bool,
+}
+
+/// Context window thresholds a session grew past between two readings.
+/// The first reading counts as rising from zero.
+fn crossed(previous: Option<u8>, percent: u8, thresholds: &[u8]) -> Vec<u8> {
+    let before = previous.unwrap_or(0);
+    thresholds
+        .iter()
+        .copied()
+        .filter(|&t| before < t && percent >= t)
+        .collect()
+}
+
+/// Share of `context_window` taken up by `tokens`, capped at 100
+fn context_percent(tokens: usize, context_window: usize) -> u8 {
+    (tokens * 100 / context_window.max(1)).min(100) as u8
+}
+
+impl SessionAnalyzer {
+    /// Follow the active session transcript, redrawing its token
+    /// composition and optimizations whenever Claude Code writes to it
+    pub async fn watch_session(&self, options: &LiveOptions) -> Result<()> {
+        let dir = self.parser.get_sessions_dir()?;
+        if !dir.exists() {
+            anyhow::bail!(
+                "No Claude Code sessions found at {:?}. \
+                Please ensure Claude Code is installed.",
+                dir
+            );
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .context("Failed to start watching sessions")?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {:?}", dir))?;
+
+        let mut active: Option<PathBuf> = None;
+        let mut previous_percent: Option<u8> = None;
+        loop {
+            match self.parser.find_recent_sessions(1)?.into_iter().next() {
+                Some(path) => {
+                    if active.as_ref() != Some(&path) {
+                        previous_percent = None;
+                        active = Some(path.clone());
+                    }
+                    match self.parser.parse_session(&path) {
+                        Ok(session) => {
+                            let percent = self.show_live(&session, &path)?;
+                            for threshold in
+                                crossed(previous_percent, percent, &options.context_thresholds)
+                            {
+                                warn_context(threshold, percent, options).await;
+                            }
+                            previous_percent = Some(percent);
+                        }
+                        Err(e) => debug!("Session {:?} not readable: {:#}", path, e),
+                    }
+                }
+                None => println!("{}", "Waiting for a Claude Code session...".yellow()),
+            }
+
+            wait_for_change(&mut rx).await?;
+        }
+    }
+
+    /// Redraw the live view of `session`; returns how full its context is
+    fn show_live(&self, session: &SessionData, path: &Path) -> Result<u8> {
+        let context_window = self.config.analyzer.context_window;
+        let composition = session.token_composition();
+        let tokens: usize = composition.iter().map(|(_, tokens)| tokens).sum();
+        // The latest prompt's recorded size is exact; fall back to the
+        // estimate for transcripts without usage
+        let context = session.context_tokens().unwrap_or(tokens);
+        let percent = context_percent(context, context_window);
+        let mut optimizations: Vec<Optimization> = self.optimizer.analyze(session)?;
+        optimizations.sort_by_key(|opt| std::cmp::Reverse(opt.estimated_savings));
+
+        // Clear screen and move to top
+        print!("\x1B[2J\x1B[1;1H");
+
+        println!(
+            "\n{}",
+            format!("Live Session: {}", session.session_id)
+                .bright_cyan()
+                .bold()
+        );
+        println!("{}", style::rule(60).bright_cyan());
+
+        let usage = format!(
+            "{}{} / {} tokens ({}%)",
+            if session.context_tokens().is_some() {
+                ""
+            } else {
+                "~"
+            },
+            context,
+            context_window,
+            percent
+        );
+        let usage = if percent >= 90 {
+            usage.bright_red()
+        } else if percent >= 70 {
+            usage.yellow()
+        } else {
+            usage.green()
+        };
+        println!("\n{} {}", "Context:".white().bold(), usage);
+        if let Some(used) = session.token_usage() {
+            println!(
+                "{} {} in, {} out, {} cache read, {} cache write",
+                "Used:".white().bold(),
+                used.input_tokens,
+                used.output_tokens,
+                used.cache_read_input_tokens,
+                used.cache_creation_input_tokens
+            );
+        }
+
+        println!("\n{}", "Token Composition (estimated):".white().bold());
+        for (part, part_tokens) in &composition {
+            println!(
+                "  {:<12} {:>8} tokens {:>5.1}%",
+                part,
+                part_tokens,
+                *part_tokens as f64 / tokens.max(1) as f64 * 100.0
+            );
+        }
+
+        let tools = session.tool_usage();
+        if !tools.is_empty() {
+            let top: Vec<String> = tools
+                .iter()
+                .take(5)
+                .map(|(tool, calls)| format!("{} {}", tool, calls))
+                .collect();
+            println!("\n{} {}", "Tool calls:".white().bold(), top.join(", "));
+        }
+
+        println!("\n{}", "Optimizations:".white().bold());
+        if optimizations.is_empty() {
+            println!("  None detected yet");
+        }
+        for opt in optimizations.iter().take(SHOWN_OPTIMIZATIONS) {
+            println!(
+                "  {} {} {}",
+                style::bullet().bright_cyan(),
+                opt.title,
+                format!("(~{} tokens)", opt.estimated_savings).green()
+            );
+        }
+        if optimizations.len() > SHOWN_OPTIMIZATIONS {
+            println!(
+                "  ...and {} more (claude-helper optimize --session {})",
+                optimizations.len() - SHOWN_OPTIMIZATIONS,
+                session.session_id
+            );
+        }
+
+        println!(
+            "\n{}",
+            format!(
+                "Watching {} (Ctrl+C to exit)...",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            )
+            .italic()
+        );
+
+        Ok(percent)
+    }
+}
+
+/// Warn that the context grew past `threshold`, on the terminal and, if
+/// asked for, with a desktop notification
+async fn warn_context(threshold: u8, percent: u8, options: &LiveOptions) {
+    let message = format!("Context passed {}% (now {}%)", threshold, percent);
+    if options.notify {
+        if let Err(e) = crate::statusline::notify(&message).await {
+            warn!("Desktop notification failed: {:#}", e);
+        }
+    }
+    println!("{} {}", style::warn().yellow(), message);
+}
+
+/// Wait until a session transcript is created or written to, then until
+/// the writes settle
+async fn wait_for_change(rx: &mut UnboundedReceiver<notify::Result<notify::Event>>) -> Result<()> {
+    loop {
+        let event = rx
+            .recv()
+            .await
+            .context("Stopped watching sessions")?
+            .context("Watching sessions failed")?;
+        let touches_session = event
+            .paths
+            .iter()
+            .any(|path| path.extension().is_some_and(|ext| ext == "jsonl"));
+        if touches_session && (event.kind.is_create() || event.kind.is_modify()) {
+            break;
+        }
+    }
+
+    tokio::time::sleep(DEBOUNCE).await;
+    while rx.try_recv().is_ok() {}
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossed() {
+        assert_eq!(crossed(None, 75, &[70, 90]), vec![70]);
+        assert_eq!(crossed(Some(75), 80, &[70, 90]), Vec::<u8>::new());
+        assert_eq!(crossed(Some(60), 95, &[70, 90]), vec![70, 90]);
+        // A new, smaller session does not warn
+        assert_eq!(crossed(Some(95), 10, &[70, 90]), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_context_percent() {
+        assert_eq!(context_percent(140_000, 200_000), 70);
+        assert_eq!(context_percent(300_000, 200_000), 100);
+    }
+}
@@ -1,42 +1,127 @@
+mod apply;
+mod archive;
+mod claude_md;
+mod hooks;
+mod live;
 mod optimizer;
+mod redundancy;
 mod session_parser;
 
+use crate::cache::{namespace, Cache};
 use crate::config::Config;
-use crate::db::Database;
+use crate::db::{Database, HiddenOptimizations, OptimizationStatus};
+use crate::style::{self, Colorize};
 use anyhow::Result;
-use colored::Colorize;
 use optimizer::Optimizer;
 use session_parser::SessionParser;
-use tracing::{info, debug};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
 
 // Re-export for external use
-pub use optimizer::Optimization;
+pub use apply::{ChangePreview, DiffLine};
+pub use claude_md::RepoProfile;
+pub use hooks::HookSuggestion;
+pub use live::LiveOptions;
+pub use optimizer::{Optimization, OptimizationType};
+pub use redundancy::Redundancy;
+pub use session_parser::{FileUsage, SessionData, TokenUsage};
+
+/// A recent session file and its parsed contents
+pub struct SessionSummary {
+    pub path: PathBuf,
+    pub project: String,
+    pub size_bytes: u64,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+    pub data: SessionData,
+}
 
 pub struct SessionAnalyzer {
     config: Config,
     parser: SessionParser,
     optimizer: Optimizer,
-    db: Database,
+    db: Arc<Database>,
+    cache: Cache,
 }
 
+/// Parsed sessions are cached for an hour; the key includes the file's
+/// size and mtime, so a session that is still growing is re-parsed
+const SESSION_CACHE_TTL: u64 = 60 * 60;
+
 impl SessionAnalyzer {
-    pub async fn new(config: Config) -> Result<Self> {
+    pub async fn new(config: Config, db: Arc<Database>) -> Result<Self> {
         let parser = SessionParser::new();
         let optimizer = Optimizer::new(config.analyzer.min_savings_threshold);
-        let db = Database::new(&config).await?;
 
         Ok(Self {
             config,
             parser,
             optimizer,
             db,
+            cache: Cache::new()?,
+        })
+    }
+
+    /// Parse a session file, reusing the cached result if the file is unchanged
+    fn load_session(&self, path: &Path) -> Result<SessionData> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let stem = session_parser::session_id(path);
+        let project = session_project(path);
+        let key = format!(
+            "{}/{}/session-{}-{}-{}",
+            namespace::ANALYSIS,
+            project,
+            stem,
+            metadata.len(),
+            modified
+        );
+
+        self.cache.get_or_insert_with(&key, SESSION_CACHE_TTL, || {
+            debug!("Parsing session {}", stem);
+            self.parser.parse_session(path)
         })
     }
 
-    /// Analyze recent sessions
-    pub async fn analyze_sessions(&self, count: usize) -> Result<()> {
+    /// Parse the most recent sessions for browsing, newest first.
+    /// Sessions that fail to parse are skipped.
+    pub fn list_sessions(&self, count: usize) -> Result<Vec<SessionSummary>> {
+        let mut sessions = Vec::new();
+
+        for path in self.parser.find_recent_sessions(count)? {
+            let data = match self.load_session(&path) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Skipping session {:?}: {:#}", path, e);
+                    continue;
+                }
+            };
+            let metadata = std::fs::metadata(&path).ok();
+
+            sessions.push(SessionSummary {
+                project: session_project(&path).to_string(),
+                size_bytes: metadata.as_ref().map_or(0, |m| m.len()),
+                modified: metadata
+                    .and_then(|m| m.modified().ok())
+                    .map(chrono::DateTime::from),
+                path,
+                data,
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    /// Analyze recent sessions, leaving out dismissed and snoozed
+    /// optimizations unless `show_dismissed`
+    pub async fn analyze_sessions(&self, count: usize, show_dismissed: bool) -> Result<()> {
         println!("\n{}", "Analyzing Sessions".bright_cyan().bold());
-        println!("{}", "═".repeat(60).bright_cyan());
+        println!("{}", style::rule(60).bright_cyan());
 
         let sessions = self.parser.find_recent_sessions(count)?;
 
@@ -47,6 +132,7 @@ impl SessionAnalyzer {
         }
 
         println!("\nFound {} recent sessions", sessions.len());
+        let hidden = self.hidden_optimizations(show_dismissed).await?;
 
         let mut total_optimizations = 0;
         let mut total_potential_savings = 0;
@@ -54,12 +140,13 @@ impl SessionAnalyzer {
         for session_path in sessions {
             println!(
                 "\n{} Analyzing: {:?}",
-                "→".bright_cyan(),
+                style::arrow().bright_cyan(),
                 session_path.file_name().unwrap()
             );
 
-            let session_data = self.parser.parse_session(&session_path)?;
-            let optimizations = self.optimizer.analyze(&session_data)?;
+            let session_data = self.load_session(&session_path)?;
+            let mut optimizations = self.optimizer.analyze(&session_data)?;
+            optimizations.retain(|opt| !hidden.hides(opt));
 
             if !optimizations.is_empty() {
                 total_optimizations += optimizations.len();
@@ -87,49 +174,77 @@ impl SessionAnalyzer {
         Ok(())
     }
 
-    /// Get optimization suggestions
-    pub async fn optimize_recent(&self, count: usize) -> Result<()> {
-        println!("\n{}", "Optimization Suggestions".bright_cyan().bold());
-        println!("{}", "═".repeat(60).bright_cyan());
+    /// Optimization opportunities in one parsed session
+    pub fn session_optimizations(&self, session: &SessionData) -> Result<Vec<Optimization>> {
+        self.optimizer.analyze(session)
+    }
 
+    /// Find optimizations across recent sessions, highest savings first
+    pub fn find_optimizations(&self, count: usize) -> Result<Vec<Optimization>> {
         let sessions = self.parser.find_recent_sessions(count)?;
         let mut all_optimizations = Vec::new();
 
         for session_path in sessions {
-            let session_data = self.parser.parse_session(&session_path)?;
+            let session_data = self.load_session(&session_path)?;
             let optimizations = self.optimizer.analyze(&session_data)?;
             all_optimizations.extend(optimizations);
         }
 
+        // Sort by savings (highest first)
+        all_optimizations.sort_by_key(|opt| std::cmp::Reverse(opt.estimated_savings));
+
+        Ok(all_optimizations)
+    }
+
+    /// Dismissed and snoozed optimizations to leave out; none with
+    /// `show_dismissed`
+    pub async fn hidden_optimizations(&self, show_dismissed: bool) -> Result<HiddenOptimizations> {
+        if show_dismissed {
+            return Ok(HiddenOptimizations::default());
+        }
+        self.db.hidden_optimizations().await
+    }
+
+    /// Get optimization suggestions
+    pub async fn optimize_recent(&self, count: usize, show_dismissed: bool) -> Result<()> {
+        println!("\n{}", "Optimization Suggestions".bright_cyan().bold());
+        println!("{}", style::rule(60).bright_cyan());
+
+        let hidden = self.hidden_optimizations(show_dismissed).await?;
+        let mut all_optimizations = self.find_optimizations(count)?;
+        all_optimizations.retain(|opt| !hidden.hides(opt));
+
         if all_optimizations.is_empty() {
-            println!("\n{}", "No optimization opportunities found! 🎉".green());
+            println!(
+                "\n{}",
+                format!("No optimization opportunities found! {}", style::icon("🎉")).green()
+            );
             println!("Your usage patterns are already efficient.");
             return Ok(());
         }
 
-        // Sort by savings (highest first)
-        all_optimizations.sort_by(|a, b| b.estimated_savings.cmp(&a.estimated_savings));
-
         self.display_optimizations(&all_optimizations)?;
 
         Ok(())
     }
 
     /// Optimize a specific session
-    pub async fn optimize_session(&self, session_id: &str) -> Result<()> {
+    pub async fn optimize_session(&self, session_id: &str, show_dismissed: bool) -> Result<()> {
         println!(
             "\n{}",
             format!("Optimizing Session: {}", session_id)
                 .bright_cyan()
                 .bold()
         );
-        println!("{}", "═".repeat(60).bright_cyan());
+        println!("{}", style::rule(60).bright_cyan());
 
         // Find session by ID
         let session_path = self.parser.find_session_by_id(session_id)?;
 
-        let session_data = self.parser.parse_session(&session_path)?;
-        let optimizations = self.optimizer.analyze(&session_data)?;
+        let session_data = self.load_session(&session_path)?;
+        let hidden = self.hidden_optimizations(show_dismissed).await?;
+        let mut optimizations = self.optimizer.analyze(&session_data)?;
+        optimizations.retain(|opt| !hidden.hides(opt));
 
         if optimizations.is_empty() {
             println!(
@@ -144,6 +259,90 @@ impl SessionAnalyzer {
         Ok(())
     }
 
+    /// Show optimizations previously stored in the database
+    pub async fn show_history(
+        &self,
+        limit: usize,
+        include_applied: bool,
+        show_dismissed: bool,
+    ) -> Result<()> {
+        println!("\n{}", "Stored Optimizations".bright_cyan().bold());
+        println!("{}", style::rule(60).bright_cyan());
+
+        let stored = self
+            .db
+            .get_optimizations(limit, include_applied, show_dismissed)
+            .await?;
+
+        if stored.is_empty() {
+            println!("\n{}", "No stored optimizations.".green());
+            if !include_applied {
+                println!("Use --all to include optimizations already marked as applied.");
+            }
+            if !show_dismissed {
+                println!("Use --show-dismissed to include dismissed and snoozed ones.");
+            }
+            return Ok(());
+        }
+
+        for entry in &stored {
+            let opt = &entry.optimization;
+            let status = match entry.status {
+                OptimizationStatus::Applied => format!("{} applied", style::ok()).green(),
+                status if status.is_pending() => "pending".yellow(),
+                status => status.to_string().dimmed(),
+            };
+
+            println!(
+                "\n{} {} [{}]",
+                format!("#{}", entry.id).bright_yellow().bold(),
+                opt.title.white().bold(),
+                status
+            );
+            println!("  Type: {}", opt.opt_type);
+            println!("  Found: {}", entry.created_at.format("%Y-%m-%d %H:%M"));
+            println!("  Description: {}", opt.description);
+            println!(
+                "  {} ~{} tokens per occurrence",
+                "Savings:".green(),
+                opt.estimated_savings
+            );
+
+            if !opt.examples.is_empty() {
+                println!("  Examples:");
+                for example in &opt.examples {
+                    println!("    {} {}", style::bullet(), example);
+                }
+            }
+
+            if let Some(suggestion) = &opt.suggestion {
+                println!("  {} {}", "Suggestion:".bright_green().bold(), suggestion);
+            }
+        }
+
+        println!("\n{}", style::rule(60).bright_cyan());
+        println!("Mark one as done with: claude-helper optimize --mark-applied <ID>");
+        println!("Hide one with --dismiss <ID>, or for a while with --snooze <ID>");
+
+        Ok(())
+    }
+
+    /// Mark a stored optimization as applied, dismissed or snoozed
+    pub async fn set_status(&self, id: i64, status: OptimizationStatus) -> Result<()> {
+        if !self.db.set_optimization_status(id, status).await? {
+            anyhow::bail!("No stored optimization with ID {}", id);
+        }
+
+        let status = match status {
+            OptimizationStatus::Snoozed(until) => {
+                format!("snoozed until {}", until.format("%Y-%m-%d %H:%M"))
+            }
+            status => format!("marked as {}", status),
+        };
+        println!("{} Optimization #{} {}", style::ok().green(), id, status);
+        Ok(())
+    }
+
     fn display_optimizations(&self, optimizations: &[Optimization]) -> Result<()> {
         for (i, opt) in optimizations.iter().enumerate() {
             println!(
@@ -151,7 +350,7 @@ impl SessionAnalyzer {
                 format!("{}.", i + 1).bright_yellow().bold(),
                 opt.title.white().bold()
             );
-            println!("  Type: {}", self.format_opt_type(&opt.opt_type));
+            println!("  Type: {}", opt.opt_type);
             println!("  Description: {}", opt.description);
             println!(
                 "  {} ~{} tokens per occurrence",
@@ -162,7 +361,7 @@ impl SessionAnalyzer {
             if !opt.examples.is_empty() {
                 println!("  Examples:");
                 for example in &opt.examples {
-                    println!("    • {}", example);
+                    println!("    {} {}", style::bullet(), example);
                 }
             }
 
@@ -171,7 +370,7 @@ impl SessionAnalyzer {
             }
         }
 
-        println!("\n{}", "═".repeat(60).bright_cyan());
+        println!("\n{}", style::rule(60).bright_cyan());
         let total_savings: usize = optimizations.iter().map(|o| o.estimated_savings).sum();
         println!(
             "{} {} tokens",
@@ -182,33 +381,18 @@ impl SessionAnalyzer {
         Ok(())
     }
 
-    fn format_opt_type(&self, opt_type: &optimizer::OptimizationType) -> String {
-        use optimizer::OptimizationType;
-
-        let (label, emoji) = match opt_type {
-            OptimizationType::QuickCommand => ("Quick Command", "⚡"),
-            OptimizationType::ParameterizedScript => ("Parameterized Script", "📝"),
-            OptimizationType::FileMerge => ("File Merge", "🔗"),
-            OptimizationType::FileSplit => ("File Split", "✂️"),
-            OptimizationType::ContextPruning => ("Context Pruning", "🎯"),
-            OptimizationType::ToolCallBatching => ("Tool Call Batching", "📦"),
-        };
-
-        format!("{} {}", emoji, label)
-    }
-
     /// Start a new session (called from sessionStart hook)
     pub async fn start_session(&self) -> Result<()> {
         // Find the most recent session
         let sessions = self.parser.find_recent_sessions(1)?;
 
         if let Some(session_path) = sessions.first() {
-            let session_id = session_path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown");
-            info!("📝 Session started: {}", session_id);
-            debug!("Session will be tracked in ~/.claude/sessions/{}.jsonl", session_id);
+            let session_id = session_parser::session_id(session_path);
+            info!("{} Session started: {}", style::icon("📝"), session_id);
+            debug!(
+                "Session will be tracked in ~/.claude/sessions/{}.jsonl",
+                session_id
+            );
         } else {
             debug!("No active session found yet");
         }
@@ -222,24 +406,27 @@ impl SessionAnalyzer {
         let sessions = self.parser.find_recent_sessions(1)?;
 
         if let Some(session_path) = sessions.first() {
-            let session_id = session_path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown");
+            let session_id = session_parser::session_id(session_path);
             debug!("Analyzing interaction in session: {}", session_id);
 
             // Parse the session to check for optimizations
-            if let Ok(session_data) = self.parser.parse_session(session_path) {
+            if let Ok(session_data) = self.load_session(session_path) {
                 if let Ok(optimizations) = self.optimizer.analyze(&session_data) {
-                    // Save any significant optimizations to database
+                    let hidden = self.db.hidden_optimizations().await.unwrap_or_default();
+                    // Save any significant optimizations to database, but
+                    // not again once dismissed or while snoozed
                     for opt in &optimizations {
-                        if opt.estimated_savings >= 500 {
+                        if opt.estimated_savings >= self.config.analyzer.min_savings_threshold
+                            && !hidden.hides(opt)
+                        {
                             if let Err(e) = self.db.save_optimization(opt).await {
                                 debug!("Failed to save optimization: {}", e);
                             } else {
                                 debug!(
-                                    "💡 Optimization detected: {} (saves ~{} tokens)",
-                                    opt.title, opt.estimated_savings
+                                    "{} Optimization detected: {} (saves ~{} tokens)",
+                                    style::icon("💡"),
+                                    opt.title,
+                                    opt.estimated_savings
                                 );
                             }
                         }
@@ -251,3 +438,13 @@ impl SessionAnalyzer {
         Ok(())
     }
 }
+
+/// Sessions are grouped by the directory they live in (one per project)
+fn session_project(path: &Path) -> &str {
+    path.parent()
+        .and_then(|p| p.file_name())
+        .and_then(|p| p.to_str())
+        .map(|p| p.trim_start_matches('.'))
+        .filter(|p| !p.is_empty())
+        .unwrap_or("default")
+}
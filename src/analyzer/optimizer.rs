@@ -1,8 +1,10 @@
 use super::session_parser::SessionData;
+use crate::style;
 use anyhow::Result;
+use serde::Serialize;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum OptimizationType {
     QuickCommand,
     ParameterizedScript,
@@ -10,9 +12,44 @@ pub enum OptimizationType {
     FileSplit,
     ContextPruning,
     ToolCallBatching,
+    CodeReuse,
 }
 
-#[derive(Debug, Clone)]
+impl OptimizationType {
+    /// Parse optimization type from Debug string representation
+    /// Returns None for invalid/unknown type strings
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "QuickCommand" => Some(Self::QuickCommand),
+            "ParameterizedScript" => Some(Self::ParameterizedScript),
+            "FileMerge" => Some(Self::FileMerge),
+            "FileSplit" => Some(Self::FileSplit),
+            "ContextPruning" => Some(Self::ContextPruning),
+            "ToolCallBatching" => Some(Self::ToolCallBatching),
+            "CodeReuse" => Some(Self::CodeReuse),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for OptimizationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (label, emoji) = match self {
+            OptimizationType::QuickCommand => ("Quick Command", "⚡"),
+            OptimizationType::ParameterizedScript => ("Parameterized Script", "📝"),
+            OptimizationType::FileMerge => ("File Merge", "🔗"),
+            OptimizationType::FileSplit => ("File Split", "✂️"),
+            OptimizationType::ContextPruning => ("Context Pruning", "🎯"),
+            OptimizationType::ToolCallBatching => ("Tool Call Batching", "📦"),
+            OptimizationType::CodeReuse => ("Code Reuse", "♻️"),
+        };
+
+        write!(f, "{} {}", style::icon(emoji), label)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Optimization {
     pub opt_type: OptimizationType,
     pub title: String,
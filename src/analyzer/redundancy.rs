@@ -0,0 +1,564 @@
+use super::apply::ChangePreview;
+use super::optimizer::{Optimization, OptimizationType};
+use super::session_parser::SessionData;
+use super::SessionAnalyzer;
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::process::Command;
+
+/// Consecutive significant lines that have to match for written code to
+/// count as a copy
+const WINDOW: usize = 4;
+
+/// Lines shorter than this, once trimmed, say too little to match on
+const MIN_LINE_LEN: usize = 12;
+
+/// Duplicated lines of an existing file a write needs to be flagged
+const MIN_LINES: usize = WINDOW + 1;
+
+/// Rewrites of the same code after which a CLAUDE.md pointer pays off
+const REPEATS: usize = 2;
+
+/// Tracked files larger than this are not indexed
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Rough tokens it costs to write a line of code
+const TOKENS_PER_LINE: usize = 10;
+
+/// Heading of the CLAUDE.md section the pointers go under
+const SECTION: &str = "## Canonical implementations";
+
+/// Code that sessions wrote again although the repository already had it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redundancy {
+    /// File with the existing implementation, relative to the root
+    pub canonical: String,
+    /// Lines of it the rewrites duplicate, 1-based and inclusive
+    pub lines: (usize, usize),
+    /// First significant line of those, e.g. a function signature
+    pub summary: String,
+    /// Files the code was written into again, with the session that did it
+    pub rewrites: Vec<(String, String)>,
+    /// Duplicated lines, summed over the rewrites
+    pub duplicated_lines: usize,
+}
+
+impl Redundancy {
+    /// The CLAUDE.md line pointing at the canonical implementation
+    pub fn pointer(&self) -> String {
+        format!(
+            "- `{}:{}` (`{}`) already exists; reuse it instead of writing it again",
+            self.canonical, self.lines.0, self.summary
+        )
+    }
+
+    pub fn to_optimization(&self) -> Optimization {
+        Optimization {
+            opt_type: OptimizationType::CodeReuse,
+            title: format!("Point Claude at {}", self.canonical),
+            description: format!(
+                "Lines {}-{} of {} were written again {} times instead of being reused",
+                self.lines.0,
+                self.lines.1,
+                self.canonical,
+                self.rewrites.len()
+            ),
+            estimated_savings: self.duplicated_lines * TOKENS_PER_LINE,
+            examples: self
+                .rewrites
+                .iter()
+                .map(|(file, session)| {
+                    let session: String = session.chars().take(8).collect();
+                    format!("{} (session {})", file, session)
+                })
+                .collect(),
+            suggestion: Some(self.pointer()),
+        }
+    }
+}
+
+/// Code a session wrote into a project file
+#[derive(Debug, Clone)]
+struct WrittenCode {
+    /// Relative to the root
+    file: String,
+    code: String,
+    session: String,
+    at: Option<DateTime<Utc>>,
+}
+
+/// The code `sessions` and their sidechains wrote with Write, Edit and
+/// MultiEdit into files under `root`
+fn writes(root: &Path, sessions: &[SessionData]) -> Vec<WrittenCode> {
+    let prefix = format!("{}/", root.to_string_lossy().trim_end_matches('/'));
+    let mut writes = Vec::new();
+
+    for session in sessions {
+        for part in std::iter::once(session).chain(&session.sidechains) {
+            for call in &part.tool_calls {
+                let Some(file) = call
+                    .parameters
+                    .get("file_path")
+                    .and_then(Value::as_str)
+                    .and_then(|path| path.strip_prefix(&prefix))
+                else {
+                    continue;
+                };
+                let code: Vec<&str> = match call.tool_name.as_str() {
+                    "Write" => call
+                        .parameters
+                        .get("content")
+                        .and_then(Value::as_str)
+                        .into_iter()
+                        .collect(),
+                    "Edit" => call
+                        .parameters
+                        .get("new_string")
+                        .and_then(Value::as_str)
+                        .into_iter()
+                        .collect(),
+                    "MultiEdit" => call
+                        .parameters
+                        .get("edits")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|edit| edit.get("new_string")?.as_str())
+                        .collect(),
+                    _ => continue,
+                };
+                let at = call
+                    .timestamp
+                    .as_deref()
+                    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    .map(|at| at.with_timezone(&Utc));
+                writes.extend(code.into_iter().map(|code| WrittenCode {
+                    file: file.to_string(),
+                    code: code.to_string(),
+                    session: session.session_id.clone(),
+                    at,
+                }));
+            }
+        }
+    }
+
+    writes
+}
+
+/// Lines worth matching on, with their 1-based numbers, whitespace
+/// collapsed. Short lines, comments and imports are left out.
+fn significant_lines(text: &str) -> Vec<(usize, String)> {
+    const SKIPPED: &[&str] = &["//", "#", "/*", "*", "--", "use ", "import ", "from "];
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            (line.len() >= MIN_LINE_LEN && !SKIPPED.iter().any(|p| line.starts_with(p)))
+                .then_some((i + 1, line))
+        })
+        .collect()
+}
+
+fn window_hash(lines: &[(usize, String)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (_, line) in lines {
+        line.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Every window of significant lines in the repository's files
+#[derive(Default)]
+struct CodeIndex {
+    files: Vec<String>,
+    /// Significant lines of each file
+    lines: Vec<Vec<(usize, String)>>,
+    /// Window hash to the file and index of its first line in `lines`
+    windows: HashMap<u64, Vec<(usize, usize)>>,
+}
+
+impl CodeIndex {
+    /// Index `files` (relative to `root`), skipping unreadable and large ones
+    fn build(root: &Path, files: &[String]) -> Self {
+        let mut index = Self::default();
+        for file in files {
+            let path = root.join(file);
+            if fs::metadata(&path).map_or(true, |meta| meta.len() > MAX_FILE_BYTES) {
+                continue;
+            }
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            index.add(file, &text);
+        }
+        index
+    }
+
+    fn add(&mut self, file: &str, text: &str) {
+        let id = self.files.len();
+        let lines = significant_lines(text);
+        for (i, window) in lines.windows(WINDOW).enumerate() {
+            self.windows
+                .entry(window_hash(window))
+                .or_default()
+                .push((id, i));
+        }
+        self.files.push(file.to_string());
+        self.lines.push(lines);
+    }
+
+    /// The other file `code` copies the most significant lines of, with
+    /// those lines' indexes in `lines`
+    fn source_of(&self, code: &str, written_to: &str) -> Option<(usize, BTreeSet<usize>)> {
+        let mut copied: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for window in significant_lines(code).windows(WINDOW) {
+            let Some(found) = self.windows.get(&window_hash(window)) else {
+                continue;
+            };
+            for &(file, first) in found {
+                if self.files[file] != written_to {
+                    copied
+                        .entry(file)
+                        .or_default()
+                        .extend(first..first + WINDOW);
+                }
+            }
+        }
+
+        copied
+            .into_iter()
+            .filter(|(_, lines)| lines.len() >= MIN_LINES)
+            .max_by_key(|(file, lines)| (lines.len(), std::cmp::Reverse(*file)))
+    }
+}
+
+/// Code written repeatedly although `index` already had it. `existed` says
+/// whether lines of a file were committed before a write; the rest are
+/// likely copies of the write instead.
+fn find(
+    index: &CodeIndex,
+    writes: &[WrittenCode],
+    existed: impl Fn(&str, (usize, usize), Option<DateTime<Utc>>) -> bool,
+) -> Vec<Redundancy> {
+    let mut found: HashMap<usize, (Redundancy, BTreeSet<usize>)> = HashMap::new();
+    for write in writes {
+        let Some((file, copied)) = index.source_of(&write.code, &write.file) else {
+            continue;
+        };
+        let (Some(&first), Some(&last)) = (copied.first(), copied.last()) else {
+            continue;
+        };
+        let span = (index.lines[file][first].0, index.lines[file][last].0);
+        if !existed(&index.files[file], span, write.at) {
+            continue;
+        }
+
+        let (entry, all_copied) = found.entry(file).or_insert_with(|| {
+            let redundancy = Redundancy {
+                canonical: index.files[file].clone(),
+                lines: span,
+                summary: String::new(),
+                rewrites: Vec::new(),
+                duplicated_lines: 0,
+            };
+            (redundancy, BTreeSet::new())
+        });
+        let rewrite = (write.file.clone(), write.session.clone());
+        if !entry.rewrites.contains(&rewrite) {
+            entry.rewrites.push(rewrite);
+            entry.duplicated_lines += copied.len();
+            all_copied.extend(copied);
+        }
+    }
+
+    let mut found: Vec<Redundancy> = found
+        .into_iter()
+        .filter(|(_, (r, _))| r.rewrites.len() >= REPEATS)
+        .map(|(file, (mut r, copied))| {
+            let lines = &index.lines[file];
+            let (first, last) = (copied.first().copied(), copied.last().copied());
+            if let (Some(first), Some(last)) = (first, last) {
+                r.lines = (lines[first].0, lines[last].0);
+                r.summary = lines[first].1.chars().take(80).collect();
+            }
+            r
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        b.duplicated_lines
+            .cmp(&a.duplicated_lines)
+            .then_with(|| a.canonical.cmp(&b.canonical))
+    });
+    found
+}
+
+/// Files git tracks under `root`
+fn tracked_files(root: &Path) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(root)
+        .output()
+        .context("Failed to run `git ls-files`")?;
+    if !output.status.success() {
+        anyhow::bail!("{} is not a git repository", root.display());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Whether `git blame` has all `lines` of `file` committed before `at`
+/// (or at all, when the write's time is unknown)
+fn committed_before(
+    root: &Path,
+    file: &str,
+    lines: (usize, usize),
+    at: Option<DateTime<Utc>>,
+) -> bool {
+    let Ok(output) = Command::new("git")
+        .args(["blame", "--line-porcelain", "-L"])
+        .arg(format!("{},{}", lines.0, lines.1))
+        .args(["--", file])
+        .current_dir(root)
+        .output()
+    else {
+        return false;
+    };
+    if !output.status.success() {
+        return false;
+    }
+
+    let blame = String::from_utf8_lossy(&output.stdout);
+    let mut newest = 0;
+    for line in blame.lines() {
+        if line.starts_with("0000000000000000000000000000000000000000") {
+            return false;
+        }
+        if let Some(time) = line.strip_prefix("committer-time ") {
+            newest = newest.max(time.parse::<i64>().unwrap_or(i64::MAX));
+        }
+    }
+    at.is_none_or(|at| newest < at.timestamp())
+}
+
+/// The CLAUDE.md section with `pointers`, added to or extending `current`
+fn with_pointers(current: Option<&str>, pointers: &[String]) -> String {
+    let mut text = current.unwrap_or_default().to_string();
+    let pointers: Vec<&String> = pointers
+        .iter()
+        .filter(|p| !text.contains(p.as_str()))
+        .collect();
+    if pointers.is_empty() {
+        return text;
+    }
+    let lines = pointers
+        .iter()
+        .map(|p| format!("{}\n", p))
+        .collect::<String>();
+
+    match text.find(SECTION) {
+        Some(start) => {
+            let body = start + SECTION.len();
+            let end = text[body..]
+                .find("\n## ")
+                .map_or(text.len(), |i| body + i + 1);
+            let at = text[..end].trim_end().len();
+            text.insert_str(at, &format!("\n{}", lines.trim_end()));
+        }
+        None => {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&format!(
+                "{}\n\nCode that already exists; use it instead of writing new versions:\n\n{}",
+                SECTION, lines
+            ));
+        }
+    }
+    text
+}
+
+impl SessionAnalyzer {
+    /// Flag code the last `count` sessions wrote into the repository at
+    /// `root` more than once although it already existed there, and offer
+    /// to point Claude at the existing versions in CLAUDE.md
+    pub async fn find_redundancy(&self, root: &Path, count: usize) -> Result<()> {
+        use dialoguer::Confirm;
+
+        println!("\n{}", "Redundant Code".bright_cyan().bold());
+        println!("{}", style::rule(60).bright_cyan());
+
+        let sessions: Vec<SessionData> = self
+            .list_sessions(count)?
+            .into_iter()
+            .map(|session| session.data)
+            .collect();
+        let writes = writes(root, &sessions);
+        let index = CodeIndex::build(root, &tracked_files(root)?);
+        let found = find(&index, &writes, |file, lines, at| {
+            committed_before(root, file, lines, at)
+        });
+        if found.is_empty() {
+            println!(
+                "\n{}",
+                format!(
+                    "No code was written again that already existed {}",
+                    style::ok()
+                )
+                .green()
+            );
+            return Ok(());
+        }
+
+        let optimizations: Vec<Optimization> =
+            found.iter().map(Redundancy::to_optimization).collect();
+        self.display_optimizations(&optimizations)?;
+
+        let pointers: Vec<String> = found.iter().map(Redundancy::pointer).collect();
+        let path = root.join("CLAUDE.md");
+        let current = fs::read_to_string(&path).ok();
+        let proposed = with_pointers(current.as_deref(), &pointers);
+        if current.as_deref() == Some(proposed.as_str()) {
+            println!("\nCLAUDE.md already points at these files.");
+            return Ok(());
+        }
+
+        let add = Confirm::new()
+            .with_prompt(format!(
+                "Add {} pointers to {}?",
+                pointers.len(),
+                path.display()
+            ))
+            .default(false)
+            .interact()?;
+        if add {
+            ChangePreview::replace(path.clone(), proposed)?.apply()?;
+            println!("{} Updated {}", style::ok().green(), path.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::session_parser::ToolCall;
+    use serde_json::json;
+
+    const RETRY: &str = "\
+use std::time::Duration;
+
+pub fn retry_with_backoff(attempts: usize) -> Vec<Duration> {
+    let mut delays = Vec::with_capacity(attempts);
+    for attempt in 0..attempts {
+        let millis = 100u64 * 2u64.pow(attempt as u32);
+        delays.push(Duration::from_millis(millis));
+    }
+    delays
+}
+";
+
+    fn session(id: &str, calls: Vec<(&str, Value)>) -> SessionData {
+        let mut session = SessionData::new(id);
+        session.tool_calls = calls
+            .into_iter()
+            .map(|(tool, parameters)| ToolCall {
+                tool_name: tool.to_string(),
+                parameters,
+                timestamp: Some("2025-06-01T12:00:00Z".to_string()),
+            })
+            .collect();
+        session
+    }
+
+    #[test]
+    fn test_flags_repeated_rewrites() {
+        let root = Path::new("/repo");
+        let mut index = CodeIndex::default();
+        index.add("src/util.rs", &format!("// Helpers\n\n{}", RETRY));
+        index.add("src/main.rs", "fn main() {}\n");
+
+        let sessions = [
+            session(
+                "aaaaaaaaaa",
+                vec![(
+                    "Write",
+                    json!({ "file_path": "/repo/src/net.rs", "content": RETRY }),
+                )],
+            ),
+            session(
+                "bbbbbbbbbb",
+                vec![
+                    (
+                        "Edit",
+                        json!({ "file_path": "/repo/src/db.rs", "new_string": RETRY }),
+                    ),
+                    (
+                        "Write",
+                        json!({ "file_path": "/elsewhere/x.rs", "content": RETRY }),
+                    ),
+                    (
+                        "Write",
+                        json!({ "file_path": "/repo/src/util.rs", "content": RETRY }),
+                    ),
+                ],
+            ),
+        ];
+        let writes = writes(root, &sessions);
+        assert_eq!(writes.len(), 3);
+        assert!(writes[0].at.is_some());
+
+        let found = find(&index, &writes, |_, _, _| true);
+        assert_eq!(found.len(), 1);
+        let found = &found[0];
+        assert_eq!(found.canonical, "src/util.rs");
+        assert_eq!(found.lines, (5, 9));
+        assert!(found.summary.starts_with("pub fn retry_with_backoff"));
+        assert_eq!(
+            found.rewrites,
+            vec![
+                ("src/net.rs".to_string(), "aaaaaaaaaa".to_string()),
+                ("src/db.rs".to_string(), "bbbbbbbbbb".to_string())
+            ]
+        );
+        assert!(found.pointer().starts_with("- `src/util.rs:5` (`pub fn"));
+        assert_eq!(
+            found.to_optimization().examples[0],
+            "src/net.rs (session aaaaaaaa)"
+        );
+
+        // Code committed after it was written is the copy, not the original
+        assert!(find(&index, &writes, |_, _, _| false).is_empty());
+        // One rewrite is not a habit yet
+        assert!(find(&index, &writes[..1], |_, _, _| true).is_empty());
+    }
+
+    #[test]
+    fn test_with_pointers() {
+        let pointers = vec!["- `src/util.rs:5` (`pub fn retry`) already exists".to_string()];
+        let text = with_pointers(
+            Some("# Project\n\n## Commands\n\n- cargo test\n"),
+            &pointers,
+        );
+        assert!(text.starts_with("# Project\n\n## Commands\n\n- cargo test\n\n## Canonical"));
+        assert!(text.ends_with(&format!("{}\n", pointers[0])));
+        assert_eq!(with_pointers(Some(&text), &pointers), text);
+
+        let more = vec!["- `src/db.rs:1` (`fn open`) already exists".to_string()];
+        let extended = with_pointers(Some(&format!("{}\n## Later\n", text)), &more);
+        assert!(extended.contains(&format!("{}\n{}\n\n## Later\n", pointers[0], more[0])));
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use tracing::debug;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionData {
@@ -9,6 +13,49 @@ pub struct SessionData {
     pub messages: Vec<Message>,
     pub tool_calls: Vec<ToolCall>,
     pub file_accesses: Vec<FileAccess>,
+    /// Claude Code version that wrote the transcript, when it says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Summaries Claude Code wrote for the conversation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summaries: Vec<String>,
+    /// Subagent conversations, in the order they started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidechains: Vec<SessionData>,
+    /// Usage reported for each API response, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub usage: Vec<TokenUsage>,
+}
+
+/// Tokens of one API response, from the `usage` block of an assistant line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenUsage {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub cache_creation_input_tokens: usize,
+    pub cache_read_input_tokens: usize,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> usize {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    /// Size of the prompt: everything sent in, cached or not
+    pub fn context(&self) -> usize {
+        self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+
+    fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +79,454 @@ pub struct FileAccess {
     pub line_count: Option<usize>,
 }
 
+/// Rough token estimate for session text: about four characters per token
+const CHARS_PER_TOKEN: usize = 4;
+
+/// User prompts that make up a session's summary
+const SUMMARY_PROMPTS: usize = 3;
+
+/// Role of the messages holding tool output
+const TOOL_RESULT_ROLE: &str = "tool";
+
+/// How often one file was touched in a session
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileUsage {
+    pub file_path: String,
+    pub reads: usize,
+    pub writes: usize,
+    pub edits: usize,
+}
+
+impl FileUsage {
+    pub fn total(&self) -> usize {
+        self.reads + self.writes + self.edits
+    }
+}
+
+/// One line of a transcript, by its `type`. Older transcripts have no
+/// `type` and put `role`, `content` and `tool_use` at the top level.
+enum Record {
+    Turn(Box<Entry>),
+    Summary { summary: String },
+    Other,
+}
+
+impl Record {
+    fn parse(line: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(line).ok()?;
+        let record = match value.get("type").and_then(Value::as_str) {
+            Some("user" | "assistant") => Self::Turn(serde_json::from_value(value).ok()?),
+            Some("summary") => Self::Summary {
+                summary: value.get("summary")?.as_str()?.to_string(),
+            },
+            Some(_) => Self::Other,
+            None => Self::Turn(Box::new(Entry::legacy(serde_json::from_value(value).ok()?))),
+        };
+        Some(record)
+    }
+}
+
+/// A user or assistant line
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(default)]
+    uuid: Option<String>,
+    #[serde(default)]
+    parent_uuid: Option<String>,
+    #[serde(default)]
+    is_sidechain: bool,
+    /// Subagent that wrote a sidechain line, in newer transcripts
+    #[serde(default)]
+    agent_id: Option<String>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    message: Option<ApiMessage>,
+    #[serde(rename = "type", default)]
+    kind: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiMessage {
+    /// The same response is written as one line per content block, each
+    /// repeating its ID and usage
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    content: Content,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Content {
+    Text(String),
+    Blocks(Vec<Block>),
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Block {
+    Text {
+        #[serde(default)]
+        text: String,
+    },
+    ToolUse {
+        name: String,
+        #[serde(default)]
+        input: Value,
+    },
+    ToolResult {
+        #[serde(default)]
+        content: Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// A line in the older format without a `type`
+#[derive(Debug, Deserialize)]
+struct LegacyLine {
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    content: Option<Value>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    tool_use: Option<LegacyToolUse>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyToolUse {
+    name: String,
+    #[serde(default)]
+    input: Value,
+}
+
+impl Entry {
+    fn legacy(line: LegacyLine) -> Self {
+        let mut blocks = Vec::new();
+        if let Some(text) = line.content.as_ref().and_then(Value::as_str) {
+            blocks.push(Block::Text {
+                text: text.to_string(),
+            });
+        }
+        if let Some(tool) = line.tool_use {
+            blocks.push(Block::ToolUse {
+                name: tool.name,
+                input: tool.input,
+            });
+        }
+
+        Self {
+            timestamp: line.timestamp,
+            message: Some(ApiMessage {
+                role: line.role,
+                content: Content::Blocks(blocks),
+                usage: line.usage,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn role(&self) -> &str {
+        self.message
+            .as_ref()
+            .and_then(|message| message.role.as_deref())
+            .unwrap_or(&self.kind)
+    }
+
+    /// Add this line's text, tool calls and tool results to `session`
+    fn add_to(self, session: &mut SessionData) {
+        let role = self.role().to_string();
+        let Some(api) = self.message else {
+            return;
+        };
+        let message = |role: &str, content: String| Message {
+            role: role.to_string(),
+            content,
+            timestamp: self.timestamp.clone(),
+        };
+
+        session.usage.extend(api.usage);
+        let blocks = match api.content {
+            Content::Text(text) => vec![Block::Text { text }],
+            Content::Blocks(blocks) => blocks,
+        };
+        let mut text = Vec::new();
+        for block in blocks {
+            match block {
+                Block::Text { text: part } => text.push(part),
+                Block::ToolUse { name, input } => {
+                    session.add_tool_call(name, input, self.timestamp.clone())
+                }
+                Block::ToolResult { content } => {
+                    let content = match content {
+                        Value::String(content) => content,
+                        Value::Null => continue,
+                        other => other.to_string(),
+                    };
+                    session.messages.push(message(TOOL_RESULT_ROLE, content));
+                }
+                Block::Other => {}
+            }
+        }
+        if !role.is_empty() && !text.is_empty() {
+            session.messages.push(message(&role, text.join("\n")));
+        }
+    }
+}
+
+/// A subagent conversation being collected
+struct Sidechain {
+    agent_id: Option<String>,
+    uuids: HashSet<String>,
+    data: SessionData,
+}
+
+impl Sidechain {
+    fn new(session_id: String, agent_id: Option<String>) -> Self {
+        Self {
+            agent_id,
+            uuids: HashSet::new(),
+            data: SessionData::new(&session_id),
+        }
+    }
+
+    /// Whether `entry` continues this conversation: same subagent, or a
+    /// reply to one of its lines
+    fn contains(&self, entry: &Entry) -> bool {
+        match (&self.agent_id, &entry.agent_id) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => entry
+                .parent_uuid
+                .as_ref()
+                .is_some_and(|parent| self.uuids.contains(parent)),
+        }
+    }
+}
+
+impl SessionData {
+    pub(crate) fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            messages: Vec::new(),
+            tool_calls: Vec::new(),
+            file_accesses: Vec::new(),
+            version: None,
+            summaries: Vec::new(),
+            sidechains: Vec::new(),
+            usage: Vec::new(),
+        }
+    }
+
+    fn add_tool_call(&mut self, name: String, input: Value, timestamp: Option<String>) {
+        if matches!(name.as_str(), "Read" | "Write" | "Edit") {
+            if let Some(file_path) = input.get("file_path").and_then(Value::as_str) {
+                self.file_accesses.push(FileAccess {
+                    file_path: file_path.to_string(),
+                    operation: name.to_lowercase(),
+                    line_count: None,
+                });
+            }
+        }
+
+        self.tool_calls.push(ToolCall {
+            tool_name: name,
+            parameters: input,
+            timestamp,
+        });
+    }
+
+    /// Usage of all of the session's API responses, sidechains included;
+    /// `None` for transcripts that do not record it
+    pub fn token_usage(&self) -> Option<TokenUsage> {
+        let mut responses = self
+            .usage
+            .iter()
+            .chain(self.sidechains.iter().flat_map(|s| s.usage.iter()))
+            .peekable();
+        responses.peek()?;
+
+        let mut total = TokenUsage::default();
+        for usage in responses {
+            total.add(usage);
+        }
+        Some(total)
+    }
+
+    /// Tokens the session used: the recorded usage when there is any,
+    /// otherwise estimated from message length
+    pub fn tokens(&self) -> usize {
+        self.token_usage()
+            .map_or_else(|| self.estimated_tokens(), |usage| usage.total())
+    }
+
+    /// Size of the latest prompt, i.e. how full the context is, when the
+    /// transcript records usage
+    pub fn context_tokens(&self) -> Option<usize> {
+        self.usage.last().map(TokenUsage::context)
+    }
+
+    /// Estimated tokens in the session's messages
+    pub fn estimated_tokens(&self) -> usize {
+        let chars: usize = self
+            .messages
+            .iter()
+            .map(|m| m.content.chars().count())
+            .sum();
+        chars.div_ceil(CHARS_PER_TOKEN)
+    }
+
+    /// Estimated tokens per message role, plus `tool calls` for the tools'
+    /// inputs, largest first
+    pub fn token_composition(&self) -> Vec<(String, usize)> {
+        let mut chars: HashMap<&str, usize> = HashMap::new();
+        for message in &self.messages {
+            *chars.entry(message.role.as_str()).or_default() += message.content.chars().count();
+        }
+        let tool_chars: usize = self
+            .tool_calls
+            .iter()
+            .map(|call| call.tool_name.len() + call.parameters.to_string().chars().count())
+            .sum();
+        if tool_chars > 0 {
+            *chars.entry("tool calls").or_default() += tool_chars;
+        }
+
+        let mut composition: Vec<(String, usize)> = chars
+            .into_iter()
+            .map(|(part, chars)| (part.to_string(), chars.div_ceil(CHARS_PER_TOKEN)))
+            .collect();
+        composition.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        composition
+    }
+
+    /// Number of calls per tool, most used first
+    pub fn tool_usage(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for call in &self.tool_calls {
+            *counts.entry(call.tool_name.as_str()).or_default() += 1;
+        }
+
+        let mut usage: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        usage.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        usage
+    }
+
+    /// Reads, writes and edits per file, most accessed first
+    pub fn file_usage(&self) -> Vec<FileUsage> {
+        let mut files: HashMap<&str, FileUsage> = HashMap::new();
+        for access in &self.file_accesses {
+            let usage = files
+                .entry(access.file_path.as_str())
+                .or_insert_with(|| FileUsage {
+                    file_path: access.file_path.clone(),
+                    ..Default::default()
+                });
+            match access.operation.as_str() {
+                "read" => usage.reads += 1,
+                "write" => usage.writes += 1,
+                _ => usage.edits += 1,
+            }
+        }
+
+        let mut usage: Vec<FileUsage> = files.into_values().collect();
+        usage.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then_with(|| a.file_path.cmp(&b.file_path))
+        });
+        usage
+    }
+
+    /// What the session was about: Claude Code's own summaries and its
+    /// first few user prompts
+    pub fn summary(&self) -> String {
+        let prompts = self
+            .messages
+            .iter()
+            .filter(|m| m.role == "user" && !m.content.trim().is_empty())
+            .take(SUMMARY_PROMPTS)
+            .map(|m| m.content.trim());
+        self.summaries
+            .iter()
+            .map(|s| s.trim())
+            .chain(prompts)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Session ID of a transcript file and whether it is gzip-compressed.
+/// Accepts `ID.jsonl`, the archived `ID.jsonl.gz` and rotated
+/// `ID.jsonl.N` and `ID.jsonl.N.gz`; a rotated part's ID is `ID.N`.
+pub fn transcript_name(path: &Path) -> Option<(String, bool)> {
+    let name = path.file_name()?.to_str()?;
+    let (name, compressed) = match name.strip_suffix(".gz") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let (id, rotation) = name.rsplit_once(".jsonl")?;
+    if id.is_empty() {
+        return None;
+    }
+
+    let id = match rotation.strip_prefix('.') {
+        None if rotation.is_empty() => id.to_string(),
+        Some(n) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{}.{}", id, n)
+        }
+        _ => return None,
+    };
+    Some((id, compressed))
+}
+
+/// Session ID of a transcript file, falling back to its name
+pub fn session_id(path: &Path) -> String {
+    transcript_name(path)
+        .map(|(id, _)| id)
+        .or_else(|| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Contents of a transcript file, decompressed if it is gzipped
+pub fn read_transcript(path: &Path) -> Result<String> {
+    if !transcript_name(path).is_some_and(|(_, compressed)| compressed) {
+        return fs::read_to_string(path).context("Failed to read session file");
+    }
+
+    let file = fs::File::open(path).context("Failed to read session file")?;
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(file)
+        .read_to_string(&mut content)
+        .context("Failed to decompress session file")?;
+    Ok(content)
+}
+
 pub struct SessionParser {}
 
 impl SessionParser {
@@ -53,7 +548,7 @@ impl SessionParser {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "jsonl") {
+            if path.is_file() && transcript_name(&path).is_some() {
                 sessions.push(path);
             }
         }
@@ -65,99 +560,299 @@ impl SessionParser {
         Ok(sessions.into_iter().take(count).collect())
     }
 
-    /// Find session by ID
+    /// Find session by ID, live, rotated or archived
     pub fn find_session_by_id(&self, session_id: &str) -> Result<PathBuf> {
         let sessions_dir = self.get_sessions_dir()?;
         let session_file = sessions_dir.join(format!("{}.jsonl", session_id));
+        if session_file.exists() {
+            return Ok(session_file);
+        }
 
-        if !session_file.exists() {
-            anyhow::bail!("Session not found: {}", session_id);
+        let entries = fs::read_dir(&sessions_dir).into_iter().flatten().flatten();
+        for entry in entries {
+            let path = entry.path();
+            if transcript_name(&path).is_some_and(|(id, _)| id == session_id) {
+                return Ok(path);
+            }
         }
 
-        Ok(session_file)
+        anyhow::bail!("Session not found: {}", session_id)
     }
 
-    /// Parse a session file
+    /// Parse a session file, decompressing `.gz` ones. Lines that are not
+    /// valid JSON (e.g. one still being written) and record types this
+    /// parser does not know are skipped.
     pub fn parse_session(&self, path: &Path) -> Result<SessionData> {
-        let content = fs::read_to_string(path).context("Failed to read session file")?;
+        let content = read_transcript(path)?;
+        let session_id = session_id(path);
 
-        let mut messages = Vec::new();
-        let mut tool_calls = Vec::new();
-        let mut file_accesses = Vec::new();
+        Ok(Self::parse_transcript(&session_id, &content))
+    }
 
-        // Parse JSONL (one JSON object per line)
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+    fn parse_transcript(session_id: &str, content: &str) -> SessionData {
+        let mut main = SessionData::new(session_id);
+        let mut sidechains: Vec<Sidechain> = Vec::new();
+        let mut responses = HashSet::new();
+        let mut skipped = 0;
 
-            let json: serde_json::Value =
-                serde_json::from_str(line).context("Failed to parse JSON line")?;
-
-            // Extract messages
-            if let Some(role) = json.get("role").and_then(|r| r.as_str()) {
-                if let Some(content) = json.get("content").and_then(|c| c.as_str()) {
-                    messages.push(Message {
-                        role: role.to_string(),
-                        content: content.to_string(),
-                        timestamp: json
-                            .get("timestamp")
-                            .and_then(|t| t.as_str())
-                            .map(|s| s.to_string()),
-                    });
-                }
-            }
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let Some(record) = Record::parse(line) else {
+                skipped += 1;
+                continue;
+            };
 
-            // Extract tool calls
-            if let Some(tool_use) = json.get("tool_use") {
-                if let Some(name) = tool_use.get("name").and_then(|n| n.as_str()) {
-                    tool_calls.push(ToolCall {
-                        tool_name: name.to_string(),
-                        parameters: tool_use
-                            .get("input")
-                            .cloned()
-                            .unwrap_or(serde_json::Value::Null),
-                        timestamp: json
-                            .get("timestamp")
-                            .and_then(|t| t.as_str())
-                            .map(|s| s.to_string()),
-                    });
-
-                    // Track file accesses
-                    if name == "Read" || name == "Write" || name == "Edit" {
-                        if let Some(file_path) = tool_use
-                            .get("input")
-                            .and_then(|i| i.get("file_path"))
-                            .and_then(|fp| fp.as_str())
+            match record {
+                Record::Turn(mut entry) => {
+                    // Count each response's usage once
+                    if let Some(message) = entry.message.as_mut() {
+                        if message
+                            .id
+                            .as_ref()
+                            .is_some_and(|id| !responses.insert(id.clone()))
                         {
-                            file_accesses.push(FileAccess {
-                                file_path: file_path.to_string(),
-                                operation: name.to_lowercase(),
-                                line_count: None,
-                            });
+                            message.usage = None;
                         }
                     }
+                    if main.version.is_none() {
+                        main.version = entry.version.clone();
+                    }
+                    if !entry.is_sidechain {
+                        entry.add_to(&mut main);
+                        continue;
+                    }
+
+                    let index = sidechains
+                        .iter()
+                        .position(|chain| chain.contains(&entry))
+                        .unwrap_or_else(|| {
+                            let id = format!("{}/{}", session_id, sidechains.len() + 1);
+                            sidechains.push(Sidechain::new(id, entry.agent_id.clone()));
+                            sidechains.len() - 1
+                        });
+                    let chain = &mut sidechains[index];
+                    chain.uuids.extend(entry.uuid.clone());
+                    entry.add_to(&mut chain.data);
                 }
+                Record::Summary { summary } => main.summaries.push(summary),
+                Record::Other => {}
             }
         }
 
-        let session_id = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_string();
-
-        Ok(SessionData {
-            session_id,
-            messages,
-            tool_calls,
-            file_accesses,
-        })
+        if skipped > 0 {
+            debug!(
+                "Skipped {} unreadable lines in session {}",
+                skipped, session_id
+            );
+        }
+        main.sidechains = sidechains.into_iter().map(|chain| chain.data).collect();
+        main
     }
 
-    fn get_sessions_dir(&self) -> Result<PathBuf> {
+    pub fn get_sessions_dir(&self) -> Result<PathBuf> {
         let home = dirs::home_dir().context("Could not find home directory")?;
 
         Ok(home.join(".claude").join("sessions"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(path: &str, operation: &str) -> FileAccess {
+        FileAccess {
+            file_path: path.to_string(),
+            operation: operation.to_string(),
+            line_count: None,
+        }
+    }
+
+    fn call(name: &str) -> ToolCall {
+        ToolCall {
+            tool_name: name.to_string(),
+            parameters: serde_json::Value::Null,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_session_breakdowns() {
+        let session = SessionData {
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "x".repeat(10),
+                timestamp: None,
+            }],
+            tool_calls: vec![call("Read"), call("Bash"), call("Read")],
+            file_accesses: vec![
+                access("src/main.rs", "read"),
+                access("src/lib.rs", "read"),
+                access("src/main.rs", "edit"),
+            ],
+            ..SessionData::new("s")
+        };
+
+        assert_eq!(session.estimated_tokens(), 3);
+        assert_eq!(
+            session.tool_usage(),
+            vec![("Read".to_string(), 2), ("Bash".to_string(), 1)]
+        );
+
+        let files = session.file_usage();
+        assert_eq!(files[0].file_path, "src/main.rs");
+        assert_eq!((files[0].reads, files[0].edits), (1, 1));
+        assert_eq!(files[1].total(), 1);
+        assert_eq!(session.summary(), "x".repeat(10));
+    }
+
+    #[test]
+    fn test_token_composition() {
+        let message = |role: &str, chars: usize| Message {
+            role: role.to_string(),
+            content: "x".repeat(chars),
+            timestamp: None,
+        };
+        let session = SessionData {
+            messages: vec![
+                message("user", 8),
+                message("assistant", 40),
+                message("user", 4),
+            ],
+            tool_calls: vec![call("Bash")],
+            ..SessionData::new("s")
+        };
+
+        // "Bash" plus "null" for the parameters
+        assert_eq!(
+            session.token_composition(),
+            vec![
+                ("assistant".to_string(), 10),
+                ("user".to_string(), 3),
+                ("tool calls".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_transcript() {
+        let transcript = r#"{"type":"summary","summary":"Fix the parser","leafUuid":"a2"}
+{"type":"user","uuid":"u1","parentUuid":null,"version":"1.0.98","timestamp":"t1","message":{"role":"user","content":"Fix the parser"}}
+{"type":"assistant","uuid":"a1","parentUuid":"u1","message":{"role":"assistant","content":[{"type":"thinking","thinking":"hm"},{"type":"text","text":"Reading it"},{"type":"tool_use","id":"x","name":"Read","input":{"file_path":"src/lib.rs"}}]}}
+{"type":"user","uuid":"u2","parentUuid":"a1","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"x","content":"fn main() {}"}]}}
+{"type":"system","content":"Compacted"}
+{"type":"assistant","uuid":"a2","parentUuid":"u2","message":{"role":"assist
+"#;
+        let session = SessionParser::parse_transcript("s", transcript);
+
+        assert_eq!(session.version.as_deref(), Some("1.0.98"));
+        assert_eq!(session.summaries, vec!["Fix the parser"]);
+        let messages: Vec<(&str, &str)> = session
+            .messages
+            .iter()
+            .map(|m| (m.role.as_str(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("user", "Fix the parser"),
+                ("assistant", "Reading it"),
+                ("tool", "fn main() {}"),
+            ]
+        );
+        assert_eq!(session.messages[0].timestamp.as_deref(), Some("t1"));
+        assert_eq!(session.tool_calls[0].tool_name, "Read");
+        assert_eq!(session.file_accesses[0].file_path, "src/lib.rs");
+        assert!(session.sidechains.is_empty());
+        assert!(session.token_usage().is_none());
+    }
+
+    #[test]
+    fn test_parse_usage() {
+        let transcript = r#"{"type":"assistant","uuid":"a1","message":{"id":"m1","role":"assistant","content":[{"type":"text","text":"Reading"}],"usage":{"input_tokens":10,"cache_creation_input_tokens":200,"cache_read_input_tokens":1000,"output_tokens":5,"service_tier":"standard"}}}
+{"type":"assistant","uuid":"a2","parentUuid":"a1","message":{"id":"m1","role":"assistant","content":[{"type":"tool_use","name":"Read","input":{}}],"usage":{"input_tokens":10,"cache_creation_input_tokens":200,"cache_read_input_tokens":1000,"output_tokens":5}}}
+{"type":"assistant","uuid":"a3","parentUuid":"a2","message":{"id":"m2","role":"assistant","content":"Done","usage":{"input_tokens":20,"cache_read_input_tokens":1200,"output_tokens":30}}}
+{"type":"assistant","uuid":"s1","isSidechain":true,"message":{"id":"m3","role":"assistant","content":"Sub","usage":{"input_tokens":1,"output_tokens":2}}}
+"#;
+        let session = SessionParser::parse_transcript("s", transcript);
+
+        assert_eq!(session.usage.len(), 2);
+        assert_eq!(session.context_tokens(), Some(1220));
+        let usage = session.token_usage().unwrap();
+        assert_eq!(
+            usage,
+            TokenUsage {
+                input_tokens: 31,
+                output_tokens: 37,
+                cache_creation_input_tokens: 200,
+                cache_read_input_tokens: 2200,
+            }
+        );
+        assert_eq!(session.tokens(), 2468);
+    }
+
+    #[test]
+    fn test_parse_sidechains() {
+        let transcript = r#"{"type":"user","uuid":"u1","message":{"role":"user","content":"Review and test"}}
+{"type":"user","uuid":"s1","parentUuid":null,"isSidechain":true,"message":{"role":"user","content":"Review"}}
+{"type":"user","uuid":"t1","parentUuid":null,"isSidechain":true,"message":{"role":"user","content":"Test"}}
+{"type":"assistant","uuid":"s2","parentUuid":"s1","isSidechain":true,"message":{"role":"assistant","content":[{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}]}}
+{"type":"assistant","uuid":"x","isSidechain":true,"agentId":"b","message":{"role":"assistant","content":"One"}}
+{"type":"assistant","uuid":"y","isSidechain":true,"agentId":"b","message":{"role":"assistant","content":"Two"}}
+"#;
+        let session = SessionParser::parse_transcript("s", transcript);
+
+        assert_eq!(session.messages.len(), 1);
+        assert!(session.tool_calls.is_empty());
+        assert_eq!(session.sidechains.len(), 3);
+
+        let review = &session.sidechains[0];
+        assert_eq!(review.session_id, "s/1");
+        assert_eq!(review.messages[0].content, "Review");
+        assert_eq!(review.tool_calls[0].tool_name, "Bash");
+        assert_eq!(session.sidechains[1].messages[0].content, "Test");
+        assert_eq!(session.sidechains[2].messages.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_legacy_lines() {
+        let transcript = r#"{"role":"user","content":"Hello","timestamp":"t1"}
+{"role":"assistant","content":"Hi","tool_use":{"name":"Edit","input":{"file_path":"a.rs"}}}
+"#;
+        let session = SessionParser::parse_transcript("s", transcript);
+
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(session.messages[1].content, "Hi");
+        assert_eq!(session.file_accesses[0].operation, "edit");
+        assert!(session.version.is_none());
+    }
+
+    #[test]
+    fn test_transcript_name() {
+        let name = |name: &str| transcript_name(Path::new(name));
+        assert_eq!(name("abc.jsonl"), Some(("abc".to_string(), false)));
+        assert_eq!(name("abc.jsonl.gz"), Some(("abc".to_string(), true)));
+        assert_eq!(name("abc.jsonl.2"), Some(("abc.2".to_string(), false)));
+        assert_eq!(name("abc.jsonl.2.gz"), Some(("abc.2".to_string(), true)));
+        assert_eq!(name("abc.json"), None);
+        assert_eq!(name("abc.jsonl.bak"), None);
+        assert_eq!(name(".jsonl"), None);
+    }
+
+    #[test]
+    fn test_read_compressed_transcript() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.jsonl.1.gz");
+        let mut encoder = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default());
+        encoder
+            .write_all(br#"{"type":"user","message":{"role":"user","content":"Hello"}}"#)
+            .unwrap();
+        encoder.finish().unwrap();
+
+        let session = SessionParser::new().parse_session(&path).unwrap();
+        assert_eq!(session.session_id, "abc.1");
+        assert_eq!(session.messages[0].content, "Hello");
+    }
+}
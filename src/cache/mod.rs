@@ -1,8 +1,66 @@
 use anyhow::{Context, Result};
+use moka::sync::Cache as MemoryCache;
+use moka::Expiry;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Default limit for the whole cache directory
+pub const DEFAULT_MAX_TOTAL_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Default limit for a single serialized entry
+pub const DEFAULT_MAX_ENTRY_BYTES: u64 = 5 * 1024 * 1024;
+
+/// How often expired entries are swept from disk
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Marker file whose mtime records the last sweep
+const SWEEP_MARKER: &str = ".last_sweep";
+
+/// Well-known namespaces. Keys are `namespace/name`; nested namespaces
+/// like `analysis/<project>/...` are allowed.
+pub mod namespace {
+    /// Usage data fetched for the status line
+    pub const USAGE: &str = "usage";
+    /// Model pricing data
+    pub const PRICING: &str = "pricing";
+    /// Parsed sessions and analysis results, per project
+    pub const ANALYSIS: &str = "analysis";
+    /// Latest release seen by the update check
+    pub const UPDATE: &str = "update";
+    /// Agent API responses, keyed by a hash of the request
+    pub const RESPONSES: &str = "responses";
+}
+
+/// Maximum number of entries kept in the in-process memory tier
+const MEMORY_MAX_ENTRIES: u64 = 1_000;
+
+/// Hit/miss counters per key, accumulated across invocations
+const STATS_FILE: &str = ".stats.json";
+
+/// Counters each process appends on drop, folded into `STATS_FILE` when
+/// the cache is swept, evicted from or cleared
+const STATS_JOURNAL: &str = ".stats.jsonl";
+
+/// Journal size at which the process appending to it compacts it itself
+const STATS_JOURNAL_MAX_BYTES: u64 = 64 * 1024;
+
+/// Keys counters are kept for at most; the least looked-up go first
+const MAX_COUNTED_KEYS: usize = 1_000;
+
+/// Appends up to this size cannot interleave, so they need only the
+/// shared lock
+const ATOMIC_APPEND_BYTES: usize = 4096;
+
+/// Advisory lock shared by every process using the cache directory.
+/// Readers take it shared, writers exclusive.
+const LOCK_FILE: &str = ".lock";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheEntry<T> {
@@ -34,46 +92,244 @@ where
             .unwrap()
             .as_secs();
 
-        now - self.timestamp < self.ttl_seconds
+        now.saturating_sub(self.timestamp) < self.ttl_seconds
+    }
+
+    /// Time left before the entry expires
+    pub fn remaining(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Duration::from_secs((self.timestamp + self.ttl_seconds).saturating_sub(now))
+    }
+}
+
+/// A decoded entry held in memory, expiring after its own TTL
+#[derive(Clone)]
+struct MemoryEntry {
+    data: Arc<serde_json::Value>,
+    ttl: Duration,
+}
+
+struct EntryTtl;
+
+impl Expiry<PathBuf, MemoryEntry> for EntryTtl {
+    fn expire_after_create(
+        &self,
+        _key: &PathBuf,
+        entry: &MemoryEntry,
+        _at: Instant,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &PathBuf,
+        entry: &MemoryEntry,
+        _at: Instant,
+        _remaining: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+}
+
+/// Process-wide memory tier in front of the file cache, keyed by entry path.
+/// Hot keys (usage data, parsed sessions) skip disk and locking entirely,
+/// while the files keep entries alive across invocations.
+fn memory() -> &'static MemoryCache<PathBuf, MemoryEntry> {
+    static MEMORY: OnceLock<MemoryCache<PathBuf, MemoryEntry>> = OnceLock::new();
+
+    MEMORY.get_or_init(|| {
+        MemoryCache::builder()
+            .max_capacity(MEMORY_MAX_ENTRIES)
+            .expire_after(EntryTtl)
+            .build()
+    })
+}
+
+/// One async mutex per key currently being fetched by [`Cache::get_or_fetch`]
+fn in_flight(path: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    static IN_FLIGHT: OnceLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> =
+        OnceLock::new();
+
+    let mut flights = IN_FLIGHT
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    // Drop locks nobody is holding or waiting on any more
+    flights.retain(|_, flight| Arc::strong_count(flight) > 1);
+
+    flights.entry(path.to_path_buf()).or_default().clone()
+}
+
+fn remember(path: &Path, data: serde_json::Value, ttl: Duration) {
+    if !ttl.is_zero() {
+        memory().insert(
+            path.to_path_buf(),
+            MemoryEntry {
+                data: Arc::new(data),
+                ttl,
+            },
+        );
     }
 }
 
+/// Lookup counters for one key
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct HitCounts {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Summary of one cached entry, for `cache stats`
+#[derive(Debug, Clone)]
+pub struct EntryStats {
+    pub key: String,
+    pub size: u64,
+    pub age: Duration,
+    /// `None` once the entry has expired (or can't be parsed)
+    pub ttl_remaining: Option<Duration>,
+    pub counts: HitCounts,
+}
+
 pub struct Cache {
     cache_dir: PathBuf,
+    max_total_bytes: u64,
+    max_entry_bytes: u64,
+    /// Counters from this process, appended to `STATS_JOURNAL` on drop
+    counts: Mutex<HashMap<String, HitCounts>>,
 }
 
 impl Cache {
     pub fn new() -> Result<Self> {
-        let cache_dir = dirs::cache_dir()
-            .context("Failed to find cache directory")?
-            .join("claude-helper");
+        let cache_dir = crate::config::Config::cache_dir()?;
 
+        let cache = Self::in_dir(cache_dir)?;
+        cache.spawn_sweep_if_due();
+
+        Ok(cache)
+    }
+
+    /// Open a cache rooted at `cache_dir` (no background sweep)
+    pub fn in_dir(cache_dir: PathBuf) -> Result<Self> {
         if !cache_dir.exists() {
-            fs::create_dir_all(&cache_dir)
-                .context("Failed to create cache directory")?;
+            fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
         }
 
-        Ok(Self { cache_dir })
+        Ok(Self {
+            cache_dir,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+            counts: Mutex::default(),
+        })
+    }
+
+    /// Override the size limits
+    pub fn with_limits(mut self, max_total_bytes: u64, max_entry_bytes: u64) -> Self {
+        self.max_total_bytes = max_total_bytes;
+        self.max_entry_bytes = max_entry_bytes;
+        self
     }
 
     pub fn get<T>(&self, key: &str) -> Result<Option<T>>
     where
         T: Serialize + for<'de> Deserialize<'de>,
     {
-        let cache_file = self.cache_dir.join(format!("{}.json", key));
+        let result = self.lookup(key);
+
+        if let Ok(mut counts) = self.counts.lock() {
+            let counts = counts.entry(key.to_string()).or_default();
+            match result {
+                Ok(Some(_)) => counts.hits += 1,
+                _ => counts.misses += 1,
+            }
+        }
+
+        result
+    }
+
+    /// Return the cached value for `key`, or compute it with `f` and cache it
+    pub fn get_or_insert_with<T, F>(&self, key: &str, ttl_seconds: u64, f: F) -> Result<T>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone,
+        F: FnOnce() -> Result<T>,
+    {
+        if let Ok(Some(value)) = self.get(key) {
+            return Ok(value);
+        }
+
+        let value = f()?;
+        if let Err(e) = self.set(key, value.clone(), ttl_seconds) {
+            debug!("Failed to cache {}: {}", key, e);
+        }
+
+        Ok(value)
+    }
+
+    /// Async version of [`Cache::get_or_insert_with`] with single-flight semantics:
+    /// concurrent callers in this process wait for one fetch instead of each
+    /// hitting the network. Fetch errors are returned and not cached.
+    pub async fn get_or_fetch<T, F, Fut>(&self, key: &str, ttl_seconds: u64, fetch: F) -> Result<T>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Ok(Some(value)) = self.get(key) {
+            return Ok(value);
+        }
+
+        let flight = in_flight(&self.entry_path(key)?);
+        let _guard = flight.lock().await;
+
+        // Another caller may have filled the entry while we waited
+        if let Ok(Some(value)) = self.lookup(key) {
+            return Ok(value);
+        }
+
+        let value = fetch().await?;
+        if let Err(e) = self.set(key, value.clone(), ttl_seconds) {
+            debug!("Failed to cache {}: {}", key, e);
+        }
+
+        Ok(value)
+    }
+
+    fn lookup<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let cache_file = self.entry_path(key)?;
+
+        if let Some(entry) = memory().get(&cache_file) {
+            if let Ok(data) = T::deserialize(entry.data.as_ref()) {
+                return Ok(Some(data));
+            }
+        }
 
         if !cache_file.exists() {
             return Ok(None);
         }
 
-        let contents = fs::read_to_string(&cache_file)
-            .context("Failed to read cache file")?;
+        let contents = {
+            let _lock = self.lock(false)?;
+            fs::read_to_string(&cache_file).context("Failed to read cache file")?
+        };
 
-        let entry: CacheEntry<T> = serde_json::from_str(&contents)
-            .context("Failed to parse cache file")?;
+        let entry: CacheEntry<serde_json::Value> =
+            serde_json::from_str(&contents).context("Failed to parse cache file")?;
 
         if entry.is_fresh() {
-            Ok(Some(entry.data))
+            // The file mtime doubles as the last access time for LRU eviction
+            touch(&cache_file);
+
+            let data = T::deserialize(&entry.data).context("Failed to parse cache file")?;
+            remember(&cache_file, entry.data.clone(), entry.remaining());
+            Ok(Some(data))
         } else {
             // Cache expired, remove it
             let _ = fs::remove_file(&cache_file);
@@ -85,37 +341,776 @@ impl Cache {
     where
         T: Serialize + for<'de> Deserialize<'de>,
     {
-        let cache_file = self.cache_dir.join(format!("{}.json", key));
-        let entry = CacheEntry::new(data, ttl_seconds);
+        let cache_file = self.entry_path(key)?;
+        let value = serde_json::to_value(&data).context("Failed to serialize cache entry")?;
+        let entry = CacheEntry::new(value, ttl_seconds);
+
+        let contents = serde_json::to_string(&entry).context("Failed to serialize cache entry")?;
 
-        let contents = serde_json::to_string(&entry)
-            .context("Failed to serialize cache entry")?;
+        if contents.len() as u64 > self.max_entry_bytes {
+            anyhow::bail!(
+                "Cache entry '{}' is {} bytes, over the {} byte limit",
+                key,
+                contents.len(),
+                self.max_entry_bytes
+            );
+        }
 
-        fs::write(&cache_file, contents)
-            .context("Failed to write cache file")?;
+        let _lock = self.lock(true)?;
+        if let Some(parent) = cache_file.parent() {
+            fs::create_dir_all(parent).context("Failed to create cache namespace")?;
+        }
+        write_atomic(&cache_file, contents.as_bytes())?;
+        remember(&cache_file, entry.data, Duration::from_secs(ttl_seconds));
+        self.evict_locked()?;
 
         Ok(())
     }
 
     pub fn clear(&self, key: &str) -> Result<()> {
-        let cache_file = self.cache_dir.join(format!("{}.json", key));
+        let cache_file = self.entry_path(key)?;
+        memory().invalidate(&cache_file);
 
         if cache_file.exists() {
-            fs::remove_file(&cache_file)
-                .context("Failed to remove cache file")?;
+            let _lock = self.lock(true)?;
+            fs::remove_file(&cache_file).context("Failed to remove cache file")?;
         }
 
         Ok(())
     }
 
     pub fn clear_all(&self) -> Result<()> {
-        if self.cache_dir.exists() {
-            fs::remove_dir_all(&self.cache_dir)
-                .context("Failed to remove cache directory")?;
-            fs::create_dir_all(&self.cache_dir)
-                .context("Failed to recreate cache directory")?;
+        // Remove entries one by one rather than the whole directory,
+        // so the lock file other processes may hold stays in place
+        let _lock = self.lock(true)?;
+        for file in self.entry_files()? {
+            memory().invalidate(&file.path);
+            fs::remove_file(&file.path).context("Failed to remove cache file")?;
         }
 
+        // Also drop memory entries whose files were already gone
+        forget_under(&self.cache_dir);
+        self.compact_counts_locked()?;
+
         Ok(())
     }
+
+    /// Remove every entry in a namespace (e.g. `analysis` or `analysis/my-project`),
+    /// leaving other namespaces untouched. Returns the number of entries removed.
+    pub fn clear_namespace(&self, namespace: &str) -> Result<usize> {
+        let dir = self
+            .cache_dir
+            .join(validate_key(namespace.trim_end_matches('/'))?);
+
+        let _lock = self.lock(true)?;
+        let removed = self
+            .entry_files()?
+            .iter()
+            .filter(|file| file.path.starts_with(&dir))
+            .filter(|file| fs::remove_file(&file.path).is_ok())
+            .count();
+
+        if dir.is_dir() {
+            fs::remove_dir_all(&dir).context("Failed to remove cache namespace")?;
+        }
+        forget_under(&dir);
+        self.compact_counts_locked()?;
+
+        Ok(removed)
+    }
+
+    /// Remove every entry whose key matches a glob pattern, where `*` matches
+    /// within one path segment and `**` across segments (e.g. `analysis/*/session-*`).
+    /// Returns the number of entries removed.
+    pub fn invalidate_matching(&self, pattern: &str) -> Result<usize> {
+        let _lock = self.lock(true)?;
+        let mut removed = 0;
+
+        for file in self.entry_files()? {
+            if glob_match(pattern, &file.key) && fs::remove_file(&file.path).is_ok() {
+                memory().invalidate(&file.path);
+                removed += 1;
+            }
+        }
+        self.compact_counts_locked()?;
+
+        Ok(removed)
+    }
+
+    /// Delete least-recently-used entries until the cache fits in `max_total_bytes`.
+    /// Returns the number of entries evicted.
+    pub fn evict_to_fit(&self) -> Result<usize> {
+        let _lock = self.lock(true)?;
+        self.evict_locked()
+    }
+
+    fn evict_locked(&self) -> Result<usize> {
+        let mut files = self.entry_files()?;
+        let mut total: u64 = files.iter().map(|f| f.size).sum();
+
+        if total <= self.max_total_bytes {
+            return Ok(0);
+        }
+
+        // Oldest access first
+        files.sort_by_key(|f| f.accessed);
+
+        let mut evicted = 0;
+        for file in files {
+            if total <= self.max_total_bytes {
+                break;
+            }
+            memory().invalidate(&file.path);
+            if fs::remove_file(&file.path).is_ok() {
+                total = total.saturating_sub(file.size);
+                evicted += 1;
+            }
+        }
+
+        debug!("Evicted {} cache entries to stay under size limit", evicted);
+        self.compact_counts_locked()?;
+        Ok(evicted)
+    }
+
+    /// Delete every expired entry. Unreadable entries are removed as well.
+    /// Returns the number of entries deleted.
+    pub fn sweep_expired(&self) -> Result<usize> {
+        let _lock = self.lock(true)?;
+        let mut removed = 0;
+
+        for file in self.entry_files()? {
+            let expired = fs::read_to_string(&file.path)
+                .ok()
+                .and_then(|contents| {
+                    serde_json::from_str::<CacheEntry<serde_json::Value>>(&contents).ok()
+                })
+                .is_none_or(|entry| !entry.is_fresh());
+
+            if expired && fs::remove_file(&file.path).is_ok() {
+                memory().invalidate(&file.path);
+                removed += 1;
+            }
+        }
+        self.compact_counts_locked()?;
+
+        Ok(removed)
+    }
+
+    /// Sweep expired entries on a background thread, at most once per `SWEEP_INTERVAL`
+    fn spawn_sweep_if_due(&self) {
+        let marker = self.cache_dir.join(SWEEP_MARKER);
+
+        let due = fs::metadata(&marker)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|elapsed| elapsed >= SWEEP_INTERVAL);
+
+        if !due || fs::write(&marker, b"").is_err() {
+            return;
+        }
+
+        let cache = Self {
+            cache_dir: self.cache_dir.clone(),
+            max_total_bytes: self.max_total_bytes,
+            max_entry_bytes: self.max_entry_bytes,
+            counts: Mutex::default(),
+        };
+
+        std::thread::spawn(move || match cache.sweep_expired() {
+            Ok(removed) => debug!("Cache sweep removed {} expired entries", removed),
+            Err(e) => debug!("Cache sweep failed: {}", e),
+        });
+    }
+
+    /// Take the cache-wide advisory lock; it is released when the file is dropped
+    fn lock(&self, exclusive: bool) -> Result<fs::File> {
+        let file = fs::File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.cache_dir.join(LOCK_FILE))
+            .context("Failed to open cache lock file")?;
+
+        if exclusive {
+            file.lock()
+        } else {
+            file.lock_shared()
+        }
+        .context("Failed to lock cache")?;
+
+        Ok(file)
+    }
+
+    /// Describe every entry on disk, sorted by key, with hit counters
+    /// from previous invocations and this one
+    pub fn stats(&self) -> Result<Vec<EntryStats>> {
+        let mut counts = {
+            let _lock = self.lock(false)?;
+            self.read_counts()
+        };
+        if let Ok(pending) = self.counts.lock() {
+            merge_counts(&mut counts, &pending);
+        }
+
+        let mut stats = Vec::new();
+        for file in self.entry_files()? {
+            let entry = fs::read_to_string(&file.path).ok().and_then(|contents| {
+                serde_json::from_str::<CacheEntry<serde_json::Value>>(&contents).ok()
+            });
+
+            let age = entry
+                .as_ref()
+                .map(|e| {
+                    let created = UNIX_EPOCH + Duration::from_secs(e.timestamp);
+                    SystemTime::now()
+                        .duration_since(created)
+                        .unwrap_or_default()
+                })
+                .unwrap_or_default();
+
+            stats.push(EntryStats {
+                ttl_remaining: entry.filter(|e| e.is_fresh()).map(|e| e.remaining()),
+                counts: counts.get(&file.key).copied().unwrap_or_default(),
+                key: file.key,
+                size: file.size,
+                age,
+            });
+        }
+
+        stats.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(stats)
+    }
+
+    /// Counters from the stats file plus those journaled since
+    fn read_counts(&self) -> HashMap<String, HitCounts> {
+        let mut counts: HashMap<String, HitCounts> =
+            fs::read_to_string(self.cache_dir.join(STATS_FILE))
+                .ok()
+                .and_then(|contents| serde_json::from_str(&contents).ok())
+                .unwrap_or_default();
+
+        if let Ok(journal) = fs::read_to_string(self.cache_dir.join(STATS_JOURNAL)) {
+            // A line cut short by a crash is skipped
+            for line in journal.lines() {
+                if let Ok(pending) = serde_json::from_str(line) {
+                    merge_counts(&mut counts, &pending);
+                }
+            }
+        }
+        counts
+    }
+
+    /// Append this process's counters to the journal, if it looked anything
+    /// up. The stats file itself is only rewritten by compaction.
+    fn flush_counts(&self) -> Result<()> {
+        let pending = match self.counts.lock() {
+            Ok(mut counts) if !counts.is_empty() => std::mem::take(&mut *counts),
+            _ => return Ok(()),
+        };
+        let mut line = serde_json::to_string(&pending)?;
+        line.push('\n');
+
+        let journal = self.cache_dir.join(STATS_JOURNAL);
+        {
+            let _lock = self.lock(line.len() > ATOMIC_APPEND_BYTES)?;
+            fs::File::options()
+                .create(true)
+                .append(true)
+                .open(&journal)
+                .and_then(|mut file| file.write_all(line.as_bytes()))
+                .context("Failed to write cache statistics")?;
+        }
+
+        if fs::metadata(&journal).is_ok_and(|meta| meta.len() > STATS_JOURNAL_MAX_BYTES) {
+            let _lock = self.lock(true)?;
+            self.compact_counts_locked()?;
+        }
+        Ok(())
+    }
+
+    /// Fold the journal into the stats file, keeping counters only for keys
+    /// that still have an entry, and for at most `MAX_COUNTED_KEYS` of them.
+    /// The caller holds the exclusive lock.
+    fn compact_counts_locked(&self) -> Result<()> {
+        let journal = self.cache_dir.join(STATS_JOURNAL);
+        let stats_file = self.cache_dir.join(STATS_FILE);
+        if !journal.exists() && !stats_file.exists() {
+            return Ok(());
+        }
+
+        let mut counts = self.read_counts();
+        let keys: HashSet<String> = self.entry_files()?.into_iter().map(|f| f.key).collect();
+        counts.retain(|key, _| keys.contains(key));
+        if counts.len() > MAX_COUNTED_KEYS {
+            let mut ranked: Vec<_> = counts.into_iter().collect();
+            ranked.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.hits + counts.misses));
+            ranked.truncate(MAX_COUNTED_KEYS);
+            counts = ranked.into_iter().collect();
+        }
+
+        let contents = serde_json::to_string(&counts)?;
+        write_atomic(&stats_file, contents.as_bytes())?;
+        match fs::remove_file(&journal) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to remove cache statistics journal")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Path of the file backing `key`
+    fn entry_path(&self, key: &str) -> Result<PathBuf> {
+        Ok(self.cache_dir.join(format!("{}.json", validate_key(key)?)))
+    }
+
+    /// All entry files, including those in namespace subdirectories
+    fn entry_files(&self) -> Result<Vec<EntryFile>> {
+        let mut files = Vec::new();
+        let mut dirs = vec![self.cache_dir.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                // Skip non-entries and bookkeeping files (.stats.json, .lock, ...)
+                let is_hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if is_hidden || path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+
+                let Ok(metadata) = fs::metadata(&path) else {
+                    continue;
+                };
+
+                let key = path
+                    .strip_prefix(&self.cache_dir)
+                    .unwrap_or(&path)
+                    .with_extension("")
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                files.push(EntryFile {
+                    key,
+                    size: metadata.len(),
+                    accessed: metadata.modified().unwrap_or(UNIX_EPOCH),
+                    path,
+                });
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush_counts() {
+            debug!("Failed to save cache statistics: {}", e);
+        }
+    }
+}
+
+fn merge_counts(into: &mut HashMap<String, HitCounts>, from: &HashMap<String, HitCounts>) {
+    for (key, counts) in from {
+        let total = into.entry(key.clone()).or_default();
+        total.hits += counts.hits;
+        total.misses += counts.misses;
+    }
+}
+
+struct EntryFile {
+    key: String,
+    path: PathBuf,
+    size: u64,
+    accessed: SystemTime,
+}
+
+/// Reject keys that would escape the cache directory or clash with its
+/// bookkeeping files. Segments are separated by `/`.
+fn validate_key(key: &str) -> Result<&str> {
+    let valid = !key.is_empty()
+        && !key.contains('\\')
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && !segment.starts_with('.'));
+
+    if !valid {
+        anyhow::bail!("Invalid cache key: '{}'", key);
+    }
+
+    Ok(key)
+}
+
+/// Drop every memory-tier entry stored under `dir`
+fn forget_under(dir: &Path) {
+    for (path, _) in memory().iter() {
+        if path.starts_with(dir) {
+            memory().invalidate(path.as_ref());
+        }
+    }
+}
+
+/// Match a cache key against a glob: `*` within a segment, `**` across segments
+fn glob_match(pattern: &str, key: &str) -> bool {
+    fn matches(p: &[u8], k: &[u8]) -> bool {
+        match p {
+            [] => k.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=k.len()).any(|i| matches(rest, &k[i..])),
+            [b'*', rest @ ..] => (0..=k.len())
+                .take_while(|&i| i == 0 || k[i - 1] != b'/')
+                .any(|i| matches(rest, &k[i..])),
+            [c, rest @ ..] => k.first() == Some(c) && matches(rest, &k[1..]),
+        }
+    }
+
+    matches(pattern.as_bytes(), key.as_bytes())
+}
+
+/// Write to a temporary file in the same directory and rename it into place,
+/// so readers see either the old or the new contents, never a partial write
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+
+    fs::write(&tmp, contents).context("Failed to write cache file")?;
+    fs::rename(&tmp, path)
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+        .context("Failed to move cache file into place")?;
+
+    Ok(())
+}
+
+/// Bump a file's mtime to now; failures only affect eviction order
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cache(dir: &tempfile::TempDir) -> Cache {
+        Cache::in_dir(dir.path().to_path_buf()).unwrap()
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("usage", 42u32, 60).unwrap();
+        assert_eq!(cache.get::<u32>("usage").unwrap(), Some(42));
+        assert_eq!(cache.get::<u32>("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_entry_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir).with_limits(1024, 16);
+
+        assert!(cache.set("big", "x".repeat(100), 60).is_err());
+        assert_eq!(cache.get::<String>("big").unwrap(), None);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir).with_limits(250, 1024);
+        let payload = "x".repeat(60);
+
+        cache.set("a", payload.clone(), 60).unwrap();
+        cache.set("b", payload.clone(), 60).unwrap();
+
+        // Make "b" the least recently used
+        let old = SystemTime::now() - Duration::from_secs(600);
+        let file = fs::File::options()
+            .append(true)
+            .open(dir.path().join("b.json"))
+            .unwrap();
+        file.set_modified(old).unwrap();
+
+        cache.set("c", payload, 60).unwrap();
+
+        assert!(cache.get::<String>("a").unwrap().is_some());
+        assert!(cache.get::<String>("b").unwrap().is_none());
+        assert!(cache.get::<String>("c").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_set_leaves_no_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("usage", 1u32, 60).unwrap();
+        cache.set("usage", 2u32, 60).unwrap();
+
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+        assert_eq!(cache.get::<u32>("usage").unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_concurrent_writers_never_tear() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let cache = Cache::in_dir(path).unwrap();
+                    for _ in 0..25 {
+                        cache.set("shared", vec![i; 500], 60).unwrap();
+                        // Every read must parse as a complete entry
+                        cache.get::<Vec<u32>>("shared").unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_clear_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("a", 1u32, 60).unwrap();
+        cache.clear_all().unwrap();
+        assert_eq!(cache.get::<u32>("a").unwrap(), None);
+    }
+
+    #[test]
+    fn test_memory_tier_serves_hot_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("pricing", 7u32, 60).unwrap();
+
+        // Served from memory even when the file is gone
+        fs::remove_file(dir.path().join("pricing.json")).unwrap();
+        assert_eq!(cache.get::<u32>("pricing").unwrap(), Some(7));
+
+        cache.clear("pricing").unwrap();
+        assert_eq!(cache.get::<u32>("pricing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_file_hit_populates_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.json");
+        let entry = CacheEntry::new(serde_json::json!(5), 60);
+        fs::write(&path, serde_json::to_string(&entry).unwrap()).unwrap();
+
+        let cache = test_cache(&dir);
+        assert_eq!(cache.get::<u32>("usage").unwrap(), Some(5));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cache.get::<u32>("usage").unwrap(), Some(5));
+    }
+
+    #[test]
+    fn test_clear_namespace_keeps_others() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("pricing/models", 1u32, 60).unwrap();
+        cache.set("analysis/app/session-1", 2u32, 60).unwrap();
+        cache.set("analysis/lib/session-2", 3u32, 60).unwrap();
+
+        assert_eq!(cache.clear_namespace("analysis/app").unwrap(), 1);
+        assert_eq!(cache.get::<u32>("analysis/app/session-1").unwrap(), None);
+        assert_eq!(cache.get::<u32>("analysis/lib/session-2").unwrap(), Some(3));
+
+        assert_eq!(cache.clear_namespace("analysis").unwrap(), 1);
+        assert_eq!(cache.get::<u32>("pricing/models").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_invalidate_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("analysis/app/session-1", 1u32, 60).unwrap();
+        cache.set("analysis/app/summary", 2u32, 60).unwrap();
+        cache.set("analysis/lib/session-2", 3u32, 60).unwrap();
+
+        assert_eq!(
+            cache.invalidate_matching("analysis/*/session-*").unwrap(),
+            2
+        );
+        assert_eq!(cache.get::<u32>("analysis/app/summary").unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        assert!(cache.set("../escape", 1u32, 60).is_err());
+        assert!(cache.set("usage//current", 1u32, 60).is_err());
+        assert!(cache.set(".lock", 1u32, 60).is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("usage/*", "usage/current"));
+        assert!(!glob_match("usage/*", "usage/a/b"));
+        assert!(glob_match("analysis/**", "analysis/app/session-1"));
+        assert!(glob_match("*", "usage"));
+        assert!(!glob_match("pricing", "pricing/models"));
+    }
+
+    #[test]
+    fn test_stats_counts_hits_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let cache = test_cache(&dir);
+            cache.set("usage/current", 1u32, 60).unwrap();
+            cache.get::<u32>("usage/current").unwrap();
+            cache.get::<u32>("usage/current").unwrap();
+            cache.get::<u32>("pricing/models").unwrap();
+        }
+
+        let cache = test_cache(&dir);
+        cache.get::<u32>("usage/current").unwrap();
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].key, "usage/current");
+        assert_eq!(stats[0].counts.hits, 3);
+        assert!(stats[0].ttl_remaining.is_some());
+        assert!(stats[0].size > 0);
+    }
+
+    #[test]
+    fn test_stats_drop_counters_of_removed_keys() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let cache = test_cache(&dir);
+            cache.set("usage/current", 1u32, 60).unwrap();
+            cache
+                .set("analysis/app/session-1-2048-1700000000", 2u32, 0)
+                .unwrap();
+            cache.get::<u32>("usage/current").unwrap();
+            cache
+                .get::<u32>("analysis/app/session-1-2048-1700000000")
+                .unwrap();
+        }
+        // Dropping only appends to the journal
+        assert!(dir.path().join(STATS_JOURNAL).exists());
+        assert!(!dir.path().join(STATS_FILE).exists());
+
+        let cache = test_cache(&dir);
+        cache.sweep_expired().unwrap();
+        assert!(!dir.path().join(STATS_JOURNAL).exists());
+        let counts = cache.read_counts();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts["usage/current"].hits, 1);
+
+        // Nothing looked up, nothing written
+        drop(cache);
+        assert!(!dir.path().join(STATS_JOURNAL).exists());
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        assert_eq!(
+            cache
+                .get_or_insert_with("usage/current", 60, || Ok(1u32))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            cache
+                .get_or_insert_with("usage/current", 60, || -> Result<u32> {
+                    panic!("should be cached")
+                })
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_or_fetch_single_flight() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Arc::new(test_cache(&dir));
+        let fetches = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                let fetches = fetches.clone();
+                tokio::spawn(async move {
+                    cache
+                        .get_or_fetch("pricing/models", 60, || async {
+                            fetches.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok(42u32)
+                        })
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 42);
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_does_not_cache_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        let result: Result<u32> = cache
+            .get_or_fetch("usage/current", 60, || async { anyhow::bail!("offline") })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(cache.get::<u32>("usage/current").unwrap(), None);
+    }
+
+    #[test]
+    fn test_sweep_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        cache.set("fresh", 1u32, 60).unwrap();
+        cache.set("stale", 2u32, 0).unwrap();
+        fs::write(dir.path().join("corrupt.json"), "{not json").unwrap();
+
+        assert_eq!(cache.sweep_expired().unwrap(), 2);
+        assert_eq!(cache.get::<u32>("fresh").unwrap(), Some(1));
+    }
 }
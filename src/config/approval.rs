@@ -0,0 +1,82 @@
+use crate::agents::AgentCapability;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Steps of a run that can require approval regardless of the autonomy mode
+pub const APPROVAL_ACTIONS: &[&str] = &["plan", "parallel", "final", "apply"];
+
+/// Approval gates on top of the autonomy mode. Anything listed here is
+/// always confirmed, even in `trust` mode.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    /// Confirm before any phase with an agent of these capabilities,
+    /// e.g. `["migration", "security"]`
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+
+    /// Confirm before these steps: `plan` (the plan, before any agent runs),
+    /// `parallel` (phases running agents in parallel), `final` (the last phase),
+    /// `apply` (writing agent code into the project with `run --apply`)
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
+
+impl ApprovalConfig {
+    pub fn validate(&self) -> Result<()> {
+        for name in &self.capabilities {
+            if AgentCapability::from_name(name).is_none() {
+                anyhow::bail!("approval.capabilities: unknown capability '{}'", name);
+            }
+        }
+
+        for action in &self.actions {
+            if !APPROVAL_ACTIONS.contains(&action.to_lowercase().as_str()) {
+                anyhow::bail!(
+                    "approval.actions must only contain: {} (got '{}')",
+                    APPROVAL_ACTIONS.join(", "),
+                    action
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether phases with `capability` agents always need approval
+    pub fn requires_capability(&self, capability: &AgentCapability) -> bool {
+        self.capabilities
+            .iter()
+            .any(|name| AgentCapability::from_name(name).as_ref() == Some(capability))
+    }
+
+    /// Whether `action` (one of [`APPROVAL_ACTIONS`]) always needs approval
+    pub fn requires_action(&self, action: &str) -> bool {
+        self.actions.iter().any(|a| a.eq_ignore_ascii_case(action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires() {
+        let approval = ApprovalConfig {
+            capabilities: vec!["migration".to_string(), "Code-Writing".to_string()],
+            actions: vec!["Plan".to_string()],
+        };
+
+        assert!(approval.validate().is_ok());
+        assert!(approval.requires_capability(&AgentCapability::Migration));
+        assert!(approval.requires_capability(&AgentCapability::CodeWriting));
+        assert!(!approval.requires_capability(&AgentCapability::Testing));
+        assert!(approval.requires_action("plan"));
+        assert!(!approval.requires_action("final"));
+
+        let invalid = ApprovalConfig {
+            actions: vec!["deploy".to_string()],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -1,8 +1,13 @@
+use super::{oauth, secrets, NetworkConfig};
+use crate::error::ClaudeHelperError;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
+/// Keyring entry holding the Anthropic API key
+const API_KEY_ENTRY: &str = "api-key";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AuthMethod {
@@ -17,9 +22,17 @@ pub struct AuthConfig {
     /// Authentication method
     pub method: AuthMethod,
 
-    /// API key (if using ApiKey method)
+    /// API key stored in the config file. Only used when
+    /// `store_api_key_in_file` is set or for configs written before keyring support;
+    /// otherwise the key lives in the OS keyring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
 
+    /// Keep the API key in this file instead of the OS keyring
+    /// (for headless machines without a keychain/Secret Service)
+    #[serde(default)]
+    pub store_api_key_in_file: bool,
+
     /// Path to Claude Code session file
     pub claude_code_session_path: Option<PathBuf>,
 }
@@ -29,6 +42,7 @@ impl Default for AuthConfig {
         Self {
             method: AuthMethod::ClaudeCode,
             api_key: None,
+            store_api_key_in_file: false,
             claude_code_session_path: Self::default_session_path(),
         }
     }
@@ -45,36 +59,56 @@ impl AuthConfig {
         dirs::home_dir().map(|home| home.join(".claude").join("settings.json"))
     }
 
-    /// Get authentication token
-    pub async fn get_token(&self) -> Result<String> {
-        match &self.method {
-            AuthMethod::ClaudeCode => self.get_claude_code_token().await,
-            AuthMethod::ApiKey => self.api_key.clone().context("API key not configured"),
+    /// Get authentication token. An expired Claude Code login is refreshed
+    /// over `network` when it has a refresh token.
+    pub async fn get_token(&self, network: &NetworkConfig) -> crate::error::Result<String> {
+        let token = match &self.method {
+            AuthMethod::ClaudeCode => self.get_claude_code_token(network).await,
+            AuthMethod::ApiKey => self
+                .get_api_key()
+                .and_then(|key| key.context("API key not configured")),
+        };
+        token.map_err(ClaudeHelperError::auth)
+    }
+
+    /// Get the API key from the config file or the OS keyring
+    fn get_api_key(&self) -> Result<Option<String>> {
+        if let Some(key) = &self.api_key {
+            return Ok(Some(key.clone()));
+        }
+
+        if self.store_api_key_in_file {
+            return Ok(None);
+        }
+
+        secrets::get_secret(API_KEY_ENTRY)
+    }
+
+    /// Store the API key according to `store_api_key_in_file`. When the keyring
+    /// is used, any plaintext key left in the config file is removed.
+    pub fn store_api_key(&mut self, api_key: &str) -> Result<()> {
+        if self.store_api_key_in_file {
+            self.api_key = Some(api_key.to_string());
+        } else {
+            secrets::set_secret(API_KEY_ENTRY, api_key).context(
+                "Could not store API key in the OS keyring. \
+                On headless machines set `auth.store_api_key_in_file = true`",
+            )?;
+            self.api_key = None;
         }
+
+        Ok(())
     }
 
     /// Get token from Claude Code session
-    async fn get_claude_code_token(&self) -> Result<String> {
+    async fn get_claude_code_token(&self, network: &NetworkConfig) -> Result<String> {
         // Try to read OAuth token from .credentials.json (new Claude Code format)
-        let home = dirs::home_dir()
-            .context("Could not find home directory")?;
+        let home = dirs::home_dir().context("Could not find home directory")?;
         let credentials_path = home.join(".claude").join(".credentials.json");
 
         if credentials_path.exists() {
-            // Parse credentials.json
-            let credentials_content = fs::read_to_string(&credentials_path)
-                .context("Failed to read .credentials.json")?;
-
-            let credentials: serde_json::Value = serde_json::from_str(&credentials_content)
-                .context("Failed to parse .credentials.json")?;
-
-            // Extract accessToken from claudeAiOauth
-            if let Some(token) = credentials
-                .get("claudeAiOauth")
-                .and_then(|oauth| oauth.get("accessToken"))
-                .and_then(|token| token.as_str())
-            {
-                return Ok(token.to_string());
+            if let Some(token) = oauth::access_token(&credentials_path, network).await? {
+                return Ok(token);
             }
         }
 
@@ -102,19 +136,8 @@ impl AuthConfig {
     }
 
     /// Validate authentication configuration
-    pub async fn validate(&self) -> Result<()> {
-        match &self.method {
-            AuthMethod::ClaudeCode => {
-                // Try to get token to validate
-                self.get_claude_code_token().await?;
-                Ok(())
-            }
-            AuthMethod::ApiKey => {
-                if self.api_key.is_none() {
-                    anyhow::bail!("API key not configured");
-                }
-                Ok(())
-            }
-        }
+    pub async fn validate(&self, network: &NetworkConfig) -> crate::error::Result<()> {
+        // Try to get token to validate
+        self.get_token(network).await.map(|_| ())
     }
 }
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Embedding providers: `none` (keyword matching), `ollama` (a local
+/// model server) or `voyage` (Voyage AI, key in `VOYAGE_API_KEY`)
+pub const EMBEDDING_PROVIDERS: &[&str] = &["none", "ollama", "voyage"];
+
+/// Semantic matching of tasks and sessions with text embeddings, used by
+/// `estimate`, plan reuse and `tasks search`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingsConfig {
+    #[serde(default = "default_provider")]
+    pub provider: String,
+
+    /// Embedding model; `nomic-embed-text` for Ollama and `voyage-3-lite`
+    /// for Voyage when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Ollama server URL
+    #[serde(default = "default_ollama_url")]
+    pub ollama_url: String,
+
+    /// Cosine similarity from which two texts count as similar
+    #[serde(default = "default_min_similarity")]
+    pub min_similarity: f64,
+
+    /// Reuse the plan of a past successful task at least this similar
+    /// instead of planning from scratch; 1.0 turns plan reuse off
+    #[serde(default = "default_reuse_plan_similarity")]
+    pub reuse_plan_similarity: f64,
+}
+
+fn default_provider() -> String {
+    "none".to_string()
+}
+
+fn default_ollama_url() -> String {
+    "http://localhost:11434".to_string()
+}
+
+fn default_min_similarity() -> f64 {
+    0.75
+}
+
+fn default_reuse_plan_similarity() -> f64 {
+    0.92
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            provider: default_provider(),
+            model: None,
+            ollama_url: default_ollama_url(),
+            min_similarity: default_min_similarity(),
+            reuse_plan_similarity: default_reuse_plan_similarity(),
+        }
+    }
+}
+
+impl EmbeddingsConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !EMBEDDING_PROVIDERS.contains(&self.provider.as_str()) {
+            anyhow::bail!(
+                "embeddings.provider must be one of: {} (got '{}')",
+                EMBEDDING_PROVIDERS.join(", "),
+                self.provider
+            );
+        }
+
+        for (key, value) in [
+            ("min_similarity", self.min_similarity),
+            ("reuse_plan_similarity", self.reuse_plan_similarity),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                anyhow::bail!("embeddings.{} must be between 0 and 1 (got {})", key, value);
+            }
+        }
+
+        Ok(())
+    }
+}
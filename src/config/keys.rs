@@ -0,0 +1,364 @@
+use super::Config;
+use anyhow::{Context, Result};
+use toml::Value;
+
+/// Keys that must not be set through `config set` (use a dedicated command)
+const PROTECTED_KEYS: &[(&str, &str)] = &[
+    ("auth.api_key", "claude-helper config set-api-key"),
+    ("org.admin_key", "claude-helper config set-admin-key"),
+];
+
+/// Shown by `config get` in place of a protected key that is set
+const SECRET_MASK: &str = "********";
+
+impl Config {
+    /// The config as TOML for display, with the protected keys stored in
+    /// the file masked
+    pub(super) fn masked_value(&self) -> Result<Value> {
+        let mut root = Value::try_from(self).context("Failed to serialize config")?;
+        for (protected, _) in PROTECTED_KEYS {
+            let (section, field) = protected.split_once('.').unwrap_or_default();
+            if let Some(secret) = root.get_mut(section).and_then(|s| s.get_mut(field)) {
+                *secret = Value::String(SECRET_MASK.to_string());
+            }
+        }
+        Ok(root)
+    }
+
+    /// Look up a single setting by dotted key (e.g. `master_coder.token_budget`).
+    /// Protected keys are masked, also inside their section.
+    pub fn get_value(&self, key: &str) -> Result<Value> {
+        let root = self.masked_value()?;
+
+        let mut current = &root;
+        for part in key.split('.') {
+            current = current
+                .get(part)
+                .with_context(|| format!("Unknown config key: {}", key))?;
+        }
+
+        Ok(current.clone())
+    }
+
+    /// Set a single setting by dotted key. The raw string is parsed according to
+    /// the type of the existing value, and the result must still pass validation.
+    pub fn set_value(&mut self, key: &str, raw: &str) -> Result<()> {
+        if let Some((_, command)) = PROTECTED_KEYS.iter().find(|(k, _)| *k == key) {
+            anyhow::bail!("'{}' cannot be set directly; use `{}`", key, command);
+        }
+
+        let (section_path, field) = key
+            .rsplit_once('.')
+            .with_context(|| format!("Expected a key like section.field, got '{}'", key))?;
+
+        let mut root = Value::try_from(&*self).context("Failed to serialize config")?;
+
+        let mut section = &mut root;
+        for part in section_path.split('.') {
+            section = section
+                .get_mut(part)
+                .with_context(|| format!("Unknown config section: {}", section_path))?;
+        }
+
+        let table = section
+            .as_table_mut()
+            .with_context(|| format!("'{}' is not a config section", section_path))?;
+
+        // Optional fields are omitted from the serialized config while unset,
+        // so a missing field is parsed as a string and checked by deserialization below
+        let value = match table.get(field) {
+            Some(Value::Table(_)) => anyhow::bail!("'{}' is a section, not a setting", key),
+            Some(existing) => {
+                parse_as(existing, raw).with_context(|| format!("Invalid value for {}", key))?
+            }
+            None => Value::String(raw.to_string()),
+        };
+        table.insert(field.to_string(), value);
+
+        let updated: Config = root
+            .try_into()
+            .with_context(|| format!("Invalid value for {}", key))?;
+
+        // Unknown fields are silently dropped by serde, so make sure the key survived
+        updated.get_value(key)?;
+        updated.validate()?;
+
+        *self = updated;
+        Ok(())
+    }
+
+    /// Check semantic constraints that the TOML types alone can't express
+    pub fn validate(&self) -> Result<()> {
+        use crate::master::AUTONOMY_MODES as MODES;
+
+        if !MODES.contains(&self.master_coder.default_mode.to_lowercase().as_str()) {
+            anyhow::bail!(
+                "master_coder.default_mode must be one of: {} (got '{}')",
+                MODES.join(", "),
+                self.master_coder.default_mode
+            );
+        }
+
+        if !crate::agents::AGENT_BACKENDS.contains(&self.master_coder.backend.as_str()) {
+            anyhow::bail!(
+                "master_coder.backend must be one of: {} (got '{}')",
+                crate::agents::AGENT_BACKENDS.join(", "),
+                self.master_coder.backend
+            );
+        }
+
+        if self
+            .master_coder
+            .model
+            .as_deref()
+            .is_some_and(|model| model.trim().is_empty())
+        {
+            anyhow::bail!("master_coder.model must not be empty (remove it to use the default)");
+        }
+
+        if !crate::master::orchestrator::SCHEDULING_MODES
+            .contains(&self.master_coder.scheduling.as_str())
+        {
+            anyhow::bail!(
+                "master_coder.scheduling must be one of: {} (got '{}')",
+                crate::master::orchestrator::SCHEDULING_MODES.join(", "),
+                self.master_coder.scheduling
+            );
+        }
+        if !(1..=100).contains(&self.master_coder.max_parallel_agents) {
+            anyhow::bail!(
+                "master_coder.max_parallel_agents must be between 1 and 100 (got {})",
+                self.master_coder.max_parallel_agents
+            );
+        }
+
+        if !(1_000..=1_000_000).contains(&self.master_coder.token_budget) {
+            anyhow::bail!(
+                "master_coder.token_budget must be between 1000 and 1000000 (got {})",
+                self.master_coder.token_budget
+            );
+        }
+
+        for (name, budget) in &self.master_coder.budget_profiles {
+            if !(1_000..=1_000_000).contains(budget) {
+                anyhow::bail!(
+                    "master_coder.budget_profiles.{} must be between 1000 and 1000000 (got {})",
+                    name,
+                    budget
+                );
+            }
+        }
+
+        if self.master_coder.usage_margin_percent > 90 {
+            anyhow::bail!(
+                "master_coder.usage_margin_percent must be at most 90 (got {})",
+                self.master_coder.usage_margin_percent
+            );
+        }
+
+        if self.master_coder.max_continuations > 20 {
+            anyhow::bail!(
+                "master_coder.max_continuations must be at most 20 (got {})",
+                self.master_coder.max_continuations
+            );
+        }
+
+        if self.master_coder.response_cache_ttl_hours == 0 {
+            anyhow::bail!("master_coder.response_cache_ttl_hours must be at least 1");
+        }
+
+        if self.statusline.update_interval == 0 {
+            anyhow::bail!("statusline.update_interval must be at least 1 second");
+        }
+        crate::statusline::validate_format(&self.statusline.format)?;
+
+        if self.analyzer.context_window < 1_000 {
+            anyhow::bail!(
+                "analyzer.context_window must be at least 1000 tokens (got {})",
+                self.analyzer.context_window
+            );
+        }
+
+        if self.database.max_connections == 0 {
+            anyhow::bail!("database.max_connections must be at least 1");
+        }
+
+        if let Some(url) = &self.network.proxy {
+            reqwest::Proxy::all(url)
+                .with_context(|| format!("network.proxy is not a valid URL: {}", url))?;
+        }
+
+        crate::tui::KeyMap::from_config(&self.tui).context("Invalid [tui] settings")?;
+        crate::tui::Theme::named(&self.tui.theme)?;
+        self.approval.validate()?;
+        self.redaction.validate()?;
+        self.knowledge.validate()?;
+        self.embeddings.validate()?;
+        self.output.validate()?;
+        self.models.validate()?;
+        self.sandbox.validate()?;
+        self.verify.validate()?;
+        self.validators.validate()?;
+        self.retention.validate()?;
+
+        Ok(())
+    }
+}
+
+/// Parse a raw CLI string into the same TOML type as `existing`
+fn parse_as(existing: &Value, raw: &str) -> Result<Value> {
+    Ok(match existing {
+        Value::String(_) => Value::String(raw.to_string()),
+        Value::Integer(_) => Value::Integer(
+            raw.trim()
+                .parse()
+                .with_context(|| format!("expected an integer, got '{}'", raw))?,
+        ),
+        Value::Float(_) => Value::Float(
+            raw.trim()
+                .parse()
+                .with_context(|| format!("expected a number, got '{}'", raw))?,
+        ),
+        Value::Boolean(_) => match raw.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Value::Boolean(true),
+            "false" | "no" | "off" | "0" => Value::Boolean(false),
+            _ => anyhow::bail!("expected true or false, got '{}'", raw),
+        },
+        // Lists are given comma-separated: `migration,security`
+        Value::Array(_) => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        other => anyhow::bail!("setting a {} value is not supported", other.type_str()),
+    })
+}
+
+/// Format a setting for `config get`: plain scalars, TOML for sections
+pub fn display_value(value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        Value::Table(_) => toml::to_string_pretty(value)?.trim_end().to_string(),
+        other => other.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_value() {
+        let config = Config::default();
+        assert_eq!(
+            config.get_value("master_coder.token_budget").unwrap(),
+            Value::Integer(50000)
+        );
+        assert!(config.get_value("master_coder.nope").is_err());
+    }
+
+    #[test]
+    fn test_get_value_masks_secrets() {
+        let mut config = Config::default();
+        config.auth.api_key = Some("sk-ant-api03-secret".to_string());
+        config.org.admin_key = Some("sk-ant-admin01-secret".to_string());
+
+        assert_eq!(
+            config.get_value("auth.api_key").unwrap(),
+            Value::String(SECRET_MASK.to_string())
+        );
+        for section in ["auth", "org"] {
+            let shown = config.get_value(section).unwrap().to_string();
+            assert!(!shown.contains("secret"), "{}", shown);
+        }
+        assert_eq!(config.auth.api_key.as_deref(), Some("sk-ant-api03-secret"));
+    }
+
+    #[test]
+    fn test_set_value_parses_by_type() {
+        let mut config = Config::default();
+        config
+            .set_value("statusline.update_interval", "10")
+            .unwrap();
+        config.set_value("statusline.show_costs", "off").unwrap();
+        config
+            .set_value("master_coder.default_mode", "trust")
+            .unwrap();
+
+        assert_eq!(config.statusline.update_interval, 10);
+        assert!(!config.statusline.show_costs);
+        assert_eq!(config.master_coder.default_mode, "trust");
+    }
+
+    #[test]
+    fn test_set_value_list() {
+        let mut config = Config::default();
+        config
+            .set_value("approval.capabilities", "migration, security")
+            .unwrap();
+        assert_eq!(config.approval.capabilities, vec!["migration", "security"]);
+
+        assert!(config.set_value("approval.actions", "plan,deploy").is_err());
+        config.set_value("approval.capabilities", "").unwrap();
+        assert!(config.approval.capabilities.is_empty());
+    }
+
+    #[test]
+    fn test_set_value_rejects_wrong_type() {
+        let mut config = Config::default();
+        assert!(config
+            .set_value("statusline.update_interval", "soon")
+            .is_err());
+        assert!(config.set_value("statusline.show_costs", "maybe").is_err());
+        assert_eq!(config.statusline.update_interval, 30);
+    }
+
+    #[test]
+    fn test_set_value_validates() {
+        let mut config = Config::default();
+        assert!(config
+            .set_value("master_coder.default_mode", "yolo")
+            .is_err());
+        assert!(config.set_value("master_coder.token_budget", "10").is_err());
+        assert_eq!(config.master_coder.default_mode, "balanced");
+    }
+
+    #[test]
+    fn test_budget_profiles() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.master_coder.budget_profile("feature").unwrap(),
+            50000
+        );
+        config
+            .set_value("master_coder.budget_profiles.quick", "20000")
+            .unwrap();
+        assert_eq!(config.master_coder.budget_profile("quick").unwrap(), 20000);
+        assert!(config
+            .set_value("master_coder.budget_profiles.quick", "10")
+            .is_err());
+
+        let e = config.master_coder.budget_profile("huge").unwrap_err();
+        assert!(e
+            .to_string()
+            .contains("available: feature, overnight, quick"));
+    }
+
+    #[test]
+    fn test_set_value_optional_and_unknown() {
+        let mut config = Config::default();
+        config
+            .set_value("database.url", "postgres://localhost/claude_helper")
+            .unwrap();
+        assert_eq!(
+            config.database.url.as_deref(),
+            Some("postgres://localhost/claude_helper")
+        );
+
+        assert!(config.set_value("statusline.updte_interval", "10").is_err());
+        assert!(config.set_value("auth.api_key", "sk-ant-test").is_err());
+        assert!(config.set_value("statusline", "10").is_err());
+    }
+}
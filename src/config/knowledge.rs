@@ -0,0 +1,50 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Per-project knowledge base of conventions and past decisions that agents
+/// are told about (`claude-helper kb`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Add relevant entries to agent system prompts
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Most entries added to one system prompt
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+
+    /// After a successful run, ask Claude for conventions or decisions worth
+    /// keeping. They are saved as proposals to accept with `kb accept`.
+    #[serde(default)]
+    pub propose_after_run: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_entries() -> usize {
+    10
+}
+
+impl Default for KnowledgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: default_max_entries(),
+            propose_after_run: false,
+        }
+    }
+}
+
+impl KnowledgeConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=50).contains(&self.max_entries) {
+            anyhow::bail!(
+                "knowledge.max_entries must be between 1 and 50 (got {})",
+                self.max_entries
+            );
+        }
+        Ok(())
+    }
+}
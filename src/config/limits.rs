@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// How fast agents may call each backend. All agents of the process share
+/// these budgets, so parallel agents wait for each other instead of running
+/// into 429 responses. Match them to your API tier.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Direct API calls (`master_coder.backend = "api"`)
+    #[serde(default)]
+    pub api: BackendLimits,
+    /// `claude -p` runs (`master_coder.backend = "claude-cli"`)
+    #[serde(default)]
+    pub claude_cli: BackendLimits,
+}
+
+/// Budgets of one backend; 0 means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendLimits {
+    /// Calls started per minute
+    #[serde(default)]
+    pub requests_per_minute: u32,
+    /// Input plus output tokens per minute
+    #[serde(default)]
+    pub tokens_per_minute: u32,
+}
+
+impl BackendLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute == 0 && self.tokens_per_minute == 0
+    }
+}
+
+impl LimitsConfig {
+    /// Limits of `backend`, one of [`crate::agents::AGENT_BACKENDS`]
+    pub fn for_backend(&self, backend: &str) -> BackendLimits {
+        match backend {
+            "claude-cli" => self.claude_cli,
+            _ => self.api,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::Config;
+use anyhow::{Context, Result};
+use toml::value::Table;
+use toml::Value;
+
+/// Version written by this build. Bump it and add a step to [`migrate`]
+/// whenever keys are renamed or removed.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Read the version of a raw config file. Files written before versioning
+/// existed have no `version` key and are treated as version 0.
+pub fn file_version(value: &Value) -> Result<u32> {
+    match value.get("version") {
+        None => Ok(0),
+        Some(v) => v
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("Config `version` must be a non-negative integer"),
+    }
+}
+
+/// Upgrade a raw config from `from` to [`CURRENT_VERSION`], one step at a time
+pub fn migrate(value: &mut Value, from: u32) -> Result<()> {
+    let root = value
+        .as_table_mut()
+        .context("Config file must be a TOML table")?;
+
+    for version in from..CURRENT_VERSION {
+        match version {
+            // v0 -> v1: sections and settings added since the first release
+            // (e.g. [database], statusline.show_costs) get their defaults
+            0 => fill_defaults(root)?,
+            _ => unreachable!("no migration from config version {}", version),
+        }
+    }
+
+    root.insert(
+        "version".to_string(),
+        Value::Integer(i64::from(CURRENT_VERSION)),
+    );
+    Ok(())
+}
+
+/// Add every key present in the default config but missing from `root`,
+/// without touching values the user has set
+fn fill_defaults(root: &mut Table) -> Result<()> {
+    let defaults = Value::try_from(Config::default()).context("Failed to serialize defaults")?;
+
+    if let Value::Table(defaults) = defaults {
+        merge_missing(root, defaults);
+    }
+
+    Ok(())
+}
+
+fn merge_missing(target: &mut Table, defaults: Table) {
+    for (key, default) in defaults {
+        match (target.get_mut(&key), default) {
+            (Some(Value::Table(existing)), Value::Table(default)) => {
+                merge_missing(existing, default)
+            }
+            (Some(_), _) => {}
+            (None, default) => {
+                target.insert(key, default);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0_CONFIG: &str = r#"
+[auth]
+method = "claude_code"
+
+[master_coder]
+default_mode = "trust"
+max_parallel_agents = 3
+token_budget = 20000
+enable_learning = false
+
+[statusline]
+update_interval = 15
+api_endpoint = "https://claude.ai/api"
+
+[analyzer]
+history_depth = 10
+min_savings_threshold = 100
+"#;
+
+    #[test]
+    fn test_unversioned_file_is_v0() {
+        let value: Value = toml::from_str(V0_CONFIG).unwrap();
+        assert_eq!(file_version(&value).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_migrate_v0_fills_missing_keys() {
+        let mut value: Value = toml::from_str(V0_CONFIG).unwrap();
+        migrate(&mut value, 0).unwrap();
+
+        assert_eq!(file_version(&value).unwrap(), CURRENT_VERSION);
+
+        let config: Config = value.try_into().unwrap();
+        assert_eq!(config.master_coder.default_mode, "trust");
+        assert_eq!(config.statusline.update_interval, 15);
+        assert!(config.statusline.show_costs);
+        assert_eq!(config.database.max_connections, 5);
+    }
+
+    #[test]
+    fn test_migrate_current_is_noop() {
+        let mut value = Value::try_from(Config::default()).unwrap();
+        let before = value.clone();
+        migrate(&mut value, CURRENT_VERSION).unwrap();
+        assert_eq!(value, before);
+    }
+}
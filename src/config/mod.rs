@@ -1,25 +1,106 @@
+pub mod approval;
 pub mod auth;
-
+pub mod embeddings;
+mod keys;
+pub mod knowledge;
+pub mod layers;
+pub mod limits;
+mod migrate;
+pub mod models;
+pub mod network;
+mod oauth;
+pub mod org;
+pub mod output;
+pub mod redaction;
+mod retention;
+pub mod sandbox;
+pub mod secrets;
+pub mod trust;
+pub mod tui;
+pub mod validators;
+pub mod verify;
+mod wizard;
+
+use crate::error::ClaudeHelperError;
+use crate::style;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+pub use approval::{ApprovalConfig, APPROVAL_ACTIONS};
 pub use auth::{AuthConfig, AuthMethod};
+pub use embeddings::EmbeddingsConfig;
+pub use keys::display_value;
+pub use knowledge::KnowledgeConfig;
+pub use limits::{BackendLimits, LimitsConfig};
+pub use migrate::CURRENT_VERSION;
+pub use models::ModelsConfig;
+pub use network::NetworkConfig;
+pub use org::OrgConfig;
+pub use output::OutputConfig;
+pub use redaction::RedactionConfig;
+pub use retention::RetentionConfig;
+pub use sandbox::SandboxConfig;
+pub use trust::TrustConfig;
+pub use tui::TuiConfig;
+pub use validators::{ValidatorsConfig, OUTPUT_VALIDATORS};
+pub use verify::VerifyConfig;
+
+/// Overrides the config directory, e.g. for containers or dotfile managers
+pub const CONFIG_DIR_ENV: &str = "CLAUDE_HELPER_CONFIG_DIR";
+
+/// Overrides the data directory holding the database and run logs
+pub const DATA_DIR_ENV: &str = "CLAUDE_HELPER_DATA_DIR";
+
+/// Overrides the cache directory
+pub const CACHE_DIR_ENV: &str = "CLAUDE_HELPER_CACHE_DIR";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Config file format version, used to migrate older files on load
+    #[serde(default)]
+    pub version: u32,
     pub auth: AuthConfig,
     pub master_coder: MasterCoderConfig,
     pub statusline: StatusLineConfig,
     pub analyzer: AnalyzerConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub tui: TuiConfig,
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub knowledge: KnowledgeConfig,
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub models: ModelsConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub org: OrgConfig,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub verify: VerifyConfig,
+    #[serde(default)]
+    pub validators: ValidatorsConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub trust: TrustConfig,
 }
 
-// TODO: Add validation for config values:
-// - default_mode must be one of: conservative, balanced, trust, interactive
-// - max_parallel_agents must be >= 1 and <= 100
-// - token_budget must be >= 1000 and reasonable (<= 1_000_000)
-// TODO: Add tests for config validation
 // TODO: Add tests for config serialization/deserialization roundtrip
 // TODO: Add tests for invalid TOML parsing
 
@@ -31,11 +112,135 @@ pub struct MasterCoderConfig {
     /// Maximum number of parallel agents
     pub max_parallel_agents: usize,
 
+    /// Run fewer agents at once while the backend answers with 429s or
+    /// slows down, and go back up to `max_parallel_agents` once it recovers
+    #[serde(default = "default_true")]
+    pub adaptive_parallelism: bool,
+
+    /// When agents start: `dag` (as soon as the agents they depend on have
+    /// finished) or `phases` (a phase waits for the whole phase before it)
+    #[serde(default = "default_scheduling")]
+    pub scheduling: String,
+
     /// Token budget per task (prevents runaway costs)
     pub token_budget: usize,
 
+    /// Named budgets to pick with `run --budget-profile`, e.g.
+    /// `feature = 50000`
+    #[serde(default = "default_budget_profiles")]
+    pub budget_profiles: BTreeMap<String, usize>,
+
+    /// Scan the project (files, languages, tests) before planning, so
+    /// estimates go by it and not by the task's wording alone
+    #[serde(default)]
+    pub scan_workspace: bool,
+
     /// Enable learning from past sessions
     pub enable_learning: bool,
+
+    /// How agents talk to Claude: `api` (direct API calls) or `claude-cli`
+    /// (the installed `claude` command, using its login and MCP servers)
+    #[serde(default = "default_backend")]
+    pub backend: String,
+
+    /// Model agents use, as a full ID or an alias (`sonnet`, `haiku`, `opus`,
+    /// or one from `[models.aliases]`).
+    /// Defaults to Sonnet for the API and to Claude Code's own choice for `claude-cli`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Claude Code command used by the `claude-cli` backend
+    #[serde(default = "default_claude_command")]
+    pub claude_command: String,
+
+    /// Before a run, check that its estimated tokens fit in what is left of
+    /// the 5-hour and 7-day usage windows
+    #[serde(default = "default_true")]
+    pub check_usage_window: bool,
+
+    /// Share of each window's limit (percent) kept in reserve by that check
+    #[serde(default = "default_usage_margin")]
+    pub usage_margin_percent: u8,
+
+    /// How often an agent answer cut off by the output token limit is
+    /// continued before it is used as is
+    #[serde(default = "default_max_continuations")]
+    pub max_continuations: usize,
+
+    /// Reuse cached answers to identical agent requests instead of paying
+    /// for them again, e.g. when re-running the same plan
+    #[serde(default)]
+    pub reuse_responses: bool,
+
+    /// How long cached agent answers are reused (hours)
+    #[serde(default = "default_response_cache_ttl")]
+    pub response_cache_ttl_hours: u64,
+
+    /// When the token budget or rate limiting stops a run's agents, offer to
+    /// pause it and queue the rest until the usage window resets
+    #[serde(default = "default_true")]
+    pub queue_blocked_agents: bool,
+}
+
+fn default_backend() -> String {
+    "api".to_string()
+}
+
+fn default_scheduling() -> String {
+    "dag".to_string()
+}
+
+fn default_claude_command() -> String {
+    "claude".to_string()
+}
+
+fn default_budget_profiles() -> BTreeMap<String, usize> {
+    [
+        ("quick", 10_000),
+        ("feature", 50_000),
+        ("overnight", 200_000),
+    ]
+    .into_iter()
+    .map(|(name, budget)| (name.to_string(), budget))
+    .collect()
+}
+
+impl MasterCoderConfig {
+    /// The token budget of the profile called `name`
+    pub fn budget_profile(&self, name: &str) -> Result<usize> {
+        match self.budget_profiles.get(name) {
+            Some(budget) => Ok(*budget),
+            None if self.budget_profiles.is_empty() => anyhow::bail!(
+                "No budget profile '{}'; add one under [master_coder.budget_profiles]",
+                name
+            ),
+            None => anyhow::bail!(
+                "No budget profile '{}' (available: {})",
+                name,
+                self.budget_profiles
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_usage_margin() -> u8 {
+    10
+}
+
+fn default_max_continuations() -> usize {
+    3
+}
+
+fn default_response_cache_ttl() -> u64 {
+    24
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +253,19 @@ pub struct StatusLineConfig {
 
     /// Claude API endpoint for usage data
     pub api_endpoint: String,
+
+    /// Mention new releases in `status`, checking at most once a day
+    #[serde(default)]
+    pub check_updates: bool,
+
+    /// Layout of `claude-helper statusline`, with the placeholders {source},
+    /// {five_hour}, {seven_day}, {burn_rate}, {model} and {branch}
+    #[serde(default = "default_statusline_format")]
+    pub format: String,
+}
+
+fn default_statusline_format() -> String {
+    "{source} [5h: {five_hour}] [7d: {seven_day}] {burn_rate}".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,43 +275,121 @@ pub struct AnalyzerConfig {
 
     /// Minimum token savings to suggest optimization
     pub min_savings_threshold: usize,
+
+    /// Context window of the session's model, in tokens; `analyze --watch`
+    /// reports how full it is
+    #[serde(default = "default_context_window")]
+    pub context_window: usize,
+}
+
+fn default_context_window() -> usize {
+    200_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    /// Maximum number of pooled SQLite connections
+    pub max_connections: u32,
+
+    /// How long to wait on a locked database before failing (milliseconds)
+    pub busy_timeout_ms: u64,
+
+    /// Shared database URL (e.g. `postgres://user@host/claude_helper`).
+    /// Requires the `postgres` feature; the local SQLite file is used when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Encrypt task descriptions, plans, and results at rest.
+    /// The key is generated on first use and kept in the OS keyring.
+    #[serde(default)]
+    pub encrypt_sensitive: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            busy_timeout_ms: 5000,
+            url: None,
+            encrypt_sensitive: false,
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CURRENT_VERSION,
             auth: AuthConfig::default(),
             master_coder: MasterCoderConfig {
                 default_mode: "balanced".to_string(),
                 max_parallel_agents: 5,
+                adaptive_parallelism: true,
+                scheduling: default_scheduling(),
                 token_budget: 50000,
+                budget_profiles: default_budget_profiles(),
+                scan_workspace: false,
                 enable_learning: true,
+                backend: default_backend(),
+                model: None,
+                claude_command: default_claude_command(),
+                check_usage_window: true,
+                usage_margin_percent: default_usage_margin(),
+                max_continuations: default_max_continuations(),
+                reuse_responses: false,
+                response_cache_ttl_hours: default_response_cache_ttl(),
+                queue_blocked_agents: true,
             },
             statusline: StatusLineConfig {
                 update_interval: 30,
                 show_costs: true,
                 api_endpoint: "https://claude.ai/api".to_string(),
+                check_updates: false,
+                format: default_statusline_format(),
             },
             analyzer: AnalyzerConfig {
                 history_depth: 50,
                 min_savings_threshold: 500,
+                context_window: default_context_window(),
             },
+            database: DatabaseConfig::default(),
+            network: NetworkConfig::default(),
+            tui: TuiConfig::default(),
+            approval: ApprovalConfig::default(),
+            redaction: RedactionConfig::default(),
+            knowledge: KnowledgeConfig::default(),
+            embeddings: EmbeddingsConfig::default(),
+            output: OutputConfig::default(),
+            models: ModelsConfig::default(),
+            limits: LimitsConfig::default(),
+            org: OrgConfig::default(),
+            sandbox: SandboxConfig::default(),
+            verify: VerifyConfig::default(),
+            validators: ValidatorsConfig::default(),
+            retention: RetentionConfig::default(),
+            trust: TrustConfig::default(),
         }
     }
 }
 
 impl Config {
-    /// Get the configuration directory path
+    /// Get the configuration directory path: `$CLAUDE_HELPER_CONFIG_DIR`, or
+    /// `claude-helper` in the platform config directory (`$XDG_CONFIG_HOME`)
     pub fn config_dir() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .context("Failed to find config directory")?
-            .join("claude-helper");
+        resolve_dir(CONFIG_DIR_ENV, dirs::config_dir(), "config")
+    }
 
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
-        }
+    /// Get the data directory path (database, run logs):
+    /// `$CLAUDE_HELPER_DATA_DIR`, or `claude-helper` in the platform data
+    /// directory (`$XDG_DATA_HOME`)
+    pub fn data_dir() -> Result<PathBuf> {
+        resolve_dir(DATA_DIR_ENV, dirs::data_dir(), "data")
+    }
 
-        Ok(config_dir)
+    /// Get the cache directory path: `$CLAUDE_HELPER_CACHE_DIR`, or
+    /// `claude-helper` in the platform cache directory (`$XDG_CACHE_HOME`)
+    pub fn cache_dir() -> Result<PathBuf> {
+        resolve_dir(CACHE_DIR_ENV, dirs::cache_dir(), "cache")
     }
 
     /// Get the configuration file path
@@ -103,13 +399,20 @@ impl Config {
 
     /// Get the database directory path
     pub fn db_dir() -> Result<PathBuf> {
-        let db_dir = Self::config_dir()?.join("db");
-
-        if !db_dir.exists() {
-            fs::create_dir_all(&db_dir).context("Failed to create database directory")?;
-        }
+        adopt_legacy_dir(
+            &Self::config_dir()?.join("db"),
+            Self::data_dir()?.join("db"),
+            "database",
+        )
+    }
 
-        Ok(db_dir)
+    /// Get the directory holding Master Coder run logs
+    pub fn runs_dir() -> Result<PathBuf> {
+        adopt_legacy_dir(
+            &Self::config_dir()?.join("runs"),
+            Self::data_dir()?.join("runs"),
+            "run logs",
+        )
     }
 
     /// Get the database file path
@@ -118,16 +421,52 @@ impl Config {
     }
 
     /// Load configuration from file or create default
-    pub async fn load() -> Result<Self> {
+    pub async fn load() -> crate::error::Result<Self> {
+        Self::load_file().await.map_err(ClaudeHelperError::config)
+    }
+
+    async fn load_file() -> Result<Self> {
         let config_file = Self::config_file()?;
 
         if config_file.exists() {
             let contents =
                 fs::read_to_string(&config_file).context("Failed to read config file")?;
 
-            let config: Config =
+            let mut value: toml::Value =
                 toml::from_str(&contents).context("Failed to parse config file")?;
 
+            let version = migrate::file_version(&value)?;
+            if version > CURRENT_VERSION {
+                anyhow::bail!(
+                    "Config file version {} is newer than this claude-helper supports ({}). \
+                    Please upgrade claude-helper.",
+                    version,
+                    CURRENT_VERSION
+                );
+            }
+
+            if version < CURRENT_VERSION {
+                // Keep the original around in case the migration gets something wrong
+                let backup = config_file.with_extension(format!("toml.v{}.bak", version));
+                fs::copy(&config_file, &backup).context("Failed to back up config file")?;
+
+                migrate::migrate(&mut value, version)?;
+                let config: Config = value
+                    .try_into()
+                    .context("Failed to parse migrated config file")?;
+                config.save().await?;
+
+                tracing::info!(
+                    "Migrated config from version {} to {} (backup: {:?})",
+                    version,
+                    CURRENT_VERSION,
+                    backup
+                );
+                return Ok(config);
+            }
+
+            let config: Config = value.try_into().context("Failed to parse config file")?;
+
             Ok(config)
         } else {
             // Create default config
@@ -137,19 +476,20 @@ impl Config {
         }
     }
 
-    /// Save configuration to file
+    /// Save configuration to file. It can hold API keys, so only the owner
+    /// may read it.
     pub async fn save(&self) -> Result<()> {
         let config_file = Self::config_file()?;
         let contents = toml::to_string_pretty(self).context("Failed to serialize config")?;
 
-        fs::write(&config_file, contents).context("Failed to write config file")?;
+        write_private(&config_file, contents.as_bytes()).context("Failed to write config file")?;
 
         Ok(())
     }
 
     /// Show current configuration
     pub fn show(&self) -> Result<()> {
-        println!("{}", toml::to_string_pretty(self)?);
+        println!("{}", toml::to_string_pretty(&self.masked_value()?)?);
         Ok(())
     }
 
@@ -169,7 +509,7 @@ impl Config {
         match selection {
             0 => {
                 config.auth.method = AuthMethod::ClaudeCode;
-                println!("✓ Using Claude Code authentication");
+                println!("{} Using Claude Code authentication", style::ok());
             }
             1 => {
                 let api_key: String = Input::new()
@@ -177,8 +517,13 @@ impl Config {
                     .interact_text()?;
 
                 config.auth.method = AuthMethod::ApiKey;
-                config.auth.api_key = Some(api_key);
-                println!("✓ API key saved");
+                config.auth.store_api_key(api_key.trim())?;
+
+                if config.auth.store_api_key_in_file {
+                    println!("{} API key saved to config file", style::ok());
+                } else {
+                    println!("{} API key saved to OS keyring", style::ok());
+                }
             }
             _ => unreachable!(),
         }
@@ -187,6 +532,28 @@ impl Config {
         Ok(())
     }
 
+    /// Ask for an Admin API key, used by `status --org` and reports for
+    /// organization usage
+    pub async fn set_admin_key() -> Result<()> {
+        use dialoguer::Input;
+
+        let admin_key: String = Input::new()
+            .with_prompt("Enter your Anthropic Admin API key (sk-ant-admin...)")
+            .interact_text()?;
+
+        let mut config = Self::load().await?;
+        let in_file = config.auth.store_api_key_in_file;
+        config.org.store_admin_key(admin_key.trim(), in_file)?;
+        if in_file {
+            println!("{} Admin API key saved to config file", style::ok());
+        } else {
+            println!("{} Admin API key saved to OS keyring", style::ok());
+        }
+
+        config.save().await?;
+        Ok(())
+    }
+
     /// Edit configuration file
     pub fn edit() -> Result<()> {
         let config_file = Self::config_file()?;
@@ -212,9 +579,136 @@ impl Config {
         if confirmed {
             let config = Config::default();
             config.save().await?;
-            println!("✓ Configuration reset to defaults");
+            println!("{} Configuration reset to defaults", style::ok());
         }
 
         Ok(())
     }
 }
+
+/// `$var` if set, else `claude-helper` under the platform directory `base`;
+/// created if missing
+fn resolve_dir(var: &str, base: Option<PathBuf>, kind: &str) -> Result<PathBuf> {
+    let dir = match std::env::var_os(var).filter(|value| !value.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => base
+            .with_context(|| format!("Failed to find {} directory", kind))?
+            .join("claude-helper"),
+    };
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {} directory {:?}", kind, dir))?;
+    }
+
+    Ok(dir)
+}
+
+/// Use `dir`, first moving `legacy` there if only it exists. The database
+/// and run logs used to live under the config directory; if they can't be
+/// moved (e.g. across file systems) they stay where they are.
+fn adopt_legacy_dir(legacy: &Path, dir: PathBuf, what: &str) -> Result<PathBuf> {
+    if !dir.exists() && legacy.is_dir() {
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        match fs::rename(legacy, &dir) {
+            Ok(()) => tracing::info!("Moved the {} from {:?} to {:?}", what, legacy, dir),
+            Err(e) => {
+                tracing::warn!(
+                    "Keeping the {} in {:?}, could not move it to {:?}: {}",
+                    what,
+                    legacy,
+                    dir,
+                    e
+                );
+                return Ok(legacy.to_path_buf());
+            }
+        }
+    }
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {} directory {:?}", what, dir))?;
+    }
+
+    Ok(dir)
+}
+
+/// Replace the file at `path` through a temporary file only the owner can
+/// read, so a crash never leaves it half written or readable by others
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    let _ = fs::remove_file(&tmp);
+
+    let mut options = fs::File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    written
+        .and_then(|_| fs::rename(&tmp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "old").unwrap();
+
+        write_private(&path, b"[analyzer]\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[analyzer]\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_adopt_legacy_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let legacy = root.path().join("config/db");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("claude-helper.db"), "data").unwrap();
+
+        let dir = root.path().join("data/db");
+        assert_eq!(
+            adopt_legacy_dir(&legacy, dir.clone(), "database").unwrap(),
+            dir
+        );
+        assert!(dir.join("claude-helper.db").is_file());
+        assert!(!legacy.exists());
+
+        // Nothing to move the second time, and a fresh install just gets the directory
+        assert_eq!(
+            adopt_legacy_dir(&legacy, dir.clone(), "database").unwrap(),
+            dir
+        );
+        let fresh = root.path().join("other/runs");
+        assert_eq!(
+            adopt_legacy_dir(&legacy, fresh.clone(), "run logs").unwrap(),
+            fresh
+        );
+        assert!(fresh.is_dir());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::agents::resolve_model;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Model names of your own, so configs and scripts survive model renames
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelsConfig {
+    /// Alias to model, e.g. `fast = "claude-haiku-4-5-20251001"`. The target
+    /// may itself be a built-in alias (`sonnet`, `haiku`, `opus`), and an
+    /// alias named like a built-in one replaces it.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl ModelsConfig {
+    /// Full model ID for `name`, which may be an alias from `[models.aliases]`
+    /// or a built-in one
+    pub fn resolve(&self, name: &str) -> String {
+        resolve_model(self.expand(name))
+    }
+
+    /// What `name` stands for in `[models.aliases]`, or `name` itself.
+    /// Built-in aliases are left as they are.
+    pub fn expand<'a>(&'a self, name: &'a str) -> &'a str {
+        let name = name.trim();
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map_or(name, |(_, target)| target.trim())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (alias, target) in &self.aliases {
+            if alias.trim().is_empty() || alias.chars().any(char::is_whitespace) {
+                anyhow::bail!("models.aliases has an invalid alias name '{}'", alias);
+            }
+            if target.trim().is_empty() {
+                anyhow::bail!("models.aliases.{} must name a model", alias);
+            }
+            // Targets are resolved once, so an alias of an alias would be
+            // passed on as a model name nobody knows
+            if self
+                .aliases
+                .keys()
+                .any(|other| other.eq_ignore_ascii_case(target.trim()))
+            {
+                anyhow::bail!(
+                    "models.aliases.{} points at another alias ('{}'); use the model ID instead",
+                    alias,
+                    target
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_aliases() {
+        let models = ModelsConfig {
+            aliases: BTreeMap::from([
+                ("fast".to_string(), "haiku".to_string()),
+                ("smart".to_string(), "claude-opus-4-5-20251101".to_string()),
+                ("sonnet".to_string(), "claude-sonnet-4-20250514".to_string()),
+            ]),
+        };
+        assert!(models.validate().is_ok());
+
+        assert_eq!(models.resolve("fast"), "claude-haiku-4-5-20251001");
+        assert_eq!(models.resolve("Smart"), "claude-opus-4-5-20251101");
+        // Configured aliases win over the built-in ones
+        assert_eq!(models.resolve("sonnet"), "claude-sonnet-4-20250514");
+        assert_eq!(models.resolve("opus"), resolve_model("opus"));
+        assert_eq!(
+            models.resolve("claude-3-5-haiku-latest"),
+            "claude-3-5-haiku-latest"
+        );
+        assert_eq!(models.expand("fast"), "haiku");
+
+        let chained = ModelsConfig {
+            aliases: BTreeMap::from([
+                ("fast".to_string(), "cheap".to_string()),
+                ("cheap".to_string(), "haiku".to_string()),
+            ]),
+        };
+        assert!(chained.validate().is_err());
+    }
+}
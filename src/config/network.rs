@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Proxy for all HTTP(S) requests, e.g. `http://proxy.corp:8080`.
+    /// When unset, the standard HTTP_PROXY/HTTPS_PROXY variables are honored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Hosts that bypass the proxy (e.g. `localhost`, `.corp.internal`)
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+
+    /// Extra PEM root certificates to trust (corporate TLS inspection)
+    #[serde(default)]
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// Verify TLS certificates. Only disable for debugging.
+    #[serde(default = "default_verify_tls")]
+    pub verify_tls: bool,
+
+    /// Make no network calls at all (also `--offline`): usage is read from
+    /// local Claude Code transcripts and commands that need Claude fail fast
+    #[serde(default)]
+    pub offline: bool,
+}
+
+fn default_verify_tls() -> bool {
+    true
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_certificates: Vec::new(),
+            verify_tls: true,
+            offline: false,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Fail with a clear message when offline mode forbids `action`,
+    /// e.g. "Running a task"
+    pub fn ensure_online(&self, action: &str) -> Result<()> {
+        if self.offline {
+            anyhow::bail!(
+                "{} needs network access, which is disabled in offline mode \
+                (--offline or network.offline)",
+                action
+            );
+        }
+        Ok(())
+    }
+
+    /// Create a reqwest client builder with proxy and TLS settings applied
+    pub fn client_builder(&self) -> Result<ClientBuilder> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(url) = &self.proxy {
+            let no_proxy = NoProxy::from_string(&self.no_proxy.join(","));
+            let proxy = Proxy::all(url)
+                .with_context(|| format!("Invalid proxy URL: {}", url))?
+                .no_proxy(no_proxy);
+            builder = builder.proxy(proxy);
+        }
+
+        for path in &self.ca_certificates {
+            let pem = fs::read(path)
+                .with_context(|| format!("Failed to read CA certificate {:?}", path))?;
+            let certs = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid PEM certificate in {:?}", path))?;
+
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if !self.verify_tls {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_builds_client() {
+        assert!(NetworkConfig::default()
+            .client_builder()
+            .unwrap()
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_proxy_with_no_proxy() {
+        let network = NetworkConfig {
+            proxy: Some("http://proxy.example.com:8080".to_string()),
+            no_proxy: vec!["localhost".to_string(), ".internal".to_string()],
+            ..Default::default()
+        };
+        assert!(network.client_builder().unwrap().build().is_ok());
+    }
+
+    #[test]
+    fn test_ensure_online() {
+        let mut network = NetworkConfig::default();
+        assert!(network.ensure_online("Checking for updates").is_ok());
+
+        network.offline = true;
+        let err = network.ensure_online("Checking for updates").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Checking for updates needs network access"));
+    }
+
+    #[test]
+    fn test_missing_ca_certificate_fails() {
+        let network = NetworkConfig {
+            ca_certificates: vec![PathBuf::from("/nonexistent/corp-ca.pem")],
+            ..Default::default()
+        };
+        assert!(network.client_builder().is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use super::{write_private, NetworkConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Where Claude Code exchanges a refresh token for a new access token
+const TOKEN_URL: &str = "https://console.anthropic.com/v1/oauth/token";
+
+/// OAuth client ID of Claude Code, which issued the stored tokens
+const CLIENT_ID: &str = "9d1c250a-e61b-44d9-88ed-5944d1962f5e";
+
+/// Refresh this long before the access token expires, so it does not run
+/// out in the middle of a run
+const EXPIRY_MARGIN_MINUTES: i64 = 5;
+
+/// Advisory lock next to `.credentials.json`, held while refreshing so
+/// concurrent processes don't all spend the single-use refresh token
+const LOCK_FILE: &str = ".credentials.lock";
+
+const RELOGIN: &str = "Please log in to Claude Code again (run `claude` and use /login) \
+    or use API key authentication.";
+
+/// The `claudeAiOauth` entry of Claude Code's `.credentials.json`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthCredentials {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Milliseconds since the epoch
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    /// Seconds
+    expires_in: i64,
+}
+
+impl OAuthCredentials {
+    /// The credentials in a parsed `.credentials.json`, if it has any
+    pub fn from_file_contents(credentials: &Value) -> Result<Option<Self>> {
+        credentials
+            .get("claudeAiOauth")
+            .map(|oauth| {
+                serde_json::from_value(oauth.clone())
+                    .context("Invalid claudeAiOauth entry in .credentials.json")
+            })
+            .transpose()
+    }
+
+    pub fn expires(&self) -> Option<DateTime<Utc>> {
+        self.expires_at.and_then(DateTime::from_timestamp_millis)
+    }
+
+    /// Whether the access token has expired or is about to. Tokens without
+    /// an expiry are taken as valid.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires()
+            .is_some_and(|expires| expires - Duration::minutes(EXPIRY_MARGIN_MINUTES) <= now)
+    }
+}
+
+/// A valid access token from the credentials file at `path`, refreshed and
+/// written back first when it has expired
+pub async fn access_token(path: &Path, network: &NetworkConfig) -> Result<Option<String>> {
+    let (_, credentials) = read_credentials(path)?;
+    match credentials {
+        Some(credentials) if credentials.is_expired(Utc::now()) => {
+            refresh_file(path, network, TOKEN_URL).await.map(Some)
+        }
+        credentials => Ok(credentials.map(|c| c.access_token)),
+    }
+}
+
+/// The parsed credentials file at `path` and the OAuth credentials in it
+fn read_credentials(path: &Path) -> Result<(Value, Option<OAuthCredentials>)> {
+    let contents = fs::read_to_string(path).context("Failed to read .credentials.json")?;
+    let file: Value =
+        serde_json::from_str(&contents).context("Failed to parse .credentials.json")?;
+    let credentials = OAuthCredentials::from_file_contents(&file)?;
+    Ok((file, credentials))
+}
+
+/// Refresh the expired token in the credentials file at `path` through
+/// `url`. Only one refresh runs at a time, in this process and across
+/// processes; whoever waited reads the token the first one stored.
+async fn refresh_file(path: &Path, network: &NetworkConfig, url: &str) -> Result<String> {
+    static REFRESH: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    let _flight = REFRESH.get_or_init(Default::default).lock().await;
+    let _lock = lock(path).await?;
+
+    // Another agent or process may have refreshed while we waited
+    let (mut file, credentials) = read_credentials(path)?;
+    let Some(credentials) = credentials else {
+        anyhow::bail!(
+            "The Claude Code login was removed from .credentials.json. {}",
+            RELOGIN
+        );
+    };
+    let now = Utc::now();
+    if !credentials.is_expired(now) {
+        return Ok(credentials.access_token);
+    }
+
+    let expired = credentials
+        .expires()
+        .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    let Some(refresh_token) = &credentials.refresh_token else {
+        anyhow::bail!(
+            "The Claude Code login expired at {} and has no refresh token. {}",
+            expired,
+            RELOGIN
+        );
+    };
+    if network.offline {
+        anyhow::bail!(
+            "The Claude Code login expired at {} and cannot be refreshed in offline mode. {}",
+            expired,
+            RELOGIN
+        );
+    }
+
+    let client = network.client_builder()?.build()?;
+    let refreshed = refresh(&client, url, refresh_token).await.map_err(|e| {
+        anyhow::anyhow!(
+            "The Claude Code login expired at {} and could not be refreshed ({:#}). {}",
+            expired,
+            e,
+            RELOGIN
+        )
+    })?;
+
+    store(&mut file, &refreshed, now);
+    write_private(path, serde_json::to_string_pretty(&file)?.as_bytes())
+        .context("Failed to save the refreshed token to .credentials.json")?;
+    Ok(refreshed.access_token)
+}
+
+/// Take the cross-process refresh lock for the credentials file at `path`;
+/// it is released when the file is dropped
+async fn lock(path: &Path) -> Result<fs::File> {
+    let lock_path = path.with_file_name(LOCK_FILE);
+    tokio::task::spawn_blocking(move || {
+        let file = fs::File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .context("Failed to open the credentials lock file")?;
+        file.lock().context("Failed to lock the credentials file")?;
+        Ok(file)
+    })
+    .await?
+}
+
+/// Exchange `refresh_token` for a new access token at `url`
+async fn refresh(
+    client: &reqwest::Client,
+    url: &str,
+    refresh_token: &str,
+) -> Result<TokenResponse> {
+    let response = client
+        .post(url)
+        .json(&serde_json::json!({
+            "grant_type": "refresh_token",
+            "refresh_token": refresh_token,
+            "client_id": CLIENT_ID,
+        }))
+        .send()
+        .await
+        .context("Failed to refresh the Claude Code token")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!(
+            "Refreshing the Claude Code token failed ({}): {}",
+            status,
+            response.text().await.unwrap_or_default()
+        );
+    }
+
+    response
+        .json()
+        .await
+        .context("Failed to parse the token refresh response")
+}
+
+/// Put refreshed tokens into the parsed credentials file, keeping its other
+/// fields (scopes, subscription type, ...) as Claude Code wrote them
+fn store(file: &mut Value, refreshed: &TokenResponse, now: DateTime<Utc>) {
+    let Some(oauth) = file.get_mut("claudeAiOauth").and_then(Value::as_object_mut) else {
+        return;
+    };
+    oauth.insert(
+        "accessToken".to_string(),
+        refreshed.access_token.clone().into(),
+    );
+    if let Some(refresh_token) = &refreshed.refresh_token {
+        oauth.insert("refreshToken".to_string(), refresh_token.clone().into());
+    }
+    let expires = now + Duration::seconds(refreshed.expires_in);
+    oauth.insert("expiresAt".to_string(), expires.timestamp_millis().into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn credentials_file() -> Value {
+        serde_json::json!({
+            "claudeAiOauth": {
+                "accessToken": "old-access",
+                "refreshToken": "old-refresh",
+                "expiresAt": 1_780_000_000_000_i64,
+                "scopes": ["user:inference"],
+            }
+        })
+    }
+
+    #[test]
+    fn test_expiry() {
+        let credentials = OAuthCredentials::from_file_contents(&credentials_file())
+            .unwrap()
+            .unwrap();
+        let expires = credentials.expires().unwrap();
+
+        assert!(!credentials.is_expired(expires - Duration::hours(1)));
+        // Within the margin counts as expired
+        assert!(credentials.is_expired(expires - Duration::minutes(1)));
+
+        let no_expiry = OAuthCredentials {
+            expires_at: None,
+            ..credentials
+        };
+        assert!(!no_expiry.is_expired(Utc::now()));
+        assert_eq!(
+            OAuthCredentials::from_file_contents(&serde_json::json!({})).unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_updates_credentials() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/oauth/token")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": "old-refresh",
+            })))
+            .with_body(
+                r#"{"access_token":"new-access","refresh_token":"new-refresh","expires_in":3600}"#,
+            )
+            .create_async()
+            .await;
+
+        let url = format!("{}/v1/oauth/token", server.url());
+        let refreshed = refresh(&reqwest::Client::new(), &url, "old-refresh")
+            .await
+            .unwrap();
+        mock.assert_async().await;
+
+        let mut file = credentials_file();
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        store(&mut file, &refreshed, now);
+
+        let oauth = &file["claudeAiOauth"];
+        assert_eq!(oauth["accessToken"], "new-access");
+        assert_eq!(oauth["refreshToken"], "new-refresh");
+        assert_eq!(
+            oauth["expiresAt"],
+            (now + Duration::hours(1)).timestamp_millis()
+        );
+        assert_eq!(oauth["scopes"][0], "user:inference");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_file() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/token")
+            .with_body(
+                r#"{"access_token":"new-access","refresh_token":"new-refresh","expires_in":3600}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let url = format!("{}/token", server.url());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".credentials.json");
+        let mut file = credentials_file();
+        file["claudeAiOauth"]["expiresAt"] = 1_000_000_000_000_i64.into();
+        fs::write(&path, file.to_string()).unwrap();
+
+        let network = NetworkConfig::default();
+        let token = refresh_file(&path, &network, &url).await.unwrap();
+        assert_eq!(token, "new-access");
+        let (_, stored) = read_credentials(&path).unwrap();
+        assert_eq!(
+            stored.unwrap().refresh_token.as_deref(),
+            Some("new-refresh")
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // A refresh that finds the token already refreshed keeps it
+        let token = refresh_file(&path, &network, &url).await.unwrap();
+        assert_eq!(token, "new-access");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rejected() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/token")
+            .with_status(400)
+            .with_body(r#"{"error":"invalid_grant"}"#)
+            .create_async()
+            .await;
+
+        let url = format!("{}/token", server.url());
+        let err = refresh(&reqwest::Client::new(), &url, "revoked")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid_grant"));
+    }
+}
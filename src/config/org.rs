@@ -0,0 +1,77 @@
+use super::secrets;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Keyring entry holding the Anthropic Admin API key
+const ADMIN_KEY_ENTRY: &str = "admin-key";
+
+/// Environment variable that overrides the stored Admin API key
+const ADMIN_KEY_ENV: &str = "ANTHROPIC_ADMIN_KEY";
+
+/// Organization usage for team admins, read from the Anthropic Admin API
+/// with an Admin API key (`sk-ant-admin...`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgConfig {
+    /// Base URL of the Admin API
+    #[serde(default = "default_admin_endpoint")]
+    pub api_endpoint: String,
+
+    /// Tokens the whole organization may use in 7 days; 0 for no shared limit
+    #[serde(default)]
+    pub seven_day_limit: usize,
+
+    /// Admin API key kept in this file, with `auth.store_api_key_in_file`;
+    /// otherwise it lives in the OS keyring
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_key: Option<String>,
+}
+
+fn default_admin_endpoint() -> String {
+    "https://api.anthropic.com".to_string()
+}
+
+impl Default for OrgConfig {
+    fn default() -> Self {
+        Self {
+            api_endpoint: default_admin_endpoint(),
+            seven_day_limit: 0,
+            admin_key: None,
+        }
+    }
+}
+
+impl OrgConfig {
+    /// The Admin API key from `ANTHROPIC_ADMIN_KEY`, the config file or the
+    /// OS keyring, if one was set up
+    pub fn get_admin_key(&self) -> Result<Option<String>> {
+        if let Ok(key) = std::env::var(ADMIN_KEY_ENV) {
+            if !key.is_empty() {
+                return Ok(Some(key));
+            }
+        }
+        if let Some(key) = &self.admin_key {
+            return Ok(Some(key.clone()));
+        }
+        secrets::get_secret(ADMIN_KEY_ENTRY)
+    }
+
+    /// Whether organization usage can be fetched. Keyring errors count as no
+    /// key, so reports on machines without a keyring still work.
+    pub fn is_configured(&self) -> bool {
+        self.get_admin_key().ok().flatten().is_some()
+    }
+
+    /// Store the Admin API key in this file or the OS keyring
+    pub fn store_admin_key(&mut self, admin_key: &str, in_file: bool) -> Result<()> {
+        if in_file {
+            self.admin_key = Some(admin_key.to_string());
+        } else {
+            secrets::set_secret(ADMIN_KEY_ENTRY, admin_key).context(
+                "Could not store the Admin API key in the OS keyring. \
+                On headless machines set `auth.store_api_key_in_file = true`",
+            )?;
+            self.admin_key = None;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use crate::style::THEMES;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// How command output looks in the terminal (the dashboard has `tui.theme`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputConfig {
+    /// `default`, or `plain` for ASCII without color, e.g. for logs or
+    /// terminals that garble emoji
+    #[serde(default = "default_theme")]
+    pub theme: String,
+}
+
+fn default_theme() -> String {
+    "default".to_string()
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            theme: default_theme(),
+        }
+    }
+}
+
+impl OutputConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !THEMES.contains(&self.theme.as_str()) {
+            anyhow::bail!(
+                "output.theme must be one of: {} (got '{}')",
+                THEMES.join(", "),
+                self.theme
+            );
+        }
+        Ok(())
+    }
+}
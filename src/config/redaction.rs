@@ -0,0 +1,62 @@
+use crate::agents::AgentCapability;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Masking of secrets (API keys, tokens, private keys) in prompts sent to
+/// Claude, in run logs and database rows, and in log output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Extra regular expressions to redact on top of the built-in ones.
+    /// A named group `secret` limits the redaction to that part of the match,
+    /// e.g. `internal_token=(?P<secret>\w+)`.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+
+    /// Agents of these capabilities get their prompts unredacted, e.g.
+    /// `["security"]` for an agent auditing credentials
+    #[serde(default)]
+    pub skip_capabilities: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: Vec::new(),
+            skip_capabilities: Vec::new(),
+        }
+    }
+}
+
+impl RedactionConfig {
+    pub fn validate(&self) -> Result<()> {
+        for pattern in &self.patterns {
+            regex::Regex::new(pattern)
+                .with_context(|| format!("redaction.patterns: invalid regex '{}'", pattern))?;
+        }
+
+        for name in &self.skip_capabilities {
+            if AgentCapability::from_name(name).is_none() {
+                anyhow::bail!("redaction.skip_capabilities: unknown capability '{}'", name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether prompts of `capability` agents are redacted
+    pub fn applies_to(&self, capability: &AgentCapability) -> bool {
+        self.enabled
+            && !self
+                .skip_capabilities
+                .iter()
+                .any(|name| AgentCapability::from_name(name).as_ref() == Some(capability))
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// How long finished runs are kept in the runs directory. Older ones are
+/// removed when the next run starts and by `runs clean`; the task history
+/// in the database stays. 0 keeps them forever.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Days the files agents wrote (`runs/<run-id>/`) are kept
+    #[serde(default = "default_artifact_days")]
+    pub artifact_days: u64,
+
+    /// Days run logs (`runs/<run-id>.jsonl`), with the agent transcripts
+    /// they hold, are kept
+    #[serde(default = "default_log_days")]
+    pub log_days: u64,
+
+    /// Clean up when a run starts instead of only with `runs clean`
+    #[serde(default = "super::default_true")]
+    pub auto_clean: bool,
+}
+
+fn default_artifact_days() -> u64 {
+    30
+}
+
+fn default_log_days() -> u64 {
+    90
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            artifact_days: default_artifact_days(),
+            log_days: default_log_days(),
+            auto_clean: true,
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.log_days != 0 && self.log_days < self.artifact_days {
+            anyhow::bail!(
+                "retention.log_days must not be shorter than retention.artifact_days ({} < {}); \
+                artifacts without a log can't be attached to or archived",
+                self.log_days,
+                self.artifact_days
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Run the shell commands of `claude-cli` agents in a Docker container
+/// instead of on the host. Claude Code itself stays on the host to reach the
+/// API; every command of its Bash tool runs in a fresh container with the
+/// project mounted at the same path. Usually set per project in
+/// `.claude-helper.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Image the commands run in; it needs the project's toolchain
+    #[serde(default)]
+    pub image: String,
+
+    /// Extra `docker run -v` mounts, e.g. `~/.cargo/registry:/root/.cargo/registry:ro`
+    #[serde(default)]
+    pub mounts: Vec<String>,
+
+    /// Give the container network access (off: `--network none`)
+    #[serde(default)]
+    pub network: bool,
+
+    /// Docker-compatible CLI, e.g. `podman`
+    #[serde(default = "default_docker_command")]
+    pub docker_command: String,
+}
+
+fn default_docker_command() -> String {
+    "docker".to_string()
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            image: String::new(),
+            mounts: Vec::new(),
+            network: false,
+            docker_command: default_docker_command(),
+        }
+    }
+}
+
+impl SandboxConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.image.trim().is_empty() {
+            anyhow::bail!("sandbox.image must be set when sandbox.enabled is true");
+        }
+        if let Some(mount) = self.mounts.iter().find(|mount| !mount.contains(':')) {
+            anyhow::bail!(
+                "sandbox.mounts must look like host_path:container_path[:options] (got '{}')",
+                mount
+            );
+        }
+        Ok(())
+    }
+
+    /// Command Claude Code puts in front of each Bash tool command
+    /// (`CLAUDE_CODE_SHELL_PREFIX`), which then runs as `sh -c <command>`
+    /// in the container with `project_root` mounted and as working directory
+    pub fn shell_prefix(&self, project_root: &Path) -> String {
+        self.command_args(project_root)
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `docker run` and its arguments up to `sh -c`; a shell command given
+    /// as the last argument runs in a fresh container in `project_root`
+    pub fn command_args(&self, project_root: &Path) -> Vec<String> {
+        let root = project_root.to_string_lossy();
+        let mut args = vec![
+            self.docker_command.clone(),
+            "run".to_string(),
+            "--rm".to_string(),
+            "-i".to_string(),
+        ];
+        if !self.network {
+            args.extend(["--network".to_string(), "none".to_string()]);
+        }
+        args.extend(["-v".to_string(), format!("{}:{}", root, root)]);
+        for mount in &self.mounts {
+            args.extend(["-v".to_string(), expand_home(mount)]);
+        }
+        args.extend([
+            "-w".to_string(),
+            root.into_owned(),
+            self.image.clone(),
+            "sh".to_string(),
+            "-c".to_string(),
+        ]);
+        args
+    }
+}
+
+/// Expand a leading `~/` in the host side of a mount
+fn expand_home(mount: &str) -> String {
+    match (mount.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}/{}", home.to_string_lossy(), rest),
+        _ => mount.to_string(),
+    }
+}
+
+/// Quote `arg` for a POSIX shell unless it is plainly safe
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_prefix() {
+        let sandbox = SandboxConfig {
+            enabled: true,
+            image: "rust:1.82".to_string(),
+            mounts: vec!["/opt/cache:/cache:ro".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sandbox.shell_prefix(Path::new("/home/dev/my app")),
+            "docker run --rm -i --network none -v '/home/dev/my app:/home/dev/my app' \
+            -v /opt/cache:/cache:ro -w '/home/dev/my app' rust:1.82 sh -c"
+        );
+
+        let online = SandboxConfig {
+            network: true,
+            mounts: Vec::new(),
+            ..sandbox
+        };
+        assert!(!online.shell_prefix(Path::new("/src")).contains("--network"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SandboxConfig::default().validate().is_ok());
+
+        let mut sandbox = SandboxConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(sandbox.validate().is_err());
+
+        sandbox.image = "node:22".to_string();
+        sandbox.mounts = vec!["/just/a/path".to_string()];
+        assert!(sandbox.validate().is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::{Context, Result};
+use keyring::Entry;
+
+/// Service name under which all claude-helper secrets are stored
+const SERVICE: &str = "claude-helper";
+
+fn entry(name: &str) -> Result<Entry> {
+    Entry::new(SERVICE, name).context("Failed to access OS keyring")
+}
+
+/// Read a secret from the OS keyring. Returns `None` if it has not been stored.
+pub fn get_secret(name: &str) -> Result<Option<String>> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read '{}' from OS keyring", name)),
+    }
+}
+
+/// Store a secret in the OS keyring, replacing any existing value
+pub fn set_secret(name: &str, value: &str) -> Result<()> {
+    entry(name)?
+        .set_password(value)
+        .with_context(|| format!("Failed to store '{}' in OS keyring", name))
+}
+
+/// Remove a secret from the OS keyring. Missing entries are not an error.
+pub fn delete_secret(name: &str) -> Result<()> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to delete '{}' from OS keyring", name)),
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Project files (`.claude-helper.toml`) trusted with `config trust`: every
+/// setting in them applies, not only the ones any project may change
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustConfig {
+    #[serde(default)]
+    pub projects: Vec<TrustedProject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustedProject {
+    /// Absolute path of the project file
+    pub path: String,
+    /// SHA-256 of its contents when it was trusted; any edit to the file
+    /// (e.g. from a `git pull`) has to be trusted again
+    pub sha256: String,
+}
+
+impl TrustConfig {
+    pub fn is_trusted(&self, path: &Path, contents: &str) -> bool {
+        let path = path_key(path);
+        let sha256 = digest(contents);
+        self.projects
+            .iter()
+            .any(|project| project.path == path && project.sha256 == sha256)
+    }
+
+    /// Trust the file at `path` as it is now
+    pub fn trust(&mut self, path: &Path, contents: &str) {
+        self.revoke(path);
+        self.projects.push(TrustedProject {
+            path: path_key(path),
+            sha256: digest(contents),
+        });
+    }
+
+    /// Stop trusting the file at `path`; false when it was not trusted
+    pub fn revoke(&mut self, path: &Path) -> bool {
+        let path = path_key(path);
+        let before = self.projects.len();
+        self.projects.retain(|project| project.path != path);
+        self.projects.len() != before
+    }
+}
+
+fn path_key(path: &Path) -> String {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn digest(contents: &str) -> String {
+    Sha256::digest(contents.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
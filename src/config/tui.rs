@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuiConfig {
+    /// Key binding preset: "default" or "vim"
+    #[serde(default = "default_keymap")]
+    pub keymap: String,
+
+    /// Color theme: dark, light, solarized, high-contrast or no-color.
+    /// The NO_COLOR environment variable forces no-color.
+    #[serde(default = "default_theme")]
+    pub theme: String,
+
+    /// Per-action overrides on top of the preset, e.g. `quit = ["q", "ctrl+c"]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, Vec<String>>,
+}
+
+fn default_keymap() -> String {
+    "default".to_string()
+}
+
+fn default_theme() -> String {
+    "dark".to_string()
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self {
+            keymap: default_keymap(),
+            theme: default_theme(),
+            keys: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::agents::AgentCapability;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Validators agent output can be checked with: `diff` (diff blocks apply
+/// to the project), `json` (JSON blocks parse), `rust` (Rust files the
+/// answer writes pass `cargo check` in the `[sandbox]` container; skipped
+/// while the sandbox is off)
+pub const OUTPUT_VALIDATORS: &[&str] = &["diff", "json", "rust"];
+
+/// Checks an agent's answer must pass before it is accepted. Whatever they
+/// find is sent back to the agent, which gets two more tries to fix it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorsConfig {
+    #[serde(default = "super::default_true")]
+    pub enabled: bool,
+
+    /// Validators by agent capability, e.g. `code-writing = ["diff", "rust"]`;
+    /// capabilities not listed are not checked
+    #[serde(default = "default_capabilities")]
+    pub capabilities: BTreeMap<String, Vec<String>>,
+}
+
+fn default_capabilities() -> BTreeMap<String, Vec<String>> {
+    let all = ["diff", "json", "rust"];
+    [
+        ("architecture", &["json"][..]),
+        ("code-writing", &all),
+        ("testing", &all),
+        ("security", &["json"]),
+        ("documentation", &["json"]),
+        ("debugging", &all),
+        ("performance", &all),
+        ("migration", &all),
+        ("review", &["json"]),
+    ]
+    .into_iter()
+    .map(|(name, validators)| {
+        let validators = validators.iter().map(|v| v.to_string()).collect();
+        (name.to_string(), validators)
+    })
+    .collect()
+}
+
+impl Default for ValidatorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capabilities: default_capabilities(),
+        }
+    }
+}
+
+impl ValidatorsConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, validators) in &self.capabilities {
+            if AgentCapability::from_name(name).is_none() {
+                anyhow::bail!("validators.capabilities: unknown capability '{}'", name);
+            }
+            if let Some(unknown) = validators
+                .iter()
+                .find(|v| !OUTPUT_VALIDATORS.contains(&v.as_str()))
+            {
+                anyhow::bail!(
+                    "validators.capabilities.{} must only contain: {} (got '{}')",
+                    name,
+                    OUTPUT_VALIDATORS.join(", "),
+                    unknown
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The validators agents of `capability` are checked with
+    pub fn for_capability(&self, capability: &AgentCapability) -> Vec<&str> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        self.capabilities
+            .iter()
+            .filter(|(name, _)| AgentCapability::from_name(name).as_ref() == Some(capability))
+            .flat_map(|(_, validators)| validators.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_capability() {
+        let mut config = ValidatorsConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.for_capability(&AgentCapability::CodeWriting),
+            vec!["diff", "json", "rust"]
+        );
+        assert_eq!(
+            config.for_capability(&AgentCapability::Architecture),
+            vec!["json"]
+        );
+
+        config.enabled = false;
+        assert!(config
+            .for_capability(&AgentCapability::CodeWriting)
+            .is_empty());
+
+        config
+            .capabilities
+            .insert("testing".to_string(), vec!["yaml".to_string()]);
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Most debugging rounds a run may spend on failing tests
+const MAX_ROUNDS: usize = 10;
+
+/// Check tests written by a Testing agent before a run finishes. With
+/// `run --apply`, `test_command` runs in the project (inside the
+/// `[sandbox]` container when it is enabled); while it fails, a Debugging
+/// agent gets the failing output and the diff and its fix is applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Shell command running the project's tests, e.g. `cargo test`
+    #[serde(default)]
+    pub test_command: String,
+
+    /// Debugging rounds before the run reports the tests as failing
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: usize,
+
+    /// How long one test run may take (seconds)
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_max_rounds() -> usize {
+    3
+}
+
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+impl Default for VerifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            test_command: String::new(),
+            max_rounds: default_max_rounds(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+impl VerifyConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.test_command.trim().is_empty() {
+            anyhow::bail!("verify.test_command must be set when verify.enabled is true");
+        }
+        if self.max_rounds > MAX_ROUNDS {
+            anyhow::bail!(
+                "verify.max_rounds must be at most {} (got {})",
+                MAX_ROUNDS,
+                self.max_rounds
+            );
+        }
+        if self.timeout_secs == 0 {
+            anyhow::bail!("verify.timeout_secs must be greater than 0");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use super::{AuthMethod, Config};
+use crate::style::{self, Colorize};
+use anyhow::Result;
+use dialoguer::{Confirm, Input, Select};
+
+const MODES: &[(&str, &str)] = &[
+    ("conservative", "ask for approval frequently"),
+    ("balanced", "approval gates at important points"),
+    ("trust", "fully automatic execution"),
+    ("interactive", "full control over every step"),
+];
+
+impl Config {
+    /// Walk through the most important settings interactively, starting from
+    /// `self`. Returns the new config; the caller decides whether to save it.
+    pub fn setup_wizard(&self) -> Result<Config> {
+        let mut config = self.clone();
+
+        println!("\n{}", "Claude Helper Setup".bright_cyan().bold());
+        println!("{}", style::rule(60).bright_cyan());
+
+        // 1. Authentication
+        println!("\n{}", "Authentication".bright_yellow().bold());
+        let methods = vec!["Claude Code (Pro/Max)", "API Key"];
+        let current = match config.auth.method {
+            AuthMethod::ClaudeCode => 0,
+            AuthMethod::ApiKey => 1,
+        };
+        let selection = Select::new()
+            .with_prompt("Select authentication method")
+            .items(&methods)
+            .default(current)
+            .interact()?;
+
+        if selection == 0 {
+            config.auth.method = AuthMethod::ClaudeCode;
+        } else {
+            let api_key: String = Input::new()
+                .with_prompt("Enter your Anthropic API key")
+                .interact_text()?;
+
+            config.auth.method = AuthMethod::ApiKey;
+            if let Err(e) = config.auth.store_api_key(api_key.trim()) {
+                println!("{} {:#}", style::warn().yellow(), e);
+
+                let use_file = Confirm::new()
+                    .with_prompt("Store the API key in the config file instead?")
+                    .default(false)
+                    .interact()?;
+
+                if !use_file {
+                    anyhow::bail!("Setup cancelled: API key could not be stored");
+                }
+
+                config.auth.store_api_key_in_file = true;
+                config.auth.store_api_key(api_key.trim())?;
+            }
+        }
+
+        // 2. Master Coder
+        println!("\n{}", "Master Coder".bright_yellow().bold());
+        let mode_items: Vec<String> = MODES
+            .iter()
+            .map(|(name, desc)| format!("{} - {}", name, desc))
+            .collect();
+        let current_mode = MODES
+            .iter()
+            .position(|(name, _)| config.master_coder.default_mode.eq_ignore_ascii_case(name))
+            .unwrap_or(1);
+        let mode = Select::new()
+            .with_prompt("Default autonomy mode")
+            .items(&mode_items)
+            .default(current_mode)
+            .interact()?;
+        config.master_coder.default_mode = MODES[mode].0.to_string();
+
+        config.master_coder.token_budget = Input::new()
+            .with_prompt("Token budget per task (1000-1000000)")
+            .default(config.master_coder.token_budget)
+            .validate_with(|budget: &usize| {
+                if (1_000..=1_000_000).contains(budget) {
+                    Ok(())
+                } else {
+                    Err("must be between 1000 and 1000000")
+                }
+            })
+            .interact_text()?;
+
+        // 3. Status line
+        println!("\n{}", "Status Line".bright_yellow().bold());
+        config.statusline.update_interval = Input::new()
+            .with_prompt("Update interval in seconds")
+            .default(config.statusline.update_interval)
+            .validate_with(|interval: &u64| {
+                if *interval >= 1 {
+                    Ok(())
+                } else {
+                    Err("must be at least 1 second")
+                }
+            })
+            .interact_text()?;
+
+        config.statusline.show_costs = Confirm::new()
+            .with_prompt("Show cost estimates?")
+            .default(config.statusline.show_costs)
+            .interact()?;
+
+        config.validate()?;
+        Ok(config)
+    }
+}
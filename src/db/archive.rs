@@ -0,0 +1,234 @@
+use super::backend::with_pool;
+use super::tags::INSERT_TAG_SQL;
+use super::{parse_timestamp, Database};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A task execution with its agents and tags, as moved between databases
+/// by `runs archive` and `runs import`. Everything is in plain text, also
+/// when the database encrypts it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedTask {
+    pub description: String,
+    pub complexity: i64,
+    pub estimated_tokens: i64,
+    pub actual_tokens: i64,
+    pub success: bool,
+    /// `task_data`, `plan_data` and `result_data` as the JSON they hold
+    pub analysis: String,
+    pub plan: String,
+    pub result: String,
+    pub variant: Option<String>,
+    pub project: Option<String>,
+    pub git_remote: Option<String>,
+    pub run_id: Option<String>,
+    /// None in archives of runs saved before the model and cost were recorded
+    pub model: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub agents: Vec<ArchivedAgent>,
+    pub tags: Vec<String>,
+}
+
+/// One `agent_executions` row of an [`ArchivedTask`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedAgent {
+    pub agent_id: String,
+    pub agent_type: String,
+    pub capability: String,
+    pub task: String,
+    pub tokens_used: i64,
+    pub execution_time_ms: i64,
+    pub success: bool,
+    pub model: Option<String>,
+    pub prompt_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+type TaskRow = (
+    String,
+    i64,
+    i64,
+    i64,
+    bool,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<f64>,
+    String,
+);
+
+type AgentRow = (
+    String,
+    String,
+    String,
+    String,
+    i64,
+    i64,
+    bool,
+    Option<String>,
+    Option<String>,
+    String,
+);
+
+/// A timestamp as bound for `timestamp_sql`
+fn timestamp_text(at: &DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+impl Database {
+    /// Task `id` with its agents and tags, to archive
+    pub async fn export_task(&self, id: usize) -> Result<Option<ArchivedTask>> {
+        let row = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, TaskRow>(
+                "SELECT task_description, complexity, estimated_tokens, actual_tokens, success, task_data, plan_data, result_data, variant, project, git_remote, run_id, model, cost_usd, CAST(created_at AS TEXT)
+                 FROM task_executions
+                 WHERE id = $1",
+            )
+            .bind(id as i64)
+            .fetch_optional(pool)
+            .await
+            .context("Failed to read the task")?
+        });
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let agents = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, AgentRow>(
+                "SELECT agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success, model, prompt_version, CAST(created_at AS TEXT)
+                 FROM agent_executions
+                 WHERE task_id = $1
+                 ORDER BY id",
+            )
+            .bind(id as i64)
+            .fetch_all(pool)
+            .await
+            .context("Failed to read the task's agents")?
+        });
+
+        Ok(Some(ArchivedTask {
+            description: self.open(row.0),
+            complexity: row.1,
+            estimated_tokens: row.2,
+            actual_tokens: row.3,
+            success: row.4,
+            analysis: self.open(row.5),
+            plan: self.open(row.6),
+            result: self.open(row.7),
+            variant: row.8,
+            project: row.9,
+            git_remote: row.10,
+            run_id: row.11,
+            model: row.12,
+            cost_usd: row.13,
+            created_at: parse_timestamp(&row.14),
+            agents: agents
+                .into_iter()
+                .map(|agent| ArchivedAgent {
+                    agent_id: agent.0,
+                    agent_type: agent.1,
+                    capability: agent.2,
+                    task: self.open(agent.3),
+                    tokens_used: agent.4,
+                    execution_time_ms: agent.5,
+                    success: agent.6,
+                    model: agent.7,
+                    prompt_version: agent.8,
+                    created_at: parse_timestamp(&agent.9),
+                })
+                .collect(),
+            tags: self.get_task_tags(id).await?,
+        }))
+    }
+
+    /// Add an archived task as a new task, keeping when it ran, and return
+    /// its ID. It is marked as imported so its tokens stay out of the usage
+    /// rollup, also when `db rollup` rebuilds it: they were spent elsewhere.
+    /// Nothing is saved unless all of it is.
+    pub async fn import_task(&self, task: &ArchivedTask) -> Result<i64> {
+        let description = self.seal(&task.description)?;
+        let analysis = self.seal(&task.analysis)?;
+        let plan = self.seal(&task.plan)?;
+        let result = self.seal(&task.result)?;
+        let agent_tasks = task
+            .agents
+            .iter()
+            .map(|agent| self.seal(&agent.task))
+            .collect::<Result<Vec<_>>>()?;
+        let insert_task = format!(
+            "INSERT INTO task_executions (task_description, complexity, estimated_tokens, actual_tokens, success, task_data, plan_data, result_data, variant, project, git_remote, run_id, model, cost_usd, imported, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, {})
+             RETURNING id",
+            self.pool.timestamp_sql(16)
+        );
+        let insert_agent = format!(
+            "INSERT INTO agent_executions (agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success, task_id, model, prompt_version, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, {})",
+            self.pool.timestamp_sql(11)
+        );
+
+        let task_id = with_pool!(&self.pool, |pool| {
+            let mut tx = pool.begin().await?;
+
+            let task_id = sqlx::query_scalar::<_, i64>(&insert_task)
+                .bind(&description)
+                .bind(task.complexity)
+                .bind(task.estimated_tokens)
+                .bind(task.actual_tokens)
+                .bind(task.success)
+                .bind(&analysis)
+                .bind(&plan)
+                .bind(&result)
+                .bind(&task.variant)
+                .bind(&task.project)
+                .bind(&task.git_remote)
+                .bind(&task.run_id)
+                .bind(&task.model)
+                .bind(task.cost_usd)
+                .bind(true)
+                .bind(timestamp_text(&task.created_at))
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to import the task")?;
+
+            for (agent, agent_task) in task.agents.iter().zip(&agent_tasks) {
+                sqlx::query(&insert_agent)
+                    .bind(&agent.agent_id)
+                    .bind(&agent.agent_type)
+                    .bind(&agent.capability)
+                    .bind(agent_task)
+                    .bind(agent.tokens_used)
+                    .bind(agent.execution_time_ms)
+                    .bind(agent.success)
+                    .bind(task_id)
+                    .bind(&agent.model)
+                    .bind(&agent.prompt_version)
+                    .bind(timestamp_text(&agent.created_at))
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to import the task's agents")?;
+            }
+
+            for tag in &task.tags {
+                sqlx::query(INSERT_TAG_SQL)
+                    .bind(task_id)
+                    .bind(tag)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to import the task's tags")?;
+            }
+
+            tx.commit().await?;
+            task_id
+        });
+
+        Ok(task_id)
+    }
+}
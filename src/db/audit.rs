@@ -0,0 +1,143 @@
+use super::backend::with_pool;
+use super::{parse_timestamp, Database};
+use crate::pricing;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// One outbound model API call, as recorded in `api_calls`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApiCall {
+    pub agent_id: String,
+    pub agent_type: String,
+    pub model: String,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub cache_read_tokens: usize,
+    pub cache_write_tokens: usize,
+    pub latency_ms: u64,
+    /// `ok`, the HTTP status of a failed call, or why it failed without one
+    pub status: String,
+    pub cost_usd: f64,
+}
+
+impl ApiCall {
+    /// Dollar cost of the call's tokens at its model's prices
+    pub fn token_cost(&self) -> f64 {
+        pricing::price_or_default(&self.model).cost(
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_read_tokens,
+            self.cache_write_tokens,
+        )
+    }
+}
+
+/// A recorded API call with the time it was made
+#[derive(Debug, Serialize)]
+pub struct ApiCallEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub call: ApiCall,
+}
+
+type ApiCallRow = (
+    String,
+    String,
+    String,
+    i64,
+    i64,
+    i64,
+    i64,
+    i64,
+    String,
+    f64,
+    String,
+);
+
+impl Database {
+    /// Add a call to the `api_calls` audit log
+    pub async fn record_api_call(&self, call: &ApiCall) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "INSERT INTO api_calls (agent_id, agent_type, model, input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, latency_ms, status, cost_usd)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            )
+            .bind(&call.agent_id)
+            .bind(&call.agent_type)
+            .bind(&call.model)
+            .bind(call.input_tokens as i64)
+            .bind(call.output_tokens as i64)
+            .bind(call.cache_read_tokens as i64)
+            .bind(call.cache_write_tokens as i64)
+            .bind(call.latency_ms as i64)
+            .bind(&call.status)
+            .bind(call.cost_usd)
+            .execute(pool)
+            .await
+            .context("Failed to record API call")?;
+        });
+
+        Ok(())
+    }
+
+    /// API calls made since `since`, oldest first
+    pub async fn get_api_calls(&self, since: DateTime<Utc>) -> Result<Vec<ApiCallEntry>> {
+        let query = format!(
+            "SELECT agent_id, agent_type, model, input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, latency_ms, status, cost_usd, CAST(created_at AS TEXT)
+             FROM api_calls
+             WHERE {}
+             ORDER BY created_at, id",
+            self.pool.created_since_sql()
+        );
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, ApiCallRow>(&query)
+                .bind(since.format("%Y-%m-%d %H:%M:%S").to_string())
+                .fetch_all(pool)
+                .await
+                .context("Failed to read API calls")?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ApiCallEntry {
+                timestamp: parse_timestamp(&row.10),
+                call: ApiCall {
+                    agent_id: row.0,
+                    agent_type: row.1,
+                    model: row.2,
+                    input_tokens: row.3 as usize,
+                    output_tokens: row.4 as usize,
+                    cache_read_tokens: row.5 as usize,
+                    cache_write_tokens: row.6 as usize,
+                    latency_ms: row.7 as u64,
+                    status: row.8,
+                    cost_usd: row.9,
+                },
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_cost() {
+        let call = ApiCall {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_read_tokens: 1_000_000,
+            ..Default::default()
+        };
+        assert!((call.token_cost() - 4.8).abs() < 1e-9);
+
+        let opus = ApiCall {
+            model: "opus".to_string(),
+            ..call
+        };
+        assert!((opus.token_cost() - 24.0).abs() < 1e-9);
+    }
+}
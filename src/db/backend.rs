@@ -0,0 +1,232 @@
+use super::schema;
+use crate::config::Config;
+use anyhow::{Context, Result};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+use std::time::Duration;
+
+/// Connection pool for the configured SQL backend
+pub(crate) enum DbPool {
+    Sqlite(SqlitePool),
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::PgPool),
+}
+
+/// Run the same query code against whichever backend is active.
+/// The body is expanded once per backend, so it must only use SQL that
+/// both dialects accept (`$N` placeholders, explicit casts on aggregates).
+macro_rules! with_pool {
+    ($pool:expr, |$p:ident| $body:expr) => {
+        match $pool {
+            $crate::db::backend::DbPool::Sqlite($p) => $body,
+            #[cfg(feature = "postgres")]
+            $crate::db::backend::DbPool::Postgres($p) => $body,
+        }
+    };
+}
+
+pub(crate) use with_pool;
+
+impl DbPool {
+    /// Connect to the configured backend and create tables
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let pool = match config.database.url.as_deref() {
+            Some(url) if is_postgres_url(url) => Self::connect_postgres(config, url, false).await?,
+            Some(url) => anyhow::bail!("Unsupported database URL: {}", url),
+            None => Self::connect_sqlite(config).await?,
+        };
+
+        pool.migrate().await?;
+        Ok(pool)
+    }
+
+    /// Connect without write access. No migrations are run.
+    pub async fn connect_read_only(config: &Config) -> Result<Self> {
+        match config.database.url.as_deref() {
+            Some(url) if is_postgres_url(url) => Self::connect_postgres(config, url, true).await,
+            Some(url) => anyhow::bail!("Unsupported database URL: {}", url),
+            None => {
+                let db_path = Config::db_file()?;
+
+                if !db_path.exists() {
+                    anyhow::bail!("Database not found at {:?}", db_path);
+                }
+
+                let pool = SqlitePoolOptions::new()
+                    .max_connections(1)
+                    .connect_with(
+                        SqliteConnectOptions::new()
+                            .filename(&db_path)
+                            .read_only(true)
+                            .busy_timeout(Duration::from_millis(config.database.busy_timeout_ms)),
+                    )
+                    .await
+                    .context("Failed to open database read-only")?;
+
+                Ok(Self::Sqlite(pool))
+            }
+        }
+    }
+
+    async fn connect_sqlite(config: &Config) -> Result<Self> {
+        let db_path = Config::db_file()?;
+
+        // WAL lets the TUI read while hooks write; busy_timeout makes
+        // concurrent writers wait instead of failing with "database is locked"
+        let options = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(Duration::from_millis(config.database.busy_timeout_ms));
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.database.max_connections.max(1))
+            .connect_with(options)
+            .await
+            .context("Failed to connect to database")?;
+
+        Ok(Self::Sqlite(pool))
+    }
+
+    #[cfg(feature = "postgres")]
+    async fn connect_postgres(config: &Config, url: &str, read_only: bool) -> Result<Self> {
+        use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+        let mut options: PgConnectOptions = url.parse().context("Invalid Postgres URL")?;
+        if read_only {
+            options = options.options([("default_transaction_read_only", "on")]);
+        }
+
+        let pool = PgPoolOptions::new()
+            .max_connections(if read_only {
+                1
+            } else {
+                config.database.max_connections.max(1)
+            })
+            .acquire_timeout(Duration::from_millis(config.database.busy_timeout_ms))
+            .connect_with(options)
+            .await
+            .context("Failed to connect to Postgres")?;
+
+        Ok(Self::Postgres(pool))
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    async fn connect_postgres(_config: &Config, _url: &str, _read_only: bool) -> Result<Self> {
+        anyhow::bail!(
+            "A Postgres database URL is configured, but claude-helper was built \
+            without Postgres support. Rebuild with `--features postgres`."
+        )
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        match self {
+            Self::Sqlite(pool) => {
+                sqlx::raw_sql(schema::CREATE_TABLES)
+                    .execute(pool)
+                    .await
+                    .context("Failed to create database tables")?;
+            }
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => {
+                sqlx::raw_sql(schema::CREATE_TABLES_POSTGRES)
+                    .execute(pool)
+                    .await
+                    .context("Failed to create database tables")?;
+            }
+        }
+
+        for (table, column, definition) in schema::COLUMN_MIGRATIONS {
+            if !self.column_exists(table, column).await? {
+                let alter = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition);
+                with_pool!(self, |pool| {
+                    sqlx::query(&alter)
+                        .execute(pool)
+                        .await
+                        .with_context(|| format!("Failed to add column {}.{}", table, column))?;
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn column_exists(&self, table: &str, column: &str) -> Result<bool> {
+        let count: i64 = match self {
+            Self::Sqlite(pool) => {
+                sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info($1) WHERE name = $2")
+                    .bind(table)
+                    .bind(column)
+                    .fetch_one(pool)
+                    .await?
+            }
+            #[cfg(feature = "postgres")]
+            Self::Postgres(pool) => {
+                sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM information_schema.columns
+                     WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2",
+                )
+                .bind(table)
+                .bind(column)
+                .fetch_one(pool)
+                .await?
+            }
+        };
+
+        Ok(count > 0)
+    }
+
+    /// SQL expression truncating `created_at` to an hour bucket as
+    /// `YYYY-MM-DD HH:00:00` text
+    pub fn hour_bucket_sql(&self) -> &'static str {
+        match self {
+            Self::Sqlite(_) => "strftime('%Y-%m-%d %H:00:00', created_at)",
+            #[cfg(feature = "postgres")]
+            Self::Postgres(_) => "to_char(date_trunc('hour', created_at), 'YYYY-MM-DD HH24:00:00')",
+        }
+    }
+
+    /// SQL expression truncating `created_at` to a `YYYY-MM-DD` day
+    pub fn day_bucket_sql(&self) -> &'static str {
+        match self {
+            Self::Sqlite(_) => "date(created_at)",
+            #[cfg(feature = "postgres")]
+            Self::Postgres(_) => "to_char(created_at, 'YYYY-MM-DD')",
+        }
+    }
+
+    /// SQL expression truncating `created_at` to the Monday (`YYYY-MM-DD`)
+    /// starting its week
+    pub fn week_bucket_sql(&self) -> &'static str {
+        match self {
+            Self::Sqlite(_) => "date(created_at, 'weekday 0', '-6 days')",
+            #[cfg(feature = "postgres")]
+            Self::Postgres(_) => "to_char(date_trunc('week', created_at), 'YYYY-MM-DD')",
+        }
+    }
+
+    /// SQL expression storing the `$n` text timestamp (`YYYY-MM-DD HH:MM:SS`)
+    /// as a `created_at` value
+    pub fn timestamp_sql(&self, n: usize) -> String {
+        match self {
+            Self::Sqlite(_) => format!("datetime(${})", n),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(_) => format!("CAST(${} AS TIMESTAMP)", n),
+        }
+    }
+
+    /// SQL condition comparing `created_at` against a `$1` text timestamp
+    pub fn created_since_sql(&self) -> &'static str {
+        match self {
+            Self::Sqlite(_) => "datetime(created_at) >= datetime($1)",
+            #[cfg(feature = "postgres")]
+            Self::Postgres(_) => "created_at >= CAST($1 AS TIMESTAMP)",
+        }
+    }
+}
+
+fn is_postgres_url(url: &str) -> bool {
+    url.starts_with("postgres://") || url.starts_with("postgresql://")
+}
@@ -0,0 +1,29 @@
+use super::backend::with_pool;
+use super::Database;
+use crate::master::bench::BenchResult;
+use anyhow::{Context, Result};
+
+impl Database {
+    /// Save the result of one benchmark task
+    pub async fn save_bench_result(&self, result: &BenchResult) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "INSERT INTO bench_results (suite, model, task, success, tokens_used, execution_time_ms, score, variant)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            )
+            .bind(&result.suite)
+            .bind(&result.model)
+            .bind(&result.task)
+            .bind(result.success)
+            .bind(result.tokens_used as i64)
+            .bind((result.execution_time_secs * 1000.0) as i64)
+            .bind(result.score)
+            .bind(&result.variant)
+            .execute(pool)
+            .await
+            .context("Failed to save benchmark result")?;
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::secrets;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// Keyring entry holding the base64-encoded 256-bit database key
+const KEYRING_ENTRY: &str = "database-key";
+
+/// Prefix marking an encrypted column value. Values without it are treated
+/// as plaintext, so rows written before encryption was enabled stay readable.
+const PREFIX: &str = "enc:v1:";
+
+const NONCE_LEN: usize = 12;
+
+/// AES-256-GCM encryption for sensitive text columns
+/// (task descriptions, plans, results, agent tasks)
+pub struct FieldCipher {
+    cipher: Aes256Gcm,
+}
+
+impl FieldCipher {
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != 32 {
+            anyhow::bail!("Database key must be 32 bytes, got {}", key.len());
+        }
+
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        })
+    }
+
+    /// Load the database key from the OS keyring, generating and storing
+    /// a new one on first use
+    pub fn from_keyring() -> Result<Self> {
+        let key = match secrets::get_secret(KEYRING_ENTRY)? {
+            Some(encoded) => BASE64
+                .decode(encoded.trim())
+                .context("Database key in keyring is not valid base64")?,
+            None => {
+                let key = Aes256Gcm::generate_key(OsRng);
+                secrets::set_secret(KEYRING_ENTRY, &BASE64.encode(key))?;
+                key.to_vec()
+            }
+        };
+
+        Self::new(&key)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt value"))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+
+        Ok(format!("{}{}", PREFIX, BASE64.encode(payload)))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let Some(encoded) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let payload = BASE64
+            .decode(encoded)
+            .context("Encrypted value is not valid base64")?;
+
+        if payload.len() < NONCE_LEN {
+            anyhow::bail!("Encrypted value is truncated");
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt value (wrong database key?)"))?;
+
+        String::from_utf8(plaintext).context("Decrypted value is not valid UTF-8")
+    }
+}
+
+/// Whether a stored value was written by [`FieldCipher::encrypt`]
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> FieldCipher {
+        FieldCipher::new(&[7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let cipher = test_cipher();
+        let encrypted = cipher.encrypt("refactor the auth module").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("auth"));
+        assert_eq!(
+            cipher.decrypt(&encrypted).unwrap(),
+            "refactor the auth module"
+        );
+    }
+
+    #[test]
+    fn test_nonce_is_random() {
+        let cipher = test_cipher();
+        assert_ne!(
+            cipher.encrypt("same").unwrap(),
+            cipher.encrypt("same").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_plaintext_passthrough() {
+        let cipher = test_cipher();
+        assert_eq!(cipher.decrypt("legacy row").unwrap(), "legacy row");
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let encrypted = test_cipher().encrypt("secret").unwrap();
+        let other = FieldCipher::new(&[8u8; 32]).unwrap();
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_invalid_key_length() {
+        assert!(FieldCipher::new(&[0u8; 16]).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use super::backend::with_pool;
+use super::Database;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+impl Database {
+    /// Store the vector `model` gave the text with hash `text_hash`
+    pub async fn save_embedding(&self, model: &str, text_hash: &str, vector: &[f32]) -> Result<()> {
+        let vector = serde_json::to_string(vector)?;
+
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "INSERT INTO embeddings (model, text_hash, vector) VALUES ($1, $2, $3)
+                 ON CONFLICT (model, text_hash) DO NOTHING",
+            )
+            .bind(model)
+            .bind(text_hash)
+            .bind(&vector)
+            .execute(pool)
+            .await
+            .context("Failed to save embedding")?;
+        });
+
+        Ok(())
+    }
+
+    /// Every stored vector of `model`, by text hash
+    pub async fn get_embeddings(&self, model: &str) -> Result<HashMap<String, Vec<f32>>> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String)>(
+                "SELECT text_hash, vector FROM embeddings WHERE model = $1",
+            )
+            .bind(model)
+            .fetch_all(pool)
+            .await
+            .context("Failed to read embeddings")?
+        });
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(hash, vector)| Some((hash, serde_json::from_str(&vector).ok()?)))
+            .collect())
+    }
+}
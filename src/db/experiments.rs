@@ -0,0 +1,158 @@
+use super::backend::with_pool;
+use super::Database;
+use crate::agents::AgentCapability;
+use anyhow::Result;
+use serde::Serialize;
+
+/// Variant labels are kept as typed (e.g. `coder-v2`) but must be a single word
+pub fn normalize_variant(label: &str) -> Result<String> {
+    let label = label.trim();
+    if label.is_empty() || label.chars().any(char::is_whitespace) {
+        anyhow::bail!(
+            "Invalid variant '{}': variant labels must be a single word",
+            label
+        );
+    }
+    Ok(label.to_string())
+}
+
+/// How the runs labelled with one prompt variant went
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VariantStats {
+    pub variant: String,
+    pub runs: usize,
+    pub successful_runs: usize,
+    pub total_tokens: usize,
+    /// Review agents of those runs, and how many finished without error
+    pub review_agents: usize,
+    pub reviews_passed: usize,
+    /// Benchmark tasks run with the variant, and the sum of their rubric scores
+    pub bench_tasks: usize,
+    pub bench_score_total: f64,
+}
+
+impl VariantStats {
+    pub fn success_rate(&self) -> Option<f64> {
+        ratio(self.successful_runs as f64, self.runs)
+    }
+
+    pub fn avg_tokens(&self) -> Option<f64> {
+        ratio(self.total_tokens as f64, self.runs)
+    }
+
+    pub fn review_pass_rate(&self) -> Option<f64> {
+        ratio(self.reviews_passed as f64, self.review_agents)
+    }
+
+    /// Mean benchmark rubric score, 0.0-1.0
+    pub fn bench_score(&self) -> Option<f64> {
+        ratio(self.bench_score_total, self.bench_tasks)
+    }
+}
+
+fn ratio(part: f64, whole: usize) -> Option<f64> {
+    (whole > 0).then(|| part / whole as f64)
+}
+
+impl Database {
+    /// Totals of the runs and benchmark tasks labelled `variant`
+    pub async fn get_variant_stats(&self, variant: &str) -> Result<VariantStats> {
+        let (runs, successful_runs, total_tokens) = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (i64, i64, i64)>(
+                "SELECT
+                    COUNT(*),
+                    CAST(COALESCE(SUM(CASE WHEN success THEN 1 ELSE 0 END), 0) AS BIGINT),
+                    CAST(COALESCE(SUM(actual_tokens), 0) AS BIGINT)
+                 FROM task_executions
+                 WHERE variant = $1",
+            )
+            .bind(variant)
+            .fetch_one(pool)
+            .await?
+        });
+
+        let (review_agents, reviews_passed) = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (i64, i64)>(
+                "SELECT
+                    COUNT(*),
+                    CAST(COALESCE(SUM(CASE WHEN success THEN 1 ELSE 0 END), 0) AS BIGINT)
+                 FROM agent_executions
+                 WHERE capability = $1
+                   AND task_id IN (SELECT id FROM task_executions WHERE variant = $2)",
+            )
+            .bind(format!("{:?}", AgentCapability::Review))
+            .bind(variant)
+            .fetch_one(pool)
+            .await?
+        });
+
+        let (bench_tasks, bench_score_total) = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (i64, f64)>(
+                "SELECT COUNT(*), CAST(COALESCE(SUM(score), 0) AS DOUBLE PRECISION)
+                 FROM bench_results
+                 WHERE variant = $1",
+            )
+            .bind(variant)
+            .fetch_one(pool)
+            .await?
+        });
+
+        Ok(VariantStats {
+            variant: variant.to_string(),
+            runs: runs as usize,
+            successful_runs: successful_runs as usize,
+            total_tokens: total_tokens as usize,
+            review_agents: review_agents as usize,
+            reviews_passed: reviews_passed as usize,
+            bench_tasks: bench_tasks as usize,
+            bench_score_total,
+        })
+    }
+
+    /// Every variant label used so far with its number of runs, most used first
+    pub async fn get_variants(&self) -> Result<Vec<(String, usize)>> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, i64)>(
+                "SELECT variant, COUNT(*) FROM task_executions
+                 WHERE variant IS NOT NULL
+                 GROUP BY variant
+                 ORDER BY COUNT(*) DESC, variant",
+            )
+            .fetch_all(pool)
+            .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|(variant, runs)| (variant, runs as usize))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_variant() {
+        assert_eq!(normalize_variant(" Coder-v2 ").unwrap(), "Coder-v2");
+        assert!(normalize_variant("").is_err());
+        assert!(normalize_variant("shorter prompt").is_err());
+    }
+
+    #[test]
+    fn test_variant_rates() {
+        let stats = VariantStats {
+            runs: 4,
+            successful_runs: 3,
+            total_tokens: 10_000,
+            bench_tasks: 2,
+            bench_score_total: 1.5,
+            ..Default::default()
+        };
+        assert_eq!(stats.success_rate(), Some(0.75));
+        assert_eq!(stats.avg_tokens(), Some(2500.0));
+        assert_eq!(stats.review_pass_rate(), None);
+        assert_eq!(stats.bench_score(), Some(0.75));
+    }
+}
@@ -0,0 +1,174 @@
+use super::backend::with_pool;
+use super::{AgentHistoryEntry, AgentHistoryRow, Database};
+use crate::agents::AgentCapability;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+
+/// Conditions for `agents history`. Empty fields match everything.
+#[derive(Debug, Default, Clone)]
+pub struct AgentHistoryFilter {
+    pub capability: Option<AgentCapability>,
+    /// Only failed executions
+    pub failed: bool,
+    pub since: Option<DateTime<Utc>>,
+    /// Case-insensitive substring of the agent's task
+    pub task_contains: Option<String>,
+    /// Only agents of runs with this tag
+    pub tag: Option<String>,
+}
+
+impl Database {
+    /// Agent executions matching `filter`, newest first
+    pub async fn get_agent_history_filtered(
+        &self,
+        filter: &AgentHistoryFilter,
+        limit: usize,
+    ) -> Result<Vec<AgentHistoryEntry>> {
+        let mut conditions = Vec::new();
+        let mut binds = Vec::new();
+
+        // created_since_sql() always refers to $1, so it has to come first
+        if let Some(since) = filter.since {
+            conditions.push(self.pool.created_since_sql().to_string());
+            binds.push(since.format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+        if let Some(capability) = &filter.capability {
+            binds.push(format!("{:?}", capability));
+            conditions.push(format!("capability = ${}", binds.len()));
+        }
+        if filter.failed {
+            conditions.push("NOT success".to_string());
+        }
+        if let Some(tag) = &filter.tag {
+            binds.push(tag.clone());
+            conditions.push(format!(
+                "task_id IN (SELECT task_id FROM task_tags WHERE tag = ${})",
+                binds.len()
+            ));
+        }
+
+        // Encrypted task text can only be searched after decrypting it, so
+        // then the filter is applied to every row that matches the rest
+        let search = filter.task_contains.as_deref().map(str::to_lowercase);
+        let search_in_sql = self.cipher.is_none();
+        if let (Some(search), true) = (&search, search_in_sql) {
+            binds.push(format!("%{}%", escape_like(search)));
+            conditions.push(format!("LOWER(task) LIKE ${} ESCAPE '\\'", binds.len()));
+        }
+
+        let mut query = String::from(
+            "SELECT agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success, CAST(created_at AS TEXT)
+             FROM agent_executions",
+        );
+        if !conditions.is_empty() {
+            query.push_str(&format!(
+                "\n             WHERE {}",
+                conditions.join(" AND ")
+            ));
+        }
+        query.push_str("\n             ORDER BY created_at DESC, id DESC");
+        let limit_in_sql = search.is_none() || search_in_sql;
+        if limit_in_sql {
+            query.push_str(&format!("\n             LIMIT ${}", binds.len() + 1));
+        }
+
+        let rows = with_pool!(&self.pool, |pool| {
+            let mut rows = sqlx::query_as::<_, AgentHistoryRow>(&query);
+            for value in &binds {
+                rows = rows.bind(value);
+            }
+            if limit_in_sql {
+                rows = rows.bind(limit as i64);
+            }
+            rows.fetch_all(pool).await?
+        });
+
+        let history = rows.into_iter().map(|row| self.history_entry(row));
+
+        Ok(match search.filter(|_| !search_in_sql) {
+            Some(search) => history
+                .filter(|entry| entry.task.to_lowercase().contains(&search))
+                .take(limit)
+                .collect(),
+            None => history.collect(),
+        })
+    }
+}
+
+/// Parse a `--since` argument: a relative age such as `30m`, `24h`, `7d`
+/// or `2w`, or an absolute `YYYY-MM-DD` date or RFC 3339 timestamp
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    parse_time(value, |age| Utc::now() - age)
+}
+
+/// Parse an `--until`-style argument: a time from now such as `7d`, or an
+/// absolute date or timestamp as for [`parse_since`]
+pub fn parse_until(value: &str) -> Result<DateTime<Utc>> {
+    parse_time(value, |age| Utc::now() + age)
+}
+
+fn parse_time(value: &str, relative: impl Fn(Duration) -> DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+
+    let split = value.len() - value.chars().last().map_or(0, char::len_utf8);
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid time: {} (use e.g. 24h, 7d or 2026-10-01)", value))?;
+    let age = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => anyhow::bail!("Invalid time unit in {} (use m, h, d or w)", value),
+    };
+
+    Ok(relative(age))
+}
+
+/// Escape LIKE wildcards so the search matches them literally
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_done"), "100\\%\\_done");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let age = Utc::now() - parse_since("24h").unwrap();
+        assert!((age - Duration::hours(24)).num_seconds().abs() < 5);
+
+        assert_eq!(
+            parse_since("2026-10-01").unwrap().to_rfc3339(),
+            "2026-10-01T00:00:00+00:00"
+        );
+        assert!(parse_since("7x").is_err());
+        assert!(parse_since("soon").is_err());
+    }
+
+    #[test]
+    fn test_parse_until() {
+        let ahead = parse_until("7d").unwrap() - Utc::now();
+        assert!((ahead - Duration::days(7)).num_seconds().abs() < 5);
+        assert_eq!(
+            parse_until("2026-10-01").unwrap(),
+            parse_since("2026-10-01").unwrap()
+        );
+    }
+}
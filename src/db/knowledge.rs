@@ -0,0 +1,104 @@
+use super::backend::with_pool;
+use super::{parse_timestamp, Database};
+use crate::knowledge::{KnowledgeEntry, KnowledgeKind};
+use anyhow::{Context, Result};
+
+type KnowledgeRow = (i64, String, String, String, String, bool, String);
+
+impl Database {
+    /// Add a knowledge base entry to `project` and return its ID
+    pub async fn add_knowledge(
+        &self,
+        project: &str,
+        kind: KnowledgeKind,
+        content: &str,
+        source: &str,
+        proposed: bool,
+    ) -> Result<i64> {
+        let content = self.seal(content)?;
+
+        let id = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, i64>(
+                "INSERT INTO knowledge_entries (project, kind, content, source, proposed)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING id",
+            )
+            .bind(project)
+            .bind(kind.as_str())
+            .bind(&content)
+            .bind(source)
+            .bind(proposed)
+            .fetch_one(pool)
+            .await
+            .context("Failed to save knowledge base entry")?
+        });
+
+        Ok(id)
+    }
+
+    /// Every entry of `project`, proposals included, newest first
+    pub async fn get_knowledge(&self, project: &str) -> Result<Vec<KnowledgeEntry>> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, KnowledgeRow>(
+                "SELECT id, project, kind, content, source, proposed, CAST(created_at AS TEXT)
+                 FROM knowledge_entries
+                 WHERE project = $1
+                 ORDER BY id DESC",
+            )
+            .bind(project)
+            .fetch_all(pool)
+            .await
+            .context("Failed to read the knowledge base")?
+        });
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(KnowledgeEntry {
+                    id: row.0,
+                    project: row.1,
+                    kind: KnowledgeKind::from_str(&row.2).ok()?,
+                    content: self.open(row.3),
+                    source: row.4,
+                    proposed: row.5,
+                    created_at: parse_timestamp(&row.6),
+                })
+            })
+            .collect())
+    }
+
+    /// Turn a proposed entry of `project` into a regular one. Returns false
+    /// when there is no such proposal.
+    pub async fn accept_knowledge(&self, project: &str, id: i64) -> Result<bool> {
+        let updated = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE knowledge_entries SET proposed = FALSE
+                 WHERE id = $1 AND project = $2 AND proposed",
+            )
+            .bind(id)
+            .bind(project)
+            .execute(pool)
+            .await
+            .context("Failed to accept knowledge base entry")?
+            .rows_affected()
+        });
+
+        Ok(updated > 0)
+    }
+
+    /// Delete an entry (or proposal) of `project`. Returns false when there
+    /// is no such entry.
+    pub async fn remove_knowledge(&self, project: &str, id: i64) -> Result<bool> {
+        let deleted = with_pool!(&self.pool, |pool| {
+            sqlx::query("DELETE FROM knowledge_entries WHERE id = $1 AND project = $2")
+                .bind(id)
+                .bind(project)
+                .execute(pool)
+                .await
+                .context("Failed to remove knowledge base entry")?
+                .rows_affected()
+        });
+
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,179 @@
+use super::backend::with_pool;
+use super::Database;
+use crate::agents::AgentCapability;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+/// What `agents top` ranks agent types by, best first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgentRanking {
+    /// Fewest tokens per run
+    Tokens,
+    /// Shortest time per run
+    Time,
+    /// Highest success rate
+    Success,
+}
+
+impl AgentRanking {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "tokens" => Ok(Self::Tokens),
+            "time" => Ok(Self::Time),
+            "success" => Ok(Self::Success),
+            _ => anyhow::bail!("Invalid ranking: {} (use tokens, time or success)", s),
+        }
+    }
+
+    fn order_sql(&self) -> &'static str {
+        match self {
+            Self::Tokens => "AVG(tokens_used) ASC",
+            Self::Time => "AVG(execution_time_ms) ASC",
+            Self::Success => {
+                "SUM(CASE WHEN success THEN 1 ELSE 0 END) * 1.0 / COUNT(*) DESC, COUNT(*) DESC"
+            }
+        }
+    }
+}
+
+/// Aggregate performance of one agent type
+pub struct AgentTypeStats {
+    pub agent_type: String,
+    pub capability: AgentCapability,
+    pub executions: usize,
+    pub successful: usize,
+    pub avg_tokens: usize,
+    pub avg_time_secs: f64,
+}
+
+impl AgentTypeStats {
+    pub fn success_rate(&self) -> f64 {
+        success_rate(self.successful, self.executions)
+    }
+}
+
+/// Runs of one capability within one week
+pub struct CapabilityTrend {
+    /// Monday starting the week, `YYYY-MM-DD`
+    pub week: String,
+    pub capability: AgentCapability,
+    pub executions: usize,
+    pub successful: usize,
+}
+
+impl CapabilityTrend {
+    pub fn success_rate(&self) -> f64 {
+        success_rate(self.successful, self.executions)
+    }
+}
+
+pub(super) fn success_rate(successful: usize, executions: usize) -> f64 {
+    if executions == 0 {
+        return 0.0;
+    }
+    successful as f64 / executions as f64 * 100.0
+}
+
+impl Database {
+    /// Agent types ranked by `by`, best first
+    pub async fn get_agent_leaderboard(
+        &self,
+        by: AgentRanking,
+        limit: usize,
+    ) -> Result<Vec<AgentTypeStats>> {
+        let query = format!(
+            "SELECT
+                agent_type,
+                capability,
+                COUNT(*),
+                CAST(SUM(CASE WHEN success THEN 1 ELSE 0 END) AS BIGINT),
+                CAST(AVG(tokens_used) AS BIGINT),
+                CAST(AVG(execution_time_ms) AS BIGINT)
+             FROM agent_executions
+             GROUP BY agent_type, capability
+             ORDER BY {}, agent_type
+             LIMIT $1",
+            by.order_sql()
+        );
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String, i64, i64, i64, i64)>(&query)
+                .bind(limit as i64)
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AgentTypeStats {
+                agent_type: row.0,
+                capability: AgentCapability::from_str(&row.1)
+                    .unwrap_or(AgentCapability::CodeWriting),
+                executions: row.2 as usize,
+                successful: row.3 as usize,
+                avg_tokens: row.4 as usize,
+                avg_time_secs: row.5 as f64 / 1000.0,
+            })
+            .collect())
+    }
+
+    /// Weekly run counts and successes per capability since `since`,
+    /// oldest week first
+    pub async fn get_capability_trends(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<CapabilityTrend>> {
+        let query = format!(
+            "SELECT
+                {} AS week,
+                capability,
+                COUNT(*),
+                CAST(SUM(CASE WHEN success THEN 1 ELSE 0 END) AS BIGINT)
+             FROM agent_executions
+             WHERE {}
+             GROUP BY week, capability
+             ORDER BY week, capability",
+            self.pool.week_bucket_sql(),
+            self.pool.created_since_sql()
+        );
+        let since = since.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String, i64, i64)>(&query)
+                .bind(&since)
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(CapabilityTrend {
+                    week: row.0,
+                    capability: AgentCapability::from_str(&row.1)?,
+                    executions: row.2 as usize,
+                    successful: row.3 as usize,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_ranking_from_str() {
+        assert_eq!(
+            AgentRanking::from_str("Tokens").unwrap(),
+            AgentRanking::Tokens
+        );
+        assert_eq!(
+            AgentRanking::from_str("success").unwrap(),
+            AgentRanking::Success
+        );
+        assert!(AgentRanking::from_str("cost").is_err());
+    }
+}
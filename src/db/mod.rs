@@ -1,18 +1,55 @@
+mod archive;
+mod audit;
+mod backend;
+mod bench;
+mod crypto;
+mod embeddings;
+mod experiments;
+mod history;
+mod knowledge;
+mod leaderboard;
+mod query;
+mod queue;
+mod regressions;
+mod rollup;
 mod schema;
+mod tags;
 
-use crate::agents::AgentCapability;
-use crate::analyzer::Optimization;
+use crate::agents::{AgentCapability, DEFAULT_MODEL};
+use crate::analyzer::{Optimization, OptimizationType};
 use crate::config::Config;
+use crate::error::ClaudeHelperError;
 use crate::master::orchestrator::{ExecutionPlan, ExecutionResult};
 use crate::master::planner::TaskAnalysis;
+use crate::project::Project;
+use crate::redact::Redactor;
 use anyhow::{Context, Result};
+use backend::{with_pool, DbPool};
 use chrono::{DateTime, Utc};
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-
+use crypto::FieldCipher;
+use rollup::RECORD_USAGE_SQL;
+use serde::Serialize;
+use std::collections::HashSet;
+use tags::INSERT_TAG_SQL;
+
+pub use archive::{ArchivedAgent, ArchivedTask};
+pub use audit::{ApiCall, ApiCallEntry};
+pub use experiments::{normalize_variant, VariantStats};
+pub use history::{parse_since, parse_until, AgentHistoryFilter};
+pub use leaderboard::{AgentRanking, AgentTypeStats, CapabilityTrend};
+pub use query::{validate_select, QueryResult};
+pub use regressions::{
+    find_regressions, rolling_rates, CapabilityRegression, CapabilityRun, RollingRate,
+    ROLLING_WINDOW,
+};
+pub use rollup::{estimate_cost, DailyUsage};
 pub use schema::*;
+pub use tags::normalize_tag;
 
 pub struct Database {
-    pool: SqlitePool,
+    pool: DbPool,
+    cipher: Option<FieldCipher>,
+    redactor: Redactor,
 }
 
 impl Database {
@@ -23,28 +60,83 @@ impl Database {
     // - Test schema migration on existing database
     // - Test concurrent database initialization
 
-    pub async fn new(_config: &Config) -> Result<Self> {
-        let db_path = Config::db_file()?;
-        let db_url = format!("sqlite://{}", db_path.display());
-
-        // Create pool with create_if_missing option
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect_with(
-                db_url
-                    .parse::<sqlx::sqlite::SqliteConnectOptions>()?
-                    .create_if_missing(true),
-            )
+    /// Connect to the configured database: the local SQLite file by default,
+    /// or a shared Postgres instance when `database.url` is set
+    pub async fn new(config: &Config) -> crate::error::Result<Self> {
+        let pool = DbPool::connect(config)
             .await
-            .context("Failed to connect to database")?;
+            .map_err(ClaudeHelperError::db)?;
+        let cipher = Self::load_cipher(config).map_err(ClaudeHelperError::db)?;
+        let redactor =
+            Redactor::from_config(&config.redaction).map_err(ClaudeHelperError::config)?;
+
+        Ok(Self {
+            pool,
+            cipher,
+            redactor,
+        })
+    }
 
-        // Run migrations
-        sqlx::query(schema::CREATE_TABLES)
-            .execute(&pool)
+    /// Open a read-only connection for ad-hoc queries.
+    /// The database must already exist; no migrations are run.
+    pub async fn new_read_only(config: &Config) -> crate::error::Result<Self> {
+        let pool = DbPool::connect_read_only(config)
             .await
-            .context("Failed to create database tables")?;
+            .map_err(ClaudeHelperError::db)?;
+
+        Ok(Self {
+            pool,
+            cipher: None,
+            redactor: Redactor::default(),
+        })
+    }
+
+    fn load_cipher(config: &Config) -> Result<Option<FieldCipher>> {
+        if !config.database.encrypt_sensitive {
+            return Ok(None);
+        }
+
+        let cipher = FieldCipher::from_keyring().context(
+            "Database encryption is enabled but the key could not be loaded from the OS keyring",
+        )?;
+
+        Ok(Some(cipher))
+    }
+
+    /// Redact secrets from a sensitive column value, then encrypt it if
+    /// encryption is enabled
+    fn seal(&self, value: &str) -> Result<String> {
+        let value = self.redactor.redact(value);
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(&value),
+            None => Ok(value.into_owned()),
+        }
+    }
+
+    /// Decrypt a sensitive column value. Encrypted values read without a key
+    /// are shown as a placeholder rather than failing the whole query.
+    fn open(&self, value: String) -> String {
+        match &self.cipher {
+            Some(cipher) => cipher
+                .decrypt(&value)
+                .unwrap_or_else(|_| "<undecryptable>".to_string()),
+            None if crypto::is_encrypted(&value) => "<encrypted>".to_string(),
+            None => value,
+        }
+    }
+
+    /// Run an ad-hoc SELECT query and return its rows as strings
+    pub async fn query(&self, sql: &str) -> Result<QueryResult> {
+        let sql = validate_select(sql)?;
 
-        Ok(Self { pool })
+        with_pool!(&self.pool, |pool| {
+            let rows = sqlx::query(sql)
+                .fetch_all(pool)
+                .await
+                .context("Query failed")?;
+
+            QueryResult::from_rows(&rows)
+        })
     }
 
     /// Save a task execution for learning
@@ -55,34 +147,112 @@ impl Database {
     // - Test with invalid JSON in analysis/plan
     // - Test concurrent saves (race conditions)
     // - Test transaction rollback on error
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_task_execution(
         &self,
         task: &str,
         analysis: &TaskAnalysis,
         plan: &ExecutionPlan,
         result: &ExecutionResult,
-    ) -> Result<()> {
-        let task_json = serde_json::to_string(analysis)?;
-        let plan_json = serde_json::to_string(plan)?;
-        let result_json = serde_json::to_string(result)?;
-
-        sqlx::query(
-            "INSERT INTO task_executions (task_description, complexity, estimated_tokens, actual_tokens, success, task_data, plan_data, result_data)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
-        )
-        .bind(task)
-        .bind(analysis.complexity as i32)
-        .bind(analysis.estimated_tokens as i64)
-        .bind(result.tokens_used as i64)
-        .bind(result.success)
-        .bind(task_json)
-        .bind(plan_json)
-        .bind(result_json)
-        .execute(&self.pool)
-        .await
-        .context("Failed to save task execution")?;
+        tags: &[String],
+        variant: Option<&str>,
+        project: Option<&Project>,
+        run_id: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<i64> {
+        let sealed_task = self.seal(task)?;
+        let task_json = self.seal(&serde_json::to_string(analysis)?)?;
+        let plan_json = self.seal(&serde_json::to_string(plan)?)?;
+        let result_json = self.seal(&serde_json::to_string(result)?)?;
+
+        let specs = plan.phases.iter().flat_map(|phase| &phase.agents);
+        let mut agents = Vec::new();
+        for outcome in &result.agents {
+            let Some(spec) = specs.clone().find(|spec| spec.id == outcome.agent_id) else {
+                continue;
+            };
+            let prompt_version = outcome
+                .system_prompt
+                .as_deref()
+                .map(|prompt| crate::master::prompt_version(prompt, &outcome.agent_type));
+            agents.push((
+                outcome,
+                format!("{:?}", spec.capability),
+                self.seal(&spec.task)?,
+                prompt_version,
+            ));
+        }
 
-        Ok(())
+        // One transaction, so a failed save leaves neither a task without
+        // its agents nor usage counted twice when the run is saved again
+        let task_id = with_pool!(&self.pool, |pool| {
+            let mut tx = pool.begin().await?;
+
+            let task_id = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO task_executions (task_description, complexity, estimated_tokens, actual_tokens, success, task_data, plan_data, result_data, variant, project, git_remote, run_id, model, cost_usd)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                 RETURNING id"
+            )
+            .bind(&sealed_task)
+            .bind(analysis.complexity as i64)
+            .bind(analysis.estimated_tokens as i64)
+            .bind(result.tokens_used as i64)
+            .bind(result.success)
+            .bind(&task_json)
+            .bind(&plan_json)
+            .bind(&result_json)
+            .bind(variant)
+            .bind(project.map(|p| p.root.as_str()))
+            .bind(project.and_then(|p| p.remote.as_deref()))
+            .bind(run_id)
+            .bind(model)
+            .bind(result.cost_usd)
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to save task execution")?;
+
+            for (outcome, capability, agent_task, prompt_version) in &agents {
+                sqlx::query(INSERT_AGENT_SQL)
+                    .bind(&outcome.agent_id)
+                    .bind(&outcome.agent_type)
+                    .bind(capability)
+                    .bind(agent_task)
+                    .bind(outcome.tokens_used as i64)
+                    .bind((outcome.execution_time_secs * 1000.0) as i64)
+                    .bind(outcome.error.is_none())
+                    .bind(task_id)
+                    .bind(model)
+                    .bind(prompt_version)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to save agent execution")?;
+            }
+
+            for tag in tags {
+                sqlx::query(INSERT_TAG_SQL)
+                    .bind(task_id)
+                    .bind(tag)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to save task tag")?;
+            }
+
+            sqlx::query(RECORD_USAGE_SQL)
+                .bind(rollup::today())
+                .bind(project.map_or("", |p| p.root.as_str()))
+                .bind(model.unwrap_or(DEFAULT_MODEL))
+                .bind(if result.success { 1i64 } else { 0i64 })
+                .bind(result.tokens_used as i64)
+                .bind(result.cost_usd)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update daily usage rollup")?;
+
+            tx.commit().await?;
+            task_id
+        });
+
+        Ok(task_id)
     }
 
     /// Get agent statistics
@@ -94,25 +264,62 @@ impl Database {
     // - Test with NULL values in tokens_used
     // - Test with negative values (should never happen but handle gracefully)
     pub async fn get_agent_stats(&self) -> Result<AgentStats> {
-        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM agent_executions")
-            .fetch_one(&self.pool)
-            .await?;
-
-        let successful: i64 =
-            sqlx::query_scalar("SELECT COUNT(*) FROM agent_executions WHERE success = 1")
-                .fetch_one(&self.pool)
-                .await?;
-
-        let total_tokens: i64 =
-            sqlx::query_scalar("SELECT COALESCE(SUM(tokens_used), 0) FROM agent_executions")
-                .fetch_one(&self.pool)
-                .await?;
-
-        let total_time: f64 =
-            sqlx::query_scalar("SELECT COALESCE(SUM(execution_time_ms), 0) FROM agent_executions")
-                .fetch_one(&self.pool)
-                .await
-                .map(|ms: i64| ms as f64 / 1000.0)?;
+        self.agent_stats(None, None).await
+    }
+
+    /// Get agent statistics for executions since `since`, optionally only
+    /// those of runs tagged `tag`
+    pub async fn get_agent_stats_since(
+        &self,
+        since: DateTime<Utc>,
+        tag: Option<&str>,
+    ) -> Result<AgentStats> {
+        self.agent_stats(Some(since), tag).await
+    }
+
+    async fn agent_stats(
+        &self,
+        since: Option<DateTime<Utc>>,
+        tag: Option<&str>,
+    ) -> Result<AgentStats> {
+        let since = since.map(|since| since.format("%Y-%m-%d %H:%M:%S").to_string());
+        let mut conditions = Vec::new();
+        if since.is_some() {
+            conditions.push(self.pool.created_since_sql().to_string());
+        }
+        if tag.is_some() {
+            conditions.push(format!(
+                "task_id IN (SELECT task_id FROM task_tags WHERE tag = ${})",
+                conditions.len() + 1
+            ));
+        }
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let totals_query = format!(
+            "SELECT
+                COUNT(*),
+                CAST(COALESCE(SUM(CASE WHEN success THEN 1 ELSE 0 END), 0) AS BIGINT),
+                CAST(COALESCE(SUM(tokens_used), 0) AS BIGINT),
+                CAST(COALESCE(SUM(execution_time_ms), 0) AS BIGINT)
+             FROM agent_executions
+             {}",
+            filter
+        );
+        let (total, successful, total_tokens, total_time_ms) = with_pool!(&self.pool, |pool| {
+            let mut query = sqlx::query_as::<_, (i64, i64, i64, i64)>(&totals_query);
+            if let Some(since) = &since {
+                query = query.bind(since);
+            }
+            if let Some(tag) = tag {
+                query = query.bind(tag);
+            }
+            query.fetch_one(pool).await?
+        });
+        let total_time = total_time_ms as f64 / 1000.0;
 
         let avg_tokens = if total > 0 { total_tokens / total } else { 0 };
         let avg_time = if total > 0 {
@@ -122,13 +329,23 @@ impl Database {
         };
 
         // Get capability breakdown
-        let capability_rows = sqlx::query_as::<_, (String, i64)>(
+        let capability_query = format!(
             "SELECT capability, COUNT(*) as count
              FROM agent_executions
+             {}
              GROUP BY capability",
-        )
-        .fetch_all(&self.pool)
-        .await?;
+            filter
+        );
+        let capability_rows = with_pool!(&self.pool, |pool| {
+            let mut query = sqlx::query_as::<_, (String, i64)>(&capability_query);
+            if let Some(since) = &since {
+                query = query.bind(since);
+            }
+            if let Some(tag) = tag {
+                query = query.bind(tag);
+            }
+            query.fetch_all(pool).await?
+        });
 
         let mut by_capability = std::collections::HashMap::new();
         for (capability_str, count) in capability_rows {
@@ -150,43 +367,63 @@ impl Database {
 
     /// Get agent execution history
     pub async fn get_agent_history(&self, limit: usize) -> Result<Vec<AgentHistoryEntry>> {
-        let rows = sqlx::query_as::<_, (String, String, String, String, i64, i64, bool, String)>(
-            "SELECT agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success, created_at
-             FROM agent_executions
-             ORDER BY created_at DESC
-             LIMIT ?",
-        )
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
-        .await?;
+        self.get_agent_history_page(limit, 0).await
+    }
 
-        let mut history = Vec::new();
+    /// Count stored agent executions
+    pub async fn count_agent_executions(&self) -> Result<usize> {
+        let count = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM agent_executions")
+                .fetch_one(pool)
+                .await?
+        });
 
-        for row in rows {
-            let timestamp = DateTime::parse_from_rfc3339(&row.7)
-                .unwrap_or_else(|_| Utc::now().into())
-                .with_timezone(&Utc);
-
-            // Parse capability from database, fallback to CodeWriting for unknown values
-            let capability =
-                AgentCapability::from_str(&row.2).unwrap_or(AgentCapability::CodeWriting);
-
-            history.push(AgentHistoryEntry {
-                agent_id: row.0,
-                agent_type: row.1,
-                capability,
-                task: row.3,
-                tokens_used: row.4 as usize,
-                execution_time_secs: row.5 as f64 / 1000.0,
-                success: row.6,
-                timestamp,
-            });
-        }
+        Ok(count as usize)
+    }
 
-        Ok(history)
+    /// Get one page of agent execution history, newest first
+    pub async fn get_agent_history_page(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<AgentHistoryEntry>> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, AgentHistoryRow>(
+                "SELECT agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success, CAST(created_at AS TEXT)
+                 FROM agent_executions
+                 ORDER BY created_at DESC, id DESC
+                 LIMIT $1 OFFSET $2",
+            )
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(pool)
+            .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| self.history_entry(row))
+            .collect())
+    }
+
+    fn history_entry(&self, row: AgentHistoryRow) -> AgentHistoryEntry {
+        // Parse capability from database, fallback to CodeWriting for unknown values
+        let capability = AgentCapability::from_str(&row.2).unwrap_or(AgentCapability::CodeWriting);
+
+        AgentHistoryEntry {
+            agent_id: row.0,
+            agent_type: row.1,
+            capability,
+            task: self.open(row.3),
+            tokens_used: row.4 as usize,
+            execution_time_secs: row.5 as f64 / 1000.0,
+            success: row.6,
+            timestamp: parse_timestamp(&row.7),
+        }
     }
 
     /// Save an agent execution
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_agent_execution(
         &self,
         agent_id: &str,
@@ -196,23 +433,29 @@ impl Database {
         tokens_used: usize,
         execution_time_ms: u64,
         success: bool,
+        task_id: Option<i64>,
+        model: Option<&str>,
+        prompt_version: Option<&str>,
     ) -> Result<()> {
         let capability_str = format!("{:?}", capability);
-
-        sqlx::query(
-            "INSERT INTO agent_executions (agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success)
-             VALUES (?, ?, ?, ?, ?, ?, ?)"
-        )
-        .bind(agent_id)
-        .bind(agent_type)
-        .bind(capability_str)
-        .bind(task)
-        .bind(tokens_used as i64)
-        .bind(execution_time_ms as i64)
-        .bind(success)
-        .execute(&self.pool)
-        .await
-        .context("Failed to save agent execution")?;
+        let task = self.seal(task)?;
+
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(INSERT_AGENT_SQL)
+                .bind(agent_id)
+                .bind(agent_type)
+                .bind(&capability_str)
+                .bind(&task)
+                .bind(tokens_used as i64)
+                .bind(execution_time_ms as i64)
+                .bind(success)
+                .bind(task_id)
+                .bind(model)
+                .bind(prompt_version)
+                .execute(pool)
+                .await
+                .context("Failed to save agent execution")?;
+        });
 
         Ok(())
     }
@@ -225,19 +468,25 @@ impl Database {
         let threshold = Utc::now() - chrono::Duration::hours(hours_i64);
         let threshold_str = threshold.format("%Y-%m-%d %H:%M:%S").to_string();
 
-        let query = "SELECT
-                strftime('%Y-%m-%d %H:00:00', created_at) as hour,
+        let query = format!(
+            "SELECT
+                {} as hour,
                 COUNT(*) as task_count,
-                COALESCE(SUM(tokens_used), 0) as total_tokens
+                CAST(COALESCE(SUM(tokens_used), 0) AS BIGINT) as total_tokens
              FROM agent_executions
-             WHERE datetime(created_at) >= datetime(?)
+             WHERE {}
              GROUP BY hour
-             ORDER BY hour DESC";
-
-        let rows = sqlx::query_as::<_, (String, i64, i64)>(query)
-            .bind(&threshold_str)
-            .fetch_all(&self.pool)
-            .await?;
+             ORDER BY hour DESC",
+            self.pool.hour_bucket_sql(),
+            self.pool.created_since_sql()
+        );
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, i64, i64)>(&query)
+                .bind(&threshold_str)
+                .fetch_all(pool)
+                .await?
+        });
 
         let mut breakdown = Vec::new();
         for row in rows {
@@ -256,27 +505,63 @@ impl Database {
         Ok(breakdown)
     }
 
-    /// Get recent task executions summary
-    pub async fn get_recent_tasks(&self, limit: usize) -> Result<Vec<TaskSummary>> {
-        let rows = sqlx::query_as::<_, (i64, String, i64, bool, String)>(
-            "SELECT id, task_description, actual_tokens, success, created_at
+    /// Get recent task executions summary, optionally only those tagged `tag`
+    /// Recent task executions, optionally only those tagged `tag` or run
+    /// in `project` (its root, or any clone of its remote)
+    pub async fn get_recent_tasks(
+        &self,
+        limit: usize,
+        tag: Option<&str>,
+        project: Option<&Project>,
+    ) -> Result<Vec<TaskSummary>> {
+        let mut binds: Vec<&str> = Vec::new();
+        let mut filters = Vec::new();
+        if let Some(tag) = tag {
+            binds.push(tag);
+            filters.push(format!(
+                "id IN (SELECT task_id FROM task_tags WHERE tag = ${})",
+                binds.len() + 1
+            ));
+        }
+        if let Some(project) = project {
+            binds.push(&project.root);
+            let mut filter = format!("(project = ${}", binds.len() + 1);
+            if let Some(remote) = &project.remote {
+                binds.push(remote);
+                filter.push_str(&format!(" OR git_remote = ${}", binds.len() + 1));
+            }
+            filter.push(')');
+            filters.push(filter);
+        }
+        let filter = match filters.is_empty() {
+            true => String::new(),
+            false => format!("WHERE {}", filters.join(" AND ")),
+        };
+        let query = format!(
+            "SELECT id, task_description, actual_tokens, success, CAST(created_at AS TEXT)
              FROM task_executions
+             {}
              ORDER BY created_at DESC
-             LIMIT ?",
-        )
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
-        .await?;
+             LIMIT $1",
+            filter
+        );
+
+        let rows = with_pool!(&self.pool, |pool| {
+            let mut rows =
+                sqlx::query_as::<_, (i64, String, i64, bool, String)>(&query).bind(limit as i64);
+            for value in &binds {
+                rows = rows.bind(*value);
+            }
+            rows.fetch_all(pool).await?
+        });
 
         let mut tasks = Vec::new();
         for row in rows {
-            let timestamp = DateTime::parse_from_rfc3339(&row.4)
-                .unwrap_or_else(|_| Utc::now().into())
-                .with_timezone(&Utc);
+            let timestamp = parse_timestamp(&row.4);
 
             tasks.push(TaskSummary {
                 id: row.0 as usize,
-                description: row.1,
+                description: self.open(row.1),
                 tokens_used: row.2 as usize,
                 success: row.3,
                 timestamp,
@@ -286,28 +571,322 @@ impl Database {
         Ok(tasks)
     }
 
-    /// Save an optimization suggestion to the database
-    pub async fn save_optimization(&self, opt: &Optimization) -> Result<()> {
+    /// Get recent task executions with the planner's estimate next to what
+    /// the run actually took
+    pub async fn get_task_outcomes(&self, limit: usize) -> Result<Vec<TaskOutcome>> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (i64, String, i64, i64, bool, String, String)>(
+                "SELECT id, task_description, estimated_tokens, actual_tokens, success, result_data, CAST(created_at AS TEXT)
+                 FROM task_executions
+                 ORDER BY created_at DESC
+                 LIMIT $1",
+            )
+            .bind(limit as i64)
+            .fetch_all(pool)
+            .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TaskOutcome {
+                id: row.0 as usize,
+                description: self.open(row.1),
+                estimated_tokens: row.2 as usize,
+                actual_tokens: row.3 as usize,
+                success: row.4,
+                execution_time_secs: serde_json::from_str::<ExecutionResult>(&self.open(row.5))
+                    .ok()
+                    .map(|result| result.execution_time_secs),
+                timestamp: parse_timestamp(&row.6),
+            })
+            .collect())
+    }
+
+    /// Get one task execution with its stored analysis, plan and result
+    pub async fn get_task_execution(&self, id: usize) -> Result<Option<TaskRecord>> {
+        let row = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (i64, String, i64, i64, bool, String, String, String, Option<String>, Option<String>, Option<String>, String)>(
+                "SELECT id, task_description, estimated_tokens, actual_tokens, success, task_data, plan_data, result_data, variant, project, run_id, CAST(created_at AS TEXT)
+                 FROM task_executions
+                 WHERE id = $1",
+            )
+            .bind(id as i64)
+            .fetch_optional(pool)
+            .await?
+        });
+
+        Ok(row.map(|row| TaskRecord {
+            id: row.0 as usize,
+            description: self.open(row.1),
+            estimated_tokens: row.2 as usize,
+            actual_tokens: row.3 as usize,
+            success: row.4,
+            analysis: serde_json::from_str(&self.open(row.5)).ok(),
+            plan: serde_json::from_str(&self.open(row.6)).ok(),
+            result: serde_json::from_str(&self.open(row.7)).ok(),
+            variant: row.8,
+            project: row.9,
+            run_id: row.10,
+            timestamp: parse_timestamp(&row.11),
+        }))
+    }
+
+    /// Save an optimization suggestion to the database, returning its ID
+    pub async fn save_optimization(&self, opt: &Optimization) -> Result<i64> {
         let opt_type = format!("{:?}", opt.opt_type);
         let examples_json = serde_json::to_string(&opt.examples)?;
 
-        sqlx::query(
-            "INSERT INTO optimizations (optimization_type, title, description, estimated_savings, examples, applied)
-             VALUES (?, ?, ?, ?, ?, 0)"
-        )
-        .bind(opt_type)
-        .bind(&opt.title)
-        .bind(&opt.description)
-        .bind(opt.estimated_savings as i64)
-        .bind(examples_json)
-        .execute(&self.pool)
-        .await
-        .context("Failed to save optimization")?;
+        let id = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, i64>(
+                "INSERT INTO optimizations (optimization_type, title, description, estimated_savings, examples, suggestion, applied, status)
+                 VALUES ($1, $2, $3, $4, $5, $6, FALSE, 'new')
+                 RETURNING id"
+            )
+            .bind(&opt_type)
+            .bind(&opt.title)
+            .bind(&opt.description)
+            .bind(opt.estimated_savings as i64)
+            .bind(&examples_json)
+            .bind(&opt.suggestion)
+            .fetch_one(pool)
+            .await
+            .context("Failed to save optimization")?
+        });
 
-        Ok(())
+        Ok(id)
+    }
+
+    /// Get stored optimizations, newest first. Applied ones are left out
+    /// unless `include_applied`, dismissed and snoozed ones unless
+    /// `include_dismissed`.
+    pub async fn get_optimizations(
+        &self,
+        limit: usize,
+        include_applied: bool,
+        include_dismissed: bool,
+    ) -> Result<Vec<StoredOptimization>> {
+        let sql = format!(
+            "SELECT id, optimization_type, title, description, estimated_savings, examples, suggestion,
+                    {status}, snoozed_until, CAST(created_at AS TEXT)
+             FROM optimizations
+             WHERE ($1 OR {status} <> 'applied')
+               AND ($2 OR ({status} <> 'dismissed'
+                           AND ({status} <> 'snoozed' OR snoozed_until <= $3)))
+             ORDER BY created_at DESC, id DESC
+             LIMIT $4",
+            status = OPTIMIZATION_STATUS_SQL
+        );
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, OptimizationRow>(&sql)
+                .bind(include_applied)
+                .bind(include_dismissed)
+                .bind(stored_time(Utc::now()))
+                .bind(limit as i64)
+                .fetch_all(pool)
+                .await?
+        });
+
+        let mut optimizations = Vec::new();
+        for row in rows {
+            // Skip rows whose type was renamed or removed since they were stored
+            let Some(opt_type) = OptimizationType::from_str(&row.1) else {
+                continue;
+            };
+
+            let examples = row
+                .5
+                .as_deref()
+                .and_then(|json| serde_json::from_str(json).ok())
+                .unwrap_or_default();
+
+            optimizations.push(StoredOptimization {
+                id: row.0,
+                optimization: Optimization {
+                    opt_type,
+                    title: row.2,
+                    description: row.3,
+                    estimated_savings: row.4 as usize,
+                    examples,
+                    suggestion: row.6,
+                },
+                status: OptimizationStatus::from_row(&row.7, row.8.as_deref()),
+                created_at: parse_timestamp(&row.9),
+            });
+        }
+
+        Ok(optimizations)
+    }
+
+    /// Mark a stored optimization as applied. Returns false if no such ID exists.
+    pub async fn mark_applied(&self, id: i64) -> Result<bool> {
+        self.set_optimization_status(id, OptimizationStatus::Applied)
+            .await
     }
+
+    /// Change the status of a stored optimization. Returns false if no such
+    /// ID exists.
+    pub async fn set_optimization_status(
+        &self,
+        id: i64,
+        status: OptimizationStatus,
+    ) -> Result<bool> {
+        let snoozed_until = match status {
+            OptimizationStatus::Snoozed(until) => Some(stored_time(until)),
+            _ => None,
+        };
+
+        let updated = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE optimizations SET status = $1, snoozed_until = $2, applied = $3 WHERE id = $4",
+            )
+            .bind(status.name())
+            .bind(&snoozed_until)
+            .bind(status == OptimizationStatus::Applied)
+            .bind(id)
+            .execute(pool)
+            .await
+            .context("Failed to update the optimization")?
+            .rows_affected()
+        });
+
+        Ok(updated > 0)
+    }
+
+    /// Optimizations dismissed or snoozed for now, to leave out when they
+    /// are found in sessions again
+    pub async fn hidden_optimizations(&self) -> Result<HiddenOptimizations> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String)>(
+                "SELECT optimization_type, title FROM optimizations
+                 WHERE status = 'dismissed' OR (status = 'snoozed' AND snoozed_until > $1)",
+            )
+            .bind(stored_time(Utc::now()))
+            .fetch_all(pool)
+            .await?
+        });
+
+        Ok(HiddenOptimizations(rows.into_iter().collect()))
+    }
+}
+
+/// The status of an optimization row; rows stored before statuses existed
+/// only have `applied`
+const INSERT_AGENT_SQL: &str =
+    "INSERT INTO agent_executions (agent_id, agent_type, capability, task, tokens_used, execution_time_ms, success, task_id, model, prompt_version)
+     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)";
+
+const OPTIMIZATION_STATUS_SQL: &str =
+    "COALESCE(status, CASE WHEN COALESCE(applied, FALSE) THEN 'applied' ELSE 'new' END)";
+
+/// `id, optimization_type, title, description, estimated_savings, examples,
+/// suggestion, status, snoozed_until, created_at`
+type OptimizationRow = (
+    i64,
+    String,
+    String,
+    String,
+    i64,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+    String,
+);
+
+/// A time as stored in text columns, in the format of CURRENT_TIMESTAMP so
+/// that the two compare as text
+fn stored_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// `agent_id, agent_type, capability, task, tokens_used, execution_time_ms,
+/// success, created_at` as selected for [`AgentHistoryEntry`]
+type AgentHistoryRow = (String, String, String, String, i64, i64, bool, String);
+
+/// Parse a stored `created_at` value. SQLite's CURRENT_TIMESTAMP and Postgres
+/// TIMESTAMP-as-text are naive UTC (`YYYY-MM-DD HH:MM:SS[.fff]`); RFC 3339 is
+/// accepted too. Falls back to now for unparseable values.
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return ts.with_timezone(&Utc);
+    }
+
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .map(|naive| naive.and_utc())
+        .unwrap_or_else(|_| Utc::now())
 }
 
+pub struct StoredOptimization {
+    pub id: i64,
+    pub optimization: Optimization,
+    pub status: OptimizationStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a stored optimization stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizationStatus {
+    New,
+    Applied,
+    Dismissed,
+    /// Hidden until then, and new again after
+    Snoozed(DateTime<Utc>),
+}
+
+impl OptimizationStatus {
+    fn from_row(status: &str, snoozed_until: Option<&str>) -> Self {
+        match (status, snoozed_until) {
+            ("applied", _) => Self::Applied,
+            ("dismissed", _) => Self::Dismissed,
+            ("snoozed", Some(until)) => Self::Snoozed(parse_timestamp(until)),
+            _ => Self::New,
+        }
+    }
+
+    /// Name stored in the `status` column
+    fn name(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Applied => "applied",
+            Self::Dismissed => "dismissed",
+            Self::Snoozed(_) => "snoozed",
+        }
+    }
+
+    /// Whether it is shown by default: new, or snoozed until a time that
+    /// has passed
+    pub fn is_pending(&self) -> bool {
+        match self {
+            Self::New => true,
+            Self::Snoozed(until) => *until <= Utc::now(),
+            Self::Applied | Self::Dismissed => false,
+        }
+    }
+}
+
+impl std::fmt::Display for OptimizationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Snoozed(_) if self.is_pending() => f.write_str("new"),
+            Self::Snoozed(until) => write!(f, "snoozed until {}", until.format("%Y-%m-%d %H:%M")),
+            status => f.write_str(status.name()),
+        }
+    }
+}
+
+/// Optimizations dismissed or snoozed in the database, by type and title
+#[derive(Default)]
+pub struct HiddenOptimizations(HashSet<(String, String)>);
+
+impl HiddenOptimizations {
+    /// Whether `opt`, e.g. found in a session again, was dismissed or snoozed
+    pub fn hides(&self, opt: &Optimization) -> bool {
+        self.0
+            .contains(&(format!("{:?}", opt.opt_type), opt.title.clone()))
+    }
+}
+
+#[derive(Serialize)]
 pub struct AgentStats {
     pub total_executions: usize,
     pub successful_executions: usize,
@@ -318,6 +897,7 @@ pub struct AgentStats {
     pub by_capability: std::collections::HashMap<AgentCapability, usize>,
 }
 
+#[derive(Serialize)]
 pub struct AgentHistoryEntry {
     pub agent_id: String,
     pub agent_type: String,
@@ -342,3 +922,62 @@ pub struct TaskSummary {
     pub success: bool,
     pub timestamp: DateTime<Utc>,
 }
+
+/// A stored task execution. The JSON columns are None when they cannot
+/// be decoded, e.g. rows written by an older version.
+pub struct TaskRecord {
+    pub id: usize,
+    pub description: String,
+    pub estimated_tokens: usize,
+    pub actual_tokens: usize,
+    pub success: bool,
+    pub analysis: Option<TaskAnalysis>,
+    pub plan: Option<ExecutionPlan>,
+    pub result: Option<ExecutionResult>,
+    /// Prompt variant label given with `run --variant`
+    pub variant: Option<String>,
+    /// Root directory of the project the task ran in; None for older runs
+    pub project: Option<String>,
+    /// ID of the run's log and artifacts; None for older runs
+    pub run_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+pub struct TaskOutcome {
+    pub id: usize,
+    pub description: String,
+    pub estimated_tokens: usize,
+    pub actual_tokens: usize,
+    pub success: bool,
+    /// Missing for runs whose result could not be read back
+    pub execution_time_secs: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimization_status() {
+        let until = Utc::now() + chrono::Duration::days(3);
+        let snoozed = OptimizationStatus::from_row("snoozed", Some(&stored_time(until)));
+        assert!(matches!(snoozed, OptimizationStatus::Snoozed(_)));
+        assert!(!snoozed.is_pending());
+        assert!(snoozed.to_string().starts_with("snoozed until "));
+
+        let expired = OptimizationStatus::from_row("snoozed", Some("2020-01-01 00:00:00"));
+        assert!(expired.is_pending());
+        assert_eq!(expired.to_string(), "new");
+
+        assert_eq!(
+            OptimizationStatus::from_row("applied", None),
+            OptimizationStatus::Applied
+        );
+        assert!(!OptimizationStatus::Dismissed.is_pending());
+        assert_eq!(
+            OptimizationStatus::from_row("new", None),
+            OptimizationStatus::New
+        );
+    }
+}
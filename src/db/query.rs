@@ -0,0 +1,217 @@
+use crate::style;
+use anyhow::Result;
+use sqlx::{Column, ColumnIndex, Decode, Row, Type, ValueRef};
+
+/// Result of an ad-hoc query: column names plus stringified cell values
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl QueryResult {
+    pub(crate) fn from_rows<R>(rows: &[R]) -> Result<Self>
+    where
+        R: Row,
+        usize: ColumnIndex<R>,
+        for<'r> i64: Decode<'r, R::Database> + Type<R::Database>,
+        for<'r> i32: Decode<'r, R::Database> + Type<R::Database>,
+        for<'r> f64: Decode<'r, R::Database> + Type<R::Database>,
+        for<'r> bool: Decode<'r, R::Database> + Type<R::Database>,
+        for<'r> String: Decode<'r, R::Database> + Type<R::Database>,
+        for<'r> Vec<u8>: Decode<'r, R::Database> + Type<R::Database>,
+    {
+        let columns = rows
+            .first()
+            .map(|row| row.columns().iter().map(|c| c.name().to_string()).collect())
+            .unwrap_or_default();
+
+        let mut values = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut cells = Vec::with_capacity(row.len());
+            for i in 0..row.len() {
+                cells.push(format_cell(row, i)?);
+            }
+            values.push(cells);
+        }
+
+        Ok(Self {
+            columns,
+            rows: values,
+        })
+    }
+
+    /// Render the result as a plain-text table with aligned columns
+    pub fn render_table(&self) -> String {
+        if self.columns.is_empty() {
+            return "(0 rows)".to_string();
+        }
+
+        let mut widths: Vec<usize> = self.columns.iter().map(|c| c.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+
+        let format_row = |cells: &[String]| -> String {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!(" {:<width$} ", cell, width = width))
+                .collect::<Vec<_>>()
+                .join(style::pick("│", "|"))
+                .trim_end()
+                .to_string()
+        };
+
+        let separator = widths
+            .iter()
+            .map(|w| style::line(w + 2))
+            .collect::<Vec<_>>()
+            .join(style::pick("┼", "+"));
+
+        let mut out = Vec::with_capacity(self.rows.len() + 3);
+        out.push(format_row(&self.columns));
+        out.push(separator);
+        for row in &self.rows {
+            out.push(format_row(row));
+        }
+        out.push(format!(
+            "({} row{})",
+            self.rows.len(),
+            if self.rows.len() == 1 { "" } else { "s" }
+        ));
+
+        out.join("\n")
+    }
+}
+
+/// Stringify a single cell by trying the common column types in turn.
+/// Decoding is type-checked, so the first compatible type wins.
+fn format_cell<R>(row: &R, index: usize) -> Result<String>
+where
+    R: Row,
+    usize: ColumnIndex<R>,
+    for<'r> i64: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> i32: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> f64: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> bool: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> String: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> Vec<u8>: Decode<'r, R::Database> + Type<R::Database>,
+{
+    if row.try_get_raw(index)?.is_null() {
+        return Ok("NULL".to_string());
+    }
+
+    if let Ok(v) = row.try_get::<i64, _>(index) {
+        return Ok(v.to_string());
+    }
+    if let Ok(v) = row.try_get::<i32, _>(index) {
+        return Ok(v.to_string());
+    }
+    if let Ok(v) = row.try_get::<f64, _>(index) {
+        return Ok(v.to_string());
+    }
+    if let Ok(v) = row.try_get::<bool, _>(index) {
+        return Ok(v.to_string());
+    }
+    if let Ok(v) = row.try_get::<String, _>(index) {
+        return Ok(v);
+    }
+    if let Ok(v) = row.try_get::<Vec<u8>, _>(index) {
+        return Ok(format!("<{} bytes>", v.len()));
+    }
+
+    Ok("<unsupported type; CAST to TEXT>".to_string())
+}
+
+/// Ensure an ad-hoc query is a single read-only SELECT statement.
+/// Returns the statement with any trailing semicolon removed.
+pub fn validate_select(sql: &str) -> Result<&str> {
+    let trimmed = sql.trim().trim_end_matches(';').trim_end();
+
+    if trimmed.is_empty() {
+        anyhow::bail!("Query is empty");
+    }
+
+    if trimmed.contains(';') {
+        anyhow::bail!("Only a single statement is allowed");
+    }
+
+    let first_word = trimmed
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+
+    if first_word != "SELECT" && first_word != "WITH" {
+        anyhow::bail!("Only SELECT queries are allowed (got {})", first_word);
+    }
+
+    Ok(trimmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_select_accepts_select() {
+        assert_eq!(
+            validate_select("SELECT * FROM agent_executions;").unwrap(),
+            "SELECT * FROM agent_executions"
+        );
+    }
+
+    #[test]
+    fn test_validate_select_accepts_lowercase_and_cte() {
+        assert!(validate_select("  select 1").is_ok());
+        assert!(validate_select("WITH t AS (SELECT 1) SELECT * FROM t").is_ok());
+    }
+
+    #[test]
+    fn test_validate_select_rejects_writes() {
+        assert!(validate_select("DELETE FROM task_executions").is_err());
+        assert!(validate_select("DROP TABLE optimizations").is_err());
+        assert!(validate_select("PRAGMA journal_mode = DELETE").is_err());
+    }
+
+    #[test]
+    fn test_validate_select_rejects_multiple_statements() {
+        assert!(validate_select("SELECT 1; DELETE FROM task_executions").is_err());
+    }
+
+    #[test]
+    fn test_validate_select_rejects_empty() {
+        assert!(validate_select("   ").is_err());
+        assert!(validate_select(";").is_err());
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let result = QueryResult {
+            columns: vec!["id".to_string(), "agent_type".to_string()],
+            rows: vec![
+                vec!["1".to_string(), "Architect".to_string()],
+                vec!["10".to_string(), "Code Writer".to_string()],
+            ],
+        };
+
+        let table = result.render_table();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines[0], " id │ agent_type");
+        assert_eq!(lines[2], " 1  │ Architect");
+        assert_eq!(lines[3], " 10 │ Code Writer");
+        assert_eq!(lines[4], "(2 rows)");
+    }
+
+    #[test]
+    fn test_render_table_empty() {
+        let result = QueryResult {
+            columns: vec![],
+            rows: vec![],
+        };
+        assert_eq!(result.render_table(), "(0 rows)");
+    }
+}
@@ -0,0 +1,107 @@
+use super::backend::with_pool;
+use super::{parse_timestamp, Database};
+use crate::master::queue::{BlockReason, Blocked, QueuedRun};
+use crate::project::Project;
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use tracing::warn;
+
+/// `id, task_description, reason, plan_data, prompts_data, resume_at,
+/// project, created_at` as selected for [`QueuedRun`]
+type QueuedRow = (
+    i64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+);
+
+impl Database {
+    /// Put the agents of a paused run in the task queue, to resume at
+    /// `resume_at`, and return its ID
+    pub async fn queue_run(
+        &self,
+        task: &str,
+        blocked: &Blocked,
+        resume_at: DateTime<Utc>,
+        project: Option<&Project>,
+    ) -> Result<i64> {
+        let task = self.seal(task)?;
+        let plan = self.seal(&serde_json::to_string(&blocked.plan)?)?;
+        let prompts = self.seal(&serde_json::to_string(&blocked.system_prompts)?)?;
+        // RFC 3339 in UTC sorts as text
+        let resume_at = resume_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let id = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, i64>(
+                "INSERT INTO task_queue (task_description, reason, plan_data, prompts_data, resume_at, project)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 RETURNING id",
+            )
+            .bind(&task)
+            .bind(blocked.reason.as_str())
+            .bind(&plan)
+            .bind(&prompts)
+            .bind(&resume_at)
+            .bind(project.map(|p| p.root.as_str()))
+            .fetch_one(pool)
+            .await
+            .context("Failed to queue the paused run")?
+        });
+
+        Ok(id)
+    }
+
+    /// The paused runs in the task queue, the one to resume first first
+    pub async fn get_queued_runs(&self) -> Result<Vec<QueuedRun>> {
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, QueuedRow>(
+                "SELECT id, task_description, reason, plan_data, prompts_data, resume_at, project, CAST(created_at AS TEXT)
+                 FROM task_queue
+                 ORDER BY resume_at, id",
+            )
+            .fetch_all(pool)
+            .await
+            .context("Failed to read the task queue")?
+        });
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let run = (|| {
+                    anyhow::Ok(QueuedRun {
+                        id: row.0,
+                        task: self.open(row.1),
+                        reason: BlockReason::from_name(&row.2)
+                            .with_context(|| format!("Unknown reason '{}'", row.2))?,
+                        plan: serde_json::from_str(&self.open(row.3))?,
+                        system_prompts: serde_json::from_str(&self.open(row.4))?,
+                        resume_at: parse_timestamp(&row.5),
+                        project: row.6,
+                        created_at: parse_timestamp(&row.7),
+                    })
+                })();
+                run.map_err(|e| warn!("Skipping queued task #{}: {:#}", row.0, e))
+                    .ok()
+            })
+            .collect())
+    }
+
+    /// Take a run out of the task queue. Returns false when there is no
+    /// such run.
+    pub async fn remove_queued_run(&self, id: i64) -> Result<bool> {
+        let deleted = with_pool!(&self.pool, |pool| {
+            sqlx::query("DELETE FROM task_queue WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .context("Failed to remove the queued run")?
+                .rows_affected()
+        });
+
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,281 @@
+use super::backend::with_pool;
+use super::leaderboard::success_rate;
+use super::{parse_timestamp, Database};
+use crate::agents::AgentCapability;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Most recent runs a rolling success rate covers
+pub const ROLLING_WINDOW: usize = 20;
+
+/// Runs needed on each side of a change before the two are compared
+const MIN_RUNS: usize = 5;
+
+/// Drop in success rate (percentage points) after a change that is
+/// reported as a likely regression rather than noise
+const REGRESSION_DROP: f64 = 20.0;
+
+/// One agent run with the model and prompt version it used
+#[derive(Debug, Clone)]
+pub struct CapabilityRun {
+    pub capability: AgentCapability,
+    pub model: String,
+    pub prompt_version: String,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CapabilityRun {
+    fn setup(&self) -> (&str, &str) {
+        (&self.model, &self.prompt_version)
+    }
+}
+
+/// Success of one capability on one model over its last
+/// [`ROLLING_WINDOW`] runs
+pub struct RollingRate {
+    pub capability: AgentCapability,
+    pub model: String,
+    pub executions: usize,
+    pub successful: usize,
+}
+
+impl RollingRate {
+    pub fn success_rate(&self) -> f64 {
+        success_rate(self.successful, self.executions)
+    }
+}
+
+/// A capability succeeding clearly less often since its model or prompt
+/// last changed
+#[derive(Debug)]
+pub struct CapabilityRegression {
+    pub capability: AgentCapability,
+    /// Model and prompt version before the change
+    pub before: (String, String),
+    /// Model and prompt version since the change
+    pub after: (String, String),
+    pub runs_before: usize,
+    pub successful_before: usize,
+    pub runs_after: usize,
+    pub successful_after: usize,
+    /// Last run before the change; it was made between this and
+    /// `first_changed`
+    pub last_unchanged: DateTime<Utc>,
+    /// First run after the change
+    pub first_changed: DateTime<Utc>,
+}
+
+impl CapabilityRegression {
+    pub fn rate_before(&self) -> f64 {
+        success_rate(self.successful_before, self.runs_before)
+    }
+
+    pub fn rate_after(&self) -> f64 {
+        success_rate(self.successful_after, self.runs_after)
+    }
+
+    /// What changed, e.g. `prompt 1a2b3c4d → 5e6f7a8b`
+    pub fn change(&self) -> String {
+        let mut changes = Vec::new();
+        if self.before.0 != self.after.0 {
+            changes.push(format!("model {} → {}", self.before.0, self.after.0));
+        }
+        if self.before.1 != self.after.1 {
+            changes.push(format!("prompt {} → {}", self.before.1, self.after.1));
+        }
+        changes.join(", ")
+    }
+}
+
+/// Rolling success rates per capability and model; `runs` oldest first
+pub fn rolling_rates(runs: &[CapabilityRun]) -> Vec<RollingRate> {
+    let mut recent: HashMap<(&AgentCapability, &str), Vec<bool>> = HashMap::new();
+    for run in runs.iter().rev() {
+        let outcomes = recent.entry((&run.capability, &run.model)).or_default();
+        if outcomes.len() < ROLLING_WINDOW {
+            outcomes.push(run.success);
+        }
+    }
+
+    let mut rates: Vec<RollingRate> = recent
+        .into_iter()
+        .map(|((capability, model), outcomes)| RollingRate {
+            capability: capability.clone(),
+            model: model.to_string(),
+            executions: outcomes.len(),
+            successful: outcomes.iter().filter(|success| **success).count(),
+        })
+        .collect();
+    rates.sort_by(|a, b| {
+        (format!("{:?}", a.capability), &a.model).cmp(&(format!("{:?}", b.capability), &b.model))
+    });
+    rates
+}
+
+/// Capabilities whose success rate since their latest change of model or
+/// prompt version fell by [`REGRESSION_DROP`] points or more against the
+/// runs just before it; `runs` oldest first
+pub fn find_regressions(runs: &[CapabilityRun]) -> Vec<CapabilityRegression> {
+    let mut by_capability: HashMap<&AgentCapability, Vec<&CapabilityRun>> = HashMap::new();
+    for run in runs {
+        by_capability.entry(&run.capability).or_default().push(run);
+    }
+
+    let mut regressions: Vec<CapabilityRegression> = by_capability
+        .into_iter()
+        .filter_map(|(capability, runs)| {
+            let current = runs.last()?.setup();
+            let last_unchanged = runs.iter().rposition(|run| run.setup() != current)?;
+            let previous = runs[last_unchanged].setup();
+
+            let before: Vec<bool> = runs[..=last_unchanged]
+                .iter()
+                .rev()
+                .filter(|run| run.setup() == previous)
+                .take(ROLLING_WINDOW)
+                .map(|run| run.success)
+                .collect();
+            let after: Vec<bool> = runs[last_unchanged + 1..]
+                .iter()
+                .rev()
+                .take(ROLLING_WINDOW)
+                .map(|run| run.success)
+                .collect();
+            if before.len() < MIN_RUNS || after.len() < MIN_RUNS {
+                return None;
+            }
+
+            let regression = CapabilityRegression {
+                capability: capability.clone(),
+                before: (previous.0.to_string(), previous.1.to_string()),
+                after: (current.0.to_string(), current.1.to_string()),
+                runs_before: before.len(),
+                successful_before: before.iter().filter(|success| **success).count(),
+                runs_after: after.len(),
+                successful_after: after.iter().filter(|success| **success).count(),
+                last_unchanged: runs[last_unchanged].created_at,
+                first_changed: runs[last_unchanged + 1].created_at,
+            };
+            (regression.rate_before() - regression.rate_after() >= REGRESSION_DROP)
+                .then_some(regression)
+        })
+        .collect();
+    regressions.sort_by_key(|regression| format!("{:?}", regression.capability));
+    regressions
+}
+
+impl Database {
+    /// Agent runs since `since` that recorded their model and prompt
+    /// version, oldest first
+    pub async fn get_capability_runs(&self, since: DateTime<Utc>) -> Result<Vec<CapabilityRun>> {
+        let query = format!(
+            "SELECT capability, model, prompt_version, success, CAST(created_at AS TEXT)
+             FROM agent_executions
+             WHERE model IS NOT NULL AND prompt_version IS NOT NULL AND {}
+             ORDER BY created_at, id",
+            self.pool.created_since_sql()
+        );
+        let since = since.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String, String, bool, String)>(&query)
+                .bind(&since)
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(CapabilityRun {
+                    capability: AgentCapability::from_str(&row.0)?,
+                    model: row.1,
+                    prompt_version: row.2,
+                    success: row.3,
+                    created_at: parse_timestamp(&row.4),
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn runs(
+        capability: AgentCapability,
+        prompt_version: &str,
+        outcomes: &[bool],
+        start: DateTime<Utc>,
+    ) -> Vec<CapabilityRun> {
+        outcomes
+            .iter()
+            .enumerate()
+            .map(|(i, success)| CapabilityRun {
+                capability: capability.clone(),
+                model: "claude-sonnet-4-5".to_string(),
+                prompt_version: prompt_version.to_string(),
+                success: *success,
+                created_at: start + Duration::hours(i as i64),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_regressions() {
+        let start = Utc::now() - Duration::days(10);
+        let later = start + Duration::days(5);
+        let mut history = runs(AgentCapability::Testing, "aaaa0000", &[true; 10], start);
+        history.extend(runs(
+            AgentCapability::CodeWriting,
+            "cccc0000",
+            &[true; 10],
+            start,
+        ));
+        history.extend(runs(
+            AgentCapability::Testing,
+            "bbbb0000",
+            &[true, false, false, true, false, false],
+            later,
+        ));
+        // The same change with no drop in success is no regression
+        history.extend(runs(
+            AgentCapability::CodeWriting,
+            "dddd0000",
+            &[true; 6],
+            later,
+        ));
+        history.sort_by_key(|run| run.created_at);
+
+        let regressions = find_regressions(&history);
+        assert_eq!(regressions.len(), 1);
+        let regression = &regressions[0];
+        assert_eq!(regression.capability, AgentCapability::Testing);
+        assert_eq!(regression.change(), "prompt aaaa0000 → bbbb0000");
+        assert_eq!(regression.rate_before(), 100.0);
+        assert_eq!(regression.runs_after, 6);
+        assert_eq!(regression.successful_after, 2);
+        assert_eq!(regression.last_unchanged, start + Duration::hours(9));
+        assert_eq!(regression.first_changed, later);
+
+        // Too few runs since the change to tell yet
+        let early: Vec<CapabilityRun> = history
+            .iter()
+            .filter(|run| run.created_at < later + Duration::hours(3))
+            .cloned()
+            .collect();
+        assert!(find_regressions(&early).is_empty());
+
+        let rates = rolling_rates(&history);
+        assert_eq!(rates.len(), 2);
+        let testing = rates
+            .iter()
+            .find(|rate| rate.capability == AgentCapability::Testing)
+            .unwrap();
+        assert_eq!((testing.successful, testing.executions), (12, 16));
+    }
+}
@@ -0,0 +1,149 @@
+use super::backend::with_pool;
+use super::Database;
+use crate::agents::{DEFAULT_COST_PER_MILLION, DEFAULT_MODEL};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+
+/// One row of the `usage_daily` rollup
+#[derive(Serialize)]
+pub struct DailyUsage {
+    pub day: String,
+    pub project: String,
+    pub model: String,
+    pub runs: usize,
+    pub successful_runs: usize,
+    pub tokens: usize,
+    pub cost_usd: f64,
+}
+
+/// Dollar cost of `tokens` at the blended [`DEFAULT_MODEL`] rate
+pub fn estimate_cost(tokens: usize) -> f64 {
+    (tokens as f64 / 1_000_000.0) * DEFAULT_COST_PER_MILLION
+}
+
+/// SQL summing the recorded cost of task executions; runs saved before
+/// costs were recorded are estimated at `$2` dollars per million tokens
+pub(super) const COST_SUM_SQL: &str = "CAST(COALESCE(SUM(COALESCE(cost_usd, \
+     CAST(actual_tokens AS DOUBLE PRECISION) / 1000000.0 * $2)), 0) AS DOUBLE PRECISION)";
+
+/// SQL adding one run to the rollup row of day `$1`, project `$2` and
+/// model `$3`: `$4` successful runs (0 or 1), `$5` tokens and `$6` dollars
+pub(super) const RECORD_USAGE_SQL: &str =
+    "INSERT INTO usage_daily (day, project, model, runs, successful_runs, tokens, cost_usd)
+     VALUES ($1, $2, $3, 1, $4, $5, $6)
+     ON CONFLICT (day, project, model) DO UPDATE SET
+        runs = usage_daily.runs + excluded.runs,
+        successful_runs = usage_daily.successful_runs + excluded.successful_runs,
+        tokens = usage_daily.tokens + excluded.tokens,
+        cost_usd = usage_daily.cost_usd + excluded.cost_usd";
+
+/// Today's `usage_daily` day
+pub(super) fn today() -> String {
+    Utc::now().format("%Y-%m-%d").to_string()
+}
+
+impl Database {
+    /// Add a finished run to today's rollup row for its project and model
+    /// (called on ingest)
+    pub async fn record_daily_usage(
+        &self,
+        project: &str,
+        model: &str,
+        tokens: usize,
+        cost_usd: f64,
+        success: bool,
+    ) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(RECORD_USAGE_SQL)
+                .bind(today())
+                .bind(project)
+                .bind(model)
+                .bind(if success { 1i64 } else { 0i64 })
+                .bind(tokens as i64)
+                .bind(cost_usd)
+                .execute(pool)
+                .await
+                .context("Failed to update daily usage rollup")?;
+        });
+
+        Ok(())
+    }
+
+    /// Rebuild `usage_daily` from scratch out of the raw task executions,
+    /// one row per day, project and model. Runs saved before their model
+    /// was recorded count as [`DEFAULT_MODEL`]; imported runs are left out,
+    /// as they were never recorded on ingest. Returns the number of rollup
+    /// rows written.
+    pub async fn rebuild_daily_rollup(&self) -> Result<usize> {
+        let insert = format!(
+            "INSERT INTO usage_daily (day, project, model, runs, successful_runs, tokens, cost_usd)
+             SELECT
+                {},
+                COALESCE(project, ''),
+                COALESCE(model, $1),
+                COUNT(*),
+                CAST(COALESCE(SUM(CASE WHEN success THEN 1 ELSE 0 END), 0) AS BIGINT),
+                CAST(COALESCE(SUM(actual_tokens), 0) AS BIGINT),
+                {}
+             FROM task_executions
+             WHERE NOT imported
+             GROUP BY 1, 2, 3",
+            self.pool.day_bucket_sql(),
+            COST_SUM_SQL
+        );
+
+        let written = with_pool!(&self.pool, |pool| {
+            let mut tx = pool.begin().await?;
+
+            sqlx::query("DELETE FROM usage_daily")
+                .execute(&mut *tx)
+                .await
+                .context("Failed to clear daily usage rollup")?;
+
+            let result = sqlx::query(&insert)
+                .bind(DEFAULT_MODEL)
+                .bind(DEFAULT_COST_PER_MILLION)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to rebuild daily usage rollup")?;
+
+            tx.commit().await?;
+            result.rows_affected() as usize
+        });
+
+        Ok(written)
+    }
+
+    /// Get rollup rows for the most recent `days` days, newest first
+    pub async fn get_daily_usage(&self, days: usize) -> Result<Vec<DailyUsage>> {
+        let since = (Utc::now() - chrono::Duration::days(days as i64))
+            .format("%Y-%m-%d")
+            .to_string();
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String, String, i64, i64, i64, f64)>(
+                "SELECT day, project, model, runs, successful_runs, tokens, cost_usd
+                 FROM usage_daily
+                 WHERE day > $1
+                 ORDER BY day DESC, project, model",
+            )
+            .bind(&since)
+            .fetch_all(pool)
+            .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DailyUsage {
+                day: row.0,
+                project: row.1,
+                model: row.2,
+                runs: row.3 as usize,
+                successful_runs: row.4 as usize,
+                tokens: row.5 as usize,
+                cost_usd: row.6,
+            })
+            .collect())
+    }
+}
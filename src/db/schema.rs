@@ -1,3 +1,29 @@
+/// Columns added after a table was first released. Each entry is
+/// `(table, column, definition)` and is applied with `ALTER TABLE ... ADD COLUMN`
+/// only when the column is missing, so older databases are upgraded in place.
+/// The definition must be valid for both SQLite and Postgres.
+pub const COLUMN_MIGRATIONS: &[(&str, &str, &str)] = &[
+    ("optimizations", "examples", "TEXT"),
+    ("optimizations", "suggestion", "TEXT"),
+    ("agent_executions", "task_id", "BIGINT"),
+    ("task_executions", "variant", "TEXT"),
+    ("bench_results", "variant", "TEXT"),
+    ("task_executions", "project", "TEXT"),
+    ("task_executions", "git_remote", "TEXT"),
+    ("task_executions", "run_id", "TEXT"),
+    ("optimizations", "status", "TEXT"),
+    ("optimizations", "snoozed_until", "TEXT"),
+    ("agent_executions", "model", "TEXT"),
+    ("agent_executions", "prompt_version", "TEXT"),
+    ("task_executions", "model", "TEXT"),
+    ("task_executions", "cost_usd", "DOUBLE PRECISION"),
+    (
+        "task_executions",
+        "imported",
+        "BOOLEAN NOT NULL DEFAULT FALSE",
+    ),
+];
+
 pub const CREATE_TABLES: &str = "
 CREATE TABLE IF NOT EXISTS task_executions (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -9,6 +35,13 @@ CREATE TABLE IF NOT EXISTS task_executions (
     task_data TEXT NOT NULL,
     plan_data TEXT NOT NULL,
     result_data TEXT NOT NULL,
+    variant TEXT,
+    project TEXT,
+    git_remote TEXT,
+    run_id TEXT,
+    model TEXT,
+    cost_usd REAL,
+    imported BOOLEAN NOT NULL DEFAULT FALSE,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -21,6 +54,9 @@ CREATE TABLE IF NOT EXISTS agent_executions (
     tokens_used INTEGER NOT NULL,
     execution_time_ms INTEGER NOT NULL,
     success BOOLEAN NOT NULL,
+    task_id INTEGER,
+    model TEXT,
+    prompt_version TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -31,11 +67,228 @@ CREATE TABLE IF NOT EXISTS optimizations (
     description TEXT NOT NULL,
     estimated_savings INTEGER NOT NULL,
     examples TEXT,
+    suggestion TEXT,
     applied BOOLEAN DEFAULT 0,
+    status TEXT,
+    snoozed_until TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS usage_daily (
+    day TEXT NOT NULL,
+    project TEXT NOT NULL DEFAULT '',
+    model TEXT NOT NULL,
+    runs INTEGER NOT NULL DEFAULT 0,
+    successful_runs INTEGER NOT NULL DEFAULT 0,
+    tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, project, model)
+);
+
+CREATE TABLE IF NOT EXISTS task_tags (
+    task_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (task_id, tag)
+);
+
+CREATE TABLE IF NOT EXISTS api_calls (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    agent_id TEXT NOT NULL,
+    agent_type TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+    latency_ms INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS bench_results (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    suite TEXT NOT NULL,
+    model TEXT NOT NULL,
+    task TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    tokens_used INTEGER NOT NULL,
+    execution_time_ms INTEGER NOT NULL,
+    score REAL NOT NULL,
+    variant TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS embeddings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    model TEXT NOT NULL,
+    text_hash TEXT NOT NULL,
+    vector TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (model, text_hash)
+);
+
+CREATE TABLE IF NOT EXISTS knowledge_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    content TEXT NOT NULL,
+    source TEXT NOT NULL,
+    proposed BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS task_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_description TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    plan_data TEXT NOT NULL,
+    prompts_data TEXT NOT NULL,
+    resume_at TEXT NOT NULL,
+    project TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
 CREATE INDEX IF NOT EXISTS idx_task_executions_created ON task_executions(created_at);
 CREATE INDEX IF NOT EXISTS idx_agent_executions_created ON agent_executions(created_at);
 CREATE INDEX IF NOT EXISTS idx_agent_executions_type ON agent_executions(agent_type);
+CREATE INDEX IF NOT EXISTS idx_task_tags_tag ON task_tags(tag);
+CREATE INDEX IF NOT EXISTS idx_api_calls_created ON api_calls(created_at);
+CREATE INDEX IF NOT EXISTS idx_knowledge_entries_project ON knowledge_entries(project);
+";
+
+/// Postgres equivalent of [`CREATE_TABLES`]. Integer columns are BIGINT so
+/// they decode to the same Rust types as SQLite's 64-bit integers.
+#[cfg(feature = "postgres")]
+pub const CREATE_TABLES_POSTGRES: &str = "
+CREATE TABLE IF NOT EXISTS task_executions (
+    id BIGSERIAL PRIMARY KEY,
+    task_description TEXT NOT NULL,
+    complexity BIGINT NOT NULL,
+    estimated_tokens BIGINT NOT NULL,
+    actual_tokens BIGINT NOT NULL,
+    success BOOLEAN NOT NULL,
+    task_data TEXT NOT NULL,
+    plan_data TEXT NOT NULL,
+    result_data TEXT NOT NULL,
+    variant TEXT,
+    project TEXT,
+    git_remote TEXT,
+    run_id TEXT,
+    model TEXT,
+    cost_usd DOUBLE PRECISION,
+    imported BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE IF NOT EXISTS agent_executions (
+    id BIGSERIAL PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    agent_type TEXT NOT NULL,
+    capability TEXT NOT NULL,
+    task TEXT NOT NULL,
+    tokens_used BIGINT NOT NULL,
+    execution_time_ms BIGINT NOT NULL,
+    success BOOLEAN NOT NULL,
+    task_id BIGINT,
+    model TEXT,
+    prompt_version TEXT,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE IF NOT EXISTS optimizations (
+    id BIGSERIAL PRIMARY KEY,
+    optimization_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    estimated_savings BIGINT NOT NULL,
+    examples TEXT,
+    suggestion TEXT,
+    applied BOOLEAN DEFAULT FALSE,
+    status TEXT,
+    snoozed_until TEXT,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE IF NOT EXISTS usage_daily (
+    day TEXT NOT NULL,
+    project TEXT NOT NULL DEFAULT '',
+    model TEXT NOT NULL,
+    runs BIGINT NOT NULL DEFAULT 0,
+    successful_runs BIGINT NOT NULL DEFAULT 0,
+    tokens BIGINT NOT NULL DEFAULT 0,
+    cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, project, model)
+);
+
+CREATE TABLE IF NOT EXISTS task_tags (
+    task_id BIGINT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (task_id, tag)
+);
+
+CREATE TABLE IF NOT EXISTS api_calls (
+    id BIGSERIAL PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    agent_type TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_tokens BIGINT NOT NULL,
+    output_tokens BIGINT NOT NULL,
+    cache_read_tokens BIGINT NOT NULL DEFAULT 0,
+    cache_write_tokens BIGINT NOT NULL DEFAULT 0,
+    latency_ms BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE IF NOT EXISTS bench_results (
+    id BIGSERIAL PRIMARY KEY,
+    suite TEXT NOT NULL,
+    model TEXT NOT NULL,
+    task TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    tokens_used BIGINT NOT NULL,
+    execution_time_ms BIGINT NOT NULL,
+    score DOUBLE PRECISION NOT NULL,
+    variant TEXT,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE IF NOT EXISTS embeddings (
+    id BIGSERIAL PRIMARY KEY,
+    model TEXT NOT NULL,
+    text_hash TEXT NOT NULL,
+    vector TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc'),
+    UNIQUE (model, text_hash)
+);
+
+CREATE TABLE IF NOT EXISTS knowledge_entries (
+    id BIGSERIAL PRIMARY KEY,
+    project TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    content TEXT NOT NULL,
+    source TEXT NOT NULL,
+    proposed BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE IF NOT EXISTS task_queue (
+    id BIGSERIAL PRIMARY KEY,
+    task_description TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    plan_data TEXT NOT NULL,
+    prompts_data TEXT NOT NULL,
+    resume_at TEXT NOT NULL,
+    project TEXT,
+    created_at TIMESTAMP DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_executions_created ON task_executions(created_at);
+CREATE INDEX IF NOT EXISTS idx_agent_executions_created ON agent_executions(created_at);
+CREATE INDEX IF NOT EXISTS idx_agent_executions_type ON agent_executions(agent_type);
+CREATE INDEX IF NOT EXISTS idx_task_tags_tag ON task_tags(tag);
+CREATE INDEX IF NOT EXISTS idx_api_calls_created ON api_calls(created_at);
+CREATE INDEX IF NOT EXISTS idx_knowledge_entries_project ON knowledge_entries(project);
 ";
@@ -0,0 +1,96 @@
+use super::backend::with_pool;
+use super::rollup::COST_SUM_SQL;
+use super::{DailyUsage, Database};
+use crate::agents::{DEFAULT_COST_PER_MILLION, DEFAULT_MODEL};
+use anyhow::Result;
+use chrono::Utc;
+
+/// Tags are compared case-insensitively, so they are stored lowercase
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+        anyhow::bail!("Invalid tag '{}': tags must be a single word", tag);
+    }
+    Ok(tag)
+}
+
+/// Tag task `$1` with `$2`
+pub(super) const INSERT_TAG_SQL: &str = "INSERT INTO task_tags (task_id, tag) VALUES ($1, $2)
+     ON CONFLICT (task_id, tag) DO NOTHING";
+
+impl Database {
+    /// Tags of one task, alphabetically
+    pub async fn get_task_tags(&self, task_id: usize) -> Result<Vec<String>> {
+        let tags = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, String>(
+                "SELECT tag FROM task_tags WHERE task_id = $1 ORDER BY tag",
+            )
+            .bind(task_id as i64)
+            .fetch_all(pool)
+            .await?
+        });
+
+        Ok(tags)
+    }
+
+    /// Per-day totals of the runs tagged `tag` over the last `days` days,
+    /// by project and model, newest first. The `usage_daily` rollup is not
+    /// split by tag, so this reads the task executions directly.
+    pub async fn get_daily_usage_tagged(&self, days: usize, tag: &str) -> Result<Vec<DailyUsage>> {
+        let since = (Utc::now() - chrono::Duration::days(days as i64))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let query = format!(
+            "SELECT
+                {},
+                COALESCE(project, ''),
+                COALESCE(model, $3),
+                COUNT(*),
+                CAST(COALESCE(SUM(CASE WHEN success THEN 1 ELSE 0 END), 0) AS BIGINT),
+                CAST(COALESCE(SUM(actual_tokens), 0) AS BIGINT),
+                {}
+             FROM task_executions
+             WHERE {} AND id IN (SELECT task_id FROM task_tags WHERE tag = $4)
+             GROUP BY 1, 2, 3
+             ORDER BY 1 DESC, 2, 3",
+            self.pool.day_bucket_sql(),
+            COST_SUM_SQL,
+            self.pool.created_since_sql()
+        );
+
+        let rows = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, (String, String, String, i64, i64, i64, f64)>(&query)
+                .bind(&since)
+                .bind(DEFAULT_COST_PER_MILLION)
+                .bind(DEFAULT_MODEL)
+                .bind(tag)
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DailyUsage {
+                day: row.0,
+                project: row.1,
+                model: row.2,
+                runs: row.3 as usize,
+                successful_runs: row.4 as usize,
+                tokens: row.5 as usize,
+                cost_usd: row.6,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag(" Backend ").unwrap(), "backend");
+        assert!(normalize_tag("").is_err());
+        assert!(normalize_tag("two words").is_err());
+    }
+}
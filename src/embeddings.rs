@@ -0,0 +1,288 @@
+use crate::config::Config;
+use crate::db::Database;
+use crate::redact;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Texts sent to the provider in one request
+const BATCH_SIZE: usize = 64;
+
+/// Longest text embedded; longer ones are cut, which keeps requests small
+/// and the leading description (usually the gist) intact
+const MAX_TEXT_CHARS: usize = 4000;
+
+const VOYAGE_URL: &str = "https://api.voyageai.com/v1/embeddings";
+
+enum Provider {
+    Ollama { url: String },
+    Voyage { api_key: String },
+}
+
+/// Turns text into embedding vectors with the configured provider
+pub struct Embedder {
+    client: Client,
+    provider: Provider,
+    model: String,
+    min_similarity: f64,
+}
+
+impl Embedder {
+    /// The configured embedder, or None when `embeddings.provider` is `none`
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let embeddings = &config.embeddings;
+        let (provider, default_model) = match embeddings.provider.as_str() {
+            "none" => return Ok(None),
+            "ollama" => (
+                Provider::Ollama {
+                    url: embeddings.ollama_url.trim_end_matches('/').to_string(),
+                },
+                "nomic-embed-text",
+            ),
+            "voyage" => {
+                // Ollama runs locally; Voyage is the only provider needing the network
+                config.network.ensure_online("Embedding with Voyage AI")?;
+                let api_key = std::env::var("VOYAGE_API_KEY")
+                    .context("embeddings.provider is voyage but VOYAGE_API_KEY is not set")?;
+                (Provider::Voyage { api_key }, "voyage-3-lite")
+            }
+            other => anyhow::bail!("Unknown embeddings provider '{}'", other),
+        };
+
+        let client = config
+            .network
+            .client_builder()?
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Some(Self {
+            client,
+            provider,
+            model: embeddings
+                .model
+                .clone()
+                .unwrap_or_else(|| default_model.to_string()),
+            min_similarity: embeddings.min_similarity,
+        }))
+    }
+
+    /// Provider and model, e.g. `ollama/nomic-embed-text`. Vectors are only
+    /// compared with others of the same model.
+    pub fn model_key(&self) -> String {
+        let provider = match self.provider {
+            Provider::Ollama { .. } => "ollama",
+            Provider::Voyage { .. } => "voyage",
+        };
+        format!("{}/{}", provider, self.model)
+    }
+
+    /// Cosine similarity from which two texts count as similar
+    pub fn min_similarity(&self) -> f64 {
+        self.min_similarity
+    }
+
+    /// One vector per text, in order. Secrets are redacted first.
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+
+        for batch in texts.chunks(BATCH_SIZE) {
+            let input: Vec<String> = batch
+                .iter()
+                .map(|text| redact::active().redact(&truncate(text)).into_owned())
+                .collect();
+
+            let request = match &self.provider {
+                Provider::Ollama { url } => self
+                    .client
+                    .post(format!("{}/api/embed", url))
+                    .json(&json!({ "model": self.model, "input": input })),
+                Provider::Voyage { api_key } => self
+                    .client
+                    .post(VOYAGE_URL)
+                    .bearer_auth(api_key)
+                    .json(&json!({ "model": self.model, "input": input })),
+            };
+            let response = request.send().await.with_context(|| {
+                format!("Failed to reach the {} embeddings API", self.model_key())
+            })?;
+            let status = response.status();
+            if !status.is_success() {
+                anyhow::bail!(
+                    "Embeddings request failed ({}): {}",
+                    status,
+                    response.text().await.unwrap_or_default()
+                );
+            }
+            let body: Value = response
+                .json()
+                .await
+                .context("Failed to parse embeddings response")?;
+
+            let batch_vectors = match self.provider {
+                Provider::Ollama { .. } => parse_ollama(&body)?,
+                Provider::Voyage { .. } => parse_voyage(&body)?,
+            };
+            if batch_vectors.len() != batch.len() {
+                anyhow::bail!(
+                    "Embeddings API returned {} vectors for {} texts",
+                    batch_vectors.len(),
+                    batch.len()
+                );
+            }
+            vectors.extend(batch_vectors);
+        }
+
+        Ok(vectors)
+    }
+
+    /// Score every `(id, text)` candidate by its similarity to `query`, most
+    /// similar first. Candidate vectors are stored in the database, so each
+    /// text is only embedded once per model.
+    pub async fn rank<K: Clone>(
+        &self,
+        db: &Database,
+        query: &str,
+        candidates: &[(K, String)],
+    ) -> Result<Vec<(K, f64)>> {
+        let model = self.model_key();
+        let mut stored = db.get_embeddings(&model).await?;
+
+        let missing: Vec<(String, String)> = candidates
+            .iter()
+            .map(|(_, text)| (text_hash(text), text.clone()))
+            .filter(|(hash, _)| !stored.contains_key(hash))
+            .collect::<HashMap<_, _>>()
+            .into_iter()
+            .collect();
+        if !missing.is_empty() {
+            let texts: Vec<String> = missing.iter().map(|(_, text)| text.clone()).collect();
+            for ((hash, _), vector) in missing.into_iter().zip(self.embed(&texts).await?) {
+                db.save_embedding(&model, &hash, &vector).await?;
+                stored.insert(hash, vector);
+            }
+        }
+
+        let query = self
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .context("Embeddings API returned no vector")?;
+
+        let mut scored: Vec<(K, f64)> = candidates
+            .iter()
+            .filter_map(|(id, text)| {
+                let vector = stored.get(&text_hash(text))?;
+                Some((id.clone(), cosine(&query, vector)))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        Ok(scored)
+    }
+}
+
+/// Cosine similarity of two vectors; 0.0 when either is empty or zero
+pub fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (*x as f64, *y as f64);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Key a text's vector is stored under
+fn text_hash(text: &str) -> String {
+    Sha256::digest(truncate(text).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_TEXT_CHARS).collect()
+}
+
+/// `{"embeddings": [[...], ...]}` from Ollama's `/api/embed`
+fn parse_ollama(body: &Value) -> Result<Vec<Vec<f32>>> {
+    body.get("embeddings")
+        .and_then(Value::as_array)
+        .context("Ollama response has no embeddings")?
+        .iter()
+        .map(parse_vector)
+        .collect()
+}
+
+/// `{"data": [{"embedding": [...], "index": 0}, ...]}` from Voyage
+fn parse_voyage(body: &Value) -> Result<Vec<Vec<f32>>> {
+    let mut data: Vec<&Value> = body
+        .get("data")
+        .and_then(Value::as_array)
+        .context("Voyage response has no data")?
+        .iter()
+        .collect();
+    data.sort_by_key(|item| {
+        item.get("index")
+            .and_then(Value::as_u64)
+            .unwrap_or_default()
+    });
+
+    data.into_iter()
+        .map(|item| parse_vector(item.get("embedding").unwrap_or(&Value::Null)))
+        .collect()
+}
+
+fn parse_vector(value: &Value) -> Result<Vec<f32>> {
+    value
+        .as_array()
+        .context("Embedding is not a list of numbers")?
+        .iter()
+        .map(|x| {
+            x.as_f64()
+                .map(|x| x as f32)
+                .context("Embedding is not a list of numbers")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-9);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+        assert_eq!(cosine(&[1.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let ollama = json!({"model": "nomic-embed-text", "embeddings": [[0.5, 1.0], [0.0, -1.0]]});
+        assert_eq!(
+            parse_ollama(&ollama).unwrap(),
+            vec![vec![0.5, 1.0], vec![0.0, -1.0]]
+        );
+
+        let voyage = json!({"data": [
+            {"embedding": [2.0], "index": 1},
+            {"embedding": [1.0], "index": 0}
+        ]});
+        assert_eq!(parse_voyage(&voyage).unwrap(), vec![vec![1.0], vec![2.0]]);
+
+        assert!(parse_ollama(&json!({"error": "model not found"})).is_err());
+    }
+}
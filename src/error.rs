@@ -0,0 +1,133 @@
+use thiserror::Error;
+
+/// Result type of the library's public entry points
+pub type Result<T> = std::result::Result<T, ClaudeHelperError>;
+
+/// Kinds of failure the library reports, so embedders and the CLI can branch
+/// on them. Internals still use `anyhow`; a `ClaudeHelperError` raised deep
+/// inside keeps its kind when it crosses the public API, and anything else
+/// ends up as [`ClaudeHelperError::Other`].
+#[derive(Debug, Error)]
+pub enum ClaudeHelperError {
+    /// No usable credentials, or the API rejected them
+    #[error("Authentication failed: {0}")]
+    AuthError(String),
+
+    /// A run spent more tokens than its budget allows
+    #[error("Token budget exceeded: {used} tokens used, budget is {budget}")]
+    BudgetExceeded { used: usize, budget: usize },
+
+    /// A run cost more dollars than `run --max-cost` allows
+    #[error("Cost cap exceeded: ${used:.2} spent, cap is ${cap:.2}")]
+    CostExceeded { used: f64, cap: f64 },
+
+    /// The task could not be analyzed or planned
+    #[error("Planning failed: {0}")]
+    PlanError(String),
+
+    /// The Claude API answered with an error status
+    #[error("Claude API error ({status}): {message}")]
+    ApiError { status: u16, message: String },
+
+    #[error("Database error: {0:#}")]
+    DbError(anyhow::Error),
+
+    #[error("Configuration error: {0:#}")]
+    ConfigError(anyhow::Error),
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl ClaudeHelperError {
+    /// Process exit code the CLI uses for this kind of failure:
+    ///
+    /// | Code | Failure |
+    /// |------|---------|
+    /// | 1 | anything else |
+    /// | 3 | authentication |
+    /// | 4 | configuration |
+    /// | 5 | token budget or cost cap exceeded |
+    /// | 6 | Claude API error |
+    /// | 7 | database |
+    /// | 8 | planning |
+    ///
+    /// 2 is left to argument parsing errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Other(_) => 1,
+            Self::AuthError(_) => 3,
+            Self::ConfigError(_) => 4,
+            Self::BudgetExceeded { .. } | Self::CostExceeded { .. } => 5,
+            Self::ApiError { .. } => 6,
+            Self::DbError(_) => 7,
+            Self::PlanError(_) => 8,
+        }
+    }
+
+    /// Exit code for an error returned by the CLI: that of the
+    /// `ClaudeHelperError` in its chain, or 1
+    pub fn exit_code_of(error: &anyhow::Error) -> i32 {
+        error
+            .chain()
+            .find_map(|e| e.downcast_ref::<Self>())
+            .map_or(1, Self::exit_code)
+    }
+
+    pub fn auth(error: anyhow::Error) -> Self {
+        Self::from_anyhow(error, |e| Self::AuthError(format!("{:#}", e)))
+    }
+
+    pub fn plan(error: anyhow::Error) -> Self {
+        Self::from_anyhow(error, |e| Self::PlanError(format!("{:#}", e)))
+    }
+
+    pub fn db(error: anyhow::Error) -> Self {
+        Self::from_anyhow(error, Self::DbError)
+    }
+
+    pub fn config(error: anyhow::Error) -> Self {
+        Self::from_anyhow(error, Self::ConfigError)
+    }
+
+    /// Keep the kind of an error that already is a `ClaudeHelperError`,
+    /// otherwise classify it with `kind`
+    fn from_anyhow(error: anyhow::Error, kind: impl FnOnce(anyhow::Error) -> Self) -> Self {
+        match error.downcast::<Self>() {
+            Ok(error) => error,
+            Err(error) => kind(error),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ClaudeHelperError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::from_anyhow(error, Self::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_kind_survives_anyhow() {
+        let inner: anyhow::Result<()> = Err(ClaudeHelperError::ApiError {
+            status: 429,
+            message: "rate limited".to_string(),
+        }
+        .into());
+        let error = inner.context("Agent failed").unwrap_err();
+
+        assert_eq!(ClaudeHelperError::exit_code_of(&error), 6);
+        assert!(matches!(
+            ClaudeHelperError::db(error),
+            ClaudeHelperError::ApiError { status: 429, .. }
+        ));
+
+        let other = ClaudeHelperError::from(anyhow::anyhow!("boom"));
+        assert_eq!(other.exit_code(), 1);
+        assert_eq!(other.to_string(), "boom");
+    }
+}
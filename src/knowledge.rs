@@ -0,0 +1,239 @@
+use crate::master::estimate::{keywords, similarity};
+use crate::project::Project;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Kinds of knowledge base entries
+pub const KNOWLEDGE_KINDS: &[&str] = &["convention", "decision"];
+
+/// Longest excerpt of each agent's output shown to the curator
+const CURATOR_EXCERPT_CHARS: usize = 2000;
+
+/// Most entries the curator may propose after one run
+const MAX_PROPOSALS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KnowledgeKind {
+    /// How things are done in the project, e.g. "errors use anyhow"
+    Convention,
+    /// A choice made earlier and why, e.g. "SQLite over Postgres for local data"
+    Decision,
+}
+
+impl KnowledgeKind {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "convention" => Ok(Self::Convention),
+            "decision" => Ok(Self::Decision),
+            _ => anyhow::bail!(
+                "Unknown knowledge kind '{}' (expected one of: {})",
+                s,
+                KNOWLEDGE_KINDS.join(", ")
+            ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Convention => "convention",
+            Self::Decision => "decision",
+        }
+    }
+}
+
+/// One convention or decision of a project
+#[derive(Debug, Clone, Serialize)]
+pub struct KnowledgeEntry {
+    pub id: i64,
+    /// Root directory of the project the entry belongs to
+    pub project: String,
+    pub kind: KnowledgeKind,
+    pub content: String,
+    /// Where the entry came from: `manual` or the run it was distilled from
+    pub source: String,
+    /// Proposed after a run and not yet accepted; proposals are not given to agents
+    pub proposed: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Root of the project in the current directory: the nearest directory with
+/// a `.git` or `.claude-helper.toml`, or the current directory itself
+pub fn current_project() -> Result<String> {
+    Ok(Project::current()?.root)
+}
+
+/// Entries worth telling an agent about for `task`, most relevant first.
+/// Conventions always qualify; decisions only when they share keywords
+/// with the task.
+pub fn relevant(entries: &[KnowledgeEntry], task: &str, limit: usize) -> Vec<KnowledgeEntry> {
+    let mut scored = rank(entries, task);
+    scored.retain(|(entry, score)| entry.kind == KnowledgeKind::Convention || *score > 0.0);
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(entry, _)| entry.clone())
+        .collect()
+}
+
+/// Entries matching `query` as a phrase or by shared keywords, best first
+pub fn search(entries: &[KnowledgeEntry], query: &str) -> Vec<KnowledgeEntry> {
+    let phrase = query.to_lowercase();
+    rank(entries, query)
+        .into_iter()
+        .filter(|(entry, score)| *score > 0.0 || entry.content.to_lowercase().contains(&phrase))
+        .map(|(entry, _)| entry.clone())
+        .collect()
+}
+
+fn rank<'a>(entries: &'a [KnowledgeEntry], text: &str) -> Vec<(&'a KnowledgeEntry, f64)> {
+    let wanted = keywords(text);
+    let mut scored: Vec<_> = entries
+        .iter()
+        .filter(|entry| !entry.proposed)
+        .map(|entry| (entry, similarity(&wanted, &keywords(&entry.content))))
+        .collect();
+
+    // Stable sort keeps newer entries first among equally relevant ones
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored
+}
+
+/// System prompt section listing `entries`, empty when there are none
+pub fn prompt_section(entries: &[KnowledgeEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|entry| format!("- ({}) {}", entry.kind.as_str(), entry.content))
+        .collect();
+    format!(
+        "Follow these conventions and earlier decisions of the project:\n{}",
+        lines.join("\n")
+    )
+}
+
+/// Task given to the agent that distills new entries from a successful run
+pub fn curator_task(task: &str, outputs: &[&str], known: &[KnowledgeEntry]) -> String {
+    let excerpts: Vec<String> = outputs
+        .iter()
+        .map(|output| output.chars().take(CURATOR_EXCERPT_CHARS).collect())
+        .collect();
+    let known: Vec<&str> = known.iter().map(|entry| entry.content.as_str()).collect();
+
+    format!(
+        "A run of the task below just succeeded. List at most {} project conventions \
+        or design decisions from it that future work on this project should follow. \
+        Only include lasting, project-specific points, not details of this task, and \
+        nothing already known. Return an empty list if there is nothing worth keeping.\n\n\
+        Task:\n{}\n\nAlready known:\n- {}\n\nAgent output:\n{}",
+        MAX_PROPOSALS,
+        task,
+        known.join("\n- "),
+        excerpts.join("\n---\n")
+    )
+}
+
+/// Output schema of the curator agent
+pub fn curator_schema() -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["kind", "content"],
+            "properties": {
+                "kind": { "type": "string", "enum": KNOWLEDGE_KINDS },
+                "content": { "type": "string" }
+            }
+        }
+    })
+}
+
+/// Parse the curator's answer into `(kind, content)` proposals
+pub fn parse_proposals(output: &str) -> Result<Vec<(KnowledgeKind, String)>> {
+    let value: Value = serde_json::from_str(output).context("Curator output is not JSON")?;
+    let proposals = value
+        .as_array()
+        .context("Curator output is not a list")?
+        .iter()
+        .filter_map(|item| {
+            let kind = KnowledgeKind::from_str(item.get("kind")?.as_str()?).ok()?;
+            let content = item.get("content")?.as_str()?.trim();
+            (!content.is_empty()).then(|| (kind, content.to_string()))
+        })
+        .take(MAX_PROPOSALS)
+        .collect();
+
+    Ok(proposals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, kind: KnowledgeKind, content: &str) -> KnowledgeEntry {
+        KnowledgeEntry {
+            id,
+            project: "/work/app".to_string(),
+            kind,
+            content: content.to_string(),
+            source: "manual".to_string(),
+            proposed: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_relevant_entries() {
+        let entries = vec![
+            entry(
+                1,
+                KnowledgeKind::Convention,
+                "Errors are returned with anyhow",
+            ),
+            entry(
+                2,
+                KnowledgeKind::Decision,
+                "Sessions are stored in Redis, not cookies",
+            ),
+            entry(
+                3,
+                KnowledgeKind::Decision,
+                "Billing uses the Stripe webhook flow",
+            ),
+        ];
+
+        let ids: Vec<i64> = relevant(&entries, "Expire stale sessions", 10)
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![2, 1]);
+
+        assert_eq!(relevant(&entries, "Expire stale sessions", 1)[0].id, 2);
+        assert_eq!(search(&entries, "stripe")[0].id, 3);
+    }
+
+    #[test]
+    fn test_parse_proposals() {
+        let proposals = parse_proposals(
+            r#"[{"kind": "decision", "content": "Use sqlx for database access"},
+                {"kind": "convention", "content": "  "},
+                {"kind": "rumour", "content": "Ignored"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            proposals,
+            vec![(
+                KnowledgeKind::Decision,
+                "Use sqlx for database access".to_string()
+            )]
+        );
+
+        assert!(prompt_section(&[]).is_empty());
+    }
+}
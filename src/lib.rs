@@ -3,13 +3,24 @@ pub mod analyzer;
 pub mod cache;
 pub mod config;
 pub mod db;
+pub mod embeddings;
+pub mod error;
+pub mod knowledge;
 pub mod master;
+pub mod pricing;
+pub mod project;
+pub mod redact;
+pub mod report;
 pub mod statusline;
+pub mod style;
+pub mod templates;
 pub mod tui;
+pub mod update;
 
 pub use analyzer::SessionAnalyzer;
 pub use cache::Cache;
 pub use config::Config;
+pub use error::ClaudeHelperError;
 pub use master::MasterCoder;
 pub use statusline::StatusLine;
 pub use tui::App;
@@ -1,11 +1,24 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use claude_helper::{
-    analyzer::SessionAnalyzer, config::Config, master::MasterCoder, statusline::StatusLine,
+    analyzer::{LiveOptions, SessionAnalyzer},
+    config::{display_value, Config},
+    db::{normalize_tag, normalize_variant, AgentRanking, Database},
+    embeddings::Embedder,
+    master::{archive, ExportFormat, MasterCoder, TaskHistory},
+    project::Project,
+    redact::{self, Redactor},
+    report::{Report, ReportFormat},
+    statusline::{StatusLine, StatusTarget, WatchOptions},
+    style,
+    templates::{self, CommandTemplate},
     tui::App,
+    update, ClaudeHelperError,
 };
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::{info, Level};
-use tracing_subscriber;
 
 #[derive(Parser)]
 #[command(name = "claude-helper")]
@@ -18,14 +31,28 @@ struct Cli {
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Make no network calls: usage comes from local Claude Code transcripts
+    /// and commands that need Claude (e.g. `run`) fail fast
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Print without color (same as setting NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Run a task with Master Coder orchestration
     Run {
-        /// The task description
-        task: String,
+        /// The task description, or `-` to read it from stdin
+        #[arg(required_unless_present = "task_file")]
+        task: Option<String>,
+
+        /// Read the task description from a file, e.g. a Markdown spec
+        #[arg(short = 'f', long, value_name = "PATH", conflicts_with = "task")]
+        task_file: Option<PathBuf>,
 
         /// Autonomy mode: conservative, balanced, trust, interactive
         #[arg(short, long, default_value = "balanced")]
@@ -38,6 +65,73 @@ enum Commands {
         /// Token budget for this task
         #[arg(short = 'b', long)]
         token_budget: Option<usize>,
+
+        /// Use a named budget from `[master_coder.budget_profiles]`, e.g.
+        /// quick, feature or overnight
+        #[arg(long, value_name = "NAME", conflicts_with = "token_budget")]
+        budget_profile: Option<String>,
+
+        /// Stop starting agents once the run cost more than this many
+        /// dollars, priced from the tokens its agents use
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Tag the run for filtering later, e.g. --tag backend (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// If the task may not fit in the 5-hour usage window, wait for the
+        /// window to reset instead of asking
+        #[arg(long)]
+        wait_for_reset: bool,
+
+        /// Write the files agents produce into the current directory
+        /// instead of the run's artifact directory
+        #[arg(long)]
+        apply: bool,
+
+        /// Reuse cached answers to identical agent requests (e.g. when
+        /// re-running the same plan) instead of paying for them again
+        #[arg(long)]
+        reuse_responses: bool,
+
+        /// Label the run with the prompt variant it uses (e.g. after editing
+        /// an agent template) so variants can be compared with `experiments`
+        #[arg(long, value_name = "LABEL")]
+        variant: Option<String>,
+
+        /// Scan the project's files, languages and tests before planning
+        /// (always on with `master_coder.scan_workspace`)
+        #[arg(long)]
+        scan_workspace: bool,
+    },
+
+    /// Estimate the tokens, cost and time of a task without running it
+    Estimate {
+        /// The task description, or `-` to read it from stdin
+        #[arg(required_unless_present = "task_file")]
+        task: Option<String>,
+
+        /// Read the task description from a file, e.g. a Markdown spec
+        #[arg(short = 'f', long, value_name = "PATH", conflicts_with = "task")]
+        task_file: Option<PathBuf>,
+
+        /// Maximum number of parallel agents
+        #[arg(long)]
+        max_agents: Option<usize>,
+
+        /// Token budget to compare the estimate against
+        #[arg(short = 'b', long)]
+        token_budget: Option<usize>,
+
+        /// Compare against a named budget from `[master_coder.budget_profiles]`
+        #[arg(long, value_name = "NAME", conflicts_with = "token_budget")]
+        budget_profile: Option<String>,
+
+        /// Scan the project's files, languages and tests before planning
+        /// (always on with `master_coder.scan_workspace`)
+        #[arg(long)]
+        scan_workspace: bool,
     },
 
     /// Show current token usage status
@@ -45,6 +139,10 @@ enum Commands {
         /// Show detailed breakdown
         #[arg(short, long)]
         detailed: bool,
+
+        /// Show organization usage per member from the Admin API
+        #[arg(long, conflicts_with = "detailed")]
+        org: bool,
     },
 
     /// Watch token usage in real-time
@@ -52,16 +150,70 @@ enum Commands {
         /// Update interval in seconds
         #[arg(short, long, default_value = "30")]
         interval: u64,
+
+        /// Usage percentages that trigger --notify and --exec, comma-separated
+        #[arg(short, long, value_delimiter = ',', default_value = "80,95",
+              value_parser = clap::value_parser!(u8).range(1..=100))]
+        threshold: Vec<u8>,
+
+        /// Show a desktop notification when a threshold is crossed
+        #[arg(long)]
+        notify: bool,
+
+        /// Run a shell command when a threshold is crossed. It gets
+        /// CLAUDE_HELPER_WINDOW (5h or 7d), CLAUDE_HELPER_THRESHOLD and
+        /// CLAUDE_HELPER_PERCENT in its environment.
+        #[arg(long, value_name = "CMD")]
+        exec: Option<String>,
+
+        /// Exit once the 5-hour window resets
+        #[arg(long)]
+        until_reset: bool,
     },
 
     /// Display status line (for Claude Code integration)
-    Statusline,
+    Statusline {
+        /// Output target: line for Claude Code, or json with every
+        /// computed field for other statusbars (waybar, polybar, xbar)
+        #[arg(long, default_value = "line")]
+        target: String,
+    },
+
+    /// Update claude-helper to the latest GitHub release
+    Upgrade {
+        /// Only report whether a newer release exists
+        #[arg(long)]
+        check: bool,
+    },
 
     /// Analyze sessions for optimization opportunities
     Analyze {
         /// Number of recent sessions to analyze
         #[arg(short, long, default_value = "10")]
         last: usize,
+
+        /// Follow the active session, updating its token composition and
+        /// optimizations as Claude Code writes to it
+        #[arg(long, conflicts_with = "last")]
+        watch: bool,
+
+        /// Context window percentages to warn about in --watch, comma-separated
+        #[arg(long, value_delimiter = ',', default_value = "70", requires = "watch",
+              value_parser = clap::value_parser!(u8).range(1..=100))]
+        context_threshold: Vec<u8>,
+
+        /// Show a desktop notification when a context threshold is crossed
+        #[arg(long, requires = "watch")]
+        notify: bool,
+
+        /// Compress sessions not written to for this many days to .jsonl.gz;
+        /// they stay analyzable
+        #[arg(long, value_name = "DAYS", conflicts_with_all = ["last", "watch"])]
+        archive: Option<u64>,
+
+        /// Count dismissed and snoozed optimizations too
+        #[arg(long)]
+        show_dismissed: bool,
     },
 
     /// Get optimization suggestions
@@ -73,10 +225,118 @@ enum Commands {
         /// Analyze last N sessions
         #[arg(short, long)]
         last: Option<usize>,
+
+        /// Show optimizations stored by the afterResponse hook
+        #[arg(long)]
+        history: bool,
+
+        /// Include already-applied optimizations in --history
+        #[arg(long, requires = "history")]
+        all: bool,
+
+        /// Mark a stored optimization as applied
+        #[arg(long, value_name = "ID")]
+        mark_applied: Option<i64>,
+
+        /// Dismiss a stored optimization: it is no longer shown, nor stored
+        /// again when sessions show it
+        #[arg(long, value_name = "ID", conflicts_with = "mark_applied")]
+        dismiss: Option<i64>,
+
+        /// Hide a stored optimization until --until
+        #[arg(long, value_name = "ID", conflicts_with_all = ["mark_applied", "dismiss"])]
+        snooze: Option<i64>,
+
+        /// When a snoozed optimization comes back, e.g. 3d, 2w or 2026-11-01
+        #[arg(long, value_name = "TIME", default_value = "7d", requires = "snooze")]
+        until: String,
+
+        /// Include dismissed and snoozed optimizations
+        #[arg(long)]
+        show_dismissed: bool,
+
+        /// Suggest Claude Code hooks for recurring tool patterns (lint
+        /// reruns, destructive or often-approved commands) and offer to
+        /// add them to settings.json
+        #[arg(long, conflicts_with_all = ["session", "history", "mark_applied"])]
+        hooks: bool,
+
+        /// Flag code sessions wrote into this repository again although it
+        /// already existed (per git blame), and offer to point Claude at the
+        /// existing versions in CLAUDE.md
+        #[arg(long, conflicts_with_all = ["session", "history", "mark_applied", "hooks"])]
+        redundancy: bool,
+    },
+
+    /// Combined report of usage, session analysis, top optimizations and
+    /// agent performance
+    Report {
+        /// Number of days to cover
+        #[arg(short, long, default_value = "7")]
+        days: usize,
+
+        /// Output format: markdown, html, json
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+
+        /// Write the report to a file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// Only count runs with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
+    /// Run a suite of canned tasks with each model and compare tokens, time
+    /// and a simple quality score
+    Bench {
+        /// Suite of tasks to run: basic
+        #[arg(short, long, default_value = "basic")]
+        suite: String,
+
+        /// Models to compare, e.g. sonnet,haiku (default: the configured model)
+        #[arg(short, long, value_delimiter = ',')]
+        models: Vec<String>,
+
+        /// Label the results with the prompt variant being benchmarked
+        #[arg(long, value_name = "LABEL")]
+        variant: Option<String>,
+    },
+
+    /// List every model API call with its tokens, latency, status and cost
+    Audit {
+        /// Only calls since a time: 30m, 24h, 7d, 2w or a date (YYYY-MM-DD)
+        #[arg(short, long, default_value = "24h")]
+        since: String,
+
+        /// Print the calls as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Dollar spend of the recorded API calls, priced per model with cache
+    /// discounts
+    Cost {
+        /// Period to cover: 30m, 24h, 7d, 2w or a start date (YYYY-MM-DD)
+        #[arg(short, long, default_value = "7d")]
+        period: String,
+
+        /// Group spend by: model, agent, day
+        #[arg(short, long, default_value = "model")]
+        by: String,
+
+        /// Print the breakdown as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Open interactive TUI
-    Tui,
+    Tui {
+        /// Follow a Master Coder run started elsewhere (shown as "Run ID" by `run`)
+        #[arg(long, value_name = "RUN_ID")]
+        attach: Option<String>,
+    },
 
     /// Configuration management
     Config {
@@ -84,6 +344,9 @@ enum Commands {
         action: ConfigAction,
     },
 
+    /// Guided first-run setup (auth, budget, mode, status line, integration)
+    Init,
+
     /// Install complete Claude Code integration (status line + hooks + commands)
     #[command(alias = "install-statusline")]
     InstallClaudeIntegration,
@@ -99,6 +362,58 @@ enum Commands {
         #[command(subcommand)]
         action: AgentAction,
     },
+
+    /// Inspect past Master Coder runs
+    Tasks {
+        #[command(subcommand)]
+        action: TaskAction,
+    },
+
+    /// Share runs: bundle one with its database rows, log and files into
+    /// a tarball, or load such a bundle
+    Runs {
+        #[command(subcommand)]
+        action: RunsAction,
+    },
+
+    /// Database access for power users
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+
+    /// Slash command templates to install into `.claude/commands`
+    #[command(name = "commands")]
+    CommandTemplates {
+        #[command(subcommand)]
+        action: CommandsAction,
+    },
+
+    /// Generate starter files for Claude Code from this repository and
+    /// your session history
+    Generate {
+        #[command(subcommand)]
+        what: GenerateTarget,
+    },
+
+    /// Manage the local cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Knowledge base of the current project's conventions and past
+    /// decisions, which agents are told about
+    Kb {
+        #[command(subcommand)]
+        action: KbAction,
+    },
+
+    /// Compare prompt variants labelled with `run --variant`
+    Experiments {
+        #[command(subcommand)]
+        action: ExperimentAction,
+    },
 }
 
 #[derive(Subcommand)]
@@ -106,9 +421,41 @@ enum ConfigAction {
     /// Show current configuration
     Show,
 
+    /// Print a single setting, e.g. `config get master_coder.token_budget`
+    Get {
+        /// Dotted key (section.field)
+        key: String,
+    },
+
+    /// Change a single setting, e.g. `config set statusline.update_interval 10`
+    Set {
+        /// Dotted key (section.field)
+        key: String,
+
+        /// New value, parsed according to the setting's type
+        value: String,
+    },
+
     /// Set API key
     SetApiKey,
 
+    /// Set the Admin API key used for organization usage (`status --org`)
+    SetAdminKey,
+
+    /// Check the config file, overrides, and authentication
+    Validate,
+
+    /// Trust this project's .claude-helper.toml as it is now, so settings
+    /// other projects may not change (hosts, commands, credentials) apply too
+    Trust {
+        /// Stop trusting it instead
+        #[arg(long)]
+        revoke: bool,
+    },
+
+    /// Show settings that differ from the defaults and where they come from
+    Diff,
+
     /// Edit configuration file
     Edit,
 
@@ -124,83 +471,592 @@ enum AgentAction {
     /// List available agent types
     List,
 
+    /// Rank agent types by tokens, time or success rate
+    Top {
+        /// Rank by: tokens (fewest per run), time (fastest), success (highest rate)
+        #[arg(long, default_value = "success")]
+        by: String,
+
+        /// Number of agent types to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Show weekly success rates per capability
+    Trends {
+        /// Number of recent weeks to cover
+        #[arg(short, long, default_value = "8")]
+        weeks: usize,
+    },
+
     /// Show agent history
     History {
         /// Number of recent agents to show
         #[arg(short, long, default_value = "20")]
         last: usize,
+
+        /// Only agents with this capability, e.g. testing or code-writing
+        #[arg(short, long)]
+        capability: Option<String>,
+
+        /// Only failed executions
+        #[arg(long)]
+        failed: bool,
+
+        /// Only executions since a time: 30m, 24h, 7d, 2w or a date (YYYY-MM-DD)
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Only agents whose task contains this text (case-insensitive)
+        #[arg(short, long, value_name = "TEXT")]
+        task_contains: Option<String>,
+
+        /// Only agents of runs with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Print the executions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TaskAction {
+    /// List recent tasks with status, tokens and cost
+    List {
+        /// Number of recent tasks to show
+        #[arg(short, long, default_value = "20")]
+        last: usize,
+
+        /// Only tasks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only tasks run in the project containing PATH (default: the
+        /// current one), including other clones of its git remote
+        #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = ".")]
+        project: Option<PathBuf>,
+    },
+
+    /// Find past tasks by meaning (with an embeddings provider) or keywords,
+    /// e.g. "auth overhaul" also finds "refactor login flow"
+    Search {
+        query: String,
+
+        /// Also search recent Claude Code sessions (analyzer.history_depth)
+        #[arg(long)]
+        sessions: bool,
+
+        /// Most results to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Show the analysis, plan, per-agent outcomes and errors of a task
+    Show {
+        /// Task ID as shown by `tasks list`
+        id: usize,
+    },
+
+    /// Compare two tasks side by side: plan, tokens, cost, time and outcome,
+    /// e.g. after rerunning a task with another model or prompt variant
+    Compare {
+        /// Task ID as shown by `tasks list`, the baseline
+        first: usize,
+
+        /// Task ID to compare against it
+        second: usize,
+    },
+
+    /// List runs paused because the token budget or rate limiting stopped
+    /// their agents
+    Queue,
+
+    /// Run the agents of a paused run, waiting for its usage window to reset
+    /// first if it hasn't yet
+    Resume {
+        /// Queue ID as shown by `tasks queue` (default: the next one due)
+        id: Option<i64>,
+    },
+
+    /// Drop a paused run from the queue without running it
+    Dequeue {
+        /// Queue ID as shown by `tasks queue`
+        id: i64,
+    },
+
+    /// Export a task as a document with every agent's prompt and output,
+    /// e.g. to attach to a PR, or as a commit message and PR body (`pr`)
+    Export {
+        /// Task ID as shown by `tasks list`
+        id: usize,
+
+        /// Output format: md, or pr for a conventional-commit subject and a
+        /// PR body (use with `git commit -F`)
+        #[arg(short, long, default_value = "md")]
+        format: String,
+
+        /// Write the document to a file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunsAction {
+    /// Bundle a task's database rows, run log and the files its agents wrote
+    /// into a .tar.gz, e.g. to share a full reproduction with a colleague
+    Archive {
+        /// Task ID as shown by `tasks list`
+        id: usize,
+
+        /// Where to write the archive (default: run-<ID>.tar.gz)
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+
+    /// Load a run archived with `runs archive` into the local history
+    Import {
+        /// The .tar.gz written by `runs archive`
+        path: PathBuf,
+    },
+
+    /// Remove old run logs and the files their agents wrote; the task
+    /// history stays. Without --older-than, `[retention]` decides.
+    Clean {
+        /// Remove runs last touched before this, e.g. 30d or 2w
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbAction {
+    /// Run a read-only SELECT query and print the results as a table
+    Query {
+        /// The SQL query (SELECT only)
+        sql: String,
+    },
+
+    /// Rebuild the daily usage rollup from raw task history
+    Rollup {
+        /// Number of recent days to display afterwards
+        #[arg(short, long, default_value = "14")]
+        days: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum CommandsAction {
+    /// List the templates and their parameters
+    List,
+
+    /// Print a template with its parameters filled in
+    Show {
+        name: String,
+
+        /// Parameter value, e.g. --set test_command="npm test"
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        values: Vec<String>,
+    },
+
+    /// Copy a template into the project's .claude/commands
+    Install {
+        name: String,
+
+        /// Parameter value, e.g. --set test_command="npm test"
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        values: Vec<String>,
+
+        /// Install into ~/.claude/commands for every project
+        #[arg(long)]
+        global: bool,
+
+        /// Replace a command that already exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GenerateTarget {
+    /// CLAUDE.md with the build/test commands, layout and most-read files,
+    /// shown as a diff before it is written
+    ClaudeMd {
+        /// Write without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// List cached entries with size, age, TTL remaining and hit counts
+    Stats,
+
+    /// Clear cached data (everything, a namespace, or a glob pattern)
+    Clear {
+        /// Namespace such as `usage`, `pricing`, `analysis/<project>`,
+        /// or a pattern like `analysis/*/session-*`
+        namespace: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KbAction {
+    /// Add a convention or decision, e.g. "Errors are returned with anyhow"
+    Add {
+        content: String,
+
+        /// Kind of entry: convention, decision
+        #[arg(short, long, default_value = "convention")]
+        kind: String,
+    },
+
+    /// List the project's entries
+    List {
+        /// Show the entries proposed after runs, awaiting `kb accept`
+        #[arg(long)]
+        proposed: bool,
+    },
+
+    /// Find entries by phrase or keywords
+    Search { query: String },
+
+    /// Accept a proposed entry so agents are told about it
+    Accept {
+        /// Entry ID as shown by `kb list --proposed`
+        id: i64,
+    },
+
+    /// Remove an entry or reject a proposal
+    Remove {
+        /// Entry ID as shown by `kb list`
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExperimentAction {
+    /// List the variant labels used so far with their number of runs
+    List,
+
+    /// Compare success rate, tokens and review scores of two variants
+    Compare {
+        /// First variant label
+        a: String,
+
+        /// Second variant label
+        b: String,
+
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let cli = Cli::parse();
 
+    if let Err(e) = run(cli).await {
+        let message = format!("{:?}", e);
+        eprintln!("Error: {}", redact::active().redact(&message));
+        std::process::exit(ClaudeHelperError::exit_code_of(&e));
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
     // Initialize logging
     let log_level = if cli.verbose {
         Level::DEBUG
     } else {
         Level::INFO
     };
+    // Logging starts before the config is loaded, so only the flag and
+    // NO_COLOR apply here, and logs piped to a file never get escape codes
+    let log_color = !cli.no_color && style::color_enabled() && std::io::stderr().is_terminal();
     tracing_subscriber::fmt()
         .with_max_level(log_level)
         .with_target(false)
+        .with_ansi(log_color)
+        .with_writer(|| redact::RedactingStderr)
         .init();
 
     info!("Claude Helper starting...");
 
-    // Load configuration
-    let config = Config::load().await?;
+    // `config validate` loads the config itself, so it can report a broken file
+    if let Commands::Config {
+        action: ConfigAction::Validate,
+    } = cli.command
+    {
+        return validate_config(cli.offline).await;
+    }
+
+    // Load configuration (file + project overlay + environment)
+    let mut config = Config::load_effective().await?;
+    if cli.offline {
+        config.network.offline = true;
+    }
+    style::install(&config.output, cli.no_color);
+    redact::install(Redactor::from_config(&config.redaction)?);
 
     // Execute command
     match cli.command {
         Commands::Run {
             task,
+            task_file,
             mode,
             max_agents,
             token_budget,
+            budget_profile,
+            max_cost,
+            tags,
+            wait_for_reset,
+            apply,
+            reuse_responses,
+            variant,
+            scan_workspace,
         } => {
-            let mut master = MasterCoder::new(config, mode).await?;
+            let task = read_task(task, task_file)?;
+            let tags = tags
+                .iter()
+                .map(|tag| normalize_tag(tag))
+                .collect::<Result<Vec<_>>>()?;
+            let variant = variant.as_deref().map(normalize_variant).transpose()?;
+            let token_budget = match budget_profile {
+                Some(name) => Some(config.master_coder.budget_profile(&name)?),
+                None => token_budget,
+            };
+            if max_cost.is_some_and(|cost| cost.is_nan() || cost <= 0.0) {
+                anyhow::bail!("--max-cost must be a positive number of dollars");
+            }
+            let db = open_database(&config).await?;
+            let mut master = MasterCoder::new(config, mode, db).await?;
+            master.set_tags(tags);
+            master.set_variant(variant);
+            master.set_wait_for_reset(wait_for_reset);
+            master.set_apply(apply);
+            if reuse_responses {
+                master.set_reuse_responses(true);
+            }
+            if scan_workspace {
+                master.set_scan_workspace(true);
+            }
             if let Some(max) = max_agents {
                 master.set_max_agents(max);
             }
             if let Some(budget) = token_budget {
                 master.set_token_budget(budget);
             }
+            if let Some(cost) = max_cost {
+                master.set_max_cost(cost);
+            }
             master.execute(&task).await?;
         }
 
-        Commands::Status { detailed } => {
-            let statusline = StatusLine::new(config).await?;
-            statusline.show_status(detailed).await?;
+        Commands::Estimate {
+            task,
+            task_file,
+            max_agents,
+            token_budget,
+            budget_profile,
+            scan_workspace,
+        } => {
+            let task = read_task(task, task_file)?;
+            let token_budget = match budget_profile {
+                Some(name) => Some(config.master_coder.budget_profile(&name)?),
+                None => token_budget,
+            };
+            let db = open_database(&config).await?;
+            let mode = config.master_coder.default_mode.clone();
+            let mut master = MasterCoder::new(config, mode, db).await?;
+            if scan_workspace {
+                master.set_scan_workspace(true);
+            }
+            if let Some(max) = max_agents {
+                master.set_max_agents(max);
+            }
+            if let Some(budget) = token_budget {
+                master.set_token_budget(budget);
+            }
+            master.estimate(&task).await?.print(master.token_budget());
+        }
+
+        Commands::Status { detailed, org } => {
+            let mut statusline = StatusLine::new(config.clone()).await?;
+            if org {
+                statusline.show_org_status().await?;
+            } else {
+                if detailed {
+                    // Historical data is optional; fall back to basic info without a database
+                    if let Ok(db) = open_database(&config).await {
+                        statusline = statusline.with_database(db);
+                    }
+                }
+                statusline.show_status(detailed).await?;
+            }
+            if config.statusline.check_updates {
+                if let Some(hint) = update::update_hint(&config).await {
+                    println!("\n  {} {}", style::pick("↑", "^"), hint);
+                }
+            }
         }
 
-        Commands::Watch { interval } => {
+        Commands::Watch {
+            interval,
+            threshold,
+            notify,
+            exec,
+            until_reset,
+        } => {
+            let options = WatchOptions {
+                thresholds: threshold,
+                notify,
+                exec,
+                until_reset,
+            };
             let statusline = StatusLine::new(config).await?;
-            statusline.watch(interval).await?;
+            statusline.watch(interval, &options).await?;
         }
 
-        Commands::Statusline => {
+        Commands::Statusline { target } => {
+            let target = StatusTarget::from_str(&target)?;
             let statusline = StatusLine::new(config).await?;
-            statusline.render_line().await?;
+            statusline.render(target).await?;
         }
 
-        Commands::Analyze { last } => {
-            let analyzer = SessionAnalyzer::new(config).await?;
-            analyzer.analyze_sessions(last).await?;
+        Commands::Upgrade { check } => {
+            update::Updater::new(&config)?.upgrade(check).await?;
         }
 
-        Commands::Optimize { session, last } => {
-            let analyzer = SessionAnalyzer::new(config).await?;
-            if let Some(sid) = session {
-                analyzer.optimize_session(&sid).await?;
+        Commands::Analyze {
+            last,
+            watch,
+            context_threshold,
+            notify,
+            archive,
+            show_dismissed,
+        } => {
+            let db = open_database(&config).await?;
+            let analyzer = SessionAnalyzer::new(config, db).await?;
+            if let Some(days) = archive {
+                analyzer.archive_sessions(days).await?;
+            } else if watch {
+                let options = LiveOptions {
+                    context_thresholds: context_threshold,
+                    notify,
+                };
+                analyzer.watch_session(&options).await?;
             } else {
-                analyzer.optimize_recent(last.unwrap_or(10)).await?;
+                analyzer.analyze_sessions(last, show_dismissed).await?;
+            }
+        }
+
+        Commands::Optimize {
+            session,
+            last,
+            history,
+            all,
+            mark_applied,
+            dismiss,
+            snooze,
+            until,
+            show_dismissed,
+            hooks,
+            redundancy,
+        } => {
+            use claude_helper::db::{parse_until, OptimizationStatus};
+
+            let db = open_database(&config).await?;
+            let analyzer = SessionAnalyzer::new(config, db).await?;
+            if let Some(id) = mark_applied {
+                analyzer.set_status(id, OptimizationStatus::Applied).await?;
+            } else if let Some(id) = dismiss {
+                analyzer
+                    .set_status(id, OptimizationStatus::Dismissed)
+                    .await?;
+            } else if let Some(id) = snooze {
+                let until = OptimizationStatus::Snoozed(parse_until(&until)?);
+                analyzer.set_status(id, until).await?;
+            } else if hooks {
+                analyzer.suggest_hooks(last.unwrap_or(10)).await?;
+            } else if redundancy {
+                let root = PathBuf::from(Project::current()?.root);
+                analyzer.find_redundancy(&root, last.unwrap_or(20)).await?;
+            } else if history {
+                analyzer
+                    .show_history(last.unwrap_or(20), all, show_dismissed)
+                    .await?;
+            } else if let Some(sid) = session {
+                analyzer.optimize_session(&sid, show_dismissed).await?;
+            } else {
+                analyzer
+                    .optimize_recent(last.unwrap_or(10), show_dismissed)
+                    .await?;
+            }
+        }
+
+        Commands::Report {
+            days,
+            format,
+            output,
+            tag,
+        } => {
+            let format = ReportFormat::from_str(&format)?;
+            let tag = tag.as_deref().map(normalize_tag).transpose()?;
+            let db = open_database(&config).await?;
+            let report = Report::generate(&config, db, days, tag)
+                .await?
+                .render(format)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, report)
+                        .with_context(|| format!("Failed to write {:?}", path))?;
+                    println!("{} Report written to {}", style::ok(), path.display());
+                }
+                None => print!("{}", report),
             }
         }
 
-        Commands::Tui => {
-            let app = App::new(config).await?;
+        Commands::Bench {
+            suite,
+            models,
+            variant,
+        } => {
+            use claude_helper::master::bench;
+
+            let models = if models.is_empty() {
+                let model = config.master_coder.model.as_deref().unwrap_or("sonnet");
+                vec![model.to_string()]
+            } else {
+                models
+            };
+            let variant = variant.as_deref().map(normalize_variant).transpose()?;
+            let db = open_database(&config).await?;
+            let results = bench::run(&config, db, &suite, &models, variant.as_deref()).await?;
+            bench::print_comparison(&results);
+        }
+
+        Commands::Audit { since, json } => {
+            show_audit(&config, &since, json).await?;
+        }
+
+        Commands::Cost { period, by, json } => {
+            show_cost(&config, &period, &by, json).await?;
+        }
+
+        Commands::Tui { attach } => {
+            let db = open_database(&config).await?;
+            let mut app = App::new(config, db).await?;
+            if let Some(run_id) = attach {
+                app.attach(&run_id)?;
+            }
             app.run().await?;
         }
 
@@ -208,6 +1064,10 @@ async fn main() -> Result<()> {
             handle_config_action(action, &config).await?;
         }
 
+        Commands::Init => {
+            handle_init().await?;
+        }
+
         Commands::InstallClaudeIntegration => {
             install_claude_integration().await?;
         }
@@ -223,33 +1083,512 @@ async fn main() -> Result<()> {
         Commands::Agents { action } => {
             handle_agent_action(action, &config).await?;
         }
+
+        Commands::Runs { action } => {
+            handle_runs_action(action, &config).await?;
+        }
+
+        Commands::Tasks { action } => {
+            let db = open_database(&config).await?;
+            let tasks = TaskHistory::new(db.clone());
+            match action {
+                TaskAction::List { last, tag, project } => {
+                    let tag = tag.as_deref().map(normalize_tag).transpose()?;
+                    let project = project
+                        .map(|path| {
+                            let dir = path.canonicalize().with_context(|| {
+                                format!("No such directory: {}", path.display())
+                            })?;
+                            anyhow::Ok(Project::containing(&dir))
+                        })
+                        .transpose()?;
+                    tasks
+                        .show_list(last, tag.as_deref(), project.as_ref())
+                        .await?
+                }
+                TaskAction::Search {
+                    query,
+                    sessions,
+                    limit,
+                } => {
+                    let embedder = Embedder::from_config(&config).unwrap_or_else(|e| {
+                        tracing::warn!("Searching by keywords, embeddings unavailable: {:#}", e);
+                        None
+                    });
+                    let sessions = if sessions {
+                        SessionAnalyzer::new(config.clone(), db)
+                            .await?
+                            .list_sessions(config.analyzer.history_depth)?
+                    } else {
+                        Vec::new()
+                    };
+                    tasks
+                        .search(&query, embedder.as_ref(), &sessions, limit)
+                        .await?
+                }
+                TaskAction::Show { id } => tasks.show(id).await?,
+                TaskAction::Compare { first, second } => tasks.compare(first, second).await?,
+                TaskAction::Queue => tasks.show_queue().await?,
+                TaskAction::Resume { id } => {
+                    let mode = config.master_coder.default_mode.clone();
+                    let mut master = MasterCoder::new(config, mode, db).await?;
+                    master.resume(id).await?;
+                }
+                TaskAction::Dequeue { id } => tasks.dequeue(id).await?,
+                TaskAction::Export { id, format, output } => {
+                    let document = tasks.export(id, ExportFormat::from_str(&format)?).await?;
+                    match output {
+                        Some(path) => {
+                            std::fs::write(&path, document)
+                                .with_context(|| format!("Failed to write {:?}", path))?;
+                            println!("{} Task {} exported to {}", style::ok(), id, path.display());
+                        }
+                        None => print!("{}", document),
+                    }
+                }
+            }
+        }
+
+        Commands::CommandTemplates { action } => {
+            handle_commands_action(action)?;
+        }
+
+        Commands::Generate { what } => match what {
+            GenerateTarget::ClaudeMd { yes } => {
+                let root = PathBuf::from(Project::current()?.root);
+                let db = open_database(&config).await?;
+                let analyzer = SessionAnalyzer::new(config, db).await?;
+                analyzer.generate_claude_md(&root, yes).await?;
+            }
+        },
+
+        Commands::Cache { action } => {
+            handle_cache_action(action)?;
+        }
+
+        Commands::Db { action } => {
+            handle_db_action(action, &config).await?;
+        }
+
+        Commands::Kb { action } => {
+            handle_kb_action(action, &config).await?;
+        }
+
+        Commands::Experiments { action } => {
+            handle_experiment_action(action, &config).await?;
+        }
     }
 
     Ok(())
 }
 
+/// Open the process-wide database handle shared by all components
+/// The task given on the command line, on stdin (`-`) or in a file.
+/// Only trailing whitespace is trimmed so Markdown specs keep their layout.
+fn read_task(task: Option<String>, task_file: Option<PathBuf>) -> Result<String> {
+    use std::io::IsTerminal;
+
+    let task = match (task, task_file) {
+        (_, Some(path)) => std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read task file {:?}", path))?,
+        (Some(task), None) if task == "-" => {
+            if std::io::stdin().is_terminal() {
+                eprintln!("Reading the task from stdin, finish with Ctrl+D");
+            }
+            std::io::read_to_string(std::io::stdin()).context("Failed to read task from stdin")?
+        }
+        (Some(task), None) => task,
+        (None, None) => anyhow::bail!("No task given"),
+    };
+
+    let task = task.trim_end();
+    if task.trim().is_empty() {
+        anyhow::bail!("The task description is empty");
+    }
+    Ok(task.to_string())
+}
+
+async fn open_database(config: &Config) -> Result<Arc<Database>> {
+    Ok(Arc::new(Database::new(config).await?))
+}
+
+async fn handle_runs_action(action: RunsAction, config: &Config) -> Result<()> {
+    use claude_helper::db::parse_since;
+    use claude_helper::master::retention;
+
+    match action {
+        RunsAction::Archive { id, output } => {
+            let db = open_database(config).await?;
+            let path = output.unwrap_or_else(|| PathBuf::from(format!("run-{}.tar.gz", id)));
+            let contents = archive::archive_run(&db, id, &path).await?;
+            println!(
+                "{} Task {}{} archived to {}",
+                style::ok(),
+                id,
+                describe_archive(&contents),
+                path.display()
+            );
+            println!(
+                "  Load it elsewhere with: claude-helper runs import {}",
+                path.display()
+            );
+        }
+        RunsAction::Import { path } => {
+            let db = open_database(config).await?;
+            let (id, contents) = archive::import_run(&db, &path).await?;
+            println!(
+                "{} Imported as task {}{}",
+                style::ok(),
+                id,
+                describe_archive(&contents)
+            );
+            println!("  Show it with: claude-helper tasks show {}", id);
+            if let Some(run_id) = contents.run_id.filter(|_| contents.has_log) {
+                println!(
+                    "  Replay its log with: claude-helper tui --attach {}",
+                    run_id
+                );
+            }
+        }
+        RunsAction::Clean {
+            older_than,
+            dry_run,
+        } => {
+            let older_than = older_than.as_deref().map(parse_since).transpose()?;
+            let cleaned = retention::clean_runs(&config.retention, older_than, dry_run)?;
+            println!(
+                "{} {} {} run logs and {} artifact directories ({:.1} MB)",
+                style::ok(),
+                if dry_run { "Would remove" } else { "Removed" },
+                cleaned.logs,
+                cleaned.artifact_dirs,
+                cleaned.bytes as f64 / 1_000_000.0
+            );
+        }
+    }
+    Ok(())
+}
+
+/// What came along with an archived task's database rows
+fn describe_archive(contents: &archive::ArchiveContents) -> String {
+    let mut parts = Vec::new();
+    if contents.has_log {
+        parts.push("run log".to_string());
+    }
+    if contents.files > 0 {
+        parts.push(format!("{} file(s)", contents.files));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" with its {}", parts.join(" and "))
+    }
+}
+
+async fn handle_init() -> Result<()> {
+    use dialoguer::Confirm;
+
+    let config = Config::load().await?.setup_wizard()?;
+    config.save().await?;
+    println!(
+        "\n{} Configuration saved to {:?}",
+        style::ok(),
+        Config::config_file()?
+    );
+
+    let install = Confirm::new()
+        .with_prompt("Install Claude Code integration (status line, hooks, slash commands)?")
+        .default(true)
+        .interact()?;
+
+    if install {
+        println!();
+        install_claude_integration().await?;
+    }
+
+    println!(
+        "\n{} Setup complete! Try `claude-helper status` or `claude-helper tui`.",
+        style::icon("🎉")
+    );
+    Ok(())
+}
+
 async fn handle_config_action(action: ConfigAction, config: &Config) -> Result<()> {
     match action {
         ConfigAction::Show => {
             config.show()?;
         }
+        ConfigAction::Get { key } => {
+            println!("{}", display_value(&config.get_value(&key)?)?);
+        }
+        ConfigAction::Set { key, value } => {
+            // Edit the file itself, without project/env overrides baked in
+            let mut config = Config::load().await?;
+            config.set_value(&key, &value)?;
+            config.save().await?;
+            println!(
+                "{} {} = {}",
+                style::ok(),
+                key,
+                display_value(&config.get_value(&key)?)?
+            );
+        }
         ConfigAction::SetApiKey => {
             Config::set_api_key().await?;
         }
+        ConfigAction::SetAdminKey => {
+            Config::set_admin_key().await?;
+        }
         ConfigAction::Edit => {
             Config::edit()?;
         }
         ConfigAction::Reset => {
             Config::reset().await?;
         }
+        ConfigAction::Validate => {
+            validate_config(config.network.offline).await?;
+        }
+        ConfigAction::Trust { revoke } => {
+            let path = claude_helper::config::layers::find_project_file().with_context(|| {
+                format!(
+                    "No {} in this directory or its parents",
+                    claude_helper::config::layers::PROJECT_FILE
+                )
+            })?;
+            let mut config = Config::load().await?;
+            if revoke {
+                if !config.trust.revoke(&path) {
+                    println!("{} is not trusted", path.display());
+                    return Ok(());
+                }
+                config.save().await?;
+                println!("{} No longer trusting {}", style::ok(), path.display());
+            } else {
+                let refused = config
+                    .project_overlay()?
+                    .map(|project| project.refused)
+                    .unwrap_or_default();
+                config.trust_project_file(&path)?;
+                config.save().await?;
+                println!("{} Trusted {}", style::ok(), path.display());
+                if !refused.is_empty() {
+                    println!("  Now applying: {}", refused.join(", "));
+                }
+            }
+        }
+        ConfigAction::Diff => {
+            let diffs = config.diff_from_defaults()?;
+            if diffs.is_empty() {
+                println!("All settings are at their defaults");
+            }
+            for diff in diffs {
+                let default = match &diff.default {
+                    Some(value) => display_value(value)?,
+                    None => "unset".to_string(),
+                };
+                println!(
+                    "{} = {}  (default: {}, from {})",
+                    diff.key,
+                    display_value(&diff.value)?,
+                    default,
+                    diff.source
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run every config check and report each one, failing if any did. The
+/// config is loaded here rather than at startup, so a file that does not
+/// load is reported as a failed check.
+async fn validate_config(offline: bool) -> Result<()> {
+    use claude_helper::style::Colorize;
+
+    let mut checks: Vec<(&str, Result<()>)> = Vec::new();
+    let config = match Config::load().await {
+        Ok(_) => {
+            checks.push(("Config file parses", Ok(())));
+            match Config::load_effective().await {
+                Ok(config) => {
+                    checks.push(("Project and environment overrides apply", Ok(())));
+                    Some(config)
+                }
+                Err(e) => {
+                    checks.push(("Project and environment overrides apply", Err(e.into())));
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            checks.push(("Config file parses", Err(e.into())));
+            None
+        }
+    };
+
+    if let Some(mut config) = config {
+        config.network.offline |= offline;
+        checks.push(("Settings are valid", config.validate()));
+        checks.push((
+            "Network settings load",
+            config.network.client_builder().map(|_| ()),
+        ));
+        checks.push((
+            "Authentication works",
+            config
+                .auth
+                .validate(&config.network)
+                .await
+                .map_err(Into::into),
+        ));
+    }
+
+    let mut failed = 0;
+    for (name, result) in checks {
+        match result {
+            Ok(()) => println!("{} {}", style::ok().green(), name),
+            Err(e) => {
+                failed += 1;
+                println!("{} {}: {:#}", style::fail().red(), name, e);
+            }
+        }
+    }
+
+    if let Some(project) = Config::load()
+        .await
+        .ok()
+        .and_then(|config| config.project_overlay().ok().flatten())
+    {
+        println!("  Project overrides: {}", project.path.display());
+        if project.trusted {
+            println!("  Trusted: all of its settings apply");
+        } else if !project.refused.is_empty() {
+            println!(
+                "  Not trusted, ignoring: {} (`config trust` applies them)",
+                project.refused.join(", ")
+            );
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} config check(s) failed", failed);
+    }
+
+    Ok(())
+}
+
+fn handle_cache_action(action: CacheAction) -> Result<()> {
+    use claude_helper::cache::Cache;
+
+    let cache = Cache::new()?;
+
+    match action {
+        CacheAction::Stats => {
+            show_cache_stats(&cache)?;
+        }
+        CacheAction::Clear { namespace: None } => {
+            cache.clear_all()?;
+            println!("{} Cache cleared", style::ok());
+        }
+        CacheAction::Clear {
+            namespace: Some(pattern),
+        } if pattern.contains('*') => {
+            let removed = cache.invalidate_matching(&pattern)?;
+            println!(
+                "{} Removed {} entries matching {}",
+                style::ok(),
+                removed,
+                pattern
+            );
+        }
+        CacheAction::Clear {
+            namespace: Some(namespace),
+        } => {
+            let removed = cache.clear_namespace(&namespace)?;
+            println!(
+                "{} Removed {} entries from {}/",
+                style::ok(),
+                removed,
+                namespace
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn show_cache_stats(cache: &claude_helper::cache::Cache) -> Result<()> {
+    use claude_helper::style::Colorize;
+
+    let stats = cache.stats()?;
+
+    println!("\n{}", "Cache Entries".bright_cyan().bold());
+    println!("{}", style::rule(80).bright_cyan());
+
+    if stats.is_empty() {
+        println!("\nCache is empty");
+        return Ok(());
+    }
+
+    let key_width = stats.iter().map(|s| s.key.len()).max().unwrap_or(0).max(3);
+    println!(
+        "{:<key_width$}  {:>8}  {:>6}  {:>8}  {:>6}  {:>6}",
+        "KEY", "SIZE", "AGE", "TTL LEFT", "HITS", "MISSES"
+    );
+
+    for entry in &stats {
+        let ttl = match entry.ttl_remaining {
+            Some(ttl) => format_duration(ttl).normal(),
+            None => "expired".red(),
+        };
+        println!(
+            "{:<key_width$}  {:>8}  {:>6}  {:>8}  {:>6}  {:>6}",
+            entry.key,
+            format_bytes(entry.size),
+            format_duration(entry.age),
+            ttl,
+            entry.counts.hits,
+            entry.counts.misses
+        );
+    }
+
+    let total_size: u64 = stats.iter().map(|s| s.size).sum();
+    let hits: u64 = stats.iter().map(|s| s.counts.hits).sum();
+    let lookups: u64 = hits + stats.iter().map(|s| s.counts.misses).sum::<u64>();
+
+    println!("{}", style::line(80).bright_cyan());
+    print!("{} entries, {}", stats.len(), format_bytes(total_size));
+    if lookups > 0 {
+        print!(", {:.0}% hit rate", hits as f64 / lookups as f64 * 100.0);
     }
+    println!();
+
     Ok(())
 }
 
+fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
 async fn handle_agent_action(action: AgentAction, config: &Config) -> Result<()> {
-    use claude_helper::agents::AgentManager;
+    use claude_helper::agents::{AgentCapability, AgentManager};
+    use claude_helper::db::{parse_since, AgentHistoryFilter};
 
-    let manager = AgentManager::new(config).await?;
+    let manager = AgentManager::new(open_database(config).await?);
 
     match action {
         AgentAction::Stats => {
@@ -258,46 +1597,426 @@ async fn handle_agent_action(action: AgentAction, config: &Config) -> Result<()>
         AgentAction::List => {
             manager.list_types()?;
         }
-        AgentAction::History { last } => {
-            manager.show_history(last).await?;
+        AgentAction::Top { by, limit } => {
+            manager
+                .show_leaderboard(AgentRanking::from_str(&by)?, limit)
+                .await?;
+        }
+        AgentAction::Trends { weeks } => {
+            manager.show_trends(weeks).await?;
+        }
+        AgentAction::History {
+            last,
+            capability,
+            failed,
+            since,
+            task_contains,
+            tag,
+            json,
+        } => {
+            let capability = capability
+                .map(|name| {
+                    AgentCapability::from_name(&name).with_context(|| {
+                        format!("Unknown capability: {} (see `agents list`)", name)
+                    })
+                })
+                .transpose()?;
+            let filter = AgentHistoryFilter {
+                capability,
+                failed,
+                since: since.as_deref().map(parse_since).transpose()?,
+                task_contains,
+                tag: tag.as_deref().map(normalize_tag).transpose()?,
+            };
+            manager.show_history(&filter, last, json).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn show_audit(config: &Config, since: &str, json: bool) -> Result<()> {
+    use claude_helper::db::parse_since;
+
+    let db = open_database(config).await?;
+    let calls = db.get_api_calls(parse_since(since)?).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&calls)?);
+        return Ok(());
+    }
+    if calls.is_empty() {
+        println!("No API calls since {}", since);
+        return Ok(());
+    }
+
+    println!(
+        "{:<19}  {:<24} {:<28} {:>8} {:>7} {:>9} {:>8} {:>8} {:>9}",
+        "Time", "Agent", "Model", "Input", "Output", "Cache r/w", "Latency", "Status", "Cost"
+    );
+    for entry in &calls {
+        let call = &entry.call;
+        println!(
+            "{:<19}  {:<24} {:<28} {:>8} {:>7} {:>9} {:>7.1}s {:>8} {:>9}",
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            call.agent_type,
+            call.model,
+            call.input_tokens,
+            call.output_tokens,
+            format!("{}/{}", call.cache_read_tokens, call.cache_write_tokens),
+            call.latency_ms as f64 / 1000.0,
+            call.status,
+            format!("${:.4}", call.cost_usd)
+        );
+    }
+
+    let tokens: usize = calls
+        .iter()
+        .map(|e| e.call.input_tokens + e.call.output_tokens)
+        .sum();
+    let cost: f64 = calls.iter().map(|e| e.call.cost_usd).sum();
+    let failed = calls.iter().filter(|e| e.call.status != "ok").count();
+    println!(
+        "\n{} calls ({} failed), {} input+output tokens, ${:.4}",
+        calls.len(),
+        failed,
+        tokens,
+        cost
+    );
+
+    Ok(())
+}
+
+async fn show_cost(config: &Config, period: &str, by: &str, json: bool) -> Result<()> {
+    use claude_helper::db::parse_since;
+    use claude_helper::pricing::cost_breakdown;
+
+    let since = parse_since(period)?;
+    let db = open_database(config).await?;
+    let rows = cost_breakdown(&db.get_api_calls(since).await?, by)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("No API calls in the last {}", period);
+        return Ok(());
+    }
+
+    println!(
+        "{:<28} {:>6} {:>10} {:>9} {:>13} {:>10} {:>10}",
+        match by {
+            "model" => "Model",
+            "agent" => "Agent",
+            _ => "Day",
+        },
+        "Calls",
+        "Input",
+        "Output",
+        "Cache r/w",
+        "Cost",
+        "Saved"
+    );
+    for row in &rows {
+        println!(
+            "{:<28} {:>6} {:>10} {:>9} {:>13} {:>10} {:>10}",
+            format!("{}{}", row.group, if row.estimated { " *" } else { "" }),
+            row.calls,
+            row.input_tokens,
+            row.output_tokens,
+            format!("{}/{}", row.cache_read_tokens, row.cache_write_tokens),
+            format!("${:.4}", row.cost_usd),
+            format!("${:.4}", row.cache_savings_usd)
+        );
+    }
+
+    let cost: f64 = rows.iter().map(|r| r.cost_usd).sum();
+    let saved: f64 = rows.iter().map(|r| r.cache_savings_usd).sum();
+    println!(
+        "\n${:.4} since {} (cache reads saved ${:.4})",
+        cost,
+        since.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        saved
+    );
+    if rows.iter().any(|r| r.estimated) {
+        println!(
+            "* unknown model, priced as {}",
+            claude_helper::agents::DEFAULT_MODEL
+        );
+    }
+
+    Ok(())
+}
+
+async fn handle_db_action(action: DbAction, config: &Config) -> Result<()> {
+    match action {
+        DbAction::Query { sql } => {
+            let db = Database::new_read_only(config).await?;
+            let result = db.query(&sql).await?;
+            println!("{}", result.render_table());
+        }
+        DbAction::Rollup { days } => {
+            let db = Database::new(config).await?;
+            let written = db.rebuild_daily_rollup().await?;
+            println!(
+                "{} Rebuilt daily usage rollup ({} rows)\n",
+                style::ok(),
+                written
+            );
+
+            let daily = db.get_daily_usage(days).await?;
+            if daily.is_empty() {
+                println!("No usage recorded in the last {} days", days);
+            }
+            for row in daily {
+                println!(
+                    "{}  {:<28} {:>4} runs ({} ok)  {:>9} tokens  ${:.2}  {}",
+                    row.day,
+                    row.model,
+                    row.runs,
+                    row.successful_runs,
+                    row.tokens,
+                    row.cost_usd,
+                    row.project
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_kb_action(action: KbAction, config: &Config) -> Result<()> {
+    use claude_helper::knowledge::{self, KnowledgeKind};
+
+    let db = open_database(config).await?;
+    let project = knowledge::current_project()?;
+
+    let print_entries = |entries: &[knowledge::KnowledgeEntry]| {
+        for entry in entries {
+            println!(
+                "{:>4}  {:<10}  {}  ({}, {})",
+                entry.id,
+                entry.kind.as_str(),
+                entry.content,
+                entry.source,
+                entry.created_at.format("%Y-%m-%d")
+            );
+        }
+    };
+
+    match action {
+        KbAction::Add { content, kind } => {
+            let kind = KnowledgeKind::from_str(&kind)?;
+            if content.trim().is_empty() {
+                anyhow::bail!("Knowledge base entries must not be empty");
+            }
+            let id = db
+                .add_knowledge(&project, kind, content.trim(), "manual", false)
+                .await?;
+            println!(
+                "{} Added {} {} for {}",
+                style::ok(),
+                kind.as_str(),
+                id,
+                project
+            );
+        }
+        KbAction::List { proposed } => {
+            let entries: Vec<_> = db
+                .get_knowledge(&project)
+                .await?
+                .into_iter()
+                .filter(|entry| entry.proposed == proposed)
+                .collect();
+            if entries.is_empty() {
+                if proposed {
+                    println!("No proposed entries for {}", project);
+                } else {
+                    println!(
+                        "No knowledge base entries for {} yet (add one with `kb add`)",
+                        project
+                    );
+                }
+            }
+            print_entries(&entries);
+        }
+        KbAction::Search { query } => {
+            let entries = db.get_knowledge(&project).await?;
+            let found = knowledge::search(&entries, &query);
+            if found.is_empty() {
+                println!("No entries matching '{}'", query);
+            }
+            print_entries(&found);
+        }
+        KbAction::Accept { id } => {
+            if !db.accept_knowledge(&project, id).await? {
+                anyhow::bail!("No proposed entry {} for {}", id, project);
+            }
+            println!("{} Accepted entry {}", style::ok(), id);
+        }
+        KbAction::Remove { id } => {
+            if !db.remove_knowledge(&project, id).await? {
+                anyhow::bail!("No entry {} for {}", id, project);
+            }
+            println!("{} Removed entry {}", style::ok(), id);
+        }
+    }
+    Ok(())
+}
+
+async fn handle_experiment_action(action: ExperimentAction, config: &Config) -> Result<()> {
+    let db = open_database(config).await?;
+
+    match action {
+        ExperimentAction::List => {
+            let variants = db.get_variants().await?;
+            if variants.is_empty() {
+                println!("No runs labelled with a variant yet (use `run --variant <label>`)");
+            }
+            for (variant, runs) in variants {
+                println!("{:<24} {:>5} runs", variant, runs);
+            }
+        }
+        ExperimentAction::Compare { a, b, json } => {
+            let a = db.get_variant_stats(&normalize_variant(&a)?).await?;
+            let b = db.get_variant_stats(&normalize_variant(&b)?).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&[&a, &b])?);
+                return Ok(());
+            }
+            for stats in [&a, &b] {
+                if stats.runs == 0 && stats.bench_tasks == 0 {
+                    println!("{} No runs labelled '{}' yet", style::warn(), stats.variant);
+                }
+            }
+
+            let percent =
+                |rate: Option<f64>| rate.map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0));
+            let tokens = |avg: Option<f64>| avg.map_or("-".to_string(), |t| format!("{:.0}", t));
+            let rows = [
+                ("Runs", a.runs.to_string(), b.runs.to_string()),
+                (
+                    "Success rate",
+                    percent(a.success_rate()),
+                    percent(b.success_rate()),
+                ),
+                ("Avg tokens", tokens(a.avg_tokens()), tokens(b.avg_tokens())),
+                (
+                    "Reviews passed",
+                    percent(a.review_pass_rate()),
+                    percent(b.review_pass_rate()),
+                ),
+                (
+                    "Bench score",
+                    percent(a.bench_score()),
+                    percent(b.bench_score()),
+                ),
+            ];
+
+            println!("{:<16} {:>16} {:>16}", "", a.variant, b.variant);
+            for (label, a, b) in rows {
+                println!("{:<16} {:>16} {:>16}", label, a, b);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_commands_action(action: CommandsAction) -> Result<()> {
+    use claude_helper::style::Colorize;
+
+    match action {
+        CommandsAction::List => {
+            println!("\n{}", "Command Templates".bright_cyan().bold());
+            println!("{}", style::rule(60).bright_cyan());
+            for template in templates::LIBRARY {
+                println!(
+                    "\n{} {}",
+                    format!("/{}", template.name).bright_yellow().bold(),
+                    template.description()
+                );
+                for parameter in template.parameters() {
+                    match parameter.default {
+                        Some(default) => println!("  {} (default: {})", parameter.name, default),
+                        None => println!("  {} (required)", parameter.name),
+                    }
+                }
+            }
+            println!(
+                "\nInstall one with: claude-helper commands install <name> [--set NAME=VALUE]"
+            );
+        }
+        CommandsAction::Show { name, values } => {
+            let template = CommandTemplate::named(&name)?;
+            print!("{}", template.render(&templates::parse_values(&values)?)?);
+        }
+        CommandsAction::Install {
+            name,
+            values,
+            global,
+            force,
+        } => {
+            let template = CommandTemplate::named(&name)?;
+            let dir = if global {
+                dirs::home_dir()
+                    .context("Could not find home directory")?
+                    .join(".claude")
+            } else {
+                PathBuf::from(Project::current()?.root).join(".claude")
+            };
+            let path = template.install(
+                &dir.join("commands"),
+                &templates::parse_values(&values)?,
+                force,
+            )?;
+            println!(
+                "{} Installed /{} to {}",
+                style::ok().green(),
+                template.name,
+                path.display()
+            );
         }
     }
     Ok(())
 }
 
 async fn install_claude_integration() -> Result<()> {
-    use std::fs;
     use anyhow::Context;
     use serde_json::Value;
+    use std::fs;
 
-    println!("📦 Installing Claude Code integration...\n");
+    println!(
+        "{} Installing Claude Code integration...\n",
+        style::icon("📦")
+    );
 
     // Get home directory (platform-independent)
-    let home = dirs::home_dir()
-        .context("Could not find home directory")?;
+    let home = dirs::home_dir().context("Could not find home directory")?;
     let claude_dir = home.join(".claude");
     let commands_dir = claude_dir.join("commands");
     let settings_path = claude_dir.join("settings.json");
 
     // Create directories
-    fs::create_dir_all(&commands_dir)
-        .context("Failed to create .claude/commands directory")?;
-    println!("✓ Created directory structure");
+    fs::create_dir_all(&commands_dir).context("Failed to create .claude/commands directory")?;
+    println!("{} Created directory structure", style::ok());
 
     // Merge settings.json (preserve existing settings)
     let mut settings: Value = if settings_path.exists() {
         // Read existing settings
-        let existing_content = fs::read_to_string(&settings_path)
-            .context("Failed to read existing settings.json")?;
+        let existing_content =
+            fs::read_to_string(&settings_path).context("Failed to read existing settings.json")?;
 
         // Backup existing file
         let backup_path = claude_dir.join("settings.json.backup");
         fs::write(&backup_path, &existing_content)
             .context("Failed to create backup of settings.json")?;
-        println!("✓ Backed up existing settings to settings.json.backup");
+        println!(
+            "{} Backed up existing settings to settings.json.backup",
+            style::ok()
+        );
 
-        serde_json::from_str(&existing_content)
-            .context("Failed to parse existing settings.json")?
+        serde_json::from_str(&existing_content).context("Failed to parse existing settings.json")?
     } else {
         // Create new settings object
         serde_json::json!({})
@@ -335,36 +2054,69 @@ async fn install_claude_integration() -> Result<()> {
     }
 
     // Write updated settings
-    let settings_json = serde_json::to_string_pretty(&settings)
-        .context("Failed to serialize settings")?;
-    fs::write(&settings_path, settings_json)
-        .context("Failed to write settings.json")?;
-    println!("✓ Updated settings.json (existing settings preserved)");
+    let settings_json =
+        serde_json::to_string_pretty(&settings).context("Failed to serialize settings")?;
+    fs::write(&settings_path, settings_json).context("Failed to write settings.json")?;
+    println!(
+        "{} Updated settings.json (existing settings preserved)",
+        style::ok()
+    );
 
     // Copy command files
     let commands = vec![
-        ("master.md", include_str!("../.claude-templates/commands/master.md")),
-        ("optimize.md", include_str!("../.claude-templates/commands/optimize.md")),
-        ("token-usage.md", include_str!("../.claude-templates/commands/token-usage.md")),
+        (
+            "master.md",
+            include_str!("../.claude-templates/commands/master.md"),
+        ),
+        (
+            "optimize.md",
+            include_str!("../.claude-templates/commands/optimize.md"),
+        ),
+        (
+            "token-usage.md",
+            include_str!("../.claude-templates/commands/token-usage.md"),
+        ),
     ];
 
     for (filename, content) in commands {
         fs::write(commands_dir.join(filename), content)
             .with_context(|| format!("Failed to write {}", filename))?;
-        println!("✓ Installed /{}",  filename.trim_end_matches(".md"));
+        println!(
+            "{} Installed /{}",
+            style::ok(),
+            filename.trim_end_matches(".md")
+        );
     }
 
-    println!("\n✨ Claude Code integration installed successfully!\n");
+    println!(
+        "\n{} Claude Code integration installed successfully!\n",
+        style::icon("✨")
+    );
     println!("Next time you run 'claude', you'll have:");
-    println!("  • Status line showing token usage (updates every 5s)");
-    println!("  • /master - Run Master Coder orchestration");
-    println!("  • /optimize - Get session optimization suggestions");
-    println!("  • /token-usage - View detailed token breakdown");
+    println!(
+        "  {} Status line showing token usage (updates every 5s)",
+        style::bullet()
+    );
+    println!(
+        "  {} /master - Run Master Coder orchestration",
+        style::bullet()
+    );
+    println!(
+        "  {} /optimize - Get session optimization suggestions",
+        style::bullet()
+    );
+    println!(
+        "  {} /token-usage - View detailed token breakdown",
+        style::bullet()
+    );
     println!("\nConfiguration: ~/.claude/settings.json");
     println!("Commands: ~/.claude/commands/");
 
     if settings_path.exists() {
-        println!("\n⚠️  Your existing settings were preserved and backed up to:");
+        println!(
+            "\n{} Your existing settings were preserved and backed up to:",
+            style::warn()
+        );
         println!("   ~/.claude/settings.json.backup\n");
     } else {
         println!();
@@ -374,9 +2126,9 @@ async fn install_claude_integration() -> Result<()> {
 }
 
 async fn handle_session_start(config: &Config) -> Result<()> {
+    use chrono::Utc;
     use std::fs::OpenOptions;
     use std::io::Write;
-    use chrono::Utc;
 
     // Log session start to database
     let db_path = Config::db_file()?;
@@ -390,16 +2142,17 @@ async fn handle_session_start(config: &Config) -> Result<()> {
     writeln!(file, "[{}] Session started", Utc::now().to_rfc3339())?;
 
     // Initialize session tracking
-    let analyzer = SessionAnalyzer::new(config.clone()).await?;
+    let db = open_database(config).await?;
+    let analyzer = SessionAnalyzer::new(config.clone(), db).await?;
     analyzer.start_session().await?;
 
     Ok(())
 }
 
 async fn handle_log_usage(config: &Config) -> Result<()> {
+    use chrono::Utc;
     use std::fs::OpenOptions;
     use std::io::Write;
-    use chrono::Utc;
 
     // Get current usage and log it
     let statusline = StatusLine::new(config.clone()).await?;
@@ -428,7 +2181,8 @@ async fn handle_log_usage(config: &Config) -> Result<()> {
     )?;
 
     // Analyze for optimization opportunities
-    let analyzer = SessionAnalyzer::new(config.clone()).await?;
+    let db = open_database(config).await?;
+    let analyzer = SessionAnalyzer::new(config.clone(), db).await?;
     analyzer.log_interaction().await?;
 
     Ok(())
@@ -1,26 +1,116 @@
+use super::inventory::WorkspaceInventory;
+use super::language;
 use super::orchestrator::ExecutionPlan;
-use crate::agents::{Agent, AgentCapability, ClaudeAgent};
+use crate::agents::{Agent, AgentCapability, ClaudeAgent, ClaudeCliAgent, DEFAULT_MODEL};
 use crate::config::Config;
+use crate::db::Database;
+use crate::knowledge::{self, KnowledgeEntry};
 use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// How the task is introduced at the end of every system prompt
+const TASK_INTRO: &str = "You are one of several agents working on the task below, \
+    given exactly as the user wrote it:";
 
 pub struct AgentFactory {
     config: Config,
+    audit: Option<Arc<Database>>,
+    /// Project conventions and decisions added to every system prompt
+    knowledge: Vec<KnowledgeEntry>,
+    /// How to write and check code in the project's language, added to
+    /// every system prompt
+    language: Option<String>,
 }
 
 impl AgentFactory {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            audit: None,
+            knowledge: Vec::new(),
+            language: None,
+        }
+    }
+
+    /// Let agents created from now on reuse cached answers to identical
+    /// requests, overriding `master_coder.reuse_responses`
+    pub fn set_reuse_responses(&mut self, reuse: bool) {
+        self.config.master_coder.reuse_responses = reuse;
+    }
+
+    /// Have every agent created from now on record its API calls in `db`
+    pub fn set_audit_log(&mut self, db: Arc<Database>) {
+        self.audit = Some(db);
+    }
+
+    /// Tell every agent created from now on about these knowledge base entries
+    pub fn set_knowledge(&mut self, entries: Vec<KnowledgeEntry>) {
+        self.knowledge = entries;
+    }
+
+    /// Tailor the system prompts of agents created from now on to the
+    /// language and tools of `workspace`
+    pub fn set_workspace(&mut self, workspace: &WorkspaceInventory) {
+        self.language = language::prompt_section(workspace);
     }
 
-    /// Create agents based on execution plan
-    pub async fn create_agents(&self, plan: &ExecutionPlan) -> Result<Vec<Box<dyn Agent>>> {
+    /// Create the agent that distills knowledge base proposals from a run
+    pub async fn create_curator(&self, task: &str) -> Result<Box<dyn Agent>> {
+        let mut agent = self
+            .create_agent(
+                "knowledge-curator",
+                "Knowledge Curator",
+                &AgentCapability::Documentation,
+                task,
+            )
+            .await?;
+        agent.set_output_schema(knowledge::curator_schema());
+        if let Some(db) = &self.audit {
+            agent.set_audit_log(db.clone());
+        }
+
+        Ok(agent)
+    }
+
+    /// Create the agent that fixes tests failing after a run
+    pub async fn create_debugger(&self, task: &str) -> Result<Box<dyn Agent>> {
+        let mut agent = self
+            .create_agent(
+                "test-debugger",
+                "Test Debugger",
+                &AgentCapability::Debugging,
+                task,
+            )
+            .await?;
+        if let Some(db) = &self.audit {
+            agent.set_audit_log(db.clone());
+        }
+
+        Ok(agent)
+    }
+
+    /// Create agents based on execution plan. Every agent is given the
+    /// original `task` as written, Markdown and all, as context for its
+    /// own part of the plan.
+    pub async fn create_agents(
+        &self,
+        plan: &ExecutionPlan,
+        task: &str,
+    ) -> Result<Vec<Box<dyn Agent>>> {
         let mut agents = Vec::new();
 
         for phase in &plan.phases {
             for spec in &phase.agents {
-                let agent = self
-                    .create_agent(&spec.id, &spec.agent_type, &spec.capability, &spec.task)
+                let mut agent = self
+                    .create_agent(&spec.id, &spec.agent_type, &spec.capability, task)
                     .await?;
+                if let Some(schema) = &spec.output_schema {
+                    agent.set_output_schema(schema.clone());
+                }
+                if let Some(db) = &self.audit {
+                    agent.set_audit_log(db.clone());
+                }
 
                 agents.push(agent);
             }
@@ -34,13 +124,27 @@ impl AgentFactory {
         id: &str,
         agent_type: &str,
         capability: &AgentCapability,
-        _task_description: &str,
+        task: &str,
     ) -> Result<Box<dyn Agent>> {
         // Create a system prompt based on agent type and capability
-        let system_prompt = self.generate_system_prompt(agent_type, capability);
+        let system_prompt = self.generate_system_prompt(agent_type, capability, task);
+
+        let validators = super::validators::for_capability(&self.config, capability);
+
+        if self.config.master_coder.backend == "claude-cli" {
+            let mut agent = ClaudeCliAgent::new(
+                id.to_string(),
+                agent_type.to_string(),
+                capability.clone(),
+                system_prompt,
+                &self.config,
+            )?;
+            agent.set_validators(validators);
+            return Ok(Box::new(agent));
+        }
 
         // Create Claude agent with specialized prompt
-        let agent = ClaudeAgent::new(
+        let mut agent = ClaudeAgent::new(
             id.to_string(),
             agent_type.to_string(),
             capability.clone(),
@@ -48,117 +152,258 @@ impl AgentFactory {
             self.config.clone(),
         )
         .await?;
+        agent.set_validators(validators);
 
         Ok(Box::new(agent))
     }
 
-    fn generate_system_prompt(&self, agent_type: &str, capability: &AgentCapability) -> String {
+    fn generate_system_prompt(
+        &self,
+        agent_type: &str,
+        capability: &AgentCapability,
+        task: &str,
+    ) -> String {
         let base = format!(
             "You are {}, a specialized AI agent with expertise in {}.",
             agent_type,
             capability.description()
         );
 
-        let specific = match capability {
-            AgentCapability::Architecture => {
-                "Your role is to design system architecture and create implementation plans. \
-                Focus on:\n\
-                - System design and component interaction\n\
-                - Technology selection and trade-offs\n\
-                - Scalability and maintainability\n\
-                - Clear documentation of architectural decisions\n\n\
-                Provide a comprehensive design document with diagrams (using text/ASCII) where helpful."
-            }
+        let specific = role_instructions(capability);
 
-            AgentCapability::CodeWriting => {
-                "Your role is to write high-quality, production-ready code. \
-                Focus on:\n\
-                - Clean, readable, and maintainable code\n\
-                - Following best practices and design patterns\n\
-                - Proper error handling\n\
-                - Code comments where necessary\n\
-                - Type safety and correctness\n\n\
-                Write complete, working code that can be directly used."
-            }
+        let mut prompt = format!("{}\n\n{}", base, specific);
+        if let Some(language) = &self.language {
+            prompt.push_str("\n\n");
+            prompt.push_str(language);
+        }
+        let conventions = knowledge::prompt_section(&self.knowledge);
+        if !conventions.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&conventions);
+        }
 
-            AgentCapability::Testing => {
-                "Your role is to write comprehensive tests. \
-                Focus on:\n\
-                - Unit tests for individual functions/methods\n\
-                - Integration tests for component interaction\n\
-                - Edge cases and error conditions\n\
-                - Test coverage and quality\n\
-                - Clear test descriptions\n\n\
-                Write tests that are thorough, maintainable, and catch potential bugs."
-            }
+        format!("{}\n\n{}\n\n{}", prompt, TASK_INTRO, task)
+    }
+}
 
-            AgentCapability::Security => {
-                "Your role is to audit code for security vulnerabilities. \
-                Focus on:\n\
-                - OWASP Top 10 vulnerabilities\n\
-                - Input validation and sanitization\n\
-                - Authentication and authorization\n\
-                - Data encryption and secure storage\n\
-                - Security best practices\n\n\
-                Provide detailed security analysis with specific recommendations for fixes."
-            }
+/// What agents with `capability` are told their role is
+fn role_instructions(capability: &AgentCapability) -> &'static str {
+    match capability {
+        AgentCapability::Architecture => {
+            "Your role is to design system architecture and create implementation plans. \
+            Focus on:\n\
+            - System design and component interaction\n\
+            - Technology selection and trade-offs\n\
+            - Scalability and maintainability\n\
+            - Clear documentation of architectural decisions\n\n\
+            Provide a comprehensive design document with diagrams (using text/ASCII) where helpful."
+        }
 
-            AgentCapability::Documentation => {
-                "Your role is to create comprehensive documentation. \
-                Focus on:\n\
-                - Clear API documentation\n\
-                - Usage examples and tutorials\n\
-                - Architecture overview\n\
-                - Installation and setup instructions\n\
-                - Troubleshooting guides\n\n\
-                Write documentation that is clear, complete, and helpful for developers."
-            }
+        AgentCapability::CodeWriting => {
+            "Your role is to write high-quality, production-ready code. \
+            Focus on:\n\
+            - Clean, readable, and maintainable code\n\
+            - Following best practices and design patterns\n\
+            - Proper error handling\n\
+            - Code comments where necessary\n\
+            - Type safety and correctness\n\n\
+            Write complete, working code that can be directly used."
+        }
 
-            AgentCapability::Debugging => {
-                "Your role is to find and fix bugs. \
-                Focus on:\n\
-                - Systematic debugging approach\n\
-                - Root cause analysis\n\
-                - Minimal, targeted fixes\n\
-                - Preventing similar bugs\n\
-                - Testing the fix\n\n\
-                Provide clear explanation of the bug and why your fix resolves it."
-            }
+        AgentCapability::Testing => {
+            "Your role is to write comprehensive tests. \
+            Focus on:\n\
+            - Unit tests for individual functions/methods\n\
+            - Integration tests for component interaction\n\
+            - Edge cases and error conditions\n\
+            - Test coverage and quality\n\
+            - Clear test descriptions\n\n\
+            Write tests that are thorough, maintainable, and catch potential bugs."
+        }
 
-            AgentCapability::Performance => {
-                "Your role is to optimize performance. \
-                Focus on:\n\
-                - Identifying bottlenecks\n\
-                - Algorithm and data structure optimization\n\
-                - Resource usage (CPU, memory, I/O)\n\
-                - Benchmarking and profiling\n\
-                - Caching strategies\n\n\
-                Provide measurable performance improvements with before/after metrics."
-            }
+        AgentCapability::Security => {
+            "Your role is to audit code for security vulnerabilities. \
+            Focus on:\n\
+            - OWASP Top 10 vulnerabilities\n\
+            - Input validation and sanitization\n\
+            - Authentication and authorization\n\
+            - Data encryption and secure storage\n\
+            - Security best practices\n\n\
+            Provide detailed security analysis with specific recommendations for fixes."
+        }
 
-            AgentCapability::Migration => {
-                "Your role is to plan and execute migrations. \
-                Focus on:\n\
-                - Migration strategy and planning\n\
-                - Data preservation and integrity\n\
-                - Backward compatibility where needed\n\
-                - Rollback procedures\n\
-                - Testing migration thoroughly\n\n\
-                Provide a safe, well-tested migration path with clear steps."
-            }
+        AgentCapability::Documentation => {
+            "Your role is to create comprehensive documentation. \
+            Focus on:\n\
+            - Clear API documentation\n\
+            - Usage examples and tutorials\n\
+            - Architecture overview\n\
+            - Installation and setup instructions\n\
+            - Troubleshooting guides\n\n\
+            Write documentation that is clear, complete, and helpful for developers."
+        }
 
-            AgentCapability::Review => {
-                "Your role is to review code for quality. \
-                Focus on:\n\
-                - Code quality and maintainability\n\
-                - Best practices adherence\n\
-                - Potential bugs or issues\n\
-                - Performance considerations\n\
-                - Consistency with codebase\n\n\
-                Provide constructive feedback with specific suggestions for improvement."
-            }
+        AgentCapability::Debugging => {
+            "Your role is to find and fix bugs. \
+            Focus on:\n\
+            - Systematic debugging approach\n\
+            - Root cause analysis\n\
+            - Minimal, targeted fixes\n\
+            - Preventing similar bugs\n\
+            - Testing the fix\n\n\
+            Provide clear explanation of the bug and why your fix resolves it."
+        }
+
+        AgentCapability::Performance => {
+            "Your role is to optimize performance. \
+            Focus on:\n\
+            - Identifying bottlenecks\n\
+            - Algorithm and data structure optimization\n\
+            - Resource usage (CPU, memory, I/O)\n\
+            - Benchmarking and profiling\n\
+            - Caching strategies\n\n\
+            Provide measurable performance improvements with before/after metrics."
+        }
+
+        AgentCapability::Migration => {
+            "Your role is to plan and execute migrations. \
+            Focus on:\n\
+            - Migration strategy and planning\n\
+            - Data preservation and integrity\n\
+            - Backward compatibility where needed\n\
+            - Rollback procedures\n\
+            - Testing migration thoroughly\n\n\
+            Provide a safe, well-tested migration path with clear steps."
+        }
+
+        AgentCapability::Review => {
+            "Your role is to review code for quality. \
+            Focus on:\n\
+            - Code quality and maintainability\n\
+            - Best practices adherence\n\
+            - Potential bugs or issues\n\
+            - Performance considerations\n\
+            - Consistency with codebase\n\n\
+            Provide constructive feedback with specific suggestions for improvement."
+        }
+    }
+}
+
+/// Short fingerprint of the `system_prompt` an agent named `agent_type`
+/// got: its role instructions, language and knowledge sections and how the
+/// task is framed, but not its name or the task itself. Recorded with its
+/// executions so a prompt change shows in the stats.
+pub(crate) fn prompt_version(system_prompt: &str, agent_type: &str) -> String {
+    let template = system_prompt
+        .strip_prefix(&format!("You are {}", agent_type))
+        .unwrap_or(system_prompt);
+    let template = template
+        .find(TASK_INTRO)
+        .map_or(template, |at| &template[..at + TASK_INTRO.len()]);
+    Sha256::digest(template.as_bytes())[..4]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Model the agents of a run call, recorded with their executions
+pub(crate) fn agent_model(config: &Config) -> String {
+    let model = config.master_coder.model.as_deref();
+    if config.master_coder.backend == "claude-cli" {
+        // Claude Code picks its own model when none is configured
+        return model.map_or_else(
+            || "claude-cli".to_string(),
+            |model| config.models.expand(model).to_string(),
+        );
+    }
+    config.models.resolve(model.unwrap_or(DEFAULT_MODEL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_prompt_keeps_task_markdown() {
+        let factory = AgentFactory::new(Config::default());
+        let task = "# Spec\n\n- parse `--flag`\n- keep\n  indentation";
+
+        let prompt = factory.generate_system_prompt("Tester", &AgentCapability::Testing, task);
+        assert!(prompt.starts_with("You are Tester"));
+        assert!(prompt.ends_with(&format!(":\n\n{}", task)));
+        assert!(!prompt.contains("conventions and earlier decisions"));
+    }
+
+    #[test]
+    fn test_system_prompt_includes_knowledge() {
+        let mut factory = AgentFactory::new(Config::default());
+        factory.set_knowledge(vec![KnowledgeEntry {
+            id: 1,
+            project: "/work/app".to_string(),
+            kind: knowledge::KnowledgeKind::Convention,
+            content: "Errors are returned with anyhow".to_string(),
+            source: "manual".to_string(),
+            proposed: false,
+            created_at: chrono::Utc::now(),
+        }]);
+
+        let prompt =
+            factory.generate_system_prompt("Coder", &AgentCapability::CodeWriting, "Add a flag");
+        assert!(prompt.contains("- (convention) Errors are returned with anyhow\n\nYou are one of"));
+    }
+
+    #[test]
+    fn test_system_prompt_follows_language() {
+        let mut factory = AgentFactory::new(Config::default());
+        factory.set_workspace(&WorkspaceInventory {
+            files: 3,
+            languages: vec![("Rust".to_string(), 2)],
+            frameworks: vec!["cargo".to_string()],
+            ..Default::default()
+        });
+
+        let prompt =
+            factory.generate_system_prompt("Coder", &AgentCapability::CodeWriting, "Add a flag");
+        assert!(prompt.contains("directly used.\n\nThis is a Rust project built with cargo."));
+        assert!(!prompt.contains("npm"));
+    }
+
+    #[test]
+    fn test_prompt_version() {
+        let mut factory = AgentFactory::new(Config::default());
+        let version = |factory: &AgentFactory, name: &str, capability, task: &str| {
+            prompt_version(
+                &factory.generate_system_prompt(name, capability, task),
+                name,
+            )
         };
 
-        format!("{}\n\n{}", base, specific)
+        let testing = version(&factory, "Tester", &AgentCapability::Testing, "Add a flag");
+        assert_eq!(testing.len(), 8);
+        // Neither the agent's name nor the task make a new version
+        assert_eq!(
+            testing,
+            version(
+                &factory,
+                "Tester Beta",
+                &AgentCapability::Testing,
+                "Fix the parser"
+            )
+        );
+        assert_ne!(
+            testing,
+            version(&factory, "Tester", &AgentCapability::Review, "Add a flag")
+        );
+
+        factory.set_workspace(&WorkspaceInventory {
+            files: 3,
+            languages: vec![("Rust".to_string(), 2)],
+            frameworks: vec!["cargo".to_string()],
+            ..Default::default()
+        });
+        assert_ne!(
+            testing,
+            version(&factory, "Tester", &AgentCapability::Testing, "Add a flag")
+        );
     }
 }
@@ -0,0 +1,324 @@
+use super::run_log::check_run_id;
+use crate::config::Config;
+use crate::db::{ArchivedTask, Database};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Layout version of run archives; newer ones are refused on import
+const FORMAT: u32 = 1;
+
+/// The task's database rows, always the first entry
+const MANIFEST: &str = "manifest.json";
+
+/// The run log, for `tui --attach`-style replay
+const RUN_LOG: &str = "run.jsonl";
+
+/// The files agents wrote, as under `runs/<run-id>/files`
+const FILES: &str = "files";
+
+#[derive(Serialize)]
+struct ManifestRef<'a> {
+    format: u32,
+    task: &'a ArchivedTask,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    format: u32,
+    task: ArchivedTask,
+}
+
+/// What a run archive holds
+#[derive(Debug)]
+pub struct ArchiveContents {
+    pub run_id: Option<String>,
+    pub has_log: bool,
+    pub files: usize,
+}
+
+/// Bundle task `id` (its database rows, run log and the files its agents
+/// wrote) into a gzipped tarball at `path`
+pub async fn archive_run(db: &Database, id: usize, path: &Path) -> Result<ArchiveContents> {
+    let Some(task) = db.export_task(id).await? else {
+        anyhow::bail!("Task {} not found", id);
+    };
+    write_archive(path, &task, &Config::runs_dir()?)
+}
+
+/// Load a run archive: its log and files go into the runs directory and
+/// its task into the database as a new task, whose ID is returned. The
+/// files are removed again when the task cannot be saved, so the import
+/// can be retried.
+pub async fn import_run(db: &Database, path: &Path) -> Result<(i64, ArchiveContents)> {
+    let runs_dir = Config::runs_dir()?;
+    let (task, contents) = read_archive(path, &runs_dir)?;
+    match db.import_task(&task).await {
+        Ok(id) => Ok((id, contents)),
+        Err(e) => {
+            if let Some(run_id) = &task.run_id {
+                remove_unpacked(&runs_dir, run_id);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn write_archive(path: &Path, task: &ArchivedTask, runs_dir: &Path) -> Result<ArchiveContents> {
+    let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let manifest = serde_json::to_vec_pretty(&ManifestRef {
+        format: FORMAT,
+        task,
+    })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(task.created_at.timestamp().max(0) as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, MANIFEST, manifest.as_slice())?;
+
+    let mut contents = ArchiveContents {
+        run_id: task.run_id.clone(),
+        has_log: false,
+        files: 0,
+    };
+    if let Some(run_id) = &task.run_id {
+        check_run_id(run_id)?;
+        let log = runs_dir.join(format!("{}.jsonl", run_id));
+        if log.is_file() {
+            tar.append_path_with_name(&log, RUN_LOG)
+                .with_context(|| format!("Failed to archive {:?}", log))?;
+            contents.has_log = true;
+        }
+        let files = runs_dir.join(run_id).join(FILES);
+        if files.is_dir() {
+            tar.append_dir_all(FILES, &files)
+                .with_context(|| format!("Failed to archive {:?}", files))?;
+            contents.files = count_files(&files)?;
+        }
+    }
+
+    tar.into_inner()?.finish()?;
+    Ok(contents)
+}
+
+/// Read the task of an archive and unpack its log and files into
+/// `runs_dir`. Refuses runs already there, e.g. imported before.
+fn read_archive(path: &Path, runs_dir: &Path) -> Result<(ArchivedTask, ArchiveContents)> {
+    let file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    // Retention ages runs by their files, so an imported run counts from
+    // its import rather than from when it was archived
+    tar.set_preserve_mtime(false);
+    let mut entries = tar.entries().context("Not a run archive")?;
+
+    let manifest: Manifest = match entries.next() {
+        Some(entry) => {
+            let entry = entry.context("Not a run archive")?;
+            if entry.path()?.as_ref() != Path::new(MANIFEST) {
+                anyhow::bail!("Not a run archive: {} is missing", MANIFEST);
+            }
+            serde_json::from_reader(entry).context("Failed to read the archived task")?
+        }
+        None => anyhow::bail!("Not a run archive: it is empty"),
+    };
+    if manifest.format > FORMAT {
+        anyhow::bail!("The archive was made by a newer claude-helper; upgrade to import it");
+    }
+
+    let mut contents = ArchiveContents {
+        run_id: manifest.task.run_id.clone(),
+        has_log: false,
+        files: 0,
+    };
+    let Some(run_id) = &manifest.task.run_id else {
+        return Ok((manifest.task, contents));
+    };
+    check_run_id(run_id)?;
+    let log = runs_dir.join(format!("{}.jsonl", run_id));
+    let run_dir = runs_dir.join(run_id);
+    if log.exists() || run_dir.exists() {
+        anyhow::bail!(
+            "Run {} is already here; it was probably imported before",
+            run_id
+        );
+    }
+
+    if let Err(e) = unpack(entries, runs_dir, &log, &run_dir, &mut contents) {
+        remove_unpacked(runs_dir, run_id);
+        return Err(e.context("Failed to unpack the run archive"));
+    }
+
+    Ok((manifest.task, contents))
+}
+
+fn unpack(
+    entries: tar::Entries<'_, GzDecoder<File>>,
+    runs_dir: &Path,
+    log: &Path,
+    run_dir: &Path,
+    contents: &mut ArchiveContents,
+) -> Result<()> {
+    for entry in entries {
+        let mut entry = entry?;
+        let name: PathBuf = entry.path()?.into_owned();
+        if name == Path::new(RUN_LOG) {
+            fs::create_dir_all(runs_dir)?;
+            entry.unpack(log)?;
+            contents.has_log = true;
+        } else if name.starts_with(FILES) {
+            fs::create_dir_all(run_dir)?;
+            // unpack_in refuses paths that would leave the directory
+            if entry.unpack_in(run_dir)? && entry.header().entry_type().is_file() {
+                contents.files += 1;
+            }
+        } else {
+            warn!("Skipping unexpected entry {:?} in the run archive", name);
+        }
+    }
+    Ok(())
+}
+
+/// Remove what [`read_archive`] unpacked for `run_id`; it refuses runs
+/// already there, so everything under that name came from the archive
+fn remove_unpacked(runs_dir: &Path, run_id: &str) {
+    let log = runs_dir.join(format!("{}.jsonl", run_id));
+    if let Err(e) = fs::remove_file(&log) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove {:?}: {}", log, e);
+        }
+    }
+    let run_dir = runs_dir.join(run_id);
+    if let Err(e) = fs::remove_dir_all(&run_dir) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove {:?}: {}", run_dir, e);
+        }
+    }
+}
+
+fn count_files(dir: &Path) -> Result<usize> {
+    let mut count = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        count += if entry.file_type()?.is_dir() {
+            count_files(&entry.path())?
+        } else {
+            1
+        };
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::master::retention;
+    use chrono::Utc;
+
+    fn task(run_id: Option<&str>) -> ArchivedTask {
+        ArchivedTask {
+            description: "Add a --json flag".to_string(),
+            complexity: 3,
+            estimated_tokens: 5000,
+            actual_tokens: 4200,
+            success: true,
+            analysis: "{}".to_string(),
+            plan: "{}".to_string(),
+            result: "{}".to_string(),
+            variant: None,
+            project: None,
+            git_remote: None,
+            run_id: run_id.map(str::to_string),
+            model: Some(crate::agents::DEFAULT_MODEL.to_string()),
+            cost_usd: Some(0.12),
+            created_at: Utc::now(),
+            agents: Vec::new(),
+            tags: vec!["cli".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let here = tempfile::tempdir().unwrap();
+        let id = "20250101-120000-00ab";
+        fs::write(here.path().join(format!("{}.jsonl", id)), "{}\n").unwrap();
+        let files = here.path().join(id).join("files/src");
+        fs::create_dir_all(&files).unwrap();
+        fs::write(files.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let path = here.path().join("run.tar.gz");
+        let written = write_archive(&path, &task(Some(id)), here.path()).unwrap();
+        assert!(written.has_log);
+        assert_eq!(written.files, 1);
+
+        let there = tempfile::tempdir().unwrap();
+        let (imported, contents) = read_archive(&path, there.path()).unwrap();
+        assert_eq!(imported.description, "Add a --json flag");
+        assert_eq!(imported.tags, vec!["cli"]);
+        assert!(contents.has_log);
+        assert_eq!(contents.files, 1);
+        assert_eq!(
+            fs::read_to_string(there.path().join(id).join("files/src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert!(there.path().join(format!("{}.jsonl", id)).exists());
+
+        // A second import would clash with the first, unless the first
+        // was rolled back
+        assert!(read_archive(&path, there.path()).is_err());
+        remove_unpacked(there.path(), id);
+        assert!(read_archive(&path, there.path()).is_ok());
+    }
+
+    #[test]
+    fn test_imported_run_survives_cleanup() {
+        let here = tempfile::tempdir().unwrap();
+        let id = "20250101-120000-00ab";
+        let log = here.path().join(format!("{}.jsonl", id));
+        fs::write(&log, "{}\n").unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(400 * 24 * 60 * 60);
+        File::options()
+            .write(true)
+            .open(&log)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        fs::create_dir_all(here.path().join(id).join("files")).unwrap();
+        fs::write(here.path().join(id).join("files/main.rs"), "").unwrap();
+
+        let path = here.path().join("run.tar.gz");
+        write_archive(&path, &task(Some(id)), here.path()).unwrap();
+
+        let there = tempfile::tempdir().unwrap();
+        read_archive(&path, there.path()).unwrap();
+        let (logs_before, artifacts_before) =
+            retention::cutoffs(&crate::config::RetentionConfig::default());
+        let cleaned =
+            retention::clean_dir(there.path(), logs_before, artifacts_before, false).unwrap();
+        assert_eq!(cleaned, retention::Cleaned::default());
+        assert!(there.path().join(format!("{}.jsonl", id)).exists());
+        assert!(there.path().join(id).join("files/main.rs").exists());
+    }
+
+    #[test]
+    fn test_archive_without_run_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.tar.gz");
+        let written = write_archive(&path, &task(None), dir.path()).unwrap();
+        assert!(!written.has_log);
+
+        let (imported, contents) = read_archive(&path, dir.path()).unwrap();
+        assert_eq!(imported.actual_tokens, 4200);
+        assert_eq!(contents.run_id, None);
+
+        fs::write(&path, "not a tarball").unwrap();
+        assert!(read_archive(&path, dir.path()).is_err());
+    }
+}
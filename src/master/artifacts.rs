@@ -0,0 +1,264 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A fenced code block an agent annotated with the file it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Relative path the block should be written to
+    pub path: PathBuf,
+    pub language: Option<String>,
+    pub content: String,
+}
+
+/// A fenced code block of an agent answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct FencedBlock {
+    pub language: Option<String>,
+    /// File the block names, as written
+    pub path: Option<String>,
+    pub content: String,
+}
+
+/// Every fenced code block in an agent answer, with the file it names, if
+/// any, in the fence or on the line just above it
+pub fn fenced_blocks(output: &str) -> Vec<FencedBlock> {
+    let mut blocks = Vec::new();
+    let mut previous = "";
+    let mut lines = output.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let fence_len = trimmed.chars().take_while(|&c| c == '`').count();
+        if fence_len < 3 {
+            if !line.trim().is_empty() {
+                previous = line;
+            }
+            continue;
+        }
+
+        let fence = &trimmed[..fence_len];
+        let info = trimmed[fence_len..].trim();
+        let mut content = String::new();
+        for inner in lines.by_ref() {
+            if inner.trim() == fence {
+                break;
+            }
+            content.push_str(inner);
+            content.push('\n');
+        }
+
+        let (language, fence_path) = parse_info(info);
+        blocks.push(FencedBlock {
+            language,
+            path: fence_path.or_else(|| path_above(previous)),
+            content,
+        });
+        previous = "";
+    }
+
+    blocks
+}
+
+/// Find the code blocks in an agent answer that name their file, either in
+/// the fence (```` ```rust src/lib.rs ````, ```` ```rust title="src/lib.rs" ````,
+/// ```` ```src/lib.rs ````) or on the line just above it (`File: src/lib.rs`,
+/// `**src/lib.rs**`, `` `src/lib.rs`: ``). Blocks without a path, or with a
+/// path outside the project, are left out.
+pub fn extract_code_blocks(output: &str) -> Vec<CodeBlock> {
+    fenced_blocks(output)
+        .into_iter()
+        .filter_map(|block| {
+            Some(CodeBlock {
+                path: safe_path(block.path.as_deref()?)?,
+                language: block.language,
+                content: block.content,
+            })
+        })
+        .collect()
+}
+
+/// Split a fence info string into its language and file path
+fn parse_info(info: &str) -> (Option<String>, Option<String>) {
+    if info.is_empty() {
+        return (None, None);
+    }
+
+    if let Some((_, rest)) = info.split_once("title=") {
+        let path = rest
+            .trim_start_matches(['"', '\''])
+            .split(['"', '\''])
+            .next()
+            .unwrap_or_default();
+        let language = info.split_whitespace().next().filter(|l| !l.contains('='));
+        return (language.map(str::to_string), Some(path.to_string()));
+    }
+
+    let mut words = info.split_whitespace();
+    let first = words.next().unwrap_or_default();
+    if let Some((language, path)) = first.split_once(':') {
+        return (Some(language.to_string()), Some(path.to_string()));
+    }
+    if looks_like_path(first) {
+        return (None, Some(first.to_string()));
+    }
+    (Some(first.to_string()), words.next().map(str::to_string))
+}
+
+/// A path named on the line before a fence
+fn path_above(line: &str) -> Option<String> {
+    let line = line.trim().trim_end_matches(':').trim();
+    let line = ["File:", "file:", "Filename:", "Path:", "//", "#"]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .unwrap_or(line)
+        .trim()
+        .trim_matches(['*', '`'])
+        .trim_end_matches(':');
+
+    (!line.contains(char::is_whitespace) && looks_like_path(line)).then(|| line.to_string())
+}
+
+fn looks_like_path(s: &str) -> bool {
+    s.contains('/')
+        || s.rsplit_once('.')
+            .is_some_and(|(stem, ext)| !stem.is_empty() && !ext.is_empty())
+}
+
+/// Version control, CI, hook and editor task files agents may not write:
+/// writing them could run code on the next commit, checkout, pipeline run,
+/// `cd` into the project or editor action
+const PROTECTED_PATHS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    ".github",
+    ".gitlab-ci.yml",
+    ".circleci",
+    ".gitmodules",
+    ".husky",
+    ".pre-commit-config.yaml",
+    ".claude",
+    ".envrc",
+    ".vscode/tasks.json",
+];
+
+/// `path` as a relative path inside the project, or None if it would
+/// escape it or touch a protected file
+fn safe_path(path: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(path.trim_start_matches("./"));
+    let mut names = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => names.push(name.to_str()?),
+            _ => return None,
+        }
+    }
+
+    let protected = (0..names.len()).any(|start| {
+        PROTECTED_PATHS.iter().any(|protected| {
+            let parts = protected.split('/').collect::<Vec<_>>();
+            names.len() - start >= parts.len()
+                && parts
+                    .iter()
+                    .zip(&names[start..])
+                    .all(|(part, name)| name.eq_ignore_ascii_case(part))
+        })
+    });
+
+    (!protected && !names.is_empty()).then_some(path)
+}
+
+/// Write `blocks` under `root`. When several blocks name the same file the
+/// last one wins, so later phases override earlier drafts. Returns the
+/// files written, relative to `root`.
+pub fn write_blocks(blocks: &[CodeBlock], root: &Path) -> Result<Vec<PathBuf>> {
+    let files: BTreeMap<&Path, &str> = blocks
+        .iter()
+        .map(|block| (block.path.as_path(), block.content.as_str()))
+        .collect();
+
+    for (path, content) in &files {
+        let target = root.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        fs::write(&target, content).with_context(|| format!("Failed to write {:?}", target))?;
+    }
+
+    Ok(files.into_keys().map(Path::to_path_buf).collect())
+}
+
+/// Directory holding the files extracted from a run
+pub fn artifact_dir(run_id: &str) -> Result<PathBuf> {
+    Ok(Config::runs_dir()?.join(run_id).join("files"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let output = "Here is the change.\n\n\
+            ```rust src/lib.rs\npub fn a() {}\n```\n\n\
+            **tests/a.rs**\n```rust\n#[test]\nfn t() {}\n```\n\n\
+            ```toml title=\"Cargo.toml\"\n[package]\n```\n\n\
+            ```bash\ncargo test\n```\n\n\
+            ```rust ../outside.rs\nbad\n```\n";
+
+        let blocks = extract_code_blocks(output);
+        let paths: Vec<_> = blocks.iter().map(|b| b.path.to_str().unwrap()).collect();
+        assert_eq!(paths, vec!["src/lib.rs", "tests/a.rs", "Cargo.toml"]);
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].content, "pub fn a() {}\n");
+        assert_eq!(blocks[2].language.as_deref(), Some("toml"));
+    }
+
+    #[test]
+    fn test_safe_path() {
+        assert_eq!(safe_path("./src/a.rs"), Some(PathBuf::from("src/a.rs")));
+        assert_eq!(safe_path("../a.rs"), None);
+        assert_eq!(safe_path("/etc/passwd"), None);
+        assert_eq!(safe_path(".git/hooks/pre-commit"), None);
+        assert_eq!(safe_path("vendor/.GIT/config"), None);
+        assert_eq!(safe_path(".github/workflows/ci.yml"), None);
+        assert_eq!(safe_path(".gitlab-ci.yml"), None);
+        assert_eq!(safe_path(".claude/settings.json"), None);
+        assert_eq!(safe_path(".husky/pre-commit"), None);
+        assert_eq!(safe_path(".pre-commit-config.yaml"), None);
+        assert_eq!(safe_path("web/.envrc"), None);
+        assert_eq!(safe_path(".vscode/tasks.json"), None);
+        assert_eq!(safe_path("app/.VSCode/Tasks.json"), None);
+        assert_eq!(
+            safe_path(".vscode/settings.json"),
+            Some(PathBuf::from(".vscode/settings.json"))
+        );
+        assert_eq!(
+            safe_path("src/tasks.json"),
+            Some(PathBuf::from("src/tasks.json"))
+        );
+        assert_eq!(safe_path(".gitignore"), Some(PathBuf::from(".gitignore")));
+    }
+
+    #[test]
+    fn test_write_blocks_last_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let block = |content: &str| CodeBlock {
+            path: PathBuf::from("src/a.rs"),
+            language: None,
+            content: content.to_string(),
+        };
+
+        let written = write_blocks(&[block("draft\n"), block("final\n")], root).unwrap();
+        assert_eq!(written, vec![PathBuf::from("src/a.rs")]);
+        assert_eq!(
+            fs::read_to_string(root.join("src/a.rs")).unwrap(),
+            "final\n"
+        );
+    }
+}
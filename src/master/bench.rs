@@ -0,0 +1,239 @@
+use super::orchestrator::ExecutionResult;
+use super::progress::{ExecutionObserver, RunEvent};
+use super::MasterCoder;
+use crate::config::Config;
+use crate::db::Database;
+use crate::style::{self, Colorize};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// A canned task and the rubric its result is scored by
+pub struct BenchTask {
+    pub name: &'static str,
+    pub prompt: &'static str,
+    /// Phrases a good answer contains (case-insensitive). The score is the
+    /// share of them found in the agents' output.
+    pub expect: &'static [&'static str],
+}
+
+const BASIC: &[BenchTask] = &[
+    BenchTask {
+        name: "fizzbuzz",
+        prompt: "Write a Rust function `fizzbuzz(n: u32) -> String` that returns \"Fizz\" \
+            for multiples of 3, \"Buzz\" for multiples of 5, \"FizzBuzz\" for multiples of \
+            both and the number otherwise. Include unit tests.",
+        expect: &["fn fizzbuzz", "FizzBuzz", "#[test]", "assert_eq!"],
+    },
+    BenchTask {
+        name: "slugify",
+        prompt: "Write a Python function `slugify(title)` that lowercases a title, replaces \
+            runs of non-alphanumeric characters with a single hyphen and strips leading and \
+            trailing hyphens. Add pytest tests.",
+        expect: &["def slugify", ".lower()", "def test_", "strip"],
+    },
+    BenchTask {
+        name: "review",
+        prompt: "Review this Rust function, list its bugs and give a fixed version:\n\n\
+            fn average(xs: &[i32]) -> i32 {\n    xs.iter().sum::<i32>() / xs.len() as i32\n}",
+        expect: &["empty", "overflow", "fn average", "Option"],
+    },
+];
+
+/// Benchmark suites by name
+pub const SUITES: &[(&str, &[BenchTask])] = &[("basic", BASIC)];
+
+pub fn suite(name: &str) -> Result<&'static [BenchTask]> {
+    SUITES
+        .iter()
+        .find(|(suite, _)| *suite == name)
+        .map(|(_, tasks)| *tasks)
+        .ok_or_else(|| {
+            let names: Vec<_> = SUITES.iter().map(|(suite, _)| *suite).collect();
+            anyhow::anyhow!("Unknown suite: {} (available: {})", name, names.join(", "))
+        })
+}
+
+/// How one task of a suite went with one model
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub suite: String,
+    pub model: String,
+    pub task: String,
+    pub success: bool,
+    pub tokens_used: usize,
+    pub execution_time_secs: f64,
+    /// Rubric score, 0.0-1.0
+    pub score: f64,
+    /// Prompt variant the task ran with, if labelled
+    pub variant: Option<String>,
+}
+
+/// Share of `task`'s expected phrases found in `output`
+pub fn score(task: &BenchTask, output: &str) -> f64 {
+    if task.expect.is_empty() {
+        return 1.0;
+    }
+
+    let output = output.to_lowercase();
+    let found = task
+        .expect
+        .iter()
+        .filter(|phrase| output.contains(&phrase.to_lowercase()))
+        .count();
+    found as f64 / task.expect.len() as f64
+}
+
+/// Runs benchmark tasks unattended: prints nothing and approves everything
+struct BenchObserver;
+
+#[async_trait]
+impl ExecutionObserver for BenchObserver {
+    fn on_event(&self, _event: RunEvent) {}
+
+    async fn approve(&self, _prompt: &str) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Run every task of `suite_name` through Master Coder once per model, in
+/// trust mode, saving each result to the database
+pub async fn run(
+    config: &Config,
+    db: Arc<Database>,
+    suite_name: &str,
+    models: &[String],
+    variant: Option<&str>,
+) -> Result<Vec<BenchResult>> {
+    let tasks = suite(suite_name)?;
+    config.network.ensure_online("Running a benchmark")?;
+
+    let mut results = Vec::new();
+    for model in models {
+        // Record the model ID, so results stay comparable when an alias moves on
+        let model = config.models.resolve(model);
+        let mut config = config.clone();
+        config.master_coder.model = Some(model.clone());
+
+        for task in tasks {
+            println!(
+                "{} {} / {}",
+                style::pick("▶", ">").bright_cyan(),
+                model,
+                task.name
+            );
+
+            let mut master =
+                MasterCoder::new(config.clone(), "trust".to_string(), db.clone()).await?;
+            master.set_tags(vec!["bench".to_string()]);
+            master.set_observer(Arc::new(BenchObserver));
+            master.set_variant(variant.map(str::to_string));
+
+            let mut result = BenchResult {
+                suite: suite_name.to_string(),
+                model: model.clone(),
+                task: task.name.to_string(),
+                success: false,
+                tokens_used: 0,
+                execution_time_secs: 0.0,
+                score: 0.0,
+                variant: variant.map(str::to_string),
+            };
+            match master.execute(task.prompt).await {
+                Ok(Some(execution)) => {
+                    result.success = execution.success;
+                    result.tokens_used = execution.tokens_used;
+                    result.execution_time_secs = execution.execution_time_secs;
+                    if execution.success {
+                        result.score = score(task, &agent_output(&execution));
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => println!("  {} {:#}", style::fail().red(), e),
+            }
+
+            db.save_bench_result(&result).await?;
+            results.push(result);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Everything the agents of a run answered
+fn agent_output(result: &ExecutionResult) -> String {
+    result
+        .agents
+        .iter()
+        .filter_map(|agent| agent.output.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Print each task's result, then one comparison row per model
+pub fn print_comparison(results: &[BenchResult]) {
+    println!(
+        "\n{:<12} {:<28} {:>4} {:>9} {:>8} {:>6}",
+        "Task", "Model", "OK", "Tokens", "Time", "Score"
+    );
+    for r in results {
+        println!(
+            "{:<12} {:<28} {:>4} {:>9} {:>7.1}s {:>5.0}%",
+            r.task,
+            r.model,
+            if r.success {
+                style::ok()
+            } else {
+                style::fail()
+            },
+            r.tokens_used,
+            r.execution_time_secs,
+            r.score * 100.0
+        );
+    }
+
+    println!("\n{}", "Comparison:".bright_yellow().bold());
+    println!(
+        "{:<28} {:>7} {:>10} {:>9} {:>10}",
+        "Model", "Passed", "Tokens", "Avg time", "Avg score"
+    );
+    let mut models: Vec<&str> = Vec::new();
+    for r in results {
+        if !models.contains(&r.model.as_str()) {
+            models.push(&r.model);
+        }
+    }
+    for model in models {
+        let runs: Vec<_> = results.iter().filter(|r| r.model == model).collect();
+        let count = runs.len() as f64;
+        println!(
+            "{:<28} {:>7} {:>10} {:>8.1}s {:>9.0}%",
+            model,
+            format!(
+                "{}/{}",
+                runs.iter().filter(|r| r.success).count(),
+                runs.len()
+            ),
+            runs.iter().map(|r| r.tokens_used).sum::<usize>(),
+            runs.iter().map(|r| r.execution_time_secs).sum::<f64>() / count,
+            runs.iter().map(|r| r.score).sum::<f64>() / count * 100.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let task = &suite("basic").unwrap()[0];
+        assert_eq!(score(task, "nothing useful"), 0.0);
+        assert_eq!(
+            score(task, "pub fn fizzbuzz(n: u32)\n\"FizzBuzz\"\n#[test]"),
+            0.75
+        );
+        assert!(suite("huge").is_err());
+    }
+}
@@ -0,0 +1,435 @@
+use crate::db::{estimate_cost, TaskRecord};
+use crate::style::{self, Colorize};
+
+/// How an agent of a run went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Ok,
+    Failed,
+    /// Planned, but never started
+    NotRun,
+}
+
+/// An agent of a run: its plan entry, or an outcome without one (the
+/// Test Debugger), and the tokens it used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slot {
+    pub status: Status,
+    pub tokens: usize,
+}
+
+/// Agents of the same type in two runs, paired in plan order. A side is
+/// None when that run had fewer agents of the type.
+#[derive(Debug, PartialEq)]
+pub struct AgentPair {
+    pub agent_type: String,
+    pub left: Option<Slot>,
+    pub right: Option<Slot>,
+}
+
+/// The agents of `task` in plan order, followed by outcomes of agents the
+/// plan doesn't list
+fn agents(task: &TaskRecord) -> Vec<(String, Slot)> {
+    let outcomes = task
+        .result
+        .as_ref()
+        .map(|r| r.agents.as_slice())
+        .unwrap_or_default();
+    let slot = |id: &str| match outcomes.iter().find(|o| o.agent_id == id) {
+        Some(o) if o.error.is_none() => Slot {
+            status: Status::Ok,
+            tokens: o.tokens_used,
+        },
+        Some(o) => Slot {
+            status: Status::Failed,
+            tokens: o.tokens_used,
+        },
+        None => Slot {
+            status: Status::NotRun,
+            tokens: 0,
+        },
+    };
+
+    let planned: Vec<_> = task
+        .plan
+        .iter()
+        .flat_map(|plan| &plan.phases)
+        .flat_map(|phase| &phase.agents)
+        .collect();
+    let mut agents: Vec<(String, Slot)> = planned
+        .iter()
+        .map(|spec| (spec.agent_type.clone(), slot(&spec.id)))
+        .collect();
+    agents.extend(
+        outcomes
+            .iter()
+            .filter(|o| !planned.iter().any(|spec| spec.id == o.agent_id))
+            .map(|o| (o.agent_type.clone(), slot(&o.agent_id))),
+    );
+    agents
+}
+
+/// Pair the agents of two runs by type. Agent IDs are not compared: a
+/// replanned run numbers its agents afresh.
+pub fn pair_agents(left: &TaskRecord, right: &TaskRecord) -> Vec<AgentPair> {
+    let mut unpaired: Vec<Option<(String, Slot)>> = agents(right).into_iter().map(Some).collect();
+
+    let mut pairs: Vec<AgentPair> = agents(left)
+        .into_iter()
+        .map(|(agent_type, slot)| {
+            let right = unpaired
+                .iter_mut()
+                .find(|other| matches!(other, Some((t, _)) if *t == agent_type))
+                .and_then(Option::take)
+                .map(|(_, slot)| slot);
+            AgentPair {
+                agent_type,
+                left: Some(slot),
+                right,
+            }
+        })
+        .collect();
+    pairs.extend(
+        unpaired
+            .into_iter()
+            .flatten()
+            .map(|(agent_type, slot)| AgentPair {
+                agent_type,
+                left: None,
+                right: Some(slot),
+            }),
+    );
+    pairs
+}
+
+/// `right - left` with a sign and, when `left` isn't zero, the change in
+/// percent
+fn change(left: usize, right: usize) -> String {
+    let delta = right as i64 - left as i64;
+    if delta == 0 {
+        return "same".to_string();
+    }
+    if left == 0 {
+        return format!("{:+}", delta);
+    }
+    format!("{:+} ({:+.0}%)", delta, delta as f64 * 100.0 / left as f64)
+}
+
+fn status_cell(status: Option<Status>, tokens: usize) -> String {
+    match status {
+        Some(Status::Ok) => format!("{} {}", style::ok(), tokens),
+        Some(Status::Failed) => format!("{} failed", style::fail()),
+        Some(Status::NotRun) => "not run".to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn first_line(text: &str, width: usize) -> String {
+    text.lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(width)
+        .collect()
+}
+
+/// Print two runs side by side: overall outcome, tokens, cost and time,
+/// then their plans agent by agent
+pub fn print_comparison(left: &TaskRecord, right: &TaskRecord) {
+    let (l, r) = (format!("#{}", left.id), format!("#{}", right.id));
+
+    println!(
+        "\n{} {} vs {}",
+        "Compare".bright_cyan().bold(),
+        l.bright_cyan(),
+        r.bright_cyan()
+    );
+    println!("{}", style::rule(80).bright_cyan());
+    if left.description == right.description {
+        println!("{}", first_line(&left.description, 78));
+    } else {
+        println!("  {:<6}{}", l, first_line(&left.description, 70));
+        println!("  {:<6}{}", r, first_line(&right.description, 70));
+    }
+
+    println!(
+        "\n{}",
+        format!("  {:<12}  {:>18}  {:>18}  {:>18}", "", l, r, "Change")
+            .white()
+            .bold()
+    );
+    let row = |label: &str, left: String, right: String, change: String| {
+        println!(
+            "  {:<12}  {:>18}  {:>18}  {:>18}",
+            label, left, right, change
+        );
+    };
+
+    let status = |task: &TaskRecord| {
+        if task.success {
+            format!("{} ok", style::ok())
+        } else {
+            format!("{} fail", style::fail())
+        }
+    };
+    let outcome = match (left.success, right.success) {
+        (false, true) => format!("{:>18}", "fixed").green().to_string(),
+        (true, false) => format!("{:>18}", "broken").red().to_string(),
+        _ => String::new(),
+    };
+    row("Status", status(left), status(right), outcome);
+    row(
+        "Variant",
+        left.variant.clone().unwrap_or_else(|| "-".to_string()),
+        right.variant.clone().unwrap_or_else(|| "-".to_string()),
+        String::new(),
+    );
+
+    // Fewer tokens for the same task is the improvement to look for
+    let tokens = format!("{:>18}", change(left.actual_tokens, right.actual_tokens));
+    row(
+        "Tokens",
+        left.actual_tokens.to_string(),
+        right.actual_tokens.to_string(),
+        match right.actual_tokens.cmp(&left.actual_tokens) {
+            std::cmp::Ordering::Less => tokens.green().to_string(),
+            std::cmp::Ordering::Greater => tokens.red().to_string(),
+            std::cmp::Ordering::Equal => tokens,
+        },
+    );
+    let (left_cost, right_cost) = (
+        estimate_cost(left.actual_tokens),
+        estimate_cost(right.actual_tokens),
+    );
+    row(
+        "Cost",
+        format!("${:.2}", left_cost),
+        format!("${:.2}", right_cost),
+        format!(
+            "{}${:.2}",
+            if right_cost < left_cost { "-" } else { "+" },
+            (right_cost - left_cost).abs()
+        ),
+    );
+
+    let time = |task: &TaskRecord| task.result.as_ref().map(|r| r.execution_time_secs);
+    if let (Some(left_time), Some(right_time)) = (time(left), time(right)) {
+        row(
+            "Time",
+            format!("{:.1}s", left_time),
+            format!("{:.1}s", right_time),
+            format!("{:+.1}s", right_time - left_time),
+        );
+    }
+
+    let phases = |task: &TaskRecord| task.plan.as_ref().map_or(0, |p| p.phases.len());
+    row(
+        "Phases",
+        phases(left).to_string(),
+        phases(right).to_string(),
+        change(phases(left), phases(right)),
+    );
+    let executed = |task: &TaskRecord| task.result.as_ref().map_or(0, |r| r.agents_executed);
+    row(
+        "Agents run",
+        executed(left).to_string(),
+        executed(right).to_string(),
+        change(executed(left), executed(right)),
+    );
+    let scheduling = |task: &TaskRecord| {
+        task.plan.as_ref().map_or("-".to_string(), |p| {
+            format!("{:?}", p.scheduling).to_lowercase()
+        })
+    };
+    if scheduling(left) != scheduling(right) {
+        row(
+            "Scheduling",
+            scheduling(left),
+            scheduling(right),
+            String::new(),
+        );
+    }
+
+    println!("\n{}", "Agents:".bright_yellow().bold());
+    let pairs = pair_agents(left, right);
+    if pairs.is_empty() {
+        println!("  No plans recorded");
+        return;
+    }
+    println!(
+        "{}",
+        format!("  {:<22}  {:>14}  {:>14}  {:>18}", "Agent", l, r, "Change")
+            .white()
+            .bold()
+    );
+    for pair in pairs {
+        let cell =
+            |slot: Option<Slot>| status_cell(slot.map(|s| s.status), slot.map_or(0, |s| s.tokens));
+        let change = match (pair.left, pair.right) {
+            (Some(a), Some(b)) if a.status == Status::Ok && b.status == Status::Ok => {
+                format!("{:>18}", change(a.tokens, b.tokens)).normal()
+            }
+            (Some(a), Some(b)) if a.status != b.status => match b.status {
+                Status::Ok => format!("{:>18}", "now ok").green(),
+                Status::Failed => format!("{:>18}", "now failing").red(),
+                Status::NotRun => format!("{:>18}", "not run").dimmed(),
+            },
+            (Some(_), Some(_)) => "".normal(),
+            (Some(_), None) => format!("{:>18}", format!("only in {}", l)).dimmed(),
+            (None, _) => format!("{:>18}", format!("only in {}", r)).dimmed(),
+        };
+        println!(
+            "  {:<22}  {:>14}  {:>14}  {}",
+            pair.agent_type.chars().take(22).collect::<String>(),
+            cell(pair.left),
+            cell(pair.right),
+            change
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentCapability;
+    use crate::master::orchestrator::{
+        AgentOutcome, AgentSpec, ExecutionPhase, ExecutionPlan, ExecutionResult, Scheduling,
+    };
+    use chrono::Utc;
+
+    /// A run of agents `(type, tokens, succeeded)`, each in its own phase;
+    /// agents with `None` were planned but not run
+    fn record(id: usize, agents: &[(&str, Option<(usize, bool)>)]) -> TaskRecord {
+        let specs: Vec<AgentSpec> = agents
+            .iter()
+            .enumerate()
+            .map(|(i, (agent_type, _))| AgentSpec {
+                id: format!("agent-{}", i + 1),
+                agent_type: agent_type.to_string(),
+                capability: AgentCapability::CodeWriting,
+                task: "work".to_string(),
+                dependencies: Vec::new(),
+                budget_share: 0.0,
+                output_schema: None,
+            })
+            .collect();
+        let outcomes: Vec<AgentOutcome> = specs
+            .iter()
+            .zip(agents)
+            .filter_map(|(spec, (_, run))| {
+                let (tokens, ok) = (*run)?;
+                Some(AgentOutcome {
+                    agent_id: spec.id.clone(),
+                    agent_type: spec.agent_type.clone(),
+                    tokens_used: tokens,
+                    cost_usd: 0.0,
+                    execution_time_secs: 1.0,
+                    error: (!ok).then(|| "boom".to_string()),
+                    system_prompt: None,
+                    output: None,
+                    rate_limited: false,
+                })
+            })
+            .collect();
+
+        TaskRecord {
+            id,
+            description: "Add tests".to_string(),
+            estimated_tokens: 0,
+            actual_tokens: outcomes.iter().map(|o| o.tokens_used).sum(),
+            success: outcomes.iter().all(|o| o.error.is_none()),
+            analysis: None,
+            plan: Some(ExecutionPlan {
+                phases: specs
+                    .into_iter()
+                    .map(|spec| ExecutionPhase {
+                        description: "Work".to_string(),
+                        agents: vec![spec],
+                        parallel: false,
+                    })
+                    .collect(),
+                scheduling: Scheduling::Phases,
+            }),
+            result: Some(ExecutionResult {
+                success: true,
+                agents_executed: outcomes.len(),
+                tokens_used: 0,
+                cost_usd: 0.0,
+                execution_time_secs: 2.0,
+                errors: Vec::new(),
+                warnings: Vec::new(),
+                agents: outcomes,
+                tests: None,
+                blocked: None,
+            }),
+            variant: None,
+            project: None,
+            run_id: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_pair_agents_by_type() {
+        let left = record(
+            1,
+            &[
+                ("Architect", Some((2000, true))),
+                ("Coder", Some((5000, true))),
+                ("Tester", Some((900, true))),
+            ],
+        );
+        let right = record(
+            2,
+            &[
+                ("Coder", Some((4000, false))),
+                ("Architect", Some((1500, true))),
+                ("Reviewer", None),
+            ],
+        );
+
+        let pairs = pair_agents(&left, &right);
+        let ok = |tokens| Slot {
+            status: Status::Ok,
+            tokens,
+        };
+        assert_eq!(pairs.len(), 4);
+        assert_eq!(pairs[0].agent_type, "Architect");
+        assert_eq!(
+            (pairs[0].left, pairs[0].right),
+            (Some(ok(2000)), Some(ok(1500)))
+        );
+        assert_eq!(pairs[1].right.unwrap().status, Status::Failed);
+        assert_eq!(
+            (pairs[2].agent_type.as_str(), pairs[2].right),
+            ("Tester", None)
+        );
+        assert_eq!(pairs[3].agent_type, "Reviewer");
+        assert_eq!(pairs[3].left, None);
+        assert_eq!(pairs[3].right.unwrap().status, Status::NotRun);
+    }
+
+    #[test]
+    fn test_pair_agents_includes_unplanned_outcomes() {
+        let left = record(1, &[("Coder", Some((100, true)))]);
+        let mut right = record(2, &[("Coder", Some((100, true)))]);
+        let result = right.result.as_mut().unwrap();
+        let mut debugger = result.agents[0].clone();
+        debugger.agent_id = "test-debugger".to_string();
+        debugger.agent_type = "Test Debugger".to_string();
+        result.agents.push(debugger);
+
+        let pairs = pair_agents(&left, &right);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].agent_type, "Test Debugger");
+        assert_eq!(pairs[1].left, None);
+    }
+
+    #[test]
+    fn test_change() {
+        assert_eq!(change(1000, 750), "-250 (-25%)");
+        assert_eq!(change(1000, 1500), "+500 (+50%)");
+        assert_eq!(change(0, 300), "+300");
+        assert_eq!(change(42, 42), "same");
+    }
+}
@@ -0,0 +1,339 @@
+use super::orchestrator::{AgentSpec, ExecutionPlan};
+use super::planner::{TaskAnalysis, TaskPlanner};
+use super::terminal::{print_analysis, print_plan};
+use crate::agents::DEFAULT_MODEL;
+use crate::db::{estimate_cost, Database, TaskOutcome};
+use crate::embeddings::Embedder;
+use crate::style::{self, Colorize};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use tracing::warn;
+
+/// Past tasks searched for similar ones
+const HISTORY_LIMIT: usize = 200;
+
+/// Share of keywords two task descriptions must have in common to count
+/// as similar
+const MIN_SIMILARITY: f64 = 0.3;
+
+/// Similar tasks listed
+const SIMILAR_LIMIT: usize = 5;
+
+/// Expected tokens, cost and time of a task, from the planner and from
+/// how similar tasks went before. Nothing is executed.
+pub struct Estimate {
+    pub analysis: TaskAnalysis,
+    pub plan: ExecutionPlan,
+    /// Planner tokens split by the model each agent calls
+    pub costs: Vec<ModelCost>,
+    /// Most similar past tasks first
+    pub similar: Vec<SimilarTask>,
+}
+
+pub struct ModelCost {
+    pub model: String,
+    pub agents: usize,
+    pub tokens: usize,
+    pub cost_usd: f64,
+}
+
+pub struct SimilarTask {
+    pub outcome: TaskOutcome,
+    /// Keyword overlap with the estimated task or, with embeddings, the
+    /// cosine similarity of their meaning, 0.0-1.0
+    pub similarity: f64,
+}
+
+impl Estimate {
+    pub async fn new(
+        planner: &TaskPlanner,
+        db: &Database,
+        embedder: Option<&Embedder>,
+        task: &str,
+        max_agents: usize,
+    ) -> Result<Self> {
+        let analysis = planner.analyze_task(task).await?;
+        let plan = planner.create_plan(&analysis, max_agents).await?;
+        let costs = cost_by_model(&plan, analysis.estimated_tokens);
+        let similar = similar_tasks(db, embedder, task).await?;
+
+        Ok(Self {
+            analysis,
+            plan,
+            costs,
+            similar,
+        })
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.costs.iter().map(|c| c.cost_usd).sum()
+    }
+
+    /// Planner tokens scaled by how far off the estimates of similar
+    /// successful tasks were. None without such history.
+    pub fn adjusted_tokens(&self) -> Option<usize> {
+        let (estimated, actual) = self.successful().fold((0, 0), |(estimated, actual), t| {
+            (estimated + t.estimated_tokens, actual + t.actual_tokens)
+        });
+        if estimated == 0 {
+            return None;
+        }
+
+        let ratio = actual as f64 / estimated as f64;
+        Some((self.analysis.estimated_tokens as f64 * ratio) as usize)
+    }
+
+    /// Shortest and longest wall-clock time of similar successful tasks
+    pub fn similar_time_range(&self) -> Option<(f64, f64)> {
+        self.successful()
+            .filter_map(|t| t.execution_time_secs)
+            .fold(None, |range, secs| match range {
+                None => Some((secs, secs)),
+                Some((min, max)) => Some((f64::min(min, secs), f64::max(max, secs))),
+            })
+    }
+
+    fn successful(&self) -> impl Iterator<Item = &TaskOutcome> {
+        self.similar
+            .iter()
+            .map(|s| &s.outcome)
+            .filter(|t| t.success)
+    }
+
+    /// Print the analysis, the plan and the estimate
+    pub fn print(&self, token_budget: usize) {
+        print_analysis(&self.analysis);
+        print_plan(&self.plan, token_budget);
+
+        println!("\n{}", "Estimate:".bright_yellow().bold());
+        println!(
+            "  {} ~{}",
+            "Planner tokens:".white(),
+            self.analysis.estimated_tokens
+        );
+        if let Some(adjusted) = self.adjusted_tokens() {
+            println!("  {} ~{}", "Adjusted by history:".white(), adjusted);
+        }
+        let expected = self
+            .adjusted_tokens()
+            .unwrap_or(self.analysis.estimated_tokens);
+        if expected > token_budget {
+            println!(
+                "  {} Expected tokens exceed the token budget ({})",
+                style::warn().yellow(),
+                token_budget
+            );
+        }
+
+        println!("\n  {}", "Cost by model:".white());
+        for cost in &self.costs {
+            println!(
+                "    {} {} ({} agents, ~{} tokens): ${:.2}",
+                style::bullet().bright_cyan(),
+                cost.model,
+                cost.agents,
+                cost.tokens,
+                cost.cost_usd
+            );
+        }
+        println!("    Total: ${:.2}", self.total_cost());
+
+        println!(
+            "\n  {} {}-{} minutes",
+            "Wall-clock time:".white(),
+            self.analysis.estimated_time_min,
+            self.analysis.estimated_time_max
+        );
+        if let Some((min, max)) = self.similar_time_range() {
+            println!(
+                "  {} {}-{}",
+                "Similar tasks took:".white(),
+                format_secs(min),
+                format_secs(max)
+            );
+        }
+
+        println!("\n{}", "Similar past tasks:".bright_yellow().bold());
+        if self.similar.is_empty() {
+            println!("  None found in the task history");
+        }
+        for similar in &self.similar {
+            let task = &similar.outcome;
+            println!(
+                "  {} #{} {} ({:.0}% similar)",
+                if task.success {
+                    style::ok().green()
+                } else {
+                    style::fail().red()
+                },
+                task.id,
+                task.description.lines().next().unwrap_or_default(),
+                similar.similarity * 100.0
+            );
+            println!(
+                "      estimated ~{} tokens, used {}{}, {}",
+                task.estimated_tokens,
+                task.actual_tokens,
+                task.execution_time_secs
+                    .map(|secs| format!(" in {}", format_secs(secs)))
+                    .unwrap_or_default(),
+                task.timestamp.format("%Y-%m-%d")
+            );
+        }
+    }
+}
+
+/// Model an agent calls. Every agent uses [`DEFAULT_MODEL`] for now.
+fn model_for(_spec: &AgentSpec) -> &'static str {
+    DEFAULT_MODEL
+}
+
+/// Split `tokens` evenly across the plan's agents and total them per model
+fn cost_by_model(plan: &ExecutionPlan, tokens: usize) -> Vec<ModelCost> {
+    let agents: Vec<&AgentSpec> = plan.phases.iter().flat_map(|p| &p.agents).collect();
+    let total_agents = agents.len().max(1);
+
+    let mut by_model: BTreeMap<&str, usize> = BTreeMap::new();
+    for spec in &agents {
+        *by_model.entry(model_for(spec)).or_default() += 1;
+    }
+
+    by_model
+        .into_iter()
+        .map(|(model, agents)| {
+            let tokens = tokens * agents / total_agents;
+            ModelCost {
+                model: model.to_string(),
+                agents,
+                tokens,
+                cost_usd: estimate_cost(tokens),
+            }
+        })
+        .collect()
+}
+
+/// Past tasks similar to `task`, most similar first: by meaning with an
+/// embedder, otherwise (or when the embeddings provider fails) by keywords
+pub(crate) async fn similar_tasks(
+    db: &Database,
+    embedder: Option<&Embedder>,
+    task: &str,
+) -> Result<Vec<SimilarTask>> {
+    let history = db.get_task_outcomes(HISTORY_LIMIT).await?;
+    let Some(embedder) = embedder else {
+        return Ok(find_similar(task, history));
+    };
+
+    let candidates: Vec<(usize, String)> = history
+        .iter()
+        .enumerate()
+        .map(|(i, outcome)| (i, outcome.description.clone()))
+        .collect();
+    let ranked = match embedder.rank(db, task, &candidates).await {
+        Ok(ranked) => ranked,
+        Err(e) => {
+            warn!(
+                "Matching tasks by keywords, embeddings unavailable: {:#}",
+                e
+            );
+            return Ok(find_similar(task, history));
+        }
+    };
+
+    let mut history: Vec<Option<TaskOutcome>> = history.into_iter().map(Some).collect();
+    Ok(ranked
+        .into_iter()
+        .filter(|(_, similarity)| *similarity >= embedder.min_similarity())
+        .take(SIMILAR_LIMIT)
+        .filter_map(|(i, similarity)| {
+            Some(SimilarTask {
+                outcome: history[i].take()?,
+                similarity,
+            })
+        })
+        .collect())
+}
+
+/// Past tasks sharing enough keywords with `task`, most similar first
+fn find_similar(task: &str, history: Vec<TaskOutcome>) -> Vec<SimilarTask> {
+    let wanted = keywords(task);
+
+    let mut similar: Vec<SimilarTask> = history
+        .into_iter()
+        .map(|outcome| SimilarTask {
+            similarity: similarity(&wanted, &keywords(&outcome.description)),
+            outcome,
+        })
+        .filter(|s| s.similarity >= MIN_SIMILARITY)
+        .collect();
+
+    similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    similar.truncate(SIMILAR_LIMIT);
+    similar
+}
+
+/// Lowercase words of more than three letters, like the planner's keywords
+pub(crate) fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() > 3)
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Jaccard index of two keyword sets
+pub(crate) fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn format_secs(secs: f64) -> String {
+    if secs < 60.0 {
+        format!("{:.0}s", secs)
+    } else {
+        format!("{:.1} min", secs / 60.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn outcome(id: usize, description: &str) -> TaskOutcome {
+        TaskOutcome {
+            id,
+            description: description.to_string(),
+            estimated_tokens: 1000,
+            actual_tokens: 1500,
+            success: true,
+            execution_time_secs: Some(90.0),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_find_similar_ranks_by_overlap() {
+        let similar = find_similar(
+            "Add unit tests for the login handler",
+            vec![
+                outcome(1, "Refactor the database layer"),
+                outcome(2, "Write unit tests for login"),
+                outcome(3, "Add unit tests for the login handler, again"),
+            ],
+        );
+
+        let ids: Vec<usize> = similar.iter().map(|s| s.outcome.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_keywords_ignore_case_and_punctuation() {
+        let words = keywords("Fix the Parser, then test it.");
+        assert!(words.contains("parser"));
+        assert!(words.contains("test"));
+        assert!(!words.contains("the"));
+    }
+}
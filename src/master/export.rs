@@ -0,0 +1,439 @@
+use super::format_complexity;
+use crate::agents::AgentCapability;
+use crate::db::{estimate_cost, TaskRecord};
+use anyhow::Result;
+use chrono::Local;
+
+/// Longest commit subject line, as git tooling expects
+const SUBJECT_WIDTH: usize = 72;
+
+/// Output format of `claude-helper tasks export`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    /// Conventional-commit subject line, then a PR body
+    Pr,
+}
+
+impl ExportFormat {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "pr" => Ok(Self::Pr),
+            _ => anyhow::bail!("Invalid export format: {} (use md or pr)", s),
+        }
+    }
+}
+
+/// A run as a commit message: a conventional-commit subject, a blank line
+/// and a PR body with the task, plan, agents, tokens and test results.
+/// Works with `git commit -F` and as the title and body of a PR.
+pub fn pr_summary(task: &TaskRecord) -> String {
+    format!("{}\n\n{}", commit_subject(task), pr_body(task))
+}
+
+/// `feat: add a --dry-run flag` style subject for the run's task
+pub fn commit_subject(task: &TaskRecord) -> String {
+    let prefix = format!("{}: ", commit_type(task));
+    let first_line = task.description.lines().next().unwrap_or_default().trim();
+    let mut summary: String = first_line.trim_end_matches('.').to_string();
+    if let Some(first) = summary.chars().next() {
+        // Keep acronyms like "API" as they are
+        if !summary.chars().nth(1).is_some_and(char::is_uppercase) {
+            summary.replace_range(..first.len_utf8(), &first.to_lowercase().to_string());
+        }
+    }
+
+    let width = SUBJECT_WIDTH - prefix.len();
+    if summary.chars().count() > width {
+        let cut: String = summary.chars().take(width).collect();
+        summary = match cut.rsplit_once(' ') {
+            Some((words, _)) if !words.is_empty() => words.to_string(),
+            _ => cut,
+        };
+    }
+    format!("{}{}", prefix, summary)
+}
+
+/// Conventional-commit type, from the task's wording and, failing that,
+/// the expertise it needed
+fn commit_type(task: &TaskRecord) -> &'static str {
+    let text = task.description.to_lowercase();
+    let has = |words: &[&str]| {
+        text.split(|c: char| !c.is_alphanumeric())
+            .any(|word| words.contains(&word))
+    };
+
+    if has(&["fix", "fixes", "bug", "crash", "broken"]) {
+        return "fix";
+    }
+    if has(&["refactor", "restructure", "cleanup"]) {
+        return "refactor";
+    }
+    if has(&["optimize", "performance", "speed", "faster"]) {
+        return "perf";
+    }
+    let capabilities = task
+        .analysis
+        .as_ref()
+        .map(|analysis| analysis.required_capabilities.as_slice())
+        .unwrap_or_default();
+    match capabilities {
+        [AgentCapability::Testing] => "test",
+        [AgentCapability::Documentation] => "docs",
+        _ if has(&["test", "tests"]) && !has(&["implement", "feature"]) => "test",
+        _ if has(&["document", "docs", "readme"]) => "docs",
+        _ => "feat",
+    }
+}
+
+/// PR description of a run, without the title
+pub fn pr_body(task: &TaskRecord) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("## Task\n\n{}\n\n", task.description.trim_end()));
+
+    let outcomes = task
+        .result
+        .as_ref()
+        .map(|r| r.agents.as_slice())
+        .unwrap_or_default();
+    if let Some(plan) = &task.plan {
+        out.push_str("## Plan\n\n");
+        for phase in &plan.phases {
+            out.push_str(&format!(
+                "- {}{}\n",
+                phase.description,
+                if phase.parallel { " (parallel)" } else { "" }
+            ));
+            for spec in &phase.agents {
+                let after = plan
+                    .waits_for(spec)
+                    .map(|after| format!(" ({})", after))
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "  - {}: {}{}\n",
+                    spec.agent_type, spec.task, after
+                ));
+            }
+        }
+        out.push('\n');
+    }
+
+    if !outcomes.is_empty() {
+        out.push_str("## Agents\n\n| Agent | Tokens | Time | Result |\n|---|---:|---:|---|\n");
+        for outcome in outcomes {
+            out.push_str(&format!(
+                "| {} | {} | {:.1}s | {} |\n",
+                outcome.agent_type,
+                outcome.tokens_used,
+                outcome.execution_time_secs,
+                match &outcome.error {
+                    Some(error) => format!("failed: {}", error.replace('|', "\\|")),
+                    None => "done".to_string(),
+                }
+            ));
+        }
+        out.push('\n');
+    }
+
+    out.push_str(&format!(
+        "## Tokens\n\n{} used (${:.2}), estimated {}\n\n",
+        task.actual_tokens,
+        estimate_cost(task.actual_tokens),
+        task.estimated_tokens
+    ));
+
+    out.push_str("## Tests\n\n");
+    match task
+        .result
+        .as_ref()
+        .and_then(|result| result.tests.as_ref())
+    {
+        Some(tests) => {
+            let rounds = match tests.debug_rounds {
+                0 => String::new(),
+                1 => " after 1 debugging round".to_string(),
+                n => format!(" after {} debugging rounds", n),
+            };
+            out.push_str(&format!(
+                "`{}` {}{}\n\n",
+                tests.command,
+                if tests.passed { "passed" } else { "failed" },
+                rounds
+            ));
+        }
+        None => out.push_str("Not run by Claude Helper.\n\n"),
+    }
+
+    if let Some(result) = &task.result {
+        if !result.errors.is_empty() {
+            out.push_str("## Errors\n\n");
+            for error in &result.errors {
+                // Test output spans lines; only its first line fits a bullet
+                out.push_str(&format!("- {}\n", error.lines().next().unwrap_or_default()));
+            }
+            out.push('\n');
+        }
+    }
+
+    out.push_str(&format!(
+        "Generated from Claude Helper task #{}.\n",
+        task.id
+    ));
+    out
+}
+
+/// A run as a readable document: the task, analysis, plan, every agent's
+/// prompt and output, and the final summary
+pub fn transcript_markdown(task: &TaskRecord, tags: &[String]) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("# Task #{}\n\n", task.id));
+    out.push_str(&format!(
+        "Run {} · {} · {} tokens (${:.2}){}\n\n",
+        task.timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        if task.success { "succeeded" } else { "failed" },
+        task.actual_tokens,
+        estimate_cost(task.actual_tokens),
+        if tags.is_empty() {
+            String::new()
+        } else {
+            format!(" · tags: {}", tags.join(", "))
+        }
+    ));
+
+    out.push_str(&format!("## Task\n\n{}\n\n", task.description.trim_end()));
+
+    out.push_str("## Analysis\n\n");
+    match &task.analysis {
+        Some(analysis) => {
+            out.push_str(&format!(
+                "- Complexity: {} ({}/10)\n- Estimated files: {}\n- Estimated tokens: {}\n- Estimated time: {}-{} minutes\n- Required expertise: {:?}\n\n",
+                format_complexity(analysis.complexity),
+                analysis.complexity,
+                analysis.estimated_files,
+                analysis.estimated_tokens,
+                analysis.estimated_time_min,
+                analysis.estimated_time_max,
+                analysis.required_capabilities
+            ));
+        }
+        None => out.push_str("Not recorded.\n\n"),
+    }
+
+    let outcomes = task
+        .result
+        .as_ref()
+        .map(|r| r.agents.as_slice())
+        .unwrap_or_default();
+    out.push_str("## Plan\n\n");
+    match &task.plan {
+        Some(plan) => {
+            for (i, phase) in plan.phases.iter().enumerate() {
+                out.push_str(&format!(
+                    "### Phase {}/{}: {}{}\n\n",
+                    i + 1,
+                    plan.phases.len(),
+                    phase.description,
+                    if phase.parallel { " (parallel)" } else { "" }
+                ));
+
+                for spec in &phase.agents {
+                    out.push_str(&format!("#### {} (`{}`)\n\n", spec.agent_type, spec.id));
+                    out.push_str(&format!("Task: {}\n\n", spec.task));
+
+                    let Some(outcome) = outcomes.iter().find(|o| o.agent_id == spec.id) else {
+                        out.push_str(if outcomes.is_empty() {
+                            "Outcome not recorded.\n\n"
+                        } else {
+                            "Not run.\n\n"
+                        });
+                        continue;
+                    };
+
+                    out.push_str(&format!(
+                        "{} tokens in {:.1}s\n\n",
+                        outcome.tokens_used, outcome.execution_time_secs
+                    ));
+                    if let Some(prompt) = &outcome.system_prompt {
+                        out.push_str(&format!("System prompt:\n\n{}\n\n", fenced(prompt)));
+                    }
+                    match (&outcome.output, &outcome.error) {
+                        (_, Some(error)) => {
+                            out.push_str(&format!("Failed: {}\n\n", error));
+                        }
+                        (Some(output), None) => {
+                            out.push_str(&format!("Output:\n\n{}\n\n", output.trim_end()));
+                        }
+                        (None, None) => out.push_str("Output not recorded.\n\n"),
+                    }
+                }
+            }
+        }
+        None => out.push_str("Not recorded.\n\n"),
+    }
+
+    out.push_str("## Summary\n\n");
+    match &task.result {
+        Some(result) => {
+            out.push_str(&format!(
+                "{} agents executed in {:.1}s using {} tokens (estimated {}).\n\n",
+                result.agents_executed,
+                result.execution_time_secs,
+                result.tokens_used,
+                task.estimated_tokens
+            ));
+            for (title, items) in [("Errors", &result.errors), ("Warnings", &result.warnings)] {
+                if items.is_empty() {
+                    continue;
+                }
+                out.push_str(&format!("{}:\n\n", title));
+                for item in items {
+                    out.push_str(&format!("- {}\n", item));
+                }
+                out.push('\n');
+            }
+        }
+        None => out.push_str("Not recorded.\n\n"),
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// Wrap `text` in a code fence longer than any backtick run inside it
+fn fenced(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}\n{}\n{}", fence, text.trim_end(), fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::master::orchestrator::{
+        AgentOutcome, AgentSpec, ExecutionPhase, ExecutionPlan, ExecutionResult, Scheduling,
+        TestOutcome,
+    };
+    use chrono::Utc;
+
+    #[test]
+    fn test_fenced_outlasts_inner_fences() {
+        assert_eq!(fenced("plain"), "```\nplain\n```");
+        assert_eq!(
+            fenced("```rust\nfn x() {}\n```"),
+            "````\n```rust\nfn x() {}\n```\n````"
+        );
+    }
+
+    fn sample_task() -> TaskRecord {
+        let spec = AgentSpec {
+            id: "agent-1".to_string(),
+            agent_type: "Tester".to_string(),
+            capability: AgentCapability::Testing,
+            task: "Write tests".to_string(),
+            dependencies: Vec::new(),
+            budget_share: 1.0,
+            output_schema: None,
+        };
+        TaskRecord {
+            id: 7,
+            description: "Add tests".to_string(),
+            estimated_tokens: 1000,
+            actual_tokens: 900,
+            success: true,
+            analysis: None,
+            plan: Some(ExecutionPlan {
+                phases: vec![ExecutionPhase {
+                    description: "Testing".to_string(),
+                    agents: vec![spec],
+                    parallel: false,
+                }],
+                scheduling: Scheduling::Phases,
+            }),
+            result: Some(ExecutionResult {
+                success: true,
+                agents_executed: 1,
+                tokens_used: 900,
+                cost_usd: 0.01,
+                execution_time_secs: 3.0,
+                errors: Vec::new(),
+                warnings: Vec::new(),
+                agents: vec![AgentOutcome {
+                    agent_id: "agent-1".to_string(),
+                    agent_type: "Tester".to_string(),
+                    tokens_used: 900,
+                    cost_usd: 0.01,
+                    execution_time_secs: 3.0,
+                    error: None,
+                    system_prompt: Some("You are a tester".to_string()),
+                    output: Some("Added 3 tests".to_string()),
+                    rate_limited: false,
+                }],
+                tests: None,
+                blocked: None,
+            }),
+            variant: None,
+            project: None,
+            run_id: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_transcript_markdown() {
+        let task = sample_task();
+        let md = transcript_markdown(&task, &["backend".to_string()]);
+        assert!(md.starts_with("# Task #7\n"));
+        assert!(md.contains("tags: backend"));
+        assert!(md.contains("## Analysis\n\nNot recorded."));
+        assert!(md.contains("#### Tester (`agent-1`)"));
+        assert!(md.contains("```\nYou are a tester\n```"));
+        assert!(md.contains("Output:\n\nAdded 3 tests"));
+        assert!(md.ends_with("1 agents executed in 3.0s using 900 tokens (estimated 1000).\n"));
+    }
+
+    #[test]
+    fn test_commit_subject() {
+        let mut task = sample_task();
+        assert_eq!(commit_subject(&task), "test: add tests");
+
+        task.description = "Fix the crash when the config file is empty.".to_string();
+        assert_eq!(
+            commit_subject(&task),
+            "fix: fix the crash when the config file is empty"
+        );
+
+        task.description = "API keys should be rotated automatically every thirty days \
+            without anyone having to remember it"
+            .to_string();
+        let subject = commit_subject(&task);
+        assert_eq!(
+            subject,
+            "feat: API keys should be rotated automatically every thirty days"
+        );
+        assert!(subject.len() <= SUBJECT_WIDTH);
+    }
+
+    #[test]
+    fn test_pr_summary() {
+        let mut task = sample_task();
+        task.result.as_mut().unwrap().tests = Some(TestOutcome {
+            command: "cargo test".to_string(),
+            passed: true,
+            debug_rounds: 1,
+        });
+
+        let pr = pr_summary(&task);
+        assert!(pr.starts_with("test: add tests\n\n## Task\n\nAdd tests\n"));
+        assert!(pr.contains("- Testing\n  - Tester: Write tests\n"));
+        assert!(pr.contains("| Tester | 900 | 3.0s | done |"));
+        assert!(pr.contains("## Tokens\n\n900 used"));
+        assert!(pr.contains("`cargo test` passed after 1 debugging round"));
+        assert!(pr.ends_with("Generated from Claude Helper task #7.\n"));
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Files looked at, at most; bigger projects are sized by their first ones
+const MAX_FILES: usize = 50_000;
+
+/// Directories skipped outside git, where there is no .gitignore to respect
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+
+/// Words of tasks that change the project as a whole
+const WHOLE_PROJECT: &[&str] = &["entire", "system", "codebase", "everywhere", "all files"];
+
+/// Files a whole-project task is estimated to touch, at most
+const MAX_WHOLE_PROJECT_FILES: usize = 40;
+
+/// Test directories listed
+const TEST_DIRS_SHOWN: usize = 3;
+
+/// Node packages worth telling agents about when `package.json` names them
+const NODE_FRAMEWORKS: &[&str] = &[
+    "jest", "vitest", "mocha", "eslint", "react", "next", "vue", "svelte", "express",
+];
+
+/// Python tools and frameworks worth telling agents about when the
+/// project's requirements mention them
+const PYTHON_FRAMEWORKS: &[&str] = &["pytest", "ruff", "mypy", "django", "flask", "fastapi"];
+
+/// What a project is made of, so the planner sizes tasks by it instead of
+/// by the wording of the task alone
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceInventory {
+    /// Files not ignored by .gitignore
+    pub files: usize,
+    /// Source files by language, most files first
+    pub languages: Vec<(String, usize)>,
+    /// Source files with tests, including Rust files with a test module
+    pub test_files: usize,
+    /// Where the tests are, most test files first
+    pub test_dirs: Vec<String>,
+    /// Build tools, test runners and frameworks its manifests name, e.g.
+    /// `cargo`, `npm` and `jest`
+    #[serde(default)]
+    pub frameworks: Vec<String>,
+}
+
+impl WorkspaceInventory {
+    /// Take stock of the project at `root`: the files git tracks or would
+    /// track, or every file not in a hidden or build directory outside git
+    pub fn scan(root: &Path) -> Result<Self> {
+        let paths = match git_files(root) {
+            Some(paths) => paths,
+            None => {
+                let mut paths = Vec::new();
+                walk(root, root, &mut paths)?;
+                paths
+            }
+        };
+        Ok(Self {
+            frameworks: frameworks(root),
+            ..Self::from_paths(root, &paths)
+        })
+    }
+
+    fn from_paths(root: &Path, paths: &[String]) -> Self {
+        let mut languages: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut test_dirs: BTreeMap<String, usize> = BTreeMap::new();
+        let mut test_files = 0;
+
+        for path in paths.iter().take(MAX_FILES) {
+            let Some(language) = language(path) else {
+                continue;
+            };
+            *languages.entry(language).or_default() += 1;
+
+            let dir = match test_dir(path) {
+                Some(dir) => dir,
+                None if language == "Rust" && has_test_module(&root.join(path)) => parent(path),
+                None => continue,
+            };
+            test_files += 1;
+            *test_dirs.entry(dir).or_default() += 1;
+        }
+
+        Self {
+            files: paths.len(),
+            languages: by_count(languages.into_iter().map(|(l, n)| (l.to_string(), n))),
+            test_files,
+            test_dirs: by_count(test_dirs.into_iter())
+                .into_iter()
+                .take(TEST_DIRS_SHOWN)
+                .map(|(dir, _)| dir)
+                .collect(),
+            frameworks: Vec::new(),
+        }
+    }
+
+    pub fn uses(&self, framework: &str) -> bool {
+        self.frameworks.iter().any(|f| f == framework)
+    }
+
+    pub fn source_files(&self) -> usize {
+        self.languages.iter().map(|(_, n)| n).sum()
+    }
+
+    pub fn main_language(&self) -> Option<&str> {
+        self.languages
+            .first()
+            .map(|(language, _)| language.as_str())
+    }
+
+    /// Where tests go, e.g. `tests/, src/`; None when the project has none
+    pub fn test_location(&self) -> Option<String> {
+        (!self.test_dirs.is_empty()).then(|| self.test_dirs.join(", "))
+    }
+
+    /// Files a task is likely to touch here, from the keyword estimate: a
+    /// change to the whole project grows with the project, and no task
+    /// touches many more files than a small project has
+    pub fn scale_files(&self, estimate: usize, task: &str) -> usize {
+        let sources = self.source_files();
+        if sources == 0 {
+            return estimate;
+        }
+        let estimate = if WHOLE_PROJECT.iter().any(|word| task.contains(word)) {
+            estimate.max((sources / 4).min(MAX_WHOLE_PROJECT_FILES))
+        } else {
+            estimate
+        };
+        estimate.min(sources + 2).max(1)
+    }
+
+    /// One line for the analysis, e.g.
+    /// `120 files; Rust (80), Python (4); 12 test files in tests/`
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} files", self.files);
+        if !self.languages.is_empty() {
+            let languages: Vec<_> = self
+                .languages
+                .iter()
+                .take(3)
+                .map(|(language, n)| format!("{} ({})", language, n))
+                .collect();
+            summary.push_str(&format!("; {}", languages.join(", ")));
+        }
+        match self.test_location() {
+            Some(location) => {
+                summary.push_str(&format!("; {} test files in {}", self.test_files, location))
+            }
+            None => summary.push_str("; no tests"),
+        }
+        summary
+    }
+}
+
+/// Files under `root` that git tracks or would, respecting .gitignore.
+/// None outside a git repository.
+fn git_files(root: &Path) -> Option<Vec<String>> {
+    let output = Command::new("git")
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .split('\0')
+            .filter(|file| !file.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn walk(root: &Path, dir: &Path, paths: &mut Vec<String>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Failed to read {:?}", dir))?;
+    for entry in entries.flatten() {
+        if paths.len() >= MAX_FILES {
+            break;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() && !SKIPPED_DIRS.contains(&name.as_str()) => {
+                walk(root, &path, paths)?;
+            }
+            Ok(kind) if kind.is_file() => {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    paths.push(relative.to_string_lossy().replace('\\', "/"));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Build tools, test runners and frameworks named by the manifests at `root`
+fn frameworks(root: &Path) -> Vec<String> {
+    let read = |name: &str| fs::read_to_string(root.join(name)).ok();
+    let mut found = Vec::new();
+
+    if root.join("Cargo.toml").is_file() {
+        found.push("cargo");
+    }
+    if let Some(package) = read("package.json") {
+        found.push(if root.join("pnpm-lock.yaml").is_file() {
+            "pnpm"
+        } else if root.join("yarn.lock").is_file() {
+            "yarn"
+        } else {
+            "npm"
+        });
+        found.extend(
+            NODE_FRAMEWORKS
+                .iter()
+                .filter(|name| package.contains(&format!("\"{}\"", name))),
+        );
+    }
+    let python: String = ["pyproject.toml", "requirements.txt", "setup.py"]
+        .into_iter()
+        .filter_map(read)
+        .collect::<String>()
+        .to_lowercase();
+    found.extend(
+        PYTHON_FRAMEWORKS
+            .iter()
+            .filter(|name| python.contains(*name)),
+    );
+
+    found.into_iter().map(str::to_string).collect()
+}
+
+/// Programming language of a source file, by its extension
+fn language(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1;
+    Some(match extension {
+        "rs" => "Rust",
+        "py" => "Python",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "ts" | "tsx" => "TypeScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "rb" => "Ruby",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" => "C++",
+        "cs" => "C#",
+        "swift" => "Swift",
+        "php" => "PHP",
+        "scala" => "Scala",
+        "ex" | "exs" => "Elixir",
+        "hs" => "Haskell",
+        "lua" => "Lua",
+        "dart" => "Dart",
+        "zig" => "Zig",
+        "sh" | "bash" => "Shell",
+        _ => return None,
+    })
+}
+
+/// Directory of a test file going by its path, e.g. `tests` for
+/// `tests/integration/db.rs` and `src` for `src/parser_test.go`
+fn test_dir(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let (name, dirs) = parts.split_last()?;
+    if let Some(i) = dirs
+        .iter()
+        .position(|dir| matches!(*dir, "tests" | "test" | "__tests__" | "spec"))
+    {
+        return Some(dirs[..=i].join("/"));
+    }
+
+    let stem = name.split('.').next().unwrap_or(name);
+    let is_test = stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+        || name.contains(".test.")
+        || name.contains(".spec.");
+    is_test.then(|| parent(path))
+}
+
+fn parent(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, _)) => dir.to_string(),
+        None => ".".to_string(),
+    }
+}
+
+fn has_test_module(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|source| source.contains("#[cfg(test)]"))
+}
+
+fn by_count<T: Ord>(counts: impl Iterator<Item = (T, usize)>) -> Vec<(T, usize)> {
+    let mut counts: Vec<_> = counts.collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("Cargo.toml", ""),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/parser.rs", "#[cfg(test)]\nmod tests {}\n"),
+            ("tests/integration/db.rs", ""),
+            ("web/app.test.ts", ""),
+            ("target/debug/build.rs", ""),
+            (".hidden/notes.rs", ""),
+        ];
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let mut paths = Vec::new();
+        walk(dir.path(), dir.path(), &mut paths).unwrap();
+        let inventory = WorkspaceInventory::from_paths(dir.path(), &paths);
+        assert_eq!(inventory.files, 5);
+        assert_eq!(
+            inventory.languages,
+            vec![("Rust".to_string(), 3), ("TypeScript".to_string(), 1)]
+        );
+        assert_eq!(inventory.main_language(), Some("Rust"));
+        assert_eq!(inventory.test_files, 3);
+        assert_eq!(inventory.test_dirs, vec!["src", "tests", "web"]);
+        assert_eq!(
+            inventory.summary(),
+            "5 files; Rust (3), TypeScript (1); 3 test files in src, tests, web"
+        );
+
+        // A tiny project caps the estimate; a whole-project change grows it
+        assert_eq!(inventory.scale_files(12, "refactor the parser"), 6);
+        let big = WorkspaceInventory {
+            files: 400,
+            languages: vec![("Go".to_string(), 200)],
+            ..Default::default()
+        };
+        assert_eq!(big.scale_files(3, "implement a flag"), 3);
+        assert_eq!(
+            big.scale_files(3, "rename the logger across the entire codebase"),
+            40
+        );
+        assert_eq!(big.summary(), "400 files; Go (200); no tests");
+    }
+
+    #[test]
+    fn test_frameworks() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(frameworks(dir.path()).is_empty());
+
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"devDependencies": {"jest": "^29", "typescript": "^5"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("yarn.lock"), "").unwrap();
+        fs::write(dir.path().join("requirements.txt"), "Django==5.0\npytest\n").unwrap();
+        assert_eq!(
+            frameworks(dir.path()),
+            vec!["cargo", "yarn", "jest", "pytest", "django"]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use super::inventory::WorkspaceInventory;
+
+/// How agents should write code in the project's main language and check
+/// it with its tools, for their system prompts. None when no language is
+/// known, e.g. because the workspace was not scanned.
+pub fn prompt_section(workspace: &WorkspaceInventory) -> Option<String> {
+    let language = workspace.main_language()?;
+    let (intro, mut lines) = match language {
+        "Rust" => (
+            "This is a Rust project built with cargo.".to_string(),
+            vec![
+                "Write idiomatic Rust: return `Result` and use `?`, no `unwrap()` outside \
+                 tests, borrow instead of cloning where you can"
+                    .to_string(),
+                "Put unit tests in a `#[cfg(test)] mod tests` next to the code and \
+                 integration tests under `tests/`"
+                    .to_string(),
+                "The code has to pass `cargo build`, `cargo clippy -- -D warnings` and \
+                 `cargo test`, formatted as `rustfmt` would"
+                    .to_string(),
+            ],
+        ),
+        "TypeScript" | "JavaScript" => node(workspace, language),
+        "Python" => python(workspace),
+        "Go" => (
+            "This is a Go project.".to_string(),
+            vec![
+                "Return errors instead of panicking and wrap them with `fmt.Errorf(\"...: %w\", err)`"
+                    .to_string(),
+                "Write table-driven tests in `_test.go` files next to the code".to_string(),
+                "The code has to pass `go vet ./...` and `go test ./...`, formatted with `gofmt`"
+                    .to_string(),
+            ],
+        ),
+        _ => (
+            format!("This project is written in {}.", language),
+            vec!["Follow the language's idioms and the style of the code around your changes"
+                .to_string()],
+        ),
+    };
+    if let Some(location) = workspace.test_location() {
+        lines.push(format!(
+            "Its tests are in {}; put new ones alongside them",
+            location
+        ));
+    }
+
+    let lines: Vec<String> = lines.iter().map(|line| format!("- {}", line)).collect();
+    Some(format!("{}\n{}", intro, lines.join("\n")))
+}
+
+fn node(workspace: &WorkspaceInventory, language: &str) -> (String, Vec<String>) {
+    let manager = ["pnpm", "yarn", "npm"]
+        .into_iter()
+        .find(|manager| workspace.uses(manager))
+        .unwrap_or("npm");
+    let frameworks: Vec<&str> = ["react", "next", "vue", "svelte", "express"]
+        .into_iter()
+        .filter(|framework| workspace.uses(framework))
+        .collect();
+    let intro = if frameworks.is_empty() {
+        format!("This is a {} project managed with {}.", language, manager)
+    } else {
+        format!(
+            "This is a {} project using {}, managed with {}.",
+            language,
+            frameworks.join(" and "),
+            manager
+        )
+    };
+
+    let mut lines = vec![if language == "TypeScript" {
+        "Write strictly typed TypeScript: no `any`, explicit types on exported functions"
+            .to_string()
+    } else {
+        "Write modern JavaScript: ES modules, `const`/`let` and async/await".to_string()
+    }];
+    match ["vitest", "jest", "mocha"]
+        .into_iter()
+        .find(|runner| workspace.uses(runner))
+    {
+        Some(runner) => lines.push(format!(
+            "Write tests for {} and run them with `{} test`",
+            runner, manager
+        )),
+        None => lines.push(format!("Run the tests with `{} test`", manager)),
+    }
+    if workspace.uses("eslint") {
+        lines.push("The code has to pass eslint with the project's configuration".to_string());
+    }
+    (intro, lines)
+}
+
+fn python(workspace: &WorkspaceInventory) -> (String, Vec<String>) {
+    let intro = match ["django", "flask", "fastapi"]
+        .into_iter()
+        .find(|framework| workspace.uses(framework))
+    {
+        Some(framework) => format!("This is a Python project using {}.", framework),
+        None => "This is a Python project.".to_string(),
+    };
+
+    let mut lines = vec![
+        "Write idiomatic Python 3 with type hints on public functions".to_string(),
+        if workspace.uses("pytest") {
+            "Write pytest tests (plain `assert`, fixtures) and run them with `pytest`".to_string()
+        } else {
+            "Write unittest tests and run them with `python -m unittest`".to_string()
+        },
+    ];
+    for (tool, check) in [("ruff", "`ruff check`"), ("mypy", "`mypy`")] {
+        if workspace.uses(tool) {
+            lines.push(format!("The code has to pass {}", check));
+        }
+    }
+    (intro, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(language: &str, frameworks: &[&str]) -> WorkspaceInventory {
+        WorkspaceInventory {
+            files: 10,
+            languages: vec![(language.to_string(), 8)],
+            test_files: 2,
+            test_dirs: vec!["tests".to_string()],
+            frameworks: frameworks.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_prompt_section() {
+        let rust = prompt_section(&workspace("Rust", &["cargo"])).unwrap();
+        assert!(rust.starts_with("This is a Rust project built with cargo.\n- "));
+        assert!(rust.contains("`cargo clippy -- -D warnings`"));
+        assert!(rust.ends_with("- Its tests are in tests; put new ones alongside them"));
+
+        let ts = prompt_section(&workspace("TypeScript", &["pnpm", "jest", "react"])).unwrap();
+        assert!(ts.starts_with("This is a TypeScript project using react, managed with pnpm."));
+        assert!(ts.contains("Write tests for jest and run them with `pnpm test`"));
+        assert!(!ts.contains("cargo"));
+
+        let python = prompt_section(&workspace("Python", &["pytest", "ruff"])).unwrap();
+        assert!(python.contains("run them with `pytest`"));
+        assert!(python.contains("`ruff check`"));
+
+        let elixir = prompt_section(&workspace("Elixir", &[])).unwrap();
+        assert!(elixir.starts_with("This project is written in Elixir."));
+
+        assert_eq!(prompt_section(&WorkspaceInventory::default()), None);
+    }
+}
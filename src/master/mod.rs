@@ -1,19 +1,56 @@
 mod agent_factory;
+pub mod archive;
+pub mod artifacts;
+pub mod bench;
+pub mod compare;
+pub mod estimate;
+pub mod export;
+pub mod inventory;
+mod language;
 pub mod orchestrator;
+mod parallelism;
 pub mod planner;
+pub mod progress;
+pub mod queue;
+pub mod retention;
+pub mod run_log;
+pub mod tasks;
+pub mod terminal;
+mod usage_guard;
+mod validators;
+mod verify;
 
-use crate::agents::AgentCapability;
 use crate::config::Config;
 use crate::db::Database;
-use agent_factory::AgentFactory;
-use anyhow::Result;
-use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use orchestrator::Orchestrator;
+use crate::embeddings::Embedder;
+use crate::error::ClaudeHelperError;
+use crate::knowledge::{self, KnowledgeEntry};
+use crate::project::Project;
+use crate::style::{self, Colorize};
+use agent_factory::{agent_model, AgentFactory};
+use anyhow::{Context, Result};
+use orchestrator::{AgentOutcome, Orchestrator, TestOutcome};
 use planner::TaskPlanner;
-
-pub use orchestrator::{ExecutionPhase, ExecutionPlan};
+use run_log::RunLog;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+pub(crate) use agent_factory::prompt_version;
+pub use estimate::Estimate;
+pub use export::ExportFormat;
+pub use orchestrator::{ExecutionPhase, ExecutionPlan, ExecutionResult};
 pub use planner::TaskAnalysis;
+pub use progress::{AgentPrompt, ExecutionObserver, Progress, PromptDecision, RunEvent};
+pub use queue::{BlockReason, QueuedRun};
+pub use tasks::TaskHistory;
+pub use terminal::TerminalObserver;
+
+/// How long a run waits for current usage before starting unchecked
+const USAGE_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Names accepted by [`AutonomyMode::from_str`], from least to most autonomous
+pub const AUTONOMY_MODES: &[&str] = &["conservative", "balanced", "trust", "interactive"];
 
 /// Autonomy mode for Master Coder
 #[derive(Debug, Clone, PartialEq)]
@@ -29,6 +66,7 @@ pub enum AutonomyMode {
 }
 
 impl AutonomyMode {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "conservative" => Ok(Self::Conservative),
@@ -38,6 +76,15 @@ impl AutonomyMode {
             _ => anyhow::bail!("Invalid autonomy mode: {}", s),
         }
     }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Self::Conservative => "ask for approval frequently",
+            Self::Balanced => "approval gates at important points",
+            Self::Trust => "fully automatic execution",
+            Self::Interactive => "full control over every step",
+        }
+    }
 }
 
 /// Master Coder - orchestrates specialized agents to complete complex tasks
@@ -47,26 +94,62 @@ pub struct MasterCoder {
     planner: TaskPlanner,
     factory: AgentFactory,
     orchestrator: Orchestrator,
-    db: Database,
+    db: Arc<Database>,
+    /// Semantic matching of past tasks, when an embeddings provider is set
+    embedder: Option<Embedder>,
     max_agents: usize,
     token_budget: usize,
+    /// Dollars the run may cost, from `run --max-cost`
+    max_cost: Option<f64>,
+    tags: Vec<String>,
+    variant: Option<String>,
+    wait_for_reset: bool,
+    apply: bool,
+    progress: Progress,
 }
 
 impl MasterCoder {
     /// Create a new Master Coder instance
-    pub async fn new(config: Config, mode: String) -> Result<Self> {
+    pub async fn new(
+        config: Config,
+        mode: String,
+        db: Arc<Database>,
+    ) -> crate::error::Result<Self> {
         let autonomy_mode = AutonomyMode::from_str(&mode)?;
-        let db = Database::new(&config).await?;
+        let mut factory = AgentFactory::new(config.clone());
+        factory.set_audit_log(db.clone());
+        let embedder = Embedder::from_config(&config).unwrap_or_else(|e| {
+            warn!(
+                "Matching tasks by keywords, embeddings unavailable: {:#}",
+                e
+            );
+            None
+        });
+
+        let mut orchestrator = Orchestrator::new(config.clone(), autonomy_mode.clone());
+        orchestrator.set_offer_pause(config.master_coder.queue_blocked_agents);
+
+        let mut planner = TaskPlanner::new(config.clone());
+        if config.master_coder.scan_workspace {
+            planner.set_workspace_root(workspace_root());
+        }
 
         Ok(Self {
-            planner: TaskPlanner::new(config.clone()),
-            factory: AgentFactory::new(config.clone()),
-            orchestrator: Orchestrator::new(config.clone(), autonomy_mode.clone()),
+            planner,
+            factory,
+            orchestrator,
             max_agents: config.master_coder.max_parallel_agents,
             token_budget: config.master_coder.token_budget,
+            max_cost: None,
             config,
             mode: autonomy_mode,
             db,
+            embedder,
+            tags: Vec::new(),
+            variant: None,
+            wait_for_reset: false,
+            apply: false,
+            progress: Progress::default(),
         })
     }
 
@@ -79,176 +162,551 @@ impl MasterCoder {
     /// Set token budget for this task
     pub fn set_token_budget(&mut self, budget: usize) {
         self.token_budget = budget;
+        self.orchestrator.set_token_budget(budget);
     }
 
-    /// Execute a task with agent orchestration
-    pub async fn execute(&mut self, task: &str) -> Result<()> {
-        println!(
-            "\n{}",
-            "═══════════════════════════════════════════════════════".bright_cyan()
-        );
-        println!(
-            "{} {}",
-            "Master Coder".bright_cyan().bold(),
-            "Analyzing task...".white()
-        );
-        println!(
-            "{}",
-            "═══════════════════════════════════════════════════════".bright_cyan()
-        );
+    /// Stop the run once its agents cost more than `max_cost` dollars, at
+    /// the prices of the models they call
+    pub fn set_max_cost(&mut self, max_cost: f64) {
+        self.max_cost = Some(max_cost);
+        self.orchestrator.set_max_cost(Some(max_cost));
+    }
 
-        // Step 1: Analyze the task
-        let spinner = ProgressBar::new_spinner();
-        spinner.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.cyan} {msg}")
-                .unwrap(),
-        );
-        spinner.set_message("Analyzing task complexity and requirements...");
+    /// Scan the project before planning, as `master_coder.scan_workspace`
+    pub fn set_scan_workspace(&mut self, scan: bool) {
+        self.planner
+            .set_workspace_root(if scan { workspace_root() } else { None });
+    }
 
-        let analysis = self.planner.analyze_task(task).await?;
-        spinner.finish_with_message(format!("✓ Analysis complete"));
+    /// Reuse cached answers to identical agent requests in this run
+    pub fn set_reuse_responses(&mut self, reuse: bool) {
+        self.factory.set_reuse_responses(reuse);
+    }
 
-        self.print_analysis(&analysis);
+    /// Tag the run so it can be filtered in history and reports
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
 
-        // Step 2: Create execution plan
-        println!(
-            "\n{}",
-            "Creating agent team and execution plan...".bright_yellow()
-        );
+    /// Label the run with the prompt variant it used, so variants can be
+    /// compared with `experiments compare`
+    pub fn set_variant(&mut self, variant: Option<String>) {
+        self.variant = variant;
+    }
 
-        let plan = self.planner.create_plan(&analysis, self.max_agents).await?;
+    /// When the plan doesn't fit in the 5-hour usage window, wait for the
+    /// window to reset instead of asking whether to start anyway
+    pub fn set_wait_for_reset(&mut self, wait: bool) {
+        self.wait_for_reset = wait;
+    }
 
-        self.print_plan(&plan)?;
+    /// Write the files agents produce into the current directory instead
+    /// of the run's artifact directory
+    pub fn set_apply(&mut self, apply: bool) {
+        self.apply = apply;
+    }
 
-        // Step 3: Get user approval (if needed based on mode)
-        if !self.should_auto_approve() {
-            if !self.get_user_approval("Proceed with this plan?")? {
-                println!("Task cancelled by user.");
-                return Ok(());
+    /// Report progress and ask for approvals through `progress` instead of
+    /// the terminal
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.orchestrator.set_progress(progress.clone());
+        self.progress = progress;
+    }
+
+    /// Report progress and ask for approvals through `observer`
+    pub fn set_observer(&mut self, observer: Arc<dyn ExecutionObserver>) {
+        self.set_progress(Progress::new(observer));
+    }
+
+    pub fn token_budget(&self) -> usize {
+        self.token_budget
+    }
+
+    /// Execute a task with agent orchestration, reporting each step to the
+    /// observer. Returns None when the plan was not approved. Errors are
+    /// reported as [`RunEvent::Failed`] as well; a run that overspent its
+    /// budget is saved and then fails with [`ClaudeHelperError::BudgetExceeded`].
+    pub async fn execute(&mut self, task: &str) -> crate::error::Result<Option<ExecutionResult>> {
+        self.config.network.ensure_online("Running a task")?;
+
+        self.start_run_log(task);
+        let result = self.run(task).await;
+
+        match &result {
+            Ok(Some(result)) => self.progress.send(RunEvent::Finished(result.clone())),
+            Ok(None) => self.progress.send(RunEvent::Cancelled),
+            Err(e) => self.progress.send(RunEvent::Failed(format!("{:#}", e))),
+        }
+
+        result
+    }
+
+    async fn run(&mut self, task: &str) -> crate::error::Result<Option<ExecutionResult>> {
+        // Step 1: Analyze the task
+        let analysis = self
+            .planner
+            .analyze_task(task)
+            .await
+            .map_err(ClaudeHelperError::plan)?;
+        self.progress.send(RunEvent::Analyzed(analysis.clone()));
+        if let Some(workspace) = &analysis.workspace {
+            self.factory.set_workspace(workspace);
+        }
+
+        // Step 2: Create execution plan, or reuse the plan of a past
+        // successful run of practically the same task
+        let plan = match self.reusable_plan(task).await {
+            Some(plan) => plan,
+            None => self
+                .planner
+                .create_plan(&analysis, self.max_agents)
+                .await
+                .map_err(ClaudeHelperError::plan)?,
+        };
+        self.progress.send(RunEvent::Planned(plan.clone()));
+
+        // Step 3: Make sure the plan fits in the remaining usage allowance
+        if !self.check_usage_window(analysis.estimated_tokens).await? {
+            return Ok(None);
+        }
+
+        // Step 4: Get user approval (if needed based on mode)
+        if !self.should_auto_approve() && !self.progress.approve("Proceed with this plan?").await? {
+            return Ok(None);
+        }
+
+        // Step 5: Create agents based on plan, telling them the project's conventions
+        let project_knowledge = self.project_knowledge().await;
+        if let Some((_, entries)) = &project_knowledge {
+            let max = self.config.knowledge.max_entries;
+            self.factory
+                .set_knowledge(knowledge::relevant(entries, task, max));
+        }
+        let agents = self.factory.create_agents(&plan, task).await?;
+        self.progress.send(RunEvent::AgentsCreated {
+            count: agents.len(),
+        });
+
+        // Step 6: Execute the plan. Agents the token budget or rate limiting
+        // stopped can wait in the task queue for the usage window to reset.
+        let mut result = self.orchestrator.execute_plan(&plan, agents).await?;
+        let budgeted = self.resume_blocked(task, &mut result).await?;
+
+        // Step 7: Write out the files agents produced, then have the tests
+        // a Testing agent wrote pass before the run counts as done
+        let applied = self.write_files(&result).await?;
+        if self.config.verify.enabled && !applied.is_empty() && wrote_tests(&plan, &result) {
+            self.verify_tests(task, &mut result, applied).await?;
+        }
+
+        // Step 8: Save to database for learning
+        self.save_execution(task, &analysis, &plan, &result)
+            .await
+            .map_err(ClaudeHelperError::db)?;
+
+        // Step 9: Offer what the run settled as knowledge base proposals
+        if let Some((project, entries)) = &project_knowledge {
+            if result.success && self.config.knowledge.propose_after_run {
+                if let Err(e) = self
+                    .propose_knowledge(task, &result, project, entries)
+                    .await
+                {
+                    warn!("No knowledge base proposals: {:#}", e);
+                }
             }
         }
 
-        // Step 4: Create agents based on plan
-        let agents = self.factory.create_agents(&plan).await?;
+        if budgeted > self.token_budget {
+            return Err(ClaudeHelperError::BudgetExceeded {
+                used: budgeted,
+                budget: self.token_budget,
+            });
+        }
+        if let Some(cap) = self.max_cost.filter(|cap| result.cost_usd > *cap) {
+            return Err(ClaudeHelperError::CostExceeded {
+                used: result.cost_usd,
+                cap,
+            });
+        }
 
-        println!("\n{} {} agents created", "✓".green(), agents.len());
+        Ok(Some(result))
+    }
+
+    /// Plan of the most similar past successful task, when it is at least
+    /// `embeddings.reuse_plan_similarity` alike. Needs an embeddings provider.
+    async fn reusable_plan(&self, task: &str) -> Option<ExecutionPlan> {
+        let embedder = self.embedder.as_ref()?;
+        let threshold = self.config.embeddings.reuse_plan_similarity;
+        if threshold >= 1.0 {
+            return None;
+        }
 
-        // Step 5: Execute the plan
-        println!(
-            "\n{}",
-            "═══════════════════════════════════════════════════════".bright_cyan()
-        );
-        println!("{}", "Executing plan...".bright_cyan().bold());
-        println!(
-            "{}",
-            "═══════════════════════════════════════════════════════".bright_cyan()
-        );
+        let similar = match estimate::similar_tasks(&self.db, Some(embedder), task).await {
+            Ok(similar) => similar,
+            Err(e) => {
+                debug!("No plan reuse: {:#}", e);
+                return None;
+            }
+        };
+        let best = similar
+            .into_iter()
+            .find(|s| s.outcome.success)
+            .filter(|s| s.similarity >= threshold)?;
+        let plan = self
+            .db
+            .get_task_execution(best.outcome.id)
+            .await
+            .ok()??
+            .plan?;
+
+        self.progress.send(RunEvent::Message(format!(
+            "{} Reusing the plan of task #{} ({:.0}% similar)",
+            style::icon("♻"),
+            best.outcome.id,
+            best.similarity * 100.0
+        )));
+        Some(plan)
+    }
 
-        let result = self.orchestrator.execute_plan(&plan, agents).await?;
+    /// Knowledge base of the current project. Without one (or when it is
+    /// disabled or unreadable) the run goes ahead without it.
+    async fn project_knowledge(&self) -> Option<(String, Vec<KnowledgeEntry>)> {
+        if !self.config.knowledge.enabled {
+            return None;
+        }
 
-        // Step 6: Save to database for learning
-        self.save_execution(&task, &analysis, &plan, &result)
+        let loaded = async {
+            let project = knowledge::current_project()?;
+            let entries = self.db.get_knowledge(&project).await?;
+            anyhow::Ok((project, entries))
+        };
+        match loaded.await {
+            Ok(knowledge) => Some(knowledge),
+            Err(e) => {
+                debug!("Knowledge base not used: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Ask a curator agent for conventions and decisions worth keeping from
+    /// a successful run and save them as proposals to accept with `kb accept`
+    async fn propose_knowledge(
+        &self,
+        task: &str,
+        result: &ExecutionResult,
+        project: &str,
+        known: &[KnowledgeEntry],
+    ) -> Result<()> {
+        let outputs: Vec<&str> = result
+            .agents
+            .iter()
+            .filter_map(|agent| agent.output.as_deref())
+            .collect();
+
+        let mut curator = self.factory.create_curator(task).await?;
+        let answer = curator
+            .execute(&knowledge::curator_task(task, &outputs, known))
             .await?;
+        let proposals = knowledge::parse_proposals(&answer.output)?;
+        if proposals.is_empty() {
+            return Ok(());
+        }
 
-        // Step 7: Show results
-        self.print_results(&result)?;
+        let source = match self.progress.run_id() {
+            Some(id) => format!("run:{}", id),
+            None => "run".to_string(),
+        };
+        for (kind, content) in &proposals {
+            self.db
+                .add_knowledge(project, *kind, content, &source, true)
+                .await?;
+        }
+        self.progress.send(RunEvent::Message(format!(
+            "{} Proposed {} knowledge base entr{} ({} tokens); review with `claude-helper kb list --proposed`",
+            style::icon("💡"),
+            proposals.len(),
+            if proposals.len() == 1 { "y" } else { "ies" },
+            answer.tokens_used
+        )));
 
         Ok(())
     }
 
-    fn print_analysis(&self, analysis: &TaskAnalysis) {
-        println!("\n{}", "Task Analysis:".bright_yellow().bold());
-        println!(
-            "  {} {}",
-            "Complexity:".white(),
-            self.format_complexity(analysis.complexity)
-        );
-        println!(
-            "  {} {}",
-            "Estimated files:".white(),
-            analysis.estimated_files
-        );
-        println!(
-            "  {} {:?}",
-            "Required expertise:".white(),
-            analysis.required_capabilities
-        );
-        println!(
-            "  {} ~{}",
-            "Estimated tokens:".white(),
-            analysis.estimated_tokens
-        );
-        println!(
-            "  {} {}-{} minutes",
-            "Estimated time:".white(),
-            analysis.estimated_time_min,
-            analysis.estimated_time_max
-        );
-    }
+    /// Warn when `needed` tokens may not fit in the 5-hour or 7-day usage
+    /// window, then wait for the reset or ask whether to start anyway.
+    /// Returns false when the run should not start. Usage that can't be
+    /// fetched is not checked.
+    async fn check_usage_window(&self, needed: usize) -> Result<bool> {
+        if !self.config.master_coder.check_usage_window {
+            return Ok(true);
+        }
 
-    fn format_complexity(&self, complexity: u8) -> String {
-        let (label, color) = match complexity {
-            0..=3 => ("Low", "green"),
-            4..=6 => ("Medium", "yellow"),
-            7..=8 => ("High", "red"),
-            _ => ("Very High", "bright_red"),
+        let usage = match self.live_usage().await {
+            Ok(usage) => usage,
+            Err(e) => {
+                debug!("Usage window not checked: {:#}", e);
+                return Ok(true);
+            }
         };
 
-        match color {
-            "green" => label.green().to_string(),
-            "yellow" => label.yellow().to_string(),
-            "red" => label.red().to_string(),
-            "bright_red" => label.bright_red().bold().to_string(),
-            _ => label.to_string(),
+        let margin = self.config.master_coder.usage_margin_percent;
+        let Some(shortfall) = usage_guard::check(&usage, needed, margin) else {
+            return Ok(true);
+        };
+        self.progress.send(RunEvent::Message(format!(
+            "{} {}",
+            style::warn(),
+            shortfall.message()
+        )));
+
+        match shortfall.minutes_until_reset {
+            Some(minutes) if self.wait_for_reset => {
+                self.progress.send(RunEvent::Message(format!(
+                    "Waiting {}m for the {} window to reset...",
+                    minutes, shortfall.window
+                )));
+                tokio::time::sleep(std::time::Duration::from_secs((minutes as u64 + 1) * 60)).await;
+                Ok(true)
+            }
+            _ => self.progress.approve("Start the run anyway?").await,
         }
     }
 
-    fn print_plan(&self, plan: &ExecutionPlan) -> Result<()> {
-        println!("\n{}", "Execution Plan:".bright_yellow().bold());
-        println!("  {} {} phases", "Total phases:".white(), plan.phases.len());
-        println!(
-            "  {} {} agents",
-            "Total agents:".white(),
-            plan.total_agents()
-        );
-        println!("  {} {}", "Token budget:".white(), self.token_budget);
-
-        for (i, phase) in plan.phases.iter().enumerate() {
-            println!(
-                "\n  {} Phase {}/{}: {}",
-                if phase.parallel { "⚡" } else { "→" },
-                i + 1,
-                plan.phases.len(),
-                phase.description
-            );
+    async fn live_usage(&self) -> Result<crate::statusline::Usage> {
+        let tracker = crate::statusline::UsageTracker::new(self.config.clone()).await?;
+        tokio::time::timeout(USAGE_CHECK_TIMEOUT, tracker.get_live_usage())
+            .await
+            .context("Timed out fetching usage")?
+    }
 
-            for agent_spec in &phase.agents {
-                println!(
-                    "    {} {} - {}",
-                    "•".bright_cyan(),
-                    agent_spec.agent_type,
-                    agent_spec.task
-                );
-            }
+    /// Write the code blocks agents annotated with a file path: into the
+    /// run's artifact directory, or into the project with `--apply`.
+    /// Write failures are reported but don't fail the run. Returns the files
+    /// written into the project, relative to it.
+    async fn write_files(&self, result: &ExecutionResult) -> Result<Vec<PathBuf>> {
+        let blocks: Vec<_> = result
+            .agents
+            .iter()
+            .filter_map(|agent| agent.output.as_deref())
+            .flat_map(artifacts::extract_code_blocks)
+            .collect();
+        if blocks.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok(())
+        let apply = self.apply
+            && (!self.config.approval.requires_action("apply")
+                || self
+                    .progress
+                    .approve(&format!("Write {} file(s) into the project?", blocks.len()))
+                    .await?);
+        let root = if apply {
+            std::env::current_dir().context("Failed to read the current directory")
+        } else {
+            let run_id = match self.progress.run_id() {
+                Some(id) => id.to_string(),
+                None => chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string(),
+            };
+            artifacts::artifact_dir(&run_id)
+        };
+
+        match root.and_then(|root| Ok((artifacts::write_blocks(&blocks, &root)?, root))) {
+            Ok((written, root)) => {
+                self.progress.send(RunEvent::Message(format!(
+                    "{} Wrote {} file(s) to {}: {}",
+                    style::ok(),
+                    written.len(),
+                    root.display(),
+                    written
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+                Ok(if apply { written } else { Vec::new() })
+            }
+            Err(e) => {
+                self.progress.send(RunEvent::Message(format!(
+                    "{} Could not write agent files: {:#}",
+                    style::warn(),
+                    e
+                )));
+                Ok(Vec::new())
+            }
+        }
     }
 
-    fn should_auto_approve(&self) -> bool {
-        matches!(self.mode, AutonomyMode::Trust)
+    /// Run `verify.test_command` on the applied files. While it fails, a
+    /// Debugging agent gets the failing output and the diff, and its fixed
+    /// files are applied, for up to `verify.max_rounds` rounds or until the
+    /// cost cap is exceeded. Tests still failing after that fail the run.
+    async fn verify_tests(
+        &self,
+        task: &str,
+        result: &mut ExecutionResult,
+        mut changed: Vec<PathBuf>,
+    ) -> Result<()> {
+        let root = std::env::current_dir().context("Failed to read the current directory")?;
+        let command = &self.config.verify.test_command;
+        let max_rounds = self.config.verify.max_rounds;
+        let mut round = 0;
+
+        loop {
+            self.progress.send(RunEvent::Message(format!(
+                "{} Running `{}`",
+                style::icon("🧪"),
+                command
+            )));
+            let run = verify::run_tests(&self.config, &root).await?;
+            result.tests = Some(TestOutcome {
+                command: command.clone(),
+                passed: run.passed,
+                debug_rounds: round,
+            });
+            if run.passed {
+                self.progress.send(RunEvent::Message(format!(
+                    "{} Tests pass{}",
+                    style::ok(),
+                    match round {
+                        0 => String::new(),
+                        1 => " after 1 debugging round".to_string(),
+                        n => format!(" after {} debugging rounds", n),
+                    }
+                )));
+                return Ok(());
+            }
+            if round == max_rounds {
+                self.progress.send(RunEvent::Message(format!(
+                    "{} Tests still fail after {} debugging round(s)",
+                    style::warn(),
+                    round
+                )));
+                result.success = false;
+                result.errors.push(format!(
+                    "`{}` fails after {} debugging round(s):\n{}",
+                    command, round, run.output
+                ));
+                return Ok(());
+            }
+            if let Some(error) = self.orchestrator.cost_cap_error(result.cost_usd) {
+                result.success = false;
+                result.errors.push(format!(
+                    "`{}` fails and no debugging round started ({}):\n{}",
+                    command, error, run.output
+                ));
+                return Ok(());
+            }
+            round += 1;
+
+            let diff = verify::diff(&root, &changed).await;
+            let agent_id = format!("test-debugger-{}", round);
+            self.progress.send(RunEvent::AgentStarted {
+                agent_id: agent_id.clone(),
+                agent_type: "Test Debugger".to_string(),
+            });
+            let started = std::time::Instant::now();
+            let answer = match self.factory.create_debugger(task).await {
+                Ok(mut debugger) => debugger
+                    .execute(&verify::debugger_task(task, command, &run.output, &diff))
+                    .await
+                    .map(|answer| (answer, debugger.system_prompt().map(str::to_string))),
+                Err(e) => Err(e),
+            };
+            let (tokens_used, cost_usd, error) = match &answer {
+                Ok((answer, _)) => (answer.tokens_used, answer.cost_usd, None),
+                Err(e) => (0, 0.0, Some(format!("{:#}", e))),
+            };
+            self.progress.send(RunEvent::AgentFinished {
+                agent_id: agent_id.clone(),
+                tokens_used,
+                error: error.clone(),
+            });
+            result.agents_executed += 1;
+            result.tokens_used += tokens_used;
+            result.cost_usd += cost_usd;
+            result.agents.push(AgentOutcome {
+                agent_id,
+                agent_type: "Test Debugger".to_string(),
+                tokens_used,
+                cost_usd,
+                execution_time_secs: started.elapsed().as_secs_f64(),
+                error: error.clone(),
+                system_prompt: answer.as_ref().ok().and_then(|(_, prompt)| prompt.clone()),
+                output: answer
+                    .as_ref()
+                    .ok()
+                    .map(|(answer, _)| answer.output.clone()),
+                rate_limited: false,
+            });
+
+            let blocks = match &answer {
+                Ok((answer, _)) => artifacts::extract_code_blocks(&answer.output),
+                Err(_) => Vec::new(),
+            };
+            if blocks.is_empty() {
+                result.success = false;
+                result.errors.push(format!(
+                    "`{}` fails and the debugging agent {}:\n{}",
+                    command,
+                    error.map_or("proposed no fix".to_string(), |e| format!("failed: {}", e)),
+                    run.output
+                ));
+                return Ok(());
+            }
+            let written = artifacts::write_blocks(&blocks, &root)?;
+            self.progress.send(RunEvent::Message(format!(
+                "{} Applied fix to {}",
+                style::ok(),
+                written
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+            for path in written {
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
     }
 
-    fn get_user_approval(&self, prompt: &str) -> Result<bool> {
-        use dialoguer::Confirm;
+    /// Open a run log so the run can be followed with `tui --attach`.
+    /// Without one the run still works; it just can't be attached to.
+    fn start_run_log(&mut self, task: &str) {
+        retention::enforce(&self.config.retention);
+        match RunLog::create() {
+            Ok(log) => {
+                let progress = self.progress.clone().with_log(Arc::new(log));
+                self.set_progress(progress);
+            }
+            Err(e) => warn!("Run will not be attachable: {:#}", e),
+        }
 
-        let result = Confirm::new()
-            .with_prompt(prompt)
-            .default(true)
-            .interact()?;
+        self.progress.send(RunEvent::Started {
+            run_id: self.progress.run_id().unwrap_or_default().to_string(),
+            task: task.to_string(),
+            mode: format!("{:?}", self.mode).to_lowercase(),
+            token_budget: self.token_budget,
+        });
+    }
 
-        Ok(result)
+    /// Plan a task without executing it and compare the expected tokens,
+    /// cost and wall-clock time with similar past tasks
+    pub async fn estimate(&self, task: &str) -> crate::error::Result<Estimate> {
+        Ok(Estimate::new(
+            &self.planner,
+            &self.db,
+            self.embedder.as_ref(),
+            task,
+            self.max_agents,
+        )
+        .await?)
+    }
+
+    fn should_auto_approve(&self) -> bool {
+        matches!(self.mode, AutonomyMode::Trust) && !self.config.approval.requires_action("plan")
     }
 
     async fn save_execution(
@@ -259,60 +717,60 @@ impl MasterCoder {
         result: &orchestrator::ExecutionResult,
     ) -> Result<()> {
         if self.config.master_coder.enable_learning {
+            let project = Project::current().ok();
             self.db
-                .save_task_execution(task, analysis, plan, result)
+                .save_task_execution(
+                    task,
+                    analysis,
+                    plan,
+                    result,
+                    &self.tags,
+                    self.variant.as_deref(),
+                    project.as_ref(),
+                    self.progress.run_id(),
+                    Some(&agent_model(&self.config)),
+                )
                 .await?;
         }
         Ok(())
     }
+}
 
-    fn print_results(&self, result: &orchestrator::ExecutionResult) -> Result<()> {
-        println!(
-            "\n{}",
-            "═══════════════════════════════════════════════════════".bright_green()
-        );
-        println!("{}", "Execution Complete!".bright_green().bold());
-        println!(
-            "{}",
-            "═══════════════════════════════════════════════════════".bright_green()
-        );
-
-        println!("\n{}", "Summary:".bright_yellow().bold());
-        println!(
-            "  {} {}",
-            "Status:".white(),
-            if result.success {
-                "Success ✓".green()
-            } else {
-                "Failed ✗".red()
-            }
-        );
-        println!(
-            "  {} {}",
-            "Agents executed:".white(),
-            result.agents_executed
-        );
-        println!("  {} {}", "Total tokens used:".white(), result.tokens_used);
-        println!(
-            "  {} {:.2}s",
-            "Total time:".white(),
-            result.execution_time_secs
-        );
-
-        if !result.errors.is_empty() {
-            println!("\n{}", "Errors:".red().bold());
-            for error in &result.errors {
-                println!("  {} {}", "✗".red(), error);
-            }
-        }
+/// Root of the project the current directory is in, for the planner to scan
+fn workspace_root() -> Option<PathBuf> {
+    Project::current()
+        .map(|project| PathBuf::from(project.root))
+        .map_err(|e| warn!("Not scanning the workspace: {:#}", e))
+        .ok()
+}
 
-        if !result.warnings.is_empty() {
-            println!("\n{}", "Warnings:".yellow().bold());
-            for warning in &result.warnings {
-                println!("  {} {}", "⚠".yellow(), warning);
-            }
-        }
+/// Whether a Testing agent of the plan answered, so there are tests to run
+fn wrote_tests(plan: &ExecutionPlan, result: &ExecutionResult) -> bool {
+    plan.phases
+        .iter()
+        .flat_map(|phase| &phase.agents)
+        .filter(|spec| spec.capability == crate::agents::AgentCapability::Testing)
+        .any(|spec| {
+            result
+                .agents
+                .iter()
+                .any(|agent| agent.agent_id == spec.id && agent.output.is_some())
+        })
+}
 
-        Ok(())
+fn format_complexity(complexity: u8) -> String {
+    let (label, color) = match complexity {
+        0..=3 => ("Low", "green"),
+        4..=6 => ("Medium", "yellow"),
+        7..=8 => ("High", "red"),
+        _ => ("Very High", "bright_red"),
+    };
+
+    match color {
+        "green" => label.green().to_string(),
+        "yellow" => label.yellow().to_string(),
+        "red" => label.red().to_string(),
+        "bright_red" => label.bright_red().bold().to_string(),
+        _ => label.to_string(),
     }
 }
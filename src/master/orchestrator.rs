@@ -1,13 +1,16 @@
+use super::parallelism::Parallelism;
+use super::progress::{AgentPrompt, Progress, PromptDecision, RunEvent};
+use super::queue::{BlockReason, Blocked};
 use super::AutonomyMode;
-use crate::agents::{Agent, AgentCapability};
-use crate::config::Config;
+use crate::agents::{is_rate_limited, Agent, AgentCapability, AgentResult, RateLimiter};
+use crate::config::{ApprovalConfig, Config};
 use anyhow::Result;
-use colored::Colorize;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentSpec {
@@ -16,6 +19,14 @@ pub struct AgentSpec {
     pub capability: AgentCapability,
     pub task: String,
     pub dependencies: Vec<String>, // IDs of agents that must complete first
+    /// Share of the run's token budget this agent may use, from 0 to 1.
+    /// 0 (plans recorded before shares existed) means no per-agent limit.
+    #[serde(default)]
+    pub budget_share: f64,
+    /// JSON Schema the agent's output must match, for output that later
+    /// steps process automatically
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,12 +39,61 @@ pub struct ExecutionPhase {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionPlan {
     pub phases: Vec<ExecutionPhase>,
+    /// How agents are started; plans recorded before this existed ran phase
+    /// by phase
+    #[serde(default)]
+    pub scheduling: Scheduling,
+}
+
+/// Names accepted for `master_coder.scheduling`
+pub const SCHEDULING_MODES: &[&str] = &["dag", "phases"];
+
+/// When the orchestrator starts an agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scheduling {
+    /// A phase starts once every agent of the phase before it has finished
+    #[default]
+    Phases,
+    /// An agent starts as soon as the agents it depends on have finished.
+    /// The plan keeps all agents in a single phase.
+    Dag,
+}
+
+impl Scheduling {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "phases" => Some(Self::Phases),
+            "dag" => Some(Self::Dag),
+            _ => None,
+        }
+    }
 }
 
 impl ExecutionPlan {
     pub fn total_agents(&self) -> usize {
         self.phases.iter().map(|p| p.agents.len()).sum()
     }
+
+    /// "after Architect, Code Writer" for an agent of a [`Scheduling::Dag`]
+    /// plan that waits for others, to show the dependency graph
+    pub fn waits_for(&self, spec: &AgentSpec) -> Option<String> {
+        if self.scheduling != Scheduling::Dag || spec.dependencies.is_empty() {
+            return None;
+        }
+        let names: Vec<&str> = spec
+            .dependencies
+            .iter()
+            .map(|id| {
+                self.phases
+                    .iter()
+                    .flat_map(|phase| &phase.agents)
+                    .find(|other| &other.id == id)
+                    .map_or(id.as_str(), |other| other.agent_type.as_str())
+            })
+            .collect();
+        Some(format!("after {}", names.join(", ")))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,23 +101,132 @@ pub struct ExecutionResult {
     pub success: bool,
     pub agents_executed: usize,
     pub tokens_used: usize,
+    /// Dollars the agents' calls cost
+    #[serde(default)]
+    pub cost_usd: f64,
     pub execution_time_secs: f64,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    /// One entry per agent that ran, in completion order. Empty for runs
+    /// recorded before this was tracked.
+    #[serde(default)]
+    pub agents: Vec<AgentOutcome>,
+    /// How `[verify]` ran the tests agents wrote, when it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tests: Option<TestOutcome>,
+    /// Agents the token budget or rate limiting stopped, when the user
+    /// chose to pause the run for them
+    #[serde(skip)]
+    pub blocked: Option<Box<Blocked>>,
+}
+
+impl ExecutionResult {
+    /// Add the result of running the agents a pause left over
+    pub fn merge(&mut self, resumed: ExecutionResult) {
+        self.agents_executed += resumed.agents_executed;
+        self.tokens_used += resumed.tokens_used;
+        self.cost_usd += resumed.cost_usd;
+        self.execution_time_secs += resumed.execution_time_secs;
+        self.errors.extend(resumed.errors);
+        self.warnings.extend(resumed.warnings);
+        self.agents.extend(resumed.agents);
+        self.success = self.errors.is_empty();
+        self.blocked = resumed.blocked;
+    }
+}
+
+/// Result of running `verify.test_command` after a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestOutcome {
+    pub command: String,
+    pub passed: bool,
+    /// Debugging agent rounds it took
+    pub debug_rounds: usize,
+}
+
+/// How one agent of a run went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentOutcome {
+    pub agent_id: String,
+    pub agent_type: String,
+    pub tokens_used: usize,
+    #[serde(default)]
+    pub cost_usd: f64,
+    #[serde(default)]
+    pub execution_time_secs: f64,
+    pub error: Option<String>,
+    /// System prompt the agent ran with
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// What the agent answered, if it succeeded
+    #[serde(default)]
+    pub output: Option<String>,
+    /// Whether it failed because the backend refused its calls for rate
+    /// limiting or overload
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rate_limited: bool,
+}
+
+impl AgentOutcome {
+    fn new(
+        spec: &AgentSpec,
+        system_prompt: Option<String>,
+        started: Instant,
+        result: std::result::Result<&AgentResult, String>,
+    ) -> Self {
+        let (tokens_used, cost_usd, output, error) = match result {
+            Ok(result) => (
+                result.tokens_used,
+                result.cost_usd,
+                Some(result.output.clone()),
+                None,
+            ),
+            Err(error) => (0, 0.0, None, Some(error)),
+        };
+
+        Self {
+            agent_id: spec.id.clone(),
+            agent_type: spec.agent_type.clone(),
+            tokens_used,
+            cost_usd,
+            execution_time_secs: started.elapsed().as_secs_f64(),
+            error,
+            system_prompt,
+            output,
+            rate_limited: false,
+        }
+    }
 }
 
 pub struct Orchestrator {
-    config: Config,
     mode: AutonomyMode,
     max_parallel: usize,
+    /// Lower parallelism while the backend rate-limits or slows down
+    adaptive_parallelism: bool,
+    token_budget: usize,
+    /// Stop starting new agents once the run cost more dollars than this
+    max_cost: Option<f64>,
+    approval: ApprovalConfig,
+    progress: Progress,
+    /// Where agents report how their calls to the backend went
+    limiter: Arc<RateLimiter>,
+    /// Offer to pause the run when the token budget or rate limiting stops
+    /// its agents
+    offer_pause: bool,
 }
 
 impl Orchestrator {
     pub fn new(config: Config, mode: AutonomyMode) -> Self {
         Self {
             max_parallel: config.master_coder.max_parallel_agents,
-            config,
+            adaptive_parallelism: config.master_coder.adaptive_parallelism,
+            token_budget: config.master_coder.token_budget,
+            max_cost: None,
+            limiter: RateLimiter::shared(&config, &config.master_coder.backend),
+            approval: config.approval,
             mode,
+            progress: Progress::default(),
+            offer_pause: false,
         }
     }
 
@@ -65,54 +234,181 @@ impl Orchestrator {
         self.max_parallel = max;
     }
 
+    /// Stop starting new phases once more than `budget` tokens are used
+    pub fn set_token_budget(&mut self, budget: usize) {
+        self.token_budget = budget;
+    }
+
+    /// Stop starting new phases or agents once the run cost more than
+    /// `max_cost` dollars
+    pub fn set_max_cost(&mut self, max_cost: Option<f64>) {
+        self.max_cost = max_cost;
+    }
+
+    /// Offer to pause the run and hand over the agents the token budget or
+    /// rate limiting stops in [`ExecutionResult::blocked`], instead of
+    /// going on without them
+    pub fn set_offer_pause(&mut self, offer: bool) {
+        self.offer_pause = offer;
+    }
+
+    /// Whether to pause for the `count` agents `reason` stops, when pausing
+    /// is offered
+    async fn pause(&self, reason: BlockReason, count: usize) -> Result<bool> {
+        if !self.offer_pause {
+            return Ok(false);
+        }
+        self.progress
+            .approve(&format!(
+                "Agents stopped ({}). Pause the run and queue the {} remaining agent(s) until the usage window resets?",
+                reason.description(),
+                count
+            ))
+            .await
+    }
+
+    /// Why no more agents may start once the run cost `cost_usd`, if the
+    /// cost cap is exceeded
+    pub(super) fn cost_cap_error(&self, cost_usd: f64) -> Option<String> {
+        let cap = self.max_cost?;
+        (cost_usd > cap)
+            .then(|| format!("Cost cap of ${:.2} exceeded (${:.2} spent)", cap, cost_usd))
+    }
+
+    /// Tokens `spec` may use: its share of the token budget
+    fn allotment(&self, spec: &AgentSpec) -> Option<usize> {
+        (spec.budget_share > 0.0).then_some((spec.budget_share * self.token_budget as f64) as usize)
+    }
+
+    /// Warn when an agent used more than its share, which leaves less for the
+    /// agents after it
+    fn check_allotment(&self, spec: &AgentSpec, tokens_used: usize, warnings: &mut Vec<String>) {
+        if let Some(allotment) = self.allotment(spec) {
+            if tokens_used > allotment {
+                warnings.push(format!(
+                    "{} used {} tokens, more than its share of {}",
+                    spec.agent_type, tokens_used, allotment
+                ));
+            }
+        }
+    }
+
+    /// Report progress and ask for approvals through `progress`
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = progress;
+    }
+
     /// Execute the plan with agents
     pub async fn execute_plan(
         &self,
         plan: &ExecutionPlan,
         mut agents: Vec<Box<dyn Agent>>,
     ) -> Result<ExecutionResult> {
+        if plan.scheduling == Scheduling::Dag {
+            return self.execute_dag(plan, agents).await;
+        }
+
         let start_time = Instant::now();
         let mut total_tokens = 0;
+        let mut total_cost = 0.0;
         let mut agents_executed = 0;
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
-
-        let multi_progress = MultiProgress::new();
+        let mut outcomes = Vec::new();
+        let mut parallelism = Parallelism::new(self.max_parallel, self.adaptive_parallelism);
+        let mut blocked = None;
+        // Calls from before this run say nothing about it
+        self.limiter.take_health();
 
         for (phase_num, phase) in plan.phases.iter().enumerate() {
-            println!(
-                "\n{} Phase {}/{}: {}",
-                if phase.parallel {
-                    "⚡".bright_yellow()
-                } else {
-                    "→".bright_cyan()
-                },
-                phase_num + 1,
-                plan.phases.len(),
-                phase.description.bright_white().bold()
-            );
+            if let Some(error) = self.cost_cap_error(total_cost) {
+                errors.push(format!(
+                    "{}, stopping before phase {}",
+                    error,
+                    phase_num + 1
+                ));
+                break;
+            }
+            if total_tokens > self.token_budget {
+                let error = format!(
+                    "Token budget of {} exceeded ({} used), stopping before phase {}",
+                    self.token_budget,
+                    total_tokens,
+                    phase_num + 1
+                );
+                errors.push(error.clone());
+                let ids = agent_ids(&plan.phases[phase_num..]);
+                if self.pause(BlockReason::TokenBudget, ids.len()).await? {
+                    blocked = Some(Box::new(Blocked::new(
+                        BlockReason::TokenBudget,
+                        plan,
+                        &ids,
+                        &agents,
+                        &[],
+                        vec![error],
+                    )));
+                }
+                break;
+            }
+
+            self.progress.send(RunEvent::PhaseStarted {
+                index: phase_num,
+                total: plan.phases.len(),
+                description: phase.description.clone(),
+                parallel: phase.parallel,
+            });
 
             // Get user approval if needed
-            if self.needs_approval_for_phase(phase_num, plan.phases.len()) {
-                if !self.get_phase_approval(phase)? {
-                    warnings.push(format!("Phase {} skipped by user", phase_num + 1));
-                    continue;
-                }
+            if self.needs_approval_for_phase(phase, phase_num, plan.phases.len())
+                && !self
+                    .progress
+                    .approve(&format!("Execute phase: {}?", phase.description))
+                    .await?
+            {
+                warnings.push(format!("Phase {} skipped by user", phase_num + 1));
+                self.progress
+                    .send(RunEvent::PhaseSkipped { index: phase_num });
+                continue;
             }
 
             // Execute agents in this phase
             let phase_result = if phase.parallel {
-                self.execute_parallel(phase, &mut agents, &multi_progress)
+                self.execute_parallel(phase, &mut agents, &mut parallelism, total_cost)
                     .await?
             } else {
-                self.execute_sequential(phase, &mut agents, &multi_progress)
+                self.execute_sequential(phase, &mut agents, &mut parallelism, total_cost)
                     .await?
             };
 
+            let refused: Vec<AgentOutcome> = phase_result
+                .agents
+                .iter()
+                .filter(|outcome| outcome.rate_limited)
+                .cloned()
+                .collect();
             total_tokens += phase_result.tokens_used;
+            total_cost += phase_result.cost_usd;
             agents_executed += phase_result.agents_completed;
             errors.extend(phase_result.errors);
             warnings.extend(phase_result.warnings);
+            outcomes.extend(phase_result.agents);
+
+            if !refused.is_empty() {
+                let mut ids: Vec<String> = refused.iter().map(|o| o.agent_id.clone()).collect();
+                ids.extend(agent_ids(&plan.phases[phase_num + 1..]));
+                if self.pause(BlockReason::RateLimited, ids.len()).await? {
+                    let refused_errors = refused.iter().filter_map(|o| o.error.clone()).collect();
+                    blocked = Some(Box::new(Blocked::new(
+                        BlockReason::RateLimited,
+                        plan,
+                        &ids,
+                        &agents,
+                        &refused,
+                        refused_errors,
+                    )));
+                    break;
+                }
+            }
 
             if !phase_result.success && phase_result.critical {
                 // Critical failure, stop execution
@@ -131,145 +427,473 @@ impl Orchestrator {
             success,
             agents_executed,
             tokens_used: total_tokens,
+            cost_usd: total_cost,
             execution_time_secs: execution_time,
             errors,
             warnings,
+            agents: outcomes,
+            tests: None,
+            blocked,
         })
     }
 
+    /// Run the agents of `phase` side by side. `spent_usd` is what the
+    /// phases before cost; no agent starts once the cost cap is exceeded.
     async fn execute_parallel(
         &self,
         phase: &ExecutionPhase,
         agents: &mut Vec<Box<dyn Agent>>,
-        multi_progress: &MultiProgress,
+        parallelism: &mut Parallelism,
+        spent_usd: f64,
     ) -> Result<PhaseResult> {
-        let semaphore = Arc::new(Semaphore::new(self.max_parallel));
-        let mut handles: Vec<tokio::task::JoinHandle<Result<(usize, Option<String>)>>> = Vec::new();
-        let mut tokens_used = 0;
-        let mut completed = 0;
-        let mut errors = Vec::new();
-        let mut warnings = Vec::new();
+        let mut running: JoinSet<(AgentSpec, AgentOutcome)> = JoinSet::new();
+        let mut result = PhaseResult {
+            success: true,
+            critical: false,
+            agents_completed: 0,
+            tokens_used: 0,
+            cost_usd: 0.0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            agents: Vec::new(),
+        };
 
-        for spec in &phase.agents {
+        for (i, spec) in phase.agents.iter().enumerate() {
             // Find matching agent
             let agent_idx = agents.iter().position(|a| a.id() == spec.id);
 
             if let Some(idx) = agent_idx {
                 let mut agent = agents.remove(idx);
-                let permit = semaphore.clone().acquire_owned().await?;
-
-                let pb = multi_progress.add(ProgressBar::new(100));
-                pb.set_style(
-                    ProgressStyle::default_bar()
-                        .template("  {spinner:.cyan} [{bar:40.cyan/blue}] {msg}")
-                        .unwrap()
-                        .progress_chars("=>-"),
-                );
-                pb.set_message(format!("{}: Starting...", spec.agent_type));
 
-                let spec_clone = spec.clone();
+                // Wait for a slot; how many there are follows the backend's health
+                while running.len() >= parallelism.current() {
+                    let Some(finished) = running.join_next().await else {
+                        break;
+                    };
+                    self.collect_parallel(finished, parallelism, &mut result);
+                }
 
-                let handle = tokio::spawn(async move {
-                    pb.set_position(10);
-                    pb.set_message(format!("{}: Executing...", spec_clone.agent_type));
+                if let Some(error) = self.cost_cap_error(spent_usd + result.cost_usd) {
+                    result.errors.push(format!(
+                        "{}, not starting {} more agent(s)",
+                        error,
+                        phase.agents.len() - i
+                    ));
+                    break;
+                }
 
-                    let result = agent.execute(&spec_clone.task).await;
+                match self.review_prompt(spec, &mut agent).await? {
+                    Some(task) => self.spawn_agent(&mut running, spec, agent, task),
+                    None => self.skipped(spec, &mut result.warnings),
+                }
+            } else {
+                result.warnings.push(format!("Agent {} not found", spec.id));
+            }
+        }
 
-                    pb.set_position(90);
+        // Wait for all agents to complete
+        while let Some(finished) = running.join_next().await {
+            self.collect_parallel(finished, parallelism, &mut result);
+        }
 
-                    drop(permit);
+        result.success = result.errors.is_empty();
+        Ok(result)
+    }
 
-                    match result {
-                        Ok(agent_result) => {
-                            pb.set_position(100);
-                            pb.finish_with_message(format!(
-                                "{}: ✓ Complete ({} tokens)",
-                                spec_clone.agent_type, agent_result.tokens_used
-                            ));
-                            Ok((agent_result.tokens_used, None))
-                        }
-                        Err(e) => {
-                            pb.finish_with_message(format!("{}: ✗ Failed", spec_clone.agent_type));
-                            Ok((0, Some(format!("{} failed: {}", spec_clone.agent_type, e))))
+    /// Run every agent of a [`Scheduling::Dag`] plan as soon as the agents
+    /// it depends on have succeeded. Agents whose dependencies failed are
+    /// skipped; the token budget stops new agents from starting.
+    async fn execute_dag(
+        &self,
+        plan: &ExecutionPlan,
+        mut agents: Vec<Box<dyn Agent>>,
+    ) -> Result<ExecutionResult> {
+        let start_time = Instant::now();
+        let specs: Vec<&AgentSpec> = plan.phases.iter().flat_map(|p| &p.agents).collect();
+        let mut result = PhaseResult {
+            success: true,
+            critical: false,
+            agents_completed: 0,
+            tokens_used: 0,
+            cost_usd: 0.0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            agents: Vec::new(),
+        };
+        let mut parallelism = Parallelism::new(self.max_parallel, self.adaptive_parallelism);
+        // Calls from before this run say nothing about it
+        self.limiter.take_health();
+
+        let description = match plan.phases.as_slice() {
+            [phase] => phase.description.clone(),
+            _ => "Dependency graph".to_string(),
+        };
+        self.progress.send(RunEvent::PhaseStarted {
+            index: 0,
+            total: 1,
+            description: description.clone(),
+            parallel: true,
+        });
+        let needs_approval = plan
+            .phases
+            .iter()
+            .any(|phase| self.needs_approval_for_phase(phase, 0, 1));
+        if needs_approval
+            && !self
+                .progress
+                .approve(&format!("Execute: {}?", description))
+                .await?
+        {
+            result.warnings.push("Run skipped by user".to_string());
+            self.progress.send(RunEvent::PhaseSkipped { index: 0 });
+            return Ok(result.into_execution_result(start_time));
+        }
+
+        let mut pending: Vec<&AgentSpec> = specs.clone();
+        let mut succeeded: HashSet<String> = HashSet::new();
+        let mut failed: HashSet<String> = HashSet::new();
+        let mut running: JoinSet<(AgentSpec, AgentOutcome)> = JoinSet::new();
+        let mut blocked = None;
+        // Whether to pause for agents refused for rate limiting, once asked,
+        // and the agents refused since
+        let mut pausing: Option<bool> = None;
+        let mut refused: Vec<AgentOutcome> = Vec::new();
+
+        loop {
+            // Skip agents a failed agent was to prepare for, and the agents
+            // depending on those in turn
+            while let Some(pos) = pending
+                .iter()
+                .position(|spec| spec.dependencies.iter().any(|dep| failed.contains(dep)))
+            {
+                let spec = pending.remove(pos);
+                result
+                    .errors
+                    .push(format!("{} skipped, a dependency failed", spec.agent_type));
+                failed.insert(spec.id.clone());
+            }
+
+            if let Some(error) = self
+                .cost_cap_error(result.cost_usd)
+                .filter(|_| pausing != Some(true) && !pending.is_empty())
+            {
+                result.errors.push(format!(
+                    "{}, not starting {} more agent(s)",
+                    error,
+                    pending.len()
+                ));
+                pending.clear();
+            }
+            if pausing != Some(true)
+                && result.tokens_used > self.token_budget
+                && !pending.is_empty()
+            {
+                let error = format!(
+                    "Token budget of {} exceeded ({} used), not starting {} more agent(s)",
+                    self.token_budget,
+                    result.tokens_used,
+                    pending.len()
+                );
+                result.errors.push(error.clone());
+                let ids: Vec<String> = pending.iter().map(|spec| spec.id.clone()).collect();
+                if self.pause(BlockReason::TokenBudget, ids.len()).await? {
+                    blocked = Some(Box::new(Blocked::new(
+                        BlockReason::TokenBudget,
+                        plan,
+                        &ids,
+                        &agents,
+                        &[],
+                        vec![error],
+                    )));
+                }
+                pending.clear();
+            }
+
+            // Nothing new starts while pausing for rate limits
+            while pausing != Some(true) && running.len() < parallelism.current() {
+                let Some(pos) = pending
+                    .iter()
+                    .position(|spec| spec.dependencies.iter().all(|dep| succeeded.contains(dep)))
+                else {
+                    break;
+                };
+                let spec = pending.remove(pos);
+                match agents.iter().position(|a| a.id() == spec.id) {
+                    Some(idx) => {
+                        let mut agent = agents.remove(idx);
+                        match self.review_prompt(spec, &mut agent).await? {
+                            Some(task) => self.spawn_agent(&mut running, spec, agent, task),
+                            // Agents after a skipped one still run; the user
+                            // chose to go on without it
+                            None => {
+                                self.skipped(spec, &mut result.warnings);
+                                succeeded.insert(spec.id.clone());
+                            }
                         }
                     }
-                });
+                    None => {
+                        result.warnings.push(format!("Agent {} not found", spec.id));
+                        succeeded.insert(spec.id.clone());
+                    }
+                }
+            }
 
-                handles.push(handle);
-            } else {
-                warnings.push(format!("Agent {} not found", spec.id));
+            let Some(finished) = running.join_next().await else {
+                if pausing == Some(true) {
+                    let mut ids: Vec<String> = refused.iter().map(|o| o.agent_id.clone()).collect();
+                    ids.extend(pending.iter().map(|spec| spec.id.clone()));
+                    let refused_errors = refused.iter().filter_map(|o| o.error.clone()).collect();
+                    blocked = Some(Box::new(Blocked::new(
+                        BlockReason::RateLimited,
+                        plan,
+                        &ids,
+                        &agents,
+                        &refused,
+                        refused_errors,
+                    )));
+                } else if !pending.is_empty() {
+                    // Only a dependency cycle or an unknown dependency
+                    // leaves agents that can never start
+                    result.errors.push(format!(
+                        "Not run, their dependencies can never finish: {}",
+                        pending
+                            .iter()
+                            .map(|spec| spec.id.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+                break;
+            };
+            if let Ok((spec, outcome)) = &finished {
+                let pause = outcome.rate_limited
+                    && blocked.is_none()
+                    && match pausing {
+                        Some(pause) => pause,
+                        None => *pausing.insert(
+                            self.pause(BlockReason::RateLimited, pending.len() + 1)
+                                .await?,
+                        ),
+                    };
+                if outcome.error.is_none() {
+                    succeeded.insert(spec.id.clone());
+                } else if pause {
+                    // Not failed: the agents depending on it wait with it
+                    refused.push(outcome.clone());
+                } else {
+                    failed.insert(spec.id.clone());
+                }
             }
+            self.collect_parallel(finished, &mut parallelism, &mut result);
         }
 
-        // Wait for all agents to complete
-        for handle in handles {
-            match handle.await? {
-                Ok((tokens, error)) => {
-                    tokens_used += tokens;
-                    completed += 1;
-                    if let Some(err) = error {
-                        errors.push(err);
-                    }
+        let mut execution = result.into_execution_result(start_time);
+        execution.blocked = blocked;
+        Ok(execution)
+    }
+
+    /// In interactive mode, have the user run, edit or skip the prompt of
+    /// `agent` first. Returns the task to run it with, or None to skip it.
+    async fn review_prompt(
+        &self,
+        spec: &AgentSpec,
+        agent: &mut Box<dyn Agent>,
+    ) -> Result<Option<String>> {
+        if self.mode != AutonomyMode::Interactive {
+            return Ok(Some(spec.task.clone()));
+        }
+
+        let system_prompt = agent.system_prompt().unwrap_or_default().to_string();
+        let prompt = AgentPrompt {
+            agent_id: spec.id.clone(),
+            agent_type: spec.agent_type.clone(),
+            system_prompt: system_prompt.clone(),
+            task: spec.task.clone(),
+        };
+        match self.progress.review_prompt(prompt).await? {
+            PromptDecision::Run(prompt) => {
+                if prompt.system_prompt != system_prompt {
+                    agent.set_system_prompt(prompt.system_prompt);
+                }
+                Ok(Some(prompt.task))
+            }
+            PromptDecision::Skip => Ok(None),
+        }
+    }
+
+    fn skipped(&self, spec: &AgentSpec, warnings: &mut Vec<String>) {
+        let warning = format!("{} skipped by user", spec.agent_type);
+        self.progress.send(RunEvent::Message(warning.clone()));
+        warnings.push(warning);
+    }
+
+    /// Start `agent` on `task` in `running`, reporting when it starts and
+    /// finishes
+    fn spawn_agent(
+        &self,
+        running: &mut JoinSet<(AgentSpec, AgentOutcome)>,
+        spec: &AgentSpec,
+        mut agent: Box<dyn Agent>,
+        task: String,
+    ) {
+        if let Some(allotment) = self.allotment(spec) {
+            agent.set_max_tokens(allotment);
+        }
+        let spec = spec.clone();
+        let progress = self.progress.clone();
+
+        running.spawn(async move {
+            progress.send(RunEvent::AgentStarted {
+                agent_id: spec.id.clone(),
+                agent_type: spec.agent_type.clone(),
+            });
+
+            let system_prompt = agent.system_prompt().map(str::to_string);
+            let started = Instant::now();
+            let result = agent.execute(&task).await;
+
+            let outcome = match result {
+                Ok(agent_result) => {
+                    progress.send(RunEvent::AgentFinished {
+                        agent_id: spec.id.clone(),
+                        tokens_used: agent_result.tokens_used,
+                        error: None,
+                    });
+                    AgentOutcome::new(&spec, system_prompt, started, Ok(&agent_result))
                 }
                 Err(e) => {
-                    errors.push(format!("Agent execution error: {}", e));
+                    let error = format!("{} failed: {}", spec.agent_type, e);
+                    progress.send(RunEvent::AgentFinished {
+                        agent_id: spec.id.clone(),
+                        tokens_used: 0,
+                        error: Some(error.clone()),
+                    });
+                    let mut outcome = AgentOutcome::new(&spec, system_prompt, started, Err(error));
+                    outcome.rate_limited = is_rate_limited(&e);
+                    outcome
                 }
+            };
+            (spec, outcome)
+        });
+    }
+
+    /// Add an agent started with [`Self::spawn_agent`] that finished to
+    /// `result`, and adapt the parallelism to how its calls went
+    fn collect_parallel(
+        &self,
+        finished: std::result::Result<(AgentSpec, AgentOutcome), tokio::task::JoinError>,
+        parallelism: &mut Parallelism,
+        result: &mut PhaseResult,
+    ) {
+        self.adapt(parallelism);
+        match finished {
+            Ok((spec, outcome)) => {
+                self.check_allotment(&spec, outcome.tokens_used, &mut result.warnings);
+                result.tokens_used += outcome.tokens_used;
+                result.cost_usd += outcome.cost_usd;
+                result.agents_completed += 1;
+                if let Some(err) = &outcome.error {
+                    result.errors.push(err.clone());
+                }
+                result.agents.push(outcome);
+            }
+            Err(e) => {
+                result.errors.push(format!("Agent execution error: {}", e));
             }
         }
+    }
 
-        Ok(PhaseResult {
-            success: errors.is_empty(),
-            critical: false,
-            agents_completed: completed,
-            tokens_used,
-            errors,
-            warnings,
-        })
+    /// Feed the backend's health since the last agent into `parallelism`
+    fn adapt(&self, parallelism: &mut Parallelism) {
+        let health = self.limiter.take_health();
+        let previous = parallelism.current();
+        if let Some(current) = parallelism.observe(health) {
+            let reason = if current > previous {
+                "backend healthy again"
+            } else if health.rate_limited > 0 {
+                "backend is rate limiting"
+            } else {
+                "backend calls slowed down"
+            };
+            info!("Running up to {} agents at once ({})", current, reason);
+        }
     }
 
+    /// Run the agents of `phase` one after another. `spent_usd` is what the
+    /// phases before cost; no agent starts once the cost cap is exceeded.
     async fn execute_sequential(
         &self,
         phase: &ExecutionPhase,
         agents: &mut Vec<Box<dyn Agent>>,
-        multi_progress: &MultiProgress,
+        parallelism: &mut Parallelism,
+        spent_usd: f64,
     ) -> Result<PhaseResult> {
         let mut tokens_used = 0;
+        let mut cost_usd = 0.0;
         let mut completed = 0;
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
+        let mut outcomes = Vec::new();
+
+        for (i, spec) in phase.agents.iter().enumerate() {
+            if let Some(error) = self.cost_cap_error(spent_usd + cost_usd) {
+                errors.push(format!(
+                    "{}, not starting {} more agent(s)",
+                    error,
+                    phase.agents.len() - i
+                ));
+                break;
+            }
 
-        for spec in &phase.agents {
             // Find matching agent
             let agent_idx = agents.iter().position(|a| a.id() == spec.id);
 
             if let Some(idx) = agent_idx {
                 let mut agent = agents.remove(idx);
+                if let Some(allotment) = self.allotment(spec) {
+                    agent.set_max_tokens(allotment);
+                }
+                let Some(task) = self.review_prompt(spec, &mut agent).await? else {
+                    self.skipped(spec, &mut warnings);
+                    continue;
+                };
 
-                let pb = multi_progress.add(ProgressBar::new(100));
-                pb.set_style(
-                    ProgressStyle::default_bar()
-                        .template("  {spinner:.cyan} [{bar:40.cyan/blue}] {msg}")
-                        .unwrap()
-                        .progress_chars("=>-"),
-                );
-                pb.set_message(format!("{}: Starting...", spec.agent_type));
-
-                pb.set_position(10);
-                pb.set_message(format!("{}: Executing...", spec.agent_type));
+                self.progress.send(RunEvent::AgentStarted {
+                    agent_id: spec.id.clone(),
+                    agent_type: spec.agent_type.clone(),
+                });
 
-                match agent.execute(&spec.task).await {
+                let system_prompt = agent.system_prompt().map(str::to_string);
+                let started = Instant::now();
+                let result = agent.execute(&task).await;
+                // Sequential agents run one at a time anyway, but their calls
+                // tell the next parallel phase how the backend is doing
+                self.adapt(parallelism);
+                match result {
                     Ok(result) => {
+                        self.check_allotment(spec, result.tokens_used, &mut warnings);
                         tokens_used += result.tokens_used;
+                        cost_usd += result.cost_usd;
                         completed += 1;
-                        pb.set_position(100);
-                        pb.finish_with_message(format!(
-                            "{}: ✓ Complete ({} tokens)",
-                            spec.agent_type, result.tokens_used
-                        ));
+                        self.progress.send(RunEvent::AgentFinished {
+                            agent_id: spec.id.clone(),
+                            tokens_used: result.tokens_used,
+                            error: None,
+                        });
+                        outcomes.push(AgentOutcome::new(spec, system_prompt, started, Ok(&result)));
                     }
                     Err(e) => {
-                        pb.finish_with_message(format!("{}: ✗ Failed", spec.agent_type));
-                        errors.push(format!("{} failed: {}", spec.agent_type, e));
+                        let error = format!("{} failed: {}", spec.agent_type, e);
+                        self.progress.send(RunEvent::AgentFinished {
+                            agent_id: spec.id.clone(),
+                            tokens_used: 0,
+                            error: Some(error.clone()),
+                        });
+                        let mut outcome =
+                            AgentOutcome::new(spec, system_prompt, started, Err(error.clone()));
+                        outcome.rate_limited = is_rate_limited(&e);
+                        outcomes.push(outcome);
+                        errors.push(error);
 
                         // In sequential mode, a failure might be critical
                         if spec.capability == AgentCapability::Architecture {
@@ -278,8 +902,10 @@ impl Orchestrator {
                                 critical: true,
                                 agents_completed: completed,
                                 tokens_used,
+                                cost_usd,
                                 errors,
                                 warnings,
+                                agents: outcomes,
                             });
                         }
                     }
@@ -294,35 +920,40 @@ impl Orchestrator {
             critical: false,
             agents_completed: completed,
             tokens_used,
+            cost_usd,
             errors,
             warnings,
+            agents: outcomes,
         })
     }
 
-    fn needs_approval_for_phase(&self, phase_num: usize, total_phases: usize) -> bool {
+    fn needs_approval_for_phase(
+        &self,
+        phase: &ExecutionPhase,
+        phase_num: usize,
+        total_phases: usize,
+    ) -> bool {
+        let is_final = phase_num == total_phases - 1;
+        let required_by_policy = phase
+            .agents
+            .iter()
+            .any(|spec| self.approval.requires_capability(&spec.capability))
+            || (phase.parallel && self.approval.requires_action("parallel"))
+            || (is_final && self.approval.requires_action("final"));
+        if required_by_policy {
+            return true;
+        }
+
         match self.mode {
             AutonomyMode::Trust => false,
             AutonomyMode::Interactive => true,
             AutonomyMode::Balanced => {
                 // Ask at the beginning and before final phase
-                phase_num == 0 || phase_num == total_phases - 1
+                phase_num == 0 || is_final
             }
             AutonomyMode::Conservative => true,
         }
     }
-
-    fn get_phase_approval(&self, phase: &ExecutionPhase) -> Result<bool> {
-        use dialoguer::Confirm;
-
-        let prompt = format!("Execute phase: {}?", phase.description);
-
-        let result = Confirm::new()
-            .with_prompt(prompt)
-            .default(true)
-            .interact()?;
-
-        Ok(result)
-    }
 }
 
 struct PhaseResult {
@@ -330,6 +961,518 @@ struct PhaseResult {
     critical: bool, // If true, should stop execution
     agents_completed: usize,
     tokens_used: usize,
+    cost_usd: f64,
     errors: Vec<String>,
     warnings: Vec<String>,
+    agents: Vec<AgentOutcome>,
+}
+
+impl PhaseResult {
+    fn into_execution_result(self, start_time: Instant) -> ExecutionResult {
+        ExecutionResult {
+            success: self.errors.is_empty(),
+            agents_executed: self.agents_completed,
+            tokens_used: self.tokens_used,
+            cost_usd: self.cost_usd,
+            execution_time_secs: start_time.elapsed().as_secs_f64(),
+            errors: self.errors,
+            warnings: self.warnings,
+            agents: self.agents,
+            tests: None,
+            blocked: None,
+        }
+    }
+}
+
+/// IDs of the agents of `phases`, in order
+fn agent_ids(phases: &[ExecutionPhase]) -> Vec<String> {
+    phases
+        .iter()
+        .flat_map(|phase| &phase.agents)
+        .map(|spec| spec.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    /// Agent that answers instantly with a fixed token count
+    struct StubAgent {
+        id: String,
+        capability: AgentCapability,
+    }
+
+    #[async_trait]
+    impl Agent for StubAgent {
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn agent_type(&self) -> &str {
+            "Stub"
+        }
+
+        fn capability(&self) -> &AgentCapability {
+            &self.capability
+        }
+
+        async fn execute(&mut self, _task: &str) -> Result<AgentResult> {
+            Ok(AgentResult {
+                success: true,
+                output: "done".to_string(),
+                tokens_used: 600,
+                execution_time_ms: 0,
+                cost_usd: 0.05,
+            })
+        }
+    }
+
+    /// Agent whose every call fails
+    struct FailingAgent {
+        id: String,
+    }
+
+    #[async_trait]
+    impl Agent for FailingAgent {
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn agent_type(&self) -> &str {
+            "Failing"
+        }
+
+        fn capability(&self) -> &AgentCapability {
+            &AgentCapability::CodeWriting
+        }
+
+        async fn execute(&mut self, _task: &str) -> Result<AgentResult> {
+            anyhow::bail!("backend unavailable")
+        }
+    }
+
+    /// Agent the backend refuses for rate limiting, whose system prompt
+    /// carries the project knowledge it was given
+    struct RefusedAgent {
+        id: String,
+    }
+
+    #[async_trait]
+    impl Agent for RefusedAgent {
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn agent_type(&self) -> &str {
+            "Refused"
+        }
+
+        fn capability(&self) -> &AgentCapability {
+            &AgentCapability::CodeWriting
+        }
+
+        fn system_prompt(&self) -> Option<&str> {
+            Some("You are Refused. Conventions: use anyhow")
+        }
+
+        async fn execute(&mut self, _task: &str) -> Result<AgentResult> {
+            Err(crate::error::ClaudeHelperError::ApiError {
+                status: 429,
+                message: "rate_limit_error".to_string(),
+            }
+            .into())
+        }
+    }
+
+    fn phase(id: &str) -> ExecutionPhase {
+        ExecutionPhase {
+            description: format!("Phase {}", id),
+            agents: vec![AgentSpec {
+                id: id.to_string(),
+                agent_type: "Stub".to_string(),
+                capability: AgentCapability::CodeWriting,
+                task: "work".to_string(),
+                dependencies: Vec::new(),
+                budget_share: 0.0,
+                output_schema: None,
+            }],
+            parallel: false,
+        }
+    }
+
+    #[test]
+    fn test_approval_policy_overrides_trust_mode() {
+        let mut config = Config::default();
+        config.approval.capabilities = vec!["migration".to_string()];
+        config.approval.actions = vec!["final".to_string()];
+        let orchestrator = Orchestrator::new(config, AutonomyMode::Trust);
+
+        let mut migration = phase("m");
+        migration.agents[0].capability = AgentCapability::Migration;
+
+        assert!(!orchestrator.needs_approval_for_phase(&phase("a"), 0, 3));
+        assert!(orchestrator.needs_approval_for_phase(&migration, 1, 3));
+        assert!(orchestrator.needs_approval_for_phase(&phase("b"), 2, 3));
+    }
+
+    #[tokio::test]
+    async fn test_warns_when_agent_exceeds_its_share() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+        orchestrator.set_token_budget(1_000);
+
+        let mut phase = phase("a");
+        phase.agents[0].budget_share = 0.5;
+        let plan = ExecutionPlan {
+            phases: vec![phase],
+            scheduling: Scheduling::Phases,
+        };
+        let agents: Vec<Box<dyn Agent>> = vec![Box::new(StubAgent {
+            id: "a".to_string(),
+            capability: AgentCapability::CodeWriting,
+        })];
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+        assert_eq!(
+            result.warnings,
+            vec!["Stub used 600 tokens, more than its share of 500"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_budget_is_spent() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+        orchestrator.set_token_budget(500);
+
+        let plan = ExecutionPlan {
+            phases: vec![phase("a"), phase("b")],
+            scheduling: Scheduling::Phases,
+        };
+        let agents: Vec<Box<dyn Agent>> = ["a", "b"]
+            .into_iter()
+            .map(|id| {
+                Box::new(StubAgent {
+                    id: id.to_string(),
+                    capability: AgentCapability::CodeWriting,
+                }) as Box<dyn Agent>
+            })
+            .collect();
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert_eq!(result.agents_executed, 1);
+        assert_eq!(result.tokens_used, 600);
+        assert!(result.errors[0].contains("stopping before phase 2"));
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_cost_cap_is_spent() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+        orchestrator.set_max_cost(Some(0.08));
+        let stubs = |ids: &[&str]| -> Vec<Box<dyn Agent>> {
+            ids.iter()
+                .map(|id| {
+                    Box::new(StubAgent {
+                        id: id.to_string(),
+                        capability: AgentCapability::CodeWriting,
+                    }) as Box<dyn Agent>
+                })
+                .collect()
+        };
+
+        let plan = ExecutionPlan {
+            phases: vec![phase("a"), phase("b"), phase("c")],
+            scheduling: Scheduling::Phases,
+        };
+        let result = orchestrator
+            .execute_plan(&plan, stubs(&["a", "b", "c"]))
+            .await
+            .unwrap();
+        assert_eq!(result.agents_executed, 2);
+        assert!((result.cost_usd - 0.10).abs() < 1e-9);
+        assert_eq!(
+            result.errors,
+            vec!["Cost cap of $0.08 exceeded ($0.10 spent), stopping before phase 3"]
+        );
+
+        let mut chain = phase("a");
+        chain.agents.push(AgentSpec {
+            id: "b".to_string(),
+            dependencies: vec!["a".to_string()],
+            ..chain.agents[0].clone()
+        });
+        chain.agents.push(AgentSpec {
+            id: "c".to_string(),
+            dependencies: vec!["b".to_string()],
+            ..chain.agents[0].clone()
+        });
+        let plan = ExecutionPlan {
+            phases: vec![chain],
+            scheduling: Scheduling::Dag,
+        };
+        let result = orchestrator
+            .execute_plan(&plan, stubs(&["a", "b", "c"]))
+            .await
+            .unwrap();
+        assert_eq!(result.agents_executed, 2);
+        assert!(result.errors[0].contains("not starting 1 more agent(s)"));
+
+        // Within a phase too, sequential or one at a time in parallel
+        orchestrator.set_max_parallel(1);
+        orchestrator.adaptive_parallelism = false;
+        for parallel in [false, true] {
+            let mut phase = phase("a");
+            phase.parallel = parallel;
+            for id in ["b", "c"] {
+                phase.agents.push(AgentSpec {
+                    id: id.to_string(),
+                    ..phase.agents[0].clone()
+                });
+            }
+            let plan = ExecutionPlan {
+                phases: vec![phase],
+                scheduling: Scheduling::Phases,
+            };
+            let result = orchestrator
+                .execute_plan(&plan, stubs(&["a", "b", "c"]))
+                .await
+                .unwrap();
+            assert_eq!(result.agents_executed, 2);
+            assert_eq!(
+                result.errors,
+                vec!["Cost cap of $0.08 exceeded ($0.10 spent), not starting 1 more agent(s)"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_phase_runs_every_agent() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+        orchestrator.set_max_parallel(2);
+
+        let mut parallel = phase("a");
+        for id in ["b", "c"] {
+            parallel.agents.push(AgentSpec {
+                id: id.to_string(),
+                ..parallel.agents[0].clone()
+            });
+        }
+        parallel.parallel = true;
+        let plan = ExecutionPlan {
+            phases: vec![parallel],
+            scheduling: Scheduling::Phases,
+        };
+        let agents: Vec<Box<dyn Agent>> = ["a", "b", "c"]
+            .into_iter()
+            .map(|id| {
+                Box::new(StubAgent {
+                    id: id.to_string(),
+                    capability: AgentCapability::CodeWriting,
+                }) as Box<dyn Agent>
+            })
+            .collect();
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.agents_executed, 3);
+        assert_eq!(result.tokens_used, 1800);
+    }
+
+    #[tokio::test]
+    async fn test_dag_skips_dependents_of_failed_agents() {
+        let orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+
+        // a fails, b depends on a, c on b, d on nothing
+        let mut graph = phase("a");
+        for (id, deps) in [("b", vec!["a"]), ("c", vec!["b"]), ("d", vec![])] {
+            graph.agents.push(AgentSpec {
+                id: id.to_string(),
+                dependencies: deps.into_iter().map(str::to_string).collect(),
+                ..graph.agents[0].clone()
+            });
+        }
+        let plan = ExecutionPlan {
+            phases: vec![graph],
+            scheduling: Scheduling::Dag,
+        };
+        let mut agents: Vec<Box<dyn Agent>> = vec![Box::new(FailingAgent {
+            id: "a".to_string(),
+        })];
+        for id in ["b", "c", "d"] {
+            agents.push(Box::new(StubAgent {
+                id: id.to_string(),
+                capability: AgentCapability::CodeWriting,
+            }));
+        }
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.agents_executed, 2);
+        assert_eq!(result.tokens_used, 600);
+        assert_eq!(
+            result.errors,
+            vec![
+                "Stub failed: backend unavailable",
+                "Stub skipped, a dependency failed",
+                "Stub skipped, a dependency failed",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dag_reports_unresolvable_dependencies() {
+        let orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+
+        let mut graph = phase("a");
+        graph.agents[0].dependencies = vec!["missing".to_string()];
+        let plan = ExecutionPlan {
+            phases: vec![graph],
+            scheduling: Scheduling::Dag,
+        };
+        let agents: Vec<Box<dyn Agent>> = vec![Box::new(StubAgent {
+            id: "a".to_string(),
+            capability: AgentCapability::CodeWriting,
+        })];
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert_eq!(result.agents_executed, 0);
+        assert_eq!(
+            result.errors,
+            vec!["Not run, their dependencies can never finish: a"]
+        );
+    }
+
+    /// Approves everything and skips the agents it is told to
+    struct Reviewer {
+        skip: &'static str,
+    }
+
+    #[async_trait]
+    impl super::super::ExecutionObserver for Reviewer {
+        fn on_event(&self, _event: RunEvent) {}
+
+        async fn approve(&self, _prompt: &str) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn review_prompt(&self, prompt: AgentPrompt) -> Result<PromptDecision> {
+            Ok(if prompt.agent_id == self.skip {
+                PromptDecision::Skip
+            } else {
+                PromptDecision::Run(prompt)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interactive_mode_skips_reviewed_agents() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Interactive);
+        orchestrator.set_progress(Progress::new(Arc::new(Reviewer { skip: "b" })));
+
+        let plan = ExecutionPlan {
+            phases: vec![phase("a"), phase("b")],
+            scheduling: Scheduling::Phases,
+        };
+        let agents: Vec<Box<dyn Agent>> = ["a", "b"]
+            .into_iter()
+            .map(|id| {
+                Box::new(StubAgent {
+                    id: id.to_string(),
+                    capability: AgentCapability::CodeWriting,
+                }) as Box<dyn Agent>
+            })
+            .collect();
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.agents_executed, 1);
+        assert_eq!(result.warnings, vec!["Stub skipped by user"]);
+    }
+
+    #[tokio::test]
+    async fn test_pauses_for_rate_limited_agents() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+        orchestrator.set_progress(Progress::new(Arc::new(Reviewer { skip: "" })));
+        orchestrator.set_offer_pause(true);
+
+        let plan = ExecutionPlan {
+            phases: vec![phase("a"), phase("b")],
+            scheduling: Scheduling::Phases,
+        };
+        let agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(RefusedAgent {
+                id: "a".to_string(),
+            }),
+            Box::new(StubAgent {
+                id: "b".to_string(),
+                capability: AgentCapability::CodeWriting,
+            }),
+        ];
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert!(result.agents[0].rate_limited);
+        let blocked = result.blocked.unwrap();
+        assert_eq!(blocked.reason, BlockReason::RateLimited);
+        assert_eq!(agent_ids(&blocked.plan.phases), vec!["a", "b"]);
+        assert_eq!(
+            blocked.system_prompts["a"],
+            "You are Refused. Conventions: use anyhow"
+        );
+        assert_eq!(blocked.errors, result.errors);
+    }
+
+    #[tokio::test]
+    async fn test_dag_pauses_when_budget_is_spent() {
+        let mut orchestrator = Orchestrator::new(Config::default(), AutonomyMode::Trust);
+        orchestrator.set_progress(Progress::new(Arc::new(Reviewer { skip: "" })));
+        orchestrator.set_offer_pause(true);
+        orchestrator.set_token_budget(500);
+
+        let mut graph = phase("a");
+        graph.agents.push(AgentSpec {
+            id: "b".to_string(),
+            dependencies: vec!["a".to_string()],
+            ..graph.agents[0].clone()
+        });
+        let plan = ExecutionPlan {
+            phases: vec![graph],
+            scheduling: Scheduling::Dag,
+        };
+        let agents: Vec<Box<dyn Agent>> = ["a", "b"]
+            .into_iter()
+            .map(|id| {
+                Box::new(StubAgent {
+                    id: id.to_string(),
+                    capability: AgentCapability::CodeWriting,
+                }) as Box<dyn Agent>
+            })
+            .collect();
+
+        let result = orchestrator.execute_plan(&plan, agents).await.unwrap();
+
+        assert_eq!(result.agents_executed, 1);
+        let blocked = result.blocked.unwrap();
+        assert_eq!(blocked.reason, BlockReason::TokenBudget);
+        // b no longer waits for a, which already ran
+        assert_eq!(blocked.plan.phases[0].agents[0].id, "b");
+        assert!(blocked.plan.phases[0].agents[0].dependencies.is_empty());
+        assert!(blocked.errors[0].contains("not starting 1 more agent(s)"));
+    }
+
+    #[test]
+    fn test_execution_result_without_agent_outcomes() {
+        let result: ExecutionResult = serde_json::from_str(
+            r#"{"success":true,"agents_executed":2,"tokens_used":900,"execution_time_secs":1.5,"errors":[],"warnings":[]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(result.tokens_used, 900);
+        assert!(result.agents.is_empty());
+    }
 }
@@ -0,0 +1,97 @@
+use crate::agents::Health;
+
+/// How many agents a parallel phase runs at once. Halves when the backend
+/// rate-limits calls, steps down when calls get slow, and climbs back one
+/// agent at a time towards `master_coder.max_parallel_agents` while calls
+/// stay healthy.
+#[derive(Debug, Clone)]
+pub struct Parallelism {
+    max: usize,
+    current: usize,
+    adaptive: bool,
+    /// Healthy agents finished since the last change
+    healthy: usize,
+}
+
+impl Parallelism {
+    /// Start at `max`; without `adaptive` it stays there
+    pub fn new(max: usize, adaptive: bool) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            current: max,
+            adaptive,
+            healthy: 0,
+        }
+    }
+
+    /// Agents that may run at once right now
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Adjust to how the backend's calls went while an agent ran. Returns the
+    /// new parallelism when it changed.
+    pub fn observe(&mut self, health: Health) -> Option<usize> {
+        if !self.adaptive {
+            return None;
+        }
+
+        let previous = self.current;
+        if health.rate_limited > 0 {
+            self.current = (self.current / 2).max(1);
+            self.healthy = 0;
+        } else if health.slow > 0 {
+            self.current = (self.current - 1).max(1);
+            self.healthy = 0;
+        } else if health.ok > 0 && self.current < self.max {
+            // One step up per round of healthy agents at the current level
+            self.healthy += 1;
+            if self.healthy >= self.current {
+                self.current += 1;
+                self.healthy = 0;
+            }
+        }
+
+        (self.current != previous).then_some(self.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OK: Health = Health {
+        ok: 1,
+        slow: 0,
+        rate_limited: 0,
+    };
+
+    #[test]
+    fn test_backs_off_and_recovers() {
+        let mut parallelism = Parallelism::new(6, true);
+        assert_eq!(parallelism.observe(OK), None);
+
+        let limited = Health {
+            rate_limited: 2,
+            ..OK
+        };
+        assert_eq!(parallelism.observe(limited), Some(3));
+        assert_eq!(parallelism.observe(limited), Some(1));
+        assert_eq!(parallelism.observe(limited), None);
+
+        // Back up: one healthy agent at 1, two at 2, ...
+        assert_eq!(parallelism.observe(OK), Some(2));
+        assert_eq!(parallelism.observe(OK), None);
+        assert_eq!(parallelism.observe(OK), Some(3));
+        let slow = Health { slow: 1, ..OK };
+        assert_eq!(parallelism.observe(slow), Some(2));
+
+        // Agents without calls, e.g. answered from the cache, tell nothing
+        assert_eq!(parallelism.observe(Health::default()), None);
+
+        let mut fixed = Parallelism::new(4, false);
+        assert_eq!(fixed.observe(limited), None);
+        assert_eq!(fixed.current(), 4);
+    }
+}
@@ -1,8 +1,11 @@
-use super::orchestrator::{AgentSpec, ExecutionPhase, ExecutionPlan};
+use super::inventory::WorkspaceInventory;
+use super::orchestrator::{AgentSpec, ExecutionPhase, ExecutionPlan, Scheduling};
 use crate::agents::AgentCapability;
 use crate::config::Config;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskAnalysis {
@@ -14,15 +17,62 @@ pub struct TaskAnalysis {
     pub estimated_time_max: u32,
     pub required_capabilities: Vec<AgentCapability>,
     pub keywords: Vec<String>,
+    /// The project the task was sized by, when it was scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceInventory>,
+}
+
+/// Output the architect returns, so later steps can read the decisions and
+/// the files to touch without parsing prose
+fn architecture_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["summary", "decisions", "files"],
+        "properties": {
+            "summary": {"type": "string"},
+            "decisions": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["decision", "rationale"],
+                    "properties": {
+                        "decision": {"type": "string"},
+                        "rationale": {"type": "string"}
+                    }
+                }
+            },
+            "files": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "change"],
+                    "properties": {
+                        "path": {"type": "string"},
+                        "change": {"enum": ["create", "modify", "delete"]}
+                    }
+                }
+            }
+        }
+    })
 }
 
 pub struct TaskPlanner {
     config: Config,
+    workspace_root: Option<PathBuf>,
 }
 
 impl TaskPlanner {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            workspace_root: None,
+        }
+    }
+
+    /// Scan the project at `root` before analyzing a task, so estimates
+    /// and the agent team go by its files, languages and tests
+    pub fn set_workspace_root(&mut self, root: Option<PathBuf>) {
+        self.workspace_root = root;
     }
 
     /// Analyze a task to understand its requirements
@@ -43,8 +93,14 @@ impl TaskPlanner {
         // Extract keywords
         let keywords = self.extract_keywords(&task_lower);
 
+        let workspace = self.workspace_root.as_deref().and_then(|root| {
+            WorkspaceInventory::scan(root)
+                .map_err(|e| tracing::warn!("Sizing the task without the workspace: {:#}", e))
+                .ok()
+        });
+
         // Estimate resources
-        let estimated_files = self.estimate_files(&task_lower, complexity);
+        let estimated_files = self.estimate_files(&task_lower, complexity, workspace.as_ref());
         let estimated_tokens = self.estimate_tokens(complexity, estimated_files);
         let (time_min, time_max) = self.estimate_time(complexity);
 
@@ -57,6 +113,7 @@ impl TaskPlanner {
             estimated_time_max: time_max,
             required_capabilities: capabilities,
             keywords,
+            workspace,
         })
     }
 
@@ -66,15 +123,30 @@ impl TaskPlanner {
         analysis: &TaskAnalysis,
         max_agents: usize,
     ) -> Result<ExecutionPlan> {
-        let mut phases = Vec::new();
-
         // Determine agent team composition based on capabilities
-        let agent_specs = self.plan_agents(analysis, max_agents);
+        let mut agent_specs = self.plan_agents(analysis, max_agents);
+        self.assign_budget_shares(&mut agent_specs, analysis.complexity);
+
+        // Schedule by dependencies alone, unless they can't all be met;
+        // phases fall back to running such agents anyway
+        let scheduling =
+            Scheduling::from_name(&self.config.master_coder.scheduling).unwrap_or_default();
+        if scheduling == Scheduling::Dag && dependencies_resolve(&agent_specs) {
+            return Ok(ExecutionPlan {
+                phases: vec![ExecutionPhase {
+                    description: "Agents as their dependencies finish".to_string(),
+                    parallel: agent_specs.len() > 1,
+                    agents: agent_specs,
+                }],
+                scheduling,
+            });
+        }
 
         // Group agents into phases
-        phases.extend(self.create_phases(analysis, agent_specs));
-
-        Ok(ExecutionPlan { phases })
+        Ok(ExecutionPlan {
+            phases: self.create_phases(analysis, agent_specs),
+            scheduling: Scheduling::Phases,
+        })
     }
 
     fn estimate_complexity(&self, task: &str) -> u8 {
@@ -239,7 +311,12 @@ impl TaskPlanner {
             .collect()
     }
 
-    fn estimate_files(&self, task: &str, complexity: u8) -> usize {
+    fn estimate_files(
+        &self,
+        task: &str,
+        complexity: u8,
+        workspace: Option<&WorkspaceInventory>,
+    ) -> usize {
         let base = match complexity {
             0..=3 => 1,
             4..=6 => 3,
@@ -256,7 +333,11 @@ impl TaskPlanner {
             1.0
         };
 
-        (base as f32 * multiplier) as usize
+        let estimate = (base as f32 * multiplier) as usize;
+        match workspace {
+            Some(workspace) => workspace.scale_files(estimate, task),
+            None => estimate,
+        }
     }
 
     fn estimate_tokens(&self, complexity: u8, files: usize) -> usize {
@@ -277,6 +358,7 @@ impl TaskPlanner {
 
     fn plan_agents(&self, analysis: &TaskAnalysis, max_agents: usize) -> Vec<AgentSpec> {
         let mut specs = Vec::new();
+        let workspace = analysis.workspace.as_ref();
 
         // Create agents based on required capabilities
         for capability in &analysis.required_capabilities {
@@ -289,6 +371,8 @@ impl TaskPlanner {
                         task: "Design system architecture and create implementation plan"
                             .to_string(),
                         dependencies: vec![],
+                        budget_share: 0.0,
+                        output_schema: Some(architecture_schema()),
                     });
                 }
 
@@ -321,12 +405,21 @@ impl TaskPlanner {
                             id: format!("coder-{}", i),
                             agent_type: agent_name,
                             capability: capability.clone(),
-                            task: format!("Implement code changes{}", suffix),
+                            task: format!(
+                                "Implement code changes{}{}",
+                                suffix,
+                                workspace
+                                    .and_then(WorkspaceInventory::main_language)
+                                    .map(|language| format!(" in {}", language))
+                                    .unwrap_or_default()
+                            ),
                             dependencies: specs
                                 .iter()
                                 .filter(|s| s.capability == AgentCapability::Architecture)
                                 .map(|s| s.id.clone())
                                 .collect(),
+                            budget_share: 0.0,
+                            output_schema: None,
                         });
                     }
                 }
@@ -342,6 +435,8 @@ impl TaskPlanner {
                             .filter(|s| s.capability == AgentCapability::CodeWriting)
                             .map(|s| s.id.clone())
                             .collect(),
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
 
@@ -350,12 +445,25 @@ impl TaskPlanner {
                         id: format!("tester-{}", specs.len()),
                         agent_type: "Test Engineer".to_string(),
                         capability: capability.clone(),
-                        task: "Write comprehensive tests".to_string(),
+                        task: match workspace {
+                            Some(workspace) => match workspace.test_location() {
+                                Some(location) => format!(
+                                    "Write comprehensive tests, alongside the existing ones in {}",
+                                    location
+                                ),
+                                None => "Write comprehensive tests; the project has none yet, \
+                                         so set up its test harness too"
+                                    .to_string(),
+                            },
+                            None => "Write comprehensive tests".to_string(),
+                        },
                         dependencies: specs
                             .iter()
                             .filter(|s| s.capability == AgentCapability::CodeWriting)
                             .map(|s| s.id.clone())
                             .collect(),
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
 
@@ -366,6 +474,8 @@ impl TaskPlanner {
                         capability: capability.clone(),
                         task: "Create comprehensive documentation".to_string(),
                         dependencies: specs.iter().map(|s| s.id.clone()).collect(),
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
 
@@ -376,6 +486,8 @@ impl TaskPlanner {
                         capability: capability.clone(),
                         task: "Plan and execute migration strategy".to_string(),
                         dependencies: vec![],
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
 
@@ -390,6 +502,8 @@ impl TaskPlanner {
                             .filter(|s| s.capability == AgentCapability::CodeWriting)
                             .map(|s| s.id.clone())
                             .collect(),
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
 
@@ -407,6 +521,8 @@ impl TaskPlanner {
                             })
                             .map(|s| s.id.clone())
                             .collect(),
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
 
@@ -421,16 +537,38 @@ impl TaskPlanner {
                             .filter(|s| s.capability == AgentCapability::CodeWriting)
                             .map(|s| s.id.clone())
                             .collect(),
+                        budget_share: 0.0,
+                        output_schema: None,
                     });
                 }
-
-                _ => {}
             }
         }
 
         specs
     }
 
+    /// Split the token budget between agents by capability. Agents that
+    /// write code get relatively more the more complex the task is.
+    fn assign_budget_shares(&self, specs: &mut [AgentSpec], complexity: u8) {
+        let weight = |spec: &AgentSpec| {
+            let base = spec.capability.token_weight();
+            if spec.capability.produces_code() {
+                base * (1.0 + complexity as f64 / 10.0)
+            } else {
+                base
+            }
+        };
+
+        let total: f64 = specs.iter().map(weight).sum();
+        if total <= 0.0 {
+            return;
+        }
+
+        for spec in specs.iter_mut() {
+            spec.budget_share = weight(spec) / total;
+        }
+    }
+
     fn create_phases(
         &self,
         _analysis: &TaskAnalysis,
@@ -549,6 +687,29 @@ impl TaskPlanner {
     }
 }
 
+/// Whether every agent can start at some point: no dependency cycles and
+/// no dependencies on agents outside `specs`
+fn dependencies_resolve(specs: &[AgentSpec]) -> bool {
+    let mut done: Vec<&str> = Vec::new();
+    while done.len() < specs.len() {
+        let ready: Vec<&str> = specs
+            .iter()
+            .filter(|spec| !done.contains(&spec.id.as_str()))
+            .filter(|spec| {
+                spec.dependencies
+                    .iter()
+                    .all(|dep| done.contains(&dep.as_str()))
+            })
+            .map(|spec| spec.id.as_str())
+            .collect();
+        if ready.is_empty() {
+            return false;
+        }
+        done.extend(ready);
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -707,6 +868,7 @@ mod tests {
             estimated_time_max: 10,
             required_capabilities: vec![AgentCapability::CodeWriting],
             keywords: vec![],
+            workspace: None,
         };
 
         let phases = planner.create_phases(&analysis, vec![]);
@@ -727,6 +889,7 @@ mod tests {
             estimated_time_max: 10,
             required_capabilities: vec![AgentCapability::CodeWriting],
             keywords: vec![],
+            workspace: None,
         };
 
         let specs = vec![AgentSpec {
@@ -735,6 +898,8 @@ mod tests {
             capability: AgentCapability::CodeWriting,
             task: "write code".to_string(),
             dependencies: vec![],
+            budget_share: 0.0,
+            output_schema: None,
         }];
 
         let phases = planner.create_phases(&analysis, specs);
@@ -745,6 +910,34 @@ mod tests {
         assert_eq!(phases[0].agents[0].id, "agent-1");
     }
 
+    #[test]
+    fn test_assign_budget_shares() {
+        let planner = create_test_planner();
+        let spec = |id: &str, capability: AgentCapability| AgentSpec {
+            id: id.to_string(),
+            agent_type: id.to_string(),
+            capability,
+            task: String::new(),
+            dependencies: vec![],
+            budget_share: 0.0,
+            output_schema: None,
+        };
+        let mut specs = vec![
+            spec("coder", AgentCapability::CodeWriting),
+            spec("reviewer", AgentCapability::Review),
+        ];
+
+        planner.assign_budget_shares(&mut specs, 0);
+        let total: f64 = specs.iter().map(|s| s.budget_share).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!((specs[0].budget_share - 2.0 / 2.8).abs() < 1e-9);
+
+        // A more complex task shifts budget towards the code writer
+        let simple_share = specs[0].budget_share;
+        planner.assign_budget_shares(&mut specs, 10);
+        assert!(specs[0].budget_share > simple_share);
+    }
+
     #[test]
     fn test_create_phases_linear_chain() {
         let planner = create_test_planner();
@@ -757,6 +950,7 @@ mod tests {
             estimated_time_max: 20,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // A -> B -> C (linear dependency chain)
@@ -767,6 +961,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "task C".to_string(),
                 dependencies: vec!["B".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "A".to_string(),
@@ -774,6 +970,8 @@ mod tests {
                 capability: AgentCapability::Architecture,
                 task: "task A".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "B".to_string(),
@@ -781,6 +979,8 @@ mod tests {
                 capability: AgentCapability::Testing,
                 task: "task B".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
         ];
 
@@ -808,6 +1008,7 @@ mod tests {
             estimated_time_max: 30,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Diamond: A -> B, A -> C, B -> D, C -> D
@@ -818,6 +1019,8 @@ mod tests {
                 capability: AgentCapability::Architecture,
                 task: "design".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "B".to_string(),
@@ -825,6 +1028,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "implement feature 1".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "C".to_string(),
@@ -832,6 +1037,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "implement feature 2".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "D".to_string(),
@@ -839,6 +1046,8 @@ mod tests {
                 capability: AgentCapability::Testing,
                 task: "test both features".to_string(),
                 dependencies: vec!["B".to_string(), "C".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
         ];
 
@@ -869,6 +1078,7 @@ mod tests {
             estimated_time_max: 20,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Four independent agents with no dependencies
@@ -879,6 +1089,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "task A".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "B".to_string(),
@@ -886,6 +1098,8 @@ mod tests {
                 capability: AgentCapability::Testing,
                 task: "task B".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "C".to_string(),
@@ -893,6 +1107,8 @@ mod tests {
                 capability: AgentCapability::Documentation,
                 task: "task C".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "D".to_string(),
@@ -900,6 +1116,8 @@ mod tests {
                 capability: AgentCapability::Security,
                 task: "task D".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
         ];
 
@@ -924,6 +1142,7 @@ mod tests {
             estimated_time_max: 20,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Circular dependency: A depends on B, B depends on A
@@ -934,6 +1153,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "task A".to_string(),
                 dependencies: vec!["B".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "B".to_string(),
@@ -941,6 +1162,8 @@ mod tests {
                 capability: AgentCapability::Testing,
                 task: "task B".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
         ];
 
@@ -967,6 +1190,7 @@ mod tests {
             estimated_time_max: 10,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Agent that depends on itself
@@ -976,6 +1200,8 @@ mod tests {
             capability: AgentCapability::CodeWriting,
             task: "task A".to_string(),
             dependencies: vec!["A".to_string()],
+            budget_share: 0.0,
+            output_schema: None,
         }];
 
         let phases = planner.create_phases(&analysis, specs);
@@ -998,6 +1224,7 @@ mod tests {
             estimated_time_max: 15,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Agent A depends on non-existent agent "X"
@@ -1007,6 +1234,8 @@ mod tests {
             capability: AgentCapability::CodeWriting,
             task: "task A".to_string(),
             dependencies: vec!["X".to_string()], // X doesn't exist
+            budget_share: 0.0,
+            output_schema: None,
         }];
 
         let phases = planner.create_phases(&analysis, specs);
@@ -1029,6 +1258,7 @@ mod tests {
             estimated_time_max: 20,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Sequential chain should have parallel=false
@@ -1039,6 +1269,8 @@ mod tests {
                 capability: AgentCapability::Architecture,
                 task: "design".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "B".to_string(),
@@ -1046,6 +1278,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "implement".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
         ];
 
@@ -1070,6 +1304,7 @@ mod tests {
             estimated_time_max: 30,
             required_capabilities: vec![],
             keywords: vec![],
+            workspace: None,
         };
 
         // Complex graph with multiple paths
@@ -1080,6 +1315,8 @@ mod tests {
                 capability: AgentCapability::Architecture,
                 task: "a".to_string(),
                 dependencies: vec![],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "B".to_string(),
@@ -1087,6 +1324,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "b".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "C".to_string(),
@@ -1094,6 +1333,8 @@ mod tests {
                 capability: AgentCapability::CodeWriting,
                 task: "c".to_string(),
                 dependencies: vec!["A".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "D".to_string(),
@@ -1101,6 +1342,8 @@ mod tests {
                 capability: AgentCapability::Testing,
                 task: "d".to_string(),
                 dependencies: vec!["B".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
             AgentSpec {
                 id: "E".to_string(),
@@ -1108,6 +1351,8 @@ mod tests {
                 capability: AgentCapability::Documentation,
                 task: "e".to_string(),
                 dependencies: vec!["C".to_string()],
+                budget_share: 0.0,
+                output_schema: None,
             },
         ];
 
@@ -1127,4 +1372,42 @@ mod tests {
         all_ids.dedup();
         assert_eq!(all_ids.len(), original_count);
     }
+
+    #[tokio::test]
+    async fn test_create_plan_scheduling() {
+        let mut planner = create_test_planner();
+        let analysis = planner
+            .analyze_task("implement a parser and write tests for it")
+            .await
+            .unwrap();
+
+        let plan = planner.create_plan(&analysis, 5).await.unwrap();
+        assert_eq!(plan.scheduling, Scheduling::Dag);
+        assert_eq!(plan.phases.len(), 1);
+
+        planner.config.master_coder.scheduling = "phases".to_string();
+        let phased = planner.create_plan(&analysis, 5).await.unwrap();
+        assert_eq!(phased.scheduling, Scheduling::Phases);
+        assert_eq!(phased.total_agents(), plan.total_agents());
+    }
+
+    #[test]
+    fn test_dependencies_resolve() {
+        let spec = |id: &str, deps: &[&str]| AgentSpec {
+            id: id.to_string(),
+            agent_type: "code".to_string(),
+            capability: AgentCapability::CodeWriting,
+            task: "task".to_string(),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            budget_share: 0.0,
+            output_schema: None,
+        };
+
+        assert!(dependencies_resolve(&[spec("A", &[]), spec("B", &["A"])]));
+        assert!(!dependencies_resolve(&[
+            spec("A", &["B"]),
+            spec("B", &["A"])
+        ]));
+        assert!(!dependencies_resolve(&[spec("A", &["X"])]));
+    }
 }
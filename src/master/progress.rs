@@ -0,0 +1,329 @@
+use super::orchestrator::{ExecutionPlan, ExecutionResult};
+use super::planner::TaskAnalysis;
+use super::run_log::RunLog;
+use super::terminal::TerminalObserver;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Something that happened during a run, as passed to an
+/// [`ExecutionObserver`] and recorded in the run log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RunEvent {
+    Started {
+        run_id: String,
+        task: String,
+        mode: String,
+        token_budget: usize,
+    },
+    Analyzed(TaskAnalysis),
+    Planned(ExecutionPlan),
+    /// The plan was approved and its agents are about to run
+    AgentsCreated {
+        count: usize,
+    },
+    PhaseStarted {
+        index: usize,
+        total: usize,
+        description: String,
+        #[serde(default)]
+        parallel: bool,
+    },
+    PhaseSkipped {
+        index: usize,
+    },
+    AgentStarted {
+        agent_id: String,
+        agent_type: String,
+    },
+    AgentFinished {
+        agent_id: String,
+        tokens_used: usize,
+        error: Option<String>,
+    },
+    /// A free-form line for the run's log
+    Message(String),
+    /// The run is waiting for a yes/no answer
+    #[serde(skip)]
+    ApprovalNeeded {
+        prompt: String,
+        reply: oneshot::Sender<bool>,
+    },
+    /// The run waits for an agent's prompt to be run as is, edited or
+    /// skipped (interactive mode)
+    #[serde(skip)]
+    PromptReviewNeeded {
+        prompt: AgentPrompt,
+        reply: oneshot::Sender<PromptDecision>,
+    },
+    Finished(ExecutionResult),
+    /// The plan was not approved
+    Cancelled,
+    Failed(String),
+}
+
+/// What an agent is about to run with, for review in interactive mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentPrompt {
+    pub agent_id: String,
+    pub agent_type: String,
+    /// System prompt, including the project knowledge given to the agent
+    pub system_prompt: String,
+    pub task: String,
+}
+
+/// Answer to an [`AgentPrompt`] review
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptDecision {
+    /// Run the agent with this prompt, edited or not
+    Run(AgentPrompt),
+    /// Don't run the agent
+    Skip,
+}
+
+const SYSTEM_MARKER: &str = "=== System prompt ===";
+const TASK_MARKER: &str = "=== Task ===";
+
+impl AgentPrompt {
+    /// The prompt as text to edit in an editor
+    pub fn to_editable(&self) -> String {
+        format!(
+            "Prompt of {}. Edit the text under the markers, keeping the marker lines.\n\n\
+            {}\n{}\n{}\n{}\n",
+            self.agent_type,
+            SYSTEM_MARKER,
+            self.system_prompt.trim_end(),
+            TASK_MARKER,
+            self.task.trim_end()
+        )
+    }
+
+    /// This prompt with the system prompt and task from `text`, as written
+    /// by [`Self::to_editable`] and edited
+    pub fn with_edits(&self, text: &str) -> Result<Self> {
+        let (_, rest) = text
+            .split_once(&format!("{}\n", SYSTEM_MARKER))
+            .with_context(|| format!("The '{}' line is missing", SYSTEM_MARKER))?;
+        let (system_prompt, task) = rest
+            .split_once(&format!("\n{}\n", TASK_MARKER))
+            .with_context(|| format!("The '{}' line is missing", TASK_MARKER))?;
+        if task.trim().is_empty() {
+            anyhow::bail!("The task is empty; skip the agent instead");
+        }
+
+        Ok(Self {
+            system_prompt: system_prompt.trim_end().to_string(),
+            task: task.trim_end().to_string(),
+            ..self.clone()
+        })
+    }
+}
+
+/// Receives everything that happens during a run and answers its approval
+/// questions. The CLI prints to the terminal ([`TerminalObserver`]), the TUI
+/// receives events over a channel ([`Progress::channel`]), and embedders can
+/// plug in their own.
+///
+/// [`TerminalObserver`]: super::terminal::TerminalObserver
+#[async_trait]
+pub trait ExecutionObserver: Send + Sync {
+    /// Called for every event of the run, in order
+    fn on_event(&self, event: RunEvent);
+
+    /// Answer a yes/no question the run is waiting on
+    async fn approve(&self, prompt: &str) -> Result<bool>;
+
+    /// Run, edit or skip an agent's prompt before it runs, in interactive
+    /// mode. Observers that can't ask run it as is.
+    async fn review_prompt(&self, prompt: AgentPrompt) -> Result<PromptDecision> {
+        Ok(PromptDecision::Run(prompt))
+    }
+}
+
+/// Sends events to a channel; approvals are answered through the reply
+/// sender of [`RunEvent::ApprovalNeeded`]
+struct ChannelObserver {
+    tx: mpsc::UnboundedSender<RunEvent>,
+}
+
+#[async_trait]
+impl ExecutionObserver for ChannelObserver {
+    fn on_event(&self, event: RunEvent) {
+        // Nobody listening is fine; the run carries on
+        let _ = self.tx.send(event);
+    }
+
+    /// A frontend that goes away without answering counts as a no
+    async fn approve(&self, prompt: &str) -> Result<bool> {
+        let (reply, answer) = oneshot::channel();
+        let _ = self.tx.send(RunEvent::ApprovalNeeded {
+            prompt: prompt.to_string(),
+            reply,
+        });
+        Ok(answer.await.unwrap_or(false))
+    }
+
+    /// A frontend that goes away without answering skips the agent
+    async fn review_prompt(&self, prompt: AgentPrompt) -> Result<PromptDecision> {
+        let (reply, answer) = oneshot::channel();
+        let _ = self.tx.send(RunEvent::PromptReviewNeeded { prompt, reply });
+        Ok(answer.await.unwrap_or(PromptDecision::Skip))
+    }
+}
+
+/// Where a run reports progress and asks for approval: an
+/// [`ExecutionObserver`], plus the run log when one is set. The default
+/// observer prints to the terminal and prompts there.
+#[derive(Clone)]
+pub struct Progress {
+    observer: Arc<dyn ExecutionObserver>,
+    log: Option<Arc<RunLog>>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new(Arc::new(TerminalObserver::default()))
+    }
+}
+
+impl Progress {
+    pub fn new(observer: Arc<dyn ExecutionObserver>) -> Self {
+        Self {
+            observer,
+            log: None,
+        }
+    }
+
+    /// Create a reporter that sends every event over a channel, and the
+    /// receiving end of its events
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<RunEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self::new(Arc::new(ChannelObserver { tx })), rx)
+    }
+
+    /// Also record events to `log`
+    pub fn with_log(mut self, log: Arc<RunLog>) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    pub fn run_id(&self) -> Option<&str> {
+        self.log.as_deref().map(RunLog::id)
+    }
+
+    /// Record an event and pass it to the observer
+    pub fn send(&self, event: RunEvent) {
+        if let Some(log) = &self.log {
+            log.append(&event);
+        }
+        self.observer.on_event(event);
+    }
+
+    /// Ask the observer a yes/no question
+    pub async fn approve(&self, prompt: &str) -> Result<bool> {
+        if let Some(log) = &self.log {
+            log.append(&RunEvent::Message(format!(
+                "Waiting for approval: {}",
+                prompt
+            )));
+        }
+
+        let approved = self.observer.approve(prompt).await?;
+
+        if let Some(log) = &self.log {
+            let answer = if approved { "Approved" } else { "Declined" };
+            log.append(&RunEvent::Message(answer.to_string()));
+        }
+
+        Ok(approved)
+    }
+
+    /// Have the observer review an agent's prompt before it runs
+    pub async fn review_prompt(&self, prompt: AgentPrompt) -> Result<PromptDecision> {
+        if let Some(log) = &self.log {
+            log.append(&RunEvent::Message(format!(
+                "Waiting for review of the prompt of {}",
+                prompt.agent_type
+            )));
+        }
+
+        let original = prompt.clone();
+        let decision = self.observer.review_prompt(prompt).await?;
+
+        if let Some(log) = &self.log {
+            let answer = match &decision {
+                PromptDecision::Run(prompt) if *prompt == original => "Running the prompt as is",
+                PromptDecision::Run(_) => "Running the edited prompt",
+                PromptDecision::Skip => "Skipped",
+            };
+            log.append(&RunEvent::Message(answer.to_string()));
+        }
+
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records events and declines every approval
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ExecutionObserver for Recorder {
+        fn on_event(&self, event: RunEvent) {
+            let name = serde_json::to_value(&event).unwrap()["type"].to_string();
+            self.events.lock().unwrap().push(name);
+        }
+
+        async fn approve(&self, _prompt: &str) -> Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_edit_prompt() {
+        let prompt = AgentPrompt {
+            agent_id: "coder".to_string(),
+            agent_type: "Code Writer".to_string(),
+            system_prompt: "You are a coder.\n\n## Conventions\n- anyhow".to_string(),
+            task: "Add a flag".to_string(),
+        };
+        assert_eq!(prompt.with_edits(&prompt.to_editable()).unwrap(), prompt);
+
+        let edited = prompt
+            .to_editable()
+            .replace("Add a flag", "Add a --dry-run flag");
+        assert_eq!(
+            prompt.with_edits(&edited).unwrap().task,
+            "Add a --dry-run flag"
+        );
+
+        let no_task = prompt.to_editable().replace("Add a flag", "");
+        assert!(prompt.with_edits(&no_task).is_err());
+        assert!(prompt.with_edits("just some text").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_observer() {
+        let recorder = Arc::new(Recorder::default());
+        let progress = Progress::new(recorder.clone());
+
+        progress.send(RunEvent::PhaseSkipped { index: 0 });
+        progress.send(RunEvent::Cancelled);
+        assert!(!progress.approve("Proceed?").await.unwrap());
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec!["\"phase_skipped\"", "\"cancelled\""]
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use super::orchestrator::{AgentOutcome, ExecutionPlan, ExecutionResult};
+use super::{MasterCoder, RunEvent};
+use crate::agents::Agent;
+use crate::error::ClaudeHelperError;
+use crate::project::Project;
+use crate::style;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+/// How long a rate limited run waits when the usage window is unknown
+const RATE_LIMIT_WAIT: chrono::Duration = chrono::Duration::minutes(1);
+
+/// How long a run out of tokens waits when the usage window is unknown:
+/// a whole 5-hour window
+const BUDGET_WAIT: chrono::Duration = chrono::Duration::hours(5);
+
+/// Why a run stopped starting agents before all of them ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockReason {
+    /// The run's token budget was spent
+    TokenBudget,
+    /// The backend refused calls for rate limiting or overload
+    RateLimited,
+}
+
+impl BlockReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TokenBudget => "token_budget",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "token_budget" => Some(Self::TokenBudget),
+            "rate_limited" => Some(Self::RateLimited),
+            _ => None,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::TokenBudget => "token budget spent",
+            Self::RateLimited => "rate limited",
+        }
+    }
+}
+
+/// The agents of a run that the token budget or rate limiting stopped, as
+/// the orchestrator hands them over for the task queue
+#[derive(Debug, Clone)]
+pub struct Blocked {
+    pub reason: BlockReason,
+    /// What is left of the plan
+    pub plan: ExecutionPlan,
+    /// System prompts the agents were created with, project knowledge
+    /// included, by agent ID
+    pub system_prompts: HashMap<String, String>,
+    /// Errors the block added to the run's result, which no longer apply
+    /// once the agents have run
+    pub errors: Vec<String>,
+}
+
+impl Blocked {
+    /// The agents `ids` of `plan`, with the system prompts of the `agents`
+    /// not started and the `refused` ones that ran into rate limiting
+    pub(super) fn new(
+        reason: BlockReason,
+        plan: &ExecutionPlan,
+        ids: &[String],
+        agents: &[Box<dyn Agent>],
+        refused: &[AgentOutcome],
+        errors: Vec<String>,
+    ) -> Self {
+        let mut system_prompts: HashMap<String, String> = agents
+            .iter()
+            .filter(|agent| ids.iter().any(|id| id == agent.id()))
+            .filter_map(|agent| Some((agent.id().to_string(), agent.system_prompt()?.to_string())))
+            .collect();
+        for outcome in refused {
+            if let Some(prompt) = &outcome.system_prompt {
+                system_prompts.insert(outcome.agent_id.clone(), prompt.clone());
+            }
+        }
+
+        Self {
+            reason,
+            plan: remaining(plan, ids),
+            system_prompts,
+            errors,
+        }
+    }
+}
+
+/// A paused run waiting in the task queue
+#[derive(Debug, Clone)]
+pub struct QueuedRun {
+    pub id: i64,
+    pub task: String,
+    pub reason: BlockReason,
+    /// The agents still to run
+    pub plan: ExecutionPlan,
+    /// System prompts the agents were created with, by agent ID
+    pub system_prompts: HashMap<String, String>,
+    /// When the usage window the run waits for resets
+    pub resume_at: DateTime<Utc>,
+    /// Root directory of the project the run was started in
+    pub project: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The agents `ids` of `plan` in their phases, leaving out empty phases and
+/// dependencies on agents that already ran
+fn remaining(plan: &ExecutionPlan, ids: &[String]) -> ExecutionPlan {
+    let left: HashSet<&str> = ids.iter().map(String::as_str).collect();
+    let phases = plan
+        .phases
+        .iter()
+        .filter_map(|phase| {
+            let agents: Vec<_> = phase
+                .agents
+                .iter()
+                .filter(|spec| left.contains(spec.id.as_str()))
+                .map(|spec| {
+                    let mut spec = spec.clone();
+                    spec.dependencies.retain(|dep| left.contains(dep.as_str()));
+                    spec
+                })
+                .collect();
+            (!agents.is_empty()).then(|| super::orchestrator::ExecutionPhase {
+                agents,
+                ..phase.clone()
+            })
+        })
+        .collect();
+
+    ExecutionPlan {
+        phases,
+        scheduling: plan.scheduling,
+    }
+}
+
+impl MasterCoder {
+    /// While the orchestrator reports agents the token budget or rate
+    /// limiting stopped (and the user chose to pause for them), queue them,
+    /// wait for the usage window to reset and run them. Returns the tokens
+    /// of the last stretch, the part the token budget applies to.
+    pub(super) async fn resume_blocked(
+        &mut self,
+        task: &str,
+        result: &mut ExecutionResult,
+    ) -> crate::error::Result<usize> {
+        let mut budgeted = result.tokens_used;
+        while let Some(blocked) = result.blocked.take() {
+            result.errors.retain(|e| !blocked.errors.contains(e));
+            result.success = result.errors.is_empty();
+
+            let resume_at = self.resume_time(blocked.reason).await;
+            let project = Project::current().ok();
+            let id = self
+                .db
+                .queue_run(task, &blocked, resume_at, project.as_ref())
+                .await
+                .map_err(ClaudeHelperError::db)?;
+            self.progress.send(RunEvent::Message(format!(
+                "{} Paused ({}): {} agent(s) queued as #{}, resuming at {}. \
+                If this stops waiting, `claude-helper tasks resume {}` runs them later.",
+                style::icon("⏸"),
+                blocked.reason.description(),
+                blocked.plan.total_agents(),
+                id,
+                resume_at.with_timezone(&Local).format("%H:%M"),
+                id
+            )));
+            wait_until(resume_at).await;
+
+            let resumed = self
+                .run_queued(task, &blocked.plan, &blocked.system_prompts)
+                .await?;
+            self.db
+                .remove_queued_run(id)
+                .await
+                .map_err(ClaudeHelperError::db)?;
+            budgeted = resumed.tokens_used;
+            result.merge(resumed);
+        }
+        Ok(budgeted)
+    }
+
+    /// Run a run from the task queue: `id`, or the one to resume first.
+    /// Waits for its resume time, then runs its agents with the prompts
+    /// they were created with.
+    pub async fn resume(&mut self, id: Option<i64>) -> crate::error::Result<ExecutionResult> {
+        self.config.network.ensure_online("Resuming a task")?;
+
+        let queued = self
+            .db
+            .get_queued_runs()
+            .await
+            .map_err(ClaudeHelperError::db)?;
+        let Some(run) = queued
+            .into_iter()
+            .find(|run| id.is_none_or(|id| run.id == id))
+        else {
+            return Err(match id {
+                Some(id) => anyhow::anyhow!("No queued task #{}", id).into(),
+                None => anyhow::anyhow!("The task queue is empty").into(),
+            });
+        };
+
+        self.start_run_log(&run.task);
+        let result = self.resume_run(&run).await;
+        match &result {
+            Ok(result) => self.progress.send(RunEvent::Finished(result.clone())),
+            Err(e) => self.progress.send(RunEvent::Failed(format!("{:#}", e))),
+        }
+        result
+    }
+
+    async fn resume_run(&mut self, run: &QueuedRun) -> crate::error::Result<ExecutionResult> {
+        if run.resume_at > Utc::now() {
+            self.progress.send(RunEvent::Message(format!(
+                "Waiting until {} for the usage window to reset...",
+                run.resume_at.with_timezone(&Local).format("%H:%M")
+            )));
+            wait_until(run.resume_at).await;
+        }
+
+        let analysis = self
+            .planner
+            .analyze_task(&run.task)
+            .await
+            .map_err(ClaudeHelperError::plan)?;
+        self.progress.send(RunEvent::Planned(run.plan.clone()));
+        let mut result = self
+            .run_queued(&run.task, &run.plan, &run.system_prompts)
+            .await?;
+        self.db
+            .remove_queued_run(run.id)
+            .await
+            .map_err(ClaudeHelperError::db)?;
+        let budgeted = self.resume_blocked(&run.task, &mut result).await?;
+
+        self.write_files(&result).await?;
+        self.save_execution(&run.task, &analysis, &run.plan, &result)
+            .await
+            .map_err(ClaudeHelperError::db)?;
+
+        if budgeted > self.token_budget {
+            return Err(ClaudeHelperError::BudgetExceeded {
+                used: budgeted,
+                budget: self.token_budget,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Run the agents of `plan` with the system prompts they were first
+    /// created with
+    async fn run_queued(
+        &self,
+        task: &str,
+        plan: &ExecutionPlan,
+        system_prompts: &HashMap<String, String>,
+    ) -> crate::error::Result<ExecutionResult> {
+        let mut agents = self.factory.create_agents(plan, task).await?;
+        for agent in &mut agents {
+            if let Some(prompt) = system_prompts.get(agent.id()) {
+                agent.set_system_prompt(prompt.clone());
+            }
+        }
+        self.progress.send(RunEvent::AgentsCreated {
+            count: agents.len(),
+        });
+
+        Ok(self.orchestrator.execute_plan(plan, agents).await?)
+    }
+
+    /// When the usage window resets, by the live usage; without it, after a
+    /// minute for rate limiting or a whole window for a spent budget
+    async fn resume_time(&self, reason: BlockReason) -> DateTime<Utc> {
+        let now = Utc::now();
+        match self.live_usage().await {
+            Ok(usage) if usage.five_hour_minutes_remaining > 0 => {
+                now + chrono::Duration::minutes(usage.five_hour_minutes_remaining as i64 + 1)
+            }
+            other => {
+                if let Err(e) = other {
+                    debug!("Usage window unknown: {:#}", e);
+                }
+                now + match reason {
+                    BlockReason::RateLimited => RATE_LIMIT_WAIT,
+                    BlockReason::TokenBudget => BUDGET_WAIT,
+                }
+            }
+        }
+    }
+}
+
+async fn wait_until(at: DateTime<Utc>) {
+    if let Ok(wait) = (at - Utc::now()).to_std() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentCapability;
+    use crate::master::orchestrator::{AgentSpec, ExecutionPhase, Scheduling};
+
+    fn spec(id: &str, dependencies: &[&str]) -> AgentSpec {
+        AgentSpec {
+            id: id.to_string(),
+            agent_type: "Stub".to_string(),
+            capability: AgentCapability::CodeWriting,
+            task: "work".to_string(),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            budget_share: 0.0,
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn test_remaining() {
+        let plan = ExecutionPlan {
+            phases: vec![
+                ExecutionPhase {
+                    description: "Design".to_string(),
+                    agents: vec![spec("a", &[])],
+                    parallel: false,
+                },
+                ExecutionPhase {
+                    description: "Build".to_string(),
+                    agents: vec![spec("b", &["a"]), spec("c", &["a", "b"])],
+                    parallel: true,
+                },
+            ],
+            scheduling: Scheduling::Dag,
+        };
+
+        let left = remaining(&plan, &["b".to_string(), "c".to_string()]);
+        assert_eq!(left.phases.len(), 1);
+        assert_eq!(left.phases[0].description, "Build");
+        assert!(left.phases[0].agents[0].dependencies.is_empty());
+        assert_eq!(left.phases[0].agents[1].dependencies, vec!["b"]);
+        assert_eq!(left.scheduling, Scheduling::Dag);
+    }
+
+    #[test]
+    fn test_block_reason_names() {
+        for reason in [BlockReason::TokenBudget, BlockReason::RateLimited] {
+            assert_eq!(BlockReason::from_name(reason.as_str()), Some(reason));
+        }
+        assert_eq!(BlockReason::from_name("tired"), None);
+    }
+}
@@ -0,0 +1,194 @@
+use super::run_log::check_run_id;
+use crate::config::{Config, RetentionConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// What a cleanup removed, or would remove in a dry run
+#[derive(Debug, Default, PartialEq)]
+pub struct Cleaned {
+    pub logs: usize,
+    pub artifact_dirs: usize,
+    pub bytes: u64,
+}
+
+/// Cutoffs from `[retention]`: runs last touched before them go
+pub(super) fn cutoffs(config: &RetentionConfig) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let cutoff = |days: u64| (days > 0).then(|| Utc::now() - Duration::days(days as i64));
+    (cutoff(config.log_days), cutoff(config.artifact_days))
+}
+
+/// Remove the runs `[retention]` no longer keeps; called when a run starts.
+/// Failing to clean up never fails the run.
+pub fn enforce(config: &RetentionConfig) {
+    if !config.auto_clean {
+        return;
+    }
+    let (logs_before, artifacts_before) = cutoffs(config);
+    let cleaned =
+        Config::runs_dir().and_then(|dir| clean_dir(&dir, logs_before, artifacts_before, false));
+    match cleaned {
+        Ok(cleaned) if cleaned != Cleaned::default() => debug!(
+            "Removed {} run logs and {} artifact directories ({} bytes)",
+            cleaned.logs, cleaned.artifact_dirs, cleaned.bytes
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to clean up old runs: {:#}", e),
+    }
+}
+
+/// Remove runs last touched before `older_than` (logs and artifacts
+/// alike), or by the `[retention]` settings without it
+pub fn clean_runs(
+    config: &RetentionConfig,
+    older_than: Option<DateTime<Utc>>,
+    dry_run: bool,
+) -> Result<Cleaned> {
+    let (logs_before, artifacts_before) = match older_than {
+        Some(cutoff) => (Some(cutoff), Some(cutoff)),
+        None => cutoffs(config),
+    };
+    clean_dir(&Config::runs_dir()?, logs_before, artifacts_before, dry_run)
+}
+
+/// A run's age is that of its log, which every event touches, or of its
+/// artifact directory when the log is gone
+pub(super) fn clean_dir(
+    runs_dir: &Path,
+    logs_before: Option<DateTime<Utc>>,
+    artifacts_before: Option<DateTime<Utc>>,
+    dry_run: bool,
+) -> Result<Cleaned> {
+    let mut cleaned = Cleaned::default();
+    let entries = match fs::read_dir(runs_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(cleaned),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", runs_dir)),
+    };
+
+    let mut ids: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            Some(match name.strip_suffix(".jsonl") {
+                Some(id) => id.to_string(),
+                None => name,
+            })
+        })
+        .filter(|id| check_run_id(id).is_ok())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    for id in ids {
+        let log = runs_dir.join(format!("{}.jsonl", id));
+        let artifacts = runs_dir.join(&id);
+        let Some(touched) = modified(&log).or_else(|| modified(&artifacts)) else {
+            continue;
+        };
+        let expired = |cutoff: Option<DateTime<Utc>>| cutoff.is_some_and(|cutoff| touched < cutoff);
+
+        if artifacts.is_dir() && (expired(artifacts_before) || expired(logs_before)) {
+            cleaned.bytes += size(&artifacts);
+            cleaned.artifact_dirs += 1;
+            if !dry_run {
+                fs::remove_dir_all(&artifacts)
+                    .with_context(|| format!("Failed to remove {:?}", artifacts))?;
+            }
+        }
+        if log.is_file() && expired(logs_before) {
+            cleaned.bytes += size(&log);
+            cleaned.logs += 1;
+            if !dry_run {
+                fs::remove_file(&log).with_context(|| format!("Failed to remove {:?}", log))?;
+            }
+        }
+    }
+    Ok(cleaned)
+}
+
+fn modified(path: &Path) -> Option<DateTime<Utc>> {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+/// Bytes a file or directory takes up
+fn size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::SystemTime;
+
+    fn run(dir: &Path, id: &str, age_days: u64) {
+        let log = dir.join(format!("{}.jsonl", id));
+        fs::write(&log, "{}\n").unwrap();
+        let files = dir.join(id).join("files");
+        fs::create_dir_all(&files).unwrap();
+        fs::write(files.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let touched = SystemTime::now() - std::time::Duration::from_secs(age_days * 24 * 60 * 60);
+        File::options()
+            .write(true)
+            .open(&log)
+            .unwrap()
+            .set_modified(touched)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_clean_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        run(dir.path(), "20250101-120000-00ab", 100);
+        run(dir.path(), "20250301-120000-00ab", 40);
+        run(dir.path(), "20250401-120000-00ab", 1);
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        let (logs_before, artifacts_before) = cutoffs(&RetentionConfig::default());
+        let dry = clean_dir(dir.path(), logs_before, artifacts_before, true).unwrap();
+        assert_eq!(dry.logs, 1);
+        assert_eq!(dry.artifact_dirs, 2);
+        assert!(dry.bytes > 0);
+        assert!(dir.path().join("20250101-120000-00ab").exists());
+
+        assert_eq!(
+            clean_dir(dir.path(), logs_before, artifacts_before, false).unwrap(),
+            dry
+        );
+        assert!(!dir.path().join("20250101-120000-00ab.jsonl").exists());
+        assert!(dir.path().join("20250301-120000-00ab.jsonl").exists());
+        assert!(!dir.path().join("20250301-120000-00ab").exists());
+        assert!(dir
+            .path()
+            .join("20250401-120000-00ab/files/main.rs")
+            .exists());
+        assert!(dir.path().join("notes.txt").exists());
+
+        // 0 keeps runs forever
+        let keep = RetentionConfig {
+            artifact_days: 0,
+            log_days: 0,
+            auto_clean: true,
+        };
+        let (logs_before, artifacts_before) = cutoffs(&keep);
+        assert_eq!(
+            clean_dir(dir.path(), logs_before, artifacts_before, false).unwrap(),
+            Cleaned::default()
+        );
+    }
+}
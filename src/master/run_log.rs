@@ -0,0 +1,357 @@
+use super::progress::RunEvent;
+use crate::config::Config;
+use crate::redact;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// One line of a run log
+#[derive(Serialize)]
+struct EntryRef<'a> {
+    at: DateTime<Utc>,
+    event: &'a RunEvent,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    at: DateTime<Utc>,
+    event: RunEvent,
+}
+
+/// How long a run log may go without a new event before its run counts
+/// as dead; agents are cut off well before this
+const STALE_AFTER: Duration = Duration::from_secs(30 * 60);
+
+/// Recent run logs looked at for a run in progress
+const ACTIVE_CANDIDATES: usize = 5;
+
+/// Append-only JSONL log of a run's events, so another process can follow
+/// the run with `claude-helper tui --attach <run-id>`
+pub struct RunLog {
+    id: String,
+    file: Mutex<File>,
+}
+
+impl RunLog {
+    /// Start a new log with a fresh run ID. Runs started by the same
+    /// process within a second (e.g. `bench`) get a numbered suffix.
+    pub fn create() -> Result<Self> {
+        let base = format!(
+            "{}-{:04x}",
+            Utc::now().format("%Y%m%d-%H%M%S"),
+            std::process::id() & 0xffff
+        );
+        let runs_dir = Config::runs_dir()?;
+
+        let mut attempt = 0;
+        loop {
+            let id = match attempt {
+                0 => base.clone(),
+                n => format!("{}-{}", base, n),
+            };
+            let path = runs_dir.join(format!("{}.jsonl", id));
+
+            match OpenOptions::new().create_new(true).append(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Self {
+                        id,
+                        file: Mutex::new(file),
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create run log {:?}", path))
+                }
+            }
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Record an event. Failures are logged, never fatal to the run.
+    pub fn append(&self, event: &RunEvent) {
+        // Approval requests carry a reply channel and are not recorded
+        if matches!(
+            event,
+            RunEvent::ApprovalNeeded { .. } | RunEvent::PromptReviewNeeded { .. }
+        ) {
+            return;
+        }
+
+        let entry = EntryRef {
+            at: Utc::now(),
+            event,
+        };
+        let result = serde_json::to_string(&entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| {
+                let line = redact::active().redact(&line);
+                let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+                writeln!(file, "{}", line)?;
+                Ok(())
+            });
+
+        if let Err(e) = result {
+            warn!("Failed to write run log {}: {:#}", self.id, e);
+        }
+    }
+}
+
+/// Follows a run log written by another process
+pub struct RunLogReader {
+    id: String,
+    reader: BufReader<File>,
+    partial: String,
+}
+
+impl RunLogReader {
+    pub fn open(id: &str) -> Result<Self> {
+        let path = log_path(id)?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let recent = recent_runs(5)?;
+                if recent.is_empty() {
+                    anyhow::bail!("No run with ID '{}' (no runs recorded yet)", id);
+                }
+                anyhow::bail!(
+                    "No run with ID '{}'. Recent runs: {}",
+                    id,
+                    recent.join(", ")
+                );
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open run log {:?}", path));
+            }
+        };
+
+        Ok(Self::from_file(id, file))
+    }
+
+    fn from_file(id: &str, file: File) -> Self {
+        Self {
+            id: id.to_string(),
+            reader: BufReader::new(file),
+            partial: String::new(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Read events appended since the last call
+    pub fn read_new(&mut self) -> Result<Vec<(DateTime<Utc>, RunEvent)>> {
+        let mut events = Vec::new();
+
+        loop {
+            let read = self.reader.read_line(&mut self.partial)?;
+            // A line without a newline is still being written; finish it next time
+            if read == 0 || !self.partial.ends_with('\n') {
+                break;
+            }
+
+            match serde_json::from_str::<Entry>(self.partial.trim_end()) {
+                Ok(entry) => events.push((entry.at, entry.event)),
+                Err(e) => warn!("Skipping malformed line in run log {}: {}", self.id, e),
+            }
+            self.partial.clear();
+        }
+
+        Ok(events)
+    }
+}
+
+/// Progress of a run, folded from the events of its log
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RunStatus {
+    pub id: String,
+    /// The phase running and how many there are, in phase scheduling
+    pub phase: Option<(usize, usize)>,
+    pub agents: usize,
+    pub agents_finished: usize,
+    pub tokens_used: usize,
+    /// Finished, failed or cancelled
+    pub ended: bool,
+}
+
+impl RunStatus {
+    pub fn from_events<'a>(id: &str, events: impl IntoIterator<Item = &'a RunEvent>) -> Self {
+        let mut status = Self {
+            id: id.to_string(),
+            ..Self::default()
+        };
+        for event in events {
+            match event {
+                RunEvent::AgentsCreated { count } => status.agents += count,
+                RunEvent::PhaseStarted { index, total, .. } => {
+                    status.phase = Some((index + 1, *total))
+                }
+                RunEvent::AgentFinished { tokens_used, .. } => {
+                    status.agents_finished += 1;
+                    status.tokens_used += tokens_used;
+                }
+                RunEvent::Finished(_) | RunEvent::Cancelled | RunEvent::Failed(_) => {
+                    status.ended = true
+                }
+                _ => {}
+            }
+        }
+        status
+    }
+
+    /// Status line segment, e.g. `[MC: phase 2/4, 12k tok]`
+    pub fn segment(&self) -> String {
+        let progress = match self.phase {
+            Some((phase, total)) => format!("phase {}/{}", phase, total),
+            None if self.agents > 0 => format!("{}/{} agents", self.agents_finished, self.agents),
+            None => return "[MC: planning]".to_string(),
+        };
+        let tokens = if self.tokens_used >= 1000 {
+            format!("{}k", self.tokens_used / 1000)
+        } else {
+            self.tokens_used.to_string()
+        };
+        format!("[MC: {}, {} tok]", progress, tokens)
+    }
+}
+
+/// The most recent run still in progress. Logs untouched for
+/// [`STALE_AFTER`] belong to runs that died without ending them.
+pub fn active_run() -> Result<Option<RunStatus>> {
+    let runs_dir = Config::runs_dir()?;
+    for id in recent_runs(ACTIVE_CANDIDATES)? {
+        let modified = fs::metadata(runs_dir.join(format!("{}.jsonl", id)))
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|at| at.elapsed().ok());
+        if modified.is_none_or(|age| age > STALE_AFTER) {
+            continue;
+        }
+
+        let events = RunLogReader::open(&id)?.read_new()?;
+        let status = RunStatus::from_events(&id, events.iter().map(|(_, event)| event));
+        if !status.ended {
+            return Ok(Some(status));
+        }
+    }
+    Ok(None)
+}
+
+/// IDs of the most recent runs, newest first
+pub fn recent_runs(limit: usize) -> Result<Vec<String>> {
+    let mut ids: Vec<String> = fs::read_dir(Config::runs_dir()?)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.strip_suffix(".jsonl").map(str::to_string)
+        })
+        .collect();
+
+    // IDs start with a timestamp, so they sort chronologically
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    ids.truncate(limit);
+    Ok(ids)
+}
+
+fn log_path(id: &str) -> Result<PathBuf> {
+    check_run_id(id)?;
+    Ok(Config::runs_dir()?.join(format!("{}.jsonl", id)))
+}
+
+/// Fail on run IDs that aren't safe to use in a file name
+pub(super) fn check_run_id(id: &str) -> Result<()> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        anyhow::bail!("Invalid run ID '{}'", id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_waits_for_complete_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+        let mut writer = File::create(&path).unwrap();
+        let mut reader = RunLogReader::from_file("run", File::open(&path).unwrap());
+
+        let line = serde_json::to_string(&EntryRef {
+            at: Utc::now(),
+            event: &RunEvent::Message("hello".to_string()),
+        })
+        .unwrap();
+        let (head, tail) = line.split_at(10);
+
+        write!(writer, "{}", head).unwrap();
+        assert!(reader.read_new().unwrap().is_empty());
+
+        writeln!(writer, "{}", tail).unwrap();
+        writeln!(writer, "not json").unwrap();
+        let events = reader.read_new().unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0].1, RunEvent::Message(m) if m == "hello"));
+    }
+
+    #[test]
+    fn test_run_status_segment() {
+        let mut events = vec![RunEvent::Started {
+            run_id: "run".to_string(),
+            task: "Add a flag".to_string(),
+            mode: "trust".to_string(),
+            token_budget: 50_000,
+        }];
+        assert_eq!(
+            RunStatus::from_events("run", &events).segment(),
+            "[MC: planning]"
+        );
+
+        events.push(RunEvent::AgentsCreated { count: 3 });
+        events.push(RunEvent::AgentFinished {
+            agent_id: "a".to_string(),
+            tokens_used: 800,
+            error: None,
+        });
+        assert_eq!(
+            RunStatus::from_events("run", &events).segment(),
+            "[MC: 1/3 agents, 800 tok]"
+        );
+
+        events.push(RunEvent::PhaseStarted {
+            index: 1,
+            total: 4,
+            description: "Build".to_string(),
+            parallel: false,
+        });
+        events.push(RunEvent::AgentFinished {
+            agent_id: "b".to_string(),
+            tokens_used: 11_500,
+            error: None,
+        });
+        let status = RunStatus::from_events("run", &events);
+        assert_eq!(status.segment(), "[MC: phase 2/4, 12k tok]");
+        assert!(!status.ended);
+
+        events.push(RunEvent::Cancelled);
+        assert!(RunStatus::from_events("run", &events).ended);
+    }
+
+    #[test]
+    fn test_rejects_path_like_ids() {
+        assert!(log_path("../config").is_err());
+        assert!(log_path("").is_err());
+    }
+}
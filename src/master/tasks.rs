@@ -0,0 +1,449 @@
+use super::compare::print_comparison;
+use super::estimate::keywords;
+use super::export::{pr_summary, transcript_markdown, ExportFormat};
+use super::format_complexity;
+use crate::analyzer::SessionSummary;
+use crate::db::{estimate_cost, Database, TaskRecord};
+use crate::embeddings::Embedder;
+use crate::project::Project;
+use crate::style::{self, Colorize};
+use anyhow::Result;
+use chrono::Local;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Past tasks searched by `tasks search`
+const SEARCH_HISTORY: usize = 500;
+
+/// Something `tasks search` can find
+#[derive(Clone, Copy)]
+enum Hit {
+    Task(usize),
+    Session(usize),
+}
+
+/// Past Master Coder runs as stored in `task_executions`
+pub struct TaskHistory {
+    db: Arc<Database>,
+}
+
+impl TaskHistory {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    pub async fn show_list(
+        &self,
+        limit: usize,
+        tag: Option<&str>,
+        project: Option<&Project>,
+    ) -> Result<()> {
+        let tasks = self.db.get_recent_tasks(limit, tag, project).await?;
+
+        println!("\n{}", "Recent Tasks".bright_cyan().bold());
+        println!("{}", style::rule(80).bright_cyan());
+
+        if tasks.is_empty() {
+            println!("  No tasks recorded yet");
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            format!(
+                "  {:>5}  {:<16}  {:<6}  {:>8}  {:>7}  {}",
+                "ID", "Date", "Status", "Tokens", "Cost", "Task"
+            )
+            .white()
+            .bold()
+        );
+        for task in tasks {
+            println!(
+                "  {:>5}  {:<16}  {}  {:>8}  {:>7}  {}",
+                task.id,
+                task.timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M"),
+                if task.success {
+                    format!("{:<6}", format!("{} ok", style::ok())).green()
+                } else {
+                    format!("{:<6}", format!("{} fail", style::fail())).red()
+                },
+                task.tokens_used,
+                format!("${:.2}", estimate_cost(task.tokens_used)),
+                task.description
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(50)
+                    .collect::<String>()
+            );
+        }
+
+        println!("\n  Show one with: claude-helper tasks show <ID>");
+
+        Ok(())
+    }
+
+    /// List the paused runs waiting in the task queue
+    pub async fn show_queue(&self) -> Result<()> {
+        let queued = self.db.get_queued_runs().await?;
+
+        println!("\n{}", "Task Queue".bright_cyan().bold());
+        println!("{}", style::rule(80).bright_cyan());
+
+        if queued.is_empty() {
+            println!("  No paused runs");
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            format!(
+                "  {:>5}  {:<16}  {:>6}  {:<18}  {}",
+                "ID", "Resumes", "Agents", "Paused", "Task"
+            )
+            .white()
+            .bold()
+        );
+        for run in queued {
+            println!(
+                "  {:>5}  {:<16}  {:>6}  {:<18}  {}",
+                run.id,
+                run.resume_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                run.plan.total_agents(),
+                run.reason.description(),
+                run.task
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(40)
+                    .collect::<String>()
+            );
+        }
+
+        println!("\n  Resume one now or when due with: claude-helper tasks resume <ID>");
+
+        Ok(())
+    }
+
+    /// Take a paused run out of the task queue without running it
+    pub async fn dequeue(&self, id: i64) -> Result<()> {
+        if !self.db.remove_queued_run(id).await? {
+            anyhow::bail!("No queued task #{}", id);
+        }
+        println!("{} Removed #{} from the task queue", style::ok(), id);
+        Ok(())
+    }
+
+    /// Print the past tasks, and the Claude Code `sessions` given, that match
+    /// `query`: by meaning with an embedder, by shared keywords otherwise
+    pub async fn search(
+        &self,
+        query: &str,
+        embedder: Option<&Embedder>,
+        sessions: &[SessionSummary],
+        limit: usize,
+    ) -> Result<()> {
+        let tasks = self.db.get_task_outcomes(SEARCH_HISTORY).await?;
+
+        let mut candidates: Vec<(Hit, String)> = tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| (Hit::Task(i), task.description.clone()))
+            .collect();
+        candidates.extend(
+            sessions
+                .iter()
+                .enumerate()
+                .map(|(i, session)| (Hit::Session(i), session.data.summary()))
+                .filter(|(_, summary)| !summary.is_empty()),
+        );
+
+        let semantic = match embedder {
+            Some(embedder) => match embedder.rank(&self.db, query, &candidates).await {
+                Ok(ranked) => Some(
+                    ranked
+                        .into_iter()
+                        .filter(|(_, score)| *score >= embedder.min_similarity())
+                        .collect::<Vec<_>>(),
+                ),
+                Err(e) => {
+                    warn!("Searching by keywords, embeddings unavailable: {:#}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let by_meaning = semantic.is_some();
+        let mut hits = semantic.unwrap_or_else(|| keyword_matches(query, &candidates));
+        hits.truncate(limit);
+
+        println!(
+            "\n{} {}",
+            "Search".bright_cyan().bold(),
+            format!(
+                "({})",
+                if by_meaning {
+                    "by meaning"
+                } else {
+                    "by keywords"
+                }
+            )
+            .dimmed()
+        );
+        println!("{}", style::rule(80).bright_cyan());
+        if hits.is_empty() {
+            println!("  Nothing matches '{}'", query);
+            return Ok(());
+        }
+
+        for (hit, score) in hits {
+            let (label, status, text) = match hit {
+                Hit::Task(i) => {
+                    let task = &tasks[i];
+                    let status = if task.success {
+                        style::ok().green()
+                    } else {
+                        style::fail().red()
+                    };
+                    (format!("#{}", task.id), status, task.description.clone())
+                }
+                Hit::Session(i) => {
+                    let session = &sessions[i];
+                    let id: String = session.data.session_id.chars().take(8).collect();
+                    (
+                        format!("{}/{}", session.project, id),
+                        style::pick("·", "-").normal(),
+                        session.data.summary(),
+                    )
+                }
+            };
+            println!(
+                "  {} {:>4.0}%  {:<24}  {}",
+                status,
+                score * 100.0,
+                label,
+                text.lines()
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(60)
+                    .collect::<String>()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn show(&self, id: usize) -> Result<()> {
+        let Some(task) = self.db.get_task_execution(id).await? else {
+            anyhow::bail!("Task {} not found", id);
+        };
+
+        println!(
+            "\n{} {}",
+            "Task".bright_cyan().bold(),
+            format!("#{}", task.id).bright_cyan()
+        );
+        println!("{}", style::rule(80).bright_cyan());
+        println!("{}", task.description);
+        println!(
+            "\n  {} {}",
+            "Run at:".white(),
+            task.timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        );
+        if let Some(project) = &task.project {
+            println!("  {} {}", "Project:".white(), project);
+        }
+        println!(
+            "  {} {}",
+            "Status:".white(),
+            if task.success {
+                format!("Success {}", style::ok()).green()
+            } else {
+                format!("Failed {}", style::fail()).red()
+            }
+        );
+        println!(
+            "  {} {} used of ~{} estimated (${:.2})",
+            "Tokens:".white(),
+            task.actual_tokens,
+            task.estimated_tokens,
+            estimate_cost(task.actual_tokens)
+        );
+        let tags = self.db.get_task_tags(task.id).await?;
+        if !tags.is_empty() {
+            println!("  {} {}", "Tags:".white(), tags.join(", ").bright_magenta());
+        }
+
+        print_analysis(&task);
+        print_phases(&task);
+        print_outcome(&task);
+
+        Ok(())
+    }
+
+    /// Print two runs side by side, e.g. a task and its rerun with another
+    /// model or prompt variant
+    pub async fn compare(&self, left: usize, right: usize) -> Result<()> {
+        let mut tasks = Vec::new();
+        for id in [left, right] {
+            let Some(task) = self.db.get_task_execution(id).await? else {
+                anyhow::bail!("Task {} not found", id);
+            };
+            tasks.push(task);
+        }
+
+        print_comparison(&tasks[0], &tasks[1]);
+        Ok(())
+    }
+
+    /// The task rendered as a document in `format`
+    pub async fn export(&self, id: usize, format: ExportFormat) -> Result<String> {
+        let Some(task) = self.db.get_task_execution(id).await? else {
+            anyhow::bail!("Task {} not found", id);
+        };
+        let tags = self.db.get_task_tags(task.id).await?;
+
+        Ok(match format {
+            ExportFormat::Markdown => transcript_markdown(&task, &tags),
+            ExportFormat::Pr => pr_summary(&task),
+        })
+    }
+}
+
+fn print_analysis(task: &TaskRecord) {
+    println!("\n{}", "Analysis:".bright_yellow().bold());
+    let Some(analysis) = &task.analysis else {
+        println!("  Not recorded");
+        return;
+    };
+
+    println!(
+        "  {} {} ({}/10)",
+        "Complexity:".white(),
+        format_complexity(analysis.complexity),
+        analysis.complexity
+    );
+    println!(
+        "  {} {}",
+        "Estimated files:".white(),
+        analysis.estimated_files
+    );
+    println!(
+        "  {} {:?}",
+        "Required expertise:".white(),
+        analysis.required_capabilities
+    );
+    println!(
+        "  {} {}-{} minutes",
+        "Estimated time:".white(),
+        analysis.estimated_time_min,
+        analysis.estimated_time_max
+    );
+}
+
+fn print_phases(task: &TaskRecord) {
+    println!("\n{}", "Plan:".bright_yellow().bold());
+    let Some(plan) = &task.plan else {
+        println!("  Not recorded");
+        return;
+    };
+
+    let outcomes = task
+        .result
+        .as_ref()
+        .map(|r| r.agents.as_slice())
+        .unwrap_or_default();
+    for (i, phase) in plan.phases.iter().enumerate() {
+        println!(
+            "  {} Phase {}/{}: {}",
+            if phase.parallel {
+                style::icon("⚡")
+            } else {
+                style::arrow()
+            },
+            i + 1,
+            plan.phases.len(),
+            phase.description
+        );
+
+        for spec in &phase.agents {
+            let outcome = outcomes.iter().find(|o| o.agent_id == spec.id);
+            let status = match outcome {
+                Some(o) if o.error.is_none() => {
+                    format!(" {} {} tokens", style::ok(), o.tokens_used).green()
+                }
+                Some(_) => format!(" {} failed", style::fail()).red(),
+                None if outcomes.is_empty() => "".normal(),
+                None => " - not run".dimmed(),
+            };
+            let after = plan
+                .waits_for(spec)
+                .map(|after| format!(" ({})", after))
+                .unwrap_or_default();
+            println!(
+                "    {} {} - {}{}{}",
+                style::bullet().bright_cyan(),
+                spec.agent_type,
+                spec.task,
+                after.dimmed(),
+                status
+            );
+        }
+    }
+}
+
+fn print_outcome(task: &TaskRecord) {
+    println!("\n{}", "Result:".bright_yellow().bold());
+    let Some(result) = &task.result else {
+        println!("  Not recorded");
+        return;
+    };
+
+    println!(
+        "  {} {} in {:.1}s",
+        "Agents executed:".white(),
+        result.agents_executed,
+        result.execution_time_secs
+    );
+
+    if !result.errors.is_empty() {
+        println!("\n{}", "Errors:".red().bold());
+        for error in &result.errors {
+            println!("  {} {}", style::fail().red(), error);
+        }
+    }
+
+    if !result.warnings.is_empty() {
+        println!("\n{}", "Warnings:".yellow().bold());
+        for warning in &result.warnings {
+            println!("  {} {}", style::warn().yellow(), warning);
+        }
+    }
+}
+
+/// Candidates sharing keywords with `query`, scored by the share of the
+/// query's keywords they contain, best first
+fn keyword_matches(query: &str, candidates: &[(Hit, String)]) -> Vec<(Hit, f64)> {
+    let wanted = keywords(query);
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<(Hit, f64)> = candidates
+        .iter()
+        .map(|(hit, text)| {
+            let found = keywords(text).intersection(&wanted).count();
+            (*hit, found as f64 / wanted.len() as f64)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    matches.sort_by(|a, b| b.1.total_cmp(&a.1));
+    matches
+}
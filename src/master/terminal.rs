@@ -0,0 +1,345 @@
+use super::format_complexity;
+use super::orchestrator::{ExecutionPlan, ExecutionResult, Scheduling};
+use super::planner::TaskAnalysis;
+use super::progress::{AgentPrompt, ExecutionObserver, PromptDecision, RunEvent};
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Width of the rules framing each stage of a run
+const RULE_WIDTH: usize = 55;
+
+/// Prints a run to the terminal with spinners and per-agent progress bars,
+/// and asks for approvals with an interactive prompt
+#[derive(Default)]
+pub struct TerminalObserver {
+    state: Mutex<TerminalState>,
+}
+
+#[derive(Default)]
+struct TerminalState {
+    token_budget: usize,
+    spinner: Option<ProgressBar>,
+    bars: MultiProgress,
+    /// Running agents by ID: their progress bar and type
+    agents: HashMap<String, (ProgressBar, String)>,
+}
+
+#[async_trait]
+impl ExecutionObserver for TerminalObserver {
+    fn on_event(&self, event: RunEvent) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        match event {
+            RunEvent::Started {
+                run_id,
+                token_budget,
+                ..
+            } => {
+                state.token_budget = token_budget;
+
+                println!("\n{}", style::rule(RULE_WIDTH).bright_cyan());
+                println!(
+                    "{} {}",
+                    "Master Coder".bright_cyan().bold(),
+                    "Analyzing task...".white()
+                );
+                println!("{}", style::rule(RULE_WIDTH).bright_cyan());
+                if !run_id.is_empty() {
+                    println!(
+                        "{} {} (follow with: claude-helper tui --attach {})",
+                        "Run ID:".white(),
+                        run_id,
+                        run_id
+                    );
+                }
+
+                let spinner = ProgressBar::new_spinner();
+                spinner.set_style(
+                    ProgressStyle::default_spinner()
+                        .template("{spinner:.cyan} {msg}")
+                        .unwrap()
+                        .tick_chars(style::spinner_ticks()),
+                );
+                spinner.set_message("Analyzing task complexity and requirements...");
+                state.spinner = Some(spinner);
+            }
+            RunEvent::Analyzed(analysis) => {
+                if let Some(spinner) = state.spinner.take() {
+                    spinner.finish_with_message(format!("{} Analysis complete", style::ok()));
+                }
+                print_analysis(&analysis);
+                println!(
+                    "\n{}",
+                    "Creating agent team and execution plan...".bright_yellow()
+                );
+            }
+            RunEvent::Planned(plan) => print_plan(&plan, state.token_budget),
+            RunEvent::AgentsCreated { count } => {
+                println!("\n{} {} agents created", style::ok().green(), count);
+                println!("\n{}", style::rule(RULE_WIDTH).bright_cyan());
+                println!("{}", "Executing plan...".bright_cyan().bold());
+                println!("{}", style::rule(RULE_WIDTH).bright_cyan());
+            }
+            RunEvent::PhaseStarted {
+                index,
+                total,
+                description,
+                parallel,
+            } => {
+                println!(
+                    "\n{} Phase {}/{}: {}",
+                    if parallel {
+                        style::icon("⚡").bright_yellow()
+                    } else {
+                        style::arrow().bright_cyan()
+                    },
+                    index + 1,
+                    total,
+                    description.bright_white().bold()
+                );
+            }
+            RunEvent::PhaseSkipped { index } => {
+                println!("  {} Phase {} skipped", style::warn().yellow(), index + 1);
+            }
+            RunEvent::AgentStarted {
+                agent_id,
+                agent_type,
+            } => {
+                let pb = state.bars.add(ProgressBar::new(100));
+                pb.set_style(
+                    ProgressStyle::default_bar()
+                        .template("  {spinner:.cyan} [{bar:40.cyan/blue}] {msg}")
+                        .unwrap()
+                        .tick_chars(style::spinner_ticks())
+                        .progress_chars("=>-"),
+                );
+                pb.set_position(10);
+                pb.set_message(format!("{}: Executing...", agent_type));
+                state.agents.insert(agent_id, (pb, agent_type));
+            }
+            RunEvent::AgentFinished {
+                agent_id,
+                tokens_used,
+                error,
+            } => {
+                if let Some((pb, agent_type)) = state.agents.remove(&agent_id) {
+                    if error.is_none() {
+                        pb.set_position(100);
+                        pb.finish_with_message(format!(
+                            "{}: {} Complete ({} tokens)",
+                            agent_type,
+                            style::ok(),
+                            tokens_used
+                        ));
+                    } else {
+                        pb.finish_with_message(format!("{}: {} Failed", agent_type, style::fail()));
+                    }
+                }
+            }
+            RunEvent::Message(message) => println!("{}", message),
+            RunEvent::Finished(result) => print_results(&result),
+            RunEvent::Cancelled => println!("Task cancelled by user."),
+            // The error itself is reported by the caller
+            RunEvent::Failed(_) => {
+                if let Some(spinner) = state.spinner.take() {
+                    spinner.abandon();
+                }
+            }
+            // Prompts go through approve() and review_prompt()
+            RunEvent::ApprovalNeeded { .. } | RunEvent::PromptReviewNeeded { .. } => {}
+        }
+    }
+
+    async fn approve(&self, prompt: &str) -> Result<bool> {
+        use dialoguer::Confirm;
+
+        Ok(Confirm::new()
+            .with_prompt(prompt)
+            .default(true)
+            .interact()?)
+    }
+
+    async fn review_prompt(&self, mut prompt: AgentPrompt) -> Result<PromptDecision> {
+        use dialoguer::Select;
+
+        println!(
+            "\n{} {}",
+            "Prompt of".bright_yellow().bold(),
+            prompt.agent_type.bright_yellow().bold()
+        );
+        println!("{}", prompt.system_prompt.dimmed());
+        println!("\n{} {}", "Task:".white(), prompt.task);
+
+        loop {
+            let choice = Select::new()
+                .with_prompt("Run this agent?")
+                .items(&["Run", "Edit in $EDITOR", "Skip"])
+                .default(0)
+                .interact()?;
+            match choice {
+                0 => return Ok(PromptDecision::Run(prompt)),
+                1 => match edit_prompt(&prompt) {
+                    Ok(edited) => {
+                        println!("{} {}", "Task:".white(), edited.task);
+                        prompt = edited;
+                    }
+                    Err(e) => println!("{} {:#}", style::warn(), e),
+                },
+                _ => return Ok(PromptDecision::Skip),
+            }
+        }
+    }
+}
+
+/// Let the user edit `prompt` in `$EDITOR`
+fn edit_prompt(prompt: &AgentPrompt) -> Result<AgentPrompt> {
+    let path = std::env::temp_dir().join(format!("claude-helper-prompt-{}.md", prompt.agent_id));
+    std::fs::write(&path, prompt.to_editable()).context("Failed to write the prompt to edit")?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+    let status = std::process::Command::new(editor)
+        .arg(&path)
+        .status()
+        .context("Failed to open editor");
+    let text = std::fs::read_to_string(&path).context("Failed to read the edited prompt");
+    let _ = std::fs::remove_file(&path);
+
+    if !status?.success() {
+        anyhow::bail!("The editor exited with an error; the prompt is unchanged");
+    }
+    prompt.with_edits(&text?)
+}
+
+pub(super) fn print_analysis(analysis: &TaskAnalysis) {
+    println!("\n{}", "Task Analysis:".bright_yellow().bold());
+    println!(
+        "  {} {}",
+        "Complexity:".white(),
+        format_complexity(analysis.complexity)
+    );
+    println!(
+        "  {} {}",
+        "Estimated files:".white(),
+        analysis.estimated_files
+    );
+    if let Some(workspace) = &analysis.workspace {
+        println!("  {} {}", "Workspace:".white(), workspace.summary());
+    }
+    println!(
+        "  {} {:?}",
+        "Required expertise:".white(),
+        analysis.required_capabilities
+    );
+    println!(
+        "  {} ~{}",
+        "Estimated tokens:".white(),
+        analysis.estimated_tokens
+    );
+    println!(
+        "  {} {}-{} minutes",
+        "Estimated time:".white(),
+        analysis.estimated_time_min,
+        analysis.estimated_time_max
+    );
+}
+
+pub(super) fn print_plan(plan: &ExecutionPlan, token_budget: usize) {
+    println!("\n{}", "Execution Plan:".bright_yellow().bold());
+    if plan.scheduling == Scheduling::Dag {
+        println!("  {} by dependencies", "Scheduling:".white());
+    } else {
+        println!("  {} {} phases", "Total phases:".white(), plan.phases.len());
+    }
+    println!(
+        "  {} {} agents",
+        "Total agents:".white(),
+        plan.total_agents()
+    );
+    println!("  {} {}", "Token budget:".white(), token_budget);
+
+    for (i, phase) in plan.phases.iter().enumerate() {
+        println!(
+            "\n  {} Phase {}/{}: {}",
+            if phase.parallel {
+                style::icon("⚡")
+            } else {
+                style::arrow()
+            },
+            i + 1,
+            plan.phases.len(),
+            phase.description
+        );
+
+        for agent_spec in &phase.agents {
+            let share = if agent_spec.budget_share > 0.0 {
+                format!(
+                    " (~{} tokens)",
+                    (agent_spec.budget_share * token_budget as f64) as usize
+                )
+            } else {
+                String::new()
+            };
+            let after = plan
+                .waits_for(agent_spec)
+                .map(|after| format!(" ({})", after))
+                .unwrap_or_default();
+            println!(
+                "    {} {} - {}{}{}",
+                style::bullet().bright_cyan(),
+                agent_spec.agent_type,
+                agent_spec.task,
+                share.dimmed(),
+                after.dimmed()
+            );
+        }
+    }
+}
+
+fn print_results(result: &ExecutionResult) {
+    println!("\n{}", style::rule(RULE_WIDTH).bright_green());
+    println!("{}", "Execution Complete!".bright_green().bold());
+    println!("{}", style::rule(RULE_WIDTH).bright_green());
+
+    println!("\n{}", "Summary:".bright_yellow().bold());
+    println!(
+        "  {} {}",
+        "Status:".white(),
+        if result.success {
+            format!("Success {}", style::ok()).green()
+        } else {
+            format!("Failed {}", style::fail()).red()
+        }
+    );
+    println!(
+        "  {} {}",
+        "Agents executed:".white(),
+        result.agents_executed
+    );
+    println!("  {} {}", "Total tokens used:".white(), result.tokens_used);
+    if result.cost_usd > 0.0 {
+        println!("  {} ${:.2}", "Total cost:".white(), result.cost_usd);
+    }
+    println!(
+        "  {} {:.2}s",
+        "Total time:".white(),
+        result.execution_time_secs
+    );
+
+    if !result.errors.is_empty() {
+        println!("\n{}", "Errors:".red().bold());
+        for error in &result.errors {
+            println!("  {} {}", style::fail().red(), error);
+        }
+    }
+
+    if !result.warnings.is_empty() {
+        println!("\n{}", "Warnings:".yellow().bold());
+        for warning in &result.warnings {
+            println!("  {} {}", style::warn().yellow(), warning);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::statusline::Usage;
+
+/// A usage window with too little allowance left for a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortfall {
+    /// `5h` or `7d`
+    pub window: &'static str,
+    /// Tokens the run is expected to use
+    pub needed: usize,
+    /// Tokens left in the window after the reserve margin
+    pub remaining: usize,
+    /// When the window resets, if known
+    pub minutes_until_reset: Option<u32>,
+}
+
+impl Shortfall {
+    pub fn message(&self) -> String {
+        let reset = self
+            .minutes_until_reset
+            .map(|m| format!(", resets in {}h {}m", m / 60, m % 60))
+            .unwrap_or_default();
+        format!(
+            "This run is estimated at ~{} tokens but only ~{} are left in the {} window{}",
+            self.needed, self.remaining, self.window, reset
+        )
+    }
+}
+
+/// The first window (5-hour, then 7-day) whose allowance, minus
+/// `margin_percent` of its limit held in reserve, can't cover `needed`
+pub fn check(usage: &Usage, needed: usize, margin_percent: u8) -> Option<Shortfall> {
+    let windows = [
+        (
+            "5h",
+            usage.five_hour_used,
+            usage.five_hour_limit,
+            Some(usage.five_hour_minutes_remaining),
+        ),
+        ("7d", usage.seven_day_used, usage.seven_day_limit, None),
+    ];
+
+    windows
+        .into_iter()
+        .filter(|&(_, _, limit, _)| limit > 0)
+        .find_map(|(window, used, limit, minutes_until_reset)| {
+            let reserve = limit * margin_percent as usize / 100;
+            let remaining = limit.saturating_sub(used).saturating_sub(reserve);
+            (needed > remaining).then_some(Shortfall {
+                window,
+                needed,
+                remaining,
+                minutes_until_reset,
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(five_hour_used: usize, seven_day_used: usize) -> Usage {
+        Usage {
+            five_hour_used,
+            five_hour_limit: 20_000,
+            five_hour_percent: 0,
+            five_hour_minutes_remaining: 95,
+            seven_day_used,
+            seven_day_limit: 200_000,
+            seven_day_percent: 0,
+            burn_rate_per_hour: 0.0,
+            estimated_seven_day_cost: 0.0,
+            stale_since: None,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        // 20k limit, 10k used, 2k reserve: 8k left
+        assert_eq!(check(&usage(10_000, 0), 8_000, 10), None);
+        let shortfall = check(&usage(10_000, 0), 8_001, 10).unwrap();
+        assert_eq!(shortfall.window, "5h");
+        assert_eq!(shortfall.remaining, 8_000);
+        assert!(shortfall.message().ends_with("resets in 1h 35m"));
+
+        let shortfall = check(&usage(0, 190_000), 12_000, 0).unwrap();
+        assert_eq!(shortfall.window, "7d");
+        assert_eq!(shortfall.minutes_until_reset, None);
+    }
+}
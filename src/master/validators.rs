@@ -0,0 +1,340 @@
+use super::artifacts::{self, CodeBlock, FencedBlock};
+use crate::agents::{AgentCapability, OutputValidator};
+use crate::config::{Config, SandboxConfig};
+use crate::project::Project;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+/// Most lines of `git apply` or `cargo check` errors handed back to an agent
+const MAX_ERROR_LINES: usize = 20;
+
+/// Directories left out of the scratch copy `cargo check` runs in
+const SKIPPED_DIRS: &[&str] = &["target", ".git", "node_modules"];
+
+/// Numbers the scratch copies of one process
+static SCRATCH: AtomicUsize = AtomicUsize::new(0);
+
+/// The validators `[validators]` sets for agents of `capability`. The
+/// ones that need the project check against the current one. `rust` only
+/// runs in the `[sandbox]`: a build script or proc macro in the answer
+/// would otherwise run on this machine.
+pub fn for_capability(
+    config: &Config,
+    capability: &AgentCapability,
+) -> Vec<Arc<dyn OutputValidator>> {
+    let names = config.validators.for_capability(capability);
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let root = Project::current()
+        .map(|project| PathBuf::from(project.root))
+        .or_else(|_| std::env::current_dir());
+
+    names
+        .into_iter()
+        .filter_map(|name| -> Option<Arc<dyn OutputValidator>> {
+            match name {
+                "json" => Some(Arc::new(JsonBlocks)),
+                "diff" => Some(Arc::new(DiffApplies {
+                    root: root.as_ref().ok()?.clone(),
+                })),
+                "rust" if !config.sandbox.enabled => {
+                    debug!("Not checking Rust code, the sandbox is off");
+                    None
+                }
+                "rust" => Some(Arc::new(RustCheck {
+                    root: root.as_ref().ok()?.clone(),
+                    sandbox: config.sandbox.clone(),
+                    timeout: Duration::from_secs(config.verify.timeout_secs),
+                })),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// How a block is referred to in errors: its file, or its number among the
+/// blocks checked
+fn label(block: &FencedBlock, number: usize) -> String {
+    match &block.path {
+        Some(path) => path.clone(),
+        None => format!("block {}", number),
+    }
+}
+
+fn has_language(block: &FencedBlock, languages: &[&str]) -> bool {
+    block
+        .language
+        .as_deref()
+        .is_some_and(|language| languages.iter().any(|l| language.eq_ignore_ascii_case(l)))
+}
+
+/// The first `MAX_ERROR_LINES` lines of `text`
+fn head(text: &str) -> String {
+    text.trim()
+        .lines()
+        .take(MAX_ERROR_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// JSON code blocks must parse
+pub struct JsonBlocks;
+
+#[async_trait]
+impl OutputValidator for JsonBlocks {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    async fn check(&self, output: &str) -> Vec<String> {
+        artifacts::fenced_blocks(output)
+            .iter()
+            .filter(|block| has_language(block, &["json"]))
+            .enumerate()
+            .filter_map(|(i, block)| {
+                let e = serde_json::from_str::<serde_json::Value>(&block.content).err()?;
+                Some(format!("{} is not valid JSON: {}", label(block, i + 1), e))
+            })
+            .collect()
+    }
+}
+
+/// Diff code blocks must apply to the project (`git apply --check`)
+pub struct DiffApplies {
+    root: PathBuf,
+}
+
+impl DiffApplies {
+    /// Why `diff` doesn't apply, or None when it does
+    async fn check_diff(&self, diff: &str) -> Result<Option<String>> {
+        let mut child = tokio::process::Command::new("git")
+            .args(["apply", "--check", "--recount", "-"])
+            .current_dir(&self.root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to run `git apply`")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(diff.as_bytes()).await?;
+        }
+        let output = child.wait_with_output().await?;
+
+        Ok((!output.status.success()).then(|| head(&String::from_utf8_lossy(&output.stderr))))
+    }
+}
+
+#[async_trait]
+impl OutputValidator for DiffApplies {
+    fn name(&self) -> &str {
+        "diff"
+    }
+
+    async fn check(&self, output: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let blocks = artifacts::fenced_blocks(output);
+        let diffs = blocks
+            .iter()
+            .filter(|block| has_language(block, &["diff", "patch"]));
+        for (i, block) in diffs.enumerate() {
+            match self.check_diff(&block.content).await {
+                Ok(Some(error)) => {
+                    errors.push(format!("{} does not apply: {}", label(block, i + 1), error))
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    debug!("Not checking diffs: {:#}", e);
+                    break;
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Rust files an answer writes must pass `cargo check` with the rest of the
+/// project, in a scratch copy of it and in the `[sandbox]` container. The
+/// build output stays in the scratch copy, out of the project's `target/`.
+pub struct RustCheck {
+    root: PathBuf,
+    sandbox: SandboxConfig,
+    timeout: Duration,
+}
+
+impl RustCheck {
+    /// The compiler errors for `blocks` written over a copy of the project
+    async fn cargo_check(&self, blocks: &[CodeBlock]) -> Result<Vec<String>> {
+        let scratch = std::env::temp_dir().join(format!(
+            "claude-helper-check-{}-{}",
+            std::process::id(),
+            SCRATCH.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = async {
+            copy_project(&self.root, &scratch)?;
+            artifacts::write_blocks(blocks, &scratch)?;
+            self.run(&scratch).await
+        }
+        .await;
+        if let Err(e) = fs::remove_dir_all(&scratch) {
+            debug!("Failed to remove {:?}: {}", scratch, e);
+        }
+        result
+    }
+
+    async fn run(&self, dir: &Path) -> Result<Vec<String>> {
+        let command = "cargo check --quiet --message-format short";
+        let args = self.sandbox.command_args(dir);
+        let mut cmd = tokio::process::Command::new(&args[0]);
+        cmd.args(&args[1..])
+            .arg(command)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+
+        let output = tokio::time::timeout(self.timeout, cmd.output())
+            .await
+            .with_context(|| format!("`{}` did not finish in time", command))?
+            .with_context(|| format!("Failed to run `{}`", command))?;
+        if output.status.success() {
+            return Ok(Vec::new());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let errors: Vec<String> = stderr
+            .lines()
+            .filter(|line| line.contains("error") && !line.starts_with("error: could not compile"))
+            .take(MAX_ERROR_LINES)
+            .map(str::to_string)
+            .collect();
+        Ok(if errors.is_empty() {
+            vec![head(&stderr)]
+        } else {
+            errors
+        })
+    }
+}
+
+#[async_trait]
+impl OutputValidator for RustCheck {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    async fn check(&self, output: &str) -> Vec<String> {
+        let blocks = artifacts::extract_code_blocks(output);
+        let writes_rust = blocks
+            .iter()
+            .any(|block| block.path.extension().is_some_and(|ext| ext == "rs"));
+        if !writes_rust || !self.root.join("Cargo.toml").exists() {
+            return Vec::new();
+        }
+
+        match self.cargo_check(&blocks).await {
+            Ok(errors) => errors,
+            Err(e) => {
+                warn!("Not checking Rust code: {:#}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Copy the project at `from` to `to`, leaving out build output, VCS data
+/// and symlinks
+fn copy_project(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).with_context(|| format!("Failed to create {:?}", to))?;
+    for entry in fs::read_dir(from).with_context(|| format!("Failed to read {:?}", from))? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.iter().any(|dir| entry.file_name() == *dir) {
+                copy_project(&entry.path(), &target)?;
+            }
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy {:?}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_json_blocks() {
+        let output =
+            "Config:\n```json\n{\"a\": 1}\n```\nand\n\n```json config.json\n{\"a\": }\n```\n";
+        let errors = JsonBlocks.check(output).await;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("config.json is not valid JSON"));
+
+        assert!(JsonBlocks
+            .check("```rust\nfn main() {}\n```")
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_diff_applies() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "one\ntwo\n").unwrap();
+        let validator = DiffApplies {
+            root: dir.path().to_path_buf(),
+        };
+
+        let good =
+            "```diff\n--- a/notes.txt\n+++ b/notes.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n```";
+        assert!(validator.check(good).await.is_empty());
+
+        let stale = good.replace(" one", " uno");
+        let errors = validator.check(&stale).await;
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("block 1 does not apply"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_rust_check_needs_sandbox() {
+        let names = |config: &Config| -> Vec<String> {
+            for_capability(config, &AgentCapability::CodeWriting)
+                .iter()
+                .map(|v| v.name().to_string())
+                .collect()
+        };
+
+        let mut config = Config::default();
+        assert_eq!(names(&config), vec!["diff", "json"]);
+        config.sandbox.enabled = true;
+        assert_eq!(names(&config), vec!["diff", "json", "rust"]);
+    }
+
+    #[test]
+    fn test_copy_project_skips_build_output() {
+        let from = tempfile::tempdir().unwrap();
+        fs::create_dir_all(from.path().join("src")).unwrap();
+        fs::create_dir_all(from.path().join("target/debug")).unwrap();
+        fs::write(from.path().join("src/lib.rs"), "").unwrap();
+        fs::write(from.path().join("target/debug/big"), "").unwrap();
+
+        let to = tempfile::tempdir().unwrap();
+        copy_project(from.path(), to.path()).unwrap();
+        assert!(to.path().join("src/lib.rs").exists());
+        assert!(!to.path().join("target").exists());
+    }
+}
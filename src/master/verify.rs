@@ -0,0 +1,174 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Most lines of test output handed to the Debugging agent; failures are
+/// usually summed up at the end
+const OUTPUT_TAIL_LINES: usize = 200;
+
+/// Most characters of the diff handed to the Debugging agent
+const MAX_DIFF_CHARS: usize = 40_000;
+
+/// Outcome of one run of `verify.test_command`
+#[derive(Debug, Clone)]
+pub struct TestRun {
+    pub passed: bool,
+    /// The end of stdout and stderr
+    pub output: String,
+}
+
+/// Run `verify.test_command` in `root`, inside the `[sandbox]` container
+/// when it is enabled. A run over `verify.timeout_secs` counts as failed.
+pub async fn run_tests(config: &Config, root: &Path) -> Result<TestRun> {
+    let command = &config.verify.test_command;
+    let mut cmd = if config.sandbox.enabled {
+        let args = config.sandbox.command_args(root);
+        let mut cmd = tokio::process::Command::new(&args[0]);
+        cmd.args(&args[1..]);
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(command)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    let timeout = Duration::from_secs(config.verify.timeout_secs);
+    let output = match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(output) => output.with_context(|| format!("Failed to run `{}`", command))?,
+        Err(_) => {
+            return Ok(TestRun {
+                passed: false,
+                output: format!(
+                    "`{}` did not finish within {} seconds",
+                    command, config.verify.timeout_secs
+                ),
+            })
+        }
+    };
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(TestRun {
+        passed: output.status.success(),
+        output: tail(&text, OUTPUT_TAIL_LINES),
+    })
+}
+
+/// The last `lines` lines of `text`
+fn tail(text: &str, lines: usize) -> String {
+    let all: Vec<&str> = text.trim_end().lines().collect();
+    let skipped = all.len().saturating_sub(lines);
+    let mut tail = all[skipped..].join("\n");
+    if skipped > 0 {
+        tail.insert_str(0, &format!("[{} earlier lines omitted]\n", skipped));
+    }
+    tail
+}
+
+/// What changed in `files` (relative to `root`): the git diff of tracked
+/// files and the whole content of new ones. Empty outside a git repository.
+pub async fn diff(root: &Path, files: &[PathBuf]) -> String {
+    if files.is_empty() {
+        return String::new();
+    }
+    let git = |args: &[&str]| {
+        let mut cmd = tokio::process::Command::new("git");
+        cmd.args(args).args(files).current_dir(root);
+        async move {
+            cmd.output()
+                .await
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+    };
+
+    let Some(mut diff) = git(&["diff", "--no-color", "--"]).await else {
+        return String::new();
+    };
+    let untracked = git(&["ls-files", "--others", "--exclude-standard", "--"])
+        .await
+        .unwrap_or_default();
+    for path in untracked.lines() {
+        if let Ok(content) = std::fs::read_to_string(root.join(path)) {
+            diff.push_str(&format!(
+                "--- /dev/null\n+++ b/{} (new file)\n{}\n",
+                path, content
+            ));
+        }
+    }
+
+    if diff.len() > MAX_DIFF_CHARS {
+        let cut = (0..=MAX_DIFF_CHARS)
+            .rev()
+            .find(|&i| diff.is_char_boundary(i))
+            .unwrap_or(0);
+        diff.truncate(cut);
+        diff.push_str("\n[diff truncated]");
+    }
+    diff
+}
+
+/// Task for the Debugging agent of one round: the failing output, what the
+/// run changed, and how to answer so the fix can be applied
+pub fn debugger_task(task: &str, command: &str, output: &str, diff: &str) -> String {
+    let diff = if diff.is_empty() {
+        "(no diff available)"
+    } else {
+        diff
+    };
+    format!(
+        "The tests written for this task fail.\n\n\
+        ## Task\n{}\n\n\
+        ## `{}` output\n```\n{}\n```\n\n\
+        ## Changes made by this run\n```diff\n{}\n```\n\n\
+        Find the root cause and fix it, in the code or in the tests if they \
+        are wrong. Give every file you change in full, in a code block with \
+        its path after the language, e.g. ```rust src/lib.rs",
+        task, command, output, diff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail() {
+        assert_eq!(tail("a\nb\n", 5), "a\nb");
+
+        let output: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        assert_eq!(
+            tail(&output, 2),
+            "[8 earlier lines omitted]\nline 9\nline 10"
+        );
+    }
+
+    #[test]
+    fn test_debugger_task() {
+        let task = debugger_task("Add a parser", "cargo test", "test parse ... FAILED", "");
+        assert!(task.contains("## `cargo test` output\n```\ntest parse ... FAILED\n```"));
+        assert!(task.contains("(no diff available)"));
+        assert!(task.contains("```rust src/lib.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_run_tests() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.verify.test_command = "echo ok; echo broken >&2; exit 1".to_string();
+
+        let run = run_tests(&config, dir.path()).await.unwrap();
+        assert!(!run.passed);
+        assert_eq!(run.output, "ok\nbroken");
+
+        config.verify.test_command = "true".to_string();
+        assert!(run_tests(&config, dir.path()).await.unwrap().passed);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::agents::{resolve_model, DEFAULT_MODEL};
+use crate::db::ApiCallEntry;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Ways `claude-helper cost` can group spend
+pub const COST_GROUPS: &[&str] = &["model", "agent", "day"];
+
+/// Dollars per million tokens of one model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    /// Writing a prompt to the (5-minute) cache
+    pub cache_write: f64,
+    /// Reading a cached prompt, a tenth of the input price
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    const fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_write: input * 1.25,
+            cache_read: input * 0.1,
+        }
+    }
+
+    /// Dollar cost of the given token counts
+    pub fn cost(&self, input: usize, output: usize, cache_read: usize, cache_write: usize) -> f64 {
+        (input as f64 * self.input
+            + output as f64 * self.output
+            + cache_write as f64 * self.cache_write
+            + cache_read as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Anthropic list prices by model ID prefix. Dated IDs such as
+/// `claude-sonnet-4-5-20250929` match their family; the longest prefix wins.
+const PRICES: &[(&str, ModelPrice)] = &[
+    ("claude-opus-4-5", ModelPrice::new(5.0, 25.0)),
+    ("claude-opus-4", ModelPrice::new(15.0, 75.0)),
+    ("claude-sonnet-4", ModelPrice::new(3.0, 15.0)),
+    ("claude-haiku-4-5", ModelPrice::new(1.0, 5.0)),
+    ("claude-3-opus", ModelPrice::new(15.0, 75.0)),
+    ("claude-3-7-sonnet", ModelPrice::new(3.0, 15.0)),
+    ("claude-3-5-sonnet", ModelPrice::new(3.0, 15.0)),
+    ("claude-3-5-haiku", ModelPrice::new(0.8, 4.0)),
+    ("claude-3-haiku", ModelPrice::new(0.25, 1.25)),
+];
+
+/// Prices of `model` (an ID or alias such as `haiku`), or None for models
+/// missing from the table
+pub fn price_of(model: &str) -> Option<ModelPrice> {
+    let model = resolve_model(model);
+    PRICES
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price)
+}
+
+/// Prices of `model`, falling back to [`DEFAULT_MODEL`] for unknown models
+pub fn price_or_default(model: &str) -> ModelPrice {
+    price_of(model)
+        .or_else(|| price_of(DEFAULT_MODEL))
+        .unwrap_or(ModelPrice::new(3.0, 15.0))
+}
+
+/// Spend of one group of API calls
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CostRow {
+    /// Model, agent type or day, depending on the grouping
+    pub group: String,
+    pub calls: usize,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub cache_read_tokens: usize,
+    pub cache_write_tokens: usize,
+    pub cost_usd: f64,
+    /// What the cache reads would have cost more as regular input
+    pub cache_savings_usd: f64,
+    /// Some calls used a model missing from the price table and were priced
+    /// as the default model
+    pub estimated: bool,
+}
+
+/// Price `calls` and group them by `by` (model, agent or day), most
+/// expensive first (days in order)
+pub fn cost_breakdown(calls: &[ApiCallEntry], by: &str) -> Result<Vec<CostRow>> {
+    if !COST_GROUPS.contains(&by) {
+        anyhow::bail!(
+            "Unknown grouping '{}' (expected one of: {})",
+            by,
+            COST_GROUPS.join(", ")
+        );
+    }
+
+    let mut groups: BTreeMap<String, CostRow> = BTreeMap::new();
+    for entry in calls {
+        let call = &entry.call;
+        let group = match by {
+            "model" => call.model.clone(),
+            "agent" => call.agent_type.clone(),
+            _ => entry.timestamp.format("%Y-%m-%d").to_string(),
+        };
+        let price = price_of(&call.model);
+        let priced = price.unwrap_or_else(|| price_or_default(&call.model));
+
+        let row = groups.entry(group.clone()).or_insert_with(|| CostRow {
+            group,
+            ..Default::default()
+        });
+        row.calls += 1;
+        row.input_tokens += call.input_tokens;
+        row.output_tokens += call.output_tokens;
+        row.cache_read_tokens += call.cache_read_tokens;
+        row.cache_write_tokens += call.cache_write_tokens;
+        row.cost_usd += priced.cost(
+            call.input_tokens,
+            call.output_tokens,
+            call.cache_read_tokens,
+            call.cache_write_tokens,
+        );
+        row.cache_savings_usd +=
+            call.cache_read_tokens as f64 * (priced.input - priced.cache_read) / 1_000_000.0;
+        row.estimated |= price.is_none();
+    }
+
+    let mut rows: Vec<CostRow> = groups.into_values().collect();
+    if by != "day" {
+        rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ApiCall;
+    use chrono::{TimeZone, Utc};
+
+    fn call(
+        model: &str,
+        agent: &str,
+        day: u32,
+        input: usize,
+        output: usize,
+        cache_read: usize,
+    ) -> ApiCallEntry {
+        ApiCallEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, day, 12, 0, 0).unwrap(),
+            call: ApiCall {
+                agent_type: agent.to_string(),
+                model: model.to_string(),
+                input_tokens: input,
+                output_tokens: output,
+                cache_read_tokens: cache_read,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_price_of() {
+        assert_eq!(price_of("claude-opus-4-1-20250805").unwrap().input, 15.0);
+        assert_eq!(price_of("claude-opus-4-5-20251101").unwrap().input, 5.0);
+        assert_eq!(price_of("haiku").unwrap().output, 5.0);
+        assert!((price_of("claude-sonnet-4-5-20250929").unwrap().cache_read - 0.3).abs() < 1e-9);
+        assert!(price_of("gpt-4o").is_none());
+        assert_eq!(price_or_default("gpt-4o"), price_of(DEFAULT_MODEL).unwrap());
+    }
+
+    #[test]
+    fn test_cost_breakdown() {
+        let calls = vec![
+            call(
+                "claude-sonnet-4-5-20250929",
+                "Code Writer",
+                1,
+                1_000_000,
+                0,
+                1_000_000,
+            ),
+            call("claude-haiku-4-5-20251001", "Reviewer", 2, 0, 1_000_000, 0),
+            call("mystery-model", "Reviewer", 2, 1_000_000, 0, 0),
+        ];
+
+        let by_model = cost_breakdown(&calls, "model").unwrap();
+        assert_eq!(by_model[0].group, "claude-haiku-4-5-20251001");
+        assert!((by_model[0].cost_usd - 5.0).abs() < 1e-9);
+        // $3 input plus $0.30 for the cached million, which saved $2.70
+        let sonnet = by_model
+            .iter()
+            .find(|r| r.group.starts_with("claude-sonnet"))
+            .unwrap();
+        assert!((sonnet.cost_usd - 3.3).abs() < 1e-9);
+        assert!((sonnet.cache_savings_usd - 2.7).abs() < 1e-9);
+        assert!(
+            by_model
+                .iter()
+                .find(|r| r.group == "mystery-model")
+                .unwrap()
+                .estimated
+        );
+
+        let by_day = cost_breakdown(&calls, "day").unwrap();
+        assert_eq!(by_day[0].group, "2026-10-01");
+        assert_eq!(by_day[1].calls, 2);
+
+        assert!(cost_breakdown(&calls, "project").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::db::HourlyBreakdown;
+use chrono::{Datelike, TimeZone, Timelike};
+use serde::Serialize;
+
+/// Shades from no tokens to the busiest hour
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Tokens per weekday and hour of day in local time, to show when usage is
+/// usually low
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Heatmap {
+    /// Monday first, one column per hour
+    pub tokens: [[u64; 24]; 7],
+}
+
+impl Heatmap {
+    /// Add up hourly totals by the weekday and hour they fall on in `tz`
+    pub fn from_hourly<Tz: TimeZone>(entries: &[HourlyBreakdown], tz: &Tz) -> Self {
+        let mut heatmap = Self::default();
+        for entry in entries {
+            let local = entry.hour.with_timezone(tz);
+            let day = local.weekday().num_days_from_monday() as usize;
+            heatmap.tokens[day][local.hour() as usize] += entry.total_tokens as u64;
+        }
+        heatmap
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.iter().flatten().all(|&tokens| tokens == 0)
+    }
+
+    /// One character per hour of `day`, darker the more tokens it used
+    /// compared to the busiest hour of the week
+    pub fn shades(&self, day: usize) -> String {
+        let max = self.tokens.iter().flatten().copied().max().unwrap_or(0);
+        self.tokens[day]
+            .iter()
+            .map(|&tokens| shade(tokens, max))
+            .collect()
+    }
+
+    /// First hour of the `hours` consecutive hours (wrapping past midnight)
+    /// that used the fewest tokens over all weekdays, or None without usage
+    pub fn quietest_window(&self, hours: usize) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        let per_hour: Vec<u64> = (0..24)
+            .map(|hour| self.tokens.iter().map(|day| day[hour]).sum())
+            .collect();
+        (0..24).min_by_key(|&start| {
+            (0..hours)
+                .map(|offset| per_hour[(start + offset) % 24])
+                .sum::<u64>()
+        })
+    }
+}
+
+fn shade(tokens: u64, max: u64) -> char {
+    if tokens == 0 || max == 0 {
+        return SHADES[0];
+    }
+    let level = (tokens * (SHADES.len() as u64 - 1)).div_ceil(max);
+    SHADES[level as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn entry(hour: chrono::DateTime<Utc>, tokens: usize) -> HourlyBreakdown {
+        HourlyBreakdown {
+            hour,
+            task_count: 1,
+            total_tokens: tokens,
+        }
+    }
+
+    #[test]
+    fn test_heatmap_uses_local_time() {
+        // Sunday 23:00 UTC is Monday 01:00 two hours east
+        let sunday = Utc.with_ymd_and_hms(2026, 10, 11, 23, 0, 0).unwrap();
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let heatmap = Heatmap::from_hourly(&[entry(sunday, 400), entry(sunday, 100)], &tz);
+
+        assert_eq!(heatmap.tokens[0][1], 500);
+        assert_eq!(heatmap.tokens[6][23], 0);
+        assert!(heatmap.shades(0).starts_with("·█·"));
+    }
+
+    #[test]
+    fn test_quietest_window_wraps_midnight() {
+        let mut heatmap = Heatmap::default();
+        assert_eq!(heatmap.quietest_window(3), None);
+
+        for hour in 2..22 {
+            heatmap.tokens[2][hour] = 1000;
+        }
+        heatmap.tokens[4][1] = 10;
+        assert_eq!(heatmap.quietest_window(3), Some(22));
+        assert_eq!(heatmap.shades(4).chars().nth(1), Some('░'));
+    }
+}
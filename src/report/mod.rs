@@ -0,0 +1,298 @@
+pub mod heatmap;
+mod render;
+
+pub use heatmap::Heatmap;
+
+use crate::analyzer::{Optimization, SessionAnalyzer};
+use crate::config::Config;
+use crate::db::{AgentStats, Database, HiddenOptimizations};
+use crate::statusline::{OrgUsage, Usage, UsageTracker};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Most recent session files considered; older ones fall outside any
+/// sensible report period anyway
+const SESSION_LIMIT: usize = 200;
+
+/// Stored optimizations considered for the top list
+const OPTIMIZATION_LIMIT: usize = 100;
+
+/// Optimizations listed in the report
+const TOP_OPTIMIZATIONS: usize = 5;
+
+/// Tools listed in the session summary
+const TOP_TOOLS: usize = 5;
+
+/// Output format of `claude-helper report`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!("Invalid report format: {} (use markdown, html or json)", s),
+        }
+    }
+}
+
+/// Usage status, session analysis, top optimizations and agent
+/// performance for one period, in one place
+#[derive(Serialize)]
+pub struct Report {
+    pub generated_at: DateTime<Utc>,
+    pub days: usize,
+    /// Only runs with this tag, if set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Current 5-hour and 7-day usage, if it could be fetched
+    pub usage: Option<Usage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_error: Option<String>,
+    /// Organization usage per member, when an Admin API key is set up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org: Option<OrgUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_error: Option<String>,
+    /// Runs, tokens and cost per day, newest first
+    pub daily: Vec<DayTotal>,
+    /// Agent tokens by weekday and hour over the period, across all runs
+    pub heatmap: Heatmap,
+    pub sessions: SessionStats,
+    pub top_optimizations: Vec<Optimization>,
+    pub agents: AgentStats,
+}
+
+/// Rollup totals for one day across projects and models
+#[derive(Debug, Clone, Serialize)]
+pub struct DayTotal {
+    pub day: String,
+    pub runs: usize,
+    pub successful_runs: usize,
+    pub tokens: usize,
+    pub cost_usd: f64,
+}
+
+/// Totals over the sessions active in the period
+#[derive(Debug, Default, Serialize)]
+pub struct SessionStats {
+    pub sessions: usize,
+    pub messages: usize,
+    pub tool_calls: usize,
+    /// Recorded usage, or estimated from message length for sessions
+    /// without it
+    pub tokens: usize,
+    pub optimizations_found: usize,
+    pub potential_savings: usize,
+    /// Most used tools with their call counts
+    pub top_tools: Vec<(String, usize)>,
+}
+
+impl Report {
+    /// Gather everything for the last `days` days. Usage that cannot be
+    /// fetched (e.g. without credentials) is reported as unavailable
+    /// instead of failing the whole report. With a `tag`, the activity and
+    /// agent sections only count runs with that tag.
+    pub async fn generate(
+        config: &Config,
+        db: Arc<Database>,
+        days: usize,
+        tag: Option<String>,
+    ) -> Result<Self> {
+        let since = Utc::now() - Duration::days(days as i64);
+
+        let usage = async { UsageTracker::new(config.clone()).await?.get_usage().await }.await;
+        let (usage, usage_error) = match usage {
+            Ok(usage) => (Some(usage), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+
+        let (org, org_error) = if config.org.is_configured() {
+            let org = async {
+                UsageTracker::new(config.clone())
+                    .await?
+                    .get_org_usage(days)
+                    .await
+            }
+            .await;
+            match org {
+                Ok(org) => (Some(org), None),
+                Err(e) => (None, Some(format!("{:#}", e))),
+            }
+        } else {
+            (None, None)
+        };
+
+        let daily = match &tag {
+            Some(tag) => db.get_daily_usage_tagged(days, tag).await?,
+            None => db.get_daily_usage(days).await?,
+        };
+        let daily = day_totals(&daily);
+        let hourly = db.get_hourly_breakdown(days * 24).await?;
+        let heatmap = Heatmap::from_hourly(&hourly, &Local);
+        let agents = db.get_agent_stats_since(since, tag.as_deref()).await?;
+
+        let analyzer = SessionAnalyzer::new(config.clone(), db.clone()).await?;
+        let hidden = db.hidden_optimizations().await?;
+        let (sessions, found) = analyze_sessions(&analyzer, since, &hidden)?;
+
+        // Stored suggestions are the reviewed ones; fall back to what the
+        // sessions show when nothing was stored in the period
+        let mut top_optimizations: Vec<Optimization> = db
+            .get_optimizations(OPTIMIZATION_LIMIT, false, false)
+            .await?
+            .into_iter()
+            .filter(|stored| stored.created_at >= since)
+            .map(|stored| stored.optimization)
+            .collect();
+        if top_optimizations.is_empty() {
+            top_optimizations = found;
+        }
+        top_optimizations.sort_by_key(|opt| std::cmp::Reverse(opt.estimated_savings));
+        top_optimizations.truncate(TOP_OPTIMIZATIONS);
+
+        Ok(Self {
+            generated_at: Utc::now(),
+            days,
+            tag,
+            usage,
+            usage_error,
+            org,
+            org_error,
+            daily,
+            heatmap,
+            sessions,
+            top_optimizations,
+            agents,
+        })
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        Ok(match format {
+            ReportFormat::Markdown => render::to_markdown(&render::document(self)),
+            ReportFormat::Html => render::to_html(&render::document(self)),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+        })
+    }
+}
+
+/// Sum rollup rows per day, keeping the newest-first order
+fn day_totals(rows: &[crate::db::DailyUsage]) -> Vec<DayTotal> {
+    let mut totals: Vec<DayTotal> = Vec::new();
+
+    for row in rows {
+        match totals.last_mut() {
+            Some(total) if total.day == row.day => {
+                total.runs += row.runs;
+                total.successful_runs += row.successful_runs;
+                total.tokens += row.tokens;
+                total.cost_usd += row.cost_usd;
+            }
+            _ => totals.push(DayTotal {
+                day: row.day.clone(),
+                runs: row.runs,
+                successful_runs: row.successful_runs,
+                tokens: row.tokens,
+                cost_usd: row.cost_usd,
+            }),
+        }
+    }
+
+    totals
+}
+
+/// Totals over the sessions modified since `since`, and the optimizations
+/// found in them
+fn analyze_sessions(
+    analyzer: &SessionAnalyzer,
+    since: DateTime<Utc>,
+    hidden: &HiddenOptimizations,
+) -> Result<(SessionStats, Vec<Optimization>)> {
+    let mut stats = SessionStats::default();
+    let mut tools: HashMap<String, usize> = HashMap::new();
+    let mut found = Vec::new();
+
+    for session in analyzer.list_sessions(SESSION_LIMIT)? {
+        if session.modified.is_some_and(|modified| modified < since) {
+            continue;
+        }
+
+        let data = &session.data;
+        stats.sessions += 1;
+        stats.messages += data.messages.len();
+        stats.tool_calls += data.tool_calls.len();
+        stats.tokens += data.tokens();
+        for (tool, count) in data.tool_usage() {
+            *tools.entry(tool).or_default() += count;
+        }
+
+        let mut optimizations = analyzer.session_optimizations(data)?;
+        optimizations.retain(|opt| !hidden.hides(opt));
+        stats.optimizations_found += optimizations.len();
+        stats.potential_savings += optimizations
+            .iter()
+            .map(|opt| opt.estimated_savings)
+            .sum::<usize>();
+        found.extend(optimizations);
+    }
+
+    let mut tools: Vec<(String, usize)> = tools.into_iter().collect();
+    tools.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tools.truncate(TOP_TOOLS);
+    stats.top_tools = tools;
+
+    Ok((stats, found))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DailyUsage;
+
+    fn row(day: &str, model: &str, tokens: usize) -> DailyUsage {
+        DailyUsage {
+            day: day.to_string(),
+            project: "app".to_string(),
+            model: model.to_string(),
+            runs: 2,
+            successful_runs: 1,
+            tokens,
+            cost_usd: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_day_totals_merge_models() {
+        let totals = day_totals(&[
+            row("2026-10-16", "opus", 100),
+            row("2026-10-16", "sonnet", 50),
+            row("2026-10-15", "sonnet", 10),
+        ]);
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].day, "2026-10-16");
+        assert_eq!((totals[0].runs, totals[0].tokens), (4, 150));
+        assert_eq!(totals[0].cost_usd, 1.0);
+        assert_eq!(totals[1].tokens, 10);
+    }
+
+    #[test]
+    fn test_report_format_from_str() {
+        assert_eq!(
+            ReportFormat::from_str("MD").unwrap(),
+            ReportFormat::Markdown
+        );
+        assert_eq!(ReportFormat::from_str("html").unwrap(), ReportFormat::Html);
+        assert!(ReportFormat::from_str("pdf").is_err());
+    }
+}
@@ -0,0 +1,372 @@
+use super::heatmap::WEEKDAYS;
+use super::Report;
+use chrono::Local;
+
+/// Length of the quiet window suggested for heavy work
+const QUIET_HOURS: usize = 3;
+
+/// One piece of a report, rendered to Markdown or HTML
+#[derive(Debug)]
+pub enum Block {
+    Title(String),
+    Heading(String),
+    Text(String),
+    List(Vec<String>),
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+fn table(header: &[&str], rows: Vec<Vec<String>>) -> Block {
+    Block::Table {
+        header: header.iter().map(|h| h.to_string()).collect(),
+        rows,
+    }
+}
+
+/// Lay the report out as a sequence of blocks
+pub fn document(report: &Report) -> Vec<Block> {
+    let mut doc = vec![
+        Block::Title("Claude usage report".to_string()),
+        Block::Text(format!(
+            "Generated {} · last {} days{}",
+            report
+                .generated_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            report.days,
+            report
+                .tag
+                .as_ref()
+                .map(|tag| format!(" · runs tagged {}", tag))
+                .unwrap_or_default()
+        )),
+        Block::Heading("Current usage".to_string()),
+    ];
+
+    match (&report.usage, &report.usage_error) {
+        (Some(usage), _) => {
+            if let Some(at) = usage.stale_since {
+                doc.push(Block::Text(format!(
+                    "Fetching usage failed; these are the numbers from {}.",
+                    at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                )));
+            }
+            doc.push(table(
+                &["", "Used", "Limit", "Percent"],
+                vec![
+                    vec![
+                        "5-hour block".to_string(),
+                        usage.five_hour_used.to_string(),
+                        usage.five_hour_limit.to_string(),
+                        format!("{}%", usage.five_hour_percent),
+                    ],
+                    vec![
+                        "7-day total".to_string(),
+                        usage.seven_day_used.to_string(),
+                        usage.seven_day_limit.to_string(),
+                        format!("{}%", usage.seven_day_percent),
+                    ],
+                ],
+            ));
+            doc.push(Block::Text(format!(
+                "Burn rate ${:.2}/hour, estimated 7-day cost ${:.2}",
+                usage.burn_rate_per_hour, usage.estimated_seven_day_cost
+            )));
+        }
+        (None, error) => doc.push(Block::Text(format!(
+            "Usage unavailable: {}",
+            error.as_deref().unwrap_or("unknown error")
+        ))),
+    }
+
+    match (&report.org, &report.org_error) {
+        (Some(org), _) => {
+            doc.push(Block::Heading("Organization".to_string()));
+            let limit = match org.seven_day_percent() {
+                Some(percent) => format!(
+                    " Last 7 days: {} of the shared {} tokens ({}%).",
+                    org.seven_day_tokens, org.seven_day_limit, percent
+                ),
+                None => String::new(),
+            };
+            doc.push(Block::Text(format!(
+                "{} tokens across the organization.{}",
+                org.total_tokens, limit
+            )));
+            if !org.members.is_empty() {
+                doc.push(table(
+                    &["Member", "Tokens", "Share"],
+                    org.members
+                        .iter()
+                        .map(|member| {
+                            let share =
+                                member.tokens as f64 / org.total_tokens.max(1) as f64 * 100.0;
+                            vec![
+                                member.member.clone(),
+                                member.tokens.to_string(),
+                                format!("{:.1}%", share),
+                            ]
+                        })
+                        .collect(),
+                ));
+            }
+        }
+        (None, Some(error)) => {
+            doc.push(Block::Heading("Organization".to_string()));
+            doc.push(Block::Text(format!(
+                "Organization usage unavailable: {}",
+                error
+            )));
+        }
+        (None, None) => {}
+    }
+
+    doc.push(Block::Heading("Activity".to_string()));
+    if report.daily.is_empty() {
+        doc.push(Block::Text("No runs recorded in this period.".to_string()));
+    } else {
+        let runs: usize = report.daily.iter().map(|d| d.runs).sum();
+        let tokens: usize = report.daily.iter().map(|d| d.tokens).sum();
+        let cost: f64 = report.daily.iter().map(|d| d.cost_usd).sum();
+        doc.push(Block::Text(format!(
+            "{} runs, {} tokens, ${:.2}",
+            runs, tokens, cost
+        )));
+        doc.push(table(
+            &["Day", "Runs", "Successful", "Tokens", "Cost"],
+            report
+                .daily
+                .iter()
+                .map(|day| {
+                    vec![
+                        day.day.clone(),
+                        day.runs.to_string(),
+                        day.successful_runs.to_string(),
+                        day.tokens.to_string(),
+                        format!("${:.2}", day.cost_usd),
+                    ]
+                })
+                .collect(),
+        ));
+    }
+
+    doc.push(Block::Heading("Usage by hour".to_string()));
+    let heatmap = &report.heatmap;
+    if heatmap.is_empty() {
+        doc.push(Block::Text(
+            "No agent tokens recorded in this period.".to_string(),
+        ));
+    } else {
+        doc.push(Block::Text(format!(
+            "Agent tokens of all runs by weekday and local hour, darker is busier.{}",
+            heatmap
+                .quietest_window(QUIET_HOURS)
+                .map(|start| format!(
+                    " Usage is usually lowest from {:02}:00 to {:02}:00.",
+                    start,
+                    (start + QUIET_HOURS) % 24
+                ))
+                .unwrap_or_default()
+        )));
+        doc.push(table(
+            &["Day", "Hours 00–23"],
+            WEEKDAYS
+                .iter()
+                .enumerate()
+                .map(|(day, name)| vec![name.to_string(), heatmap.shades(day)])
+                .collect(),
+        ));
+    }
+
+    let sessions = &report.sessions;
+    doc.push(Block::Heading("Sessions".to_string()));
+    doc.push(Block::Text(format!(
+        "{} sessions, {} messages, {} tool calls, ~{} tokens. {} optimization opportunities worth ~{} tokens.",
+        sessions.sessions,
+        sessions.messages,
+        sessions.tool_calls,
+        sessions.tokens,
+        sessions.optimizations_found,
+        sessions.potential_savings
+    )));
+    if !sessions.top_tools.is_empty() {
+        doc.push(Block::List(
+            sessions
+                .top_tools
+                .iter()
+                .map(|(tool, count)| format!("{}: {} calls", tool, count))
+                .collect(),
+        ));
+    }
+
+    doc.push(Block::Heading("Top optimizations".to_string()));
+    if report.top_optimizations.is_empty() {
+        doc.push(Block::Text(
+            "No optimization opportunities found.".to_string(),
+        ));
+    } else {
+        doc.push(Block::List(
+            report
+                .top_optimizations
+                .iter()
+                .map(|opt| {
+                    format!(
+                        "{} ({}, ~{} tokens): {}",
+                        opt.title, opt.opt_type, opt.estimated_savings, opt.description
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    let agents = &report.agents;
+    doc.push(Block::Heading("Agent performance".to_string()));
+    if agents.total_executions == 0 {
+        doc.push(Block::Text(
+            "No agent executions in this period.".to_string(),
+        ));
+    } else {
+        let success_rate =
+            agents.successful_executions as f64 / agents.total_executions as f64 * 100.0;
+        doc.push(Block::Text(format!(
+            "{} executions, {:.1}% successful, {} tokens ({} per agent), {:.1}s per agent",
+            agents.total_executions,
+            success_rate,
+            agents.total_tokens,
+            agents.avg_tokens_per_agent,
+            agents.avg_time_per_agent
+        )));
+
+        let mut capabilities: Vec<_> = agents.by_capability.iter().collect();
+        capabilities.sort_by(|a, b| {
+            b.1.cmp(a.1)
+                .then_with(|| a.0.description().cmp(b.0.description()))
+        });
+        doc.push(table(
+            &["Capability", "Executions"],
+            capabilities
+                .into_iter()
+                .map(|(capability, count)| {
+                    vec![capability.description().to_string(), count.to_string()]
+                })
+                .collect(),
+        ));
+    }
+
+    doc
+}
+
+pub fn to_markdown(doc: &[Block]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut out = String::new();
+
+    for block in doc {
+        match block {
+            Block::Title(text) => out.push_str(&format!("# {}\n\n", text)),
+            Block::Heading(text) => out.push_str(&format!("## {}\n\n", text)),
+            Block::Text(text) => out.push_str(&format!("{}\n\n", text)),
+            Block::List(items) => {
+                for item in items {
+                    out.push_str(&format!("- {}\n", item));
+                }
+                out.push('\n');
+            }
+            Block::Table { header, rows } => {
+                let line = |cells: &[String]| {
+                    let cells: Vec<String> = cells.iter().map(|c| cell(c)).collect();
+                    format!("| {} |\n", cells.join(" | "))
+                };
+                out.push_str(&line(header));
+                out.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+                for row in rows {
+                    out.push_str(&line(row));
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+pub fn to_html(doc: &[Block]) -> String {
+    let mut body = String::new();
+
+    for block in doc {
+        match block {
+            Block::Title(text) => body.push_str(&format!("<h1>{}</h1>\n", escape(text))),
+            Block::Heading(text) => body.push_str(&format!("<h2>{}</h2>\n", escape(text))),
+            Block::Text(text) => body.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::List(items) => {
+                body.push_str("<ul>\n");
+                for item in items {
+                    body.push_str(&format!("  <li>{}</li>\n", escape(item)));
+                }
+                body.push_str("</ul>\n");
+            }
+            Block::Table { header, rows } => {
+                body.push_str("<table>\n  <tr>");
+                for cell in header {
+                    body.push_str(&format!("<th>{}</th>", escape(cell)));
+                }
+                body.push_str("</tr>\n");
+                for row in rows {
+                    body.push_str("  <tr>");
+                    for cell in row {
+                        body.push_str(&format!("<td>{}</td>", escape(cell)));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</table>\n");
+            }
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Claude usage report</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        STYLE, body
+    )
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Block> {
+        vec![
+            Block::Title("Report".to_string()),
+            Block::Text("a < b".to_string()),
+            table(
+                &["Tool", "Calls"],
+                vec![vec!["Bash | sh".to_string(), "3".to_string()]],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let md = to_markdown(&sample());
+        assert!(md.starts_with("# Report\n\na < b\n\n"));
+        assert!(md.contains("| Tool | Calls |\n|---|---|\n| Bash \\| sh | 3 |\n"));
+    }
+
+    #[test]
+    fn test_html_escapes_text() {
+        let html = to_html(&sample());
+        assert!(html.contains("<p>a &lt; b</p>"));
+        assert!(html.contains("<td>Bash | sh</td><td>3</td>"));
+    }
+}
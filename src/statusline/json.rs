@@ -0,0 +1,142 @@
+use super::usage_tracker::Usage;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Everything the status line computes, for statusbars (waybar, polybar,
+/// xbar, ...) that render it themselves
+#[derive(Debug, Serialize)]
+pub struct StatusJson {
+    pub five_hour: Window,
+    pub seven_day: Window,
+    pub burn_rate_per_hour: f64,
+    pub estimated_seven_day_cost: f64,
+    /// Usage is counted from local transcripts, the limits may be stale
+    pub offline: bool,
+    /// Fetching usage failed and these are the numbers last fetched
+    pub stale: bool,
+    /// When the numbers (or, offline, the limits) were last fetched from
+    /// the API; unset when they are live
+    pub stale_since: Option<DateTime<Utc>>,
+    pub stale_minutes: Option<i64>,
+    pub model: Option<String>,
+    pub branch: Option<String>,
+}
+
+/// Usage of one rolling window
+#[derive(Debug, Serialize)]
+pub struct Window {
+    pub used: usize,
+    pub limit: usize,
+    pub percent: u8,
+    pub remaining: usize,
+    /// Minutes until the window resets; only known for the 5-hour window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes_remaining: Option<u32>,
+}
+
+impl Window {
+    fn new(used: usize, limit: usize, percent: u8, minutes_remaining: Option<u32>) -> Self {
+        Self {
+            used,
+            limit,
+            percent,
+            remaining: limit.saturating_sub(used),
+            minutes_remaining,
+        }
+    }
+}
+
+impl StatusJson {
+    /// `limits_fetched_at` is when the limits used offline were fetched
+    pub fn new(
+        usage: &Usage,
+        offline: bool,
+        limits_fetched_at: Option<DateTime<Utc>>,
+        model: Option<String>,
+        branch: Option<String>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let stale_since = if offline {
+            limits_fetched_at
+        } else {
+            usage.stale_since
+        };
+
+        Self {
+            five_hour: Window::new(
+                usage.five_hour_used,
+                usage.five_hour_limit,
+                usage.five_hour_percent,
+                Some(usage.five_hour_minutes_remaining),
+            ),
+            seven_day: Window::new(
+                usage.seven_day_used,
+                usage.seven_day_limit,
+                usage.seven_day_percent,
+                None,
+            ),
+            burn_rate_per_hour: usage.burn_rate_per_hour,
+            estimated_seven_day_cost: usage.estimated_seven_day_cost,
+            offline,
+            stale: !offline && usage.stale_since.is_some(),
+            stale_since,
+            stale_minutes: stale_since.map(|at| (now - at).num_minutes().max(0)),
+            model,
+            branch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn usage() -> Usage {
+        Usage {
+            five_hour_used: 14_000,
+            five_hour_limit: 20_000,
+            five_hour_percent: 70,
+            five_hour_minutes_remaining: 42,
+            seven_day_used: 130_000,
+            seven_day_limit: 200_000,
+            seven_day_percent: 65,
+            burn_rate_per_hour: 0.15,
+            estimated_seven_day_cost: 4.2,
+            stale_since: None,
+        }
+    }
+
+    #[test]
+    fn test_live_usage() {
+        let now = Utc::now();
+        let status = StatusJson::new(&usage(), false, None, Some("Opus".into()), None, now);
+        let value = serde_json::to_value(&status).unwrap();
+
+        assert_eq!(value["five_hour"]["remaining"], 6_000);
+        assert_eq!(value["five_hour"]["minutes_remaining"], 42);
+        assert!(value["seven_day"].get("minutes_remaining").is_none());
+        assert_eq!(value["seven_day"]["percent"], 65);
+        assert_eq!(value["burn_rate_per_hour"], 0.15);
+        assert_eq!(value["stale"], false);
+        assert!(value["stale_minutes"].is_null());
+        assert_eq!(value["model"], "Opus");
+        assert!(value["branch"].is_null());
+    }
+
+    #[test]
+    fn test_stale_and_offline_usage() {
+        let now = Utc::now();
+        let mut stale = usage();
+        stale.stale_since = Some(now - Duration::minutes(90));
+        let status = StatusJson::new(&stale, false, None, None, None, now);
+        assert!(status.stale && !status.offline);
+        assert_eq!(status.stale_minutes, Some(90));
+
+        let fetched = now - Duration::hours(3);
+        let status = StatusJson::new(&usage(), true, Some(fetched), None, None, now);
+        assert!(status.offline && !status.stale);
+        assert_eq!(status.stale_since, Some(fetched));
+        assert_eq!(status.stale_minutes, Some(180));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::analyzer::TokenUsage;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Length of the rolling usage block
+const FIVE_HOURS: i64 = 5;
+
+/// Tokens used in the current windows, counted from the transcripts Claude
+/// Code writes under `~/.claude/projects`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LocalUsage {
+    pub five_hour_tokens: usize,
+    pub seven_day_tokens: usize,
+    /// When the current 5-hour block started (its first message)
+    pub block_started: Option<DateTime<Utc>>,
+}
+
+impl LocalUsage {
+    /// Minutes until the current 5-hour block ends, 0 when there is none
+    pub fn minutes_until_reset(&self, now: DateTime<Utc>) -> u32 {
+        self.block_started
+            .map(|start| {
+                (start + Duration::hours(FIVE_HOURS) - now)
+                    .num_minutes()
+                    .max(0) as u32
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Directory holding Claude Code's per-project session transcripts
+pub fn transcripts_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".claude").join("projects"))
+}
+
+/// Sum the input and output tokens of every assistant response in the
+/// `.jsonl` transcripts under `root` that falls in the 5-hour and 7-day
+/// windows ending at `now`. A response written over several lines counts
+/// once. Unreadable files and lines are skipped.
+pub fn scan(root: &Path, now: DateTime<Utc>) -> LocalUsage {
+    let week_ago = now - Duration::days(7);
+    let block_start = now - Duration::hours(FIVE_HOURS);
+    let mut usage = LocalUsage::default();
+    let mut responses = HashSet::new();
+
+    let mut files = Vec::new();
+    collect_transcripts(root, SystemTime::from(week_ago), &mut files);
+
+    for path in files {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for line in contents.lines() {
+            let Some((at, id, tokens)) = parse_line(line) else {
+                continue;
+            };
+            if at < week_ago || at > now {
+                continue;
+            }
+            if id.is_some_and(|id| !responses.insert(id)) {
+                continue;
+            }
+            usage.seven_day_tokens += tokens;
+            if at >= block_start {
+                usage.five_hour_tokens += tokens;
+                usage.block_started = Some(usage.block_started.map_or(at, |s| s.min(at)));
+            }
+        }
+    }
+
+    usage
+}
+
+/// `.jsonl` files under `dir` modified after `since`
+fn collect_transcripts(dir: &Path, since: SystemTime, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_transcripts(&path, since, files);
+        } else if path.extension().is_some_and(|ext| ext == "jsonl")
+            && entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified >= since)
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Timestamp, response ID and input+output tokens of a transcript line
+/// that carries usage
+fn parse_line(line: &str) -> Option<(DateTime<Utc>, Option<String>, usize)> {
+    let json: serde_json::Value = serde_json::from_str(line).ok()?;
+    let at = DateTime::parse_from_rfc3339(json.get("timestamp")?.as_str()?)
+        .ok()?
+        .with_timezone(&Utc);
+    let message = json.get("message");
+    let usage = message
+        .and_then(|m| m.get("usage"))
+        .or_else(|| json.get("usage"))?;
+    let usage: TokenUsage = serde_json::from_value(usage.clone()).ok()?;
+    let id = message
+        .and_then(|m| m.get("id"))
+        .and_then(|id| id.as_str())
+        .map(str::to_string);
+
+    Some((at, id, usage.input_tokens + usage.output_tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("-home-me-app");
+        fs::create_dir_all(&project).unwrap();
+
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let line = |at: &str, input: u64, output: u64| {
+            format!(
+                r#"{{"type":"assistant","timestamp":"{}","message":{{"usage":{{"input_tokens":{},"output_tokens":{}}}}}}}"#,
+                at, input, output
+            )
+        };
+        let transcript = [
+            line("2026-10-16T09:30:00Z", 100, 50),
+            line("2026-10-16T11:00:00Z", 10, 5),
+            line("2026-10-13T08:00:00Z", 1000, 0),
+            // One response split over two lines
+            r#"{"type":"assistant","timestamp":"2026-10-14T08:00:00Z","message":{"id":"m1","usage":{"input_tokens":7,"output_tokens":3}}}"#.to_string(),
+            r#"{"type":"assistant","timestamp":"2026-10-14T08:00:01Z","message":{"id":"m1","usage":{"input_tokens":7,"output_tokens":3}}}"#.to_string(),
+            line("2026-10-01T08:00:00Z", 9999, 0),
+            r#"{"type":"user","timestamp":"2026-10-16T11:00:00Z"}"#.to_string(),
+            "not json".to_string(),
+        ]
+        .join("\n");
+        fs::write(project.join("session.jsonl"), transcript).unwrap();
+
+        let usage = scan(dir.path(), now);
+        assert_eq!(usage.five_hour_tokens, 165);
+        assert_eq!(usage.seven_day_tokens, 1175);
+        assert_eq!(usage.minutes_until_reset(now), 150);
+    }
+}
@@ -1,42 +1,129 @@
+mod json;
+mod local_usage;
+mod org_usage;
+mod segments;
 mod usage_tracker;
+mod watch;
 
 use crate::config::Config;
+use crate::db::Database;
+use crate::master::run_log::{self, RunStatus};
+use crate::style::{self, Colorize};
 use anyhow::{Context, Result};
-use colored::Colorize;
 use std::io::Write;
-use usage_tracker::UsageTracker;
+use std::sync::Arc;
+use tracing::debug;
 
 // Re-export for external use
-pub use usage_tracker::Usage;
+pub use json::StatusJson;
+pub use org_usage::{MemberUsage, OrgUsage};
+pub use segments::{validate_format, FORMAT_FIELDS};
+pub use usage_tracker::{Usage, UsageTracker};
+pub(crate) use watch::notify;
+pub use watch::WatchOptions;
+
+/// What `claude-helper statusline` prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusTarget {
+    /// One line formatted by `statusline.format`, for Claude Code
+    Line,
+    /// Every computed field as JSON, for other statusbars
+    Json,
+}
+
+impl StatusTarget {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "line" => Ok(Self::Line),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!("Invalid status line target: {} (use line or json)", s),
+        }
+    }
+}
 
 pub struct StatusLine {
     config: Config,
     tracker: UsageTracker,
+    db: Option<Arc<Database>>,
 }
 
 impl StatusLine {
     pub async fn new(config: Config) -> Result<Self> {
         let tracker = UsageTracker::new(config.clone()).await?;
 
-        Ok(Self { config, tracker })
+        Ok(Self {
+            config,
+            tracker,
+            db: None,
+        })
+    }
+
+    /// Use a shared database handle for historical data.
+    /// Without one, the status line never touches the database, keeping
+    /// `claude-helper statusline` fast.
+    pub fn with_database(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Print the status line for `target`
+    pub async fn render(&self, target: StatusTarget) -> Result<()> {
+        match target {
+            StatusTarget::Line => self.render_line().await,
+            StatusTarget::Json => self.render_json().await,
+        }
     }
 
     /// Render a single status line (for Claude Code integration)
     pub async fn render_line(&self) -> Result<()> {
+        let payload = segments::Payload::read();
         let usage = self.tracker.get_usage().await?;
 
-        // Format: [MOCK] [5h: 14k/20k 70%] [7d: 130k/200k 65%] $0.15/hr
-        // Note: Currently shows mock data until Claude.ai usage API is available
-        let line = format!(
-            "[MOCK] [5h: {}/{}k {}%] [7d: {}/{}k {}%] ${:.2}/hr",
-            usage.five_hour_used / 1000,
-            usage.five_hour_limit / 1000,
-            usage.five_hour_percent,
-            usage.seven_day_used / 1000,
-            usage.seven_day_limit / 1000,
-            usage.seven_day_percent,
-            usage.burn_rate_per_hour,
-        );
+        // Offline, usage is counted locally and the limits may be stale;
+        // after a failed fetch it is the last usage fetched
+        let source = if self.tracker.is_offline() {
+            format!("[OFFLINE{}]", self.stale_suffix())
+        } else if let Some(at) = usage.stale_since {
+            format!("[STALE {}]", ago(at))
+        } else {
+            String::new()
+        };
+        let format = &self.config.statusline.format;
+        let branch = if format.contains("{branch}") {
+            branch(payload.workspace)
+        } else {
+            None
+        };
+        let fields = [
+            ("source", source),
+            (
+                "five_hour",
+                format!(
+                    "{}/{}k {}%",
+                    usage.five_hour_used / 1000,
+                    usage.five_hour_limit / 1000,
+                    usage.five_hour_percent
+                ),
+            ),
+            (
+                "seven_day",
+                format!(
+                    "{}/{}k {}%",
+                    usage.seven_day_used / 1000,
+                    usage.seven_day_limit / 1000,
+                    usage.seven_day_percent
+                ),
+            ),
+            ("burn_rate", format!("${:.2}/hr", usage.burn_rate_per_hour)),
+            ("model", payload.model.unwrap_or_default()),
+            ("branch", branch.unwrap_or_default()),
+        ];
+        let mut line = segments::render(format, &fields);
+        if let Some(run) = active_run() {
+            line.push(' ');
+            line.push_str(&run.segment());
+        }
 
         print!("{}", line);
         std::io::stdout().flush()?;
@@ -44,12 +131,88 @@ impl StatusLine {
         Ok(())
     }
 
+    /// Print every computed field as one JSON object
+    pub async fn render_json(&self) -> Result<()> {
+        let payload = segments::Payload::read();
+        let usage = self.tracker.get_usage().await?;
+
+        let status = StatusJson::new(
+            &usage,
+            self.tracker.is_offline(),
+            self.tracker.last_live_at(),
+            payload.model,
+            branch(payload.workspace),
+            chrono::Utc::now(),
+        );
+        println!("{}", serde_json::to_string(&status)?);
+
+        Ok(())
+    }
+
     /// Show detailed status
     pub async fn show_status(&self, detailed: bool) -> Result<()> {
         let usage = self.tracker.get_usage().await?;
+        self.print_status(&usage, detailed).await
+    }
+
+    /// Show the organization's usage over the last 7 days, per member
+    pub async fn show_org_status(&self) -> Result<()> {
+        let usage = self.tracker.get_org_usage(7).await?;
+
+        println!("\n{}", "Organization Usage".bright_cyan().bold());
+        println!("{}", style::rule(60).bright_cyan());
+
+        println!("\n{}", "7-Day Total:".white().bold());
+        match usage.seven_day_percent() {
+            Some(percent) => {
+                println!("  {}", self.create_progress_bar(percent));
+                println!(
+                    "  Used: {} / {} tokens ({}%)",
+                    usage.seven_day_tokens, usage.seven_day_limit, percent
+                );
+            }
+            None => println!(
+                "  Used: {} tokens (set org.seven_day_limit to track a shared limit)",
+                usage.seven_day_tokens
+            ),
+        }
+
+        println!("\n{}", "By Member:".white().bold());
+        if usage.members.is_empty() {
+            println!("  No usage in the last 7 days");
+        }
+        for member in &usage.members {
+            let share = member.tokens as f64 / usage.total_tokens.max(1) as f64 * 100.0;
+            println!(
+                "  {:<40} {:>12} tokens {:>5.1}%",
+                member.member, member.tokens, share
+            );
+        }
+
+        Ok(())
+    }
 
+    async fn print_status(&self, usage: &Usage, detailed: bool) -> Result<()> {
         println!("\n{}", "Claude Usage Status".bright_cyan().bold());
-        println!("{}", "═".repeat(60).bright_cyan());
+        println!("{}", style::rule(60).bright_cyan());
+
+        if self.tracker.is_offline() {
+            let limits = match self.tracker.last_live_at() {
+                Some(_) => format!("limits as of{}", self.stale_suffix()),
+                None => "limits unknown until usage is fetched online".to_string(),
+            };
+            println!(
+                "\n  {} Offline: usage counted from local Claude Code transcripts, {}",
+                style::warn().yellow(),
+                limits
+            );
+        } else if let Some(at) = usage.stale_since {
+            println!(
+                "\n  {} Fetching usage failed, showing usage from {}",
+                style::warn().yellow(),
+                ago(at)
+            );
+        }
 
         // 5-hour block
         println!("\n{}", "Current 5-Hour Block:".white().bold());
@@ -88,19 +251,31 @@ impl StatusLine {
             );
 
             if usage.five_hour_percent > 80 {
-                println!("\n  {} You're using tokens quickly!", "⚠".yellow());
+                println!(
+                    "\n  {} You're using tokens quickly!",
+                    style::warn().yellow()
+                );
             }
         }
 
         if detailed {
-            self.show_detailed_breakdown(&usage).await?;
+            self.show_detailed_breakdown(usage).await?;
         }
 
         Ok(())
     }
 
-    /// Watch usage in real-time
-    pub async fn watch(&self, interval: u64) -> Result<()> {
+    /// How long ago the limits used offline were fetched, e.g. " 3h ago"
+    fn stale_suffix(&self) -> String {
+        self.tracker
+            .last_live_at()
+            .map(|at| format!(" {}", ago(at)))
+            .unwrap_or_default()
+    }
+
+    /// Watch usage in real-time, running the triggers in `options` when
+    /// usage crosses a threshold
+    pub async fn watch(&self, interval: u64, options: &WatchOptions) -> Result<()> {
         use tokio::time::{sleep, Duration};
 
         println!(
@@ -109,17 +284,36 @@ impl StatusLine {
         );
         println!();
 
+        let mut previous: Option<Usage> = None;
         loop {
+            let usage = self.tracker.get_usage().await?;
+            if options.until_reset
+                && previous
+                    .as_ref()
+                    .is_some_and(|p| watch::window_reset(p, &usage))
+            {
+                println!(
+                    "\n{}",
+                    format!("{} The 5-hour window has reset", style::ok()).green()
+                );
+                return Ok(());
+            }
+
             // Clear screen and move to top
             print!("\x1B[2J\x1B[1;1H");
 
-            self.show_status(false).await?;
+            self.print_status(&usage, false).await?;
 
             println!(
                 "\n{}",
                 format!("Updating every {} seconds...", interval).italic()
             );
 
+            for crossing in watch::crossings(previous.as_ref(), &usage, &options.thresholds) {
+                watch::trigger(&crossing, options).await;
+            }
+            previous = Some(usage);
+
             sleep(Duration::from_secs(interval)).await;
         }
     }
@@ -175,7 +369,10 @@ impl StatusLine {
         let updated_content = serde_json::to_string_pretty(&settings)?;
         fs::write(&settings_path, updated_content).context("Failed to write Claude settings")?;
 
-        println!("{}", "✓ Status line integration installed!".green());
+        println!(
+            "{}",
+            format!("{} Status line integration installed!", style::ok()).green()
+        );
         println!("\nRestart Claude Code to see the status line.");
 
         Ok(())
@@ -185,7 +382,11 @@ impl StatusLine {
         let filled = (percent as usize * 40) / 100;
         let empty = 40 - filled;
 
-        let bar = format!("[{}{}]", "█".repeat(filled), "░".repeat(empty));
+        let bar = format!(
+            "[{}{}]",
+            style::pick("█", "#").repeat(filled),
+            style::pick("░", ".").repeat(empty)
+        );
 
         if percent >= 90 {
             bar.bright_red().to_string()
@@ -197,14 +398,12 @@ impl StatusLine {
     }
 
     async fn show_detailed_breakdown(&self, _usage: &usage_tracker::Usage) -> Result<()> {
-        use crate::db::Database;
-
         println!("\n{}", "Detailed Breakdown:".white().bold());
-        println!("{}", "─".repeat(60).bright_cyan());
+        println!("{}", style::line(60).bright_cyan());
 
         // Try to connect to database and show historical data
-        match Database::new(&self.config).await {
-            Ok(db) => {
+        match &self.db {
+            Some(db) => {
                 // Show hourly breakdown
                 println!("\n{}", "Token Usage (Last 24 Hours):".white());
                 match db.get_hourly_breakdown(24).await {
@@ -213,7 +412,7 @@ impl StatusLine {
                             let hour_str = entry.hour.format("%Y-%m-%d %H:%M");
                             let tokens_k = entry.total_tokens / 1000;
                             let bar_len = (entry.total_tokens / 500).min(20);
-                            let bar = "▓".repeat(bar_len);
+                            let bar = style::pick("▓", "#").repeat(bar_len);
 
                             println!(
                                 "  {} | {}k tokens | {} tasks | {}",
@@ -234,13 +433,13 @@ impl StatusLine {
 
                 // Show recent tasks
                 println!("\n{}", "Recent Task Executions:".white());
-                match db.get_recent_tasks(5).await {
+                match db.get_recent_tasks(5, None, None).await {
                     Ok(tasks) if !tasks.is_empty() => {
                         for task in tasks {
                             let status = if task.success {
-                                "✓".green()
+                                style::ok().green()
                             } else {
-                                "✗".red()
+                                style::fail().red()
                             };
                             let time_str = task.timestamp.format("%H:%M:%S");
                             let desc = if task.description.len() > 40 {
@@ -266,7 +465,7 @@ impl StatusLine {
                     }
                 }
             }
-            Err(_) => {
+            None => {
                 // Database not available, show basic info
                 println!("\n  API endpoint: {}", self.config.statusline.api_endpoint);
                 println!("  Database not initialized - no historical data available");
@@ -281,3 +480,29 @@ impl StatusLine {
         self.tracker.get_usage().await
     }
 }
+
+/// The Master Coder run in progress, if any. Reading the run logs never
+/// fails the status line.
+fn active_run() -> Option<RunStatus> {
+    run_log::active_run()
+        .inspect_err(|e| debug!("No run status: {:#}", e))
+        .ok()
+        .flatten()
+}
+
+/// Git branch of the session's workspace, or of the current directory
+fn branch(workspace: Option<std::path::PathBuf>) -> Option<String> {
+    workspace
+        .or_else(|| std::env::current_dir().ok())
+        .and_then(|dir| segments::git_branch(&dir))
+}
+
+/// How long ago `at` was, e.g. "5m ago"
+pub fn ago(at: chrono::DateTime<chrono::Utc>) -> String {
+    let age = chrono::Utc::now() - at;
+    match age.num_hours() {
+        0 => format!("{}m ago", age.num_minutes().max(0)),
+        hours if hours < 48 => format!("{}h ago", hours),
+        _ => format!("{}d ago", age.num_days()),
+    }
+}
@@ -0,0 +1,365 @@
+use crate::config::Config;
+use crate::error::ClaudeHelperError;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Most members listed; the rest are summed up as one entry
+const TOP_MEMBERS: usize = 20;
+
+/// Name for tokens of API keys without a known creator
+const UNATTRIBUTED: &str = "(unattributed)";
+
+/// Token consumption of the whole organization, from the Admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgUsage {
+    pub days: usize,
+    /// Tokens used in the last `days` days
+    pub total_tokens: usize,
+    /// Tokens used in the last 7 days, counted against `seven_day_limit`
+    pub seven_day_tokens: usize,
+    /// Shared 7-day limit from `org.seven_day_limit`; 0 without one
+    pub seven_day_limit: usize,
+    /// Tokens per member over `days`, most first
+    pub members: Vec<MemberUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberUsage {
+    /// Email of the member who created the API keys, or the key's name
+    pub member: String,
+    pub tokens: usize,
+}
+
+impl OrgUsage {
+    pub fn seven_day_percent(&self) -> Option<u8> {
+        (self.seven_day_limit > 0).then(|| {
+            (self.seven_day_tokens as f64 / self.seven_day_limit as f64 * 100.0).clamp(0.0, 100.0)
+                as u8
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UsagePage {
+    data: Vec<UsageBucket>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    next_page: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageBucket {
+    starting_at: DateTime<Utc>,
+    results: Vec<UsageResult>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct UsageResult {
+    #[serde(default)]
+    api_key_id: Option<String>,
+    #[serde(default)]
+    uncached_input_tokens: usize,
+    #[serde(default)]
+    cache_read_input_tokens: usize,
+    #[serde(default)]
+    cache_creation: CacheCreation,
+    #[serde(default)]
+    output_tokens: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CacheCreation {
+    #[serde(default)]
+    ephemeral_5m_input_tokens: usize,
+    #[serde(default)]
+    ephemeral_1h_input_tokens: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListPage<T> {
+    data: Vec<T>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    last_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKey {
+    id: String,
+    name: String,
+    #[serde(default)]
+    created_by: Option<Actor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Actor {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: String,
+    email: String,
+}
+
+impl UsageResult {
+    fn tokens(&self) -> usize {
+        self.uncached_input_tokens
+            + self.cache_read_input_tokens
+            + self.cache_creation.ephemeral_5m_input_tokens
+            + self.cache_creation.ephemeral_1h_input_tokens
+            + self.output_tokens
+    }
+}
+
+/// Organization usage over the last `days` days (and at least the last 7,
+/// for the shared limit)
+pub async fn fetch(client: &Client, config: &Config, days: usize) -> Result<OrgUsage> {
+    config
+        .network
+        .ensure_online("Fetching organization usage")?;
+    let admin_key = config.org.get_admin_key()?.context(
+        "No Admin API key; run `claude-helper config set-admin-key` or set ANTHROPIC_ADMIN_KEY",
+    )?;
+    let admin = AdminApi {
+        client,
+        endpoint: config.org.api_endpoint.trim_end_matches('/'),
+        key: &admin_key,
+    };
+
+    let now = Utc::now();
+    let buckets = admin
+        .usage(now - Duration::days(days.max(7) as i64))
+        .await?;
+    let keys: Vec<ApiKey> = admin.list("/v1/organizations/api_keys").await?;
+    let users: Vec<User> = admin.list("/v1/organizations/users").await?;
+
+    Ok(aggregate(
+        &buckets,
+        &keys,
+        &users,
+        now,
+        days,
+        config.org.seven_day_limit,
+    ))
+}
+
+struct AdminApi<'a> {
+    client: &'a Client,
+    endpoint: &'a str,
+    key: &'a str,
+}
+
+impl AdminApi<'_> {
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.endpoint, path))
+            .header("x-api-key", self.key)
+            .header("anthropic-version", "2023-06-01")
+            .query(query)
+            .send()
+            .await
+            .context("Failed to reach the Admin API")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(ClaudeHelperError::ApiError {
+                status: status.as_u16(),
+                message: response.text().await.unwrap_or_default(),
+            }
+            .into());
+        }
+
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse the Admin API response of {}", path))
+    }
+
+    /// Daily token buckets per API key since `since`
+    async fn usage(&self, since: DateTime<Utc>) -> Result<Vec<UsageBucket>> {
+        let mut buckets = Vec::new();
+        let mut page = None;
+        loop {
+            let mut query = vec![
+                ("starting_at", since.to_rfc3339()),
+                ("bucket_width", "1d".to_string()),
+                ("group_by[]", "api_key_id".to_string()),
+                ("limit", "31".to_string()),
+            ];
+            if let Some(page) = page {
+                query.push(("page", page));
+            }
+            let response: UsagePage = self
+                .get("/v1/organizations/usage_report/messages", &query)
+                .await?;
+            buckets.extend(response.data);
+            match response.next_page {
+                Some(next) if response.has_more => page = Some(next),
+                _ => return Ok(buckets),
+            }
+        }
+    }
+
+    /// Every entry of a paginated list endpoint
+    async fn list<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut after = None;
+        loop {
+            let mut query = vec![("limit", "100".to_string())];
+            if let Some(after) = after {
+                query.push(("after_id", after));
+            }
+            let page: ListPage<T> = self.get(path, &query).await?;
+            items.extend(page.data);
+            match page.last_id {
+                Some(last) if page.has_more => after = Some(last),
+                _ => return Ok(items),
+            }
+        }
+    }
+}
+
+/// Sum the buckets per member: the user who created each API key, else the
+/// key's name
+fn aggregate(
+    buckets: &[UsageBucket],
+    keys: &[ApiKey],
+    users: &[User],
+    now: DateTime<Utc>,
+    days: usize,
+    seven_day_limit: usize,
+) -> OrgUsage {
+    let emails: HashMap<&str, &str> = users
+        .iter()
+        .map(|user| (user.id.as_str(), user.email.as_str()))
+        .collect();
+    let member_of = |key_id: Option<&str>| -> String {
+        let key = key_id.and_then(|id| keys.iter().find(|key| key.id == id));
+        match key {
+            Some(key) => key
+                .created_by
+                .as_ref()
+                .and_then(|actor| emails.get(actor.id.as_str()))
+                .map_or_else(|| key.name.clone(), |email| email.to_string()),
+            None => UNATTRIBUTED.to_string(),
+        }
+    };
+
+    let period_start = now - Duration::days(days as i64);
+    let week_start = now - Duration::days(7);
+    let mut usage = OrgUsage {
+        days,
+        total_tokens: 0,
+        seven_day_tokens: 0,
+        seven_day_limit,
+        members: Vec::new(),
+    };
+    let mut members: HashMap<String, usize> = HashMap::new();
+
+    for bucket in buckets {
+        // Daily buckets start at midnight, so the first one partly precedes
+        // the period; it counts as a whole
+        let in_period = bucket.starting_at + Duration::days(1) > period_start;
+        let in_week = bucket.starting_at + Duration::days(1) > week_start;
+        for result in &bucket.results {
+            let tokens = result.tokens();
+            if in_week {
+                usage.seven_day_tokens += tokens;
+            }
+            if in_period {
+                usage.total_tokens += tokens;
+                *members
+                    .entry(member_of(result.api_key_id.as_deref()))
+                    .or_default() += tokens;
+            }
+        }
+    }
+
+    let mut members: Vec<MemberUsage> = members
+        .into_iter()
+        .filter(|(_, tokens)| *tokens > 0)
+        .map(|(member, tokens)| MemberUsage { member, tokens })
+        .collect();
+    members.sort_by(|a, b| {
+        b.tokens
+            .cmp(&a.tokens)
+            .then_with(|| a.member.cmp(&b.member))
+    });
+    if members.len() > TOP_MEMBERS {
+        let others: usize = members.drain(TOP_MEMBERS..).map(|m| m.tokens).sum();
+        members.push(MemberUsage {
+            member: "(others)".to_string(),
+            tokens: others,
+        });
+    }
+    usage.members = members;
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_aggregate_by_member() {
+        let buckets: Vec<UsageBucket> = serde_json::from_value(serde_json::json!([
+            {
+                "starting_at": "2026-10-16T00:00:00Z",
+                "results": [
+                    {"api_key_id": "key_a", "uncached_input_tokens": 100, "output_tokens": 50,
+                     "cache_read_input_tokens": 10,
+                     "cache_creation": {"ephemeral_5m_input_tokens": 5, "ephemeral_1h_input_tokens": 0}},
+                    {"api_key_id": "key_b", "uncached_input_tokens": 40, "output_tokens": 0},
+                    {"api_key_id": null, "uncached_input_tokens": 7, "output_tokens": 0}
+                ]
+            },
+            {
+                "starting_at": "2026-10-12T00:00:00Z",
+                "results": [{"api_key_id": "key_a", "uncached_input_tokens": 1000, "output_tokens": 0}]
+            }
+        ]))
+        .unwrap();
+        let keys: Vec<ApiKey> = serde_json::from_value(serde_json::json!([
+            {"id": "key_a", "name": "ci", "created_by": {"id": "user_1", "type": "user"}},
+            {"id": "key_b", "name": "batch-jobs", "created_by": {"id": "user_gone", "type": "user"}}
+        ]))
+        .unwrap();
+        let users: Vec<User> = serde_json::from_value(serde_json::json!([
+            {"id": "user_1", "email": "ana@example.com"}
+        ]))
+        .unwrap();
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let usage = aggregate(&buckets, &keys, &users, now, 1, 10_000);
+
+        assert_eq!(usage.total_tokens, 212);
+        assert_eq!(usage.seven_day_tokens, 1212);
+        assert_eq!(usage.seven_day_percent(), Some(12));
+        assert_eq!(
+            usage.members,
+            vec![
+                MemberUsage {
+                    member: "ana@example.com".to_string(),
+                    tokens: 165
+                },
+                MemberUsage {
+                    member: "batch-jobs".to_string(),
+                    tokens: 40
+                },
+                MemberUsage {
+                    member: UNATTRIBUTED.to_string(),
+                    tokens: 7
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::project;
+use anyhow::Result;
+use regex::Regex;
+use serde_json::Value;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Placeholders accepted by `statusline.format`
+pub const FORMAT_FIELDS: &[&str] = &[
+    "source",
+    "five_hour",
+    "seven_day",
+    "burn_rate",
+    "model",
+    "branch",
+];
+
+/// How long to wait for Claude Code's payload on stdin
+const PAYLOAD_TIMEOUT: Duration = Duration::from_millis(300);
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// Brackets left empty by a segment without a value, e.g. `[]` outside a repo
+static EMPTY_GROUP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\s*\]|\(\s*\)").unwrap());
+
+/// What Claude Code tells its status line command on stdin
+#[derive(Debug, Default, PartialEq)]
+pub struct Payload {
+    /// Display name of the active model, e.g. "Opus"
+    pub model: Option<String>,
+    /// Directory the session works in
+    pub workspace: Option<PathBuf>,
+}
+
+impl Payload {
+    pub fn parse(input: &str) -> Self {
+        let Ok(value) = serde_json::from_str::<Value>(input) else {
+            return Self::default();
+        };
+        let text = |pointer: &str| {
+            value
+                .pointer(pointer)
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+
+        Self {
+            model: text("/model/display_name").or_else(|| text("/model/id")),
+            workspace: text("/workspace/current_dir")
+                .or_else(|| text("/cwd"))
+                .map(PathBuf::from),
+        }
+    }
+
+    /// The payload piped in by Claude Code; empty when run from a terminal
+    /// or when nothing arrives in time
+    pub fn read() -> Self {
+        if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+            return Self::default();
+        }
+
+        // A plain thread, so a stdin that never closes can't hold up exit
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut input = String::new();
+            if std::io::stdin().read_to_string(&mut input).is_ok() {
+                let _ = tx.send(input);
+            }
+        });
+
+        rx.recv_timeout(PAYLOAD_TIMEOUT)
+            .map(|input| Self::parse(&input))
+            .unwrap_or_default()
+    }
+}
+
+/// Current branch of the repository containing `dir`, or the short commit
+/// hash when HEAD is detached. Reads `.git/HEAD` directly, which is much
+/// quicker than running git on every refresh.
+pub fn git_branch(dir: &Path) -> Option<String> {
+    let git_dir = project::git_dir(dir)?;
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(
+                reference
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(reference)
+                    .to_string(),
+            )
+        }
+        None => Some(head.chars().take(7).collect()).filter(|s: &String| !s.is_empty()),
+    }
+}
+
+/// Fill the placeholders of `format` with `fields`. Segments without a value
+/// disappear together with brackets directly around them.
+pub fn render(format: &str, fields: &[(&str, String)]) -> String {
+    let filled = PLACEHOLDER.replace_all(format, |caps: &regex::Captures| {
+        fields
+            .iter()
+            .find(|(name, _)| *name == &caps[1])
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    });
+    let trimmed = EMPTY_GROUP.replace_all(&filled, "");
+
+    trimmed.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Check that `format` only uses known placeholders
+pub fn validate_format(format: &str) -> Result<()> {
+    for caps in PLACEHOLDER.captures_iter(format) {
+        if !FORMAT_FIELDS.contains(&&caps[1]) {
+            anyhow::bail!(
+                "statusline.format has an unknown placeholder {{{}}} (expected one of: {})",
+                &caps[1],
+                FORMAT_FIELDS.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_payload() {
+        let payload = Payload::parse(
+            r#"{"model": {"id": "claude-opus-4-1", "display_name": "Opus"},
+                "workspace": {"current_dir": "/work/app", "project_dir": "/work"},
+                "cwd": "/elsewhere"}"#,
+        );
+        assert_eq!(payload.model.as_deref(), Some("Opus"));
+        assert_eq!(payload.workspace, Some(PathBuf::from("/work/app")));
+
+        let payload = Payload::parse(r#"{"model": {"id": "claude-sonnet-4"}, "cwd": "/tmp"}"#);
+        assert_eq!(payload.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(payload.workspace, Some(PathBuf::from("/tmp")));
+
+        assert_eq!(Payload::parse("not json"), Payload::default());
+    }
+
+    #[test]
+    fn test_git_branch() {
+        let repo = tempfile::tempdir().unwrap();
+        let git = repo.path().join(".git");
+        std::fs::create_dir(&git).unwrap();
+        std::fs::write(git.join("HEAD"), "ref: refs/heads/feature/login\n").unwrap();
+        let nested = repo.path().join("src");
+        std::fs::create_dir(&nested).unwrap();
+        assert_eq!(git_branch(&nested).as_deref(), Some("feature/login"));
+
+        // A worktree points at its own HEAD
+        let worktree = tempfile::tempdir().unwrap();
+        let worktree_git = repo.path().join("worktrees-wt");
+        std::fs::create_dir(&worktree_git).unwrap();
+        std::fs::write(worktree_git.join("HEAD"), "0123456789abcdef\n").unwrap();
+        std::fs::write(
+            worktree.path().join(".git"),
+            format!("gitdir: {}\n", worktree_git.display()),
+        )
+        .unwrap();
+        assert_eq!(git_branch(worktree.path()).as_deref(), Some("0123456"));
+    }
+
+    #[test]
+    fn test_render_format() {
+        let fields = [
+            ("model", "Opus".to_string()),
+            ("branch", String::new()),
+            ("burn_rate", "$0.15/hr".to_string()),
+        ];
+        assert_eq!(
+            render("[{model}] ({branch}) {burn_rate}", &fields),
+            "[Opus] $0.15/hr"
+        );
+
+        assert!(validate_format("{model} {branch}").is_ok());
+        assert!(validate_format("{modle}").is_err());
+    }
+}
@@ -1,9 +1,24 @@
+use super::local_usage;
+use super::org_usage::{self, OrgUsage};
+use crate::cache::{namespace, Cache};
+use crate::config::Config;
+use crate::error::ClaudeHelperError;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tracing::{debug, warn};
-use crate::cache::Cache;
-use crate::config::Config;
+
+/// How long the last usage fetched from the API is kept for offline mode
+/// and as the fallback when a fetch fails
+const LAST_USAGE_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Attempts at fetching usage before falling back to the last snapshot
+const FETCH_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry; doubles with each further one
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
@@ -18,6 +33,19 @@ pub struct Usage {
 
     pub burn_rate_per_hour: f64,
     pub estimated_seven_day_cost: f64,
+
+    /// Set when fetching usage failed and these are the numbers last
+    /// fetched, at this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_since: Option<DateTime<Utc>>,
+}
+
+/// The most recent usage fetched from the API, kept so offline mode knows
+/// the window limits and a failed fetch has something to show
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LastUsage {
+    usage: Usage,
+    fetched_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,44 +76,143 @@ pub struct UsageTracker {
 
 impl UsageTracker {
     pub async fn new(config: Config) -> Result<Self> {
-        let client = Client::builder()
+        let client = config
+            .network
+            .client_builder()?
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .context("Failed to create HTTP client")?;
 
         let cache = Cache::new()?;
 
-        Ok(Self { config, client, cache })
+        Ok(Self {
+            config,
+            client,
+            cache,
+        })
     }
 
     pub async fn get_usage(&self) -> Result<Usage> {
-        // Try to get from cache first (5 second TTL)
-        if let Ok(Some(usage)) = self.cache.get::<Usage>("usage") {
-            debug!("Using cached usage data");
-            return Ok(usage);
+        if self.is_offline() {
+            return Ok(self.local_usage());
         }
 
-        // Try to fetch from Claude API
-        match self.fetch_from_api().await {
-            Ok(usage) => {
-                // Cache the result for 5 seconds
-                if let Err(e) = self.cache.set("usage", usage.clone(), 5) {
-                    warn!("Failed to cache usage data: {}", e);
+        match self.get_live_usage().await {
+            Ok(usage) => Ok(usage),
+            Err(e) => match self.last_live_usage() {
+                Some(last) => {
+                    warn!(
+                        "Fetching usage failed ({:#}), showing usage from {}",
+                        e, last.fetched_at
+                    );
+                    Ok(Usage {
+                        stale_since: Some(last.fetched_at),
+                        ..last.usage
+                    })
+                }
+                None => Err(e.context("No earlier usage to fall back to")),
+            },
+        }
+    }
+
+    /// Current usage from the API, without the stale fallback of [`Self::get_usage`]
+    pub async fn get_live_usage(&self) -> Result<Usage> {
+        self.config.network.ensure_online("Fetching usage")?;
+        let cache_key = format!("{}/current", namespace::USAGE);
+
+        // Cached for 5 seconds; concurrent callers share a single API request
+        self.cache
+            .get_or_fetch(&cache_key, 5, || async {
+                let usage = self.fetch_with_retries().await?;
+                let last = LastUsage {
+                    usage: usage.clone(),
+                    fetched_at: Utc::now(),
+                };
+                if let Err(e) = self
+                    .cache
+                    .set(&Self::last_usage_key(), last, LAST_USAGE_TTL_SECS)
+                {
+                    debug!("Failed to remember usage for offline mode: {}", e);
                 }
                 Ok(usage)
-            }
-            Err(e) => {
-                // TEMPORARY: Use mock data as fallback until correct API endpoint is verified
-                // TODO: Find correct Claude.ai usage API endpoint or parse from session files
-                warn!("API fetch failed ({}), using mock data as fallback", e);
-                debug!("This is expected in alpha - API endpoint verification in progress");
-                Ok(self.mock_usage())
+            })
+            .await
+    }
+
+    /// Usage of the whole organization over the last `days` days, from the
+    /// Admin API (needs an Admin API key, see `org` in the config)
+    pub async fn get_org_usage(&self, days: usize) -> Result<OrgUsage> {
+        org_usage::fetch(&self.client, &self.config, days).await
+    }
+
+    /// Whether network access is disabled, so usage comes from local transcripts
+    pub fn is_offline(&self) -> bool {
+        self.config.network.offline
+    }
+
+    /// When usage was last fetched from the API, i.e. how old the limits
+    /// shown in offline mode are
+    pub fn last_live_at(&self) -> Option<DateTime<Utc>> {
+        self.last_live_usage().map(|last| last.fetched_at)
+    }
+
+    fn last_live_usage(&self) -> Option<LastUsage> {
+        self.cache.get(&Self::last_usage_key()).ok().flatten()
+    }
+
+    fn last_usage_key() -> String {
+        format!("{}/last", namespace::USAGE)
+    }
+
+    /// Usage counted from local Claude Code transcripts, with the limits of
+    /// the last usage fetched from the API (0 when it never was)
+    fn local_usage(&self) -> Usage {
+        let now = Utc::now();
+        let local = local_usage::transcripts_dir()
+            .map(|dir| local_usage::scan(&dir, now))
+            .unwrap_or_default();
+        let (five_hour_limit, seven_day_limit) = self.last_live_usage().map_or((0, 0), |last| {
+            (last.usage.five_hour_limit, last.usage.seven_day_limit)
+        });
+
+        let cost_per_million = crate::agents::DEFAULT_COST_PER_MILLION;
+        Usage {
+            five_hour_used: local.five_hour_tokens,
+            five_hour_limit,
+            five_hour_percent: percent(local.five_hour_tokens, five_hour_limit),
+            five_hour_minutes_remaining: local.minutes_until_reset(now),
+
+            seven_day_used: local.seven_day_tokens,
+            seven_day_limit,
+            seven_day_percent: percent(local.seven_day_tokens, seven_day_limit),
+
+            burn_rate_per_hour: (local.five_hour_tokens as f64 / 5.0 / 1_000_000.0)
+                * cost_per_million,
+            estimated_seven_day_cost: (local.seven_day_tokens as f64 / 1_000_000.0)
+                * cost_per_million,
+            stale_since: None,
+        }
+    }
+
+    /// Fetch usage, retrying with backoff while failures look transient
+    async fn fetch_with_retries(&self) -> Result<Usage> {
+        let mut attempt = 1;
+        loop {
+            match self.fetch_from_api().await {
+                Ok(usage) => return Ok(usage),
+                Err(e) if attempt < FETCH_ATTEMPTS && is_transient(&e) => {
+                    let wait = RETRY_BACKOFF * 2u32.pow(attempt - 1);
+                    debug!("Fetching usage failed ({:#}), retrying in {:?}", e, wait);
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
             }
         }
     }
 
     async fn fetch_from_api(&self) -> Result<Usage> {
-        let token = self.config.auth.get_token().await?;
+        let token = self.config.auth.get_token(&self.config.network).await?;
 
         // Note: This endpoint might not be the correct one
         // You'd need to find the actual Claude usage API endpoint
@@ -98,7 +225,12 @@ impl UsageTracker {
             .context("Failed to fetch usage data")?;
 
         if !response.status().is_success() {
-            anyhow::bail!("API returned error: {}", response.status());
+            let status = response.status();
+            return Err(ClaudeHelperError::ApiError {
+                status: status.as_u16(),
+                message: response.text().await.unwrap_or_default(),
+            }
+            .into());
         }
 
         let usage_response: ClaudeUsageResponse = response
@@ -119,33 +251,21 @@ impl UsageTracker {
     // - Test percentage overflow (255+)
     // - Test floating point precision issues
     fn convert_response(&self, response: ClaudeUsageResponse) -> Usage {
-        // Calculate percentages with division by zero protection and clamping to 0-100 range
-        let five_hour_percent = if response.usage.five_hour.limit == 0 {
-            0
-        } else {
-            let percent = (response.usage.five_hour.used as f64
-                / response.usage.five_hour.limit as f64)
-                * 100.0;
-            percent.clamp(0.0, 100.0) as u8
-        };
-
-        let seven_day_percent = if response.usage.seven_day.limit == 0 {
-            0
-        } else {
-            let percent = (response.usage.seven_day.used as f64
-                / response.usage.seven_day.limit as f64)
-                * 100.0;
-            percent.clamp(0.0, 100.0) as u8
-        };
+        let five_hour_percent = percent(
+            response.usage.five_hour.used,
+            response.usage.five_hour.limit,
+        );
+        let seven_day_percent = percent(
+            response.usage.seven_day.used,
+            response.usage.seven_day.limit,
+        );
 
         // Calculate burn rate (tokens per hour)
         let burn_rate_tokens = response.usage.five_hour.used as f64 / 5.0;
 
         // Estimate cost (this would use actual pricing from LiteLLM or similar)
-        // Using rough estimate: $3 per million input tokens, $15 per million output tokens
-        // Assuming 50/50 split for simplicity
         // TODO: Use actual input/output token split for accurate cost calculation
-        let avg_cost_per_million = 9.0; // Average of input and output
+        let avg_cost_per_million = crate::agents::DEFAULT_COST_PER_MILLION;
         let burn_rate_cost = (burn_rate_tokens / 1_000_000.0) * avg_cost_per_million;
 
         let estimated_seven_day_cost =
@@ -155,7 +275,9 @@ impl UsageTracker {
             five_hour_used: response.usage.five_hour.used,
             five_hour_limit: response.usage.five_hour.limit,
             five_hour_percent,
-            five_hour_minutes_remaining: 60, // Would calculate from reset_at
+            five_hour_minutes_remaining: Self::minutes_until(
+                response.usage.five_hour.reset_at.as_deref(),
+            ),
 
             seven_day_used: response.usage.seven_day.used,
             seven_day_limit: response.usage.seven_day.limit,
@@ -163,26 +285,63 @@ impl UsageTracker {
 
             burn_rate_per_hour: burn_rate_cost,
             estimated_seven_day_cost,
+            stale_since: None,
         }
     }
 
-    /// Returns mock usage data for testing and as temporary fallback.
-    /// TEMPORARY: Used as fallback when API endpoint is unavailable (alpha version).
-    /// TODO: Remove this fallback once correct API endpoint is verified.
-    fn mock_usage(&self) -> Usage {
-        // Mock data for explicit testing only
-        Usage {
-            five_hour_used: 14_000,
-            five_hour_limit: 20_000,
-            five_hour_percent: 70,
-            five_hour_minutes_remaining: 120,
+    /// Minutes until the given RFC 3339 reset time, defaulting to 60 when unknown
+    fn minutes_until(reset_at: Option<&str>) -> u32 {
+        reset_at
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|reset| {
+                let remaining = reset.with_timezone(&chrono::Utc) - chrono::Utc::now();
+                remaining.num_minutes().max(0) as u32
+            })
+            .unwrap_or(60)
+    }
+}
 
-            seven_day_used: 130_000,
-            seven_day_limit: 200_000,
-            seven_day_percent: 65,
+/// Share of `limit` used, with division by zero protection and clamping to
+/// the 0-100 range
+fn percent(used: usize, limit: usize) -> u8 {
+    if limit == 0 {
+        return 0;
+    }
+    ((used as f64 / limit as f64) * 100.0).clamp(0.0, 100.0) as u8
+}
 
-            burn_rate_per_hour: 0.15,
-            estimated_seven_day_cost: 1.17,
+/// Whether a failed fetch may succeed when tried again: the request did not
+/// get through, or the API was overloaded, rate limiting or failing
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(ClaudeHelperError::ApiError { status, .. }) = cause.downcast_ref() {
+            return *status == 429 || *status >= 500;
         }
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout() || e.is_connect() || e.is_request())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_errors() {
+        let api = |status| -> anyhow::Error {
+            anyhow::Error::from(ClaudeHelperError::ApiError {
+                status,
+                message: String::new(),
+            })
+            .context("Failed to fetch usage data")
+        };
+
+        assert!(is_transient(&api(429)));
+        assert!(is_transient(&api(503)));
+        assert!(!is_transient(&api(401)));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "Failed to parse usage response"
+        )));
     }
 }
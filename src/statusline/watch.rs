@@ -0,0 +1,194 @@
+use super::Usage;
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use tracing::warn;
+
+/// What `claude-helper watch` does besides redrawing the status
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Usage percentages that trigger `notify` and `exec` when crossed
+    pub thresholds: Vec<u8>,
+    /// Show a desktop notification on each crossing
+    pub notify: bool,
+    /// Shell command to run on each crossing
+    pub exec: Option<String>,
+    /// Stop once the 5-hour window resets
+    pub until_reset: bool,
+}
+
+/// A usage window rising past one of the thresholds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crossing {
+    /// `5h` or `7d`
+    pub window: &'static str,
+    pub threshold: u8,
+    pub percent: u8,
+}
+
+impl Crossing {
+    fn message(&self) -> String {
+        format!(
+            "{} usage passed {}% (now {}%)",
+            self.window, self.threshold, self.percent
+        )
+    }
+}
+
+/// Thresholds crossed between two readings. The first reading counts as
+/// rising from zero, so limits that are already exceeded fire right away.
+pub fn crossings(previous: Option<&Usage>, current: &Usage, thresholds: &[u8]) -> Vec<Crossing> {
+    let windows = [
+        (
+            "5h",
+            previous.map_or(0, |p| p.five_hour_percent),
+            current.five_hour_percent,
+        ),
+        (
+            "7d",
+            previous.map_or(0, |p| p.seven_day_percent),
+            current.seven_day_percent,
+        ),
+    ];
+
+    windows
+        .into_iter()
+        .flat_map(|(window, before, now)| {
+            thresholds
+                .iter()
+                .filter(move |&&t| before < t && now >= t)
+                .map(move |&threshold| Crossing {
+                    window,
+                    threshold,
+                    percent: now,
+                })
+        })
+        .collect()
+}
+
+/// Whether the 5-hour window started over between two readings: usage
+/// went down, or the time left jumped back up
+pub fn window_reset(previous: &Usage, current: &Usage) -> bool {
+    current.five_hour_used < previous.five_hour_used
+        || current.five_hour_minutes_remaining > previous.five_hour_minutes_remaining
+}
+
+/// Run the configured triggers for one crossing. Failures are logged and
+/// do not stop watching.
+pub async fn trigger(crossing: &Crossing, options: &WatchOptions) {
+    if options.notify {
+        if let Err(e) = notify(&crossing.message()).await {
+            warn!("Desktop notification failed: {:#}", e);
+        }
+    }
+
+    if let Some(command) = &options.exec {
+        let status = shell(command)
+            .env("CLAUDE_HELPER_WINDOW", crossing.window)
+            .env("CLAUDE_HELPER_THRESHOLD", crossing.threshold.to_string())
+            .env("CLAUDE_HELPER_PERCENT", crossing.percent.to_string())
+            .stdin(Stdio::null())
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!("`{}` exited with {}", command, status),
+            Err(e) => warn!("Failed to run `{}`: {}", command, e),
+        }
+    }
+
+    println!("{} {}", style::warn().yellow(), crossing.message());
+}
+
+fn shell(command: &str) -> tokio::process::Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut cmd = tokio::process::Command::new(shell);
+    cmd.arg(flag).arg(command);
+    cmd
+}
+
+/// Show a desktop notification with the platform's own tool
+pub(crate) async fn notify(message: &str) -> Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = tokio::process::Command::new("osascript");
+        cmd.arg("-e").arg(format!(
+            "display notification {:?} with title \"Claude Helper\"",
+            message
+        ));
+        cmd
+    } else if cfg!(windows) {
+        // No notifier that ships with Windows; ring the terminal bell instead
+        print!("\x07");
+        return Ok(());
+    } else {
+        let mut cmd = tokio::process::Command::new("notify-send");
+        cmd.arg("Claude Helper").arg(message);
+        cmd
+    };
+
+    let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+    let status = cmd
+        .stdin(Stdio::null())
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(five_hour: u8, seven_day: u8, minutes_left: u32) -> Usage {
+        Usage {
+            five_hour_used: five_hour as usize * 200,
+            five_hour_limit: 20_000,
+            five_hour_percent: five_hour,
+            five_hour_minutes_remaining: minutes_left,
+            seven_day_used: seven_day as usize * 2_000,
+            seven_day_limit: 200_000,
+            seven_day_percent: seven_day,
+            burn_rate_per_hour: 0.0,
+            estimated_seven_day_cost: 0.0,
+            stale_since: None,
+        }
+    }
+
+    #[test]
+    fn test_crossings() {
+        let thresholds = [80, 95];
+
+        // Already above 80% on the first reading
+        let first = usage(85, 10, 100);
+        assert_eq!(
+            crossings(None, &first, &thresholds),
+            vec![Crossing {
+                window: "5h",
+                threshold: 80,
+                percent: 85
+            }]
+        );
+
+        // Staying above a threshold does not fire again
+        let second = usage(90, 10, 90);
+        assert!(crossings(Some(&first), &second, &thresholds).is_empty());
+
+        // Jumping past several thresholds at once fires each of them
+        let third = usage(90, 96, 80);
+        assert_eq!(crossings(Some(&second), &third, &thresholds).len(), 2);
+    }
+
+    #[test]
+    fn test_window_reset() {
+        assert!(!window_reset(&usage(50, 10, 100), &usage(60, 10, 90)));
+        assert!(window_reset(&usage(90, 10, 2), &usage(0, 10, 299)));
+        assert!(window_reset(&usage(90, 10, 0), &usage(90, 10, 300)));
+    }
+}
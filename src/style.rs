@@ -0,0 +1,131 @@
+// Terminal styling for command output. Every colored string and symbol goes
+// through here, so `NO_COLOR`, `--no-color` and the `plain` theme apply to
+// all commands alike.
+
+use crate::config::OutputConfig;
+use std::sync::OnceLock;
+
+pub use colored::{ColoredString, Colorize};
+
+/// Names accepted by `output.theme`
+pub const THEMES: &[&str] = &["default", "plain"];
+
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    color: bool,
+    unicode: bool,
+}
+
+impl Style {
+    fn new(theme: &str, no_color: bool) -> Self {
+        let plain = theme == "plain";
+        Self {
+            color: !(plain || no_color),
+            unicode: !plain,
+        }
+    }
+}
+
+static INSTALLED: OnceLock<Style> = OnceLock::new();
+
+/// Apply the output settings for this process. `no_color` comes from
+/// `--no-color`; the `NO_COLOR` environment variable has the same effect
+/// (https://no-color.org). The `plain` theme also drops color, and prints
+/// ASCII in place of symbols and emoji. Only the first call has an effect.
+pub fn install(config: &OutputConfig, no_color: bool) {
+    let style = Style::new(&config.theme, no_color || no_color_env());
+
+    // Otherwise `colored` and the progress bars decide per stream, e.g. no
+    // color when piped
+    if !style.color {
+        colored::control::set_override(false);
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    let _ = INSTALLED.set(style);
+}
+
+fn no_color_env() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// False when color was turned off by `--no-color`, `NO_COLOR` or the
+/// plain theme
+pub fn color_enabled() -> bool {
+    INSTALLED
+        .get()
+        .map_or_else(|| !no_color_env(), |style| style.color)
+}
+
+/// `fancy`, or its ASCII stand-in `plain` under the plain theme
+pub fn pick<'a>(fancy: &'a str, plain: &'a str) -> &'a str {
+    if INSTALLED.get().is_none_or(|style| style.unicode) {
+        fancy
+    } else {
+        plain
+    }
+}
+
+/// Marks a success
+pub fn ok() -> &'static str {
+    pick("✓", "+")
+}
+
+/// Marks a failure
+pub fn fail() -> &'static str {
+    pick("✗", "x")
+}
+
+/// Marks a warning
+pub fn warn() -> &'static str {
+    pick("⚠", "!")
+}
+
+pub fn arrow() -> &'static str {
+    pick("→", "->")
+}
+
+pub fn bullet() -> &'static str {
+    pick("•", "-")
+}
+
+/// Tick characters of progress spinners
+pub fn spinner_ticks() -> &'static str {
+    pick("⠁⠂⠄⡀⢀⠠⠐⠈ ", "|/-\\ ")
+}
+
+/// A decorative emoji, `*` under the plain theme
+pub fn icon(emoji: &str) -> &str {
+    pick(emoji, "*")
+}
+
+/// Heavy horizontal rule framing a section
+pub fn rule(width: usize) -> String {
+    pick("═", "=").repeat(width)
+}
+
+/// Light horizontal rule, e.g. under a table header
+pub fn line(width: usize) -> String {
+    pick("─", "-").repeat(width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_from_settings() {
+        let style = Style::new("default", false);
+        assert!(style.color && style.unicode);
+
+        // --no-color keeps symbols, the plain theme drops both
+        let style = Style::new("default", true);
+        assert!(!style.color && style.unicode);
+        let style = Style::new("plain", false);
+        assert!(!style.color && !style.unicode);
+
+        // Nothing installed in tests: symbols stay as they are
+        assert_eq!(ok(), "✓");
+        assert_eq!(rule(3), "═══");
+    }
+}
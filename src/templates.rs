@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// A slash command that can be installed into `.claude/commands`. Its text
+/// has parameters written as `{{name}}` or `{{name:default}}`, filled in
+/// at install time; Claude Code's own `$ARGUMENTS` is left as it is.
+#[derive(Debug, Clone, Copy)]
+pub struct CommandTemplate {
+    pub name: &'static str,
+    pub text: &'static str,
+}
+
+/// The command templates shipped with claude-helper
+pub const LIBRARY: &[CommandTemplate] = &[
+    CommandTemplate {
+        name: "commit",
+        text: include_str!("../.claude-templates/commands/library/commit.md"),
+    },
+    CommandTemplate {
+        name: "test",
+        text: include_str!("../.claude-templates/commands/library/test.md"),
+    },
+    CommandTemplate {
+        name: "review",
+        text: include_str!("../.claude-templates/commands/library/review.md"),
+    },
+    CommandTemplate {
+        name: "pr",
+        text: include_str!("../.claude-templates/commands/library/pr.md"),
+    },
+];
+
+static PARAMETER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{(\w+)(?::([^}]*))?\}\}").unwrap());
+
+/// One parameter of a template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+impl CommandTemplate {
+    /// The template called `name`
+    pub fn named(name: &str) -> Result<&'static CommandTemplate> {
+        LIBRARY
+            .iter()
+            .find(|template| template.name == name)
+            .with_context(|| {
+                let names: Vec<&str> = LIBRARY.iter().map(|t| t.name).collect();
+                format!(
+                    "Unknown command template: {} (available: {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// The `description` from the front matter
+    pub fn description(&self) -> &'static str {
+        self.text
+            .lines()
+            .find_map(|line| line.strip_prefix("description:"))
+            .map(str::trim)
+            .unwrap_or_default()
+    }
+
+    /// Parameters in order of first use, with the first default given
+    pub fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters: Vec<Parameter> = Vec::new();
+        for captures in PARAMETER.captures_iter(self.text) {
+            let default = captures.get(2).map(|d| d.as_str().to_string());
+            match parameters.iter_mut().find(|p| p.name == captures[1]) {
+                Some(parameter) => {
+                    if parameter.default.is_none() {
+                        parameter.default = default;
+                    }
+                }
+                None => parameters.push(Parameter {
+                    name: captures[1].to_string(),
+                    default,
+                }),
+            }
+        }
+        parameters
+    }
+
+    /// The command text with `values` filled in, falling back to the
+    /// defaults. Fails on unknown values and on parameters left without one.
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
+        let parameters = self.parameters();
+        if let Some(unknown) = values
+            .keys()
+            .find(|key| !parameters.iter().any(|p| &p.name == *key))
+        {
+            anyhow::bail!("The {} template has no parameter '{}'", self.name, unknown);
+        }
+
+        let mut missing = Vec::new();
+        let text = PARAMETER.replace_all(self.text, |captures: &regex::Captures| {
+            // A default given at any use applies to all of them
+            let default = parameters
+                .iter()
+                .find(|p| p.name == captures[1])
+                .and_then(|p| p.default.as_deref());
+            match values.get(&captures[1]).map(String::as_str).or(default) {
+                Some(value) => value.to_string(),
+                None => {
+                    if !missing.contains(&captures[1].to_string()) {
+                        missing.push(captures[1].to_string());
+                    }
+                    String::new()
+                }
+            }
+        });
+        if !missing.is_empty() {
+            anyhow::bail!("Set {} with --set NAME=VALUE", missing.join(", "));
+        }
+        Ok(text.into_owned())
+    }
+
+    /// Write the command to `<dir>/<name>.md`. An existing command is only
+    /// replaced with `force`.
+    pub fn install(
+        &self,
+        dir: &Path,
+        values: &HashMap<String, String>,
+        force: bool,
+    ) -> Result<PathBuf> {
+        let text = self.render(values)?;
+        let path = dir.join(format!("{}.md", self.name));
+        if path.exists() && !force {
+            anyhow::bail!(
+                "{} already exists (use --force to replace it)",
+                path.display()
+            );
+        }
+
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        fs::write(&path, text).with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(path)
+    }
+}
+
+/// Parse `NAME=VALUE` pairs given with `--set`
+pub fn parse_values(pairs: &[String]) -> Result<HashMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+                .with_context(|| format!("Expected NAME=VALUE, got '{}'", pair))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_library_templates_render_with_defaults() {
+        for template in LIBRARY {
+            assert!(!template.description().is_empty(), "{}", template.name);
+            let text = template.render(&HashMap::new()).unwrap();
+            assert!(!text.contains("{{"), "{}", template.name);
+        }
+    }
+
+    #[test]
+    fn test_parameters_and_render() {
+        let template = CommandTemplate {
+            name: "t",
+            text: "Run {{cmd:cargo test}} on {{branch}}, then {{cmd}} again. $ARGUMENTS",
+        };
+        assert_eq!(
+            template.parameters(),
+            vec![
+                Parameter {
+                    name: "cmd".to_string(),
+                    default: Some("cargo test".to_string())
+                },
+                Parameter {
+                    name: "branch".to_string(),
+                    default: None
+                },
+            ]
+        );
+
+        let err = template.render(&HashMap::new()).unwrap_err();
+        assert_eq!(err.to_string(), "Set branch with --set NAME=VALUE");
+
+        let values = parse_values(&["branch=dev".to_string(), "cmd=npm test".to_string()]).unwrap();
+        assert_eq!(
+            template.render(&values).unwrap(),
+            "Run npm test on dev, then npm test again. $ARGUMENTS"
+        );
+
+        let unknown = parse_values(&["nope=1".to_string()]).unwrap();
+        assert!(template.render(&unknown).is_err());
+        assert!(parse_values(&["novalue".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = CommandTemplate::named("test").unwrap();
+        let mut values = HashMap::new();
+        values.insert("test_command".to_string(), "pytest -q".to_string());
+
+        let path = template.install(dir.path(), &values, false).unwrap();
+        assert_eq!(path, dir.path().join("test.md"));
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("`pytest -q $ARGUMENTS`"));
+
+        assert!(template.install(dir.path(), &values, false).is_err());
+        assert!(template.install(dir.path(), &values, true).is_ok());
+        assert!(CommandTemplate::named("missing").is_err());
+    }
+}
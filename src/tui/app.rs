@@ -1,73 +1,332 @@
-use crate::analyzer::SessionAnalyzer;
+use super::event::{AppEvent, Events};
+use super::export::Export;
+use super::filter::{Filterable, ListFilter, SortKey};
+use super::history::UsageHistory;
+use super::keymap::{Action, KeyMap};
+use super::panel::Panel;
+use super::run::RunTab;
+use super::sessions::SessionsTab;
+use super::widgets::{
+    centered_rect, is_narrow, is_short, set_theme, spinner, split_panes, theme, ListRows, Popup,
+    Theme,
+};
+use crate::analyzer::{ChangePreview, DiffLine, Optimization, SessionAnalyzer, SessionSummary};
 use crate::config::Config;
-use crate::db::Database;
-use crate::statusline::StatusLine;
+use crate::db::{AgentHistoryEntry, Database, OptimizationStatus};
+use crate::statusline::{Usage, UsageTracker};
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Tabs},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Frame, Terminal,
 };
+use serde::Serialize;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// How often spinners, run progress and the usage refresh timer advance
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+/// Usage charts are left out when they would be shorter than this
+const MIN_CHART_HEIGHT: u16 = 6;
+
+/// Stored optimizations shown in the Optimizations tab
+const OPTIMIZATION_LIMIT: usize = 100;
+
+/// How long the snooze key hides an optimization
+const SNOOZE_DAYS: i64 = 7;
+
+/// Agent executions per page in the Agent History tab
+const HISTORY_PAGE_SIZE: usize = 20;
+
+/// Recent agent executions searched and sorted when a filter is set. Task
+/// text may be encrypted at rest, so filtering happens in memory.
+const HISTORY_SEARCH_WINDOW: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum AppTab {
     Usage,
     Optimization,
     AgentHistory,
+    Sessions,
+    Run,
+}
+
+impl AppTab {
+    const ALL: [AppTab; 5] = [
+        AppTab::Usage,
+        AppTab::Optimization,
+        AppTab::AgentHistory,
+        AppTab::Sessions,
+        AppTab::Run,
+    ];
+
+    /// Abbreviated title for narrow screens
+    fn short_title(self) -> &'static str {
+        match self {
+            AppTab::Usage => "Usage",
+            AppTab::Optimization => "Opt",
+            AppTab::AgentHistory => "History",
+            AppTab::Sessions => "Sessions",
+            AppTab::Run => "Run",
+        }
+    }
+
+    /// The title shown in a tab bar drawn in `area`
+    fn label(self, area: Rect) -> &'static str {
+        if is_narrow(area) {
+            self.short_title()
+        } else {
+            self.title()
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            AppTab::Usage => "Usage",
+            AppTab::Optimization => "Optimizations",
+            AppTab::AgentHistory => "Agent History",
+            AppTab::Sessions => "Sessions",
+            AppTab::Run => "Run",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            AppTab::Usage => "5-hour and 7-day usage, burn rate and usage charts",
+            AppTab::Optimization => "Suggestions from your sessions; apply them from the details",
+            AppTab::AgentHistory => "Past agent executions, newest first",
+            AppTab::Sessions => "Recent Claude Code sessions with tool and file breakdowns",
+            AppTab::Run => "Launch Master Coder and follow its progress",
+        }
+    }
+
+    /// The tab whose title is at `column` of a tab bar drawn in `area`
+    fn at(area: Rect, column: u16, row: u16) -> Option<AppTab> {
+        if row != area.y + 1 || column <= area.x {
+            return None;
+        }
+
+        // Each title is padded by a space on both sides and followed by
+        // a one-column divider
+        let mut right = area.x + 1;
+        AppTab::ALL.into_iter().find(|tab| {
+            right += tab.label(area).chars().count() as u16 + 3;
+            column < right
+        })
+    }
+}
+
+/// An optimization in the Optimizations tab. Stored suggestions have a
+/// database ID; ones derived from recent sessions do not.
+#[derive(Serialize)]
+pub(super) struct OptimizationEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    #[serde(flatten)]
+    pub optimization: Optimization,
+}
+
+impl Filterable for OptimizationEntry {
+    const SORT_KEYS: &'static [SortKey] = &[SortKey::Tokens];
+
+    fn search_text(&self) -> String {
+        let opt = &self.optimization;
+        format!(
+            "{} {} {} {}",
+            opt.opt_type,
+            opt.title,
+            opt.description,
+            opt.examples.join(" ")
+        )
+    }
+
+    fn compare(&self, other: &Self, _key: SortKey) -> Ordering {
+        self.optimization
+            .estimated_savings
+            .cmp(&other.optimization.estimated_savings)
+    }
+}
+
+impl Filterable for AgentHistoryEntry {
+    const SORT_KEYS: &'static [SortKey] = &[SortKey::Date, SortKey::Tokens, SortKey::Success];
+
+    fn search_text(&self) -> String {
+        format!("{} {} {}", self.agent_type, self.agent_id, self.task)
+    }
+
+    fn compare(&self, other: &Self, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Tokens => self.tokens_used.cmp(&other.tokens_used),
+            SortKey::Success => self.success.cmp(&other.success),
+            _ => self.timestamp.cmp(&other.timestamp),
+        }
+    }
+}
+
+/// A change waiting for confirmation in the apply popup
+struct PendingApply {
+    id: Option<i64>,
+    preview: ChangePreview,
+}
+
+/// One page of agent history plus the total, for the page indicator
+struct HistoryPage {
+    entries: Vec<AgentHistoryEntry>,
+    total: usize,
+    /// Filter the page was loaded with
+    filter: ListFilter,
 }
 
 pub struct App {
     config: Config,
     current_tab: AppTab,
     should_quit: bool,
-    statusline: StatusLine,
-    analyzer: SessionAnalyzer,
-    db: Database,
+    tracker: Arc<UsageTracker>,
+    usage: Panel<Usage>,
+    usage_history: Panel<UsageHistory>,
+    analyzer: Arc<SessionAnalyzer>,
+    optimizations: Panel<Vec<OptimizationEntry>>,
+    selected_optimization: usize,
+    show_optimization_detail: bool,
+    optimization_filter: ListFilter,
+    pending_apply: Option<PendingApply>,
+    /// Result of the last apply, shown in the Optimizations tab
+    apply_notice: Option<Result<String, String>>,
+    history: Panel<HistoryPage>,
+    history_page: usize,
+    selected_history: usize,
+    show_history_detail: bool,
+    history_filter: ListFilter,
+    sessions: SessionsTab,
+    /// Typing goes to the current tab's search query
+    searching: bool,
+    keymap: KeyMap,
+    show_help: bool,
+    /// Result of the last export, shown in the footer until the next key
+    notice: Option<Result<String, String>>,
+    /// Where the tab bar and the current tab's list were last drawn, for
+    /// mouse clicks
+    tabs_area: Cell<Rect>,
+    list_rows: Cell<Option<ListRows>>,
+    run: RunTab,
+    db: Arc<Database>,
+    events: Events,
 }
 
 impl App {
-    pub async fn new(config: Config) -> Result<Self> {
-        let statusline = StatusLine::new(config.clone()).await?;
-        let analyzer = SessionAnalyzer::new(config.clone()).await?;
-        let db = Database::new(&config).await?;
+    pub async fn new(config: Config, db: Arc<Database>) -> Result<Self> {
+        let tracker = Arc::new(UsageTracker::new(config.clone()).await?);
+        let analyzer = Arc::new(SessionAnalyzer::new(config.clone(), db.clone()).await?);
+        let run = RunTab::new(&config);
+        let keymap = KeyMap::from_config(&config.tui)?;
+        set_theme(Theme::from_config(&config.tui)?);
 
         Ok(Self {
             config,
             current_tab: AppTab::Usage,
             should_quit: false,
-            statusline,
+            tracker,
+            usage: Panel::default(),
+            usage_history: Panel::default(),
             analyzer,
+            optimizations: Panel::default(),
+            selected_optimization: 0,
+            show_optimization_detail: false,
+            optimization_filter: ListFilter::default(),
+            pending_apply: None,
+            apply_notice: None,
+            history: Panel::default(),
+            history_page: 0,
+            selected_history: 0,
+            show_history_detail: false,
+            history_filter: ListFilter::default(),
+            sessions: SessionsTab::default(),
+            searching: false,
+            keymap,
+            show_help: false,
+            notice: None,
+            tabs_area: Cell::default(),
+            list_rows: Cell::default(),
+            run,
             db,
+            events: Events::new(TICK_RATE),
         })
     }
 
+    /// Open on the Run tab, following a run started in another process
+    pub fn attach(&mut self, run_id: &str) -> Result<()> {
+        self.run.attach(run_id)?;
+        self.current_tab = AppTab::Run;
+        Ok(())
+    }
+
     pub async fn run(mut self) -> Result<()> {
-        super::run_tui(|terminal| {
+        self.refresh_usage();
+        self.refresh_optimizations();
+        self.refresh_history();
+        self.refresh_sessions();
+
+        let mut terminal = super::setup_terminal()?;
+        let res = self.event_loop(&mut terminal).await;
+        super::restore_terminal(&mut terminal)?;
+
+        res
+    }
+
+    /// Redraw after every event. Loads run as tokio tasks and report back
+    /// over the event channel, so nothing here waits on the network or the
+    /// database.
+    async fn event_loop<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        while !self.should_quit {
+            self.poll_panels();
             self.draw(terminal)?;
-            self.handle_events()
-        })
+
+            let Some(event) = self.events.next().await else {
+                break;
+            };
+            match event {
+                AppEvent::Input(Event::Key(key)) => self.handle_key(key),
+                AppEvent::Input(Event::Mouse(mouse)) => self.handle_mouse(mouse),
+                // Resizes and finished loads only need the redraw, which
+                // lays the screen out again for the new size
+                AppEvent::Input(_) | AppEvent::Loaded | AppEvent::Tick => {}
+            }
+        }
+
+        Ok(())
     }
 
     fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         terminal.draw(|f| {
+            // A short screen drops the title bar and gets a one-line footer
+            let short = is_short(f.area());
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Length(3), // Title
-                    Constraint::Length(3), // Tabs
-                    Constraint::Min(0),    // Content
-                    Constraint::Length(3), // Footer
+                    Constraint::Length(if short { 0 } else { 3 }), // Title
+                    Constraint::Length(3),                         // Tabs
+                    Constraint::Min(0),                            // Content
+                    Constraint::Length(if short { 1 } else { 3 }), // Footer
                 ])
                 .split(f.area());
 
-            self.render_title(f, chunks[0]);
-            self.render_tabs(f, chunks[1]);
+            if !short {
+                self.render_title(f, chunks[0]);
+            }
+            self.render_tabs(f, chunks[1], short);
             self.render_content(f, chunks[2]);
-            self.render_footer(f, chunks[3]);
+            self.render_footer(f, chunks[3], short);
+
+            if self.show_help {
+                self.render_help(f, centered_rect(70, 90, f.area()));
+            }
         })?;
 
         Ok(())
@@ -75,167 +334,1135 @@ impl App {
 
     fn render_title(&self, f: &mut Frame, area: Rect) {
         let title = Paragraph::new("Claude Helper - Interactive Dashboard")
-            .style(
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )
+            .style(theme().title)
             .block(Block::default().borders(Borders::ALL));
 
         f.render_widget(title, area);
     }
 
-    fn render_tabs(&self, f: &mut Frame, area: Rect) {
-        let titles = vec!["Usage", "Optimizations", "Agent History"];
-        let index = match self.current_tab {
-            AppTab::Usage => 0,
-            AppTab::Optimization => 1,
-            AppTab::AgentHistory => 2,
-        };
+    /// `short` is set when the title bar is hidden, so the tab bar
+    /// carries the app name instead
+    fn render_tabs(&self, f: &mut Frame, area: Rect, short: bool) {
+        let title = if short { "Claude Helper" } else { "Tabs" };
+        let titles = AppTab::ALL.map(|tab| tab.label(area));
+        let index = AppTab::ALL
+            .iter()
+            .position(|tab| *tab == self.current_tab)
+            .unwrap_or(0);
 
         let tabs = Tabs::new(titles)
             .select(index)
-            .style(Style::default().fg(Color::White))
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
-            .block(Block::default().borders(Borders::ALL).title("Tabs"));
+            .style(theme().text)
+            .highlight_style(theme().highlight)
+            .block(Block::default().borders(Borders::ALL).title(title));
 
         f.render_widget(tabs, area);
+        self.tabs_area.set(area);
     }
 
     fn render_content(&self, f: &mut Frame, area: Rect) {
+        self.list_rows.set(None);
         match self.current_tab {
             AppTab::Usage => self.render_usage_tab(f, area),
             AppTab::Optimization => self.render_optimization_tab(f, area),
             AppTab::AgentHistory => self.render_agent_history_tab(f, area),
+            AppTab::Sessions => self.sessions.render(f, area, self.searching),
+            AppTab::Run => self.run.render(f, area),
         }
     }
 
     fn render_usage_tab(&self, f: &mut Frame, area: Rect) {
-        // This would fetch real usage data
-        let text = vec![
-            Line::from(""),
-            Line::from(Span::styled(
-                "Token Usage",
-                Style::default().add_modifier(Modifier::BOLD),
-            )),
-            Line::from(""),
-            Line::from("5-Hour Block: ████████████░░░░ 70% (14k/20k)"),
-            Line::from("7-Day Total:  █████████░░░░░░░ 65% (130k/200k)"),
-            Line::from(""),
-            Line::from(Span::styled(
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Usage Statistics{}", self.usage.spinner()));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let Some(usage) = &self.usage.data else {
+            let text = match &self.usage.error {
+                Some(e) => Line::from(Span::styled(
+                    format!("Failed to load usage: {}", e),
+                    theme().error,
+                )),
+                None => Line::from(format!("{} Loading usage...", spinner())),
+            };
+            f.render_widget(Paragraph::new(text), inner);
+            return;
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(3), // 5-hour block
+                Constraint::Length(3), // 7-day total
+                Constraint::Length(8), // Details
+                Constraint::Min(0),    // History charts
+            ])
+            .split(inner);
+
+        f.render_widget(
+            usage_gauge(
+                "5-Hour Block",
+                usage.five_hour_used,
+                usage.five_hour_limit,
+                usage.five_hour_percent,
+            ),
+            chunks[0],
+        );
+        f.render_widget(
+            usage_gauge(
+                "7-Day Total",
+                usage.seven_day_used,
+                usage.seven_day_limit,
+                usage.seven_day_percent,
+            ),
+            chunks[1],
+        );
+
+        let elapsed = self.usage.age();
+
+        let mut text = vec![Line::from(format!(
+            "Block resets in: {}",
+            reset_countdown(usage.five_hour_minutes_remaining, elapsed)
+        ))];
+        if let Some(at) = usage.stale_since {
+            text.push(Line::from(Span::styled(
+                format!(
+                    "Fetching usage failed, showing usage from {}",
+                    crate::statusline::ago(at)
+                ),
+                theme().warning,
+            )));
+        }
+
+        if self.config.statusline.show_costs {
+            text.push(Line::from(""));
+            text.push(Line::from(Span::styled(
                 "Cost Information",
                 Style::default().add_modifier(Modifier::BOLD),
-            )),
-            Line::from(""),
-            Line::from("Burn rate: $0.15/hour"),
-            Line::from("Estimated 7-day cost: $1.17"),
-            Line::from(""),
-            Line::from("Press 'r' to refresh"),
-        ];
+            )));
+            text.push(Line::from(format!(
+                "Burn rate: ${:.2}/hour",
+                usage.burn_rate_per_hour
+            )));
+            text.push(Line::from(format!(
+                "Estimated 7-day cost: ${:.2}",
+                usage.estimated_seven_day_cost
+            )));
+        }
 
-        let paragraph = Paragraph::new(text).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Usage Statistics"),
-        );
+        text.push(Line::from(""));
+        let status = if self.usage.is_loading() {
+            Span::styled(format!("{} Refreshing...", spinner()), theme().warning)
+        } else if let Some(e) = &self.usage.error {
+            Span::styled(format!("Refresh failed: {}", e), theme().error)
+        } else {
+            Span::styled(format!("Updated {}s ago", elapsed.as_secs()), theme().muted)
+        };
+        text.push(Line::from(status));
 
-        f.render_widget(paragraph, area);
+        f.render_widget(Paragraph::new(text), chunks[2]);
+
+        // Not enough room left for readable charts
+        if chunks[3].height < MIN_CHART_HEIGHT {
+            return;
+        }
+
+        match (&self.usage_history.data, &self.usage_history.error) {
+            (Some(history), _) => history.render(f, chunks[3]),
+            (None, Some(e)) => f.render_widget(
+                Paragraph::new(Span::styled(
+                    format!("Failed to load usage history: {}", e),
+                    theme().error,
+                )),
+                chunks[3],
+            ),
+            (None, None) => f.render_widget(
+                Paragraph::new(format!("{} Loading usage history...", spinner())),
+                chunks[3],
+            ),
+        }
     }
 
     fn render_optimization_tab(&self, f: &mut Frame, area: Rect) {
-        let items = vec![
-            ListItem::new("1. Combine git operations → Save ~600 tokens"),
-            ListItem::new("2. Create test-and-build script → Save ~400 tokens"),
-            ListItem::new("3. Reduce redundant file reads → Save ~900 tokens"),
-            ListItem::new(""),
-            ListItem::new("Total potential savings: ~1,900 tokens"),
-            ListItem::new(""),
-            ListItem::new("Press 'Enter' to view details"),
-        ];
+        let Some(entries) = &self.optimizations.data else {
+            let text = match &self.optimizations.error {
+                Some(e) => Line::from(Span::styled(
+                    format!("Failed to load optimizations: {}", e),
+                    theme().error,
+                )),
+                None => Line::from(format!("{} Loading optimizations...", spinner())),
+            };
+            let paragraph =
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(format!(
+                    "Optimization Suggestions{}",
+                    self.optimizations.spinner()
+                )));
+            f.render_widget(paragraph, area);
+            return;
+        };
 
-        let list = List::new(items).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Optimization Suggestions"),
-        );
+        if entries.is_empty() {
+            let paragraph = Paragraph::new(vec![
+                Line::from(""),
+                Line::from("No optimization opportunities found! 🎉"),
+                Line::from("Your usage patterns are already efficient."),
+            ])
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Optimization Suggestions"),
+            );
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let (list_area, detail_area) = if self.show_optimization_detail {
+            let (list, detail) = split_panes(area, 45);
+            (list, Some(detail))
+        } else {
+            (area, None)
+        };
+
+        let visible = self.visible_optimizations();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|entry| {
+                let opt = &entry.optimization;
+                let id = entry.id.map(|id| format!("#{} ", id)).unwrap_or_default();
+                ListItem::new(format!(
+                    "{}{} → Save ~{} tokens",
+                    id, opt.title, opt.estimated_savings
+                ))
+            })
+            .collect();
+
+        let total_savings: usize = entries
+            .iter()
+            .map(|e| e.optimization.estimated_savings)
+            .sum();
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Optimization Suggestions (total potential savings: ~{} tokens){}{}",
+                total_savings,
+                self.optimization_filter.describe(self.searching),
+                self.optimizations.spinner()
+            )))
+            .highlight_style(theme().highlight)
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default().with_selected(Some(self.selected_optimization));
+        f.render_stateful_widget(list, list_area, &mut state);
+        self.list_rows
+            .set(Some(ListRows::new(list_area, &state, visible.len())));
+
+        if let (Some(area), Some(entry)) = (detail_area, visible.get(self.selected_optimization)) {
+            render_optimization_detail(f, area, entry, self.apply_notice.as_ref());
+        }
 
-        f.render_widget(list, area);
+        if let Some(pending) = &self.pending_apply {
+            render_apply_popup(f, centered_rect(80, 80, area), pending);
+        }
     }
 
     fn render_agent_history_tab(&self, f: &mut Frame, area: Rect) {
-        let items = vec![
-            ListItem::new("💻 Code Writer Alpha - Implement auth (3.2k tokens) ✓"),
-            ListItem::new("🔒 Security Auditor - Review auth (1.8k tokens) ✓"),
-            ListItem::new("🧪 Test Engineer - Write tests (2.1k tokens) ✓"),
-            ListItem::new("📚 Documentation Writer - API docs (1.5k tokens) ✓"),
-            ListItem::new(""),
-            ListItem::new("Scroll with ↑↓ arrows"),
-        ];
+        let Some(page) = &self.history.data else {
+            let text = match &self.history.error {
+                Some(e) => Line::from(Span::styled(
+                    format!("Failed to load agent history: {}", e),
+                    theme().error,
+                )),
+                None => Line::from(format!("{} Loading agent history...", spinner())),
+            };
+            let paragraph = Paragraph::new(text).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Recent Agent Executions{}", self.history.spinner())),
+            );
+            f.render_widget(paragraph, area);
+            return;
+        };
 
-        let list = List::new(items).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Recent Agent Executions"),
-        );
+        if page.total == 0 && !page.filter.is_active() {
+            let paragraph = Paragraph::new(vec![
+                Line::from(""),
+                Line::from("No agent executions recorded yet."),
+                Line::from("Run a task with 'claude-helper run' to see it here."),
+            ])
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Recent Agent Executions"),
+            );
+            f.render_widget(paragraph, area);
+            return;
+        }
 
-        f.render_widget(list, area);
+        let items: Vec<ListItem> = page
+            .entries
+            .iter()
+            .map(|entry| {
+                let task = entry.task.lines().next().unwrap_or_default();
+                let status = if entry.success {
+                    Span::styled("✓", theme().success)
+                } else {
+                    Span::styled("✗", theme().error)
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!(
+                        "{} {} - {} ({:.1}k tokens) ",
+                        entry.capability.emoji(),
+                        entry.agent_type,
+                        task,
+                        entry.tokens_used as f64 / 1000.0
+                    )),
+                    status,
+                ]))
+            })
+            .collect();
+
+        let pages = page.total.div_ceil(HISTORY_PAGE_SIZE);
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Recent Agent Executions (page {}/{}, {} total){}{}",
+                self.history_page + 1,
+                pages.max(1),
+                page.total,
+                self.history_filter.describe(self.searching),
+                self.history.spinner()
+            )))
+            .highlight_style(theme().highlight)
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default().with_selected(Some(self.selected_history));
+        f.render_stateful_widget(list, area, &mut state);
+        self.list_rows
+            .set(Some(ListRows::new(area, &state, page.entries.len())));
+
+        if self.show_history_detail {
+            if let Some(entry) = page.entries.get(self.selected_history) {
+                render_history_detail(f, centered_rect(70, 60, area), entry);
+            }
+        }
     }
 
-    fn render_footer(&self, f: &mut Frame, area: Rect) {
-        let text = Line::from(vec![
-            Span::raw("Tab: "),
-            Span::styled("←/→", Style::default().fg(Color::Yellow)),
-            Span::raw(" | Quit: "),
-            Span::styled("q", Style::default().fg(Color::Yellow)),
-            Span::raw(" | Refresh: "),
-            Span::styled("r", Style::default().fg(Color::Yellow)),
-        ]);
+    /// Key hints; a narrow screen only gets the essential ones, and a
+    /// short one drops the border
+    fn render_footer(&self, f: &mut Frame, area: Rect, short: bool) {
+        let keys = &self.keymap;
+        let pair = |a, b| format!("{}/{}", keys.label(a), keys.label(b));
+        // (name, keys, shown on narrow screens)
+        let hints = [
+            ("Tab", pair(Action::NextTab, Action::PrevTab), true),
+            ("Quit", keys.label(Action::Quit), true),
+            ("Refresh", keys.label(Action::Refresh), false),
+            ("Select", pair(Action::Up, Action::Down), false),
+            ("Page", pair(Action::PageUp, Action::PageDown), false),
+            ("Details", keys.label(Action::Select), true),
+            ("Search", keys.label(Action::Search), false),
+            ("Sort", pair(Action::Sort, Action::SortOrder), false),
+            ("Export", keys.label(Action::Export), false),
+            ("Help", keys.label(Action::Help), true),
+        ];
+        let narrow = is_narrow(area);
 
-        let paragraph =
-            Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Controls"));
+        let mut spans = Vec::new();
+        for (name, key, essential) in hints {
+            if narrow && !essential {
+                continue;
+            }
+            if !spans.is_empty() {
+                spans.push(Span::raw(" | "));
+            }
+            spans.push(Span::raw(format!("{}: ", name)));
+            spans.push(Span::styled(key, theme().key));
+        }
+        let text = match &self.notice {
+            Some(Ok(message)) => Line::from(Span::styled(message.as_str(), theme().success)),
+            Some(Err(message)) => Line::from(Span::styled(message.as_str(), theme().error)),
+            None => Line::from(spans),
+        };
+
+        let paragraph = if short {
+            Paragraph::new(text)
+        } else {
+            Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Controls"))
+        };
 
         f.render_widget(paragraph, area);
     }
 
-    fn handle_events(&mut self) -> Result<bool> {
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => {
-                        self.should_quit = true;
-                        return Ok(false);
-                    }
-                    KeyCode::Right | KeyCode::Tab => {
-                        self.next_tab();
-                    }
-                    KeyCode::Left | KeyCode::BackTab => {
-                        self.prev_tab();
-                    }
-                    _ => {}
+    fn render_help(&self, f: &mut Frame, area: Rect) {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let row = |key: String, text: &str| {
+            Line::from(vec![
+                Span::styled(format!("  {:<18}", key), theme().key),
+                Span::raw(text.to_string()),
+            ])
+        };
+
+        let mut text = vec![Line::from(Span::styled("Tabs", bold))];
+        for tab in AppTab::ALL {
+            text.push(Line::from(vec![
+                Span::styled(format!("  {:<18}", tab.title()), theme().highlight),
+                Span::raw(tab.description()),
+            ]));
+        }
+
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled("Keys", bold)));
+        for action in Action::ALL {
+            let keys = self.keymap.labels(action);
+            if !keys.is_empty() {
+                text.push(row(keys.join(", "), action.description()));
+            }
+        }
+
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled("Text entry and popups", bold)));
+        text.push(row("Enter / Esc".to_string(), "Keep or clear the search"));
+        text.push(row(
+            "y / n".to_string(),
+            "Confirm or cancel a change or approval",
+        ));
+        text.push(row(
+            "↑/↓ ←/→ Enter".to_string(),
+            "Run tab: move between fields, pick a mode, launch",
+        ));
+
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "Press any key to close",
+            theme().muted,
+        )));
+
+        f.render_widget(Popup::new("Help", text), area);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        self.notice = None;
+
+        // Any key closes the help
+        if self.show_help {
+            self.show_help = false;
+            return;
+        }
+
+        if matches!(self.current_tab, AppTab::Run)
+            && self.run.handle_key(key.code, &self.config, &self.db)
+        {
+            return;
+        }
+
+        if self.pending_apply.is_some() {
+            // Other keys are swallowed until the change is confirmed or cancelled
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => self.confirm_apply(),
+                KeyCode::Char('n') | KeyCode::Esc => self.pending_apply = None,
+                _ => {}
+            }
+            return;
+        }
+
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.update_filter(|filter, _| filter.query.clear());
                 }
+                KeyCode::Backspace => self.update_filter(|filter, _| {
+                    filter.query.pop();
+                }),
+                KeyCode::Char(c) => self.update_filter(|filter, _| filter.query.push(c)),
+                _ => {}
             }
+            return;
         }
 
-        Ok(!self.should_quit)
+        let Some(action) = self.keymap.resolve(key) else {
+            return;
+        };
+
+        match action {
+            Action::Back if self.show_history_detail => {
+                self.show_history_detail = false;
+            }
+            Action::Back if self.show_optimization_detail => {
+                self.show_optimization_detail = false;
+            }
+            Action::Back
+                if matches!(self.current_tab, AppTab::Sessions) && self.sessions.close_detail() => {
+            }
+            Action::Back if self.filter_is_active() => {
+                self.update_filter(|filter, _| *filter = ListFilter::default());
+            }
+            Action::Quit | Action::Back => self.should_quit = true,
+            Action::NextTab => self.next_tab(),
+            Action::PrevTab => self.prev_tab(),
+            Action::Up => self.move_selection(-1),
+            Action::Down => self.move_selection(1),
+            Action::Top => self.move_selection(isize::MIN),
+            Action::Bottom => self.move_selection(isize::MAX),
+            Action::PageDown => self.change_history_page(1),
+            Action::PageUp => self.change_history_page(-1),
+            Action::Select => match self.current_tab {
+                AppTab::Optimization => {
+                    self.show_optimization_detail = !self.show_optimization_detail;
+                }
+                AppTab::AgentHistory => {
+                    self.show_history_detail = !self.show_history_detail;
+                }
+                AppTab::Sessions => self.sessions.toggle_detail(),
+                AppTab::Usage | AppTab::Run => {}
+            },
+            Action::Apply => {
+                if matches!(self.current_tab, AppTab::Optimization) && self.show_optimization_detail
+                {
+                    self.preview_apply();
+                }
+            }
+            Action::Dismiss | Action::Snooze
+                if matches!(self.current_tab, AppTab::Optimization) =>
+            {
+                let status = match action {
+                    Action::Dismiss => OptimizationStatus::Dismissed,
+                    _ => OptimizationStatus::Snoozed(
+                        chrono::Utc::now() + chrono::Duration::days(SNOOZE_DAYS),
+                    ),
+                };
+                self.hide_selected(status);
+            }
+            Action::Dismiss | Action::Snooze => {}
+            Action::Search => {
+                self.searching = self.current_filter().is_some();
+            }
+            Action::Sort => self.update_filter(ListFilter::cycle_sort),
+            Action::SortOrder => self.update_filter(|filter, _| filter.toggle_order()),
+            Action::Export => self.export_view(),
+            Action::Help => self.show_help = true,
+            Action::Refresh => match self.current_tab {
+                AppTab::Usage => self.refresh_usage(),
+                AppTab::Optimization => self.refresh_optimizations(),
+                AppTab::AgentHistory => self.refresh_history(),
+                AppTab::Sessions => self.refresh_sessions(),
+                AppTab::Run => {}
+            },
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        // Popups take the keyboard; a click only dismisses the help
+        if self.show_help || self.pending_apply.is_some() || self.run.needs_input() {
+            if matches!(mouse.kind, MouseEventKind::Down(_)) {
+                self.show_help = false;
+            }
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.move_selection(-1),
+            MouseEventKind::ScrollDown => self.move_selection(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(tab) = AppTab::at(self.tabs_area.get(), mouse.column, mouse.row) {
+                    self.current_tab = tab;
+                } else if self.show_history_detail {
+                    self.show_history_detail = false;
+                } else {
+                    self.click_list(mouse.column, mouse.row);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the data shown in the current tab to JSON and Markdown files
+    /// in the working directory
+    fn export_view(&mut self) {
+        let export = match self.current_tab {
+            AppTab::Usage => self.usage.data.as_ref().map(Export::usage),
+            AppTab::Optimization => self
+                .optimizations
+                .data
+                .as_ref()
+                .map(|_| Export::optimizations(&self.visible_optimizations())),
+            AppTab::AgentHistory => self
+                .history
+                .data
+                .as_ref()
+                .map(|page| Export::agent_history(&page.entries)),
+            AppTab::Sessions | AppTab::Run => {
+                self.notice = Some(Err("Nothing to export on this tab".to_string()));
+                return;
+            }
+        };
+
+        let Some(export) = export else {
+            self.notice = Some(Err("Nothing loaded yet".to_string()));
+            return;
+        };
+        let written = export.and_then(|export| export.write(&std::env::current_dir()?));
+        self.notice = Some(match written {
+            Ok(stem) => Ok(format!("Exported to {}.json and .md", stem.display())),
+            Err(e) => Err(format!("Export failed: {:#}", e)),
+        });
+    }
+
+    /// Select the clicked row of the current list and open its details
+    fn click_list(&mut self, column: u16, row: u16) {
+        if matches!(self.current_tab, AppTab::Sessions) {
+            self.sessions.click(column, row);
+            return;
+        }
+
+        let Some(index) = self
+            .list_rows
+            .get()
+            .and_then(|rows| rows.index_at(column, row))
+        else {
+            return;
+        };
+
+        self.apply_notice = None;
+        match self.current_tab {
+            AppTab::Optimization => {
+                self.selected_optimization = index;
+                self.show_optimization_detail = true;
+            }
+            AppTab::AgentHistory => {
+                self.selected_history = index;
+                self.show_history_detail = true;
+            }
+            AppTab::Usage | AppTab::Sessions | AppTab::Run => {}
+        }
+    }
+
+    /// Fetch usage in the background unless a fetch is already running
+    fn refresh_usage(&mut self) {
+        let tracker = self.tracker.clone();
+        self.usage.load(
+            async move { tracker.get_usage().await },
+            self.events.sender(),
+        );
+
+        let db = self.db.clone();
+        self.usage_history.load(
+            async move { UsageHistory::load(&db).await },
+            self.events.sender(),
+        );
+    }
+
+    /// Load pending stored optimizations, or derive them from recent
+    /// sessions when nothing has been stored yet
+    fn refresh_optimizations(&mut self) {
+        let db = self.db.clone();
+        let analyzer = self.analyzer.clone();
+        let depth = self.config.analyzer.history_depth;
+
+        self.optimizations.load(
+            async move { load_optimizations(&db, analyzer, depth).await },
+            self.events.sender(),
+        );
+    }
+
+    /// Optimizations matching the filter, in display order
+    fn visible_optimizations(&self) -> Vec<&OptimizationEntry> {
+        let Some(entries) = &self.optimizations.data else {
+            return Vec::new();
+        };
+        self.optimization_filter
+            .apply(entries)
+            .into_iter()
+            .map(|i| &entries[i])
+            .collect()
+    }
+
+    /// The current tab's search and sort state, with the columns it can
+    /// be sorted by
+    fn current_filter(&mut self) -> Option<(&mut ListFilter, &'static [SortKey])> {
+        match self.current_tab {
+            AppTab::Optimization => {
+                Some((&mut self.optimization_filter, OptimizationEntry::SORT_KEYS))
+            }
+            AppTab::AgentHistory => Some((&mut self.history_filter, AgentHistoryEntry::SORT_KEYS)),
+            AppTab::Sessions => Some((&mut self.sessions.filter, SessionSummary::SORT_KEYS)),
+            AppTab::Usage | AppTab::Run => None,
+        }
+    }
+
+    fn filter_is_active(&self) -> bool {
+        match self.current_tab {
+            AppTab::Optimization => self.optimization_filter.is_active(),
+            AppTab::AgentHistory => self.history_filter.is_active(),
+            AppTab::Sessions => self.sessions.filter.is_active(),
+            AppTab::Usage | AppTab::Run => false,
+        }
+    }
+
+    /// Change the current tab's filter and go back to the top of the list
+    fn update_filter(&mut self, update: impl FnOnce(&mut ListFilter, &'static [SortKey])) {
+        let Some((filter, sort_keys)) = self.current_filter() else {
+            return;
+        };
+        update(filter, sort_keys);
+
+        match self.current_tab {
+            AppTab::Optimization => self.selected_optimization = 0,
+            AppTab::AgentHistory => {
+                self.history_page = 0;
+                self.selected_history = 0;
+                self.refresh_history();
+            }
+            AppTab::Sessions => self.sessions.reset_selection(),
+            AppTab::Usage | AppTab::Run => {}
+        }
+    }
+
+    /// Work out the change for the selected optimization and open the
+    /// confirmation popup
+    fn preview_apply(&mut self) {
+        let Some(entry) = self
+            .visible_optimizations()
+            .get(self.selected_optimization)
+            .copied()
+        else {
+            return;
+        };
+
+        let id = entry.id;
+        let preview = std::env::current_dir()
+            .map_err(anyhow::Error::from)
+            .and_then(|dir| ChangePreview::for_optimization(&entry.optimization, &dir));
+        match preview {
+            Ok(preview) => {
+                self.apply_notice = None;
+                self.pending_apply = Some(PendingApply { id, preview });
+            }
+            Err(e) => self.apply_notice = Some(Err(format!("{:#}", e))),
+        }
+    }
+
+    /// Write the previewed change and mark the optimization applied
+    fn confirm_apply(&mut self) {
+        let Some(pending) = self.pending_apply.take() else {
+            return;
+        };
+
+        if let Err(e) = pending.preview.apply() {
+            self.apply_notice = Some(Err(format!("{:#}", e)));
+            return;
+        }
+
+        let path = pending.preview.path.display().to_string();
+        let Some(id) = pending.id else {
+            // Suggestions found in sessions are not stored, so there is nothing to mark
+            self.apply_notice = Some(Ok(format!("Wrote {}", path)));
+            return;
+        };
+
+        self.apply_notice = Some(Ok(format!("Wrote {} and marked #{} applied", path, id)));
+
+        // Applied optimizations drop out of the pending list
+        let db = self.db.clone();
+        let analyzer = self.analyzer.clone();
+        let depth = self.config.analyzer.history_depth;
+        let reload = async move {
+            db.mark_applied(id).await?;
+            load_optimizations(&db, analyzer, depth).await
+        };
+        self.optimizations.load(reload, self.events.sender());
+    }
+
+    /// Dismiss or snooze the selected optimization. Ones found in sessions
+    /// are stored first so that they stay hidden.
+    fn hide_selected(&mut self, status: OptimizationStatus) {
+        let Some(entry) = self
+            .visible_optimizations()
+            .get(self.selected_optimization)
+            .copied()
+        else {
+            return;
+        };
+
+        let id = entry.id;
+        let optimization = entry.optimization.clone();
+        self.show_optimization_detail = false;
+        self.apply_notice = None;
+
+        let db = self.db.clone();
+        let analyzer = self.analyzer.clone();
+        let depth = self.config.analyzer.history_depth;
+        let reload = async move {
+            let id = match id {
+                Some(id) => id,
+                None => db.save_optimization(&optimization).await?,
+            };
+            db.set_optimization_status(id, status).await?;
+            load_optimizations(&db, analyzer, depth).await
+        };
+        self.optimizations.load(reload, self.events.sender());
+    }
+
+    /// Load the current page of agent history
+    fn refresh_history(&mut self) {
+        let db = self.db.clone();
+        let offset = self.history_page * HISTORY_PAGE_SIZE;
+        let filter = self.history_filter.clone();
+
+        let load = async move {
+            if !filter.is_active() {
+                let total = db.count_agent_executions().await?;
+                let entries = db.get_agent_history_page(HISTORY_PAGE_SIZE, offset).await?;
+                return Ok(HistoryPage {
+                    entries,
+                    total,
+                    filter,
+                });
+            }
+
+            let recent = db.get_agent_history_page(HISTORY_SEARCH_WINDOW, 0).await?;
+            let matches = filter.apply(&recent);
+            let total = matches.len();
+
+            let mut recent: Vec<Option<AgentHistoryEntry>> = recent.into_iter().map(Some).collect();
+            let entries = matches
+                .into_iter()
+                .skip(offset)
+                .take(HISTORY_PAGE_SIZE)
+                .filter_map(|i| recent[i].take())
+                .collect();
+
+            Ok(HistoryPage {
+                entries,
+                total,
+                filter,
+            })
+        };
+        self.history.load(load, self.events.sender());
+    }
+
+    /// Parse recent session files for the Sessions tab
+    fn refresh_sessions(&mut self) {
+        self.sessions.refresh(
+            &self.analyzer,
+            self.config.analyzer.history_depth,
+            self.events.sender(),
+        );
+    }
+
+    fn change_history_page(&mut self, delta: isize) {
+        if !matches!(self.current_tab, AppTab::AgentHistory) || self.history.is_loading() {
+            return;
+        }
+
+        let total = self.history.data.as_ref().map_or(0, |p| p.total);
+        let last_page = total.div_ceil(HISTORY_PAGE_SIZE).saturating_sub(1);
+        let page = self
+            .history_page
+            .saturating_add_signed(delta)
+            .min(last_page);
+
+        if page != self.history_page {
+            self.history_page = page;
+            self.selected_history = 0;
+            self.show_history_detail = false;
+            self.refresh_history();
+        }
+    }
+
+    /// Pick up finished loads, and refetch usage once it is older than the
+    /// configured update interval
+    fn poll_panels(&mut self) {
+        self.usage.poll();
+        self.usage_history.poll();
+
+        if self.optimizations.poll() {
+            let len = self.visible_optimizations().len();
+            self.selected_optimization = self.selected_optimization.min(len.saturating_sub(1));
+        }
+
+        // A finished run adds agent executions to the history
+        if self.run.poll() {
+            self.refresh_history();
+        }
+        if self.run.needs_input() {
+            self.current_tab = AppTab::Run;
+        }
+
+        self.sessions.poll();
+
+        if self.history.poll() {
+            let len = self.history.data.as_ref().map_or(0, |p| p.entries.len());
+            self.selected_history = self.selected_history.min(len.saturating_sub(1));
+
+            // The filter changed while the page was loading
+            if self
+                .history
+                .data
+                .as_ref()
+                .is_some_and(|page| page.filter != self.history_filter)
+            {
+                self.refresh_history();
+            }
+        }
+
+        let interval = Duration::from_secs(self.config.statusline.update_interval);
+        if self.usage.is_stale(interval) {
+            self.refresh_usage();
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        self.apply_notice = None;
+
+        let (selected, len) = match self.current_tab {
+            AppTab::Optimization => {
+                let len = self.visible_optimizations().len();
+                (&mut self.selected_optimization, len)
+            }
+            AppTab::AgentHistory => (
+                &mut self.selected_history,
+                self.history.data.as_ref().map_or(0, |p| p.entries.len()),
+            ),
+            AppTab::Sessions => return self.sessions.move_selection(delta),
+            AppTab::Usage | AppTab::Run => return,
+        };
+
+        if len > 0 {
+            *selected = selected.saturating_add_signed(delta).min(len - 1);
+        }
     }
 
     fn next_tab(&mut self) {
         self.current_tab = match self.current_tab {
             AppTab::Usage => AppTab::Optimization,
             AppTab::Optimization => AppTab::AgentHistory,
-            AppTab::AgentHistory => AppTab::Usage,
+            AppTab::AgentHistory => AppTab::Sessions,
+            AppTab::Sessions => AppTab::Run,
+            AppTab::Run => AppTab::Usage,
         };
     }
 
     fn prev_tab(&mut self) {
         self.current_tab = match self.current_tab {
-            AppTab::Usage => AppTab::AgentHistory,
+            AppTab::Usage => AppTab::Run,
             AppTab::Optimization => AppTab::Usage,
             AppTab::AgentHistory => AppTab::Optimization,
+            AppTab::Sessions => AppTab::AgentHistory,
+            AppTab::Run => AppTab::Sessions,
+        };
+    }
+}
+
+/// Pending stored optimizations, or ones found in the last `depth` sessions
+/// when nothing has been stored yet. Dismissed and snoozed ones are left out.
+async fn load_optimizations(
+    db: &Database,
+    analyzer: Arc<SessionAnalyzer>,
+    depth: usize,
+) -> Result<Vec<OptimizationEntry>> {
+    let stored = db
+        .get_optimizations(OPTIMIZATION_LIMIT, false, false)
+        .await?;
+    if !stored.is_empty() {
+        return Ok(stored
+            .into_iter()
+            .map(|s| OptimizationEntry {
+                id: Some(s.id),
+                optimization: s.optimization,
+            })
+            .collect());
+    }
+
+    // Session parsing is blocking file I/O
+    let hidden = db.hidden_optimizations().await?;
+    let found = tokio::task::spawn_blocking(move || analyzer.find_optimizations(depth)).await??;
+    Ok(found
+        .into_iter()
+        .filter(|optimization| !hidden.hides(optimization))
+        .map(|optimization| OptimizationEntry {
+            id: None,
+            optimization,
+        })
+        .collect())
+}
+
+fn render_optimization_detail(
+    f: &mut Frame,
+    area: Rect,
+    entry: &OptimizationEntry,
+    notice: Option<&Result<String, String>>,
+) {
+    let opt = &entry.optimization;
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut text = vec![
+        Line::from(Span::styled(opt.title.clone(), bold)),
+        Line::from(""),
+        Line::from(format!("Type: {}", opt.opt_type)),
+    ];
+    if let Some(id) = entry.id {
+        text.push(Line::from(format!("Stored as: #{}", id)));
+    }
+    text.push(Line::from(vec![
+        Span::styled("Savings: ", theme().success),
+        Span::raw(format!("~{} tokens per occurrence", opt.estimated_savings)),
+    ]));
+
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled("Description", bold)));
+    text.push(Line::from(opt.description.clone()));
+
+    if !opt.examples.is_empty() {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled("Examples", bold)));
+        for example in &opt.examples {
+            text.push(Line::from(format!("  • {}", example)));
+        }
+    }
+
+    if let Some(suggestion) = &opt.suggestion {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "Suggestion",
+            theme().success.add_modifier(Modifier::BOLD),
+        )));
+        text.push(Line::from(suggestion.clone()));
+    }
+
+    if let Some(notice) = notice {
+        let (message, style) = match notice {
+            Ok(message) => (message, theme().success),
+            Err(message) => (message, theme().error),
         };
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(message.clone(), style)));
+    }
+
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Details (a apply, x dismiss, z snooze, Esc close)"),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_apply_popup(f: &mut Frame, area: Rect, pending: &PendingApply) {
+    let text: Vec<Line> = pending
+        .preview
+        .diff()
+        .into_iter()
+        .map(|line| match line {
+            DiffLine::Context(l) => Line::from(format!("  {}", l)),
+            DiffLine::Added(l) => Line::from(Span::styled(format!("+ {}", l), theme().success)),
+            DiffLine::Removed(l) => Line::from(Span::styled(format!("- {}", l), theme().error)),
+        })
+        .collect();
+
+    let title = format!(
+        "Apply to {} (y confirm, n cancel)",
+        pending.preview.path.display()
+    );
+    f.render_widget(Popup::new(title, text), area);
+}
+
+fn render_history_detail(f: &mut Frame, area: Rect, entry: &AgentHistoryEntry) {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let result = if entry.success {
+        Span::styled("✓ Success", theme().success)
+    } else {
+        Span::styled("✗ Failed", theme().error)
+    };
+
+    let mut text = vec![
+        Line::from(Span::styled(
+            format!("{} {}", entry.capability.emoji(), entry.agent_type),
+            bold,
+        )),
+        Line::from(format!("Agent ID: {}", entry.agent_id)),
+        Line::from(format!("Capability: {}", entry.capability.description())),
+        Line::from(format!(
+            "Started: {}",
+            entry
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        )),
+        Line::from(format!("Tokens: {}", entry.tokens_used)),
+        Line::from(format!("Duration: {:.1}s", entry.execution_time_secs)),
+        Line::from(vec![Span::raw("Result: "), result]),
+        Line::from(""),
+        Line::from(Span::styled("Task", bold)),
+    ];
+    text.extend(entry.task.lines().map(|line| Line::from(line.to_string())));
+
+    f.render_widget(Popup::new("Execution (Esc to close)", text), area);
+}
+
+fn usage_gauge(title: &str, used: usize, limit: usize, percent: u8) -> Gauge<'_> {
+    let style = match percent {
+        0..=59 => theme().success,
+        60..=79 => theme().warning,
+        _ => theme().error,
+    };
+
+    Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .gauge_style(style)
+        .percent(u16::from(percent.min(100)))
+        .label(format!("{}% ({}k/{}k)", percent, used / 1000, limit / 1000))
+}
+
+/// Time left in the 5-hour block, counting down from the last fetch
+fn reset_countdown(minutes_remaining: u32, elapsed: Duration) -> String {
+    let remaining = u64::from(minutes_remaining) * 60;
+    let left = remaining.saturating_sub(elapsed.as_secs());
+    if left == 0 {
+        return "soon".to_string();
+    }
+
+    let minutes = left.div_ceil(60);
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_countdown() {
+        assert_eq!(reset_countdown(150, Duration::ZERO), "2h 30m");
+        assert_eq!(reset_countdown(150, Duration::from_secs(90)), "2h 29m");
+        assert_eq!(reset_countdown(120, Duration::from_secs(1)), "2h 0m");
+        assert_eq!(reset_countdown(10, Duration::from_secs(600)), "soon");
+        assert_eq!(reset_countdown(0, Duration::ZERO), "soon");
+    }
+
+    #[test]
+    fn test_tab_at_column() {
+        let area = Rect::new(0, 3, 120, 3);
+        // " Usage │ Optimizations │ ..." inside the left border
+        assert_eq!(AppTab::at(area, 0, 4), None);
+        assert_eq!(AppTab::at(area, 1, 4), Some(AppTab::Usage));
+        assert_eq!(AppTab::at(area, 8, 4), Some(AppTab::Usage));
+        assert_eq!(AppTab::at(area, 9, 4), Some(AppTab::Optimization));
+        assert_eq!(AppTab::at(area, 20, 4), Some(AppTab::Optimization));
+        // The border row above the titles
+        assert_eq!(AppTab::at(area, 9, 3), None);
+        assert_eq!(AppTab::at(area, 119, 4), None);
+
+        // " Usage │ Opt │ History │ ..." on a narrow screen
+        let narrow = Rect::new(0, 3, 60, 3);
+        assert_eq!(AppTab::at(narrow, 9, 4), Some(AppTab::Optimization));
+        assert_eq!(AppTab::at(narrow, 20, 4), Some(AppTab::AgentHistory));
     }
 }
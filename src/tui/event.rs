@@ -0,0 +1,61 @@
+use crossterm::event::{Event, EventStream};
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Something the dashboard reacts to by updating its state and redrawing
+#[derive(Debug)]
+pub enum AppEvent {
+    /// A key press, mouse action or terminal resize
+    Input(Event),
+    /// A background load finished
+    Loaded,
+    /// Drives spinners, run progress and periodic refreshes
+    Tick,
+}
+
+/// Merges terminal input, background load notifications and a periodic
+/// tick into one channel, so the render loop only wakes up when there is
+/// something to do
+pub struct Events {
+    rx: mpsc::UnboundedReceiver<AppEvent>,
+    tx: mpsc::UnboundedSender<AppEvent>,
+}
+
+impl Events {
+    pub fn new(tick_rate: Duration) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let input = tx.clone();
+        tokio::spawn(async move {
+            let mut stream = EventStream::new();
+            while let Some(Ok(event)) = stream.next().await {
+                if input.send(AppEvent::Input(event)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let tick = tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick_rate);
+            loop {
+                interval.tick().await;
+                if tick.send(AppEvent::Tick).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { rx, tx }
+    }
+
+    /// Sender for background tasks to report back on
+    pub fn sender(&self) -> &mpsc::UnboundedSender<AppEvent> {
+        &self.tx
+    }
+
+    pub async fn next(&mut self) -> Option<AppEvent> {
+        self.rx.recv().await
+    }
+}
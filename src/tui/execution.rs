@@ -0,0 +1,631 @@
+use super::widgets::{centered_rect, split_panes, theme, Popup};
+use crate::master::run_log::RunLogReader;
+use crate::master::{
+    AgentPrompt, ExecutionPlan, ExecutionResult, PromptDecision, RunEvent, TaskAnalysis,
+};
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, Paragraph},
+    Frame,
+};
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::{mpsc, oneshot};
+
+/// Log lines kept for the log tail
+const LOG_LINES: usize = 200;
+
+/// Where a run's events come from
+enum Source {
+    /// A run launched from this TUI
+    Channel(mpsc::UnboundedReceiver<RunEvent>),
+    /// A run in another process, followed through its run log
+    Log(RunLogReader),
+}
+
+/// How a run ended
+enum Outcome {
+    Finished(ExecutionResult),
+    Cancelled,
+    Failed(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum AgentState {
+    Running,
+    Done,
+    Failed,
+}
+
+struct AgentProgress {
+    state: AgentState,
+    tokens_used: usize,
+}
+
+/// An agent prompt waiting to be run, edited or skipped (interactive mode)
+struct PromptReview {
+    prompt: AgentPrompt,
+    reply: oneshot::Sender<PromptDecision>,
+    /// Typed keys edit the task
+    editing: bool,
+}
+
+/// Live view of one Master Coder run, rebuilt from its [`RunEvent`]s
+pub struct Execution {
+    source: Source,
+    run_id: Option<String>,
+    task: String,
+    budget: Option<usize>,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    analysis: Option<TaskAnalysis>,
+    plan: Option<ExecutionPlan>,
+    current_phase: Option<usize>,
+    skipped_phases: Vec<usize>,
+    agents: HashMap<String, AgentProgress>,
+    log: VecDeque<String>,
+    approval: Option<(String, oneshot::Sender<bool>)>,
+    review: Option<PromptReview>,
+    outcome: Option<Outcome>,
+}
+
+impl Execution {
+    fn new(source: Source, task: String, budget: Option<usize>) -> Self {
+        Self {
+            source,
+            run_id: None,
+            task,
+            budget,
+            started_at: Utc::now(),
+            ended_at: None,
+            analysis: None,
+            plan: None,
+            current_phase: None,
+            skipped_phases: Vec::new(),
+            agents: HashMap::new(),
+            log: VecDeque::new(),
+            approval: None,
+            review: None,
+            outcome: None,
+        }
+    }
+
+    /// Follow a run launched from this TUI
+    pub fn launched(
+        events: mpsc::UnboundedReceiver<RunEvent>,
+        task: String,
+        budget: usize,
+    ) -> Self {
+        Self::new(Source::Channel(events), task, Some(budget))
+    }
+
+    /// Follow a run started elsewhere, by its run ID
+    pub fn attach(run_id: &str) -> Result<Self> {
+        let reader = RunLogReader::open(run_id)?;
+        let mut execution = Self::new(Source::Log(reader), String::new(), None);
+        execution.run_id = Some(run_id.to_string());
+        Ok(execution)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn needs_approval(&self) -> bool {
+        self.approval.is_some() || self.review.is_some()
+    }
+
+    /// Answer a pending approval with y/n, or a prompt review with
+    /// y/e/s. Returns false if the key is not an answer.
+    pub fn answer_approval(&mut self, code: KeyCode) -> bool {
+        if self.review.is_some() {
+            return self.answer_review(code);
+        }
+
+        let approved = match code {
+            KeyCode::Char('y') | KeyCode::Enter => true,
+            KeyCode::Char('n') | KeyCode::Esc => false,
+            _ => return false,
+        };
+
+        if let Some((_, reply)) = self.approval.take() {
+            let _ = reply.send(approved);
+            self.push_log(Utc::now(), if approved { "Approved" } else { "Declined" });
+        }
+        true
+    }
+
+    /// Run (y), edit the task of (e) or skip (s) the agent under review.
+    /// While editing, keys type into the task until Enter or Esc.
+    fn answer_review(&mut self, code: KeyCode) -> bool {
+        let Some(review) = &mut self.review else {
+            return false;
+        };
+
+        if review.editing {
+            match code {
+                KeyCode::Char(c) => review.prompt.task.push(c),
+                KeyCode::Backspace => {
+                    review.prompt.task.pop();
+                }
+                KeyCode::Enter | KeyCode::Esc => review.editing = false,
+                _ => return false,
+            }
+            return true;
+        }
+
+        let run = match code {
+            KeyCode::Char('y') | KeyCode::Enter => true,
+            KeyCode::Char('s') | KeyCode::Char('n') | KeyCode::Esc => false,
+            KeyCode::Char('e') => {
+                review.editing = true;
+                return true;
+            }
+            _ => return false,
+        };
+
+        if let Some(review) = self.review.take() {
+            let line = if run {
+                format!("Running {}", review.prompt.agent_type)
+            } else {
+                format!("Skipped {}", review.prompt.agent_type)
+            };
+            let decision = if run {
+                PromptDecision::Run(review.prompt)
+            } else {
+                PromptDecision::Skip
+            };
+            let _ = review.reply.send(decision);
+            self.push_log(Utc::now(), &line);
+        }
+        true
+    }
+
+    /// Apply new events. Returns true when the run ended.
+    pub fn poll(&mut self) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+
+        let events = match &mut self.source {
+            Source::Channel(rx) => {
+                let mut events = Vec::new();
+                while let Ok(event) = rx.try_recv() {
+                    events.push((Utc::now(), event));
+                }
+                events
+            }
+            Source::Log(reader) => match reader.read_new() {
+                Ok(events) => events,
+                Err(e) => {
+                    self.push_log(Utc::now(), &format!("Failed to read run log: {:#}", e));
+                    Vec::new()
+                }
+            },
+        };
+
+        for (at, event) in events {
+            self.apply(at, event);
+        }
+
+        self.is_finished()
+    }
+
+    fn apply(&mut self, at: DateTime<Utc>, event: RunEvent) {
+        let line = match event {
+            RunEvent::Started {
+                run_id,
+                task,
+                mode,
+                token_budget,
+            } => {
+                self.started_at = at;
+                self.task = task;
+                self.budget = Some(token_budget);
+                let line = format!("Run {} started ({} mode)", run_id, mode);
+                if !run_id.is_empty() {
+                    self.run_id = Some(run_id);
+                }
+                line
+            }
+            RunEvent::Analyzed(analysis) => {
+                let line = format!(
+                    "Analysis: complexity {}/10, ~{} tokens",
+                    analysis.complexity, analysis.estimated_tokens
+                );
+                self.analysis = Some(analysis);
+                line
+            }
+            RunEvent::Planned(plan) => {
+                let line = format!(
+                    "Plan: {} phases, {} agents",
+                    plan.phases.len(),
+                    plan.total_agents()
+                );
+                self.plan = Some(plan);
+                line
+            }
+            RunEvent::AgentsCreated { count } => format!("{} agents created", count),
+            RunEvent::PhaseStarted {
+                index,
+                total,
+                description,
+                ..
+            } => {
+                self.current_phase = Some(index);
+                format!("Phase {}/{}: {}", index + 1, total, description)
+            }
+            RunEvent::PhaseSkipped { index } => {
+                self.skipped_phases.push(index);
+                format!("Phase {} skipped", index + 1)
+            }
+            RunEvent::AgentStarted {
+                agent_id,
+                agent_type,
+            } => {
+                self.agents.insert(
+                    agent_id,
+                    AgentProgress {
+                        state: AgentState::Running,
+                        tokens_used: 0,
+                    },
+                );
+                format!("{} started", agent_type)
+            }
+            RunEvent::AgentFinished {
+                agent_id,
+                tokens_used,
+                error,
+            } => {
+                let state = if error.is_some() {
+                    AgentState::Failed
+                } else {
+                    AgentState::Done
+                };
+                let line = match &error {
+                    Some(error) => error.clone(),
+                    None => format!(
+                        "{} finished ({} tokens)",
+                        self.agent_type(&agent_id),
+                        tokens_used
+                    ),
+                };
+                self.agents
+                    .insert(agent_id, AgentProgress { state, tokens_used });
+                line
+            }
+            RunEvent::Message(message) => message,
+            RunEvent::ApprovalNeeded { prompt, reply } => {
+                let line = format!("Waiting for approval: {}", prompt);
+                self.approval = Some((prompt, reply));
+                line
+            }
+            RunEvent::PromptReviewNeeded { prompt, reply } => {
+                let line = format!("Waiting for review of the prompt of {}", prompt.agent_type);
+                self.review = Some(PromptReview {
+                    prompt,
+                    reply,
+                    editing: false,
+                });
+                line
+            }
+            RunEvent::Finished(result) => {
+                for warning in &result.warnings {
+                    self.push_log(at, &format!("⚠ {}", warning));
+                }
+                let line = format!(
+                    "Finished {}: {} agents, {} tokens, {:.1}s",
+                    if result.success {
+                        "successfully"
+                    } else {
+                        "with errors"
+                    },
+                    result.agents_executed,
+                    result.tokens_used,
+                    result.execution_time_secs
+                );
+                self.end(at, Outcome::Finished(result));
+                line
+            }
+            RunEvent::Cancelled => {
+                self.end(at, Outcome::Cancelled);
+                "Cancelled".to_string()
+            }
+            RunEvent::Failed(e) => {
+                let line = format!("Failed: {}", e);
+                self.end(at, Outcome::Failed(e));
+                line
+            }
+        };
+
+        self.push_log(at, &line);
+    }
+
+    fn end(&mut self, at: DateTime<Utc>, outcome: Outcome) {
+        self.ended_at = Some(at);
+        self.outcome = Some(outcome);
+        self.approval = None;
+        self.review = None;
+    }
+
+    fn push_log(&mut self, at: DateTime<Utc>, line: &str) {
+        let time = at.with_timezone(&Local).format("%H:%M:%S");
+        self.log.push_back(format!("{} {}", time, line));
+        while self.log.len() > LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    fn agent_type(&self, agent_id: &str) -> String {
+        self.plan
+            .iter()
+            .flat_map(|plan| &plan.phases)
+            .flat_map(|phase| &phase.agents)
+            .find(|spec| spec.id == agent_id)
+            .map_or_else(|| agent_id.to_string(), |spec| spec.agent_type.clone())
+    }
+
+    fn tokens_used(&self) -> usize {
+        self.agents.values().map(|a| a.tokens_used).sum()
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(6), Constraint::Min(0)])
+            .split(area);
+
+        self.render_summary(f, chunks[0]);
+
+        let (tree, log) = split_panes(chunks[1], 55);
+        self.render_tree(f, tree);
+        self.render_log(f, log);
+
+        if let Some((prompt, _)) = &self.approval {
+            render_approval(f, centered_rect(60, 30, area), prompt);
+        }
+        if let Some(review) = &self.review {
+            render_review(f, centered_rect(80, 80, area), review);
+        }
+    }
+
+    fn render_summary(&self, f: &mut Frame, area: Rect) {
+        let title = match (&self.run_id, &self.source) {
+            (Some(id), Source::Log(_)) => format!("Run {} (attached)", id),
+            (Some(id), Source::Channel(_)) => format!("Run {}", id),
+            (None, _) => "Run".to_string(),
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Length(1)])
+            .split(inner);
+
+        let status = match (&self.outcome, self.current_phase, &self.plan) {
+            (Some(Outcome::Finished(result)), _, _) if result.success => {
+                Span::styled("✓ Complete", theme().success)
+            }
+            (Some(Outcome::Finished(_)), _, _) => {
+                Span::styled("✗ Finished with errors", theme().error)
+            }
+            (Some(Outcome::Cancelled), _, _) => Span::styled("Cancelled", theme().warning),
+            (Some(Outcome::Failed(e)), _, _) => {
+                Span::styled(format!("✗ Failed: {}", e), theme().error)
+            }
+            (None, Some(index), Some(plan)) => Span::raw(format!(
+                "Phase {}/{}: {}",
+                index + 1,
+                plan.phases.len(),
+                plan.phases
+                    .get(index)
+                    .map_or("", |phase| phase.description.as_str())
+            )),
+            (None, _, Some(_)) => Span::raw("Waiting to start..."),
+            (None, _, None) => Span::raw("Analyzing task..."),
+        };
+
+        let elapsed = (self.ended_at.unwrap_or_else(Utc::now) - self.started_at)
+            .num_seconds()
+            .max(0);
+        let budget = self
+            .budget
+            .map(|b| format!(" / {} budget", b))
+            .unwrap_or_default();
+        let estimate = self
+            .analysis
+            .as_ref()
+            .map(|a| format!(" (estimated ~{})", a.estimated_tokens))
+            .unwrap_or_default();
+
+        let text = vec![
+            Line::from(format!("Task: {}", self.task)),
+            Line::from(vec![Span::raw("Status: "), status]),
+            Line::from(format!(
+                "Tokens: {}{}{} | Elapsed: {}s",
+                self.tokens_used(),
+                budget,
+                estimate,
+                elapsed
+            )),
+        ];
+        f.render_widget(Paragraph::new(text), chunks[0]);
+
+        let total = self.plan.as_ref().map_or(0, ExecutionPlan::total_agents);
+        let done = self
+            .agents
+            .values()
+            .filter(|a| a.state != AgentState::Running)
+            .count();
+        let ratio = if total > 0 {
+            (done as f64 / total as f64).min(1.0)
+        } else {
+            0.0
+        };
+        let gauge = Gauge::default()
+            .gauge_style(theme().chart)
+            .ratio(ratio)
+            .label(format!("{}/{} agents", done, total));
+        f.render_widget(gauge, chunks[1]);
+    }
+
+    /// Phases and their agents, with a status icon for each and, in a
+    /// dependency graph, what waiting agents wait for
+    fn render_tree(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title("Agents");
+
+        let Some(plan) = &self.plan else {
+            f.render_widget(Paragraph::new("Waiting for plan...").block(block), area);
+            return;
+        };
+
+        let mut text = Vec::new();
+        for (index, phase) in plan.phases.iter().enumerate() {
+            let finished = phase.agents.iter().all(|spec| {
+                self.agents
+                    .get(&spec.id)
+                    .is_some_and(|a| a.state != AgentState::Running)
+            });
+
+            let (marker, style) = if self.skipped_phases.contains(&index) {
+                ("⊘", theme().muted)
+            } else if finished {
+                ("✓", theme().success)
+            } else if self.current_phase == Some(index) {
+                ("▶", theme().warning)
+            } else {
+                ("·", Style::default())
+            };
+
+            text.push(Line::from(vec![
+                Span::styled(format!("{} ", marker), style),
+                Span::styled(
+                    format!(
+                        "Phase {}: {}{}",
+                        index + 1,
+                        phase.description,
+                        if phase.parallel { " ⚡" } else { "" }
+                    ),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ]));
+
+            for spec in &phase.agents {
+                let agent = self.agents.get(&spec.id);
+                let (icon, style) = match agent.map(|a| a.state) {
+                    None => ("○", theme().muted),
+                    Some(AgentState::Running) => ("◐", theme().warning),
+                    Some(AgentState::Done) => ("✓", theme().success),
+                    Some(AgentState::Failed) => ("✗", theme().error),
+                };
+                let tokens = agent
+                    .filter(|a| a.state != AgentState::Running)
+                    .map(|a| format!(" ({} tokens)", a.tokens_used))
+                    .unwrap_or_default();
+                // In a dependency graph, waiting agents show what they wait for
+                let after = plan
+                    .waits_for(spec)
+                    .filter(|_| agent.is_none())
+                    .map(|after| format!(" ({})", after))
+                    .unwrap_or_default();
+
+                text.push(Line::from(vec![
+                    Span::raw("   "),
+                    Span::styled(icon, style),
+                    Span::raw(format!(" {} - {}{}", spec.agent_type, spec.task, tokens)),
+                    Span::styled(after, theme().muted),
+                ]));
+            }
+        }
+
+        f.render_widget(Paragraph::new(text).block(block), area);
+    }
+
+    /// The newest log lines that fit
+    fn render_log(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title("Log");
+        let height = block.inner(area).height as usize;
+
+        let skip = self.log.len().saturating_sub(height);
+        let text: Vec<Line> = self
+            .log
+            .iter()
+            .skip(skip)
+            .map(|line| Line::from(line.as_str()))
+            .collect();
+
+        f.render_widget(Paragraph::new(text).block(block), area);
+    }
+}
+
+/// The task first, so it stays visible when a long system prompt is cut off
+fn render_review(f: &mut Frame, area: Rect, review: &PromptReview) {
+    let mut task = vec![Span::raw(review.prompt.task.clone())];
+    if review.editing {
+        task.push(Span::styled("▏", theme().key));
+    }
+    let keys = if review.editing {
+        vec![
+            Span::styled("Enter", theme().key),
+            Span::raw(": done editing"),
+        ]
+    } else {
+        vec![
+            Span::styled("y", theme().key),
+            Span::raw(": run   "),
+            Span::styled("e", theme().key),
+            Span::raw(": edit task   "),
+            Span::styled("s", theme().key),
+            Span::raw(": skip"),
+        ]
+    };
+
+    let mut text = vec![
+        Line::from(Span::styled(
+            "Task",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(task),
+        Line::from(""),
+        Line::from(keys),
+        Line::from(""),
+        Line::from(Span::styled(
+            "System prompt",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    text.extend(
+        review
+            .prompt
+            .system_prompt
+            .lines()
+            .map(|line| Line::from(Span::styled(line.to_string(), theme().muted))),
+    );
+
+    f.render_widget(
+        Popup::new(format!("Prompt of {}", review.prompt.agent_type), text),
+        area,
+    );
+}
+
+fn render_approval(f: &mut Frame, area: Rect, prompt: &str) {
+    let text = vec![
+        Line::from(""),
+        Line::from(prompt.to_string()),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("y", theme().key),
+            Span::raw(": yes   "),
+            Span::styled("n", theme().key),
+            Span::raw(": no"),
+        ]),
+    ];
+
+    f.render_widget(Popup::new("Approval needed", text), area);
+}
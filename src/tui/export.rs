@@ -0,0 +1,166 @@
+use super::app::OptimizationEntry;
+use crate::db::AgentHistoryEntry;
+use crate::statusline::Usage;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The data of one view, written side by side as JSON and Markdown
+pub struct Export {
+    /// Used in the file names, e.g. "usage"
+    view: &'static str,
+    json: String,
+    markdown: String,
+}
+
+impl Export {
+    pub fn usage(usage: &Usage) -> Result<Self> {
+        let mut md = heading("Claude usage");
+        md.push_str("| | Used | Limit | Percent |\n|---|---|---|---|\n");
+        let _ = writeln!(
+            md,
+            "| 5-hour block | {} | {} | {}% |",
+            usage.five_hour_used, usage.five_hour_limit, usage.five_hour_percent
+        );
+        let _ = writeln!(
+            md,
+            "| 7-day total | {} | {} | {}% |",
+            usage.seven_day_used, usage.seven_day_limit, usage.seven_day_percent
+        );
+        let _ = writeln!(
+            md,
+            "\nBlock resets in {} minutes.\n\nBurn rate: ${:.2}/hour  \nEstimated 7-day cost: ${:.2}",
+            usage.five_hour_minutes_remaining,
+            usage.burn_rate_per_hour,
+            usage.estimated_seven_day_cost
+        );
+
+        Self::new("usage", usage, md)
+    }
+
+    pub fn optimizations(entries: &[&OptimizationEntry]) -> Result<Self> {
+        let mut md = heading("Optimization suggestions");
+        if entries.is_empty() {
+            md.push_str("No suggestions.\n");
+        }
+
+        for entry in entries {
+            let opt = &entry.optimization;
+            let id = entry.id.map(|id| format!("#{} ", id)).unwrap_or_default();
+            let _ = writeln!(md, "## {}{}\n", id, opt.title);
+            let _ = writeln!(
+                md,
+                "*{}, saves ~{} tokens*\n\n{}\n",
+                opt.opt_type, opt.estimated_savings, opt.description
+            );
+            if let Some(suggestion) = &opt.suggestion {
+                let _ = writeln!(md, "{}\n", suggestion);
+            }
+            for example in &opt.examples {
+                let _ = writeln!(md, "- `{}`", example);
+            }
+            if !opt.examples.is_empty() {
+                md.push('\n');
+            }
+        }
+
+        Self::new("optimizations", entries, md)
+    }
+
+    pub fn agent_history(entries: &[AgentHistoryEntry]) -> Result<Self> {
+        let mut md = heading("Agent executions");
+        md.push_str("| Time | Agent | Task | Tokens | Duration | Result |\n");
+        md.push_str("|---|---|---|---|---|---|\n");
+        for entry in entries {
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {:.1}s | {} |",
+                entry
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M"),
+                table_cell(&entry.agent_type),
+                table_cell(entry.task.lines().next().unwrap_or_default()),
+                entry.tokens_used,
+                entry.execution_time_secs,
+                if entry.success { "✓" } else { "✗" }
+            );
+        }
+
+        Self::new("agent-history", entries, md)
+    }
+
+    fn new<T: Serialize + ?Sized>(view: &'static str, data: &T, markdown: String) -> Result<Self> {
+        Ok(Self {
+            view,
+            json: serde_json::to_string_pretty(data)?,
+            markdown,
+        })
+    }
+
+    /// Write `<name>.json` and `<name>.md` into `dir`. Returns the path
+    /// without the extension.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        let stem = dir.join(format!(
+            "claude-helper-{}-{}",
+            self.view,
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        for (extension, contents) in [("json", &self.json), ("md", &self.markdown)] {
+            let path = stem.with_extension(extension);
+            fs::write(&path, contents).with_context(|| format!("Failed to write {:?}", path))?;
+        }
+
+        Ok(stem)
+    }
+}
+
+fn heading(title: &str) -> String {
+    format!(
+        "# {}\n\nExported {}\n\n",
+        title,
+        Local::now().format("%Y-%m-%d %H:%M")
+    )
+}
+
+/// Keep a value from breaking out of its Markdown table cell
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentCapability;
+    use chrono::Utc;
+
+    #[test]
+    fn test_agent_history_export() {
+        let entries = vec![AgentHistoryEntry {
+            agent_id: "1".to_string(),
+            agent_type: "Code Writer".to_string(),
+            capability: AgentCapability::CodeWriting,
+            task: "Pipe a | b\nsecond line".to_string(),
+            tokens_used: 1200,
+            execution_time_secs: 3.5,
+            success: true,
+            timestamp: Utc::now(),
+        }];
+        let export = Export::agent_history(&entries).unwrap();
+        assert!(export
+            .markdown
+            .contains("| Code Writer | Pipe a \\| b | 1200 | 3.5s | ✓ |"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let stem = export.write(dir.path()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(stem.with_extension("json")).unwrap())
+                .unwrap();
+        assert_eq!(json[0]["tokens_used"], 1200);
+        assert!(stem.with_extension("md").exists());
+    }
+}
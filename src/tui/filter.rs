@@ -0,0 +1,150 @@
+use std::cmp::Ordering;
+
+/// Column a list is sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// The order the list was loaded in
+    #[default]
+    Default,
+    Date,
+    Tokens,
+    Success,
+}
+
+impl SortKey {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Default => "default",
+            SortKey::Date => "date",
+            SortKey::Tokens => "tokens",
+            SortKey::Success => "success",
+        }
+    }
+}
+
+/// A list entry that can be searched and sorted
+pub trait Filterable {
+    /// Columns the list can be sorted by, besides its default order
+    const SORT_KEYS: &'static [SortKey];
+
+    /// Text the search query is matched against
+    fn search_text(&self) -> String;
+
+    /// Compare two entries by `key`, ascending
+    fn compare(&self, other: &Self, key: SortKey) -> Ordering;
+}
+
+/// Search query and sort order of one list view
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListFilter {
+    pub query: String,
+    sort: SortKey,
+    ascending: bool,
+}
+
+impl ListFilter {
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty() || self.sort != SortKey::Default
+    }
+
+    /// Switch to the next of `keys`, then back to the default order
+    pub fn cycle_sort(&mut self, keys: &[SortKey]) {
+        let keys: Vec<SortKey> = std::iter::once(SortKey::Default)
+            .chain(keys.iter().copied())
+            .collect();
+        let index = keys.iter().position(|k| *k == self.sort).unwrap_or(0);
+        self.sort = keys[(index + 1) % keys.len()];
+    }
+
+    pub fn toggle_order(&mut self) {
+        self.ascending = !self.ascending;
+    }
+
+    /// Indices of the entries matching the query, in display order.
+    /// Sorting is stable, so ties keep their default order.
+    pub fn apply<T: Filterable>(&self, items: &[T]) -> Vec<usize> {
+        let needle = self.query.to_lowercase();
+        let mut indices: Vec<usize> = (0..items.len())
+            .filter(|&i| {
+                needle.is_empty() || items[i].search_text().to_lowercase().contains(&needle)
+            })
+            .collect();
+
+        if self.sort != SortKey::Default {
+            indices.sort_by(|&a, &b| {
+                let ordering = items[a].compare(&items[b], self.sort);
+                if self.ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            });
+        }
+
+        indices
+    }
+
+    /// Suffix for a list title, e.g. " [search: git_, sort: tokens ↓]"
+    pub fn describe(&self, editing: bool) -> String {
+        let mut parts = Vec::new();
+        if editing || !self.query.is_empty() {
+            let cursor = if editing { "_" } else { "" };
+            parts.push(format!("search: {}{}", self.query, cursor));
+        }
+        if self.sort != SortKey::Default {
+            let arrow = if self.ascending { "↑" } else { "↓" };
+            parts.push(format!("sort: {} {}", self.sort.label(), arrow));
+        }
+
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", parts.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item(&'static str, usize);
+
+    impl Filterable for Item {
+        const SORT_KEYS: &'static [SortKey] = &[SortKey::Tokens];
+
+        fn search_text(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn compare(&self, other: &Self, _key: SortKey) -> Ordering {
+            self.1.cmp(&other.1)
+        }
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let items = [
+            Item("git push", 10),
+            Item("cargo test", 30),
+            Item("Git commit", 20),
+        ];
+        let mut filter = ListFilter::default();
+        assert_eq!(filter.apply(&items), vec![0, 1, 2]);
+
+        filter.query = "GIT".to_string();
+        assert_eq!(filter.apply(&items), vec![0, 2]);
+
+        filter.cycle_sort(Item::SORT_KEYS);
+        assert_eq!(filter.describe(false), " [search: GIT, sort: tokens ↓]");
+        assert_eq!(filter.apply(&items), vec![2, 0]);
+
+        filter.toggle_order();
+        assert_eq!(filter.apply(&items), vec![0, 2]);
+
+        // Cycling past the last column returns to the default order
+        filter.cycle_sort(Item::SORT_KEYS);
+        filter.query.clear();
+        assert!(!filter.is_active());
+    }
+}
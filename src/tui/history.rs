@@ -0,0 +1,254 @@
+use super::widgets::{is_narrow, split_panes, theme, token_bar_chart};
+use crate::db::{DailyUsage, Database, HourlyBreakdown};
+use crate::report::heatmap::WEEKDAYS;
+use crate::report::Heatmap;
+use anyhow::Result;
+use chrono::{DateTime, DurationRound, Local, NaiveDate, TimeDelta, Utc};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+/// Hours shown in the hourly chart
+const HOURS: usize = 24;
+
+/// Days shown in the daily chart
+const DAYS: usize = 14;
+
+/// Length of a usage block; the most recent hours are highlighted
+const BLOCK_HOURS: usize = 5;
+
+/// Weeks of history in the weekday × hour heatmap
+const HEATMAP_WEEKS: usize = 4;
+
+/// Rows the heatmap takes: a header, one row per weekday and the borders
+const HEATMAP_HEIGHT: u16 = 10;
+
+/// Rows the daily chart keeps when the heatmap is shown below it
+const MIN_DAILY_HEIGHT: u16 = 6;
+
+/// Token counts for the usage charts, oldest first
+pub struct UsageHistory {
+    hourly: Vec<(String, u64)>,
+    daily: Vec<(String, u64)>,
+    heatmap: Heatmap,
+}
+
+impl UsageHistory {
+    pub async fn load(db: &Database) -> Result<Self> {
+        let now = Utc::now();
+        let hourly = db.get_hourly_breakdown(HOURS).await?;
+        let daily = db.get_daily_usage(DAYS).await?;
+        let weeks = db.get_hourly_breakdown(HEATMAP_WEEKS * 7 * 24).await?;
+
+        Ok(Self {
+            hourly: hourly_series(&hourly, now, HOURS),
+            daily: daily_series(&daily, now.date_naive(), DAYS),
+            heatmap: Heatmap::from_hourly(&weeks, &Local),
+        })
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let (hourly_area, daily_area) = split_panes(area, 60);
+
+        let hourly_total: u64 = self.hourly.iter().map(|(_, t)| t).sum();
+        let hourly_title = if is_narrow(area) {
+            format!("Tokens/hour, {}h: {}", HOURS, hourly_total)
+        } else {
+            format!(
+                "Tokens per hour, last {}h: {} (current {}h window highlighted)",
+                HOURS, hourly_total, BLOCK_HOURS
+            )
+        };
+        let hourly = token_bar_chart(hourly_title, &self.hourly, BLOCK_HOURS, hourly_area.width);
+        f.render_widget(hourly, hourly_area);
+
+        // The heatmap goes under the daily chart when both fit
+        let daily_area = if daily_area.height >= HEATMAP_HEIGHT + MIN_DAILY_HEIGHT {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(MIN_DAILY_HEIGHT),
+                    Constraint::Length(HEATMAP_HEIGHT),
+                ])
+                .split(daily_area);
+            self.render_heatmap(f, chunks[1]);
+            chunks[0]
+        } else {
+            daily_area
+        };
+
+        let daily_total: u64 = self.daily.iter().map(|(_, t)| t).sum();
+        let daily = token_bar_chart(
+            format!("Tokens per day, last {}d: {}", DAYS, daily_total),
+            &self.daily,
+            1,
+            daily_area.width,
+        );
+        f.render_widget(daily, daily_area);
+    }
+
+    /// Weekday rows of shaded hours, two columns per hour when there is room
+    fn render_heatmap(&self, f: &mut Frame, area: Rect) {
+        let scale = if area.width >= 24 * 2 + 6 { 2 } else { 1 };
+        let mut lines = vec![Line::from(Span::styled(
+            format!("    {}", hour_labels(scale)),
+            theme().muted,
+        ))];
+        for (day, name) in WEEKDAYS.iter().enumerate() {
+            let shades: String = self
+                .heatmap
+                .shades(day)
+                .chars()
+                .flat_map(|shade| std::iter::repeat_n(shade, scale))
+                .collect();
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} ", name), theme().muted),
+                Span::styled(shades, theme().chart),
+            ]));
+        }
+
+        let title = match self.heatmap.quietest_window(BLOCK_HOURS) {
+            Some(start) if !is_narrow(area) => format!(
+                "Tokens by hour, {} weeks (quietest {:02}-{:02})",
+                HEATMAP_WEEKS,
+                start,
+                (start + BLOCK_HOURS) % 24
+            ),
+            _ => format!("Tokens by hour, {} weeks", HEATMAP_WEEKS),
+        };
+        let paragraph =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(paragraph, area);
+    }
+}
+
+/// Hour labels every six hours over `scale` columns per hour
+fn hour_labels(scale: usize) -> String {
+    (0..24)
+        .step_by(6)
+        .map(|hour| format!("{:<width$}", format!("{:02}", hour), width = 6 * scale))
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// One bar per hour for the last `hours` hours ending with the current one.
+/// Hours without activity are zero; only every sixth hour is labelled so
+/// the labels fit under narrow bars.
+fn hourly_series(
+    entries: &[HourlyBreakdown],
+    now: DateTime<Utc>,
+    hours: usize,
+) -> Vec<(String, u64)> {
+    let current = now.duration_trunc(TimeDelta::hours(1)).unwrap_or(now);
+    let mut series = vec![0u64; hours];
+
+    for entry in entries {
+        let ago = (current - entry.hour).num_hours();
+        if (0..hours as i64).contains(&ago) {
+            series[hours - 1 - ago as usize] += entry.total_tokens as u64;
+        }
+    }
+
+    series
+        .into_iter()
+        .enumerate()
+        .map(|(i, tokens)| {
+            let hour = current - TimeDelta::hours((hours - 1 - i) as i64);
+            let label = if i % 6 == 0 {
+                hour.with_timezone(&chrono::Local).format("%H").to_string()
+            } else {
+                String::new()
+            };
+            (label, tokens)
+        })
+        .collect()
+}
+
+/// One bar per day for the last `days` days ending today, summed across
+/// projects and models, labelled with the day of month
+fn daily_series(rows: &[DailyUsage], today: NaiveDate, days: usize) -> Vec<(String, u64)> {
+    (0..days)
+        .rev()
+        .map(|ago| {
+            let day = today - TimeDelta::days(ago as i64);
+            let key = day.format("%Y-%m-%d").to_string();
+            let tokens = rows
+                .iter()
+                .filter(|row| row.day == key)
+                .map(|row| row.tokens as u64)
+                .sum();
+            (day.format("%d").to_string(), tokens)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_hourly_series_fills_gaps() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        let entries = vec![
+            HourlyBreakdown {
+                hour: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+                task_count: 2,
+                total_tokens: 500,
+            },
+            HourlyBreakdown {
+                hour: Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap(),
+                task_count: 1,
+                total_tokens: 300,
+            },
+            // Outside the window
+            HourlyBreakdown {
+                hour: Utc.with_ymd_and_hms(2024, 4, 30, 6, 0, 0).unwrap(),
+                task_count: 1,
+                total_tokens: 999,
+            },
+        ];
+
+        let series = hourly_series(&entries, now, 24);
+        let tokens: Vec<u64> = series.iter().map(|(_, t)| *t).collect();
+
+        assert_eq!(tokens.len(), 24);
+        assert_eq!(tokens[23], 500);
+        assert_eq!(tokens[20], 300);
+        assert_eq!(tokens.iter().sum::<u64>(), 800);
+    }
+
+    #[test]
+    fn test_daily_series_sums_projects() {
+        let row = |day: &str, project: &str, tokens| DailyUsage {
+            day: day.to_string(),
+            project: project.to_string(),
+            model: "model".to_string(),
+            runs: 1,
+            successful_runs: 1,
+            tokens,
+            cost_usd: 0.0,
+        };
+        let rows = vec![
+            row("2024-05-01", "a", 100),
+            row("2024-05-01", "b", 50),
+            row("2024-04-29", "a", 10),
+        ];
+
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let series = daily_series(&rows, today, 3);
+
+        assert_eq!(
+            series,
+            vec![
+                ("29".to_string(), 10),
+                ("30".to_string(), 0),
+                ("01".to_string(), 150),
+            ]
+        );
+    }
+}
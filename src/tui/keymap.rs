@@ -0,0 +1,456 @@
+use crate::config::TuiConfig;
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something a key can do in the dashboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    /// Close the open pane or popup, clear the filter, then quit
+    Back,
+    NextTab,
+    PrevTab,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Select,
+    Refresh,
+    Search,
+    Sort,
+    SortOrder,
+    Apply,
+    Dismiss,
+    Snooze,
+    Export,
+    Help,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [
+        Action::Quit,
+        Action::Back,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Select,
+        Action::Refresh,
+        Action::Search,
+        Action::Sort,
+        Action::SortOrder,
+        Action::Apply,
+        Action::Dismiss,
+        Action::Snooze,
+        Action::Export,
+        Action::Help,
+    ];
+
+    /// Name used in `[tui.keys]`
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Select => "select",
+            Action::Refresh => "refresh",
+            Action::Search => "search",
+            Action::Sort => "sort",
+            Action::SortOrder => "sort_order",
+            Action::Apply => "apply",
+            Action::Dismiss => "dismiss",
+            Action::Snooze => "snooze",
+            Action::Export => "export",
+            Action::Help => "help",
+        }
+    }
+
+    /// What the action does, for the help overlay
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Back => "Close the open pane, clear the filter, or quit",
+            Action::NextTab => "Next tab",
+            Action::PrevTab => "Previous tab",
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::PageUp => "Previous page of agent history",
+            Action::PageDown => "Next page of agent history",
+            Action::Top => "First item",
+            Action::Bottom => "Last item",
+            Action::Select => "Show or hide details",
+            Action::Refresh => "Reload the current tab",
+            Action::Search => "Search the current list",
+            Action::Sort => "Cycle the sort column",
+            Action::SortOrder => "Reverse the sort order",
+            Action::Apply => "Apply the selected optimization",
+            Action::Dismiss => "Dismiss the selected optimization",
+            Action::Snooze => "Hide the selected optimization for a week",
+            Action::Export => "Export the current view to JSON and Markdown",
+            Action::Help => "Show this help",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// Bindings of the default preset
+const DEFAULT_KEYS: &[(Action, &[&str])] = &[
+    (Action::Quit, &["q"]),
+    (Action::Back, &["esc"]),
+    (Action::NextTab, &["tab", "right"]),
+    (Action::PrevTab, &["shift+tab", "left"]),
+    (Action::Up, &["up"]),
+    (Action::Down, &["down"]),
+    (Action::PageUp, &["pageup"]),
+    (Action::PageDown, &["pagedown"]),
+    (Action::Top, &["home"]),
+    (Action::Bottom, &["end"]),
+    (Action::Select, &["enter"]),
+    (Action::Refresh, &["r"]),
+    (Action::Search, &["/"]),
+    (Action::Sort, &["s"]),
+    (Action::SortOrder, &["o"]),
+    (Action::Apply, &["a"]),
+    (Action::Dismiss, &["x"]),
+    (Action::Snooze, &["z"]),
+    (Action::Export, &["e"]),
+    (Action::Help, &["?"]),
+];
+
+/// Extra bindings of the vim preset, on top of the default ones
+const VIM_KEYS: &[(Action, &[&str])] = &[
+    (Action::NextTab, &["l"]),
+    (Action::PrevTab, &["h"]),
+    (Action::Up, &["k"]),
+    (Action::Down, &["j"]),
+    (Action::PageUp, &["ctrl+u"]),
+    (Action::PageDown, &["ctrl+d"]),
+    (Action::Top, &["g g"]),
+    (Action::Bottom, &["G"]),
+];
+
+const PRESETS: &[&str] = &["default", "vim"];
+
+/// One key press. Shift is folded into the character, so `G` and
+/// `shift+g` are the same chord.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Chord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Chord {
+    fn from_event(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if !matches!(event.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers |= event.modifiers & KeyModifiers::SHIFT;
+        }
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    /// Parse a chord like `q`, `G`, `ctrl+d`, `shift+tab` or `pagedown`
+    fn parse(spec: &str) -> Result<Self> {
+        let (prefix, key) = match spec.rsplit_once('+') {
+            Some((prefix, key)) if !key.is_empty() => (Some(prefix), key),
+            _ => (None, spec),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.into_iter().flat_map(|p| p.split('+')) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => anyhow::bail!("unknown modifier '{}' in '{}'", modifier, spec),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => anyhow::bail!("unknown key '{}'", spec),
+                },
+            },
+        };
+
+        // Normalize the same way key events are
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        Ok(Self::from_event(KeyEvent::new(code, modifiers)))
+    }
+
+    fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            other => format!("{:?}", other),
+        };
+
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("Shift+");
+        }
+        label + &key
+    }
+}
+
+struct Binding {
+    keys: Vec<Chord>,
+    action: Action,
+}
+
+/// Resolves key presses to actions using the configured preset and
+/// `[tui.keys]` overrides. Bindings may be sequences, like `g g`.
+pub struct KeyMap {
+    bindings: Vec<Binding>,
+    /// Keys typed so far of an unfinished sequence
+    pending: Vec<Chord>,
+}
+
+impl KeyMap {
+    pub fn from_config(config: &TuiConfig) -> Result<Self> {
+        let mut specs: Vec<(Action, Vec<String>)> = Action::ALL
+            .iter()
+            .map(|action| (*action, Vec::new()))
+            .collect();
+
+        let mut add = |preset: &[(Action, &[&str])]| {
+            for (action, keys) in preset {
+                if let Some((_, specs)) = specs.iter_mut().find(|(a, _)| a == action) {
+                    specs.extend(keys.iter().map(|k| k.to_string()));
+                }
+            }
+        };
+        match config.keymap.as_str() {
+            "default" => add(DEFAULT_KEYS),
+            "vim" => {
+                add(DEFAULT_KEYS);
+                add(VIM_KEYS);
+            }
+            other => anyhow::bail!(
+                "tui.keymap must be one of: {} (got '{}')",
+                PRESETS.join(", "),
+                other
+            ),
+        }
+
+        for (name, keys) in &config.keys {
+            let action = Action::from_name(name).with_context(|| {
+                let names: Vec<&str> = Action::ALL.iter().map(|a| a.name()).collect();
+                format!(
+                    "Unknown action '{}' in [tui.keys]; expected one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+            if let Some((_, specs)) = specs.iter_mut().find(|(a, _)| *a == action) {
+                specs.clone_from(keys);
+            }
+        }
+
+        let mut bindings = Vec::new();
+        for (action, keys) in specs {
+            for spec in keys {
+                let chords = spec
+                    .split_whitespace()
+                    .map(Chord::parse)
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("Invalid key for {}: '{}'", action.name(), spec))?;
+                if chords.is_empty() {
+                    anyhow::bail!("Empty key for {}", action.name());
+                }
+                bindings.push(Binding {
+                    keys: chords,
+                    action,
+                });
+            }
+        }
+
+        Ok(Self {
+            bindings,
+            pending: Vec::new(),
+        })
+    }
+
+    /// The action for a key press, or None while a sequence is unfinished
+    /// or the key is not bound
+    pub fn resolve(&mut self, event: KeyEvent) -> Option<Action> {
+        let chord = Chord::from_event(event);
+        self.pending.push(chord);
+
+        loop {
+            if let Some(binding) = self.bindings.iter().find(|b| b.keys == self.pending) {
+                self.pending.clear();
+                return Some(binding.action);
+            }
+            if self
+                .bindings
+                .iter()
+                .any(|b| b.keys.starts_with(&self.pending))
+            {
+                return None;
+            }
+            if self.pending.len() == 1 {
+                self.pending.clear();
+                return None;
+            }
+            // Not a known sequence; see if the last key starts a new one
+            self.pending = vec![chord];
+        }
+    }
+
+    /// How the first key bound to `action` is shown to the user
+    pub fn label(&self, action: Action) -> String {
+        self.labels(action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "unbound".to_string())
+    }
+
+    /// All keys bound to `action`
+    pub fn labels(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|b| b.action == action)
+            .map(|b| sequence_label(&b.keys))
+            .collect()
+    }
+}
+
+fn sequence_label(keys: &[Chord]) -> String {
+    keys.iter().map(Chord::label).collect::<Vec<_>>().join("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn vim() -> KeyMap {
+        let config = TuiConfig {
+            keymap: "vim".to_string(),
+            ..Default::default()
+        };
+        KeyMap::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_vim_sequences() {
+        let mut keys = vim();
+        assert_eq!(keys.resolve(key(KeyCode::Char('j'))), Some(Action::Down));
+        assert_eq!(keys.resolve(key(KeyCode::Char('g'))), None);
+        assert_eq!(keys.resolve(key(KeyCode::Char('g'))), Some(Action::Top));
+
+        // Shifted letters arrive with the shift modifier
+        let shifted = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(keys.resolve(shifted), Some(Action::Bottom));
+
+        // An interrupted sequence falls back to the new key
+        assert_eq!(keys.resolve(key(KeyCode::Char('g'))), None);
+        assert_eq!(keys.resolve(key(KeyCode::Char('k'))), Some(Action::Up));
+
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(keys.resolve(ctrl_d), Some(Action::PageDown));
+        assert_eq!(keys.resolve(key(KeyCode::Down)), Some(Action::Down));
+    }
+
+    #[test]
+    fn test_overrides_replace_preset_keys() {
+        let mut config = TuiConfig::default();
+        config.keys.insert(
+            "quit".to_string(),
+            vec!["ctrl+c".to_string(), "Q".to_string()],
+        );
+        let mut keys = KeyMap::from_config(&config).unwrap();
+
+        assert_eq!(keys.resolve(key(KeyCode::Char('q'))), None);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(keys.resolve(ctrl_c), Some(Action::Quit));
+        assert_eq!(keys.label(Action::Quit), "Ctrl+c");
+        assert_eq!(keys.label(Action::PrevTab), "Shift+Tab");
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut config = TuiConfig {
+            keymap: "emacs".to_string(),
+            ..Default::default()
+        };
+        assert!(KeyMap::from_config(&config).is_err());
+
+        config.keymap = "default".to_string();
+        config
+            .keys
+            .insert("jump".to_string(), vec!["j".to_string()]);
+        assert!(KeyMap::from_config(&config).is_err());
+
+        config.keys.clear();
+        config
+            .keys
+            .insert("quit".to_string(), vec!["hyper+q".to_string()]);
+        assert!(KeyMap::from_config(&config).is_err());
+    }
+}
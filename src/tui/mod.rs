@@ -1,38 +1,41 @@
 mod app;
+mod event;
+mod execution;
+mod export;
+mod filter;
+mod history;
+mod keymap;
+mod panel;
+mod run;
+mod sessions;
 mod widgets;
 
 pub use app::App;
+pub use keymap::KeyMap;
+pub use widgets::Theme;
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
 
-pub fn run_tui<F>(mut app_runner: F) -> Result<()>
-where
-    F: FnMut(&mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<bool>,
-{
-    // Setup terminal
+type Tui = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// Switch to raw mode on the alternate screen with mouse capture
+pub fn setup_terminal() -> Result<Tui> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    // Run app loop
-    let res = loop {
-        match app_runner(&mut terminal) {
-            Ok(true) => continue,
-            Ok(false) => break Ok(()),
-            Err(e) => break Err(e),
-        }
-    };
+    Ok(Terminal::new(backend)?)
+}
 
-    // Restore terminal
+/// Undo `setup_terminal`
+pub fn restore_terminal(terminal: &mut Tui) -> Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -40,6 +43,5 @@ where
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
-
-    res
+    Ok(())
 }
@@ -0,0 +1,101 @@
+use super::event::AppEvent;
+use super::widgets::spinner;
+use anyhow::Result;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+/// Data shown by one panel, loaded in the background so the render loop
+/// never waits on the network or the database
+pub struct Panel<T> {
+    pub data: Option<T>,
+    pub error: Option<String>,
+    loaded_at: Option<Instant>,
+    pending: Option<oneshot::Receiver<Result<T>>>,
+}
+
+impl<T> Default for Panel<T> {
+    fn default() -> Self {
+        Self {
+            data: None,
+            error: None,
+            loaded_at: None,
+            pending: None,
+        }
+    }
+}
+
+impl<T: Send + 'static> Panel<T> {
+    /// Start loading in the background unless a load is already running.
+    /// `updates` is told when the load finishes, so the result is shown
+    /// without waiting for the next tick.
+    pub fn load<F>(&mut self, fut: F, updates: &mpsc::UnboundedSender<AppEvent>)
+    where
+        F: Future<Output = Result<T>> + Send + 'static,
+    {
+        if self.pending.is_some() {
+            return;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let updates = updates.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(fut.await);
+            let _ = updates.send(AppEvent::Loaded);
+        });
+        self.pending = Some(rx);
+    }
+
+    /// Pick up a finished load. Returns true if new data arrived.
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &mut self.pending else {
+            return false;
+        };
+
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => return false,
+            Err(oneshot::error::TryRecvError::Closed) => Err(anyhow::anyhow!("load was cancelled")),
+        };
+        self.pending = None;
+
+        match result {
+            Ok(data) => {
+                self.data = Some(data);
+                self.error = None;
+                self.loaded_at = Some(Instant::now());
+                true
+            }
+            Err(e) => {
+                self.error = Some(format!("{:#}", e));
+                false
+            }
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Title suffix showing a spinner while a load is running
+    pub fn spinner(&self) -> String {
+        if self.is_loading() {
+            format!(" {}", spinner())
+        } else {
+            String::new()
+        }
+    }
+
+    /// Time since the data was last loaded successfully
+    pub fn age(&self) -> Duration {
+        self.loaded_at.map(|at| at.elapsed()).unwrap_or_default()
+    }
+
+    /// True if nothing was loaded yet or the data is older than `max_age`.
+    /// A failed load is not retried automatically.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        !self.is_loading()
+            && self.error.is_none()
+            && self.loaded_at.is_none_or(|at| at.elapsed() >= max_age)
+    }
+}
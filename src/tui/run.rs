@@ -0,0 +1,248 @@
+use super::execution::Execution;
+use super::widgets::theme;
+use crate::config::Config;
+use crate::db::Database;
+use crate::master::{AutonomyMode, MasterCoder, Progress, RunEvent, AUTONOMY_MODES};
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Task,
+    Mode,
+    Budget,
+    Launch,
+}
+
+const FIELDS: [Field; 4] = [Field::Task, Field::Mode, Field::Budget, Field::Launch];
+
+/// The Run tab: a form to launch a task with Master Coder, and the live
+/// view of the last launched (or attached) run
+pub struct RunTab {
+    task: String,
+    mode: usize,
+    budget: String,
+    focus: Field,
+    error: Option<String>,
+    run: Option<Execution>,
+}
+
+impl RunTab {
+    pub fn new(config: &Config) -> Self {
+        let mode = AUTONOMY_MODES
+            .iter()
+            .position(|m| m.eq_ignore_ascii_case(&config.master_coder.default_mode))
+            .unwrap_or(1);
+
+        Self {
+            task: String::new(),
+            mode,
+            budget: config.master_coder.token_budget.to_string(),
+            focus: Field::Task,
+            error: None,
+            run: None,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.run.as_ref().is_some_and(|run| !run.is_finished())
+    }
+
+    /// True if the tab is waiting for an approval answer
+    pub fn needs_input(&self) -> bool {
+        self.run.as_ref().is_some_and(Execution::needs_approval)
+    }
+
+    /// Follow a run started in another process
+    pub fn attach(&mut self, run_id: &str) -> Result<()> {
+        self.run = Some(Execution::attach(run_id)?);
+        // Keep typed keys out of the task field so `q` still quits
+        self.focus = Field::Launch;
+        Ok(())
+    }
+
+    /// Handle a key press. Returns false if the key was not used, so the
+    /// app can apply its global bindings.
+    pub fn handle_key(&mut self, code: KeyCode, config: &Config, db: &Arc<Database>) -> bool {
+        if let Some(run) = &mut self.run {
+            if run.needs_approval() {
+                // Other keys are swallowed until the question is answered
+                run.answer_approval(code);
+                return true;
+            }
+        }
+
+        // The form is locked while a run is in progress
+        if self.is_running() {
+            return false;
+        }
+
+        match (code, self.focus) {
+            (KeyCode::Up, _) => self.move_focus(-1),
+            (KeyCode::Down, _) => self.move_focus(1),
+            (KeyCode::Enter, _) => self.launch(config, db),
+            (KeyCode::Left, Field::Mode) => {
+                self.mode = (self.mode + AUTONOMY_MODES.len() - 1) % AUTONOMY_MODES.len();
+            }
+            (KeyCode::Right, Field::Mode) => {
+                self.mode = (self.mode + 1) % AUTONOMY_MODES.len();
+            }
+            (KeyCode::Backspace, Field::Task) => {
+                self.task.pop();
+            }
+            (KeyCode::Backspace, Field::Budget) => {
+                self.budget.pop();
+            }
+            (KeyCode::Char(c), Field::Task) => self.task.push(c),
+            (KeyCode::Char(c), Field::Budget) => {
+                if c.is_ascii_digit() {
+                    self.budget.push(c);
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn move_focus(&mut self, delta: isize) {
+        let index = FIELDS.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let index = index.saturating_add_signed(delta).min(FIELDS.len() - 1);
+        self.focus = FIELDS[index];
+    }
+
+    /// Validate the form and start Master Coder in the background
+    fn launch(&mut self, config: &Config, db: &Arc<Database>) {
+        let task = self.task.trim().to_string();
+        if task.is_empty() {
+            self.error = Some("Enter a task description first".to_string());
+            self.focus = Field::Task;
+            return;
+        }
+
+        let budget = match self.budget.parse::<usize>() {
+            Ok(budget) if (1_000..=1_000_000).contains(&budget) => budget,
+            _ => {
+                self.error = Some("Token budget must be between 1000 and 1000000".to_string());
+                self.focus = Field::Budget;
+                return;
+            }
+        };
+
+        self.error = None;
+
+        let (progress, events) = Progress::channel();
+        let mode = AUTONOMY_MODES[self.mode].to_string();
+        let config = config.clone();
+        let db = db.clone();
+        let run_task = task.clone();
+
+        tokio::spawn(async move {
+            let mut master = match MasterCoder::new(config, mode, db).await {
+                Ok(master) => master,
+                Err(e) => {
+                    progress.send(RunEvent::Failed(format!("{:#}", e)));
+                    return;
+                }
+            };
+            master.set_token_budget(budget);
+            master.set_progress(progress);
+            // The outcome is reported through the progress channel
+            let _ = master.execute(&run_task).await;
+        });
+
+        self.run = Some(Execution::launched(events, task, budget));
+    }
+
+    /// Apply events from the active run. Returns true when the run ended.
+    pub fn poll(&mut self) -> bool {
+        self.run.as_mut().is_some_and(Execution::poll)
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(7), Constraint::Min(0)])
+            .split(area);
+
+        self.render_form(f, chunks[0]);
+
+        match &self.run {
+            Some(run) => run.render(f, chunks[1]),
+            None => {
+                let text = Paragraph::new(vec![
+                    Line::from(""),
+                    Line::from("Describe a task, pick a mode and budget, then press Enter."),
+                    Line::from("Master Coder will plan it and run a team of agents."),
+                ])
+                .block(Block::default().borders(Borders::ALL).title("Progress"));
+                f.render_widget(text, chunks[1]);
+            }
+        }
+    }
+
+    fn render_form(&self, f: &mut Frame, area: Rect) {
+        let locked = self.is_running();
+        let label = |field: Field, name: &'static str| {
+            let style = if self.focus == field && !locked {
+                theme().highlight
+            } else {
+                Style::default()
+            };
+            Span::styled(name, style)
+        };
+        let cursor = |field: Field| {
+            if self.focus == field && !locked {
+                "█"
+            } else {
+                ""
+            }
+        };
+
+        let mode = AutonomyMode::from_str(AUTONOMY_MODES[self.mode])
+            .map(|m| m.description().to_string())
+            .unwrap_or_default();
+
+        let launch = if locked {
+            Span::styled("Running...", theme().warning)
+        } else {
+            label(Field::Launch, "[ Launch ]")
+        };
+
+        let mut text = vec![
+            Line::from(vec![
+                label(Field::Task, "Task:   "),
+                Span::raw(format!("{}{}", self.task, cursor(Field::Task))),
+            ]),
+            Line::from(vec![
+                label(Field::Mode, "Mode:   "),
+                Span::raw(format!("◀ {} ▶ ", AUTONOMY_MODES[self.mode])),
+                Span::styled(mode, theme().muted),
+            ]),
+            Line::from(vec![
+                label(Field::Budget, "Budget: "),
+                Span::raw(format!("{}{} tokens", self.budget, cursor(Field::Budget))),
+            ]),
+            Line::from(launch),
+        ];
+
+        if let Some(error) = &self.error {
+            text.push(Line::from(Span::styled(error.clone(), theme().error)));
+        }
+
+        let form = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("New Task (↑/↓ field, ←/→ mode, Enter launch)"),
+        );
+        f.render_widget(form, area);
+    }
+}
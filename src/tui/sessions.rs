@@ -0,0 +1,288 @@
+use super::event::AppEvent;
+use super::filter::{Filterable, ListFilter, SortKey};
+use super::panel::Panel;
+use super::widgets::{spinner, split_panes, theme, ListRows};
+use crate::analyzer::{SessionAnalyzer, SessionSummary};
+use chrono::Local;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Tools and files listed in the session breakdown
+const BREAKDOWN_LIMIT: usize = 15;
+
+/// The Sessions tab: recent Claude Code sessions, with a per-session
+/// breakdown of tool calls and file accesses
+#[derive(Default)]
+pub struct SessionsTab {
+    sessions: Panel<Vec<SessionSummary>>,
+    selected: usize,
+    show_detail: bool,
+    pub filter: ListFilter,
+    /// Where the list was last drawn, for mouse clicks
+    rows: Cell<Option<ListRows>>,
+}
+
+impl Filterable for SessionSummary {
+    const SORT_KEYS: &'static [SortKey] = &[SortKey::Date, SortKey::Tokens];
+
+    fn search_text(&self) -> String {
+        format!("{} {}", self.data.session_id, self.project)
+    }
+
+    fn compare(&self, other: &Self, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Tokens => self.data.tokens().cmp(&other.data.tokens()),
+            _ => self.modified.cmp(&other.modified),
+        }
+    }
+}
+
+impl SessionsTab {
+    /// Parse the most recent session files in the background
+    pub fn refresh(
+        &mut self,
+        analyzer: &Arc<SessionAnalyzer>,
+        count: usize,
+        updates: &mpsc::UnboundedSender<AppEvent>,
+    ) {
+        let analyzer = analyzer.clone();
+        self.sessions.load(
+            async move {
+                // Session parsing is blocking file I/O
+                tokio::task::spawn_blocking(move || analyzer.list_sessions(count)).await?
+            },
+            updates,
+        );
+    }
+
+    /// Sessions matching the filter, in display order
+    fn visible(&self) -> Vec<&SessionSummary> {
+        let Some(sessions) = &self.sessions.data else {
+            return Vec::new();
+        };
+        self.filter
+            .apply(sessions)
+            .into_iter()
+            .map(|i| &sessions[i])
+            .collect()
+    }
+
+    pub fn poll(&mut self) {
+        if self.sessions.poll() {
+            let len = self.visible().len();
+            self.selected = self.selected.min(len.saturating_sub(1));
+        }
+    }
+
+    /// Go back to the top after the filter changed
+    pub fn reset_selection(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.visible().len();
+        if len > 0 {
+            self.selected = self.selected.saturating_add_signed(delta).min(len - 1);
+        }
+    }
+
+    pub fn toggle_detail(&mut self) {
+        self.show_detail = !self.show_detail;
+    }
+
+    /// Select the session at a clicked screen position and show its
+    /// breakdown
+    pub fn click(&mut self, column: u16, row: u16) {
+        if let Some(index) = self.rows.get().and_then(|rows| rows.index_at(column, row)) {
+            self.selected = index;
+            self.show_detail = true;
+        }
+    }
+
+    /// Close the breakdown. Returns false if it was not open.
+    pub fn close_detail(&mut self) -> bool {
+        std::mem::replace(&mut self.show_detail, false)
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, searching: bool) {
+        self.rows.set(None);
+        let Some(sessions) = &self.sessions.data else {
+            let text = match &self.sessions.error {
+                Some(e) => Line::from(Span::styled(
+                    format!("Failed to load sessions: {}", e),
+                    theme().error,
+                )),
+                None => Line::from(format!("{} Loading sessions...", spinner())),
+            };
+            let paragraph = Paragraph::new(text).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Sessions{}", self.sessions.spinner())),
+            );
+            f.render_widget(paragraph, area);
+            return;
+        };
+
+        if sessions.is_empty() {
+            let paragraph = Paragraph::new(vec![
+                Line::from(""),
+                Line::from("No Claude Code sessions found."),
+                Line::from("Sessions appear here once ~/.claude/sessions has some history."),
+            ])
+            .block(Block::default().borders(Borders::ALL).title("Sessions"));
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let (list_area, detail_area) = if self.show_detail {
+            let (list, detail) = split_panes(area, 45);
+            (list, Some(detail))
+        } else {
+            (area, None)
+        };
+
+        let visible = self.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|session| {
+                let modified = session
+                    .modified
+                    .map(|m| m.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                ListItem::new(format!(
+                    "{} | {} | {} | {} | ~{} tokens | {} tool calls",
+                    modified,
+                    session.project,
+                    session.data.session_id,
+                    format_size(session.size_bytes),
+                    session.data.tokens(),
+                    session.data.tool_calls.len()
+                ))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Recent Sessions ({} of {}){}{}",
+                visible.len(),
+                sessions.len(),
+                self.filter.describe(searching),
+                self.sessions.spinner()
+            )))
+            .highlight_style(theme().highlight)
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_stateful_widget(list, list_area, &mut state);
+        self.rows
+            .set(Some(ListRows::new(list_area, &state, visible.len())));
+
+        if let (Some(area), Some(session)) = (detail_area, visible.get(self.selected)) {
+            render_breakdown(f, area, session);
+        }
+    }
+}
+
+fn render_breakdown(f: &mut Frame, area: Rect, session: &SessionSummary) {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let data = &session.data;
+
+    let mut text = vec![
+        Line::from(Span::styled(data.session_id.clone(), bold)),
+        Line::from(format!("Project: {}", session.project)),
+        Line::from(format!("File: {}", session.path.display())),
+        Line::from(match data.token_usage() {
+            Some(usage) => format!(
+                "{} messages, {} tokens ({} in, {} out, {} cache read, {} cache write)",
+                data.messages.len(),
+                usage.total(),
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_read_input_tokens,
+                usage.cache_creation_input_tokens
+            ),
+            None => format!(
+                "{} messages, ~{} tokens (estimated from message length)",
+                data.messages.len(),
+                data.estimated_tokens()
+            ),
+        }),
+        Line::from(match data.sidechains.len() {
+            0 => "No subagent conversations".to_string(),
+            n => format!(
+                "{} subagent conversations, {} messages",
+                n,
+                data.sidechains
+                    .iter()
+                    .map(|s| s.messages.len())
+                    .sum::<usize>()
+            ),
+        }),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Tool calls ({})", data.tool_calls.len()),
+            bold,
+        )),
+    ];
+
+    let tools = data.tool_usage();
+    if tools.is_empty() {
+        text.push(Line::from("  none"));
+    }
+    for (tool, count) in tools.iter().take(BREAKDOWN_LIMIT) {
+        text.push(Line::from(format!("  {:>4}  {}", count, tool)));
+    }
+
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        format!("File accesses ({})", data.file_accesses.len()),
+        bold,
+    )));
+
+    let files = data.file_usage();
+    if files.is_empty() {
+        text.push(Line::from("  none"));
+    }
+    for file in files.iter().take(BREAKDOWN_LIMIT) {
+        text.push(Line::from(vec![
+            Span::styled(
+                format!("  {}r {}w {}e  ", file.reads, file.writes, file.edits),
+                theme().muted,
+            ),
+            Span::raw(file.file_path.clone()),
+        ]));
+    }
+    if files.len() > BREAKDOWN_LIMIT {
+        text.push(Line::from(format!(
+            "  ... and {} more",
+            files.len() - BREAKDOWN_LIMIT
+        )));
+    }
+
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Breakdown (Esc to close)"),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
@@ -1,14 +1,363 @@
-// Custom widgets for TUI
-// Future implementations can go here
+// Custom widgets and styles for TUI
 
+use crate::config::TuiConfig;
+use anyhow::Result;
 use ratatui::{
-    style::{Color, Style},
-    widgets::{Block, Borders},
+    buffer::Buffer,
+    layout::{Constraint, Direction, Flex, Layout, Margin, Position, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Clear, ListState, Paragraph, Widget, Wrap},
 };
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn create_block(title: &str) -> Block {
-    Block::default()
-        .borders(Borders::ALL)
-        .title(title)
-        .style(Style::default().fg(Color::White))
+/// Names accepted by `tui.theme`
+pub const THEMES: &[&str] = &["dark", "light", "solarized", "high-contrast", "no-color"];
+
+/// The style of every role in the dashboard. Widgets take their colors
+/// from the active theme instead of naming colors themselves.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Regular text, e.g. inactive tab titles
+    pub text: Style,
+    /// The dashboard title
+    pub title: Style,
+    /// Selected list items, the active tab and focused form fields
+    pub highlight: Style,
+    /// Key names in hints
+    pub key: Style,
+    pub success: Style,
+    pub warning: Style,
+    pub error: Style,
+    /// Secondary details
+    pub muted: Style,
+    pub chart: Style,
+    /// Emphasized chart bars, e.g. the current usage window
+    pub chart_highlight: Style,
+}
+
+impl Theme {
+    const DARK: Theme = Theme {
+        text: Style::new().fg(Color::White),
+        title: Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        highlight: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        key: Style::new().fg(Color::Yellow),
+        success: Style::new().fg(Color::Green),
+        warning: Style::new().fg(Color::Yellow),
+        error: Style::new().fg(Color::Red),
+        muted: Style::new().fg(Color::DarkGray),
+        chart: Style::new().fg(Color::Cyan),
+        chart_highlight: Style::new().fg(Color::Yellow),
+    };
+
+    const LIGHT: Theme = Theme {
+        text: Style::new().fg(Color::Black),
+        title: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        highlight: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        key: Style::new().fg(Color::Blue),
+        success: Style::new().fg(Color::Green),
+        warning: Style::new().fg(Color::Magenta),
+        error: Style::new().fg(Color::Red),
+        muted: Style::new().fg(Color::Gray),
+        chart: Style::new().fg(Color::Blue),
+        chart_highlight: Style::new().fg(Color::Magenta),
+    };
+
+    const SOLARIZED: Theme = Theme {
+        text: Style::new().fg(Color::Rgb(131, 148, 150)),
+        title: Style::new()
+            .fg(Color::Rgb(38, 139, 210))
+            .add_modifier(Modifier::BOLD),
+        highlight: Style::new()
+            .fg(Color::Rgb(181, 137, 0))
+            .add_modifier(Modifier::BOLD),
+        key: Style::new().fg(Color::Rgb(42, 161, 152)),
+        success: Style::new().fg(Color::Rgb(133, 153, 0)),
+        warning: Style::new().fg(Color::Rgb(203, 75, 22)),
+        error: Style::new().fg(Color::Rgb(220, 50, 47)),
+        muted: Style::new().fg(Color::Rgb(88, 110, 117)),
+        chart: Style::new().fg(Color::Rgb(38, 139, 210)),
+        chart_highlight: Style::new().fg(Color::Rgb(181, 137, 0)),
+    };
+
+    const HIGH_CONTRAST: Theme = Theme {
+        text: Style::new().fg(Color::White),
+        title: Style::new()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD.union(Modifier::UNDERLINED)),
+        highlight: Style::new()
+            .fg(Color::Black)
+            .bg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD),
+        key: Style::new()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD),
+        success: Style::new()
+            .fg(Color::LightGreen)
+            .add_modifier(Modifier::BOLD),
+        warning: Style::new()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD),
+        error: Style::new()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD),
+        muted: Style::new().fg(Color::White),
+        chart: Style::new().fg(Color::White),
+        chart_highlight: Style::new().fg(Color::LightYellow),
+    };
+
+    /// Only text attributes, for terminals without color
+    const NO_COLOR: Theme = Theme {
+        text: Style::new(),
+        title: Style::new().add_modifier(Modifier::BOLD),
+        highlight: Style::new().add_modifier(Modifier::REVERSED),
+        key: Style::new().add_modifier(Modifier::BOLD),
+        success: Style::new(),
+        warning: Style::new().add_modifier(Modifier::BOLD),
+        error: Style::new().add_modifier(Modifier::BOLD),
+        muted: Style::new().add_modifier(Modifier::DIM),
+        chart: Style::new(),
+        chart_highlight: Style::new().add_modifier(Modifier::BOLD),
+    };
+
+    pub fn named(name: &str) -> Result<Self> {
+        Ok(match name {
+            "dark" => Self::DARK,
+            "light" => Self::LIGHT,
+            "solarized" => Self::SOLARIZED,
+            "high-contrast" => Self::HIGH_CONTRAST,
+            "no-color" => Self::NO_COLOR,
+            other => anyhow::bail!(
+                "tui.theme must be one of: {} (got '{}')",
+                THEMES.join(", "),
+                other
+            ),
+        })
+    }
+
+    /// The configured theme, or no-color when color is turned off with
+    /// `--no-color`, `NO_COLOR` or the plain output theme
+    pub fn from_config(config: &TuiConfig) -> Result<Self> {
+        let theme = Self::named(&config.theme)?;
+        if !crate::style::color_enabled() {
+            return Ok(Self::NO_COLOR);
+        }
+        Ok(theme)
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set the theme for this process. Only the first call has an effect.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The active theme (dark until one is set)
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(|| Theme::DARK)
+}
+
+/// A rectangle of the given percentage size, centered in `area` (for popups)
+/// Below this width panes stack and labels are abbreviated
+const NARROW_WIDTH: u16 = 100;
+
+/// Below this height the title bar is dropped and the footer shrinks
+const SHORT_HEIGHT: u16 = 30;
+
+/// Popups on a narrow or short screen take up this much of it
+const COMPACT_POPUP_PERCENT: u16 = 95;
+
+pub fn is_narrow(area: Rect) -> bool {
+    area.width < NARROW_WIDTH
+}
+
+pub fn is_short(area: Rect) -> bool {
+    area.height < SHORT_HEIGHT
+}
+
+/// Split `area` into two panes, giving `percent` to the first: side by
+/// side when there is room, stacked on a narrow screen
+pub fn split_panes(area: Rect, percent: u16) -> (Rect, Rect) {
+    let direction = if is_narrow(area) {
+        Direction::Vertical
+    } else {
+        Direction::Horizontal
+    };
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints([
+            Constraint::Percentage(percent),
+            Constraint::Percentage(100 - percent),
+        ])
+        .split(area);
+    (chunks[0], chunks[1])
+}
+
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let percent_x = if is_narrow(area) {
+        percent_x.max(COMPACT_POPUP_PERCENT)
+    } else {
+        percent_x
+    };
+    let percent_y = if is_short(area) {
+        percent_y.max(COMPACT_POPUP_PERCENT)
+    } else {
+        percent_y
+    };
+
+    let [area] = Layout::vertical([Constraint::Percentage(percent_y)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+/// A bordered box of wrapped text drawn over whatever is below it, for
+/// details, confirmations and help
+pub struct Popup<'a> {
+    title: String,
+    lines: Vec<Line<'a>>,
+}
+
+impl<'a> Popup<'a> {
+    pub fn new(title: impl Into<String>, lines: Vec<Line<'a>>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+        }
+    }
+}
+
+impl Widget for Popup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        Paragraph::new(self.lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(self.title))
+            .render(area, buf);
+    }
+}
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// The current frame of the loading spinner. It advances with the clock,
+/// so every spinner on screen turns in step.
+pub fn spinner() -> &'static str {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    SPINNER_FRAMES[(millis / 100) as usize % SPINNER_FRAMES.len()]
+}
+
+/// Where a bordered, one-line-per-item list was last drawn, so mouse
+/// clicks can be mapped back to its items
+#[derive(Debug, Clone, Copy)]
+pub struct ListRows {
+    area: Rect,
+    offset: usize,
+    len: usize,
+}
+
+impl ListRows {
+    /// Record a list of `len` items after it was rendered into `area`
+    /// with `state`, which then holds the scroll offset
+    pub fn new(area: Rect, state: &ListState, len: usize) -> Self {
+        Self {
+            area,
+            offset: state.offset(),
+            len,
+        }
+    }
+
+    /// Index of the item at a screen position
+    pub fn index_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        if !inner.contains(Position::new(column, row)) {
+            return None;
+        }
+
+        let index = self.offset + usize::from(row - inner.y);
+        (index < self.len).then_some(index)
+    }
+}
+
+/// Bar chart of token counts, oldest first, sized to fill `width` columns.
+/// The last `highlight` bars are emphasized (e.g. the current 5-hour
+/// window).
+pub fn token_bar_chart<'a>(
+    title: String,
+    bars: &[(String, u64)],
+    highlight: usize,
+    width: u16,
+) -> BarChart<'a> {
+    let count = bars.len().max(1) as u16;
+    // Inner width minus the borders, with a one-column gap between bars
+    let bar_width = (width.saturating_sub(2) + 1) / count;
+    let bar_width = bar_width.saturating_sub(1).max(1);
+    let first_highlighted = bars.len().saturating_sub(highlight);
+
+    let bars: Vec<Bar> = bars
+        .iter()
+        .enumerate()
+        .map(|(i, (label, tokens))| {
+            let style = if i >= first_highlighted {
+                theme().chart_highlight
+            } else {
+                theme().chart
+            };
+            Bar::default()
+                .value(*tokens)
+                .label(label.clone().into())
+                .text_value(String::new())
+                .style(style)
+        })
+        .collect();
+
+    BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_listed_theme_exists() {
+        for name in THEMES {
+            assert!(Theme::named(name).is_ok(), "{}", name);
+        }
+        assert!(Theme::named("neon").is_err());
+    }
+
+    #[test]
+    fn test_split_panes_stacks_when_narrow() {
+        let (left, right) = split_panes(Rect::new(0, 0, 120, 40), 45);
+        assert_eq!((left.width, right.x), (54, 54));
+
+        let (top, bottom) = split_panes(Rect::new(0, 0, 80, 40), 45);
+        assert_eq!((top.width, top.height), (80, 18));
+        assert_eq!(bottom.y, 18);
+    }
+
+    #[test]
+    fn test_list_rows_index_at() {
+        let state = ListState::default().with_offset(10);
+        let rows = ListRows::new(Rect::new(0, 5, 40, 10), &state, 15);
+
+        // The borders are not rows
+        assert_eq!(rows.index_at(5, 5), None);
+        assert_eq!(rows.index_at(0, 6), None);
+        assert_eq!(rows.index_at(5, 6), Some(10));
+        assert_eq!(rows.index_at(5, 10), Some(14));
+        // Past the last item
+        assert_eq!(rows.index_at(5, 11), None);
+    }
 }
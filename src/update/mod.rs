@@ -0,0 +1,413 @@
+use crate::cache::{namespace, Cache};
+use crate::config::Config;
+use crate::style::{self, Colorize};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tracing::debug;
+
+/// GitHub repository releases are published to
+const REPOSITORY: &str = "Metroseksuaali/Claude-helper";
+
+/// Version of the running binary
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long the `status` update hint reuses one release check
+const HINT_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// How long `status` waits for the release check before giving up
+const HINT_TIMEOUT_SECS: u64 = 3;
+
+/// Changelog lines shown by `upgrade`
+const CHANGELOG_LINES: usize = 12;
+
+/// A published release as returned by the GitHub API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Version without the leading `v` of the tag
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn is_newer(&self) -> bool {
+        is_newer(self.version(), CURRENT_VERSION)
+    }
+}
+
+/// Checks GitHub for new releases and replaces the running binary
+pub struct Updater {
+    client: reqwest::Client,
+}
+
+impl Updater {
+    pub fn new(config: &Config) -> Result<Self> {
+        config.network.ensure_online("Checking for updates")?;
+
+        let client = config
+            .network
+            .client_builder()?
+            .timeout(std::time::Duration::from_secs(60))
+            .user_agent(concat!("claude-helper/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self { client })
+    }
+
+    /// The latest published release
+    pub async fn latest_release(&self) -> Result<Release> {
+        let url = format!(
+            "https://api.github.com/repos/{}/releases/latest",
+            REPOSITORY
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header("accept", "application/vnd.github+json")
+            .send()
+            .await
+            .context("Failed to reach GitHub")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("GitHub API error ({}) fetching {}", response.status(), url);
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse GitHub release")
+    }
+
+    /// `claude-helper upgrade`: report the latest release and, unless
+    /// `check_only`, replace this binary with it
+    pub async fn upgrade(&self, check_only: bool) -> Result<()> {
+        let release = self.latest_release().await?;
+
+        if !release.is_newer() {
+            println!(
+                "{} claude-helper {} is up to date",
+                style::ok().green(),
+                CURRENT_VERSION
+            );
+            return Ok(());
+        }
+
+        println!(
+            "\n{} {} {} {}",
+            "Update available:".bright_cyan().bold(),
+            CURRENT_VERSION,
+            style::arrow(),
+            release.version().bright_green()
+        );
+        if let Some(name) = release.name.as_deref().filter(|n| !n.is_empty()) {
+            println!("  {}", name.white().bold());
+        }
+        for line in changelog_summary(release.body.as_deref().unwrap_or_default(), CHANGELOG_LINES)
+        {
+            println!("  {}", line);
+        }
+        println!("\n  {}", release.html_url.dimmed());
+
+        if check_only {
+            println!("\n  Install it with: claude-helper upgrade");
+            return Ok(());
+        }
+
+        let asset = platform_asset(&release.assets).with_context(|| {
+            format!(
+                "No prebuilt binary for {}-{} in {}; download it from {}",
+                std::env::consts::OS,
+                std::env::consts::ARCH,
+                release.tag_name,
+                release.html_url
+            )
+        })?;
+
+        let checksums = checksum_asset(&release.assets, &asset.name).with_context(|| {
+            format!(
+                "{} has no SHA-256 checksum for {}, not installing it unverified; \
+                download it from {}",
+                release.tag_name, asset.name, release.html_url
+            )
+        })?;
+
+        println!("\n  Downloading {}...", asset.name);
+        let bytes = self.download(asset).await?;
+        let checksums = self.download(checksums).await?;
+        verify_checksum(&bytes, &String::from_utf8_lossy(&checksums), &asset.name)?;
+
+        let exe = std::env::current_exe().context("Failed to locate the running binary")?;
+        replace_binary(&exe, &bytes)?;
+
+        println!(
+            "{} Upgraded to {} ({})",
+            style::ok().green(),
+            release.version(),
+            exe.display()
+        );
+        Ok(())
+    }
+
+    async fn download(&self, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+        self.client
+            .get(&asset.browser_download_url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to download {}", asset.name))?
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .with_context(|| format!("Failed to download {}", asset.name))
+    }
+}
+
+/// One-line hint for `status` when a newer release exists. Checks at most
+/// once a day; failures (e.g. offline) are cached too and show nothing.
+pub async fn update_hint(config: &Config) -> Option<String> {
+    if config.network.offline {
+        return None;
+    }
+    let cache = Cache::new().ok()?;
+    let key = format!("{}/latest", namespace::UPDATE);
+
+    let latest: Option<String> = cache
+        .get_or_fetch(&key, HINT_TTL_SECS, || async {
+            let release =
+                tokio::time::timeout(std::time::Duration::from_secs(HINT_TIMEOUT_SECS), async {
+                    Updater::new(config)?.latest_release().await
+                })
+                .await;
+            Ok(match release {
+                Ok(Ok(release)) => Some(release.version().to_string()),
+                Ok(Err(e)) => {
+                    debug!("Update check failed: {:#}", e);
+                    None
+                }
+                Err(_) => {
+                    debug!("Update check timed out");
+                    None
+                }
+            })
+        })
+        .await
+        .ok()?;
+
+    latest
+        .filter(|latest| is_newer(latest, CURRENT_VERSION))
+        .map(|latest| {
+            format!(
+                "claude-helper {} is available (you have {}); run `claude-helper upgrade`",
+                latest, CURRENT_VERSION
+            )
+        })
+}
+
+/// Whether `candidate` is a later version than `current`. Pre-release
+/// suffixes (`-rc.1`) are ignored; unparsable versions never count as newer.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => false,
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// The first `max_lines` non-empty lines of release notes
+pub fn changelog_summary(body: &str, max_lines: usize) -> Vec<String> {
+    let lines: Vec<&str> = body
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    let mut summary: Vec<String> = lines
+        .iter()
+        .take(max_lines)
+        .map(|l| l.to_string())
+        .collect();
+    if lines.len() > max_lines {
+        summary.push(format!("… and {} more lines", lines.len() - max_lines));
+    }
+    summary
+}
+
+/// The plain binary built for this OS and architecture, if the release has
+/// one. Archives are skipped since they cannot be installed in place.
+fn platform_asset(assets: &[ReleaseAsset]) -> Option<&ReleaseAsset> {
+    let os_names: &[&str] = match std::env::consts::OS {
+        "macos" => &["macos", "darwin", "apple"],
+        "windows" => &["windows", "win64", "msvc"],
+        "linux" => &["linux"],
+        _ => return None,
+    };
+    let arch_names: &[&str] = match std::env::consts::ARCH {
+        "x86_64" => &["x86_64", "amd64", "x64"],
+        "aarch64" => &["aarch64", "arm64"],
+        _ => return None,
+    };
+
+    const ARCHIVES: [&str; 6] = [".tar.gz", ".tgz", ".zip", ".sha256", ".sig", ".asc"];
+    assets.iter().find(|asset| {
+        let name = asset.name.to_lowercase();
+        os_names.iter().any(|os| name.contains(os))
+            && arch_names.iter().any(|arch| name.contains(arch))
+            && !ARCHIVES.iter().any(|ext| name.ends_with(ext))
+    })
+}
+
+/// The checksum file covering the asset `name`: `<name>.sha256`, or a
+/// `SHA256SUMS`-style list for the whole release
+fn checksum_asset<'a>(assets: &'a [ReleaseAsset], name: &str) -> Option<&'a ReleaseAsset> {
+    let own = format!("{}.sha256", name).to_lowercase();
+    assets
+        .iter()
+        .find(|asset| asset.name.to_lowercase() == own)
+        .or_else(|| {
+            assets.iter().find(|asset| {
+                let name = asset.name.to_lowercase();
+                name.starts_with("sha256sums") || name.starts_with("checksums")
+            })
+        })
+}
+
+/// Fail unless `bytes` hash to the SHA-256 `checksums` lists for `name`.
+/// A line is either a bare hash or `<hash>  <file>` as `sha256sum` writes it.
+fn verify_checksum(bytes: &[u8], checksums: &str, name: &str) -> Result<()> {
+    let expected = checksums
+        .lines()
+        .find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let hash = parts.next()?;
+            match parts.next() {
+                Some(file) if file.trim_start_matches('*') != name => None,
+                _ => Some(hash),
+            }
+        })
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .with_context(|| format!("The release checksums do not list {}", name))?
+        .to_lowercase();
+
+    let actual: String = Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if actual != expected {
+        anyhow::bail!(
+            "Checksum mismatch for {} (expected {}, got {}); not installing it",
+            name,
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Swap `exe` for `bytes`, keeping the old binary until the new one is in
+/// place. Renaming works on a running binary on every platform.
+fn replace_binary(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let new = exe.with_extension("new");
+    let old = exe.with_extension("old");
+
+    std::fs::write(&new, bytes).with_context(|| format!("Failed to write {:?}", new))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {:?} executable", new))?;
+    }
+
+    std::fs::rename(exe, &old).with_context(|| format!("Failed to move {:?} aside", exe))?;
+    if let Err(e) = std::fs::rename(&new, exe) {
+        // Put the old binary back so the install keeps working
+        let _ = std::fs::rename(&old, exe);
+        return Err(e).with_context(|| format!("Failed to install {:?}", exe));
+    }
+
+    // Windows keeps the running binary locked; it is replaced on the next upgrade
+    let _ = std::fs::remove_file(&old);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.1-rc.1", "0.1.1"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let asset = |name: &str| ReleaseAsset {
+            name: name.to_string(),
+            browser_download_url: String::new(),
+        };
+        let assets = [
+            asset("claude-helper-linux-x86_64"),
+            asset("claude-helper-linux-x86_64.sha256"),
+            asset("SHA256SUMS"),
+        ];
+        assert_eq!(
+            checksum_asset(&assets, "claude-helper-linux-x86_64")
+                .unwrap()
+                .name,
+            "claude-helper-linux-x86_64.sha256"
+        );
+        assert_eq!(
+            checksum_asset(&assets, "claude-helper-macos-arm64")
+                .unwrap()
+                .name,
+            "SHA256SUMS"
+        );
+        assert!(checksum_asset(&assets[..1], "claude-helper-linux-x86_64").is_none());
+
+        // SHA-256 of "binary"
+        let hash = "9a3a45d01531a20e89ac6ae10b0b0beb0492acd7216a368aa062d1a5fecaf9cd";
+        let name = "claude-helper-linux-x86_64";
+        assert!(verify_checksum(b"binary", hash, name).is_ok());
+        let list = format!("{}  other-file\n{} *{}\n", "0".repeat(64), hash, name);
+        assert!(verify_checksum(b"binary", &list, name).is_ok());
+        assert!(verify_checksum(b"tampered", &list, name).is_err());
+        assert!(verify_checksum(b"binary", "", name).is_err());
+    }
+
+    #[test]
+    fn test_changelog_summary() {
+        let summary = changelog_summary("## Changes\n\n- one\n- two\n- three\n", 2);
+        assert_eq!(summary, vec!["## Changes", "- one", "… and 2 more lines"]);
+    }
+}
@@ -1,7 +1,16 @@
 // Common test utilities and fixtures
 use anyhow::Result;
+use claude_helper::config::{CONFIG_DIR_ENV, DATA_DIR_ENV};
+use claude_helper::db::Database;
+use claude_helper::Config;
 use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+/// Held while a test points the config and data directories at its own
+/// temp directory
+static DATA_DIR_LOCK: Mutex<()> = Mutex::const_new(());
 
 /// Create an in-memory SQLite database for testing
 pub async fn setup_test_db() -> Result<SqlitePool> {
@@ -15,39 +24,24 @@ pub async fn setup_test_db() -> Result<SqlitePool> {
     Ok(pool)
 }
 
-/// Create a temporary directory that will be cleaned up automatically
-pub fn temp_dir() -> Result<TempDir> {
-    Ok(TempDir::new()?)
+/// Open the database in `dir` the way the app does, migrations included.
+/// The config directory moves along, so a database left in the user's
+/// legacy config directory is never adopted.
+pub async fn open_database(dir: &Path) -> Result<Database> {
+    let _lock = DATA_DIR_LOCK.lock().await;
+    std::env::set_var(CONFIG_DIR_ENV, dir.join("config"));
+    std::env::set_var(DATA_DIR_ENV, dir);
+    Ok(Database::new(&Config::default()).await?)
 }
 
-/// Sample task descriptions for testing
-pub mod sample_tasks {
-    pub const EMPTY: &str = "";
-    pub const SIMPLE: &str = "fix typo in README";
-    pub const MEDIUM: &str = "implement user authentication";
-    pub const COMPLEX: &str =
-        "refactor migrate security architecture with testing and documentation";
-    pub const HIGH_COMPLEXITY: &str = "refactor the authentication system";
-    pub const MULTIPLE_REQUIREMENTS: &str = "implement OAuth and add tests";
+/// Where [`open_database`] keeps the database file for `dir`
+pub fn db_file(dir: &Path) -> PathBuf {
+    dir.join("db").join("claude-helper.db")
 }
 
-/// Sample agent capabilities for testing
-pub mod sample_capabilities {
-    use claude_helper::agents::AgentCapability;
-
-    pub fn all_capabilities() -> Vec<AgentCapability> {
-        vec![
-            AgentCapability::Architecture,
-            AgentCapability::CodeWriting,
-            AgentCapability::Testing,
-            AgentCapability::Security,
-            AgentCapability::Documentation,
-            AgentCapability::Debugging,
-            AgentCapability::Performance,
-            AgentCapability::Migration,
-            AgentCapability::Review,
-        ]
-    }
+/// Create a temporary directory that will be cleaned up automatically
+pub fn temp_dir() -> Result<TempDir> {
+    Ok(TempDir::new()?)
 }
 
 #[cfg(test)]
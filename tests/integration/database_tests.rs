@@ -3,6 +3,11 @@
 mod common;
 
 use anyhow::Result;
+use chrono::Utc;
+use claude_helper::analyzer::{Optimization, OptimizationType};
+use claude_helper::db::{ArchivedAgent, ArchivedTask, OptimizationStatus};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
 
 #[tokio::test]
 async fn test_database_initialization() -> Result<()> {
@@ -28,6 +33,125 @@ async fn test_database_initialization() -> Result<()> {
 
     assert_eq!(count, 0);
 
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM usage_daily")
+        .fetch_one(&pool)
+        .await?;
+
+    assert_eq!(count, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_optimization_applied_roundtrip() -> Result<()> {
+    let dir = common::temp_dir()?;
+
+    // An optimizations table from before examples, suggestions and statuses
+    let db_file = common::db_file(dir.path());
+    std::fs::create_dir_all(db_file.parent().unwrap())?;
+    let old = SqlitePool::connect_with(
+        SqliteConnectOptions::new()
+            .filename(&db_file)
+            .create_if_missing(true),
+    )
+    .await?;
+    sqlx::query(
+        "CREATE TABLE optimizations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            optimization_type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            estimated_savings INTEGER NOT NULL,
+            applied BOOLEAN DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(&old)
+    .await?;
+    old.close().await;
+
+    let db = common::open_database(dir.path()).await?;
+    let id = db
+        .save_optimization(&Optimization {
+            opt_type: OptimizationType::QuickCommand,
+            title: "Repeated ls".to_string(),
+            description: "Same command run 5 times".to_string(),
+            estimated_savings: 500,
+            examples: vec!["ls -la".to_string()],
+            suggestion: Some("Add an alias".to_string()),
+        })
+        .await?;
+
+    let pending = db.get_optimizations(10, false, false).await?;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].status, OptimizationStatus::New);
+
+    assert!(db.mark_applied(id).await?);
+    assert!(db.get_optimizations(10, false, false).await?.is_empty());
+
+    let stored = db.get_optimizations(10, true, false).await?;
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, id);
+    assert_eq!(stored[0].status, OptimizationStatus::Applied);
+    assert_eq!(stored[0].optimization.examples, vec!["ls -la"]);
+    assert_eq!(
+        stored[0].optimization.suggestion.as_deref(),
+        Some("Add an alias")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_imported_task_stays_out_of_rollup() -> Result<()> {
+    let dir = common::temp_dir()?;
+    let db = common::open_database(dir.path()).await?;
+
+    let task = ArchivedTask {
+        description: "Add a --json flag".to_string(),
+        complexity: 3,
+        estimated_tokens: 5000,
+        actual_tokens: 4200,
+        success: true,
+        analysis: "{}".to_string(),
+        plan: "{}".to_string(),
+        result: "{}".to_string(),
+        variant: None,
+        project: Some("/work/api".to_string()),
+        git_remote: None,
+        run_id: None,
+        model: Some("claude-opus-4-1".to_string()),
+        cost_usd: Some(0.42),
+        created_at: Utc::now(),
+        agents: vec![ArchivedAgent {
+            agent_id: "agent-1".to_string(),
+            agent_type: "coder".to_string(),
+            capability: "rust".to_string(),
+            task: "Add the flag".to_string(),
+            tokens_used: 4200,
+            execution_time_ms: 1500,
+            success: true,
+            model: Some("claude-opus-4-1".to_string()),
+            prompt_version: Some("0123abcd".to_string()),
+            created_at: Utc::now(),
+        }],
+        tags: vec!["cli".to_string()],
+    };
+    let id = db.import_task(&task).await?;
+
+    let exported = db.export_task(id as usize).await?.expect("imported task");
+    assert_eq!(exported.model, task.model);
+    assert_eq!(exported.cost_usd, task.cost_usd);
+    assert_eq!(exported.agents[0].model, task.agents[0].model);
+    assert_eq!(
+        exported.agents[0].prompt_version,
+        task.agents[0].prompt_version
+    );
+    assert_eq!(exported.tags, task.tags);
+
+    assert_eq!(db.rebuild_daily_rollup().await?, 0);
+    assert!(db.get_daily_usage(30).await?.is_empty());
+
     Ok(())
 }
 